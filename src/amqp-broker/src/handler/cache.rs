@@ -0,0 +1,210 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+
+use common_base::tools::now_second;
+use dashmap::DashMap;
+use metadata_struct::amqp::exchange::{AmqpExchange, AmqpExchangeType};
+use metadata_struct::amqp::queue::{AmqpBinding, AmqpQueue};
+
+use crate::handler::constant::DEFAULT_EXCHANGE;
+use crate::handler::exchange::binding_matches;
+
+pub struct AmqpCacheManager {
+    // (exchange_name, AmqpExchange)
+    pub exchanges: DashMap<String, AmqpExchange>,
+    // (queue_name, AmqpQueue)
+    pub queues: DashMap<String, AmqpQueue>,
+}
+
+impl Default for AmqpCacheManager {
+    fn default() -> Self {
+        AmqpCacheManager::new()
+    }
+}
+
+impl AmqpCacheManager {
+    pub fn new() -> Self {
+        let cache = AmqpCacheManager {
+            exchanges: DashMap::with_capacity(8),
+            queues: DashMap::with_capacity(8),
+        };
+        for (name, exchange_type) in [
+            (DEFAULT_EXCHANGE, AmqpExchangeType::Direct),
+            ("amq.direct", AmqpExchangeType::Direct),
+            ("amq.fanout", AmqpExchangeType::Fanout),
+            ("amq.topic", AmqpExchangeType::Topic),
+        ] {
+            cache.add_exchange(AmqpExchange {
+                exchange_name: name.to_string(),
+                exchange_type,
+                durable: true,
+                auto_delete: false,
+                internal: false,
+                create_time: now_second(),
+                arguments: BTreeMap::new(),
+            });
+        }
+        cache
+    }
+
+    pub fn add_exchange(&self, exchange: AmqpExchange) {
+        self.exchanges
+            .insert(exchange.exchange_name.clone(), exchange);
+    }
+
+    pub fn get_exchange(&self, exchange_name: &str) -> Option<AmqpExchange> {
+        self.exchanges
+            .get(exchange_name)
+            .map(|exchange| exchange.clone())
+    }
+
+    pub fn remove_exchange(&self, exchange_name: &str) {
+        self.exchanges.remove(exchange_name);
+    }
+
+    pub fn add_queue(&self, queue: AmqpQueue) {
+        self.queues.insert(queue.queue_name.clone(), queue);
+    }
+
+    pub fn get_queue(&self, queue_name: &str) -> Option<AmqpQueue> {
+        self.queues.get(queue_name).map(|queue| queue.clone())
+    }
+
+    pub fn remove_queue(&self, queue_name: &str) {
+        self.queues.remove(queue_name);
+    }
+
+    pub fn exchange_in_use(&self, exchange_name: &str) -> bool {
+        self.queues.iter().any(|queue| {
+            queue
+                .bindings
+                .iter()
+                .any(|binding| binding.exchange_name == exchange_name)
+        })
+    }
+
+    // Queues a message published to `exchange_name` should be stored in
+    pub fn route(&self, exchange_name: &str, routing_key: &str) -> Vec<String> {
+        // the default exchange is implicitly bound to every queue by its name
+        if exchange_name == DEFAULT_EXCHANGE {
+            if self.queues.contains_key(routing_key) {
+                return vec![routing_key.to_string()];
+            }
+            return Vec::new();
+        }
+
+        let exchange = if let Some(exchange) = self.get_exchange(exchange_name) {
+            exchange
+        } else {
+            return Vec::new();
+        };
+        self.route_with(&exchange, routing_key)
+    }
+
+    // Routes with the bindings of `exchange` as they are now, the exchange type may
+    // differ from the stored one, e.g. the x-delayed-type of a delayed exchange
+    pub fn route_with(&self, exchange: &AmqpExchange, routing_key: &str) -> Vec<String> {
+        let mut results = Vec::new();
+        for queue in self.queues.iter() {
+            if queue
+                .bindings
+                .iter()
+                .any(|binding| binding_matches(exchange, binding, routing_key))
+            {
+                results.push(queue.queue_name.clone());
+            }
+        }
+        results
+    }
+
+    // (queue_name, AmqpBinding) of every binding to `exchange_name`
+    pub fn exchange_bindings(&self, exchange_name: &str) -> Vec<(String, AmqpBinding)> {
+        self.queues
+            .iter()
+            .flat_map(|queue| {
+                queue
+                    .bindings
+                    .iter()
+                    .filter(|binding| binding.exchange_name == exchange_name)
+                    .map(|binding| (queue.queue_name.clone(), binding.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use metadata_struct::amqp::exchange::{AmqpExchange, AmqpExchangeType};
+    use metadata_struct::amqp::queue::{AmqpBinding, AmqpQueue};
+
+    use super::AmqpCacheManager;
+
+    fn build_queue(name: &str, bindings: Vec<(&str, &str)>) -> AmqpQueue {
+        AmqpQueue {
+            queue_name: name.to_string(),
+            bindings: bindings
+                .into_iter()
+                .map(|(exchange_name, routing_key)| AmqpBinding {
+                    exchange_name: exchange_name.to_string(),
+                    routing_key: routing_key.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn route_test() {
+        let cache = AmqpCacheManager::new();
+        cache.add_exchange(AmqpExchange {
+            exchange_name: "logs".to_string(),
+            exchange_type: AmqpExchangeType::Fanout,
+            ..Default::default()
+        });
+        cache.add_queue(build_queue("errors", vec![("amq.direct", "error")]));
+        cache.add_queue(build_queue(
+            "eu_orders",
+            vec![("amq.topic", "order.*.eu"), ("logs", "")],
+        ));
+        cache.add_queue(build_queue("audit", vec![("logs", "")]));
+
+        assert_eq!(cache.route("", "errors"), vec!["errors".to_string()]);
+        assert!(cache.route("", "missing").is_empty());
+
+        assert_eq!(
+            cache.route("amq.direct", "error"),
+            vec!["errors".to_string()]
+        );
+        assert!(cache.route("amq.direct", "info").is_empty());
+
+        assert_eq!(
+            cache.route("amq.topic", "order.paid.eu"),
+            vec!["eu_orders".to_string()]
+        );
+
+        let mut fanout = cache.route("logs", "anything");
+        fanout.sort();
+        assert_eq!(fanout, vec!["audit".to_string(), "eu_orders".to_string()]);
+
+        assert!(cache.route("missing", "error").is_empty());
+
+        assert_eq!(cache.exchange_bindings("logs").len(), 2);
+        assert!(cache.exchange_in_use("logs"));
+        assert!(!cache.exchange_in_use("amq.fanout"));
+    }
+}
@@ -0,0 +1,220 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bytes::{Bytes, BytesMut};
+use protocol::amqp::common::{BasicProperties, BasicPublish, ContentHeader, CLASS_BASIC};
+
+use crate::handler::delivery::ChannelDelivery;
+use crate::handler::error::AmqpBrokerError;
+
+pub struct CompletePublish {
+    pub publish: BasicPublish,
+    pub properties: BasicProperties,
+    pub body: Bytes,
+}
+
+struct PendingPublish {
+    publish: BasicPublish,
+    header: Option<ContentHeader>,
+    body: BytesMut,
+}
+
+pub struct AmqpChannel {
+    pub delivery: Arc<ChannelDelivery>,
+    // channel.close was sent, everything but close-ok is discarded
+    pub closing: bool,
+    pub confirm_mode: bool,
+    // Sequence number of the last publish in confirm mode
+    pub publish_seq: u64,
+    // Name of the last queue declared, used when a method leaves the queue empty
+    pub last_queue: Option<String>,
+    // (consumer_tag, queue_name)
+    pub consumers: HashMap<String, String>,
+    pending_publish: Option<PendingPublish>,
+}
+
+impl AmqpChannel {
+    pub fn new(delivery: Arc<ChannelDelivery>) -> Self {
+        AmqpChannel {
+            delivery,
+            closing: false,
+            confirm_mode: false,
+            publish_seq: 0,
+            last_queue: None,
+            consumers: HashMap::new(),
+            pending_publish: None,
+        }
+    }
+
+    pub fn resolve_queue_name(&self, queue_name: &str) -> Result<String, AmqpBrokerError> {
+        if !queue_name.is_empty() {
+            return Ok(queue_name.to_string());
+        }
+        self.last_queue.clone().ok_or(AmqpBrokerError::NotAllowed(
+            "no previously declared queue".to_string(),
+        ))
+    }
+
+    pub fn next_publish_seq(&mut self) -> Option<u64> {
+        if !self.confirm_mode {
+            return None;
+        }
+        self.publish_seq += 1;
+        Some(self.publish_seq)
+    }
+
+    pub fn start_publish(&mut self, publish: BasicPublish) -> Result<(), AmqpBrokerError> {
+        if self.pending_publish.is_some() {
+            return Err(AmqpBrokerError::UnexpectedFrame(
+                "expected content header or body, got basic.publish".to_string(),
+            ));
+        }
+        self.pending_publish = Some(PendingPublish {
+            publish,
+            header: None,
+            body: BytesMut::new(),
+        });
+        Ok(())
+    }
+
+    pub fn content_header(
+        &mut self,
+        header: ContentHeader,
+    ) -> Result<Option<CompletePublish>, AmqpBrokerError> {
+        let pending = match self.pending_publish.as_mut() {
+            Some(pending) if pending.header.is_none() => pending,
+            _ => {
+                return Err(AmqpBrokerError::UnexpectedFrame(
+                    "content header without basic.publish".to_string(),
+                ))
+            }
+        };
+        if header.class_id != CLASS_BASIC {
+            return Err(AmqpBrokerError::UnexpectedFrame(format!(
+                "content header for class {}",
+                header.class_id
+            )));
+        }
+        pending.header = Some(header);
+        Ok(self.take_complete())
+    }
+
+    pub fn content_body(
+        &mut self,
+        body: Bytes,
+    ) -> Result<Option<CompletePublish>, AmqpBrokerError> {
+        let pending = match self.pending_publish.as_mut() {
+            Some(pending) if pending.header.is_some() => pending,
+            _ => {
+                return Err(AmqpBrokerError::UnexpectedFrame(
+                    "content body without content header".to_string(),
+                ))
+            }
+        };
+        let body_size = pending.header.as_ref().map_or(0, |h| h.body_size);
+        if (pending.body.len() + body.len()) as u64 > body_size {
+            return Err(AmqpBrokerError::UnexpectedFrame(format!(
+                "content body exceeds the declared size {}",
+                body_size
+            )));
+        }
+        pending.body.extend_from_slice(&body);
+        Ok(self.take_complete())
+    }
+
+    fn take_complete(&mut self) -> Option<CompletePublish> {
+        let complete = self.pending_publish.as_ref().is_some_and(|pending| {
+            pending
+                .header
+                .as_ref()
+                .is_some_and(|h| h.body_size == pending.body.len() as u64)
+        });
+        if !complete {
+            return None;
+        }
+        let pending = self.pending_publish.take()?;
+        Some(CompletePublish {
+            publish: pending.publish,
+            properties: pending.header?.properties,
+            body: pending.body.freeze(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use protocol::amqp::common::{BasicPublish, ContentHeader, CLASS_BASIC};
+    use tokio::sync::mpsc;
+
+    use super::AmqpChannel;
+    use crate::handler::delivery::ChannelDelivery;
+
+    fn build_channel() -> AmqpChannel {
+        let (sender, _receiver) = mpsc::channel(10);
+        AmqpChannel::new(Arc::new(ChannelDelivery::new(1, 1, sender, 4096, 0)))
+    }
+
+    fn build_header(body_size: u64) -> ContentHeader {
+        ContentHeader {
+            class_id: CLASS_BASIC,
+            body_size,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn content_assembly_test() {
+        let mut channel = build_channel();
+        assert!(channel.content_header(build_header(1)).is_err());
+
+        channel
+            .start_publish(BasicPublish {
+                routing_key: "q1".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(channel.start_publish(BasicPublish::default()).is_err());
+        assert!(channel.content_body(Bytes::from("x")).is_err());
+
+        assert!(channel.content_header(build_header(5)).unwrap().is_none());
+        assert!(channel.content_body(Bytes::from("he")).unwrap().is_none());
+        let complete = channel.content_body(Bytes::from("llo")).unwrap().unwrap();
+        assert_eq!(complete.publish.routing_key, "q1");
+        assert_eq!(complete.body, Bytes::from("hello"));
+
+        // an empty body completes with the header
+        channel.start_publish(BasicPublish::default()).unwrap();
+        let complete = channel.content_header(build_header(0)).unwrap().unwrap();
+        assert!(complete.body.is_empty());
+
+        channel.start_publish(BasicPublish::default()).unwrap();
+        channel.content_header(build_header(2)).unwrap();
+        assert!(channel.content_body(Bytes::from("abc")).is_err());
+    }
+
+    #[test]
+    fn publish_seq_test() {
+        let mut channel = build_channel();
+        assert_eq!(channel.next_publish_seq(), None);
+        channel.confirm_mode = true;
+        assert_eq!(channel.next_publish_seq(), Some(1));
+        assert_eq!(channel.next_publish_seq(), Some(2));
+    }
+}
@@ -0,0 +1,1231 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use amqp_plugins::exchange::Route;
+use amqp_plugins::manager::AmqpPluginManager;
+use amqp_plugins::message::{field_table_to_arguments, PluginMessage};
+use bytes::Bytes;
+use common_base::config::broker_amqp::broker_amqp_conf;
+use common_base::tools::{constant_time_eq, now_second, unique_id};
+use futures::StreamExt;
+use log::{error, info, warn};
+use metadata_struct::amqp::exchange::{AmqpExchange, AmqpExchangeType};
+use metadata_struct::amqp::queue::{AmqpBinding, AmqpQueue};
+use protocol::amqp::codec::AmqpCodec;
+use protocol::amqp::common::{
+    AmqpFrame, BasicAck, BasicConsume, BasicGetOk, BasicNack, BasicPublish, BasicReturn, Close,
+    ConnectionStart, ConnectionTune, ConsumerTag, ExchangeDeclare, ExchangeDelete, FieldTable,
+    FieldValue, MessageCount, Method, ProtocolHeader, QueueDeclare, QueueDeclareOk, QueueDelete,
+    FRAME_MIN_SIZE,
+};
+use storage_adapter::storage::StorageAdapter;
+use tokio::io::AsyncRead;
+use tokio::time::sleep;
+use tokio_util::codec::FramedRead;
+
+use crate::handler::cache::AmqpCacheManager;
+use crate::handler::channel::{AmqpChannel, CompletePublish};
+use crate::handler::constant::{
+    DEFAULT_EXCHANGE, DEFAULT_VIRTUAL_HOST, REPLY_ACCESS_REFUSED, REPLY_NOT_ALLOWED,
+    REPLY_NO_ROUTE, SUPPORTED_LOCALE, SUPPORTED_MECHANISM,
+};
+use crate::handler::delivery::{ChannelDelivery, FrameSender, UnackedMessage};
+use crate::handler::error::AmqpBrokerError;
+use crate::handler::queue::{Consumer, QueueManager, QueueState, QueuedMessage};
+use crate::storage::exchange::ExchangeStorage;
+use crate::storage::message::AmqpMessage;
+
+fn negotiate_u16(server: u16, client: u16) -> u16 {
+    if client == 0 || server == 0 {
+        return server.max(client);
+    }
+    server.min(client)
+}
+
+fn negotiate_u32(server: u32, client: u32) -> u32 {
+    if client == 0 || server == 0 {
+        return server.max(client);
+    }
+    server.min(client)
+}
+
+fn server_properties() -> FieldTable {
+    let mut capabilities = FieldTable::new();
+    capabilities.insert("publisher_confirms".to_string(), FieldValue::Boolean(true));
+    capabilities.insert("basic.nack".to_string(), FieldValue::Boolean(true));
+
+    let mut properties = FieldTable::new();
+    properties.insert(
+        "product".to_string(),
+        FieldValue::LongString(Bytes::from_static(b"RobustMQ")),
+    );
+    properties.insert(
+        "version".to_string(),
+        FieldValue::LongString(Bytes::from_static(env!("CARGO_PKG_VERSION").as_bytes())),
+    );
+    properties.insert(
+        "capabilities".to_string(),
+        FieldValue::FieldTable(capabilities),
+    );
+    properties
+}
+
+// PLAIN response is "authzid\0authcid\0password". The default user is always
+// accepted, other users are checked by the authentication plugin when one is enabled.
+async fn check_plain_auth(response: &[u8], plugins: &AmqpPluginManager) -> bool {
+    let conf = broker_amqp_conf();
+    let parts: Vec<&[u8]> = response.split(|b| *b == 0).collect();
+    if parts.len() != 3 {
+        return false;
+    }
+    // both are compared in full, so the time taken does not tell which one was wrong
+    let is_default_user = constant_time_eq(parts[1], conf.system.default_user.as_bytes());
+    let is_default_password = constant_time_eq(parts[2], conf.system.default_password.as_bytes());
+    if is_default_user & is_default_password {
+        return true;
+    }
+
+    let authenticator = if let Some(authenticator) = plugins.authenticator() {
+        authenticator
+    } else {
+        return false;
+    };
+    let (username, password) = match (std::str::from_utf8(parts[1]), std::str::from_utf8(parts[2]))
+    {
+        (Ok(username), Ok(password)) => (username, password),
+        _ => return false,
+    };
+    match authenticator.authenticate(username, password).await {
+        Ok(allowed) => allowed,
+        Err(e) => {
+            error!("AMQP authentication of user {} failed: {}", username, e);
+            false
+        }
+    }
+}
+
+async fn send_connection_close(
+    sender: &FrameSender,
+    reply_code: u16,
+    reply_text: String,
+    method_id: (u16, u16),
+) {
+    let close = Method::ConnectionClose(Close {
+        reply_code,
+        reply_text,
+        class_id: method_id.0,
+        method_id: method_id.1,
+    });
+    let _ = sender.send(vec![AmqpFrame::Method(0, close)]).await;
+}
+
+async fn next_handshake_method<R>(
+    reader: &mut FramedRead<R, AmqpCodec>,
+) -> Result<Method, AmqpBrokerError>
+where
+    R: AsyncRead + Unpin,
+{
+    loop {
+        match reader.next().await {
+            Some(Ok(AmqpFrame::Heartbeat)) => continue,
+            Some(Ok(AmqpFrame::Method(0, method))) => return Ok(method),
+            Some(Ok(frame)) => {
+                return Err(AmqpBrokerError::UnexpectedFrame(format!(
+                    "unexpected frame during handshake: {:?}",
+                    frame
+                )))
+            }
+            Some(Err(e)) => return Err(e.into()),
+            None => return Err(AmqpBrokerError::ConnectionClosed),
+        }
+    }
+}
+
+// Runs connection.start/tune/open and returns the tuning both sides agreed on
+pub async fn handshake<R>(
+    reader: &mut FramedRead<R, AmqpCodec>,
+    sender: &FrameSender,
+    plugins: &AmqpPluginManager,
+) -> Result<ConnectionTune, AmqpBrokerError>
+where
+    R: AsyncRead + Unpin,
+{
+    match reader.next().await {
+        Some(Ok(AmqpFrame::ProtocolHeader(header))) if header.is_supported() => {}
+        Some(Ok(AmqpFrame::ProtocolHeader(_))) => {
+            // answer with the version we speak and close, as the specification asks
+            let _ = sender
+                .send(vec![AmqpFrame::ProtocolHeader(ProtocolHeader::default())])
+                .await;
+            return Err(AmqpBrokerError::NotAllowed(
+                "unsupported protocol version".to_string(),
+            ));
+        }
+        Some(Ok(_)) => {
+            return Err(AmqpBrokerError::UnexpectedFrame(
+                "expected protocol header".to_string(),
+            ))
+        }
+        Some(Err(e)) => return Err(e.into()),
+        None => return Err(AmqpBrokerError::ConnectionClosed),
+    }
+
+    let conf = broker_amqp_conf();
+    let start = ConnectionStart {
+        version_major: 0,
+        version_minor: 9,
+        server_properties: server_properties(),
+        mechanisms: Bytes::from_static(SUPPORTED_MECHANISM.as_bytes()),
+        locales: Bytes::from_static(SUPPORTED_LOCALE.as_bytes()),
+    };
+    sender
+        .send(vec![AmqpFrame::Method(0, Method::ConnectionStart(start))])
+        .await
+        .map_err(|_| AmqpBrokerError::ConnectionClosed)?;
+
+    let start_ok = match next_handshake_method(reader).await? {
+        Method::ConnectionStartOk(start_ok) => start_ok,
+        method => {
+            return Err(AmqpBrokerError::UnexpectedFrame(format!(
+                "expected connection.start-ok, got {:?}",
+                method.id()
+            )))
+        }
+    };
+    if start_ok.mechanism != SUPPORTED_MECHANISM
+        || !check_plain_auth(&start_ok.response, plugins).await
+    {
+        let error = AmqpBrokerError::AccessRefused(format!(
+            "Login was refused using authentication mechanism {}",
+            start_ok.mechanism
+        ));
+        send_connection_close(
+            sender,
+            REPLY_ACCESS_REFUSED,
+            error.to_string(),
+            Method::ConnectionStartOk(Default::default()).id(),
+        )
+        .await;
+        return Err(error);
+    }
+
+    let offered = ConnectionTune {
+        channel_max: conf.network.channel_max,
+        frame_max: conf.network.frame_max,
+        heartbeat: conf.network.heartbeat,
+    };
+    sender
+        .send(vec![AmqpFrame::Method(0, Method::ConnectionTune(offered))])
+        .await
+        .map_err(|_| AmqpBrokerError::ConnectionClosed)?;
+
+    let tune_ok = match next_handshake_method(reader).await? {
+        Method::ConnectionTuneOk(tune_ok) => tune_ok,
+        method => {
+            return Err(AmqpBrokerError::UnexpectedFrame(format!(
+                "expected connection.tune-ok, got {:?}",
+                method.id()
+            )))
+        }
+    };
+    let tune = ConnectionTune {
+        channel_max: negotiate_u16(offered.channel_max, tune_ok.channel_max),
+        frame_max: negotiate_u32(offered.frame_max, tune_ok.frame_max).max(FRAME_MIN_SIZE),
+        // the client decides, 0 turns heartbeats off
+        heartbeat: tune_ok.heartbeat,
+    };
+
+    let open = match next_handshake_method(reader).await? {
+        Method::ConnectionOpen(open) => open,
+        method => {
+            return Err(AmqpBrokerError::UnexpectedFrame(format!(
+                "expected connection.open, got {:?}",
+                method.id()
+            )))
+        }
+    };
+    if open.virtual_host != DEFAULT_VIRTUAL_HOST {
+        let error = AmqpBrokerError::NotAllowed(format!("vhost '{}' not found", open.virtual_host));
+        send_connection_close(
+            sender,
+            REPLY_NOT_ALLOWED,
+            error.to_string(),
+            Method::ConnectionOpen(open).id(),
+        )
+        .await;
+        return Err(error);
+    }
+    sender
+        .send(vec![AmqpFrame::Method(0, Method::ConnectionOpenOk)])
+        .await
+        .map_err(|_| AmqpBrokerError::ConnectionClosed)?;
+    Ok(tune)
+}
+
+// Where a published message goes once the exchange type plugins had their say
+enum Routed {
+    Queues(Vec<String>),
+    // Routed by `exchange` once the delay is over
+    Delayed(AmqpExchange, Duration),
+}
+
+fn group_by_queue(messages: Vec<UnackedMessage>) -> HashMap<String, Vec<QueuedMessage>> {
+    let mut results: HashMap<String, Vec<QueuedMessage>> = HashMap::new();
+    for unacked in messages {
+        results
+            .entry(unacked.queue_name)
+            .or_default()
+            .push(unacked.message);
+    }
+    results
+}
+
+pub struct AmqpConnection<S> {
+    connection_id: u64,
+    client_addr: SocketAddr,
+    tune: ConnectionTune,
+    sender: FrameSender,
+    cache_manager: Arc<AmqpCacheManager>,
+    queue_manager: Arc<QueueManager<S>>,
+    exchange_storage: Arc<ExchangeStorage>,
+    plugins: Arc<AmqpPluginManager>,
+    channels: BTreeMap<u16, AmqpChannel>,
+}
+
+impl<S> AmqpConnection<S>
+where
+    S: StorageAdapter + Send + Sync + 'static,
+{
+    pub fn new(
+        connection_id: u64,
+        client_addr: SocketAddr,
+        tune: ConnectionTune,
+        sender: FrameSender,
+        cache_manager: Arc<AmqpCacheManager>,
+        queue_manager: Arc<QueueManager<S>>,
+        exchange_storage: Arc<ExchangeStorage>,
+        plugins: Arc<AmqpPluginManager>,
+    ) -> Self {
+        AmqpConnection {
+            connection_id,
+            client_addr,
+            tune,
+            sender,
+            cache_manager,
+            queue_manager,
+            exchange_storage,
+            plugins,
+            channels: BTreeMap::new(),
+        }
+    }
+
+    // Returns false once the connection should be closed
+    pub async fn handle_frame(&mut self, frame: AmqpFrame) -> bool {
+        let channel_id = frame.channel();
+        let method_id = if let AmqpFrame::Method(_, method) = &frame {
+            method.id()
+        } else {
+            (0, 0)
+        };
+
+        match self.process(frame).await {
+            Ok(keep) => keep,
+            Err(e) if e.is_channel_error() && channel_id != 0 => {
+                self.close_channel(channel_id, &e, method_id).await;
+                true
+            }
+            Err(e) => {
+                info!(
+                    "AMQP connection {} from {} closed by the broker: {}",
+                    self.connection_id, self.client_addr, e
+                );
+                self.close_connection(&e, method_id).await;
+                false
+            }
+        }
+    }
+
+    pub async fn close_connection(&mut self, error: &AmqpBrokerError, method_id: (u16, u16)) {
+        send_connection_close(
+            &self.sender,
+            error.reply_code(),
+            error.to_string(),
+            method_id,
+        )
+        .await;
+    }
+
+    // Gives back everything the connection held: unacked messages, consumers, exclusive queues
+    pub async fn cleanup(&mut self) {
+        let channel_ids: Vec<u16> = self.channels.keys().copied().collect();
+        for channel_id in channel_ids {
+            if let Some(channel) = self.channels.remove(&channel_id) {
+                self.cleanup_channel(channel).await;
+            }
+        }
+        self.queue_manager
+            .close_connection(self.connection_id)
+            .await;
+    }
+
+    async fn process(&mut self, frame: AmqpFrame) -> Result<bool, AmqpBrokerError> {
+        match frame {
+            AmqpFrame::Heartbeat => Ok(true),
+            AmqpFrame::ProtocolHeader(_) => Err(AmqpBrokerError::UnexpectedFrame(
+                "protocol header after the handshake".to_string(),
+            )),
+            AmqpFrame::Method(0, method) => self.process_connection_method(method).await,
+            AmqpFrame::Method(channel_id, method) => {
+                self.process_channel_method(channel_id, method).await?;
+                Ok(true)
+            }
+            AmqpFrame::Header(channel_id, header) => {
+                if let Some(channel) = self.open_channel(channel_id)? {
+                    if let Some(complete) = channel.content_header(header)? {
+                        self.publish(channel_id, complete).await?;
+                    }
+                }
+                Ok(true)
+            }
+            AmqpFrame::Body(channel_id, body) => {
+                if let Some(channel) = self.open_channel(channel_id)? {
+                    if let Some(complete) = channel.content_body(body)? {
+                        self.publish(channel_id, complete).await?;
+                    }
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    async fn process_connection_method(&mut self, method: Method) -> Result<bool, AmqpBrokerError> {
+        match method {
+            Method::ConnectionClose(_) => {
+                self.send(0, Method::ConnectionCloseOk).await?;
+                Ok(false)
+            }
+            Method::ConnectionCloseOk => Ok(false),
+            method => Err(AmqpBrokerError::CommandInvalid(format!(
+                "unexpected method {:?} on channel 0",
+                method.id()
+            ))),
+        }
+    }
+
+    // The channel if it is open, None while a channel.close sent by the broker is pending
+    fn open_channel(
+        &mut self,
+        channel_id: u16,
+    ) -> Result<Option<&mut AmqpChannel>, AmqpBrokerError> {
+        match self.channels.get_mut(&channel_id) {
+            Some(channel) if channel.closing => Ok(None),
+            Some(channel) => Ok(Some(channel)),
+            None => Err(AmqpBrokerError::ChannelError(format!(
+                "expected 'channel.open' on channel {}",
+                channel_id
+            ))),
+        }
+    }
+
+    fn channel(&mut self, channel_id: u16) -> Result<&mut AmqpChannel, AmqpBrokerError> {
+        self.channels
+            .get_mut(&channel_id)
+            .ok_or(AmqpBrokerError::ChannelError(format!(
+                "expected 'channel.open' on channel {}",
+                channel_id
+            )))
+    }
+
+    async fn process_channel_method(
+        &mut self,
+        channel_id: u16,
+        method: Method,
+    ) -> Result<(), AmqpBrokerError> {
+        match method {
+            Method::ChannelOpen => return self.channel_open(channel_id).await,
+            Method::ChannelClose(_) => {
+                self.channel(channel_id)?;
+                if let Some(channel) = self.channels.remove(&channel_id) {
+                    self.cleanup_channel(channel).await;
+                }
+                return self.send(channel_id, Method::ChannelCloseOk).await;
+            }
+            Method::ChannelCloseOk => {
+                if self.channel(channel_id)?.closing {
+                    self.channels.remove(&channel_id);
+                }
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        if self.open_channel(channel_id)?.is_none() {
+            return Ok(());
+        }
+
+        match method {
+            Method::ChannelFlow(flow) => {
+                self.channel(channel_id)?.delivery.set_active(flow.active);
+                self.queue_manager.wake_all();
+                self.send(channel_id, Method::ChannelFlowOk(flow)).await
+            }
+            Method::ExchangeDeclare(declare) => self.exchange_declare(channel_id, declare).await,
+            Method::ExchangeDelete(delete) => self.exchange_delete(channel_id, delete).await,
+            Method::ExchangeBind(_) | Method::ExchangeUnbind(_) => Err(
+                AmqpBrokerError::NotImplemented("exchange to exchange bindings".to_string()),
+            ),
+            Method::QueueDeclare(declare) => self.queue_declare(channel_id, declare).await,
+            Method::QueueBind(bind) => {
+                let queue_name = self.channel(channel_id)?.resolve_queue_name(&bind.queue)?;
+                let binding = self.check_binding(
+                    &queue_name,
+                    &bind.exchange,
+                    &bind.routing_key,
+                    &bind.arguments,
+                )?;
+                self.queue_manager.bind_queue(&queue_name, binding).await?;
+                if bind.no_wait {
+                    return Ok(());
+                }
+                self.send(channel_id, Method::QueueBindOk).await
+            }
+            Method::QueueUnbind(unbind) => {
+                let queue_name = self
+                    .channel(channel_id)?
+                    .resolve_queue_name(&unbind.queue)?;
+                let binding = self.check_binding(
+                    &queue_name,
+                    &unbind.exchange,
+                    &unbind.routing_key,
+                    &unbind.arguments,
+                )?;
+                self.queue_manager
+                    .unbind_queue(&queue_name, &binding)
+                    .await?;
+                self.send(channel_id, Method::QueueUnbindOk).await
+            }
+            Method::QueuePurge(purge) => {
+                let queue_name = self.channel(channel_id)?.resolve_queue_name(&purge.queue)?;
+                self.check_queue_access(&queue_name)?;
+                let message_count = self.queue_manager.purge(&queue_name).await?;
+                if purge.no_wait {
+                    return Ok(());
+                }
+                self.send(
+                    channel_id,
+                    Method::QueuePurgeOk(MessageCount { message_count }),
+                )
+                .await
+            }
+            Method::QueueDelete(delete) => self.queue_delete(channel_id, delete).await,
+            Method::BasicQos(qos) => {
+                self.channel(channel_id)?
+                    .delivery
+                    .set_prefetch_count(qos.prefetch_count);
+                self.queue_manager.wake_all();
+                self.send(channel_id, Method::BasicQosOk).await
+            }
+            Method::BasicConsume(consume) => self.basic_consume(channel_id, consume).await,
+            Method::BasicCancel(cancel) => {
+                let channel = self.channel(channel_id)?;
+                if let Some(queue_name) = channel.consumers.remove(&cancel.consumer_tag) {
+                    let delivery = channel.delivery.clone();
+                    self.queue_manager
+                        .cancel_consumer(&queue_name, &delivery, &cancel.consumer_tag)
+                        .await?;
+                }
+                if cancel.no_wait {
+                    return Ok(());
+                }
+                self.send(
+                    channel_id,
+                    Method::BasicCancelOk(ConsumerTag {
+                        consumer_tag: cancel.consumer_tag,
+                    }),
+                )
+                .await
+            }
+            Method::BasicPublish(publish) => self.basic_publish(channel_id, publish),
+            Method::BasicGet(get) => {
+                let queue_name = self.channel(channel_id)?.resolve_queue_name(&get.queue)?;
+                self.check_queue_access(&queue_name)?;
+                self.basic_get(channel_id, &queue_name, get.no_ack).await
+            }
+            Method::BasicAck(ack) => {
+                let settled = self
+                    .channel(channel_id)?
+                    .delivery
+                    .settle(ack.delivery_tag, ack.multiple)?;
+                self.settle(settled, false).await;
+                Ok(())
+            }
+            Method::BasicReject(reject) => {
+                let settled = self
+                    .channel(channel_id)?
+                    .delivery
+                    .settle(reject.delivery_tag, false)?;
+                self.settle(settled, reject.requeue).await;
+                Ok(())
+            }
+            Method::BasicNack(nack) => {
+                let settled = self
+                    .channel(channel_id)?
+                    .delivery
+                    .settle(nack.delivery_tag, nack.multiple)?;
+                self.settle(settled, nack.requeue).await;
+                Ok(())
+            }
+            Method::BasicRecover(recover) | Method::BasicRecoverAsync(recover) => {
+                if !recover.requeue {
+                    return Err(AmqpBrokerError::NotImplemented(
+                        "basic.recover with requeue=false".to_string(),
+                    ));
+                }
+                let unacked = self.channel(channel_id)?.delivery.take_all();
+                self.settle(unacked, true).await;
+                if matches!(method, Method::BasicRecover(_)) {
+                    return self.send(channel_id, Method::BasicRecoverOk).await;
+                }
+                Ok(())
+            }
+            Method::ConfirmSelect(select) => {
+                self.channel(channel_id)?.confirm_mode = true;
+                if select.no_wait {
+                    return Ok(());
+                }
+                self.send(channel_id, Method::ConfirmSelectOk).await
+            }
+            method => Err(AmqpBrokerError::CommandInvalid(format!(
+                "unexpected method {:?} on channel {}",
+                method.id(),
+                channel_id
+            ))),
+        }
+    }
+
+    async fn channel_open(&mut self, channel_id: u16) -> Result<(), AmqpBrokerError> {
+        if self.channels.contains_key(&channel_id) {
+            return Err(AmqpBrokerError::ChannelError(format!(
+                "second 'channel.open' seen on channel {}",
+                channel_id
+            )));
+        }
+        if self.tune.channel_max != 0 && channel_id > self.tune.channel_max {
+            return Err(AmqpBrokerError::NotAllowed(format!(
+                "channel {} exceeds channel_max {}",
+                channel_id, self.tune.channel_max
+            )));
+        }
+        let delivery = Arc::new(ChannelDelivery::new(
+            self.connection_id,
+            channel_id,
+            self.sender.clone(),
+            self.tune.frame_max,
+            broker_amqp_conf().system.default_prefetch_count,
+        ));
+        self.channels.insert(channel_id, AmqpChannel::new(delivery));
+        self.send(channel_id, Method::ChannelOpenOk).await
+    }
+
+    async fn close_channel(
+        &mut self,
+        channel_id: u16,
+        error: &AmqpBrokerError,
+        method_id: (u16, u16),
+    ) {
+        let channel = if let Some(channel) = self.channels.remove(&channel_id) {
+            channel
+        } else {
+            return;
+        };
+        let delivery = channel.delivery.clone();
+        self.cleanup_channel(channel).await;
+
+        // keep a closing placeholder until the client answers with channel.close-ok
+        let mut placeholder = AmqpChannel::new(delivery);
+        placeholder.closing = true;
+        self.channels.insert(channel_id, placeholder);
+
+        let close = Method::ChannelClose(Close {
+            reply_code: error.reply_code(),
+            reply_text: error.to_string(),
+            class_id: method_id.0,
+            method_id: method_id.1,
+        });
+        if let Err(e) = self.send(channel_id, close).await {
+            error!("Failed to send channel.close: {}", e);
+        }
+    }
+
+    async fn cleanup_channel(&mut self, channel: AmqpChannel) {
+        for (consumer_tag, queue_name) in channel.consumers.iter() {
+            if let Err(e) = self
+                .queue_manager
+                .cancel_consumer(queue_name, &channel.delivery, consumer_tag)
+                .await
+            {
+                error!("Failed to cancel consumer {}: {}", consumer_tag, e);
+            }
+        }
+        let unacked = channel.delivery.take_all();
+        self.settle(unacked, true).await;
+    }
+
+    async fn settle(&self, messages: Vec<UnackedMessage>, requeue: bool) {
+        for (queue_name, messages) in group_by_queue(messages) {
+            if requeue {
+                self.queue_manager.requeue(&queue_name, messages);
+            } else {
+                let offsets: Vec<u64> = messages.iter().map(|m| m.offset).collect();
+                self.queue_manager.ack(&queue_name, &offsets).await;
+            }
+        }
+        self.queue_manager.wake_all();
+    }
+
+    async fn send(&self, channel_id: u16, method: Method) -> Result<(), AmqpBrokerError> {
+        self.sender
+            .send(vec![AmqpFrame::Method(channel_id, method)])
+            .await
+            .map_err(|_| AmqpBrokerError::ConnectionClosed)
+    }
+
+    fn check_queue_access(&self, queue_name: &str) -> Result<Arc<QueueState>, AmqpBrokerError> {
+        let state = self
+            .queue_manager
+            .get_queue_state(queue_name)
+            .ok_or(AmqpBrokerError::QueueNotFound(queue_name.to_string()))?;
+        if let Some(owner) = state.owner {
+            if owner != self.connection_id {
+                return Err(AmqpBrokerError::ResourceLocked(queue_name.to_string()));
+            }
+        }
+        Ok(state)
+    }
+
+    fn check_binding(
+        &self,
+        queue_name: &str,
+        exchange_name: &str,
+        routing_key: &str,
+        arguments: &FieldTable,
+    ) -> Result<AmqpBinding, AmqpBrokerError> {
+        self.check_queue_access(queue_name)?;
+        if exchange_name == DEFAULT_EXCHANGE {
+            return Err(AmqpBrokerError::AccessRefused(
+                "operation not permitted on the default exchange".to_string(),
+            ));
+        }
+        let exchange = self
+            .cache_manager
+            .get_exchange(exchange_name)
+            .ok_or(AmqpBrokerError::ExchangeNotFound(exchange_name.to_string()))?;
+        let binding = AmqpBinding {
+            exchange_name: exchange_name.to_string(),
+            routing_key: routing_key.to_string(),
+            arguments: field_table_to_arguments(arguments),
+        };
+        if let AmqpExchangeType::Custom(kind) = &exchange.exchange_type {
+            if let Some(plugin) = self.plugins.exchange_type(kind) {
+                plugin
+                    .validate_binding(&binding)
+                    .map_err(|e| AmqpBrokerError::PreconditionFailed(e.to_string()))?;
+            }
+        }
+        Ok(binding)
+    }
+
+    async fn exchange_declare(
+        &mut self,
+        channel_id: u16,
+        declare: ExchangeDeclare,
+    ) -> Result<(), AmqpBrokerError> {
+        if declare.exchange == DEFAULT_EXCHANGE {
+            return Err(AmqpBrokerError::AccessRefused(
+                "operation not permitted on the default exchange".to_string(),
+            ));
+        }
+
+        if let Some(exchange) = self.cache_manager.get_exchange(&declare.exchange) {
+            if !declare.passive
+                && (exchange.exchange_type.to_string() != declare.kind
+                    || exchange.durable != declare.durable)
+            {
+                return Err(AmqpBrokerError::PreconditionFailed(format!(
+                    "inequivalent arg 'type' or 'durable' for exchange '{}' in vhost '/'",
+                    declare.exchange
+                )));
+            }
+        } else {
+            if declare.passive {
+                return Err(AmqpBrokerError::ExchangeNotFound(declare.exchange));
+            }
+            if declare.exchange.starts_with("amq.") {
+                return Err(AmqpBrokerError::AccessRefused(format!(
+                    "exchange name '{}' contains reserved prefix 'amq.*'",
+                    declare.exchange
+                )));
+            }
+            let plugin = self.plugins.exchange_type(&declare.kind);
+            let exchange_type = if plugin.is_some() {
+                AmqpExchangeType::Custom(declare.kind.clone())
+            } else {
+                AmqpExchangeType::from_str(&declare.kind).map_err(|_| {
+                    AmqpBrokerError::CommandInvalid(format!(
+                        "unknown exchange type '{}'",
+                        declare.kind
+                    ))
+                })?
+            };
+            let exchange = AmqpExchange {
+                exchange_name: declare.exchange.clone(),
+                exchange_type,
+                durable: declare.durable,
+                auto_delete: declare.auto_delete,
+                internal: declare.internal,
+                create_time: now_second(),
+                arguments: field_table_to_arguments(&declare.arguments),
+            };
+            if let Some(plugin) = plugin {
+                plugin
+                    .validate_exchange(&exchange)
+                    .map_err(|e| AmqpBrokerError::PreconditionFailed(e.to_string()))?;
+            }
+            if exchange.durable {
+                self.exchange_storage.save_exchange(&exchange).await?;
+            }
+            self.cache_manager.add_exchange(exchange);
+        }
+
+        if declare.no_wait {
+            return Ok(());
+        }
+        self.send(channel_id, Method::ExchangeDeclareOk).await
+    }
+
+    async fn exchange_delete(
+        &mut self,
+        channel_id: u16,
+        delete: ExchangeDelete,
+    ) -> Result<(), AmqpBrokerError> {
+        if delete.exchange == DEFAULT_EXCHANGE || delete.exchange.starts_with("amq.") {
+            return Err(AmqpBrokerError::AccessRefused(format!(
+                "operation not permitted on exchange '{}'",
+                delete.exchange
+            )));
+        }
+        let exchange = if let Some(exchange) = self.cache_manager.get_exchange(&delete.exchange) {
+            exchange
+        } else {
+            return Err(AmqpBrokerError::ExchangeNotFound(delete.exchange));
+        };
+        if delete.if_unused && self.cache_manager.exchange_in_use(&delete.exchange) {
+            return Err(AmqpBrokerError::PreconditionFailed(format!(
+                "exchange '{}' in vhost '/' in use",
+                delete.exchange
+            )));
+        }
+
+        for (queue_name, binding) in self.cache_manager.exchange_bindings(&delete.exchange) {
+            self.queue_manager
+                .unbind_queue(&queue_name, &binding)
+                .await?;
+        }
+
+        if exchange.durable {
+            self.exchange_storage
+                .delete_exchange(&delete.exchange)
+                .await?;
+        }
+        self.cache_manager.remove_exchange(&delete.exchange);
+
+        if delete.no_wait {
+            return Ok(());
+        }
+        self.send(channel_id, Method::ExchangeDeleteOk).await
+    }
+
+    async fn queue_declare(
+        &mut self,
+        channel_id: u16,
+        declare: QueueDeclare,
+    ) -> Result<(), AmqpBrokerError> {
+        let queue_name = if declare.queue.is_empty() {
+            format!("amq.gen-{}", unique_id())
+        } else {
+            declare.queue.clone()
+        };
+
+        let state = if let Some(queue) = self.cache_manager.get_queue(&queue_name) {
+            let state = self.check_queue_access(&queue_name)?;
+            if !declare.passive
+                && (queue.durable != declare.durable
+                    || queue.exclusive != declare.exclusive
+                    || queue.auto_delete != declare.auto_delete)
+            {
+                return Err(AmqpBrokerError::PreconditionFailed(format!(
+                    "inequivalent arg 'durable', 'exclusive' or 'auto_delete' for queue '{}' in vhost '/'",
+                    queue_name
+                )));
+            }
+            state
+        } else {
+            if declare.passive {
+                return Err(AmqpBrokerError::QueueNotFound(queue_name));
+            }
+            if declare.queue.starts_with("amq.") {
+                return Err(AmqpBrokerError::AccessRefused(format!(
+                    "queue name '{}' contains reserved prefix 'amq.*'",
+                    queue_name
+                )));
+            }
+            let queue = AmqpQueue {
+                queue_name: queue_name.clone(),
+                durable: declare.durable,
+                exclusive: declare.exclusive,
+                auto_delete: declare.auto_delete,
+                bindings: Vec::new(),
+                create_time: now_second(),
+            };
+            let owner = if declare.exclusive {
+                Some(self.connection_id)
+            } else {
+                None
+            };
+            self.queue_manager.declare_queue(queue, owner).await?
+        };
+
+        self.channel(channel_id)?.last_queue = Some(queue_name.clone());
+        if declare.no_wait {
+            return Ok(());
+        }
+        let message_count = self.queue_manager.message_count(&queue_name).await?;
+        self.send(
+            channel_id,
+            Method::QueueDeclareOk(QueueDeclareOk {
+                queue: queue_name,
+                message_count,
+                consumer_count: state.consumer_count(),
+            }),
+        )
+        .await
+    }
+
+    async fn queue_delete(
+        &mut self,
+        channel_id: u16,
+        delete: QueueDelete,
+    ) -> Result<(), AmqpBrokerError> {
+        let queue_name = self
+            .channel(channel_id)?
+            .resolve_queue_name(&delete.queue)?;
+        let state = self.check_queue_access(&queue_name)?;
+        if delete.if_unused && state.consumer_count() > 0 {
+            return Err(AmqpBrokerError::PreconditionFailed(format!(
+                "queue '{}' in vhost '/' in use",
+                queue_name
+            )));
+        }
+        if delete.if_empty && self.queue_manager.message_count(&queue_name).await? > 0 {
+            return Err(AmqpBrokerError::PreconditionFailed(format!(
+                "queue '{}' in vhost '/' is not empty",
+                queue_name
+            )));
+        }
+        let message_count = self.queue_manager.delete_queue(&queue_name).await?;
+        if delete.no_wait {
+            return Ok(());
+        }
+        self.send(
+            channel_id,
+            Method::QueueDeleteOk(MessageCount { message_count }),
+        )
+        .await
+    }
+
+    async fn basic_consume(
+        &mut self,
+        channel_id: u16,
+        consume: BasicConsume,
+    ) -> Result<(), AmqpBrokerError> {
+        let queue_name = self
+            .channel(channel_id)?
+            .resolve_queue_name(&consume.queue)?;
+        let state = self.check_queue_access(&queue_name)?;
+        if consume.exclusive && state.consumer_count() > 0 {
+            return Err(AmqpBrokerError::AccessRefused(format!(
+                "queue '{}' in vhost '/' in exclusive use",
+                queue_name
+            )));
+        }
+
+        let consumer_tag = if consume.consumer_tag.is_empty() {
+            format!("amq.ctag-{}", unique_id())
+        } else {
+            consume.consumer_tag.clone()
+        };
+        let channel = self.channel(channel_id)?;
+        if channel.consumers.contains_key(&consumer_tag) {
+            return Err(AmqpBrokerError::NotAllowed(format!(
+                "attempt to reuse consumer tag '{}'",
+                consumer_tag
+            )));
+        }
+        let delivery = channel.delivery.clone();
+
+        // consume-ok has to reach the client before the first basic.deliver
+        if !consume.no_wait {
+            self.send(
+                channel_id,
+                Method::BasicConsumeOk(ConsumerTag {
+                    consumer_tag: consumer_tag.clone(),
+                }),
+            )
+            .await?;
+        }
+        self.queue_manager.add_consumer(
+            &queue_name,
+            Consumer {
+                consumer_tag: consumer_tag.clone(),
+                no_ack: consume.no_ack,
+                exclusive: consume.exclusive,
+                delivery,
+            },
+        )?;
+        self.channel(channel_id)?
+            .consumers
+            .insert(consumer_tag, queue_name);
+        Ok(())
+    }
+
+    fn basic_publish(
+        &mut self,
+        channel_id: u16,
+        publish: BasicPublish,
+    ) -> Result<(), AmqpBrokerError> {
+        match self.cache_manager.get_exchange(&publish.exchange) {
+            Some(exchange) if exchange.internal => {
+                return Err(AmqpBrokerError::AccessRefused(format!(
+                    "cannot publish to internal exchange '{}' in vhost '/'",
+                    publish.exchange
+                )))
+            }
+            Some(_) => {}
+            None => return Err(AmqpBrokerError::ExchangeNotFound(publish.exchange)),
+        }
+        self.channel(channel_id)?.start_publish(publish)
+    }
+
+    async fn publish(
+        &mut self,
+        channel_id: u16,
+        complete: CompletePublish,
+    ) -> Result<(), AmqpBrokerError> {
+        let publish = complete.publish;
+        let channel = self.channel(channel_id)?;
+        let publish_seq = channel.next_publish_seq();
+        let delivery = channel.delivery.clone();
+
+        let mut content = PluginMessage {
+            exchange: publish.exchange.clone(),
+            routing_key: publish.routing_key.clone(),
+            properties: complete.properties,
+            body: complete.body,
+        };
+        self.plugins
+            .intercept_publish(&mut content)
+            .map_err(|e| AmqpBrokerError::PreconditionFailed(e.to_string()))?;
+        let routed = self.route(&content);
+        let message = AmqpMessage::build(
+            content.exchange,
+            content.routing_key,
+            &content.properties,
+            content.body,
+        )?;
+
+        let mut stored = true;
+        match routed {
+            Routed::Delayed(exchange, delay) => {
+                // confirmed once held, a delayed message is never returned as unroutable
+                self.publish_delayed(exchange, delay, &message)?;
+            }
+            Routed::Queues(queue_names) if queue_names.is_empty() => {
+                if publish.mandatory {
+                    let method = Method::BasicReturn(BasicReturn {
+                        reply_code: REPLY_NO_ROUTE,
+                        reply_text: "NO_ROUTE".to_string(),
+                        exchange: publish.exchange.clone(),
+                        routing_key: publish.routing_key.clone(),
+                    });
+                    delivery
+                        .send(delivery.content_frames(method, &message)?)
+                        .await?;
+                }
+            }
+            Routed::Queues(queue_names) => {
+                let record = message.to_record()?;
+                for queue_name in queue_names {
+                    if let Err(e) = self
+                        .queue_manager
+                        .publish(&queue_name, record.clone())
+                        .await
+                    {
+                        error!("Failed to store message in queue {}: {}", queue_name, e);
+                        stored = false;
+                    }
+                }
+            }
+        }
+
+        // unroutable messages are confirmed as well, after their basic.return
+        if let Some(delivery_tag) = publish_seq {
+            let method = if stored {
+                Method::BasicAck(BasicAck {
+                    delivery_tag,
+                    multiple: false,
+                })
+            } else {
+                Method::BasicNack(BasicNack {
+                    delivery_tag,
+                    multiple: false,
+                    requeue: false,
+                })
+            };
+            self.send(channel_id, method).await?;
+        }
+        Ok(())
+    }
+
+    // Exchanges of a plugin type are routed by their plugin, the rest by the cache
+    fn route(&self, message: &PluginMessage) -> Routed {
+        let exchange = match self.cache_manager.get_exchange(&message.exchange) {
+            Some(exchange) if matches!(exchange.exchange_type, AmqpExchangeType::Custom(_)) => {
+                exchange
+            }
+            _ => {
+                return Routed::Queues(
+                    self.cache_manager
+                        .route(&message.exchange, &message.routing_key),
+                )
+            }
+        };
+
+        let kind = exchange.exchange_type.to_string();
+        let plugin = if let Some(plugin) = self.plugins.exchange_type(&kind) {
+            plugin
+        } else {
+            warn!(
+                "Exchange type {} of exchange {} is not enabled, the message is dropped",
+                kind, exchange.exchange_name
+            );
+            return Routed::Queues(Vec::new());
+        };
+        let bindings = self
+            .cache_manager
+            .exchange_bindings(&exchange.exchange_name);
+        match plugin.route(&exchange, &bindings, message) {
+            Route::Queues(queue_names) => Routed::Queues(queue_names),
+            Route::Standard {
+                exchange_type,
+                delay,
+            } => {
+                let exchange = AmqpExchange {
+                    exchange_type,
+                    ..exchange
+                };
+                match delay {
+                    Some(delay) => Routed::Delayed(exchange, delay),
+                    None => Routed::Queues(
+                        self.cache_manager
+                            .route_with(&exchange, &message.routing_key),
+                    ),
+                }
+            }
+        }
+    }
+
+    // Delayed messages are only held in memory, they are lost if the broker stops first
+    fn publish_delayed(
+        &self,
+        exchange: AmqpExchange,
+        delay: Duration,
+        message: &AmqpMessage,
+    ) -> Result<(), AmqpBrokerError> {
+        let record = message.to_record()?;
+        let routing_key = message.routing_key.clone();
+        let cache_manager = self.cache_manager.clone();
+        let queue_manager = self.queue_manager.clone();
+        tokio::spawn(async move {
+            sleep(delay).await;
+            // bindings are looked up when the delay is over, not when it was published
+            for queue_name in cache_manager.route_with(&exchange, &routing_key) {
+                if let Err(e) = queue_manager.publish(&queue_name, record.clone()).await {
+                    error!(
+                        "Failed to store delayed message in queue {}: {}",
+                        queue_name, e
+                    );
+                }
+            }
+        });
+        Ok(())
+    }
+
+    async fn basic_get(
+        &mut self,
+        channel_id: u16,
+        queue_name: &str,
+        no_ack: bool,
+    ) -> Result<(), AmqpBrokerError> {
+        let (message, content, message_count) =
+            match self.queue_manager.get_message(queue_name, no_ack).await? {
+                Some(result) => result,
+                None => return self.send(channel_id, Method::BasicGetEmpty).await,
+            };
+
+        let delivery = self.channel(channel_id)?.delivery.clone();
+        let delivery_tag = delivery.next_delivery_tag();
+        let method = Method::BasicGetOk(BasicGetOk {
+            delivery_tag,
+            redelivered: message.redelivered,
+            exchange: message.message.exchange.clone(),
+            routing_key: message.message.routing_key.clone(),
+            message_count,
+        });
+        let frames = delivery.content_frames(method, &content)?;
+        if !no_ack {
+            delivery.track(delivery_tag, queue_name, message);
+        }
+        delivery.send(frames).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{negotiate_u16, negotiate_u32};
+
+    #[test]
+    fn negotiate_test() {
+        assert_eq!(negotiate_u16(2047, 0), 2047);
+        assert_eq!(negotiate_u16(2047, 100), 100);
+        assert_eq!(negotiate_u16(0, 100), 100);
+        assert_eq!(negotiate_u32(131072, 0), 131072);
+        assert_eq!(negotiate_u32(131072, 1_048_576), 131072);
+    }
+}
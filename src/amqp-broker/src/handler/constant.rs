@@ -0,0 +1,36 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Reply codes from the AMQP 0-9-1 specification
+pub const REPLY_SUCCESS: u16 = 200;
+pub const REPLY_NO_ROUTE: u16 = 312;
+pub const REPLY_ACCESS_REFUSED: u16 = 403;
+pub const REPLY_NOT_FOUND: u16 = 404;
+pub const REPLY_RESOURCE_LOCKED: u16 = 405;
+pub const REPLY_PRECONDITION_FAILED: u16 = 406;
+pub const REPLY_FRAME_ERROR: u16 = 501;
+pub const REPLY_COMMAND_INVALID: u16 = 503;
+pub const REPLY_CHANNEL_ERROR: u16 = 504;
+pub const REPLY_UNEXPECTED_FRAME: u16 = 505;
+pub const REPLY_NOT_ALLOWED: u16 = 530;
+pub const REPLY_NOT_IMPLEMENTED: u16 = 540;
+pub const REPLY_INTERNAL_ERROR: u16 = 541;
+
+pub const DEFAULT_EXCHANGE: &str = "";
+pub const DEFAULT_VIRTUAL_HOST: &str = "/";
+pub const SUPPORTED_MECHANISM: &str = "PLAIN";
+pub const SUPPORTED_LOCALE: &str = "en_US";
+
+// Messages read from the queue shard per storage call
+pub const QUEUE_READ_BATCH_SIZE: u64 = 100;
@@ -0,0 +1,235 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use bytes::Bytes;
+use protocol::amqp::common::{AmqpFrame, Method, FRAME_OVERHEAD};
+use tokio::sync::mpsc;
+
+use crate::handler::error::AmqpBrokerError;
+use crate::handler::queue::QueuedMessage;
+use crate::storage::message::AmqpMessage;
+
+// Frames pushed as one item are written back to back, so content never interleaves
+pub type FrameSender = mpsc::Sender<Vec<AmqpFrame>>;
+
+pub struct UnackedMessage {
+    pub queue_name: String,
+    pub message: QueuedMessage,
+}
+
+// Delivery state of a channel, shared with the queues its consumers are attached to
+pub struct ChannelDelivery {
+    pub connection_id: u64,
+    pub channel_id: u16,
+    sender: FrameSender,
+    frame_max: u32,
+    next_delivery_tag: AtomicU64,
+    // 0 means no limit
+    prefetch_count: AtomicU16,
+    // channel.flow
+    active: AtomicBool,
+    // (delivery_tag, UnackedMessage)
+    unacked: Mutex<BTreeMap<u64, UnackedMessage>>,
+}
+
+impl ChannelDelivery {
+    pub fn new(
+        connection_id: u64,
+        channel_id: u16,
+        sender: FrameSender,
+        frame_max: u32,
+        prefetch_count: u16,
+    ) -> Self {
+        ChannelDelivery {
+            connection_id,
+            channel_id,
+            sender,
+            frame_max,
+            next_delivery_tag: AtomicU64::new(1),
+            prefetch_count: AtomicU16::new(prefetch_count),
+            active: AtomicBool::new(true),
+            unacked: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn set_prefetch_count(&self, prefetch_count: u16) {
+        self.prefetch_count.store(prefetch_count, Ordering::Relaxed);
+    }
+
+    pub fn set_active(&self, active: bool) {
+        self.active.store(active, Ordering::Relaxed);
+    }
+
+    pub fn has_capacity(&self) -> bool {
+        if !self.active.load(Ordering::Relaxed) || self.sender.is_closed() {
+            return false;
+        }
+        let prefetch_count = self.prefetch_count.load(Ordering::Relaxed) as usize;
+        prefetch_count == 0 || self.unacked.lock().unwrap().len() < prefetch_count
+    }
+
+    pub fn next_delivery_tag(&self) -> u64 {
+        self.next_delivery_tag.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub fn track(&self, delivery_tag: u64, queue_name: &str, message: QueuedMessage) {
+        self.unacked.lock().unwrap().insert(
+            delivery_tag,
+            UnackedMessage {
+                queue_name: queue_name.to_string(),
+                message,
+            },
+        );
+    }
+
+    pub fn untrack(&self, delivery_tag: u64) -> Option<UnackedMessage> {
+        self.unacked.lock().unwrap().remove(&delivery_tag)
+    }
+
+    // Removes the deliveries settled by basic.ack, basic.nack or basic.reject.
+    // With `multiple` every tag up to and including `delivery_tag` is settled, 0 meaning all.
+    pub fn settle(
+        &self,
+        delivery_tag: u64,
+        multiple: bool,
+    ) -> Result<Vec<UnackedMessage>, AmqpBrokerError> {
+        let mut unacked = self.unacked.lock().unwrap();
+        if !multiple {
+            return unacked
+                .remove(&delivery_tag)
+                .map(|message| vec![message])
+                .ok_or(AmqpBrokerError::UnknownDeliveryTag(delivery_tag));
+        }
+
+        if delivery_tag == 0 {
+            return Ok(std::mem::take(&mut *unacked).into_values().collect());
+        }
+        if !unacked.contains_key(&delivery_tag) {
+            return Err(AmqpBrokerError::UnknownDeliveryTag(delivery_tag));
+        }
+        let rest = unacked.split_off(&(delivery_tag + 1));
+        Ok(std::mem::replace(&mut *unacked, rest)
+            .into_values()
+            .collect())
+    }
+
+    pub fn take_all(&self) -> Vec<UnackedMessage> {
+        std::mem::take(&mut *self.unacked.lock().unwrap())
+            .into_values()
+            .collect()
+    }
+
+    pub async fn send(&self, frames: Vec<AmqpFrame>) -> Result<(), AmqpBrokerError> {
+        self.sender
+            .send(frames)
+            .await
+            .map_err(|_| AmqpBrokerError::ConnectionClosed)
+    }
+
+    // A content method followed by its header frame and the body split by frame-max
+    pub fn content_frames(
+        &self,
+        method: Method,
+        message: &AmqpMessage,
+    ) -> Result<Vec<AmqpFrame>, AmqpBrokerError> {
+        let header = message.content_header()?;
+        let mut frames = vec![
+            AmqpFrame::Method(self.channel_id, method),
+            AmqpFrame::Header(self.channel_id, header),
+        ];
+        let body = Bytes::from(message.body.clone());
+        let chunk_size = if self.frame_max == 0 {
+            body.len().max(1)
+        } else {
+            self.frame_max as usize - FRAME_OVERHEAD
+        };
+        let mut start = 0;
+        while start < body.len() {
+            let end = (start + chunk_size).min(body.len());
+            frames.push(AmqpFrame::Body(self.channel_id, body.slice(start..end)));
+            start = end;
+        }
+        Ok(frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use protocol::amqp::common::{AmqpFrame, BasicProperties, Method};
+    use tokio::sync::mpsc;
+
+    use super::ChannelDelivery;
+    use crate::handler::queue::QueuedMessage;
+    use crate::storage::message::AmqpMessage;
+
+    fn build_message(offset: u64, body: &'static [u8]) -> QueuedMessage {
+        QueuedMessage {
+            offset,
+            message: AmqpMessage::build(
+                "".to_string(),
+                "q1".to_string(),
+                &BasicProperties::default(),
+                Bytes::from_static(body),
+            )
+            .unwrap(),
+            redelivered: false,
+        }
+    }
+
+    #[test]
+    fn settle_test() {
+        let (sender, _receiver) = mpsc::channel(10);
+        let delivery = ChannelDelivery::new(1, 1, sender, 4096, 2);
+        for offset in 0..4 {
+            let tag = delivery.next_delivery_tag();
+            delivery.track(tag, "q1", build_message(offset, b"a"));
+        }
+        assert!(!delivery.has_capacity());
+
+        assert!(delivery.settle(9, false).is_err());
+        assert_eq!(delivery.settle(2, false).unwrap().len(), 1);
+        assert!(delivery.settle(2, true).is_err());
+
+        let settled = delivery.settle(3, true).unwrap();
+        let offsets: Vec<u64> = settled.iter().map(|m| m.message.offset).collect();
+        assert_eq!(offsets, vec![0, 2]);
+        assert!(delivery.has_capacity());
+
+        assert_eq!(delivery.settle(0, true).unwrap().len(), 1);
+        assert!(delivery.take_all().is_empty());
+    }
+
+    #[test]
+    fn content_frames_test() {
+        let (sender, _receiver) = mpsc::channel(10);
+        let delivery = ChannelDelivery::new(1, 3, sender, 10, 0);
+        let frames = delivery
+            .content_frames(Method::BasicGetEmpty, &build_message(0, b"hello").message)
+            .unwrap();
+        // frame-max 10 leaves 2 body bytes per frame
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[2], AmqpFrame::Body(3, Bytes::from_static(b"he")));
+        assert_eq!(frames[4], AmqpFrame::Body(3, Bytes::from_static(b"o")));
+
+        let frames = delivery
+            .content_frames(Method::BasicGetEmpty, &build_message(0, b"").message)
+            .unwrap();
+        assert_eq!(frames.len(), 2);
+    }
+}
@@ -0,0 +1,108 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::error::common::CommonError;
+use thiserror::Error;
+
+use crate::handler::constant::{
+    REPLY_ACCESS_REFUSED, REPLY_CHANNEL_ERROR, REPLY_COMMAND_INVALID, REPLY_FRAME_ERROR,
+    REPLY_INTERNAL_ERROR, REPLY_NOT_ALLOWED, REPLY_NOT_FOUND, REPLY_NOT_IMPLEMENTED,
+    REPLY_PRECONDITION_FAILED, REPLY_RESOURCE_LOCKED, REPLY_UNEXPECTED_FRAME,
+};
+
+#[derive(Error, Debug)]
+pub enum AmqpBrokerError {
+    #[error("{0}")]
+    FromIoError(#[from] std::io::Error),
+
+    #[error("{0}")]
+    FromCommonError(#[from] CommonError),
+
+    #[error("{0}")]
+    SerdeJsonError(#[from] serde_json::Error),
+
+    #[error("{0}")]
+    FromCodecError(#[from] protocol::amqp::common::Error),
+
+    #[error("NOT_FOUND - no exchange '{0}' in vhost '/'")]
+    ExchangeNotFound(String),
+
+    #[error("NOT_FOUND - no queue '{0}' in vhost '/'")]
+    QueueNotFound(String),
+
+    #[error("PRECONDITION_FAILED - {0}")]
+    PreconditionFailed(String),
+
+    #[error("ACCESS_REFUSED - {0}")]
+    AccessRefused(String),
+
+    #[error("RESOURCE_LOCKED - cannot obtain exclusive access to locked queue '{0}' in vhost '/'")]
+    ResourceLocked(String),
+
+    #[error("PRECONDITION_FAILED - unknown delivery tag {0}")]
+    UnknownDeliveryTag(u64),
+
+    #[error("NOT_ALLOWED - {0}")]
+    NotAllowed(String),
+
+    #[error("COMMAND_INVALID - {0}")]
+    CommandInvalid(String),
+
+    #[error("CHANNEL_ERROR - {0}")]
+    ChannelError(String),
+
+    #[error("UNEXPECTED_FRAME - {0}")]
+    UnexpectedFrame(String),
+
+    #[error("NOT_IMPLEMENTED - {0}")]
+    NotImplemented(String),
+
+    #[error("Connection was closed by the client")]
+    ConnectionClosed,
+}
+
+impl AmqpBrokerError {
+    pub fn reply_code(&self) -> u16 {
+        match self {
+            AmqpBrokerError::ExchangeNotFound(_) | AmqpBrokerError::QueueNotFound(_) => {
+                REPLY_NOT_FOUND
+            }
+            AmqpBrokerError::PreconditionFailed(_) | AmqpBrokerError::UnknownDeliveryTag(_) => {
+                REPLY_PRECONDITION_FAILED
+            }
+            AmqpBrokerError::AccessRefused(_) => REPLY_ACCESS_REFUSED,
+            AmqpBrokerError::ResourceLocked(_) => REPLY_RESOURCE_LOCKED,
+            AmqpBrokerError::NotAllowed(_) => REPLY_NOT_ALLOWED,
+            AmqpBrokerError::CommandInvalid(_) => REPLY_COMMAND_INVALID,
+            AmqpBrokerError::ChannelError(_) => REPLY_CHANNEL_ERROR,
+            AmqpBrokerError::UnexpectedFrame(_) => REPLY_UNEXPECTED_FRAME,
+            AmqpBrokerError::NotImplemented(_) => REPLY_NOT_IMPLEMENTED,
+            AmqpBrokerError::FromCodecError(_) => REPLY_FRAME_ERROR,
+            _ => REPLY_INTERNAL_ERROR,
+        }
+    }
+
+    // Soft errors only close the channel, everything else closes the connection
+    pub fn is_channel_error(&self) -> bool {
+        matches!(
+            self,
+            AmqpBrokerError::ExchangeNotFound(_)
+                | AmqpBrokerError::QueueNotFound(_)
+                | AmqpBrokerError::PreconditionFailed(_)
+                | AmqpBrokerError::AccessRefused(_)
+                | AmqpBrokerError::ResourceLocked(_)
+                | AmqpBrokerError::UnknownDeliveryTag(_)
+        )
+    }
+}
@@ -0,0 +1,114 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use metadata_struct::amqp::exchange::{AmqpExchange, AmqpExchangeType};
+use metadata_struct::amqp::queue::AmqpBinding;
+
+// Whether a message published to `exchange` with `routing_key` follows `binding`
+pub fn binding_matches(exchange: &AmqpExchange, binding: &AmqpBinding, routing_key: &str) -> bool {
+    if binding.exchange_name != exchange.exchange_name {
+        return false;
+    }
+    match &exchange.exchange_type {
+        AmqpExchangeType::Direct => binding.routing_key == routing_key,
+        AmqpExchangeType::Fanout => true,
+        AmqpExchangeType::Topic => topic_match(&binding.routing_key, routing_key),
+        // Routed by the exchange type plugin instead
+        AmqpExchangeType::Custom(_) => false,
+    }
+}
+
+// Topic exchange matching, words are separated by '.', '*' matches exactly one word
+// and '#' matches zero or more words.
+pub fn topic_match(pattern: &str, routing_key: &str) -> bool {
+    topic_match_words(&split_words(pattern), &split_words(routing_key))
+}
+
+// An empty key has no words rather than one empty word
+fn split_words(key: &str) -> Vec<&str> {
+    if key.is_empty() {
+        return Vec::new();
+    }
+    key.split('.').collect()
+}
+
+fn topic_match_words(pattern: &[&str], words: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => words.is_empty(),
+        Some((&"#", rest)) => {
+            // '#' may swallow any number of words, try every split point
+            (0..=words.len()).any(|skip| topic_match_words(rest, &words[skip..]))
+        }
+        Some((&"*", rest)) => !words.is_empty() && topic_match_words(rest, &words[1..]),
+        Some((word, rest)) => {
+            !words.is_empty() && words[0] == *word && topic_match_words(rest, &words[1..])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use metadata_struct::amqp::exchange::{AmqpExchange, AmqpExchangeType};
+    use metadata_struct::amqp::queue::AmqpBinding;
+
+    use super::{binding_matches, topic_match};
+
+    #[test]
+    fn topic_match_test() {
+        assert!(topic_match("order.created", "order.created"));
+        assert!(!topic_match("order.created", "order.deleted"));
+
+        assert!(topic_match("order.*", "order.created"));
+        assert!(!topic_match("order.*", "order"));
+        assert!(!topic_match("order.*", "order.created.eu"));
+        assert!(topic_match("*.created", "order.created"));
+
+        assert!(topic_match("#", ""));
+        assert!(topic_match("#", "order.created.eu"));
+        assert!(topic_match("order.#", "order"));
+        assert!(topic_match("order.#", "order.created.eu"));
+        assert!(topic_match("#.eu", "order.created.eu"));
+        assert!(topic_match("order.#.eu", "order.eu"));
+        assert!(topic_match("order.#.eu", "order.created.paid.eu"));
+        assert!(!topic_match("order.#.eu", "order.created.us"));
+        assert!(topic_match("*.#", "order"));
+        assert!(!topic_match("*.#", ""));
+    }
+
+    #[test]
+    fn binding_matches_test() {
+        let binding = AmqpBinding {
+            exchange_name: "logs".to_string(),
+            routing_key: "error".to_string(),
+            ..Default::default()
+        };
+
+        let mut exchange = AmqpExchange {
+            exchange_name: "logs".to_string(),
+            exchange_type: AmqpExchangeType::Direct,
+            ..Default::default()
+        };
+        assert!(binding_matches(&exchange, &binding, "error"));
+        assert!(!binding_matches(&exchange, &binding, "info"));
+
+        exchange.exchange_type = AmqpExchangeType::Fanout;
+        assert!(binding_matches(&exchange, &binding, "info"));
+
+        exchange.exchange_type = AmqpExchangeType::Topic;
+        assert!(!binding_matches(&exchange, &binding, "error.disk"));
+
+        exchange.exchange_name = "events".to_string();
+        assert!(!binding_matches(&exchange, &binding, "error"));
+    }
+}
@@ -0,0 +1,779 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use amqp_plugins::manager::AmqpPluginManager;
+use amqp_plugins::message::PluginMessage;
+use bytes::Bytes;
+use dashmap::DashMap;
+use log::{error, warn};
+use metadata_struct::adapter::record::Record;
+use metadata_struct::amqp::queue::{AmqpBinding, AmqpQueue};
+use protocol::amqp::common::{BasicDeliver, Method};
+use storage_adapter::storage::StorageAdapter;
+use tokio::sync::Notify;
+use tokio::time::{sleep, timeout};
+
+use crate::handler::cache::AmqpCacheManager;
+use crate::handler::constant::QUEUE_READ_BATCH_SIZE;
+use crate::handler::delivery::ChannelDelivery;
+use crate::handler::error::AmqpBrokerError;
+use crate::storage::message::{AmqpMessage, MessageStorage};
+use crate::storage::queue::QueueStorage;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueuedMessage {
+    pub offset: u64,
+    pub message: AmqpMessage,
+    pub redelivered: bool,
+}
+
+#[derive(Clone)]
+pub struct Consumer {
+    pub consumer_tag: String,
+    pub no_ack: bool,
+    pub exclusive: bool,
+    pub delivery: Arc<ChannelDelivery>,
+}
+
+impl Consumer {
+    fn is_same(&self, delivery: &Arc<ChannelDelivery>, consumer_tag: &str) -> bool {
+        Arc::ptr_eq(&self.delivery, delivery) && self.consumer_tag == consumer_tag
+    }
+}
+
+#[derive(Default)]
+struct QueueInner {
+    // Messages read from the queue shard or requeued, waiting for a consumer
+    ready: VecDeque<QueuedMessage>,
+    // Next offset to read from the queue shard
+    read_offset: u64,
+    // Offsets delivered to a consumer that has not acked them yet
+    unacked: BTreeSet<u64>,
+    committed_offset: u64,
+    consumers: Vec<Consumer>,
+    next_consumer: usize,
+}
+
+impl QueueInner {
+    // Every offset below it has been acked, this is what gets committed to the shard.
+    // Messages acked above it are delivered again after a restart.
+    fn low_watermark(&self) -> u64 {
+        let mut offset = self.read_offset;
+        if let Some(first) = self.unacked.first() {
+            offset = offset.min(*first);
+        }
+        for message in self.ready.iter() {
+            offset = offset.min(message.offset);
+        }
+        offset
+    }
+}
+
+pub struct QueueState {
+    pub queue_name: String,
+    // Connection that declared the queue when it is exclusive
+    pub owner: Option<u64>,
+    inner: Mutex<QueueInner>,
+    fill_lock: tokio::sync::Mutex<()>,
+    notify: Notify,
+    stopped: AtomicBool,
+}
+
+impl QueueState {
+    fn new(queue_name: &str, owner: Option<u64>, offset: u64) -> Self {
+        QueueState {
+            queue_name: queue_name.to_string(),
+            owner,
+            inner: Mutex::new(QueueInner {
+                read_offset: offset,
+                committed_offset: offset,
+                ..Default::default()
+            }),
+            fill_lock: tokio::sync::Mutex::new(()),
+            notify: Notify::new(),
+            stopped: AtomicBool::new(false),
+        }
+    }
+
+    pub fn message_count(&self) -> u32 {
+        self.inner.lock().unwrap().ready.len() as u32
+    }
+
+    pub fn consumer_count(&self) -> u32 {
+        self.inner.lock().unwrap().consumers.len() as u32
+    }
+
+    pub fn wake(&self) {
+        self.notify.notify_one();
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    fn has_ready_consumer(&self) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .consumers
+            .iter()
+            .any(|consumer| consumer.delivery.has_capacity())
+    }
+
+    // Round robin over the consumers that still have prefetch capacity
+    fn next_delivery(&self) -> Option<(Consumer, QueuedMessage)> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.ready.is_empty() || inner.consumers.is_empty() {
+            return None;
+        }
+        let len = inner.consumers.len();
+        for i in 0..len {
+            let index = (inner.next_consumer + i) % len;
+            if !inner.consumers[index].delivery.has_capacity() {
+                continue;
+            }
+            let consumer = inner.consumers[index].clone();
+            inner.next_consumer = (index + 1) % len;
+            let message = inner.ready.pop_front()?;
+            if !consumer.no_ack {
+                inner.unacked.insert(message.offset);
+            }
+            return Some((consumer, message));
+        }
+        None
+    }
+
+    fn settle(&self, offsets: &[u64]) {
+        let mut inner = self.inner.lock().unwrap();
+        for offset in offsets {
+            inner.unacked.remove(offset);
+        }
+    }
+
+    fn requeue(&self, mut messages: Vec<QueuedMessage>) {
+        messages.sort_by_key(|message| message.offset);
+        let mut inner = self.inner.lock().unwrap();
+        for mut message in messages.into_iter().rev() {
+            inner.unacked.remove(&message.offset);
+            message.redelivered = true;
+            inner.ready.push_front(message);
+        }
+        drop(inner);
+        self.wake();
+    }
+}
+
+// Runtime state of the queues on this broker, one dispatcher task per queue
+pub struct QueueManager<S> {
+    cache_manager: Arc<AmqpCacheManager>,
+    message_storage: MessageStorage<S>,
+    queue_storage: QueueStorage,
+    plugins: Arc<AmqpPluginManager>,
+    // (queue_name, QueueState)
+    queues: DashMap<String, Arc<QueueState>>,
+}
+
+impl<S> QueueManager<S>
+where
+    S: StorageAdapter + Send + Sync + 'static,
+{
+    pub fn new(
+        cache_manager: Arc<AmqpCacheManager>,
+        message_storage: MessageStorage<S>,
+        queue_storage: QueueStorage,
+        plugins: Arc<AmqpPluginManager>,
+    ) -> Self {
+        QueueManager {
+            cache_manager,
+            message_storage,
+            queue_storage,
+            plugins,
+            queues: DashMap::with_capacity(8),
+        }
+    }
+
+    pub fn get_queue_state(&self, queue_name: &str) -> Option<Arc<QueueState>> {
+        self.queues.get(queue_name).map(|state| state.clone())
+    }
+
+    fn queue_state(&self, queue_name: &str) -> Result<Arc<QueueState>, AmqpBrokerError> {
+        self.get_queue_state(queue_name)
+            .ok_or(AmqpBrokerError::QueueNotFound(queue_name.to_string()))
+    }
+
+    // Starts delivering a queue whose metadata is already known to the broker
+    pub async fn start_queue(
+        self: &Arc<Self>,
+        queue_name: &str,
+        owner: Option<u64>,
+    ) -> Result<Arc<QueueState>, AmqpBrokerError> {
+        let offset = self.message_storage.get_queue_offset(queue_name).await?;
+        let state = Arc::new(QueueState::new(queue_name, owner, offset));
+        self.queues.insert(queue_name.to_string(), state.clone());
+
+        let manager = self.clone();
+        let dispatch_state = state.clone();
+        tokio::spawn(async move {
+            manager.dispatch(dispatch_state).await;
+        });
+        Ok(state)
+    }
+
+    pub async fn declare_queue(
+        self: &Arc<Self>,
+        queue: AmqpQueue,
+        owner: Option<u64>,
+    ) -> Result<Arc<QueueState>, AmqpBrokerError> {
+        self.message_storage
+            .create_queue_shard(&queue.queue_name)
+            .await?;
+        if queue.durable {
+            self.queue_storage.save_queue(&queue).await?;
+        }
+        let queue_name = queue.queue_name.clone();
+        self.cache_manager.add_queue(queue);
+        self.start_queue(&queue_name, owner).await
+    }
+
+    // Returns the number of messages that were still waiting in the queue
+    pub async fn delete_queue(&self, queue_name: &str) -> Result<u32, AmqpBrokerError> {
+        let queue = if let Some(queue) = self.cache_manager.get_queue(queue_name) {
+            queue
+        } else {
+            return Err(AmqpBrokerError::QueueNotFound(queue_name.to_string()));
+        };
+
+        let mut message_count = 0;
+        if let Some((_, state)) = self.queues.remove(queue_name) {
+            state.stopped.store(true, Ordering::Relaxed);
+            state.wake();
+            message_count = state.message_count();
+        }
+        self.cache_manager.remove_queue(queue_name);
+
+        if queue.durable {
+            self.queue_storage.delete_queue(queue_name).await?;
+        }
+        // a queue declared again under the same name starts from an empty shard
+        self.message_storage
+            .commit_queue_offset(queue_name, 0)
+            .await?;
+        self.message_storage.delete_queue_shard(queue_name).await?;
+        Ok(message_count)
+    }
+
+    // Deletes the exclusive queues of a connection that went away
+    pub async fn close_connection(&self, connection_id: u64) {
+        let queue_names: Vec<String> = self
+            .queues
+            .iter()
+            .filter(|state| state.owner == Some(connection_id))
+            .map(|state| state.queue_name.clone())
+            .collect();
+        for queue_name in queue_names {
+            if let Err(e) = self.delete_queue(&queue_name).await {
+                error!("Failed to delete exclusive queue {}: {}", queue_name, e);
+            }
+        }
+    }
+
+    pub async fn bind_queue(
+        &self,
+        queue_name: &str,
+        binding: AmqpBinding,
+    ) -> Result<(), AmqpBrokerError> {
+        let mut queue = if let Some(queue) = self.cache_manager.get_queue(queue_name) {
+            queue
+        } else {
+            return Err(AmqpBrokerError::QueueNotFound(queue_name.to_string()));
+        };
+        if !queue.add_binding(binding) {
+            return Ok(());
+        }
+        if queue.durable {
+            self.queue_storage.save_queue(&queue).await?;
+        }
+        self.cache_manager.add_queue(queue);
+        Ok(())
+    }
+
+    pub async fn unbind_queue(
+        &self,
+        queue_name: &str,
+        binding: &AmqpBinding,
+    ) -> Result<(), AmqpBrokerError> {
+        let mut queue = if let Some(queue) = self.cache_manager.get_queue(queue_name) {
+            queue
+        } else {
+            return Err(AmqpBrokerError::QueueNotFound(queue_name.to_string()));
+        };
+        if !queue.remove_binding(binding) {
+            return Ok(());
+        }
+        if queue.durable {
+            self.queue_storage.save_queue(&queue).await?;
+        }
+        self.cache_manager.add_queue(queue);
+        Ok(())
+    }
+
+    pub async fn publish(&self, queue_name: &str, record: Record) -> Result<(), AmqpBrokerError> {
+        let state = self.queue_state(queue_name)?;
+        self.message_storage
+            .append_queue_message(queue_name, record)
+            .await?;
+        state.wake();
+        Ok(())
+    }
+
+    pub fn add_consumer(
+        &self,
+        queue_name: &str,
+        consumer: Consumer,
+    ) -> Result<(), AmqpBrokerError> {
+        let state = self.queue_state(queue_name)?;
+        let mut inner = state.inner.lock().unwrap();
+        if inner.consumers.iter().any(|c| c.exclusive)
+            || (consumer.exclusive && !inner.consumers.is_empty())
+        {
+            return Err(AmqpBrokerError::AccessRefused(format!(
+                "queue '{}' in vhost '/' in exclusive use",
+                queue_name
+            )));
+        }
+        inner.consumers.push(consumer);
+        drop(inner);
+        state.wake();
+        Ok(())
+    }
+
+    // Removes a consumer, an auto-delete queue goes away with its last consumer
+    pub async fn cancel_consumer(
+        &self,
+        queue_name: &str,
+        delivery: &Arc<ChannelDelivery>,
+        consumer_tag: &str,
+    ) -> Result<(), AmqpBrokerError> {
+        let state = if let Some(state) = self.get_queue_state(queue_name) {
+            state
+        } else {
+            return Ok(());
+        };
+        let remaining = {
+            let mut inner = state.inner.lock().unwrap();
+            inner
+                .consumers
+                .retain(|c| !c.is_same(delivery, consumer_tag));
+            inner.consumers.len()
+        };
+
+        if remaining == 0 {
+            if let Some(queue) = self.cache_manager.get_queue(queue_name) {
+                if queue.auto_delete {
+                    self.delete_queue(queue_name).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn ack(&self, queue_name: &str, offsets: &[u64]) {
+        if let Some(state) = self.get_queue_state(queue_name) {
+            state.settle(offsets);
+            self.commit(&state).await;
+            state.wake();
+        }
+    }
+
+    pub fn requeue(&self, queue_name: &str, messages: Vec<QueuedMessage>) {
+        if let Some(state) = self.get_queue_state(queue_name) {
+            state.requeue(messages);
+        }
+    }
+
+    // Prefetch capacity freed on a channel may unblock any queue it consumes from
+    pub fn wake_all(&self) {
+        for state in self.queues.iter() {
+            state.wake();
+        }
+    }
+
+    // basic.get, returns the message, the content to send for it and how many are
+    // still waiting behind it. Messages dropped by a consume interceptor are skipped.
+    pub async fn get_message(
+        &self,
+        queue_name: &str,
+        no_ack: bool,
+    ) -> Result<Option<(QueuedMessage, AmqpMessage, u32)>, AmqpBrokerError> {
+        let state = self.queue_state(queue_name)?;
+        loop {
+            self.fill(&state).await?;
+            let result = {
+                let mut inner = state.inner.lock().unwrap();
+                if let Some(message) = inner.ready.pop_front() {
+                    if !no_ack {
+                        inner.unacked.insert(message.offset);
+                    }
+                    Some((message, inner.ready.len() as u32))
+                } else {
+                    None
+                }
+            };
+            let (message, message_count) = match result {
+                Some(result) => result,
+                None => return Ok(None),
+            };
+
+            match self.intercept_consume(queue_name, &message) {
+                Ok(content) => {
+                    if no_ack {
+                        self.commit(&state).await;
+                    }
+                    return Ok(Some((message, content, message_count)));
+                }
+                Err(e) => {
+                    warn!(
+                        "Dropping message at offset {} of queue {}: {}",
+                        message.offset, queue_name, e
+                    );
+                    state.settle(&[message.offset]);
+                    self.commit(&state).await;
+                }
+            }
+        }
+    }
+
+    pub async fn message_count(&self, queue_name: &str) -> Result<u32, AmqpBrokerError> {
+        let state = self.queue_state(queue_name)?;
+        Ok(self.fill(&state).await? as u32)
+    }
+
+    // Drops every message that is not waiting for an ack
+    pub async fn purge(&self, queue_name: &str) -> Result<u32, AmqpBrokerError> {
+        let state = self.queue_state(queue_name)?;
+        let _guard = state.fill_lock.lock().await;
+        let (mut count, mut read_offset) = {
+            let mut inner = state.inner.lock().unwrap();
+            let count = inner.ready.len() as u32;
+            inner.ready.clear();
+            (count, inner.read_offset)
+        };
+        loop {
+            let records = self
+                .message_storage
+                .read_queue_message(queue_name, read_offset, QUEUE_READ_BATCH_SIZE)
+                .await?;
+            if records.is_empty() {
+                break;
+            }
+            count += records.len() as u32;
+            read_offset += records.len() as u64;
+        }
+        state.inner.lock().unwrap().read_offset = read_offset;
+        self.commit(&state).await;
+        Ok(count)
+    }
+
+    pub fn stop_all(&self) {
+        for state in self.queues.iter() {
+            state.stopped.store(true, Ordering::Relaxed);
+            state.wake();
+        }
+    }
+
+    // Reads the next batch from the queue shard when nothing is ready
+    async fn fill(&self, state: &Arc<QueueState>) -> Result<usize, AmqpBrokerError> {
+        let _guard = state.fill_lock.lock().await;
+        let read_offset = {
+            let inner = state.inner.lock().unwrap();
+            if !inner.ready.is_empty() {
+                return Ok(inner.ready.len());
+            }
+            inner.read_offset
+        };
+
+        let records = self
+            .message_storage
+            .read_queue_message(&state.queue_name, read_offset, QUEUE_READ_BATCH_SIZE)
+            .await?;
+
+        let mut inner = state.inner.lock().unwrap();
+        for (i, record) in records.iter().enumerate() {
+            let offset = record.offset.unwrap_or(read_offset + i as u64);
+            inner.read_offset = offset + 1;
+            match AmqpMessage::from_record(record) {
+                Ok(message) => inner.ready.push_back(QueuedMessage {
+                    offset,
+                    message,
+                    redelivered: false,
+                }),
+                Err(e) => {
+                    error!(
+                        "Skipping undecodable message at offset {} of queue {}: {}",
+                        offset, state.queue_name, e
+                    );
+                }
+            }
+        }
+        Ok(inner.ready.len())
+    }
+
+    async fn commit(&self, state: &Arc<QueueState>) {
+        let offset = {
+            let mut inner = state.inner.lock().unwrap();
+            let offset = inner.low_watermark();
+            if offset <= inner.committed_offset {
+                return;
+            }
+            inner.committed_offset = offset;
+            offset
+        };
+        if let Err(e) = self
+            .message_storage
+            .commit_queue_offset(&state.queue_name, offset)
+            .await
+        {
+            error!(
+                "Failed to commit offset {} of queue {}: {}",
+                offset, state.queue_name, e
+            );
+        }
+    }
+
+    // Content a consumer receives for `message`, the stored message is left as it is so
+    // that a redelivery runs the interceptors on the original again
+    fn intercept_consume(
+        &self,
+        queue_name: &str,
+        message: &QueuedMessage,
+    ) -> Result<AmqpMessage, AmqpBrokerError> {
+        if !self.plugins.has_consume_interceptors() {
+            return Ok(message.message.clone());
+        }
+
+        let mut content = PluginMessage {
+            exchange: message.message.exchange.clone(),
+            routing_key: message.message.routing_key.clone(),
+            properties: message.message.content_header()?.properties,
+            body: Bytes::from(message.message.body.clone()),
+        };
+        self.plugins
+            .intercept_consume(queue_name, &mut content)
+            .map_err(|e| AmqpBrokerError::PreconditionFailed(e.to_string()))?;
+        AmqpMessage::build(
+            content.exchange,
+            content.routing_key,
+            &content.properties,
+            content.body,
+        )
+    }
+
+    async fn dispatch(self: Arc<Self>, state: Arc<QueueState>) {
+        loop {
+            if state.is_stopped() {
+                break;
+            }
+            if !state.has_ready_consumer() {
+                let _ = timeout(Duration::from_secs(1), state.notify.notified()).await;
+                continue;
+            }
+
+            match self.fill(&state).await {
+                Ok(0) => {
+                    let _ = timeout(Duration::from_secs(1), state.notify.notified()).await;
+                    continue;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    error!("Failed to read queue {}: {}", state.queue_name, e);
+                    sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            }
+
+            while let Some((consumer, message)) = state.next_delivery() {
+                self.deliver(&state, consumer, message).await;
+            }
+            self.commit(&state).await;
+        }
+    }
+
+    async fn deliver(&self, state: &Arc<QueueState>, consumer: Consumer, message: QueuedMessage) {
+        let delivery = &consumer.delivery;
+        let delivery_tag = delivery.next_delivery_tag();
+        let method = Method::BasicDeliver(BasicDeliver {
+            consumer_tag: consumer.consumer_tag.clone(),
+            delivery_tag,
+            redelivered: message.redelivered,
+            exchange: message.message.exchange.clone(),
+            routing_key: message.message.routing_key.clone(),
+        });
+        let frames = match self
+            .intercept_consume(&state.queue_name, &message)
+            .and_then(|content| delivery.content_frames(method, &content))
+        {
+            Ok(frames) => frames,
+            Err(e) => {
+                error!(
+                    "Dropping message at offset {} of queue {}: {}",
+                    message.offset, state.queue_name, e
+                );
+                state.settle(&[message.offset]);
+                return;
+            }
+        };
+
+        if !consumer.no_ack {
+            delivery.track(delivery_tag, &state.queue_name, message.clone());
+        }
+        if delivery.send(frames).await.is_err() {
+            // the connection is gone, its cleanup may already have requeued the message
+            if consumer.no_ack || delivery.untrack(delivery_tag).is_some() {
+                state.requeue(vec![message]);
+            }
+            let mut inner = state.inner.lock().unwrap();
+            inner
+                .consumers
+                .retain(|c| !c.is_same(delivery, &consumer.consumer_tag));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use amqp_plugins::manager::AmqpPluginManager;
+    use bytes::Bytes;
+    use common_base::config::broker_amqp::{init_broker_amqp_conf_by_config, BrokerAmqpConfig};
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::amqp::queue::AmqpQueue;
+    use protocol::amqp::common::{AmqpFrame, BasicProperties, Method};
+    use storage_adapter::memory::MemoryStorageAdapter;
+    use tokio::sync::mpsc;
+    use tokio::time::timeout;
+
+    use super::{Consumer, QueueManager};
+    use crate::handler::cache::AmqpCacheManager;
+    use crate::handler::delivery::ChannelDelivery;
+    use crate::storage::message::{AmqpMessage, MessageStorage};
+    use crate::storage::queue::QueueStorage;
+
+    #[tokio::test]
+    async fn deliver_ack_requeue_test() {
+        init_broker_amqp_conf_by_config(BrokerAmqpConfig {
+            cluster_name: "test-amqp".to_string(),
+            ..Default::default()
+        });
+        let message_storage = MessageStorage::new(Arc::new(MemoryStorageAdapter::new()));
+        let manager = Arc::new(QueueManager::new(
+            Arc::new(AmqpCacheManager::new()),
+            message_storage.clone(),
+            QueueStorage::new(Arc::new(ClientPool::new(1))),
+            Arc::new(AmqpPluginManager::new()),
+        ));
+        let queue_name = "orders";
+        manager
+            .declare_queue(
+                AmqpQueue {
+                    queue_name: queue_name.to_string(),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        for body in ["m0", "m1", "m2"] {
+            let message = AmqpMessage::build(
+                "".to_string(),
+                queue_name.to_string(),
+                &BasicProperties::default(),
+                Bytes::from(body),
+            )
+            .unwrap();
+            manager
+                .publish(queue_name, message.to_record().unwrap())
+                .await
+                .unwrap();
+        }
+
+        let (sender, mut receiver) = mpsc::channel(10);
+        let delivery = Arc::new(ChannelDelivery::new(1, 1, sender, 4096, 2));
+        manager
+            .add_consumer(
+                queue_name,
+                Consumer {
+                    consumer_tag: "c1".to_string(),
+                    no_ack: false,
+                    exclusive: false,
+                    delivery: delivery.clone(),
+                },
+            )
+            .unwrap();
+
+        let mut tags = Vec::new();
+        for _ in 0..2 {
+            let frames = timeout(Duration::from_secs(3), receiver.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            if let AmqpFrame::Method(_, Method::BasicDeliver(deliver)) = &frames[0] {
+                assert!(!deliver.redelivered);
+                tags.push(deliver.delivery_tag);
+            } else {
+                panic!("expected basic.deliver, got {:?}", frames[0]);
+            }
+        }
+        // prefetch 2 holds the third message back
+        assert!(timeout(Duration::from_millis(300), receiver.recv())
+            .await
+            .is_err());
+
+        let settled = delivery.settle(tags[0], false).unwrap();
+        manager.ack(queue_name, &[settled[0].message.offset]).await;
+        assert_eq!(
+            message_storage.get_queue_offset(queue_name).await.unwrap(),
+            1
+        );
+
+        let frames = timeout(Duration::from_secs(3), receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(frames[2], AmqpFrame::Body(1, Bytes::from("m2")));
+
+        // unacked messages come back flagged as redelivered
+        for unacked in delivery.take_all() {
+            manager.requeue(&unacked.queue_name, vec![unacked.message]);
+        }
+        let frames = timeout(Duration::from_secs(3), receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        if let AmqpFrame::Method(_, Method::BasicDeliver(deliver)) = &frames[0] {
+            assert!(deliver.redelivered);
+        } else {
+            panic!("expected basic.deliver, got {:?}", frames[0]);
+        }
+
+        manager.delete_queue(queue_name).await.unwrap();
+        assert!(manager.get_queue_state(queue_name).is_none());
+    }
+}
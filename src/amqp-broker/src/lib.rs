@@ -11,3 +11,264 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use amqp_plugins::manager::AmqpPluginManager;
+use common_base::config::broker_amqp::broker_amqp_conf;
+use common_base::runtime::create_runtime;
+use grpc_clients::pool::ClientPool;
+use handler::cache::AmqpCacheManager;
+use handler::error::AmqpBrokerError;
+use handler::queue::QueueManager;
+use log::{error, info};
+use server::tcp::start_tcp_server;
+use storage::cluster::ClusterStorage;
+use storage::exchange::ExchangeStorage;
+use storage::message::MessageStorage;
+use storage::queue::QueueStorage;
+use storage_adapter::journal::JournalStorageAdapter;
+use storage_adapter::memory::MemoryStorageAdapter;
+use storage_adapter::mysql::MySQLStorageAdapter;
+use storage_adapter::placement::PlacementStorageAdapter;
+use storage_adapter::rocksdb::RocksDBStorageAdapter;
+use storage_adapter::storage::StorageAdapter;
+use storage_adapter::{parse_addrs, validate_storage_config, StorageType};
+use third_driver::mysql::build_mysql_conn_pool;
+use tokio::runtime::Runtime;
+use tokio::signal;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+
+pub mod handler;
+mod server;
+pub mod storage;
+
+pub fn start_amqp_broker_server(stop_send: broadcast::Sender<bool>) {
+    let conf = broker_amqp_conf();
+    let client_pool: Arc<ClientPool> = Arc::new(ClientPool::new(5));
+    let storage_type = match validate_storage_config(&conf.storage) {
+        Ok(storage_type) => storage_type,
+        Err(e) => {
+            panic!("Message data storage configuration error, {}", e);
+        }
+    };
+    match storage_type {
+        StorageType::Memory => {
+            let message_storage_adapter = Arc::new(MemoryStorageAdapter::new());
+            let server = AmqpBroker::new(client_pool, message_storage_adapter);
+            server.start(stop_send);
+        }
+        StorageType::Journal => {
+            let message_storage_adapter = Arc::new(JournalStorageAdapter::new(
+                client_pool.clone(),
+                conf.cluster_name.clone(),
+                parse_addrs(&conf.storage.journal_addr),
+                conf.placement_center.clone(),
+            ));
+            let server = AmqpBroker::new(client_pool, message_storage_adapter);
+            server.start(stop_send);
+        }
+        StorageType::Mysql => {
+            let message_storage_adapter = match build_mysql_conn_pool(&conf.storage.mysql_addr)
+                .and_then(MySQLStorageAdapter::new)
+            {
+                Ok(adapter) => Arc::new(adapter),
+                Err(e) => {
+                    panic!("Failed to initialize MySQL message storage, {}", e);
+                }
+            };
+            let server = AmqpBroker::new(client_pool, message_storage_adapter);
+            server.start(stop_send);
+        }
+        StorageType::RocksDB => {
+            let message_storage_adapter = Arc::new(RocksDBStorageAdapter::new(
+                conf.storage.rocksdb_data_path.as_str(),
+                conf.storage.rocksdb_max_open_files.unwrap_or(10000),
+            ));
+            let server = AmqpBroker::new(client_pool, message_storage_adapter);
+            server.start(stop_send);
+        }
+        StorageType::Placement => {
+            let message_storage_adapter = Arc::new(PlacementStorageAdapter::new(
+                client_pool.clone(),
+                conf.cluster_name.clone(),
+                conf.placement_center.clone(),
+                conf.storage.placement.clone(),
+            ));
+            let server = AmqpBroker::new(client_pool, message_storage_adapter);
+            server.start(stop_send);
+        }
+        StorageType::Redis => {
+            unreachable!("rejected by validate_storage_config")
+        }
+    }
+}
+
+pub struct AmqpBroker<S> {
+    runtime: Runtime,
+    client_pool: Arc<ClientPool>,
+    cache_manager: Arc<AmqpCacheManager>,
+    queue_manager: Arc<QueueManager<S>>,
+    exchange_storage: Arc<ExchangeStorage>,
+    plugins: Arc<AmqpPluginManager>,
+}
+
+impl<S> AmqpBroker<S>
+where
+    S: StorageAdapter + Sync + Send + 'static,
+{
+    pub fn new(client_pool: Arc<ClientPool>, message_storage_adapter: Arc<S>) -> Self {
+        let conf = broker_amqp_conf();
+        let runtime = create_runtime(
+            "amqp-broker-server-runtime",
+            conf.system.runtime_worker_threads,
+        );
+        let plugins = match AmqpPluginManager::build(
+            &conf.plugins,
+            client_pool.clone(),
+            conf.placement_center.clone(),
+        ) {
+            Ok(plugins) => Arc::new(plugins),
+            Err(e) => {
+                panic!("Failed to load AMQP plugins, {}", e);
+            }
+        };
+        let cache_manager = Arc::new(AmqpCacheManager::new());
+        let queue_manager = Arc::new(QueueManager::new(
+            cache_manager.clone(),
+            MessageStorage::new(message_storage_adapter),
+            QueueStorage::new(client_pool.clone()),
+            plugins.clone(),
+        ));
+        let exchange_storage = Arc::new(ExchangeStorage::new(client_pool.clone()));
+        AmqpBroker {
+            runtime,
+            client_pool,
+            cache_manager,
+            queue_manager,
+            exchange_storage,
+            plugins,
+        }
+    }
+
+    pub fn start(&self, stop_send: broadcast::Sender<bool>) {
+        self.register_node();
+        self.load_metadata();
+        self.start_tcp_server(stop_send.clone());
+        self.start_cluster_heartbeat_report(stop_send.clone());
+        self.awaiting_stop(stop_send);
+    }
+
+    fn register_node(&self) {
+        let cluster_storage = ClusterStorage::new(self.client_pool.clone());
+        self.runtime.block_on(async move {
+            if let Err(e) = cluster_storage.register_node(broker_amqp_conf()).await {
+                panic!("{}", e);
+            }
+        });
+    }
+
+    // Durable exchanges and queues survive restarts through the placement center
+    fn load_metadata(&self) {
+        self.runtime.block_on(async move {
+            if let Err(e) = self.try_load_metadata().await {
+                panic!(
+                    "Failed to load AMQP metadata from the placement center, {}",
+                    e
+                );
+            }
+        });
+    }
+
+    async fn try_load_metadata(&self) -> Result<(), AmqpBrokerError> {
+        for exchange in self.exchange_storage.list_exchange().await? {
+            self.cache_manager.add_exchange(exchange);
+        }
+
+        let queue_storage = QueueStorage::new(self.client_pool.clone());
+        for queue in queue_storage.list_queue().await? {
+            let queue_name = queue.queue_name.clone();
+            let exclusive = queue.exclusive;
+            self.cache_manager.add_queue(queue);
+            // exclusive queues belonged to a connection of the previous run
+            if exclusive {
+                self.queue_manager.delete_queue(&queue_name).await?;
+                continue;
+            }
+            self.queue_manager.start_queue(&queue_name, None).await?;
+        }
+        Ok(())
+    }
+
+    fn start_tcp_server(&self, stop_send: broadcast::Sender<bool>) {
+        let cache_manager = self.cache_manager.clone();
+        let queue_manager = self.queue_manager.clone();
+        let exchange_storage = self.exchange_storage.clone();
+        let plugins = self.plugins.clone();
+        self.runtime.spawn(async move {
+            start_tcp_server(
+                cache_manager,
+                queue_manager,
+                exchange_storage,
+                plugins,
+                stop_send,
+            )
+            .await
+        });
+    }
+
+    fn start_cluster_heartbeat_report(&self, stop_send: broadcast::Sender<bool>) {
+        let cluster_storage = ClusterStorage::new(self.client_pool.clone());
+        self.runtime.spawn(async move {
+            let mut stop_rx = stop_send.subscribe();
+            loop {
+                tokio::select! {
+                    val = stop_rx.recv() => {
+                        if let Ok(true) = val {
+                            break;
+                        }
+                    }
+                    _ = sleep(Duration::from_secs(3)) => {
+                        if let Err(e) = cluster_storage.heartbeat().await {
+                            error!("{}", e);
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    fn awaiting_stop(&self, stop_send: broadcast::Sender<bool>) {
+        self.runtime.spawn(async move {
+            sleep(Duration::from_millis(5)).await;
+            info!("AMQP Broker service started successfully...");
+        });
+
+        // Wait for the stop signal
+        self.runtime.block_on(async move {
+            signal::ctrl_c().await.expect("failed to listen for event");
+            match stop_send.send(true) {
+                Ok(_) => {
+                    info!(
+                        "{}",
+                        "When ctrl + c is received, the service starts to stop"
+                    );
+                    self.stop_server().await;
+                }
+                Err(_) => {
+                    error!("Failed to send stop signal");
+                }
+            }
+        });
+    }
+
+    async fn stop_server(&self) {
+        self.queue_manager.stop_all();
+        let cluster_storage = ClusterStorage::new(self.client_pool.clone());
+        if let Err(e) = cluster_storage.unregister_node(broker_amqp_conf()).await {
+            error!("{}", e);
+        }
+    }
+}
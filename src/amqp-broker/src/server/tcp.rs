@@ -0,0 +1,211 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use amqp_plugins::manager::AmqpPluginManager;
+use common_base::config::broker_amqp::broker_amqp_conf;
+use futures::{SinkExt, StreamExt};
+use log::{debug, error, info};
+use protocol::amqp::codec::AmqpCodec;
+use protocol::amqp::common::AmqpFrame;
+use storage_adapter::storage::StorageAdapter;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::{interval, timeout};
+use tokio_util::codec::{FramedRead, FramedWrite};
+
+use crate::handler::cache::AmqpCacheManager;
+use crate::handler::connection::{handshake, AmqpConnection};
+use crate::handler::error::AmqpBrokerError;
+use crate::handler::queue::QueueManager;
+use crate::storage::exchange::ExchangeStorage;
+
+static CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
+
+pub async fn start_tcp_server<S>(
+    cache_manager: Arc<AmqpCacheManager>,
+    queue_manager: Arc<QueueManager<S>>,
+    exchange_storage: Arc<ExchangeStorage>,
+    plugins: Arc<AmqpPluginManager>,
+    stop_send: broadcast::Sender<bool>,
+) where
+    S: StorageAdapter + Send + Sync + 'static,
+{
+    let conf = broker_amqp_conf();
+    let addr = format!("0.0.0.0:{}", conf.network.tcp_port);
+    let listener = match TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            panic!("AMQP broker failed to bind {}, {}", addr, e);
+        }
+    };
+    info!("AMQP Broker TCP Server bind addr:{}", addr);
+
+    let mut stop_rx = stop_send.subscribe();
+    loop {
+        tokio::select! {
+            val = stop_rx.recv() => {
+                if let Ok(true) = val {
+                    info!("AMQP Broker TCP Server acceptor stopped");
+                    break;
+                }
+            }
+            val = listener.accept() => {
+                match val {
+                    Ok((stream, addr)) => {
+                        let cache_manager = cache_manager.clone();
+                        let queue_manager = queue_manager.clone();
+                        let exchange_storage = exchange_storage.clone();
+                        let plugins = plugins.clone();
+                        tokio::spawn(async move {
+                            handle_connection(
+                                stream,
+                                addr,
+                                cache_manager,
+                                queue_manager,
+                                exchange_storage,
+                                plugins,
+                            )
+                            .await;
+                        });
+                    }
+                    Err(e) => {
+                        error!("AMQP Broker accept failed, {}", e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn handle_connection<S>(
+    stream: TcpStream,
+    addr: SocketAddr,
+    cache_manager: Arc<AmqpCacheManager>,
+    queue_manager: Arc<QueueManager<S>>,
+    exchange_storage: Arc<ExchangeStorage>,
+    plugins: Arc<AmqpPluginManager>,
+) where
+    S: StorageAdapter + Send + Sync + 'static,
+{
+    let connection_id = CONNECTION_ID.fetch_add(1, Ordering::Relaxed);
+    let (r_stream, w_stream) = stream.into_split();
+    let mut reader = FramedRead::new(r_stream, AmqpCodec::new());
+    // outgoing frames are built by the broker within the negotiated frame-max
+    let mut write_codec = AmqpCodec::new();
+    write_codec.set_frame_max(0);
+    let mut writer = FramedWrite::new(w_stream, write_codec);
+
+    let (sender, mut receiver) = mpsc::channel::<Vec<AmqpFrame>>(1000);
+    let writer_task = tokio::spawn(async move {
+        while let Some(frames) = receiver.recv().await {
+            for frame in frames {
+                if let Err(e) = writer.feed(frame).await {
+                    debug!("AMQP connection {} write failed, {}", connection_id, e);
+                    return;
+                }
+            }
+            if let Err(e) = writer.flush().await {
+                debug!("AMQP connection {} write failed, {}", connection_id, e);
+                return;
+            }
+        }
+    });
+
+    let tune = match handshake(&mut reader, &sender, &plugins).await {
+        Ok(tune) => tune,
+        Err(e) => {
+            info!("AMQP handshake with {} failed: {}", addr, e);
+            drop(sender);
+            let _ = writer_task.await;
+            return;
+        }
+    };
+    reader.decoder_mut().set_frame_max(tune.frame_max);
+    info!(
+        "AMQP connection {} established from {}",
+        connection_id, addr
+    );
+
+    // the broker sends a heartbeat every half interval and gives up after two silent ones
+    let heartbeat = Duration::from_secs(tune.heartbeat as u64);
+    let heartbeat_task = if tune.heartbeat > 0 {
+        let heartbeat_sender = sender.clone();
+        Some(tokio::spawn(async move {
+            let mut ticker = interval(heartbeat / 2);
+            loop {
+                ticker.tick().await;
+                if heartbeat_sender
+                    .send(vec![AmqpFrame::Heartbeat])
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        }))
+    } else {
+        None
+    };
+
+    let mut connection = AmqpConnection::new(
+        connection_id,
+        addr,
+        tune,
+        sender,
+        cache_manager,
+        queue_manager,
+        exchange_storage,
+        plugins,
+    );
+    loop {
+        let next = if tune.heartbeat > 0 {
+            match timeout(heartbeat * 2, reader.next()).await {
+                Ok(next) => next,
+                Err(_) => {
+                    info!("AMQP connection {} missed heartbeats", connection_id);
+                    break;
+                }
+            }
+        } else {
+            reader.next().await
+        };
+
+        match next {
+            Some(Ok(frame)) => {
+                if !connection.handle_frame(frame).await {
+                    break;
+                }
+            }
+            Some(Err(e)) => {
+                let error = AmqpBrokerError::from(e);
+                connection.close_connection(&error, (0, 0)).await;
+                break;
+            }
+            None => break,
+        }
+    }
+
+    if let Some(task) = heartbeat_task {
+        task.abort();
+    }
+    connection.cleanup().await;
+    drop(connection);
+    let _ = writer_task.await;
+    info!("AMQP connection {} from {} closed", connection_id, addr);
+}
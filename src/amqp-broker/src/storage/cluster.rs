@@ -0,0 +1,77 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::config::broker_amqp::{broker_amqp_conf, BrokerAmqpConfig};
+use common_base::error::common::CommonError;
+use common_base::tools::get_local_ip;
+use grpc_clients::placement::inner::call::{heartbeat, register_node, unregister_node};
+use grpc_clients::pool::ClientPool;
+use metadata_struct::amqp::node_extend::AmqpNodeExtend;
+use protocol::placement_center::placement_center_inner::{
+    ClusterType, HeartbeatRequest, RegisterNodeRequest, UnRegisterNodeRequest,
+};
+
+pub struct ClusterStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl ClusterStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        ClusterStorage { client_pool }
+    }
+
+    pub async fn register_node(&self, config: &BrokerAmqpConfig) -> Result<(), CommonError> {
+        let local_ip = get_local_ip();
+        let amqp_addr = format!("{}:{}", local_ip, config.network.tcp_port);
+        let node = AmqpNodeExtend {
+            amqp_addr: amqp_addr.clone(),
+        };
+        let req = RegisterNodeRequest {
+            cluster_type: ClusterType::AmqpBrokerServer.into(),
+            cluster_name: config.cluster_name.clone(),
+            node_ip: local_ip.clone(),
+            node_id: config.broker_id,
+            node_inner_addr: amqp_addr,
+            extend_info: serde_json::to_string(&node)?,
+        };
+
+        register_node(&self.client_pool, &config.placement_center, req).await?;
+        Ok(())
+    }
+
+    pub async fn unregister_node(&self, config: &BrokerAmqpConfig) -> Result<(), CommonError> {
+        let req = UnRegisterNodeRequest {
+            cluster_type: ClusterType::AmqpBrokerServer.into(),
+            cluster_name: config.cluster_name.clone(),
+            node_id: config.broker_id,
+        };
+
+        unregister_node(&self.client_pool, &config.placement_center, req).await?;
+        Ok(())
+    }
+
+    pub async fn heartbeat(&self) -> Result<(), CommonError> {
+        let config = broker_amqp_conf();
+        let req = HeartbeatRequest {
+            cluster_name: config.cluster_name.clone(),
+            cluster_type: ClusterType::AmqpBrokerServer.into(),
+            node_id: config.broker_id,
+        };
+
+        heartbeat(&self.client_pool, &config.placement_center, req).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::config::broker_amqp::broker_amqp_conf;
+use grpc_clients::placement::amqp::call::{
+    placement_create_exchange, placement_delete_exchange, placement_list_exchange,
+};
+use grpc_clients::pool::ClientPool;
+use metadata_struct::amqp::exchange::AmqpExchange;
+use protocol::placement_center::placement_center_amqp::{
+    CreateExchangeRequest, DeleteExchangeRequest, ListExchangeRequest,
+};
+
+use crate::handler::error::AmqpBrokerError;
+
+pub struct ExchangeStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl ExchangeStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        ExchangeStorage { client_pool }
+    }
+
+    pub async fn list_exchange(&self) -> Result<Vec<AmqpExchange>, AmqpBrokerError> {
+        let config = broker_amqp_conf();
+        let request = ListExchangeRequest {
+            cluster_name: config.cluster_name.clone(),
+        };
+        let reply =
+            placement_list_exchange(&self.client_pool, &config.placement_center, request).await?;
+        let mut list = Vec::new();
+        for raw in reply.exchanges {
+            list.push(AmqpExchange::decode(&raw)?);
+        }
+        Ok(list)
+    }
+
+    pub async fn save_exchange(&self, exchange: &AmqpExchange) -> Result<(), AmqpBrokerError> {
+        let config = broker_amqp_conf();
+        let request = CreateExchangeRequest {
+            cluster_name: config.cluster_name.clone(),
+            exchange_name: exchange.exchange_name.clone(),
+            content: exchange.encode()?,
+        };
+        placement_create_exchange(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    pub async fn delete_exchange(&self, exchange_name: &str) -> Result<(), AmqpBrokerError> {
+        let config = broker_amqp_conf();
+        let request = DeleteExchangeRequest {
+            cluster_name: config.cluster_name.clone(),
+            exchange_name: exchange_name.to_string(),
+        };
+        placement_delete_exchange(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,213 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bytes::{Bytes, BytesMut};
+use common_base::config::broker_amqp::broker_amqp_conf;
+use common_base::error::common::CommonError;
+use metadata_struct::adapter::read_config::ReadConfig;
+use metadata_struct::adapter::record::Record;
+use protocol::amqp::codec::AmqpCodec;
+use protocol::amqp::common::{AmqpFrame, BasicProperties, ContentHeader, CLASS_BASIC};
+use serde::{Deserialize, Serialize};
+use storage_adapter::storage::{ShardConfig, StorageAdapter};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::handler::error::AmqpBrokerError;
+
+pub fn cluster_name() -> String {
+    let conf = broker_amqp_conf();
+    conf.cluster_name.clone()
+}
+
+// Every queue is kept as one shard, the group offset is the next offset to deliver
+pub fn queue_shard_name(queue_name: &str) -> String {
+    format!("amqp_queue_{}", queue_name)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AmqpMessage {
+    pub exchange: String,
+    pub routing_key: String,
+    // Content header frame as sent on the wire, BasicProperties has no serde support
+    pub header: Vec<u8>,
+    pub body: Vec<u8>,
+}
+
+impl AmqpMessage {
+    pub fn build(
+        exchange: String,
+        routing_key: String,
+        properties: &BasicProperties,
+        body: Bytes,
+    ) -> Result<Self, AmqpBrokerError> {
+        let header = ContentHeader {
+            class_id: CLASS_BASIC,
+            body_size: body.len() as u64,
+            properties: properties.clone(),
+        };
+        let mut buf = BytesMut::new();
+        let mut codec = AmqpCodec::new();
+        codec.set_frame_max(0);
+        codec.encode(AmqpFrame::Header(0, header), &mut buf)?;
+        Ok(AmqpMessage {
+            exchange,
+            routing_key,
+            header: buf.to_vec(),
+            body: body.to_vec(),
+        })
+    }
+
+    pub fn content_header(&self) -> Result<ContentHeader, AmqpBrokerError> {
+        let mut buf = BytesMut::from(self.header.as_slice());
+        let mut codec = AmqpCodec::new();
+        codec.set_frame_max(0);
+        match codec.decode(&mut buf)? {
+            Some(AmqpFrame::Header(_, header)) => Ok(header),
+            _ => Err(AmqpBrokerError::FromCommonError(CommonError::CommonError(
+                "Stored AMQP message has no valid content header".to_string(),
+            ))),
+        }
+    }
+
+    pub fn to_record(&self) -> Result<Record, AmqpBrokerError> {
+        let mut record = Record::build_byte(serde_json::to_vec(&self)?);
+        record.set_key(self.routing_key.clone());
+        Ok(record)
+    }
+
+    pub fn from_record(record: &Record) -> Result<Self, AmqpBrokerError> {
+        Ok(serde_json::from_slice(&record.data)?)
+    }
+}
+
+#[derive(Clone)]
+pub struct MessageStorage<T> {
+    storage_adapter: Arc<T>,
+}
+
+impl<T> MessageStorage<T>
+where
+    T: StorageAdapter + Send + Sync + 'static,
+{
+    pub fn new(storage_adapter: Arc<T>) -> Self {
+        MessageStorage { storage_adapter }
+    }
+
+    pub async fn create_queue_shard(&self, queue_name: &str) -> Result<(), CommonError> {
+        self.storage_adapter
+            .create_shard(
+                cluster_name(),
+                queue_shard_name(queue_name),
+                ShardConfig::default(),
+            )
+            .await
+    }
+
+    pub async fn delete_queue_shard(&self, queue_name: &str) -> Result<(), CommonError> {
+        self.storage_adapter
+            .delete_shard(cluster_name(), queue_shard_name(queue_name))
+            .await
+    }
+
+    pub async fn append_queue_message(
+        &self,
+        queue_name: &str,
+        record: Record,
+    ) -> Result<u64, CommonError> {
+        self.storage_adapter
+            .write(cluster_name(), queue_shard_name(queue_name), record)
+            .await
+    }
+
+    pub async fn read_queue_message(
+        &self,
+        queue_name: &str,
+        offset: u64,
+        record_num: u64,
+    ) -> Result<Vec<Record>, CommonError> {
+        let mut read_config = ReadConfig::new();
+        read_config.max_record_num = record_num;
+        self.storage_adapter
+            .read_by_offset(
+                cluster_name(),
+                queue_shard_name(queue_name),
+                offset,
+                read_config,
+            )
+            .await
+    }
+
+    pub async fn get_queue_offset(&self, queue_name: &str) -> Result<u64, CommonError> {
+        let offset_data = self
+            .storage_adapter
+            .get_offset_by_group(queue_shard_name(queue_name))
+            .await?;
+
+        if let Some(offset) = offset_data.first() {
+            return Ok(offset.offset);
+        }
+        Ok(0)
+    }
+
+    pub async fn commit_queue_offset(
+        &self,
+        queue_name: &str,
+        offset: u64,
+    ) -> Result<(), CommonError> {
+        let shard_name = queue_shard_name(queue_name);
+        let mut offset_data = HashMap::new();
+        offset_data.insert(shard_name.clone(), offset);
+        self.storage_adapter
+            .commit_offset(shard_name, cluster_name(), offset_data)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use protocol::amqp::common::BasicProperties;
+
+    use super::AmqpMessage;
+
+    #[test]
+    fn message_record_round_trip_test() {
+        let properties = BasicProperties {
+            content_type: Some("application/json".to_string()),
+            delivery_mode: Some(2),
+            message_id: Some("m-1".to_string()),
+            ..Default::default()
+        };
+        let message = AmqpMessage::build(
+            "amq.topic".to_string(),
+            "order.created".to_string(),
+            &properties,
+            Bytes::from_static(b"{\"id\":1}"),
+        )
+        .unwrap();
+
+        let record = message.to_record().unwrap();
+        assert_eq!(record.key, "order.created");
+
+        let decoded = AmqpMessage::from_record(&record).unwrap();
+        assert_eq!(decoded, message);
+
+        let header = decoded.content_header().unwrap();
+        assert_eq!(header.body_size, 8);
+        assert_eq!(header.properties, properties);
+    }
+}
@@ -0,0 +1,72 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::config::broker_amqp::broker_amqp_conf;
+use grpc_clients::placement::amqp::call::{
+    placement_create_queue, placement_delete_queue, placement_list_queue,
+};
+use grpc_clients::pool::ClientPool;
+use metadata_struct::amqp::queue::AmqpQueue;
+use protocol::placement_center::placement_center_amqp::{
+    CreateQueueRequest, DeleteQueueRequest, ListQueueRequest,
+};
+
+use crate::handler::error::AmqpBrokerError;
+
+pub struct QueueStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl QueueStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        QueueStorage { client_pool }
+    }
+
+    pub async fn list_queue(&self) -> Result<Vec<AmqpQueue>, AmqpBrokerError> {
+        let config = broker_amqp_conf();
+        let request = ListQueueRequest {
+            cluster_name: config.cluster_name.clone(),
+        };
+        let reply =
+            placement_list_queue(&self.client_pool, &config.placement_center, request).await?;
+        let mut list = Vec::new();
+        for raw in reply.queues {
+            list.push(AmqpQueue::decode(&raw)?);
+        }
+        Ok(list)
+    }
+
+    pub async fn save_queue(&self, queue: &AmqpQueue) -> Result<(), AmqpBrokerError> {
+        let config = broker_amqp_conf();
+        let request = CreateQueueRequest {
+            cluster_name: config.cluster_name.clone(),
+            queue_name: queue.queue_name.clone(),
+            content: queue.encode()?,
+        };
+        placement_create_queue(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    pub async fn delete_queue(&self, queue_name: &str) -> Result<(), AmqpBrokerError> {
+        let config = broker_amqp_conf();
+        let request = DeleteQueueRequest {
+            cluster_name: config.cluster_name.clone(),
+            queue_name: queue_name.to_string(),
+        };
+        placement_delete_queue(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::async_trait;
+use common_base::config::broker_amqp::PluginAuth;
+use grpc_clients::pool::ClientPool;
+use mqtt_broker::handler::error::MqttBrokerError;
+use mqtt_broker::security::password::verify_password_blocking;
+use mqtt_broker::security::{build_driver, AuthStorageAdapter};
+use placement::PlacementUserStorage;
+use storage_adapter::StorageType;
+
+use crate::error::AmqpPluginError;
+
+pub mod placement;
+
+#[async_trait]
+pub trait AmqpAuthenticator: Send + Sync {
+    // Checks a PLAIN login, Ok(false) when the credentials are wrong
+    async fn authenticate(&self, username: &str, password: &str) -> Result<bool, AmqpPluginError>;
+}
+
+// Logs AMQP clients in with the users stored for the MQTT broker
+pub struct MqttUserAuthenticator {
+    driver: Arc<dyn AuthStorageAdapter + Send + 'static + Sync>,
+}
+
+impl MqttUserAuthenticator {
+    pub fn new(driver: Arc<dyn AuthStorageAdapter + Send + 'static + Sync>) -> Self {
+        MqttUserAuthenticator { driver }
+    }
+
+    pub fn build(
+        client_pool: Arc<ClientPool>,
+        placement_center: Vec<String>,
+        auth: &PluginAuth,
+    ) -> Result<Self, AmqpPluginError> {
+        let storage_type = StorageType::from_str(&auth.auth.storage_type)
+            .map_err(|_| MqttBrokerError::UnavailableStorageType)?;
+
+        // The MQTT placement adapter reads the MQTT broker config, which an AMQP
+        // broker does not load, so users are read with the configured cluster name.
+        let driver: Arc<dyn AuthStorageAdapter + Send + 'static + Sync> =
+            if matches!(storage_type, StorageType::Placement) {
+                Arc::new(PlacementUserStorage::new(
+                    client_pool,
+                    placement_center,
+                    auth.mqtt_cluster_name.clone(),
+                ))
+            } else {
+                build_driver(client_pool, auth.auth.clone())?
+            };
+        Ok(MqttUserAuthenticator::new(driver))
+    }
+}
+
+#[async_trait]
+impl AmqpAuthenticator for MqttUserAuthenticator {
+    async fn authenticate(&self, username: &str, password: &str) -> Result<bool, AmqpPluginError> {
+        match self.driver.get_user(username.to_string()).await? {
+            Some(user) => Ok(verify_password_blocking(&user.password, password).await?),
+            None => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::async_trait;
+    use dashmap::DashMap;
+    use metadata_struct::acl::mqtt_acl::MqttAcl;
+    use metadata_struct::acl::mqtt_blacklist::MqttAclBlackList;
+    use metadata_struct::mqtt::user::MqttUser;
+    use mqtt_broker::handler::error::MqttBrokerError;
+    use mqtt_broker::security::AuthStorageAdapter;
+
+    use super::{AmqpAuthenticator, MqttUserAuthenticator};
+
+    struct StaticUsers {
+        users: DashMap<String, MqttUser>,
+    }
+
+    #[async_trait]
+    impl AuthStorageAdapter for StaticUsers {
+        async fn read_all_user(&self) -> Result<DashMap<String, MqttUser>, MqttBrokerError> {
+            Ok(self.users.clone())
+        }
+
+        async fn read_all_acl(&self) -> Result<Vec<MqttAcl>, MqttBrokerError> {
+            Ok(Vec::new())
+        }
+
+        async fn read_all_blacklist(&self) -> Result<Vec<MqttAclBlackList>, MqttBrokerError> {
+            Ok(Vec::new())
+        }
+
+        async fn get_user(&self, username: String) -> Result<Option<MqttUser>, MqttBrokerError> {
+            Ok(self.users.get(&username).map(|user| user.clone()))
+        }
+
+        async fn save_user(&self, user_info: MqttUser) -> Result<(), MqttBrokerError> {
+            self.users.insert(user_info.username.clone(), user_info);
+            Ok(())
+        }
+
+        async fn delete_user(&self, username: String) -> Result<(), MqttBrokerError> {
+            self.users.remove(&username);
+            Ok(())
+        }
+
+        async fn save_acl(&self, _acl: MqttAcl) -> Result<(), MqttBrokerError> {
+            Ok(())
+        }
+
+        async fn delete_acl(&self, _acl: MqttAcl) -> Result<(), MqttBrokerError> {
+            Ok(())
+        }
+
+        async fn save_blacklist(
+            &self,
+            _blacklist: MqttAclBlackList,
+        ) -> Result<(), MqttBrokerError> {
+            Ok(())
+        }
+
+        async fn delete_blacklist(
+            &self,
+            _blacklist: MqttAclBlackList,
+        ) -> Result<(), MqttBrokerError> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn mqtt_user_authenticate_test() {
+        let users = DashMap::new();
+        users.insert(
+            "robustmq".to_string(),
+            MqttUser {
+                username: "robustmq".to_string(),
+                password: "robustmq@2024".to_string(),
+                is_superuser: false,
+            },
+        );
+        let authenticator = MqttUserAuthenticator::new(Arc::new(StaticUsers { users }));
+
+        assert!(authenticator
+            .authenticate("robustmq", "robustmq@2024")
+            .await
+            .unwrap());
+        assert!(!authenticator
+            .authenticate("robustmq", "wrong")
+            .await
+            .unwrap());
+        assert!(!authenticator
+            .authenticate("unknown", "robustmq@2024")
+            .await
+            .unwrap());
+    }
+}
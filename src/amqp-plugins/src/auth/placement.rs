@@ -0,0 +1,194 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use axum::async_trait;
+use common_base::error::common::CommonError;
+use common_base::tools::now_second;
+use dashmap::DashMap;
+use grpc_clients::placement::mqtt::call::placement_list_user;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::acl::mqtt_acl::MqttAcl;
+use metadata_struct::acl::mqtt_blacklist::MqttAclBlackList;
+use metadata_struct::mqtt::user::MqttUser;
+use mqtt_broker::handler::error::MqttBrokerError;
+use mqtt_broker::security::AuthStorageAdapter;
+use protocol::placement_center::placement_center_mqtt::ListUserRequest;
+
+// Same interval the MQTT broker refreshes its user cache with
+const USER_CACHE_TTL_SEC: u64 = 5;
+
+// Read only view of the users an MQTT cluster keeps in the placement center.
+// Looked up users are cached for a few seconds, unknown users included, so that
+// logins do not call the placement center every time.
+pub struct PlacementUserStorage {
+    client_pool: Arc<ClientPool>,
+    placement_center: Vec<String>,
+    cluster_name: String,
+    // (username, (user, cached_at))
+    user_cache: DashMap<String, (Option<MqttUser>, u64)>,
+}
+
+impl PlacementUserStorage {
+    pub fn new(
+        client_pool: Arc<ClientPool>,
+        placement_center: Vec<String>,
+        cluster_name: String,
+    ) -> Self {
+        PlacementUserStorage {
+            client_pool,
+            placement_center,
+            cluster_name,
+            user_cache: DashMap::with_capacity(2),
+        }
+    }
+
+    // An expired entry is evicted when it is looked up, so the lookup
+    // never scans the whole cache
+    fn cached_user(&self, username: &str) -> Option<Option<MqttUser>> {
+        if let Some(entry) = self.user_cache.get(username) {
+            let (user, cached_at) = entry.value();
+            if now_second().saturating_sub(*cached_at) < USER_CACHE_TTL_SEC {
+                return Some(user.clone());
+            }
+        }
+        self.user_cache.remove_if(username, |_, (_, cached_at)| {
+            now_second().saturating_sub(*cached_at) >= USER_CACHE_TTL_SEC
+        });
+        None
+    }
+
+    async fn list_user(&self, user_name: String) -> Result<Vec<MqttUser>, MqttBrokerError> {
+        let request = ListUserRequest {
+            cluster_name: self.cluster_name.clone(),
+            user_name,
+        };
+        let reply = placement_list_user(&self.client_pool, &self.placement_center, request).await?;
+
+        let mut results = Vec::with_capacity(reply.users.len());
+        for raw in reply.users {
+            results.push(serde_json::from_slice::<MqttUser>(&raw)?);
+        }
+        Ok(results)
+    }
+
+    fn read_only(action: &str) -> MqttBrokerError {
+        MqttBrokerError::from(CommonError::NotSupportFeature(
+            "amqp-plugins".to_string(),
+            action.to_string(),
+        ))
+    }
+}
+
+#[async_trait]
+impl AuthStorageAdapter for PlacementUserStorage {
+    async fn read_all_user(&self) -> Result<DashMap<String, MqttUser>, MqttBrokerError> {
+        let results = DashMap::with_capacity(2);
+        let now = now_second();
+        for user in self.list_user(String::new()).await? {
+            self.user_cache
+                .insert(user.username.clone(), (Some(user.clone()), now));
+            results.insert(user.username.clone(), user);
+        }
+        Ok(results)
+    }
+
+    async fn read_all_acl(&self) -> Result<Vec<MqttAcl>, MqttBrokerError> {
+        Ok(Vec::new())
+    }
+
+    async fn read_all_blacklist(&self) -> Result<Vec<MqttAclBlackList>, MqttBrokerError> {
+        Ok(Vec::new())
+    }
+
+    async fn get_user(&self, username: String) -> Result<Option<MqttUser>, MqttBrokerError> {
+        if let Some(user) = self.cached_user(&username) {
+            return Ok(user);
+        }
+
+        let user = self.list_user(username.clone()).await?.into_iter().next();
+        self.user_cache
+            .insert(username, (user.clone(), now_second()));
+        Ok(user)
+    }
+
+    async fn save_user(&self, _user_info: MqttUser) -> Result<(), MqttBrokerError> {
+        Err(PlacementUserStorage::read_only("save_user"))
+    }
+
+    async fn delete_user(&self, _username: String) -> Result<(), MqttBrokerError> {
+        Err(PlacementUserStorage::read_only("delete_user"))
+    }
+
+    async fn save_acl(&self, _acl: MqttAcl) -> Result<(), MqttBrokerError> {
+        Err(PlacementUserStorage::read_only("save_acl"))
+    }
+
+    async fn delete_acl(&self, _acl: MqttAcl) -> Result<(), MqttBrokerError> {
+        Err(PlacementUserStorage::read_only("delete_acl"))
+    }
+
+    async fn save_blacklist(&self, _blacklist: MqttAclBlackList) -> Result<(), MqttBrokerError> {
+        Err(PlacementUserStorage::read_only("save_blacklist"))
+    }
+
+    async fn delete_blacklist(&self, _blacklist: MqttAclBlackList) -> Result<(), MqttBrokerError> {
+        Err(PlacementUserStorage::read_only("delete_blacklist"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_base::tools::now_second;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::user::MqttUser;
+    use mqtt_broker::security::AuthStorageAdapter;
+
+    use super::{PlacementUserStorage, USER_CACHE_TTL_SEC};
+
+    #[tokio::test]
+    async fn user_cache_test() {
+        // no placement center is reachable, only cached users can be read
+        let storage =
+            PlacementUserStorage::new(Arc::new(ClientPool::new(1)), Vec::new(), "test".to_string());
+        let user = MqttUser {
+            username: "robustmq".to_string(),
+            password: "robustmq@2024".to_string(),
+            is_superuser: false,
+        };
+        storage
+            .user_cache
+            .insert("robustmq".to_string(), (Some(user.clone()), now_second()));
+        storage
+            .user_cache
+            .insert("unknown".to_string(), (None, now_second()));
+
+        assert_eq!(
+            storage.get_user("robustmq".to_string()).await.unwrap(),
+            Some(user.clone())
+        );
+        assert_eq!(storage.get_user("unknown".to_string()).await.unwrap(), None);
+
+        storage.user_cache.insert(
+            "robustmq".to_string(),
+            (Some(user), now_second() - USER_CACHE_TTL_SEC),
+        );
+        assert!(storage.get_user("robustmq".to_string()).await.is_err());
+        assert!(!storage.user_cache.contains_key("robustmq"));
+        assert!(storage.user_cache.contains_key("unknown"));
+    }
+}
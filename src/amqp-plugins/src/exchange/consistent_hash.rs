@@ -0,0 +1,155 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use metadata_struct::amqp::exchange::AmqpExchange;
+use metadata_struct::amqp::queue::AmqpBinding;
+
+use super::{ExchangeTypePlugin, Route};
+use crate::error::AmqpPluginError;
+use crate::message::PluginMessage;
+
+pub const CONSISTENT_HASH_EXCHANGE_TYPE: &str = "x-consistent-hash";
+// Exchange argument naming a header to hash instead of the routing key
+const HASH_HEADER_ARGUMENT: &str = "hash-header";
+// Upper bound of a binding weight, the ring is rebuilt on every publish
+const MAX_WEIGHT: u32 = 1000;
+
+// Sends every message to exactly one bound queue, picked from a hash ring. The binding
+// routing key is the weight of the queue, the number of points it owns on the ring.
+#[derive(Default)]
+pub struct ConsistentHashExchange {}
+
+impl ConsistentHashExchange {
+    fn weight(binding: &AmqpBinding) -> Result<u32, AmqpPluginError> {
+        match binding.routing_key.parse::<u32>() {
+            Ok(weight) if weight > 0 && weight <= MAX_WEIGHT => Ok(weight),
+            _ => Err(AmqpPluginError::InvalidArgument(
+                "routing_key".to_string(),
+                CONSISTENT_HASH_EXCHANGE_TYPE.to_string(),
+                format!(
+                    "the routing key must be an integer weight between 1 and {}, got {}",
+                    MAX_WEIGHT, binding.routing_key
+                ),
+            )),
+        }
+    }
+}
+
+impl ExchangeTypePlugin for ConsistentHashExchange {
+    fn name(&self) -> &str {
+        CONSISTENT_HASH_EXCHANGE_TYPE
+    }
+
+    fn validate_binding(&self, binding: &AmqpBinding) -> Result<(), AmqpPluginError> {
+        ConsistentHashExchange::weight(binding)?;
+        Ok(())
+    }
+
+    fn route(
+        &self,
+        exchange: &AmqpExchange,
+        bindings: &[(String, AmqpBinding)],
+        message: &PluginMessage,
+    ) -> Route {
+        let mut ring: Vec<(u64, &String)> = Vec::new();
+        for (queue, binding) in bindings {
+            let weight = ConsistentHashExchange::weight(binding).unwrap_or(1);
+            for point in 0..weight {
+                ring.push((fnv1a(format!("{}#{}", queue, point).as_bytes()), queue));
+            }
+        }
+        if ring.is_empty() {
+            return Route::Queues(Vec::new());
+        }
+        ring.sort();
+
+        let key = match exchange.arguments.get(HASH_HEADER_ARGUMENT) {
+            Some(header) => message.header(header).unwrap_or_default(),
+            None => message.routing_key.clone(),
+        };
+        let hash = fnv1a(key.as_bytes());
+        let index = ring.partition_point(|(point, _)| *point < hash) % ring.len();
+        Route::Queues(vec![ring[index].1.clone()])
+    }
+}
+
+// 64 bit FNV-1a, stable across processes unlike the std hasher
+fn fnv1a(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in data {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use metadata_struct::amqp::exchange::AmqpExchange;
+    use metadata_struct::amqp::queue::AmqpBinding;
+
+    use super::ConsistentHashExchange;
+    use crate::exchange::{ExchangeTypePlugin, Route};
+    use crate::message::PluginMessage;
+
+    fn binding(weight: &str) -> AmqpBinding {
+        AmqpBinding {
+            exchange_name: "hash".to_string(),
+            routing_key: weight.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn consistent_hash_route_test() {
+        let plugin = ConsistentHashExchange::default();
+        let exchange = AmqpExchange::default();
+        let bindings = vec![
+            ("q1".to_string(), binding("10")),
+            ("q2".to_string(), binding("10")),
+        ];
+
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for i in 0..1000 {
+            let message = PluginMessage {
+                routing_key: format!("order-{}", i),
+                ..Default::default()
+            };
+            let route = plugin.route(&exchange, &bindings, &message);
+            let first = route.clone();
+            // the same key always lands on the same queue
+            assert_eq!(plugin.route(&exchange, &bindings, &message), first);
+            if let Route::Queues(queues) = route {
+                assert_eq!(queues.len(), 1);
+                *counts.entry(queues[0].clone()).or_default() += 1;
+            }
+        }
+        assert!(*counts.get("q1").unwrap() > 0);
+        assert!(*counts.get("q2").unwrap() > 0);
+
+        let route = plugin.route(&exchange, &[], &PluginMessage::default());
+        assert_eq!(route, Route::Queues(Vec::new()));
+    }
+
+    #[test]
+    fn consistent_hash_validate_binding_test() {
+        let plugin = ConsistentHashExchange::default();
+        assert!(plugin.validate_binding(&binding("3")).is_ok());
+        assert!(plugin.validate_binding(&binding("0")).is_err());
+        assert!(plugin.validate_binding(&binding("1001")).is_err());
+        assert!(plugin.validate_binding(&binding("key")).is_err());
+    }
+}
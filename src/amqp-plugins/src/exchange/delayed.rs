@@ -0,0 +1,145 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use metadata_struct::amqp::exchange::{AmqpExchange, AmqpExchangeType};
+use metadata_struct::amqp::queue::AmqpBinding;
+
+use super::{ExchangeTypePlugin, Route};
+use crate::error::AmqpPluginError;
+use crate::message::PluginMessage;
+
+pub const DELAYED_EXCHANGE_TYPE: &str = "x-delayed-message";
+// Exchange argument with the built-in type used to route once the delay is over
+const DELAYED_TYPE_ARGUMENT: &str = "x-delayed-type";
+// Message header with the delay in milliseconds
+const DELAY_HEADER: &str = "x-delay";
+
+// Holds messages carrying an x-delay header before routing them like the exchange type
+// in x-delayed-type. Delayed messages are kept in broker memory only and are lost if
+// the broker restarts before the delay is over.
+#[derive(Default)]
+pub struct DelayedMessageExchange {}
+
+impl DelayedMessageExchange {
+    fn delayed_type(exchange: &AmqpExchange) -> Result<AmqpExchangeType, AmqpPluginError> {
+        let invalid = |reason: String| {
+            AmqpPluginError::InvalidArgument(
+                DELAYED_TYPE_ARGUMENT.to_string(),
+                DELAYED_EXCHANGE_TYPE.to_string(),
+                reason,
+            )
+        };
+        let value = exchange
+            .arguments
+            .get(DELAYED_TYPE_ARGUMENT)
+            .ok_or_else(|| invalid("the argument is required".to_string()))?;
+        AmqpExchangeType::from_str(value).map_err(|e| invalid(e.to_string()))
+    }
+}
+
+impl ExchangeTypePlugin for DelayedMessageExchange {
+    fn name(&self) -> &str {
+        DELAYED_EXCHANGE_TYPE
+    }
+
+    fn validate_exchange(&self, exchange: &AmqpExchange) -> Result<(), AmqpPluginError> {
+        DelayedMessageExchange::delayed_type(exchange)?;
+        Ok(())
+    }
+
+    fn route(
+        &self,
+        exchange: &AmqpExchange,
+        _bindings: &[(String, AmqpBinding)],
+        message: &PluginMessage,
+    ) -> Route {
+        let exchange_type =
+            DelayedMessageExchange::delayed_type(exchange).unwrap_or(AmqpExchangeType::Direct);
+        let delay = message
+            .header(DELAY_HEADER)
+            .and_then(|value| value.parse::<i64>().ok())
+            .filter(|ms| *ms > 0)
+            .map(|ms| Duration::from_millis(ms as u64));
+        Route::Standard {
+            exchange_type,
+            delay,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use metadata_struct::amqp::exchange::{AmqpExchange, AmqpExchangeType};
+    use protocol::amqp::common::{BasicProperties, FieldTable, FieldValue};
+
+    use super::DelayedMessageExchange;
+    use crate::exchange::{ExchangeTypePlugin, Route};
+    use crate::message::PluginMessage;
+
+    fn exchange(delayed_type: Option<&str>) -> AmqpExchange {
+        let mut exchange = AmqpExchange::default();
+        if let Some(value) = delayed_type {
+            exchange
+                .arguments
+                .insert("x-delayed-type".to_string(), value.to_string());
+        }
+        exchange
+    }
+
+    #[test]
+    fn delayed_validate_exchange_test() {
+        let plugin = DelayedMessageExchange::default();
+        assert!(plugin.validate_exchange(&exchange(Some("topic"))).is_ok());
+        assert!(plugin.validate_exchange(&exchange(None)).is_err());
+        assert!(plugin
+            .validate_exchange(&exchange(Some("headers")))
+            .is_err());
+    }
+
+    #[test]
+    fn delayed_route_test() {
+        let plugin = DelayedMessageExchange::default();
+        let exchange = exchange(Some("fanout"));
+
+        let mut headers = FieldTable::new();
+        headers.insert("x-delay".to_string(), FieldValue::LongInt(1500));
+        let message = PluginMessage {
+            properties: BasicProperties {
+                headers: Some(headers),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            plugin.route(&exchange, &[], &message),
+            Route::Standard {
+                exchange_type: AmqpExchangeType::Fanout,
+                delay: Some(Duration::from_millis(1500)),
+            }
+        );
+
+        assert_eq!(
+            plugin.route(&exchange, &[], &PluginMessage::default()),
+            Route::Standard {
+                exchange_type: AmqpExchangeType::Fanout,
+                delay: None,
+            }
+        );
+    }
+}
@@ -0,0 +1,168 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use metadata_struct::amqp::exchange::AmqpExchange;
+use metadata_struct::amqp::queue::AmqpBinding;
+
+use super::{ExchangeTypePlugin, Route};
+use crate::error::AmqpPluginError;
+use crate::message::PluginMessage;
+
+pub const HEADERS_EXCHANGE_TYPE: &str = "headers";
+const MATCH_ARGUMENT: &str = "x-match";
+
+// Routes on message headers instead of the routing key. A binding matches when all
+// (x-match=all, the default) or any (x-match=any) of its arguments equal the
+// message header of the same name. Arguments starting with "x-" are not compared.
+#[derive(Default)]
+pub struct HeadersExchange {}
+
+impl HeadersExchange {
+    fn matches(binding: &AmqpBinding, message: &PluginMessage) -> bool {
+        let mut pairs = binding
+            .arguments
+            .iter()
+            .filter(|(key, _)| !key.starts_with("x-"))
+            .peekable();
+        if pairs.peek().is_none() {
+            return false;
+        }
+
+        let mut matched =
+            pairs.map(|(key, value)| message.header(key).as_deref() == Some(value.as_str()));
+        match binding.arguments.get(MATCH_ARGUMENT).map(|v| v.as_str()) {
+            Some("any") => matched.any(|m| m),
+            _ => matched.all(|m| m),
+        }
+    }
+}
+
+impl ExchangeTypePlugin for HeadersExchange {
+    fn name(&self) -> &str {
+        HEADERS_EXCHANGE_TYPE
+    }
+
+    fn validate_binding(&self, binding: &AmqpBinding) -> Result<(), AmqpPluginError> {
+        match binding.arguments.get(MATCH_ARGUMENT).map(|v| v.as_str()) {
+            None | Some("all") | Some("any") => Ok(()),
+            Some(value) => Err(AmqpPluginError::InvalidArgument(
+                MATCH_ARGUMENT.to_string(),
+                HEADERS_EXCHANGE_TYPE.to_string(),
+                format!("expected all or any, got {}", value),
+            )),
+        }
+    }
+
+    fn route(
+        &self,
+        _exchange: &AmqpExchange,
+        bindings: &[(String, AmqpBinding)],
+        message: &PluginMessage,
+    ) -> Route {
+        let mut queues: Vec<String> = Vec::new();
+        for (queue, binding) in bindings {
+            if !queues.contains(queue) && HeadersExchange::matches(binding, message) {
+                queues.push(queue.clone());
+            }
+        }
+        Route::Queues(queues)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use bytes::Bytes;
+    use metadata_struct::amqp::exchange::AmqpExchange;
+    use metadata_struct::amqp::queue::AmqpBinding;
+    use protocol::amqp::common::{BasicProperties, FieldTable, FieldValue};
+
+    use super::HeadersExchange;
+    use crate::exchange::{ExchangeTypePlugin, Route};
+    use crate::message::PluginMessage;
+
+    fn binding(arguments: &[(&str, &str)]) -> AmqpBinding {
+        AmqpBinding {
+            exchange_name: "h".to_string(),
+            routing_key: "".to_string(),
+            arguments: arguments
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<BTreeMap<_, _>>(),
+        }
+    }
+
+    fn message(headers: &[(&str, &str)]) -> PluginMessage {
+        let mut table = FieldTable::new();
+        for (k, v) in headers {
+            table.insert(
+                k.to_string(),
+                FieldValue::LongString(Bytes::from(v.to_string())),
+            );
+        }
+        PluginMessage {
+            properties: BasicProperties {
+                headers: Some(table),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn headers_route_test() {
+        let plugin = HeadersExchange::default();
+        let exchange = AmqpExchange::default();
+        let bindings = vec![
+            (
+                "q_all".to_string(),
+                binding(&[("format", "pdf"), ("type", "report")]),
+            ),
+            (
+                "q_any".to_string(),
+                binding(&[("x-match", "any"), ("format", "pdf"), ("type", "log")]),
+            ),
+            ("q_empty".to_string(), binding(&[("x-match", "any")])),
+        ];
+
+        let route = plugin.route(
+            &exchange,
+            &bindings,
+            &message(&[("format", "pdf"), ("type", "report")]),
+        );
+        assert_eq!(
+            route,
+            Route::Queues(vec!["q_all".to_string(), "q_any".to_string()])
+        );
+
+        let route = plugin.route(&exchange, &bindings, &message(&[("type", "log")]));
+        assert_eq!(route, Route::Queues(vec!["q_any".to_string()]));
+
+        let route = plugin.route(&exchange, &bindings, &PluginMessage::default());
+        assert_eq!(route, Route::Queues(Vec::new()));
+    }
+
+    #[test]
+    fn headers_validate_binding_test() {
+        let plugin = HeadersExchange::default();
+        assert!(plugin
+            .validate_binding(&binding(&[("x-match", "all")]))
+            .is_ok());
+        assert!(plugin.validate_binding(&binding(&[])).is_ok());
+        assert!(plugin
+            .validate_binding(&binding(&[("x-match", "some")]))
+            .is_err());
+    }
+}
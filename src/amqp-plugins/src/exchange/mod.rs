@@ -0,0 +1,60 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use metadata_struct::amqp::exchange::{AmqpExchange, AmqpExchangeType};
+use metadata_struct::amqp::queue::AmqpBinding;
+
+use crate::error::AmqpPluginError;
+use crate::message::PluginMessage;
+
+pub mod consistent_hash;
+pub mod delayed;
+pub mod headers;
+
+// Where an exchange type plugin sends a message
+#[derive(Clone, Debug, PartialEq)]
+pub enum Route {
+    // Exactly these queues
+    Queues(Vec<String>),
+    // Route like a built-in exchange type, optionally after a delay
+    Standard {
+        exchange_type: AmqpExchangeType,
+        delay: Option<Duration>,
+    },
+}
+
+pub trait ExchangeTypePlugin: Send + Sync {
+    // The type name used in exchange.declare
+    fn name(&self) -> &str;
+
+    // Called on exchange.declare before the exchange is stored
+    fn validate_exchange(&self, _exchange: &AmqpExchange) -> Result<(), AmqpPluginError> {
+        Ok(())
+    }
+
+    // Called on queue.bind before the binding is stored
+    fn validate_binding(&self, _binding: &AmqpBinding) -> Result<(), AmqpPluginError> {
+        Ok(())
+    }
+
+    // `bindings` holds every (queue name, binding) pair of the exchange
+    fn route(
+        &self,
+        exchange: &AmqpExchange,
+        bindings: &[(String, AmqpBinding)],
+        message: &PluginMessage,
+    ) -> Route;
+}
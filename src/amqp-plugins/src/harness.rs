@@ -0,0 +1,144 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+use metadata_struct::amqp::exchange::{AmqpExchange, AmqpExchangeType};
+use metadata_struct::amqp::queue::AmqpBinding;
+
+use crate::error::AmqpPluginError;
+use crate::exchange::Route;
+use crate::manager::AmqpPluginManager;
+use crate::message::PluginMessage;
+
+// Runs plugins the way the broker does, against an in memory exchange and binding
+// table, so a plugin can be tested without a broker or a placement center.
+pub struct PluginHarness {
+    manager: AmqpPluginManager,
+    exchanges: HashMap<String, AmqpExchange>,
+    // (queue name, binding) in bind order
+    bindings: Vec<(String, AmqpBinding)>,
+}
+
+impl PluginHarness {
+    pub fn new(manager: AmqpPluginManager) -> Self {
+        PluginHarness {
+            manager,
+            exchanges: HashMap::new(),
+            bindings: Vec::new(),
+        }
+    }
+
+    pub fn manager(&self) -> &AmqpPluginManager {
+        &self.manager
+    }
+
+    pub fn declare_exchange(
+        &mut self,
+        exchange_name: &str,
+        kind: &str,
+        arguments: &[(&str, &str)],
+    ) -> Result<(), AmqpPluginError> {
+        let mut exchange = AmqpExchange {
+            exchange_name: exchange_name.to_string(),
+            arguments: to_arguments(arguments),
+            ..Default::default()
+        };
+        if let Some(plugin) = self.manager.exchange_type(kind) {
+            exchange.exchange_type = AmqpExchangeType::Custom(kind.to_string());
+            plugin.validate_exchange(&exchange)?;
+        } else {
+            exchange.exchange_type = AmqpExchangeType::from_str(kind)
+                .map_err(|_| AmqpPluginError::UnknownPlugin(kind.to_string()))?;
+        }
+        self.exchanges.insert(exchange_name.to_string(), exchange);
+        Ok(())
+    }
+
+    pub fn bind(
+        &mut self,
+        queue_name: &str,
+        exchange_name: &str,
+        routing_key: &str,
+        arguments: &[(&str, &str)],
+    ) -> Result<(), AmqpPluginError> {
+        let binding = AmqpBinding {
+            exchange_name: exchange_name.to_string(),
+            routing_key: routing_key.to_string(),
+            arguments: to_arguments(arguments),
+        };
+        if let Some(AmqpExchangeType::Custom(kind)) = self
+            .exchanges
+            .get(exchange_name)
+            .map(|exchange| &exchange.exchange_type)
+        {
+            if let Some(plugin) = self.manager.exchange_type(kind) {
+                plugin.validate_binding(&binding)?;
+            }
+        }
+        self.bindings.push((queue_name.to_string(), binding));
+        Ok(())
+    }
+
+    // Runs the publish interceptors, then routes plugin exchanges through their plugin.
+    // Built-in exchanges come back as Route::Standard without a delay.
+    pub fn publish(
+        &self,
+        mut message: PluginMessage,
+    ) -> Result<(PluginMessage, Route), AmqpPluginError> {
+        self.manager.intercept_publish(&mut message)?;
+
+        let exchange = self
+            .exchanges
+            .get(&message.exchange)
+            .ok_or_else(|| AmqpPluginError::UnknownPlugin(message.exchange.clone()))?;
+        let route = match &exchange.exchange_type {
+            AmqpExchangeType::Custom(kind) => {
+                let plugin = self
+                    .manager
+                    .exchange_type(kind)
+                    .ok_or_else(|| AmqpPluginError::UnknownPlugin(kind.clone()))?;
+                let bindings: Vec<(String, AmqpBinding)> = self
+                    .bindings
+                    .iter()
+                    .filter(|(_, binding)| binding.exchange_name == exchange.exchange_name)
+                    .cloned()
+                    .collect();
+                plugin.route(exchange, &bindings, &message)
+            }
+            exchange_type => Route::Standard {
+                exchange_type: exchange_type.clone(),
+                delay: None,
+            },
+        };
+        Ok((message, route))
+    }
+
+    pub fn consume(
+        &self,
+        queue_name: &str,
+        mut message: PluginMessage,
+    ) -> Result<PluginMessage, AmqpPluginError> {
+        self.manager.intercept_consume(queue_name, &mut message)?;
+        Ok(message)
+    }
+}
+
+fn to_arguments(arguments: &[(&str, &str)]) -> BTreeMap<String, String> {
+    arguments
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
@@ -0,0 +1,36 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::error::AmqpPluginError;
+use crate::message::PluginMessage;
+
+pub mod timestamp;
+
+// Runs on basic.publish before the message is routed, an error rejects the publish
+pub trait PublishInterceptor: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn intercept(&self, message: &mut PluginMessage) -> Result<(), AmqpPluginError>;
+}
+
+// Runs before a message is handed to a consumer or basic.get, an error drops the message
+pub trait ConsumeInterceptor: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn intercept(
+        &self,
+        queue_name: &str,
+        message: &mut PluginMessage,
+    ) -> Result<(), AmqpPluginError>;
+}
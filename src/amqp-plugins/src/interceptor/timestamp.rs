@@ -0,0 +1,58 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::tools::now_second;
+
+use super::PublishInterceptor;
+use crate::error::AmqpPluginError;
+use crate::message::PluginMessage;
+
+pub const TIMESTAMP_INTERCEPTOR: &str = "message_timestamp";
+
+// Stamps the publish time on messages that were published without a timestamp
+#[derive(Default)]
+pub struct MessageTimestampInterceptor {}
+
+impl PublishInterceptor for MessageTimestampInterceptor {
+    fn name(&self) -> &str {
+        TIMESTAMP_INTERCEPTOR
+    }
+
+    fn intercept(&self, message: &mut PluginMessage) -> Result<(), AmqpPluginError> {
+        if message.properties.timestamp.is_none() {
+            message.properties.timestamp = Some(now_second());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MessageTimestampInterceptor;
+    use crate::interceptor::PublishInterceptor;
+    use crate::message::PluginMessage;
+
+    #[test]
+    fn timestamp_intercept_test() {
+        let interceptor = MessageTimestampInterceptor::default();
+
+        let mut message = PluginMessage::default();
+        interceptor.intercept(&mut message).unwrap();
+        assert!(message.properties.timestamp.unwrap() > 0);
+
+        message.properties.timestamp = Some(10);
+        interceptor.intercept(&mut message).unwrap();
+        assert_eq!(message.properties.timestamp, Some(10));
+    }
+}
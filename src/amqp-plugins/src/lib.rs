@@ -11,3 +11,11 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+pub mod auth;
+pub mod error;
+pub mod exchange;
+pub mod harness;
+pub mod interceptor;
+pub mod manager;
+pub mod message;
@@ -0,0 +1,138 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use common_base::config::broker_amqp::Plugins;
+use grpc_clients::pool::ClientPool;
+
+use crate::auth::{AmqpAuthenticator, MqttUserAuthenticator};
+use crate::error::AmqpPluginError;
+use crate::exchange::consistent_hash::{ConsistentHashExchange, CONSISTENT_HASH_EXCHANGE_TYPE};
+use crate::exchange::delayed::{DelayedMessageExchange, DELAYED_EXCHANGE_TYPE};
+use crate::exchange::headers::{HeadersExchange, HEADERS_EXCHANGE_TYPE};
+use crate::exchange::ExchangeTypePlugin;
+use crate::interceptor::timestamp::{MessageTimestampInterceptor, TIMESTAMP_INTERCEPTOR};
+use crate::interceptor::{ConsumeInterceptor, PublishInterceptor};
+use crate::message::PluginMessage;
+
+// The plugins enabled for one broker, built once at startup and shared read only
+#[derive(Default)]
+pub struct AmqpPluginManager {
+    exchange_types: HashMap<String, Arc<dyn ExchangeTypePlugin>>,
+    publish_interceptors: Vec<Arc<dyn PublishInterceptor>>,
+    consume_interceptors: Vec<Arc<dyn ConsumeInterceptor>>,
+    authenticator: Option<Arc<dyn AmqpAuthenticator>>,
+}
+
+impl AmqpPluginManager {
+    pub fn new() -> Self {
+        AmqpPluginManager::default()
+    }
+
+    // Enables the built-in plugins named in the [plugins] section of the broker config
+    pub fn build(
+        conf: &Plugins,
+        client_pool: Arc<ClientPool>,
+        placement_center: Vec<String>,
+    ) -> Result<Self, AmqpPluginError> {
+        let mut manager = AmqpPluginManager::new();
+        for name in conf.exchange_types.iter() {
+            let plugin: Arc<dyn ExchangeTypePlugin> = match name.as_str() {
+                HEADERS_EXCHANGE_TYPE => Arc::new(HeadersExchange::default()),
+                CONSISTENT_HASH_EXCHANGE_TYPE => Arc::new(ConsistentHashExchange::default()),
+                DELAYED_EXCHANGE_TYPE => Arc::new(DelayedMessageExchange::default()),
+                _ => return Err(AmqpPluginError::UnknownPlugin(name.clone())),
+            };
+            manager.register_exchange_type(plugin)?;
+        }
+
+        for name in conf.interceptors.iter() {
+            match name.as_str() {
+                TIMESTAMP_INTERCEPTOR => manager
+                    .register_publish_interceptor(Arc::new(MessageTimestampInterceptor::default())),
+                _ => return Err(AmqpPluginError::UnknownPlugin(name.clone())),
+            }
+        }
+
+        if let Some(auth) = conf.auth.as_ref() {
+            let authenticator = MqttUserAuthenticator::build(client_pool, placement_center, auth)?;
+            manager.set_authenticator(Arc::new(authenticator));
+        }
+        Ok(manager)
+    }
+
+    // The built-in direct, fanout and topic types cannot be replaced
+    pub fn register_exchange_type(
+        &mut self,
+        plugin: Arc<dyn ExchangeTypePlugin>,
+    ) -> Result<(), AmqpPluginError> {
+        let name = plugin.name().to_string();
+        if matches!(name.as_str(), "direct" | "fanout" | "topic")
+            || self.exchange_types.contains_key(&name)
+        {
+            return Err(AmqpPluginError::ExchangeTypeAlreadyRegistered(name));
+        }
+        self.exchange_types.insert(name, plugin);
+        Ok(())
+    }
+
+    pub fn register_publish_interceptor(&mut self, interceptor: Arc<dyn PublishInterceptor>) {
+        self.publish_interceptors.push(interceptor);
+    }
+
+    pub fn register_consume_interceptor(&mut self, interceptor: Arc<dyn ConsumeInterceptor>) {
+        self.consume_interceptors.push(interceptor);
+    }
+
+    pub fn set_authenticator(&mut self, authenticator: Arc<dyn AmqpAuthenticator>) {
+        self.authenticator = Some(authenticator);
+    }
+
+    pub fn exchange_type(&self, name: &str) -> Option<Arc<dyn ExchangeTypePlugin>> {
+        self.exchange_types.get(name).cloned()
+    }
+
+    pub fn authenticator(&self) -> Option<Arc<dyn AmqpAuthenticator>> {
+        self.authenticator.clone()
+    }
+
+    pub fn has_publish_interceptors(&self) -> bool {
+        !self.publish_interceptors.is_empty()
+    }
+
+    pub fn has_consume_interceptors(&self) -> bool {
+        !self.consume_interceptors.is_empty()
+    }
+
+    // Runs the publish interceptors in registration order, stopping at the first error
+    pub fn intercept_publish(&self, message: &mut PluginMessage) -> Result<(), AmqpPluginError> {
+        for interceptor in self.publish_interceptors.iter() {
+            interceptor.intercept(message)?;
+        }
+        Ok(())
+    }
+
+    pub fn intercept_consume(
+        &self,
+        queue_name: &str,
+        message: &mut PluginMessage,
+    ) -> Result<(), AmqpPluginError> {
+        for interceptor in self.consume_interceptors.iter() {
+            interceptor.intercept(queue_name, message)?;
+        }
+        Ok(())
+    }
+}
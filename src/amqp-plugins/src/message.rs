@@ -0,0 +1,110 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+
+use bytes::Bytes;
+use protocol::amqp::common::{BasicProperties, FieldTable, FieldValue};
+
+// A message as plugins see it, interceptors may change any part of it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PluginMessage {
+    pub exchange: String,
+    pub routing_key: String,
+    pub properties: BasicProperties,
+    pub body: Bytes,
+}
+
+impl PluginMessage {
+    // Header value in its string form
+    pub fn header(&self, name: &str) -> Option<String> {
+        self.properties
+            .headers
+            .as_ref()
+            .and_then(|headers| headers.get(name))
+            .and_then(field_value_to_string)
+    }
+}
+
+// String form of the scalar field values, tables and arrays have none
+pub fn field_value_to_string(value: &FieldValue) -> Option<String> {
+    let value = match value {
+        FieldValue::Boolean(v) => v.to_string(),
+        FieldValue::ShortShortInt(v) => v.to_string(),
+        FieldValue::ShortShortUInt(v) => v.to_string(),
+        FieldValue::ShortInt(v) => v.to_string(),
+        FieldValue::ShortUInt(v) => v.to_string(),
+        FieldValue::LongInt(v) => v.to_string(),
+        FieldValue::LongUInt(v) => v.to_string(),
+        FieldValue::LongLongInt(v) => v.to_string(),
+        FieldValue::Float(v) => v.to_string(),
+        FieldValue::Double(v) => v.to_string(),
+        FieldValue::Timestamp(v) => v.to_string(),
+        FieldValue::LongString(v) | FieldValue::ByteArray(v) => {
+            String::from_utf8_lossy(v).to_string()
+        }
+        FieldValue::Void => String::new(),
+        FieldValue::Decimal(_) | FieldValue::FieldArray(_) | FieldValue::FieldTable(_) => {
+            return None
+        }
+    };
+    Some(value)
+}
+
+// Declare and bind arguments are stored in their string form
+pub fn field_table_to_arguments(table: &FieldTable) -> BTreeMap<String, String> {
+    table
+        .iter()
+        .filter_map(|(key, value)| field_value_to_string(value).map(|value| (key.clone(), value)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use protocol::amqp::common::{BasicProperties, FieldTable, FieldValue};
+
+    use super::{field_table_to_arguments, PluginMessage};
+
+    #[test]
+    fn field_table_to_arguments_test() {
+        let mut table = FieldTable::new();
+        table.insert(
+            "x-match".to_string(),
+            FieldValue::LongString(Bytes::from("any")),
+        );
+        table.insert("x-delay".to_string(), FieldValue::LongInt(500));
+        table.insert("flag".to_string(), FieldValue::Boolean(true));
+        table.insert(
+            "nested".to_string(),
+            FieldValue::FieldTable(FieldTable::new()),
+        );
+
+        let arguments = field_table_to_arguments(&table);
+        assert_eq!(arguments.len(), 3);
+        assert_eq!(arguments.get("x-match"), Some(&"any".to_string()));
+        assert_eq!(arguments.get("x-delay"), Some(&"500".to_string()));
+        assert_eq!(arguments.get("flag"), Some(&"true".to_string()));
+
+        let message = PluginMessage {
+            properties: BasicProperties {
+                headers: Some(table),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(message.header("x-delay"), Some("500".to_string()));
+        assert_eq!(message.header("missing"), None);
+    }
+}
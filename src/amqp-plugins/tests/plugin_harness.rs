@@ -0,0 +1,174 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use amqp_plugins::error::AmqpPluginError;
+    use amqp_plugins::exchange::Route;
+    use amqp_plugins::harness::PluginHarness;
+    use amqp_plugins::interceptor::ConsumeInterceptor;
+    use amqp_plugins::manager::AmqpPluginManager;
+    use amqp_plugins::message::PluginMessage;
+    use bytes::Bytes;
+    use common_base::config::broker_amqp::Plugins;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::amqp::exchange::AmqpExchangeType;
+    use protocol::amqp::common::{BasicProperties, FieldTable, FieldValue};
+
+    struct RejectEmptyBody {}
+
+    impl ConsumeInterceptor for RejectEmptyBody {
+        fn name(&self) -> &str {
+            "reject_empty_body"
+        }
+
+        fn intercept(
+            &self,
+            queue_name: &str,
+            message: &mut PluginMessage,
+        ) -> Result<(), AmqpPluginError> {
+            if message.body.is_empty() {
+                return Err(AmqpPluginError::MessageRejected(
+                    self.name().to_string(),
+                    format!("empty body for queue {}", queue_name),
+                ));
+            }
+            Ok(())
+        }
+    }
+
+    fn build_manager() -> AmqpPluginManager {
+        let conf = Plugins {
+            exchange_types: vec![
+                "headers".to_string(),
+                "x-consistent-hash".to_string(),
+                "x-delayed-message".to_string(),
+            ],
+            interceptors: vec!["message_timestamp".to_string()],
+            auth: None,
+        };
+        AmqpPluginManager::build(&conf, Arc::new(ClientPool::new(1)), Vec::new()).unwrap()
+    }
+
+    fn message(exchange: &str, routing_key: &str, headers: FieldTable) -> PluginMessage {
+        PluginMessage {
+            exchange: exchange.to_string(),
+            routing_key: routing_key.to_string(),
+            properties: BasicProperties {
+                headers: Some(headers),
+                ..Default::default()
+            },
+            body: Bytes::from("hello"),
+        }
+    }
+
+    #[test]
+    fn build_unknown_plugin_test() {
+        let conf = Plugins {
+            exchange_types: vec!["x-unknown".to_string()],
+            ..Default::default()
+        };
+        let result = AmqpPluginManager::build(&conf, Arc::new(ClientPool::new(1)), Vec::new());
+        assert!(matches!(result, Err(AmqpPluginError::UnknownPlugin(_))));
+    }
+
+    #[test]
+    fn headers_exchange_test() {
+        let mut harness = PluginHarness::new(build_manager());
+        harness.declare_exchange("reports", "headers", &[]).unwrap();
+        harness
+            .bind(
+                "pdf",
+                "reports",
+                "",
+                &[("x-match", "all"), ("format", "pdf")],
+            )
+            .unwrap();
+        assert!(harness
+            .bind("bad", "reports", "", &[("x-match", "none")])
+            .is_err());
+
+        let mut headers = FieldTable::new();
+        headers.insert(
+            "format".to_string(),
+            FieldValue::LongString(Bytes::from("pdf")),
+        );
+        let (message, route) = harness.publish(message("reports", "", headers)).unwrap();
+        assert_eq!(route, Route::Queues(vec!["pdf".to_string()]));
+        // message_timestamp ran before routing
+        assert!(message.properties.timestamp.is_some());
+    }
+
+    #[test]
+    fn consistent_hash_exchange_test() {
+        let mut harness = PluginHarness::new(build_manager());
+        harness
+            .declare_exchange("orders", "x-consistent-hash", &[])
+            .unwrap();
+        harness.bind("q1", "orders", "1", &[]).unwrap();
+        harness.bind("q2", "orders", "2", &[]).unwrap();
+        assert!(harness.bind("q3", "orders", "#", &[]).is_err());
+
+        let (_, first) = harness
+            .publish(message("orders", "order-1", FieldTable::new()))
+            .unwrap();
+        let (_, second) = harness
+            .publish(message("orders", "order-1", FieldTable::new()))
+            .unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn delayed_exchange_test() {
+        let mut harness = PluginHarness::new(build_manager());
+        assert!(harness
+            .declare_exchange("later", "x-delayed-message", &[])
+            .is_err());
+        harness
+            .declare_exchange("later", "x-delayed-message", &[("x-delayed-type", "topic")])
+            .unwrap();
+
+        let mut headers = FieldTable::new();
+        headers.insert("x-delay".to_string(), FieldValue::LongLongInt(200));
+        let (_, route) = harness.publish(message("later", "a.b", headers)).unwrap();
+        assert_eq!(
+            route,
+            Route::Standard {
+                exchange_type: AmqpExchangeType::Topic,
+                delay: Some(Duration::from_millis(200)),
+            }
+        );
+    }
+
+    #[test]
+    fn consume_interceptor_test() {
+        let mut manager = build_manager();
+        manager.register_consume_interceptor(Arc::new(RejectEmptyBody {}));
+        let harness = PluginHarness::new(manager);
+
+        let message = message("", "q", FieldTable::new());
+        assert!(harness.consume("q", message.clone()).is_ok());
+
+        let empty = PluginMessage {
+            body: Bytes::new(),
+            ..message
+        };
+        assert!(matches!(
+            harness.consume("q", empty),
+            Err(AmqpPluginError::MessageRejected(_, _))
+        ));
+    }
+}
@@ -169,6 +169,7 @@ async fn handle_mqtt(args: MqttArgs, cmd: MqttBrokerCommand) {
                 username: arg.username,
                 password: arg.password,
                 is_superuser: arg.is_superuser,
+                password_hash: arg.password_hash.unwrap_or_default(),
             }),
             MQTTAction::DeleteUser(arg) => MqttActionType::DeleteUser(DeleteUserRequest {
                 username: arg.username,
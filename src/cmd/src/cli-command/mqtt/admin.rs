@@ -35,6 +35,10 @@ pub(crate) struct CreateUserArgs {
 
     #[arg(short, long, default_value_t = false)]
     pub(crate) is_superuser: bool,
+
+    #[arg(long, value_parser = ["bcrypt", "pbkdf2", "sha256", "plain"])]
+    #[arg(help = "Algorithm used to store the password, the broker default when not set")]
+    pub(crate) password_hash: Option<String>,
 }
 
 #[derive(clap::Args, Debug)]
@@ -0,0 +1,107 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::hint::black_box;
+
+use common_base::utils::topic_trie::{topic_match, TopicTrie};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+// `/region/{r}/device/{d}/{metric}`
+fn topic_names(count: usize) -> Vec<String> {
+    let metrics = ["temperature", "humidity", "battery", "status"];
+    (0..count)
+        .map(|i| {
+            format!(
+                "/region/{}/device/{}/{}",
+                i % 16,
+                i / metrics.len(),
+                metrics[i % metrics.len()]
+            )
+        })
+        .collect()
+}
+
+// A mix of exact, `+` and `#` filters
+fn topic_filters(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|i| match i % 4 {
+            0 => format!("/region/{}/device/{}/temperature", i % 16, i),
+            1 => format!("/region/+/device/{}/+", i),
+            2 => format!("/region/{}/device/{}/#", i % 16, i),
+            _ => format!("/region/{}/+/{}/status", i % 16, i),
+        })
+        .collect()
+}
+
+// Find the subscriptions of a published topic
+fn bench_publish_routing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("publish_routing");
+    for count in [1_000, 10_000, 50_000] {
+        let filters = topic_filters(count);
+        let mut trie = TopicTrie::new();
+        for (i, filter) in filters.iter().enumerate() {
+            trie.insert(filter, i);
+        }
+        let topic = "/region/7/device/423/temperature";
+
+        group.bench_with_input(BenchmarkId::new("trie", count), &count, |b, _| {
+            b.iter(|| trie.match_topic(black_box(topic)))
+        });
+        group.bench_with_input(BenchmarkId::new("scan", count), &count, |b, _| {
+            b.iter(|| {
+                filters
+                    .iter()
+                    .filter(|filter| topic_match(black_box(topic), filter))
+                    .count()
+            })
+        });
+    }
+    group.finish();
+}
+
+// Find the topics a new subscription covers
+fn bench_subscribe_resolution(c: &mut Criterion) {
+    let mut group = c.benchmark_group("subscribe_resolution");
+    for count in [1_000, 10_000, 50_000] {
+        let topics = topic_names(count);
+        let mut trie = TopicTrie::new();
+        for (i, topic) in topics.iter().enumerate() {
+            trie.insert(topic, i);
+        }
+
+        for filter in ["/region/3/device/+/battery", "/region/3/device/100/#"] {
+            group.bench_with_input(
+                BenchmarkId::new(format!("trie {}", filter), count),
+                &count,
+                |b, _| b.iter(|| trie.match_filter(black_box(filter))),
+            );
+            group.bench_with_input(
+                BenchmarkId::new(format!("scan {}", filter), count),
+                &count,
+                |b, _| {
+                    b.iter(|| {
+                        topics
+                            .iter()
+                            .filter(|topic| topic_match(topic, black_box(filter)))
+                            .count()
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_publish_routing, bench_subscribe_resolution);
+criterion_main!(benches);
@@ -0,0 +1,195 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use super::common::{Auth, Log, Storage};
+use super::default_amqp::{
+    default_channel_max, default_frame_max, default_heartbeat, default_log, default_network,
+    default_network_tcp_port, default_placement_center, default_prefetch_count, default_storage,
+    default_system,
+};
+use crate::tools::{read_file, try_create_fold};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct BrokerAmqpConfig {
+    pub cluster_name: String,
+    pub broker_id: u64,
+    #[serde(default = "default_placement_center")]
+    pub placement_center: Vec<String>,
+    #[serde(default = "default_network")]
+    pub network: Network,
+    #[serde(default = "default_system")]
+    pub system: System,
+    #[serde(default = "default_storage")]
+    pub storage: Storage,
+    #[serde(default)]
+    pub plugins: Plugins,
+    #[serde(default = "default_log")]
+    pub log: Log,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Network {
+    #[serde(default = "default_network_tcp_port")]
+    pub tcp_port: u32,
+    // Values offered in connection.tune, the client may lower them
+    #[serde(default = "default_channel_max")]
+    pub channel_max: u16,
+    #[serde(default = "default_frame_max")]
+    pub frame_max: u32,
+    // Heartbeat interval in seconds, 0 disables heartbeats
+    #[serde(default = "default_heartbeat")]
+    pub heartbeat: u16,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct System {
+    #[serde(default)]
+    pub runtime_worker_threads: usize,
+    #[serde(default)]
+    pub default_user: String,
+    #[serde(default)]
+    pub default_password: String,
+    // Unacked deliveries per channel when the client never sends basic.qos
+    #[serde(default = "default_prefetch_count")]
+    pub default_prefetch_count: u16,
+}
+
+// Plugins are only loaded when listed here
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Plugins {
+    // headers, x-consistent-hash, x-delayed-message
+    #[serde(default)]
+    pub exchange_types: Vec<String>,
+    // Publish interceptors in the order they run: message_timestamp
+    #[serde(default)]
+    pub interceptors: Vec<String>,
+    // Logs clients in with the users of an MQTT cluster, the default user keeps working
+    #[serde(default)]
+    pub auth: Option<PluginAuth>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct PluginAuth {
+    // Read through the placement center when storage_type is placement
+    pub mqtt_cluster_name: String,
+    #[serde(flatten)]
+    pub auth: Auth,
+}
+
+static BROKER_AMQP_CONF: OnceLock<BrokerAmqpConfig> = OnceLock::new();
+
+pub fn init_broker_amqp_conf_by_path(config_path: &str) -> &'static BrokerAmqpConfig {
+    // n.b. static items do not call [`Drop`] on program termination, so if
+    // [`DeepThought`] impls Drop, that will not be used for this instance.
+    BROKER_AMQP_CONF.get_or_init(|| {
+        let content = match read_file(config_path) {
+            Ok(data) => data,
+            Err(e) => {
+                panic!("{}", e.to_string())
+            }
+        };
+        let config: BrokerAmqpConfig = match toml::from_str(&content) {
+            Ok(da) => da,
+            Err(e) => {
+                panic!("{}", e)
+            }
+        };
+        match try_create_fold(&config.log.log_path) {
+            Ok(()) => {}
+            Err(e) => {
+                panic!("{}", e);
+            }
+        }
+        config
+    })
+}
+
+pub fn init_broker_amqp_conf_by_config(config: BrokerAmqpConfig) -> &'static BrokerAmqpConfig {
+    // n.b. static items do not call [`Drop`] on program termination, so if
+    // [`DeepThought`] impls Drop, that will not be used for this instance.
+    BROKER_AMQP_CONF.get_or_init(|| config)
+}
+
+pub fn broker_amqp_conf() -> &'static BrokerAmqpConfig {
+    match BROKER_AMQP_CONF.get() {
+        Some(config) => config,
+        None => {
+            panic!("AMQP Broker configuration is not initialized, check the configuration file.");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BrokerAmqpConfig;
+    use crate::tools::read_file;
+
+    #[test]
+    fn config_default_test() {
+        let path = format!(
+            "{}/../../../config/amqp-server.toml",
+            env!("CARGO_MANIFEST_DIR")
+        );
+
+        let content = read_file(&path).unwrap();
+        let config: BrokerAmqpConfig = match toml::from_str(&content) {
+            Ok(da) => da,
+            Err(e) => {
+                panic!("{}", e)
+            }
+        };
+        assert_eq!(config.broker_id, 1);
+        assert_eq!(config.cluster_name, "amqp-broker".to_string());
+        assert_eq!(config.placement_center, vec!["127.0.0.1:1228".to_string()]);
+
+        assert_eq!(config.network.tcp_port, 5672);
+        assert_eq!(config.network.channel_max, 2047);
+        assert_eq!(config.network.frame_max, 131072);
+        assert_eq!(config.network.heartbeat, 60);
+
+        assert_eq!(config.system.runtime_worker_threads, 128);
+        assert_eq!(config.system.default_user, "admin".to_string());
+        assert_eq!(config.system.default_password, "pwd123".to_string());
+        assert_eq!(config.system.default_prefetch_count, 100);
+
+        assert_eq!(config.storage.storage_type, "memory".to_string());
+
+        assert_eq!(
+            config.plugins.exchange_types,
+            vec![
+                "headers".to_string(),
+                "x-consistent-hash".to_string(),
+                "x-delayed-message".to_string()
+            ]
+        );
+        assert_eq!(
+            config.plugins.interceptors,
+            vec!["message_timestamp".to_string()]
+        );
+        assert!(config.plugins.auth.is_none());
+
+        assert_eq!(
+            config.log.log_config,
+            "./config/log-config/amqp-log4rs.yaml"
+        );
+        assert_eq!(
+            config.log.log_path,
+            "./robust-data/amqp-broker/logs".to_string()
+        );
+    }
+}
@@ -34,10 +34,11 @@ use serde::{Deserialize, Serialize};
 
 use super::common::{Auth, Log, Storage};
 use super::default_mqtt::{
-    default_auth, default_grpc_port, default_http_port, default_log, default_network,
-    default_network_quic_port, default_network_tcp_port, default_network_tcps_port,
-    default_network_websocket_port, default_network_websockets_port, default_placement_center,
-    default_storage, default_system, default_tcp_thread,
+    default_auth, default_bridge_batch_size, default_grpc_port, default_http_port, default_log,
+    default_network, default_network_quic_port, default_network_tcp_port,
+    default_network_tcps_port, default_network_tls_client_auth, default_network_websocket_port,
+    default_network_websockets_port, default_placement_center, default_storage, default_system,
+    default_tcp_thread,
 };
 use crate::tools::{read_file, try_create_fold};
 
@@ -63,6 +64,8 @@ pub struct BrokerMqttConfig {
     pub auth: Auth,
     #[serde(default = "default_log")]
     pub log: Log,
+    #[serde(default)]
+    pub bridge: Bridge,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
@@ -81,6 +84,15 @@ pub struct Network {
     pub tls_cert: String,
     #[serde(default)]
     pub tls_key: String,
+    // CA bundle used to verify client certificates
+    #[serde(default)]
+    pub tls_ca: String,
+    // none, optional or required
+    #[serde(default = "default_network_tls_client_auth")]
+    pub tls_client_auth: String,
+    // CRL file used to reject revoked client certificates
+    #[serde(default)]
+    pub tls_crl: String,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
@@ -113,6 +125,34 @@ pub struct System {
     pub default_password: String,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Bridge {
+    #[serde(default)]
+    pub kafka: Vec<KafkaBridge>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct KafkaBridge {
+    pub name: String,
+    pub topic_filters: Vec<String>,
+    pub bootstrap_servers: Vec<String>,
+    pub kafka_topic: String,
+    #[serde(default = "default_bridge_batch_size")]
+    pub batch_size: u64,
+    // Producer settings left out fall back to the defaults of the Kafka bridge crate.
+    // client_id, topic or none
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(default)]
+    pub acks: Option<i16>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub max_retries: Option<u32>,
+    #[serde(default)]
+    pub retry_backoff_ms: Option<u64>,
+}
+
 static BROKER_MQTT_CONF: OnceLock<BrokerMqttConfig> = OnceLock::new();
 
 pub fn init_broker_mqtt_conf_by_path(config_path: &str) -> &'static BrokerMqttConfig {
@@ -218,6 +258,7 @@ mod tests {
         );
 
         assert_eq!(config.auth.storage_type, "placement".to_string());
+        assert_eq!(config.auth.password_hash, "pbkdf2".to_string());
         assert_eq!(config.auth.journal_addr, "".to_string());
         assert_eq!(config.auth.mysql_addr, "".to_string());
     }
@@ -275,4 +316,213 @@ mod tests {
         assert_eq!(config.auth.journal_addr, "".to_string());
         assert_eq!(config.auth.mysql_addr, "".to_string());
     }
+
+    #[test]
+    fn config_bridge_test() {
+        let content = r#"
+            cluster_name = "mqtt-broker"
+            broker_id = 1
+
+            [[bridge.kafka]]
+            name = "sensor-to-kafka"
+            topic_filters = ["sensor/+/data", "device/#"]
+            bootstrap_servers = ["127.0.0.1:9092"]
+            kafka_topic = "mqtt-sensor"
+            key = "topic"
+            batch_size = 500
+        "#;
+        let config: BrokerMqttConfig = toml::from_str(content).unwrap();
+        assert_eq!(config.bridge.kafka.len(), 1);
+
+        let kafka = config.bridge.kafka.first().unwrap();
+        assert_eq!(kafka.name, "sensor-to-kafka".to_string());
+        assert_eq!(kafka.topic_filters.len(), 2);
+        assert_eq!(kafka.bootstrap_servers, vec!["127.0.0.1:9092".to_string()]);
+        assert_eq!(kafka.kafka_topic, "mqtt-sensor".to_string());
+        assert_eq!(kafka.key, Some("topic".to_string()));
+        assert_eq!(kafka.batch_size, 500);
+        assert_eq!(kafka.acks, None);
+        assert_eq!(kafka.timeout_ms, None);
+        assert_eq!(kafka.max_retries, None);
+        assert_eq!(kafka.retry_backoff_ms, None);
+
+        let config: BrokerMqttConfig =
+            toml::from_str("cluster_name = \"c\"\nbroker_id = 1").unwrap();
+        assert!(config.bridge.kafka.is_empty());
+    }
+
+    #[test]
+    fn config_auth_jwt_test() {
+        let content = r#"
+            cluster_name = "mqtt-broker"
+            broker_id = 1
+
+            [auth]
+            storage_type = "placement"
+
+            [auth.jwt]
+            secret = "c2VjcmV0"
+            secret_base64_encoded = true
+            jwks_file = "./config/jwks.json"
+            client_id_claim = "clientid"
+        "#;
+        let config: BrokerMqttConfig = toml::from_str(content).unwrap();
+        let jwt = config.auth.jwt.unwrap();
+        assert_eq!(jwt.secret, "c2VjcmV0".to_string());
+        assert!(jwt.secret_base64_encoded);
+        assert_eq!(jwt.jwks_file, "./config/jwks.json".to_string());
+        assert_eq!(jwt.client_id_claim, "clientid".to_string());
+        assert!(jwt.username_claim.is_empty());
+        assert_eq!(jwt.acl_claim, "acl".to_string());
+        assert_eq!(jwt.leeway_sec, 0);
+
+        let config: BrokerMqttConfig =
+            toml::from_str("cluster_name = \"c\"\nbroker_id = 1").unwrap();
+        assert!(config.auth.jwt.is_none());
+    }
+
+    #[test]
+    fn config_auth_http_test() {
+        let content = r#"
+            cluster_name = "mqtt-broker"
+            broker_id = 1
+
+            [auth]
+            storage_type = "placement"
+
+            [auth.http]
+            url = "http://127.0.0.1:8080/mqtt/auth"
+            acl_url = "http://127.0.0.1:8080/mqtt/acl"
+            headers = { Authorization = "Bearer t1" }
+            timeout_policy = "ignore"
+        "#;
+        let config: BrokerMqttConfig = toml::from_str(content).unwrap();
+        let http = config.auth.http.unwrap();
+        assert_eq!(http.url, "http://127.0.0.1:8080/mqtt/auth".to_string());
+        assert_eq!(http.acl_url, "http://127.0.0.1:8080/mqtt/acl".to_string());
+        assert_eq!(http.headers["Authorization"], "Bearer t1".to_string());
+        assert_eq!(http.timeout_ms, 5000);
+        assert_eq!(http.cache_ttl_sec, 60);
+        assert_eq!(http.timeout_policy, "ignore".to_string());
+        assert!(config.auth.jwt.is_none());
+    }
+
+    #[test]
+    fn config_tls_client_auth_test() {
+        let content = r#"
+            cluster_name = "mqtt-broker"
+            broker_id = 1
+
+            [network]
+            tls_cert = "./config/example/certs/cert.pem"
+            tls_key = "./config/example/certs/key.pem"
+            tls_ca = "./config/example/certs/ca.pem"
+            tls_client_auth = "required"
+            tls_crl = "./config/example/certs/crl.pem"
+
+            [auth]
+            storage_type = "placement"
+
+            [auth.x509]
+            client_id_field = "san_dns"
+        "#;
+        let config: BrokerMqttConfig = toml::from_str(content).unwrap();
+        assert_eq!(config.network.tcp_port, 1883);
+        assert_eq!(config.network.tls_ca, "./config/example/certs/ca.pem");
+        assert_eq!(config.network.tls_client_auth, "required");
+        assert_eq!(config.network.tls_crl, "./config/example/certs/crl.pem");
+        let x509 = config.auth.x509.unwrap();
+        assert_eq!(x509.username_field, "cn");
+        assert_eq!(x509.client_id_field, "san_dns");
+
+        let content = r#"
+            cluster_name = "mqtt-broker"
+            broker_id = 1
+        "#;
+        let config: BrokerMqttConfig = toml::from_str(content).unwrap();
+        assert_eq!(config.network.tls_client_auth, "none");
+        assert!(config.network.tls_ca.is_empty());
+        assert!(config.auth.x509.is_none());
+        assert!(config.auth.psk.is_none());
+        assert!(config.auth.redis.is_none());
+    }
+
+    #[test]
+    fn config_auth_psk_test() {
+        let content = r#"
+            cluster_name = "mqtt-broker"
+            broker_id = 1
+
+            [auth]
+            storage_type = "placement"
+
+            [auth.psk]
+            file = "./config/psk.txt"
+            placement = true
+        "#;
+        let config: BrokerMqttConfig = toml::from_str(content).unwrap();
+        let psk = config.auth.psk.unwrap();
+        assert_eq!(psk.file, "./config/psk.txt");
+        assert!(psk.placement);
+    }
+
+    #[test]
+    fn config_auth_redis_test() {
+        let content = r#"
+            cluster_name = "mqtt-broker"
+            broker_id = 1
+
+            [auth]
+            storage_type = "redis"
+
+            [auth.redis]
+            addr = "127.0.0.1:6379"
+            password = "pwd123"
+            user_key = "device:${username}:auth"
+        "#;
+        let config: BrokerMqttConfig = toml::from_str(content).unwrap();
+        assert_eq!(config.auth.storage_type, "redis");
+        let redis = config.auth.redis.unwrap();
+        assert_eq!(redis.addr, "127.0.0.1:6379");
+        assert_eq!(redis.password, "pwd123");
+        assert_eq!(redis.db, 0);
+        assert_eq!(redis.timeout_ms, 3000);
+        assert_eq!(redis.user_key, "device:${username}:auth");
+        assert_eq!(redis.password_field, "password");
+        assert_eq!(redis.superuser_field, "is_superuser");
+        assert_eq!(redis.acl_key, "mqtt_acl");
+        assert_eq!(redis.blacklist_key, "mqtt_blacklist");
+    }
+
+    #[test]
+    fn config_storage_placement_test() {
+        let content = r#"
+            cluster_name = "mqtt-broker"
+            broker_id = 1
+
+            [storage]
+            storage_type = "placement"
+        "#;
+        let config: BrokerMqttConfig = toml::from_str(content).unwrap();
+        assert_eq!(config.storage.storage_type, "placement");
+        assert_eq!(config.storage.placement.max_record_size, 64 * 1024);
+        assert_eq!(config.storage.placement.max_shard_records, 10000);
+        assert_eq!(config.storage.placement.retention_sec, 86400);
+
+        let content = r#"
+            cluster_name = "mqtt-broker"
+            broker_id = 1
+
+            [storage]
+            storage_type = "placement"
+
+            [storage.placement]
+            max_record_size = 1024
+            retention_sec = 0
+        "#;
+        let config: BrokerMqttConfig = toml::from_str(content).unwrap();
+        assert_eq!(config.storage.placement.max_record_size, 1024);
+        assert_eq!(config.storage.placement.max_shard_records, 10000);
+        assert_eq!(config.storage.placement.retention_sec, 0);
+    }
 }
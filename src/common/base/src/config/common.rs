@@ -12,8 +12,20 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
+use super::default_mqtt::{
+    default_http_auth_cache_ttl_sec, default_http_auth_timeout_ms,
+    default_http_auth_timeout_policy, default_jwt_acl_claim, default_password_hash,
+    default_placement_storage, default_placement_storage_max_record_size,
+    default_placement_storage_max_shard_records, default_placement_storage_retention_sec,
+    default_redis_auth_acl_key, default_redis_auth_blacklist_key,
+    default_redis_auth_password_field, default_redis_auth_superuser_field,
+    default_redis_auth_timeout_ms, default_redis_auth_user_key, default_x509_username_field,
+};
+
 #[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
 pub struct Storage {
     pub storage_type: String,
@@ -24,6 +36,22 @@ pub struct Storage {
     #[serde(default)]
     pub rocksdb_data_path: String,
     pub rocksdb_max_open_files: Option<i32>,
+    #[serde(default = "default_placement_storage")]
+    pub placement: PlacementStorage,
+}
+
+// Limits for message data kept in the placement center, every write goes through its Raft log.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct PlacementStorage {
+    // Maximum encoded size of a single record in bytes
+    #[serde(default = "default_placement_storage_max_record_size")]
+    pub max_record_size: u64,
+    // Records kept per shard, the oldest ones are removed first
+    #[serde(default = "default_placement_storage_max_shard_records")]
+    pub max_shard_records: u64,
+    // Records older than this are removed, 0 keeps them until max_shard_records is reached
+    #[serde(default = "default_placement_storage_retention_sec")]
+    pub retention_sec: u64,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
@@ -33,6 +61,91 @@ pub struct Auth {
     pub journal_addr: String,
     #[serde(default)]
     pub mysql_addr: String,
+    #[serde(default)]
+    pub jwt: Option<JwtAuth>,
+    #[serde(default)]
+    pub http: Option<HttpAuth>,
+    #[serde(default)]
+    pub x509: Option<X509Auth>,
+    #[serde(default)]
+    pub psk: Option<PskAuth>,
+    #[serde(default)]
+    pub redis: Option<RedisAuth>,
+    // pbkdf2, bcrypt, sha256 or plain, the algorithm used to store new passwords.
+    // SCRAM-SHA-256 only works for users stored with pbkdf2 or plain.
+    #[serde(default = "default_password_hash")]
+    pub password_hash: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct JwtAuth {
+    // HS256 secret
+    #[serde(default)]
+    pub secret: String,
+    #[serde(default)]
+    pub secret_base64_encoded: bool,
+    // JWKS file with RS256, ES256 or HS256 keys
+    #[serde(default)]
+    pub jwks_file: String,
+    // Claim that must equal the client id, not checked when empty
+    #[serde(default)]
+    pub client_id_claim: String,
+    // Claim that must equal the username, not checked when empty
+    #[serde(default)]
+    pub username_claim: String,
+    // Claim carrying the ACL rules of the client
+    #[serde(default = "default_jwt_acl_claim")]
+    pub acl_claim: String,
+    // Tolerated clock skew when checking exp and nbf
+    #[serde(default)]
+    pub leeway_sec: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct X509Auth {
+    // cn, san_dns, san_email or san_uri, the certificate field used as the username
+    #[serde(default = "default_x509_username_field")]
+    pub username_field: String,
+    // Certificate field that replaces the client id, the client id is kept when empty
+    #[serde(default)]
+    pub client_id_field: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct PskAuth {
+    // File with one "identity:hex key" entry per line
+    #[serde(default)]
+    pub file: String,
+    // Also read the keys stored in the placement center
+    #[serde(default)]
+    pub placement: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct RedisAuth {
+    // host:port
+    pub addr: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default)]
+    pub db: u32,
+    #[serde(default = "default_redis_auth_timeout_ms")]
+    pub timeout_ms: u64,
+    // Hash holding one user, `${username}` is replaced by the username
+    #[serde(default = "default_redis_auth_user_key")]
+    pub user_key: String,
+    #[serde(default = "default_redis_auth_password_field")]
+    pub password_field: String,
+    #[serde(default = "default_redis_auth_superuser_field")]
+    pub superuser_field: String,
+    // Set of JSON encoded ACL rules
+    #[serde(default = "default_redis_auth_acl_key")]
+    pub acl_key: String,
+    // Set of JSON encoded blacklist entries
+    #[serde(default = "default_redis_auth_blacklist_key")]
+    pub blacklist_key: String,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
@@ -40,3 +153,25 @@ pub struct Log {
     pub log_config: String,
     pub log_path: String,
 }
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct HttpAuth {
+    // Endpoint deciding whether a client may connect
+    pub url: String,
+    // Endpoint deciding whether a client may publish or subscribe, not called when empty
+    #[serde(default)]
+    pub acl_url: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default = "default_http_auth_timeout_ms")]
+    pub timeout_ms: u64,
+    // How long allow and deny results are cached, 0 disables the cache
+    #[serde(default = "default_http_auth_cache_ttl_sec")]
+    pub cache_ttl_sec: u64,
+    // deny or ignore, the result when the endpoint fails or does not answer in time
+    #[serde(default = "default_http_auth_timeout_policy")]
+    pub timeout_policy: String,
+    // CA bundle (PEM) trusted for https endpoints besides the system roots
+    #[serde(default)]
+    pub tls_ca: String,
+}
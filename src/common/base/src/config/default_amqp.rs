@@ -0,0 +1,77 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::broker_amqp::{Network, System};
+use super::common::{Log, Storage};
+use super::default_mqtt::default_placement_storage;
+
+pub fn default_placement_center() -> Vec<String> {
+    vec!["127.0.0.1:1228".to_string()]
+}
+
+pub fn default_network() -> Network {
+    Network {
+        tcp_port: default_network_tcp_port(),
+        channel_max: default_channel_max(),
+        frame_max: default_frame_max(),
+        heartbeat: default_heartbeat(),
+    }
+}
+
+pub fn default_network_tcp_port() -> u32 {
+    5672
+}
+
+pub fn default_channel_max() -> u16 {
+    2047
+}
+
+pub fn default_frame_max() -> u32 {
+    131072
+}
+
+pub fn default_heartbeat() -> u16 {
+    60
+}
+
+pub fn default_system() -> System {
+    System {
+        runtime_worker_threads: 16,
+        default_user: "admin".to_string(),
+        default_password: "pwd123".to_string(),
+        default_prefetch_count: default_prefetch_count(),
+    }
+}
+
+pub fn default_prefetch_count() -> u16 {
+    100
+}
+
+pub fn default_storage() -> Storage {
+    Storage {
+        storage_type: "memory".to_string(),
+        journal_addr: "".to_string(),
+        mysql_addr: "".to_string(),
+        rocksdb_data_path: "".to_string(),
+        rocksdb_max_open_files: None,
+        placement: default_placement_storage(),
+    }
+}
+
+pub fn default_log() -> Log {
+    Log {
+        log_path: "./logs".to_string(),
+        log_config: "./config/log4rs.yaml".to_string(),
+    }
+}
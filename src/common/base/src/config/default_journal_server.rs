@@ -20,6 +20,7 @@ pub fn default_network() -> Network {
         grpc_port: default_grpc_port(),
         tcp_port: default_network_tcp_port(),
         tcps_port: default_network_tcps_port(),
+        quic_port: default_network_quic_port(),
         tls_cert: "".to_string(),
         tls_key: "".to_string(),
     }
@@ -34,6 +35,9 @@ pub fn default_network_tcp_port() -> u32 {
 pub fn default_network_tcps_port() -> u32 {
     3111
 }
+pub fn default_network_quic_port() -> u32 {
+    3112
+}
 
 pub fn default_prometheus_port() -> u32 {
     9090
@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use super::broker_mqtt::{Network, System, TcpThread};
-use super::common::{Auth, Log, Storage};
+use super::common::{Auth, Log, PlacementStorage, Storage};
 
 pub fn default_grpc_port() -> u32 {
     9981
@@ -36,8 +36,14 @@ pub fn default_network() -> Network {
         quic_port: default_network_quic_port(),
         tls_cert: "".to_string(),
         tls_key: "".to_string(),
+        tls_ca: "".to_string(),
+        tls_client_auth: default_network_tls_client_auth(),
+        tls_crl: "".to_string(),
     }
 }
+pub fn default_network_tls_client_auth() -> String {
+    "none".to_string()
+}
 pub fn default_network_tcp_port() -> u32 {
     1883
 }
@@ -82,9 +88,30 @@ pub fn default_storage() -> Storage {
         mysql_addr: "".to_string(),
         rocksdb_data_path: "".to_string(),
         rocksdb_max_open_files: None,
+        placement: default_placement_storage(),
     }
 }
 
+pub fn default_placement_storage() -> PlacementStorage {
+    PlacementStorage {
+        max_record_size: default_placement_storage_max_record_size(),
+        max_shard_records: default_placement_storage_max_shard_records(),
+        retention_sec: default_placement_storage_retention_sec(),
+    }
+}
+
+pub fn default_placement_storage_max_record_size() -> u64 {
+    64 * 1024
+}
+
+pub fn default_placement_storage_max_shard_records() -> u64 {
+    10000
+}
+
+pub fn default_placement_storage_retention_sec() -> u64 {
+    24 * 3600
+}
+
 pub fn default_log() -> Log {
     Log {
         log_path: "./logs".to_string(),
@@ -97,5 +124,64 @@ pub fn default_auth() -> Auth {
         storage_type: "memory".to_string(),
         journal_addr: "".to_string(),
         mysql_addr: "".to_string(),
+        jwt: None,
+        http: None,
+        x509: None,
+        psk: None,
+        redis: None,
+        password_hash: default_password_hash(),
     }
 }
+
+// pbkdf2 records can also be used by SCRAM-SHA-256 enhanced authentication
+pub fn default_password_hash() -> String {
+    "pbkdf2".to_string()
+}
+
+pub fn default_redis_auth_timeout_ms() -> u64 {
+    3000
+}
+
+pub fn default_redis_auth_user_key() -> String {
+    "mqtt_user:${username}".to_string()
+}
+
+pub fn default_redis_auth_password_field() -> String {
+    "password".to_string()
+}
+
+pub fn default_redis_auth_superuser_field() -> String {
+    "is_superuser".to_string()
+}
+
+pub fn default_redis_auth_acl_key() -> String {
+    "mqtt_acl".to_string()
+}
+
+pub fn default_redis_auth_blacklist_key() -> String {
+    "mqtt_blacklist".to_string()
+}
+
+pub fn default_jwt_acl_claim() -> String {
+    "acl".to_string()
+}
+
+pub fn default_http_auth_timeout_ms() -> u64 {
+    5000
+}
+
+pub fn default_http_auth_cache_ttl_sec() -> u64 {
+    60
+}
+
+pub fn default_http_auth_timeout_policy() -> String {
+    "deny".to_string()
+}
+
+pub fn default_x509_username_field() -> String {
+    "cn".to_string()
+}
+
+pub fn default_bridge_batch_size() -> u64 {
+    100
+}
@@ -0,0 +1,87 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::common::Log;
+use super::mqtt_edge::{Buffer, Network, Uplink};
+
+pub fn default_runtime_worker_threads() -> usize {
+    4
+}
+
+pub fn default_network() -> Network {
+    Network {
+        tcp_port: default_network_tcp_port(),
+    }
+}
+
+pub fn default_network_tcp_port() -> u32 {
+    1883
+}
+
+pub fn default_uplink() -> Uplink {
+    Uplink {
+        addr: "127.0.0.1:1883".to_string(),
+        client_id: "".to_string(),
+        username: "".to_string(),
+        password: "".to_string(),
+        keep_alive: default_uplink_keep_alive(),
+        session_expiry_interval: default_uplink_session_expiry_interval(),
+        reconnect_interval_ms: default_uplink_reconnect_interval_ms(),
+        max_inflight: default_uplink_max_inflight(),
+    }
+}
+
+pub fn default_uplink_keep_alive() -> u16 {
+    30
+}
+
+pub fn default_uplink_session_expiry_interval() -> u32 {
+    3600
+}
+
+pub fn default_uplink_reconnect_interval_ms() -> u64 {
+    3000
+}
+
+pub fn default_uplink_max_inflight() -> usize {
+    32
+}
+
+pub fn default_buffer() -> Buffer {
+    Buffer {
+        data_path: "./robust-data/mqtt-edge/buffer".to_string(),
+        rocksdb_max_open_files: default_buffer_rocksdb_max_open_files(),
+        max_messages: default_buffer_max_messages(),
+        overflow_policy: default_buffer_overflow_policy(),
+    }
+}
+
+pub fn default_buffer_rocksdb_max_open_files() -> i32 {
+    1000
+}
+
+pub fn default_buffer_max_messages() -> u64 {
+    100000
+}
+
+pub fn default_buffer_overflow_policy() -> String {
+    "drop_oldest".to_string()
+}
+
+pub fn default_log() -> Log {
+    Log {
+        log_path: "./logs".to_string(),
+        log_config: "./config/log4rs.yaml".to_string(),
+    }
+}
@@ -34,9 +34,9 @@ use serde::Deserialize;
 
 use super::common::Log;
 use super::default_journal_server::{
-    default_grpc_port, default_log, default_network, default_network_tcp_port,
-    default_network_tcps_port, default_prometheus, default_prometheus_port, default_storage,
-    default_system, default_tcp_thread,
+    default_grpc_port, default_log, default_network, default_network_quic_port,
+    default_network_tcp_port, default_network_tcps_port, default_prometheus,
+    default_prometheus_port, default_storage, default_system, default_tcp_thread,
 };
 use crate::tools::{read_file, try_create_fold};
 
@@ -68,6 +68,8 @@ pub struct Network {
     pub tcp_port: u32,
     #[serde(default = "default_network_tcps_port")]
     pub tcps_port: u32,
+    #[serde(default = "default_network_quic_port")]
+    pub quic_port: u32,
     #[serde(default)]
     pub tls_cert: String,
     #[serde(default)]
@@ -188,6 +190,7 @@ mod tests {
         assert_eq!(conf.network.grpc_port, 2228);
         assert_eq!(conf.network.tcp_port, 3110);
         assert_eq!(conf.network.tcps_port, 3111);
+        assert_eq!(conf.network.quic_port, 3112);
 
         assert_eq!(conf.system.runtime_work_threads, 100);
 
@@ -12,17 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod broker_amqp;
 pub mod broker_mqtt;
 pub mod common;
+pub mod default_amqp;
 pub mod default_journal_server;
 pub mod default_mqtt;
+pub mod default_mqtt_edge;
 pub mod default_placement_center;
 pub mod journal_server;
+pub mod mqtt_edge;
 pub mod placement_center;
 
 pub const DEFAULT_MQTT_SERVER_CONFIG: &str = "config/mqtt-server.toml";
+pub const DEFAULT_AMQP_SERVER_CONFIG: &str = "config/amqp-server.toml";
 pub const DEFAULT_PLACEMENT_CENTER_CONFIG: &str = "config/placement-center.toml";
 pub const DEFAULT_JOURNAL_SERVER_CONFIG: &str = "config/journal-server.toml";
+pub const DEFAULT_MQTT_EDGE_CONFIG: &str = "config/mqtt-edge.toml";
 
 #[cfg(test)]
 mod tests {
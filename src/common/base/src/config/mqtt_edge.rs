@@ -0,0 +1,213 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use super::common::Log;
+use super::default_mqtt_edge::{
+    default_buffer, default_buffer_max_messages, default_buffer_overflow_policy,
+    default_buffer_rocksdb_max_open_files, default_log, default_network, default_network_tcp_port,
+    default_runtime_worker_threads, default_uplink, default_uplink_keep_alive,
+    default_uplink_max_inflight, default_uplink_reconnect_interval_ms,
+    default_uplink_session_expiry_interval,
+};
+use crate::tools::{read_file, try_create_fold};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct MqttEdgeConfig {
+    pub edge_id: String,
+    #[serde(default = "default_runtime_worker_threads")]
+    pub runtime_worker_threads: usize,
+    #[serde(default = "default_network")]
+    pub network: Network,
+    #[serde(default = "default_uplink")]
+    pub uplink: Uplink,
+    #[serde(default = "default_buffer")]
+    pub buffer: Buffer,
+    #[serde(default)]
+    pub mappings: Vec<TopicMapping>,
+    #[serde(default = "default_log")]
+    pub log: Log,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Network {
+    #[serde(default = "default_network_tcp_port")]
+    pub tcp_port: u32,
+}
+
+// Connection to the central RobustMQ cluster, always MQTT 5
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Uplink {
+    pub addr: String,
+    // Defaults to the edge_id
+    #[serde(default)]
+    pub client_id: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default = "default_uplink_keep_alive")]
+    pub keep_alive: u16,
+    // The cluster keeps the downlink subscriptions and queued messages this long while offline
+    #[serde(default = "default_uplink_session_expiry_interval")]
+    pub session_expiry_interval: u32,
+    #[serde(default = "default_uplink_reconnect_interval_ms")]
+    pub reconnect_interval_ms: u64,
+    // Buffered messages published upstream before waiting for their acks
+    #[serde(default = "default_uplink_max_inflight")]
+    pub max_inflight: usize,
+}
+
+// Local store for messages waiting to go upstream
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Buffer {
+    pub data_path: String,
+    #[serde(default = "default_buffer_rocksdb_max_open_files")]
+    pub rocksdb_max_open_files: i32,
+    #[serde(default = "default_buffer_max_messages")]
+    pub max_messages: u64,
+    // drop_oldest or drop_newest, what happens once max_messages is reached
+    #[serde(default = "default_buffer_overflow_policy")]
+    pub overflow_policy: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MappingDirection {
+    #[default]
+    Uplink,
+    Downlink,
+    Both,
+}
+
+// Topics under `local_prefix` + `filter` on the edge are `remote_prefix` + `filter` upstream
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct TopicMapping {
+    #[serde(default)]
+    pub direction: MappingDirection,
+    #[serde(default)]
+    pub local_prefix: String,
+    #[serde(default)]
+    pub remote_prefix: String,
+    pub filter: String,
+    // QoS used towards the cluster, 2 is served as 1
+    #[serde(default)]
+    pub qos: u8,
+}
+
+static MQTT_EDGE_CONF: OnceLock<MqttEdgeConfig> = OnceLock::new();
+
+pub fn init_mqtt_edge_conf_by_path(config_path: &str) -> &'static MqttEdgeConfig {
+    // n.b. static items do not call [`Drop`] on program termination, so if
+    // [`DeepThought`] impls Drop, that will not be used for this instance.
+    MQTT_EDGE_CONF.get_or_init(|| {
+        let content = match read_file(config_path) {
+            Ok(data) => data,
+            Err(e) => {
+                panic!("{}", e.to_string())
+            }
+        };
+        let config: MqttEdgeConfig = match toml::from_str(&content) {
+            Ok(da) => da,
+            Err(e) => {
+                panic!("{}", e)
+            }
+        };
+        match try_create_fold(&config.log.log_path) {
+            Ok(()) => {}
+            Err(e) => {
+                panic!("{}", e);
+            }
+        }
+        match try_create_fold(&config.buffer.data_path) {
+            Ok(()) => {}
+            Err(e) => {
+                panic!("{}", e);
+            }
+        }
+        config
+    })
+}
+
+pub fn init_mqtt_edge_conf_by_config(config: MqttEdgeConfig) -> &'static MqttEdgeConfig {
+    // n.b. static items do not call [`Drop`] on program termination, so if
+    // [`DeepThought`] impls Drop, that will not be used for this instance.
+    MQTT_EDGE_CONF.get_or_init(|| config)
+}
+
+pub fn mqtt_edge_conf() -> &'static MqttEdgeConfig {
+    match MQTT_EDGE_CONF.get() {
+        Some(config) => config,
+        None => {
+            panic!("MQTT Edge configuration is not initialized, check the configuration file.");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MappingDirection, MqttEdgeConfig};
+    use crate::tools::read_file;
+
+    #[test]
+    fn config_default_test() {
+        let path = format!(
+            "{}/../../../config/mqtt-edge.toml",
+            env!("CARGO_MANIFEST_DIR")
+        );
+
+        let content = read_file(&path).unwrap();
+        let config: MqttEdgeConfig = match toml::from_str(&content) {
+            Ok(da) => da,
+            Err(e) => {
+                panic!("{}", e)
+            }
+        };
+        assert_eq!(config.edge_id, "edge-1".to_string());
+        assert_eq!(config.runtime_worker_threads, 4);
+        assert_eq!(config.network.tcp_port, 1885);
+
+        assert_eq!(config.uplink.addr, "127.0.0.1:1883".to_string());
+        assert_eq!(config.uplink.keep_alive, 30);
+        assert_eq!(config.uplink.session_expiry_interval, 3600);
+        assert_eq!(config.uplink.reconnect_interval_ms, 3000);
+        assert_eq!(config.uplink.max_inflight, 32);
+
+        assert_eq!(
+            config.buffer.data_path,
+            "./robust-data/mqtt-edge/buffer".to_string()
+        );
+        assert_eq!(config.buffer.max_messages, 100000);
+        assert_eq!(config.buffer.overflow_policy, "drop_oldest".to_string());
+
+        assert_eq!(config.mappings.len(), 2);
+        assert_eq!(config.mappings[0].direction, MappingDirection::Uplink);
+        assert_eq!(config.mappings[0].local_prefix, "sensors/".to_string());
+        assert_eq!(
+            config.mappings[0].remote_prefix,
+            "factory-1/sensors/".to_string()
+        );
+        assert_eq!(config.mappings[0].filter, "#".to_string());
+        assert_eq!(config.mappings[0].qos, 1);
+        assert_eq!(config.mappings[1].direction, MappingDirection::Downlink);
+
+        assert_eq!(
+            config.log.log_path,
+            "./robust-data/mqtt-edge/logs".to_string()
+        );
+    }
+}
@@ -94,6 +94,9 @@ pub enum CommonError {
 
     #[error("RocksDB Family {0} not available")]
     RocksDBFamilyNotAvailable(String),
+
+    #[error("Shard {1} in namespace {0} does not exist")]
+    ShardNotExist(String, String),
 }
 
 impl From<CommonError> for Status {
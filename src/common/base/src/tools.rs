@@ -134,6 +134,17 @@ pub fn read_file(path: &str) -> Result<String, CommonError> {
     Ok(fs::read_to_string(path)?)
 }
 
+/// Compare two byte slices in constant time
+///
+/// The time taken only depends on the length of the slices, not on where they differ,
+/// so it is safe to use for secrets such as passwords.
+///
+/// # Return value
+/// Returns true when both slices are equal
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
 /// Serialize a DashMap object into a JSON string
 /// # parameters
 /// * ` value ` - a reference to a DashMap object that implements the Serialize trait
@@ -153,7 +164,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::tools::{get_local_ip, unique_id};
+    use crate::tools::{constant_time_eq, get_local_ip, unique_id};
 
     #[test]
     fn get_local_ip_test() {
@@ -164,4 +175,12 @@ mod tests {
     fn unique_id_test() {
         println!("{}", unique_id());
     }
+
+    #[test]
+    fn constant_time_eq_test() {
+        assert!(constant_time_eq(b"robustmq", b"robustmq"));
+        assert!(!constant_time_eq(b"robustmq", b"robustmQ"));
+        assert!(!constant_time_eq(b"robustmq", b"robust"));
+        assert!(constant_time_eq(b"", b""));
+    }
 }
@@ -13,5 +13,6 @@
 // limitations under the License.
 
 pub mod file_utils;
+pub mod topic_trie;
 pub mod topic_util;
 pub mod vec_util;
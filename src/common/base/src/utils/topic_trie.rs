@@ -0,0 +1,378 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+const MULTI_LEVEL_WILDCARD: &str = "#";
+const SINGLE_LEVEL_WILDCARD: &str = "+";
+
+// A trie keyed by the levels of a topic. It either stores topic filters and is
+// queried with topic names (publish routing), or stores topic names and is
+// queried with topic filters (subscribe resolution).
+//
+// Topics whose first level starts with `$`, such as `$SYS/...`, are not matched
+// by a filter starting with a wildcard.
+#[derive(Clone, Debug)]
+pub struct TopicTrie<V> {
+    root: TrieNode<V>,
+    len: usize,
+}
+
+#[derive(Clone, Debug)]
+struct TrieNode<V> {
+    children: HashMap<String, TrieNode<V>>,
+    values: HashSet<V>,
+}
+
+impl<V> Default for TrieNode<V> {
+    fn default() -> Self {
+        TrieNode {
+            children: HashMap::new(),
+            values: HashSet::new(),
+        }
+    }
+}
+
+impl<V> TrieNode<V> {
+    fn is_empty(&self) -> bool {
+        self.children.is_empty() && self.values.is_empty()
+    }
+}
+
+impl<V> Default for TopicTrie<V> {
+    fn default() -> Self {
+        TopicTrie {
+            root: TrieNode::default(),
+            len: 0,
+        }
+    }
+}
+
+impl<V> TopicTrie<V>
+where
+    V: Clone + Eq + Hash,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Number of (path, value) pairs
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, path: &str, value: V) -> bool {
+        let mut node = &mut self.root;
+        for level in path.split('/') {
+            node = node.children.entry(level.to_string()).or_default();
+        }
+        let inserted = node.values.insert(value);
+        if inserted {
+            self.len += 1;
+        }
+        inserted
+    }
+
+    pub fn remove(&mut self, path: &str, value: &V) -> bool {
+        let levels: Vec<&str> = path.split('/').collect();
+        let removed = remove_value(&mut self.root, &levels, value);
+        if removed {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    pub fn contains(&self, path: &str, value: &V) -> bool {
+        let mut node = &self.root;
+        for level in path.split('/') {
+            match node.children.get(level) {
+                Some(child) => node = child,
+                None => return false,
+            }
+        }
+        node.values.contains(value)
+    }
+
+    // Values of the stored filters matching the topic name.
+    pub fn match_topic(&self, topic_name: &str) -> Vec<V> {
+        let levels: Vec<&str> = topic_name.split('/').collect();
+        let mut result = HashSet::new();
+        collect_filters(&self.root, &levels, 0, &mut result);
+        result.into_iter().collect()
+    }
+
+    // Values of the stored topic names matched by the filter.
+    pub fn match_filter(&self, filter: &str) -> Vec<V> {
+        let levels: Vec<&str> = filter.split('/').collect();
+        let mut result = HashSet::new();
+        collect_topics(&self.root, &levels, 0, &mut result);
+        result.into_iter().collect()
+    }
+}
+
+fn is_system_level(index: usize, level: &str) -> bool {
+    index == 0 && level.starts_with('$')
+}
+
+fn remove_value<V: Eq + Hash>(node: &mut TrieNode<V>, levels: &[&str], value: &V) -> bool {
+    let Some((level, rest)) = levels.split_first() else {
+        return node.values.remove(value);
+    };
+    let Some(child) = node.children.get_mut(*level) else {
+        return false;
+    };
+    let removed = remove_value(child, rest, value);
+    if removed && child.is_empty() {
+        node.children.remove(*level);
+    }
+    removed
+}
+
+fn collect_filters<V: Clone + Eq + Hash>(
+    node: &TrieNode<V>,
+    levels: &[&str],
+    index: usize,
+    result: &mut HashSet<V>,
+) {
+    if index == levels.len() {
+        result.extend(node.values.iter().cloned());
+        // `a/#` also matches `a`
+        if let Some(child) = node.children.get(MULTI_LEVEL_WILDCARD) {
+            result.extend(child.values.iter().cloned());
+        }
+        return;
+    }
+
+    let level = levels[index];
+    if !is_system_level(index, level) {
+        if let Some(child) = node.children.get(MULTI_LEVEL_WILDCARD) {
+            result.extend(child.values.iter().cloned());
+        }
+        if let Some(child) = node.children.get(SINGLE_LEVEL_WILDCARD) {
+            collect_filters(child, levels, index + 1, result);
+        }
+    }
+    if level != SINGLE_LEVEL_WILDCARD && level != MULTI_LEVEL_WILDCARD {
+        if let Some(child) = node.children.get(level) {
+            collect_filters(child, levels, index + 1, result);
+        }
+    }
+}
+
+fn collect_topics<V: Clone + Eq + Hash>(
+    node: &TrieNode<V>,
+    levels: &[&str],
+    index: usize,
+    result: &mut HashSet<V>,
+) {
+    if index == levels.len() {
+        result.extend(node.values.iter().cloned());
+        return;
+    }
+
+    match levels[index] {
+        MULTI_LEVEL_WILDCARD => {
+            result.extend(node.values.iter().cloned());
+            for (level, child) in node.children.iter() {
+                if !is_system_level(index, level) {
+                    collect_all(child, result);
+                }
+            }
+        }
+        SINGLE_LEVEL_WILDCARD => {
+            for (level, child) in node.children.iter() {
+                if !is_system_level(index, level) {
+                    collect_topics(child, levels, index + 1, result);
+                }
+            }
+        }
+        level => {
+            if let Some(child) = node.children.get(level) {
+                collect_topics(child, levels, index + 1, result);
+            }
+        }
+    }
+}
+
+fn collect_all<V: Clone + Eq + Hash>(node: &TrieNode<V>, result: &mut HashSet<V>) {
+    result.extend(node.values.iter().cloned());
+    for child in node.children.values() {
+        collect_all(child, result);
+    }
+}
+
+// Whether the topic name matches the topic filter, level by level.
+pub fn topic_match(topic_name: &str, filter: &str) -> bool {
+    let mut topic_levels = topic_name.split('/');
+    let mut filter_levels = filter.split('/');
+    let mut index = 0;
+    loop {
+        match (filter_levels.next(), topic_levels.next()) {
+            (Some(MULTI_LEVEL_WILDCARD), level) => {
+                return !level.is_some_and(|level| is_system_level(index, level));
+            }
+            (Some(SINGLE_LEVEL_WILDCARD), Some(level)) => {
+                if is_system_level(index, level) {
+                    return false;
+                }
+            }
+            (Some(filter_level), Some(level)) => {
+                if filter_level != level {
+                    return false;
+                }
+            }
+            (None, None) => return true,
+            _ => return false,
+        }
+        index += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{topic_match, TopicTrie};
+
+    fn sorted(mut values: Vec<&'static str>) -> Vec<&'static str> {
+        values.sort();
+        values
+    }
+
+    #[test]
+    fn match_topic_test() {
+        let mut trie = TopicTrie::new();
+        for filter in [
+            "/sensor/1/temperature",
+            "/sensor/+/temperature",
+            "/sensor/#",
+            "/sensor/+",
+            "#",
+            "+/+/+",
+            "$SYS/#",
+            "/+/1/#",
+        ] {
+            trie.insert(filter, filter);
+        }
+        assert_eq!(trie.len(), 8);
+
+        assert_eq!(
+            sorted(trie.match_topic("/sensor/1/temperature")),
+            vec![
+                "#",
+                "/+/1/#",
+                "/sensor/#",
+                "/sensor/+/temperature",
+                "/sensor/1/temperature",
+            ]
+        );
+        assert_eq!(
+            sorted(trie.match_topic("/sensor/2")),
+            vec!["#", "+/+/+", "/sensor/#", "/sensor/+"]
+        );
+        // `#` also matches the parent level
+        assert_eq!(sorted(trie.match_topic("/sensor")), vec!["#", "/sensor/#"]);
+        assert_eq!(sorted(trie.match_topic("/other/2/x/y")), vec!["#"]);
+        // wildcards at the first level do not match `$SYS` topics
+        assert_eq!(trie.match_topic("$SYS/brokers/uptime"), vec!["$SYS/#"]);
+        assert_eq!(trie.match_topic("$SYS/a"), vec!["$SYS/#"]);
+    }
+
+    #[test]
+    fn match_filter_test() {
+        let mut trie = TopicTrie::new();
+        for topic in [
+            "/sensor/1/temperature",
+            "/sensor/2/temperature",
+            "/sensor/2/humidity",
+            "/sensor",
+            "/device/1",
+            "$SYS/brokers/uptime",
+        ] {
+            trie.insert(topic, topic);
+        }
+
+        assert_eq!(
+            sorted(trie.match_filter("/sensor/+/temperature")),
+            vec!["/sensor/1/temperature", "/sensor/2/temperature"]
+        );
+        assert_eq!(
+            sorted(trie.match_filter("/sensor/#")),
+            vec![
+                "/sensor",
+                "/sensor/1/temperature",
+                "/sensor/2/humidity",
+                "/sensor/2/temperature",
+            ]
+        );
+        assert_eq!(trie.match_filter("/device/1"), vec!["/device/1"]);
+        assert!(trie.match_filter("/device/1/x").is_empty());
+        assert!(trie.match_filter("/+/+/+/+").is_empty());
+        assert_eq!(trie.match_filter("#").len(), 5);
+        assert_eq!(trie.match_filter("+/+/+"), vec!["/device/1"]);
+        assert_eq!(
+            trie.match_filter("$SYS/+/uptime"),
+            vec!["$SYS/brokers/uptime"]
+        );
+    }
+
+    #[test]
+    fn insert_remove_test() {
+        let mut trie = TopicTrie::new();
+        assert!(trie.insert("/a/+/c", 1));
+        assert!(trie.insert("/a/+/c", 2));
+        assert!(!trie.insert("/a/+/c", 2));
+        assert!(trie.insert("/a/b", 3));
+        assert_eq!(trie.len(), 3);
+        assert!(trie.contains("/a/+/c", &1));
+
+        assert!(trie.remove("/a/+/c", &1));
+        assert!(!trie.remove("/a/+/c", &1));
+        assert!(!trie.remove("/a/x/c", &2));
+        assert_eq!(trie.match_topic("/a/b/c"), vec![2]);
+
+        assert!(trie.remove("/a/+/c", &2));
+        assert!(trie.remove("/a/b", &3));
+        assert!(trie.is_empty());
+        assert!(trie.root.children.is_empty());
+    }
+
+    #[test]
+    fn topic_match_test() {
+        assert!(topic_match(
+            "/sensor/1/temperature",
+            "/sensor/1/temperature"
+        ));
+        assert!(topic_match(
+            "/sensor/1/temperature",
+            "/sensor/+/temperature"
+        ));
+        assert!(!topic_match(
+            "/sensor/1/2/temperature",
+            "/sensor/+/temperature"
+        ));
+        assert!(topic_match("/sensor/1/2/temperature", "/sensor/#"));
+        assert!(topic_match("/sensor", "/sensor/#"));
+        assert!(!topic_match("/sensors", "/sensor/#"));
+        assert!(topic_match("/sensor/1", "/sensor/+"));
+        assert!(!topic_match("/sensor", "/sensor/+"));
+        assert!(topic_match("/sensor/1", "#"));
+        assert!(!topic_match("$SYS/uptime", "#"));
+        assert!(!topic_match("$SYS/uptime", "+/uptime"));
+        assert!(topic_match("$SYS/uptime", "$SYS/#"));
+    }
+}
@@ -0,0 +1,303 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! A small HTTP/1.1 client shared by the connectors and the security providers
+//! that call out to HTTP services. `https://` endpoints are verified against the
+//! system trust store, plus an optional CA bundle.
+
+use std::pin::Pin;
+
+use openssl::ssl::{SslConnector, SslMethod};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_openssl::SslStream;
+
+use crate::error::HttpClientError;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HttpScheme {
+    Http,
+    Https,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HttpEndpoint {
+    pub scheme: HttpScheme,
+    pub host: String,
+    pub port: u16,
+    // path prefix of a reverse proxy in front of the service, without trailing '/'
+    pub path_prefix: String,
+}
+
+impl HttpEndpoint {
+    pub fn parse(url: &str) -> Result<Self, HttpClientError> {
+        let invalid =
+            |reason: &str| HttpClientError::InvalidUrl(url.to_string(), reason.to_string());
+
+        let (scheme, rest, default_port) = if let Some(rest) = url.strip_prefix("http://") {
+            (HttpScheme::Http, rest, 80)
+        } else if let Some(rest) = url.strip_prefix("https://") {
+            (HttpScheme::Https, rest, 443)
+        } else {
+            return Err(invalid("only http:// and https:// urls are supported"));
+        };
+
+        let (authority, path) = match rest.find('/') {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, ""),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse::<u16>().map_err(|_| invalid("invalid port"))?,
+            ),
+            None => (authority, default_port),
+        };
+        if host.is_empty() {
+            return Err(invalid("host is empty"));
+        }
+
+        Ok(HttpEndpoint {
+            scheme,
+            host: host.to_string(),
+            port,
+            path_prefix: path.trim_end_matches('/').to_string(),
+        })
+    }
+
+    pub fn addr(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+#[derive(Clone)]
+pub struct HttpClient {
+    tls: SslConnector,
+}
+
+impl HttpClient {
+    // ca_path is a PEM bundle trusted in addition to the system roots, ignored when empty.
+    pub fn new(ca_path: &str) -> Result<Self, HttpClientError> {
+        let mut builder = SslConnector::builder(SslMethod::tls_client())?;
+        if !ca_path.is_empty() {
+            builder.set_ca_file(ca_path)?;
+        }
+        Ok(HttpClient {
+            tls: builder.build(),
+        })
+    }
+
+    // Sends a single request on a new connection, connections are not kept alive.
+    pub async fn send(
+        &self,
+        endpoint: &HttpEndpoint,
+        method: &str,
+        path: &str,
+        headers: &[(&str, String)],
+        body: &[u8],
+    ) -> Result<HttpResponse, HttpClientError> {
+        let stream = TcpStream::connect(endpoint.addr()).await?;
+        match endpoint.scheme {
+            HttpScheme::Http => exchange(stream, endpoint, method, path, headers, body).await,
+            HttpScheme::Https => {
+                let ssl = self.tls.configure()?.into_ssl(&endpoint.host)?;
+                let mut stream = SslStream::new(ssl, stream)?;
+                Pin::new(&mut stream).connect().await?;
+                exchange(stream, endpoint, method, path, headers, body).await
+            }
+        }
+    }
+}
+
+async fn exchange<S>(
+    mut stream: S,
+    endpoint: &HttpEndpoint,
+    method: &str,
+    path: &str,
+    headers: &[(&str, String)],
+    body: &[u8],
+) -> Result<HttpResponse, HttpClientError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut request = format!(
+        "{} {}{} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        method,
+        endpoint.path_prefix,
+        path,
+        endpoint.addr(),
+        body.len()
+    );
+    for (name, value) in headers {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    request.push_str("\r\n");
+
+    stream.write_all(request.as_bytes()).await?;
+    stream.write_all(body).await?;
+    stream.flush().await?;
+
+    let mut data = Vec::new();
+    stream.read_to_end(&mut data).await?;
+    parse_response(&data)
+}
+
+pub fn parse_response(data: &[u8]) -> Result<HttpResponse, HttpClientError> {
+    let malformed = |reason: &str| HttpClientError::MalformedResponse(reason.to_string());
+
+    let header_end = if let Some(index) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+        index
+    } else {
+        return Err(malformed("incomplete response header"));
+    };
+    let header = String::from_utf8_lossy(&data[..header_end]);
+    let body = &data[header_end + 4..];
+
+    let mut lines = header.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split(' ').nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| malformed("invalid status line"))?;
+
+    let mut chunked = false;
+    let mut content_length = None;
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':') {
+            let value = value.trim();
+            match name.trim().to_ascii_lowercase().as_str() {
+                "transfer-encoding" => chunked = value.eq_ignore_ascii_case("chunked"),
+                "content-length" => content_length = value.parse::<usize>().ok(),
+                _ => {}
+            }
+        }
+    }
+
+    let body = if chunked {
+        decode_chunked(body).ok_or_else(|| malformed("invalid chunked body"))?
+    } else if let Some(len) = content_length {
+        if body.len() < len {
+            return Err(malformed("incomplete response body"));
+        }
+        body[..len].to_vec()
+    } else {
+        body.to_vec()
+    };
+    Ok(HttpResponse { status, body })
+}
+
+fn decode_chunked(mut data: &[u8]) -> Option<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let line_end = data.windows(2).position(|w| w == b"\r\n")?;
+        let size_line = std::str::from_utf8(&data[..line_end]).ok()?;
+        let size_str = size_line.split(';').next()?.trim();
+        let size = usize::from_str_radix(size_str, 16).ok()?;
+        data = &data[line_end + 2..];
+        if size == 0 {
+            return Some(body);
+        }
+        if data.len() < size + 2 {
+            return None;
+        }
+        body.extend_from_slice(&data[..size]);
+        data = &data[size + 2..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::{parse_response, HttpClient, HttpEndpoint, HttpScheme};
+
+    #[test]
+    fn parse_endpoint_test() {
+        let endpoint = HttpEndpoint::parse("http://127.0.0.1:9200").unwrap();
+        assert_eq!(endpoint.scheme, HttpScheme::Http);
+        assert_eq!(endpoint.addr(), "127.0.0.1:9200");
+        assert_eq!(endpoint.path_prefix, "");
+
+        let endpoint = HttpEndpoint::parse("http://es.local/proxy/").unwrap();
+        assert_eq!(endpoint.addr(), "es.local:80");
+        assert_eq!(endpoint.path_prefix, "/proxy");
+
+        let endpoint = HttpEndpoint::parse("https://auth.local/mqtt/auth").unwrap();
+        assert_eq!(endpoint.scheme, HttpScheme::Https);
+        assert_eq!(endpoint.addr(), "auth.local:443");
+        assert_eq!(endpoint.path_prefix, "/mqtt/auth");
+
+        assert!(HttpEndpoint::parse("ftp://127.0.0.1:9200").is_err());
+        assert!(HttpEndpoint::parse("http://127.0.0.1:port").is_err());
+        assert!(HttpEndpoint::parse("https://").is_err());
+    }
+
+    #[test]
+    fn parse_response_test() {
+        let response = parse_response(
+            b"HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 2\r\n\r\n{}",
+        )
+        .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"{}".to_vec());
+
+        let response = parse_response(
+            b"HTTP/1.1 429 Too Many Requests\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2;ext=1\r\nde\r\n0\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(response.status, 429);
+        assert_eq!(response.body, b"abcde".to_vec());
+
+        assert!(parse_response(b"HTTP/1.1 200 OK\r\ncontent-length: 2").is_err());
+        assert!(parse_response(b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\n{}").is_err());
+        assert!(parse_response(b"garbage\r\n\r\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn send_request_test() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut data = Vec::new();
+            let mut buf = vec![0u8; 1024];
+            while !data.ends_with(b"{}") {
+                let len = stream.read(&mut buf).await.unwrap();
+                data.extend_from_slice(&buf[..len]);
+            }
+            let request = String::from_utf8_lossy(&data).to_string();
+            assert!(request.starts_with("POST /prefix/auth HTTP/1.1\r\n"));
+            assert!(request.ends_with("\r\n\r\n{}"));
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok")
+                .await
+                .unwrap();
+        });
+
+        let client = HttpClient::new("").unwrap();
+        let endpoint = HttpEndpoint::parse(&format!("http://{}/prefix/", addr)).unwrap();
+        let response = client
+            .send(&endpoint, "POST", "/auth", &[], b"{}")
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"ok".to_vec());
+    }
+}
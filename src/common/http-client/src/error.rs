@@ -0,0 +1,32 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum HttpClientError {
+    #[error("{0}")]
+    FromIoError(#[from] std::io::Error),
+
+    #[error("{0}")]
+    FromOpensslError(#[from] openssl::error::ErrorStack),
+
+    #[error("{0}")]
+    FromSslError(#[from] openssl::ssl::Error),
+
+    #[error("Invalid url [{0}], {1}")]
+    InvalidUrl(String, String),
+
+    #[error("Malformed HTTP response: {0}")]
+    MalformedResponse(String),
+}
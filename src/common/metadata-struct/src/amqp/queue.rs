@@ -0,0 +1,62 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+
+use common_base::error::common::CommonError;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct AmqpQueue {
+    pub queue_name: String,
+    pub durable: bool,
+    pub exclusive: bool,
+    pub auto_delete: bool,
+    pub bindings: Vec<AmqpBinding>,
+    pub create_time: u64,
+}
+
+impl AmqpQueue {
+    pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
+        Ok(serde_json::to_vec(&self)?)
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
+        Ok(serde_json::from_slice(data)?)
+    }
+
+    // Returns false when the binding already exists
+    pub fn add_binding(&mut self, binding: AmqpBinding) -> bool {
+        if self.bindings.contains(&binding) {
+            return false;
+        }
+        self.bindings.push(binding);
+        true
+    }
+
+    pub fn remove_binding(&mut self, binding: &AmqpBinding) -> bool {
+        let len = self.bindings.len();
+        self.bindings.retain(|b| b != binding);
+        len != self.bindings.len()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+pub struct AmqpBinding {
+    pub exchange_name: String,
+    pub routing_key: String,
+    // queue.bind arguments, values kept in their string form
+    #[serde(default)]
+    pub arguments: BTreeMap<String, String>,
+}
@@ -19,4 +19,6 @@ pub struct JournalNodeExtend {
     pub data_fold: Vec<String>,
     pub tcp_addr: String,
     pub tcps_addr: String,
+    #[serde(default)]
+    pub quic_addr: String,
 }
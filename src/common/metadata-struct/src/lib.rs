@@ -13,6 +13,7 @@
 // limitations under the License.
 
 pub mod acl;
+pub mod amqp;
 pub mod adapter;
 pub mod journal;
 pub mod mqtt;
@@ -0,0 +1,107 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::str::FromStr;
+
+use common_base::error::common::CommonError;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct MqttBridgeRule {
+    pub name: String,
+    pub bridge_type: MqttBridgeType,
+    pub topic_filters: Vec<String>,
+    pub batch_size: u64,
+    // JSON encoded configuration of the target, its schema depends on `bridge_type`
+    pub config: String,
+    #[serde(default)]
+    pub status: MqttBridgeStatus,
+    pub create_time: u64,
+}
+
+impl MqttBridgeRule {
+    pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
+        Ok(serde_json::to_vec(&self)?)
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
+        Ok(serde_json::from_slice(data)?)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Default, Debug, PartialEq)]
+pub enum MqttBridgeType {
+    #[default]
+    Kafka,
+    Redis,
+    Elasticsearch,
+    File,
+    Webhook,
+}
+
+impl fmt::Display for MqttBridgeType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                MqttBridgeType::Kafka => "Kafka",
+                MqttBridgeType::Redis => "Redis",
+                MqttBridgeType::Elasticsearch => "Elasticsearch",
+                MqttBridgeType::File => "File",
+                MqttBridgeType::Webhook => "Webhook",
+            }
+        )
+    }
+}
+
+impl FromStr for MqttBridgeType {
+    type Err = CommonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "kafka" => Ok(MqttBridgeType::Kafka),
+            "redis" => Ok(MqttBridgeType::Redis),
+            "elasticsearch" => Ok(MqttBridgeType::Elasticsearch),
+            "file" => Ok(MqttBridgeType::File),
+            "webhook" => Ok(MqttBridgeType::Webhook),
+            _ => Err(CommonError::CommonError(format!(
+                "Unsupported bridge type [{}]",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Default, Debug, PartialEq)]
+pub enum MqttBridgeStatus {
+    #[default]
+    Running,
+    // The bridge stops consuming and keeps its offsets until it is resumed.
+    Paused,
+}
+
+impl fmt::Display for MqttBridgeStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                MqttBridgeStatus::Running => "Running",
+                MqttBridgeStatus::Paused => "Paused",
+            }
+        )
+    }
+}
@@ -23,6 +23,10 @@ pub struct MqttClusterDynamicConfig {
     pub security: MqttClusterDynamicConfigSecurity,
     pub network: MqttClusterDynamicConfigNetwork,
     pub slow: MqttClusterDynamicSlowSub,
+    #[serde(default)]
+    pub rate_limit: MqttClusterDynamicConfigRateLimit,
+    #[serde(default)]
+    pub offline_message: MqttClusterDynamicConfigOfflineMessage,
 }
 
 // MQTT cluster protocol related dynamic configuration
@@ -75,6 +79,31 @@ pub struct MqttClusterDynamicSlowSub {
     pub response_ms: u32,
 }
 
+// MQTT cluster rate limit related dynamic configuration, 0 means unlimited
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MqttClusterDynamicConfigRateLimit {
+    // Maximum number of new connections per second on each listener
+    pub max_connection_rate: u32,
+    // Maximum number of publish packets per second for a single client
+    pub max_client_publish_rate: u32,
+    // Maximum number of payload bytes per second for a single client
+    pub max_client_publish_bytes_rate: u64,
+    // Maximum number of publish packets per second to a single topic
+    pub max_topic_publish_rate: u32,
+    // Maximum number of subscribe packets per second for a single client
+    pub max_client_subscribe_rate: u32,
+}
+
+// MQTT cluster offline message queue related dynamic configuration
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MqttClusterDynamicConfigOfflineMessage {
+    pub enable: bool,
+    // Maximum number of messages queued for a session, 0 means unlimited
+    pub max_queue_len: u64,
+    // Which message is dropped when the queue is full
+    pub drop_policy: OfflineMessageDropPolicy,
+}
+
 impl MqttClusterDynamicConfig {
     pub fn new() -> Self {
         MqttClusterDynamicConfig {
@@ -114,6 +143,8 @@ impl MqttClusterDynamicConfig {
                 internal_ms: 0,
                 response_ms: 0,
             },
+            rate_limit: MqttClusterDynamicConfigRateLimit::default(),
+            offline_message: MqttClusterDynamicConfigOfflineMessage::default(),
         }
     }
 
@@ -122,6 +153,29 @@ impl MqttClusterDynamicConfig {
     }
 }
 
+// Shared by new() and by cluster configs stored before the section existed
+impl Default for MqttClusterDynamicConfigRateLimit {
+    fn default() -> Self {
+        MqttClusterDynamicConfigRateLimit {
+            max_connection_rate: 1000,
+            max_client_publish_rate: 0,
+            max_client_publish_bytes_rate: 0,
+            max_topic_publish_rate: 0,
+            max_client_subscribe_rate: 0,
+        }
+    }
+}
+
+impl Default for MqttClusterDynamicConfigOfflineMessage {
+    fn default() -> Self {
+        MqttClusterDynamicConfigOfflineMessage {
+            enable: true,
+            max_queue_len: 1000,
+            drop_policy: OfflineMessageDropPolicy::DropOldest,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Default, Clone)]
 pub enum AvailableFlag {
     #[default]
@@ -129,13 +183,40 @@ pub enum AvailableFlag {
     Enable,
 }
 
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub enum OfflineMessageDropPolicy {
+    #[default]
+    DropOldest,
+    DropNewest,
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::mqtt::cluster::AvailableFlag;
+    use crate::mqtt::cluster::{AvailableFlag, MqttClusterDynamicConfig, OfflineMessageDropPolicy};
 
     #[test]
     fn client34_connect_test() {
         assert_eq!(AvailableFlag::Disable as u8, 0);
         assert_eq!(AvailableFlag::Enable as u8, 1);
     }
+
+    #[test]
+    fn missing_section_default_test() {
+        let mut value = serde_json::to_value(MqttClusterDynamicConfig::new()).unwrap();
+        let object = value.as_object_mut().unwrap();
+        object.remove("rate_limit");
+        object.remove("offline_message");
+        let config: MqttClusterDynamicConfig = serde_json::from_value(value).unwrap();
+
+        assert_eq!(config.rate_limit.max_connection_rate, 1000);
+        assert_eq!(config.rate_limit.max_client_publish_rate, 0);
+
+        let expect = MqttClusterDynamicConfig::new().offline_message;
+        assert!(config.offline_message.enable);
+        assert_eq!(config.offline_message.max_queue_len, expect.max_queue_len);
+        assert_eq!(
+            config.offline_message.drop_policy,
+            OfflineMessageDropPolicy::DropOldest
+        );
+    }
 }
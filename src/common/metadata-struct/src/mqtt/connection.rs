@@ -19,6 +19,8 @@ use common_base::tools::now_second;
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
 
+use crate::acl::mqtt_acl::MqttAcl;
+
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct MQTTConnection {
     // Connection ID
@@ -43,6 +45,12 @@ pub struct MQTTConnection {
     pub topic_alias_max: u16,
     // Flags whether to return a detailed error message to the client when an error occurs.
     pub request_problem_info: u8,
+    // MQTT 5 authentication method the connection authenticated with, re-authentication must use the same method
+    #[serde(default)]
+    pub authentication_method: Option<String>,
+    // ACL rules carried by the client's credentials (e.g. JWT claims), valid for the lifetime of the connection
+    #[serde(default)]
+    pub acl: Vec<MqttAcl>,
     // Flow control part keeps track of how many QOS 1 and QOS 2 messages are still pending on the connection
     #[serde(skip_serializing, skip_deserializing)]
     pub receive_qos_message: Arc<AtomicIsize>,
@@ -51,6 +59,9 @@ pub struct MQTTConnection {
     pub sender_qos_message: Arc<AtomicIsize>,
     // Time when the connection was created
     pub create_time: u64,
+    // The client connected with clean start, its session does not keep an offline message queue
+    #[serde(default)]
+    pub clean_session: bool,
 }
 
 pub struct ConnectionConfig {
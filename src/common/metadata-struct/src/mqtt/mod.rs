@@ -12,11 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod bridge;
 pub mod cluster;
 pub mod connection;
 pub mod lastwill;
 pub mod message;
 pub mod node_extend;
+pub mod offline_message;
+pub mod rule;
 pub mod session;
 pub mod topic;
 pub mod user;
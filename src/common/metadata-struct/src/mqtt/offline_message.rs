@@ -0,0 +1,37 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use common_base::error::common::CommonError;
+use serde::{Deserialize, Serialize};
+
+use super::message::MqttMessage;
+
+// A QoS 1/2 message waiting in the offline queue of a persistent session
+#[derive(Clone, Serialize, Deserialize, Default, Debug)]
+pub struct MqttOfflineMessage {
+    // The publish as it is delivered to the client, the pkid is non-zero when
+    // the message was already inflight and must be resent with the same identifier.
+    pub message: MqttMessage,
+    // QoS 2 message whose PUBREC was received, only the PUBREL is resent
+    pub wait_pubcomp: bool,
+}
+
+impl MqttOfflineMessage {
+    pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
+        Ok(serde_json::to_vec(&self)?)
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
+        Ok(serde_json::from_slice(data)?)
+    }
+}
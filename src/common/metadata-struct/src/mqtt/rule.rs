@@ -0,0 +1,51 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use common_base::error::common::CommonError;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct MqttRule {
+    pub name: String,
+    // SELECT <fields> FROM "<topic filter>" [WHERE <condition>]
+    pub sql: String,
+    pub actions: Vec<MqttRuleAction>,
+    pub create_time: u64,
+}
+
+impl MqttRule {
+    pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
+        Ok(serde_json::to_vec(&self)?)
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
+        Ok(serde_json::from_slice(data)?)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum MqttRuleAction {
+    // Publish the selected fields as a JSON payload. `topic` may reference
+    // selected fields with ${field}.
+    Republish {
+        topic: String,
+        qos: u8,
+        retain: bool,
+    },
+    // Write the selected fields as a JSON payload to an existing bridge.
+    Bridge {
+        bridge_name: String,
+    },
+    // Do not store the original message when the rule matches.
+    Drop,
+}
@@ -15,12 +15,16 @@
 use common_base::error::common::CommonError;
 use protocol::broker_mqtt::broker_mqtt_admin::{
     ClusterStatusReply, ClusterStatusRequest, CreateAclReply, CreateAclRequest,
-    CreateBlacklistReply, CreateBlacklistRequest, CreateUserReply, CreateUserRequest,
-    DeleteAclReply, DeleteAclRequest, DeleteBlacklistReply, DeleteBlacklistRequest,
-    DeleteUserReply, DeleteUserRequest, EnableSlowSubScribeReply, EnableSlowSubscribeRequest,
-    ListAclReply, ListAclRequest, ListBlacklistReply, ListBlacklistRequest, ListConnectionReply,
-    ListConnectionRequest, ListSlowSubscribeReply, ListSlowSubscribeRequest, ListTopicReply,
-    ListTopicRequest, ListUserReply, ListUserRequest,
+    CreateBlacklistReply, CreateBlacklistRequest, CreateConnectorReply, CreateConnectorRequest,
+    CreateRuleReply, CreateRuleRequest, CreateUserReply, CreateUserRequest, DeleteAclReply,
+    DeleteAclRequest, DeleteBlacklistReply, DeleteBlacklistRequest, DeleteConnectorReply,
+    DeleteConnectorRequest, DeleteRuleReply, DeleteRuleRequest, DeleteUserReply, DeleteUserRequest,
+    EnableSlowSubScribeReply, EnableSlowSubscribeRequest, ListAclReply, ListAclRequest,
+    ListBlacklistReply, ListBlacklistRequest, ListConnectionReply, ListConnectionRequest,
+    ListConnectorReply, ListConnectorRequest, ListRuleReply, ListRuleRequest,
+    ListSlowSubscribeReply, ListSlowSubscribeRequest, ListTopicReply, ListTopicRequest,
+    ListUserReply, ListUserRequest, PauseConnectorReply, PauseConnectorRequest,
+    ResumeConnectorReply, ResumeConnectorRequest,
 };
 
 use crate::pool::ClientPool;
@@ -143,3 +147,69 @@ pub async fn mqtt_broker_list_topic(
 ) -> Result<ListTopicReply, CommonError> {
     retry_call(client_pool, addrs, request).await
 }
+
+// ------- connector  -----------
+pub async fn mqtt_broker_list_connector(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: ListConnectorRequest,
+) -> Result<ListConnectorReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+pub async fn mqtt_broker_create_connector(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: CreateConnectorRequest,
+) -> Result<CreateConnectorReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+pub async fn mqtt_broker_pause_connector(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: PauseConnectorRequest,
+) -> Result<PauseConnectorReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+pub async fn mqtt_broker_resume_connector(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: ResumeConnectorRequest,
+) -> Result<ResumeConnectorReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+pub async fn mqtt_broker_delete_connector(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: DeleteConnectorRequest,
+) -> Result<DeleteConnectorReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+// ------- rule  -----------
+pub async fn mqtt_broker_list_rule(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: ListRuleRequest,
+) -> Result<ListRuleReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+pub async fn mqtt_broker_create_rule(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: CreateRuleRequest,
+) -> Result<CreateRuleReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+pub async fn mqtt_broker_delete_rule(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: DeleteRuleRequest,
+) -> Result<DeleteRuleReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
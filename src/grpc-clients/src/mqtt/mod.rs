@@ -15,12 +15,16 @@
 use protocol::broker_mqtt::broker_mqtt_admin::mqtt_broker_admin_service_client::MqttBrokerAdminServiceClient;
 use protocol::broker_mqtt::broker_mqtt_admin::{
     ClusterStatusReply, ClusterStatusRequest, CreateAclReply, CreateAclRequest,
-    CreateBlacklistReply, CreateBlacklistRequest, CreateUserReply, CreateUserRequest,
-    DeleteAclReply, DeleteAclRequest, DeleteBlacklistReply, DeleteBlacklistRequest,
-    DeleteUserReply, DeleteUserRequest, EnableSlowSubScribeReply, EnableSlowSubscribeRequest,
-    ListAclReply, ListAclRequest, ListBlacklistReply, ListBlacklistRequest, ListConnectionReply,
-    ListConnectionRequest, ListSlowSubscribeReply, ListSlowSubscribeRequest, ListTopicReply,
-    ListTopicRequest, ListUserReply, ListUserRequest,
+    CreateBlacklistReply, CreateBlacklistRequest, CreateConnectorReply, CreateConnectorRequest,
+    CreateRuleReply, CreateRuleRequest, CreateUserReply, CreateUserRequest, DeleteAclReply,
+    DeleteAclRequest, DeleteBlacklistReply, DeleteBlacklistRequest, DeleteConnectorReply,
+    DeleteConnectorRequest, DeleteRuleReply, DeleteRuleRequest, DeleteUserReply, DeleteUserRequest,
+    EnableSlowSubScribeReply, EnableSlowSubscribeRequest, ListAclReply, ListAclRequest,
+    ListBlacklistReply, ListBlacklistRequest, ListConnectionReply, ListConnectionRequest,
+    ListConnectorReply, ListConnectorRequest, ListRuleReply, ListRuleRequest,
+    ListSlowSubscribeReply, ListSlowSubscribeRequest, ListTopicReply, ListTopicRequest,
+    ListUserReply, ListUserRequest, PauseConnectorReply, PauseConnectorRequest,
+    ResumeConnectorReply, ResumeConnectorRequest,
 };
 use protocol::broker_mqtt::broker_mqtt_inner::mqtt_broker_inner_service_client::MqttBrokerInnerServiceClient;
 use protocol::broker_mqtt::broker_mqtt_inner::{
@@ -170,5 +174,69 @@ impl_retriable_request!(
     mqtt_broker_list_topic
 );
 
+impl_retriable_request!(
+    ListConnectorRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListConnectorReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_connector
+);
+
+impl_retriable_request!(
+    CreateConnectorRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    CreateConnectorReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_create_connector
+);
+
+impl_retriable_request!(
+    PauseConnectorRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    PauseConnectorReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_pause_connector
+);
+
+impl_retriable_request!(
+    ResumeConnectorRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ResumeConnectorReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_resume_connector
+);
+
+impl_retriable_request!(
+    DeleteConnectorRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    DeleteConnectorReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_delete_connector
+);
+
+impl_retriable_request!(
+    ListRuleRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListRuleReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_rule
+);
+
+impl_retriable_request!(
+    CreateRuleRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    CreateRuleReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_create_rule
+);
+
+impl_retriable_request!(
+    DeleteRuleRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    DeleteRuleReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_delete_rule
+);
+
 #[cfg(test)]
 mod tests {}
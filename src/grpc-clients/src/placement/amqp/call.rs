@@ -0,0 +1,71 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::error::common::CommonError;
+use protocol::placement_center::placement_center_amqp::{
+    CreateExchangeReply, CreateExchangeRequest, CreateQueueReply, CreateQueueRequest,
+    DeleteExchangeReply, DeleteExchangeRequest, DeleteQueueReply, DeleteQueueRequest,
+    ListExchangeReply, ListExchangeRequest, ListQueueReply, ListQueueRequest,
+};
+
+use crate::pool::ClientPool;
+
+macro_rules! generate_amqp_service_call {
+    ($fn_name:ident, $req_ty:ty, $rep_ty:ty, $variant:ident) => {
+        pub async fn $fn_name(
+            client_pool: &ClientPool,
+            addrs: &[impl AsRef<str>],
+            request: $req_ty,
+        ) -> Result<$rep_ty, CommonError> {
+            $crate::utils::retry_call(client_pool, addrs, request).await
+        }
+    };
+}
+
+generate_amqp_service_call!(
+    placement_list_exchange,
+    ListExchangeRequest,
+    ListExchangeReply,
+    ListExchange
+);
+generate_amqp_service_call!(
+    placement_create_exchange,
+    CreateExchangeRequest,
+    CreateExchangeReply,
+    CreateExchange
+);
+generate_amqp_service_call!(
+    placement_delete_exchange,
+    DeleteExchangeRequest,
+    DeleteExchangeReply,
+    DeleteExchange
+);
+generate_amqp_service_call!(
+    placement_list_queue,
+    ListQueueRequest,
+    ListQueueReply,
+    ListQueue
+);
+generate_amqp_service_call!(
+    placement_create_queue,
+    CreateQueueRequest,
+    CreateQueueReply,
+    CreateQueue
+);
+generate_amqp_service_call!(
+    placement_delete_queue,
+    DeleteQueueRequest,
+    DeleteQueueReply,
+    DeleteQueue
+);
@@ -0,0 +1,117 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::error::common::CommonError;
+use mobc::Manager;
+use protocol::placement_center::placement_center_amqp::amqp_service_client::AmqpServiceClient;
+use protocol::placement_center::placement_center_amqp::{
+    CreateExchangeReply, CreateExchangeRequest, CreateQueueReply, CreateQueueRequest,
+    DeleteExchangeReply, DeleteExchangeRequest, DeleteQueueReply, DeleteQueueRequest,
+    ListExchangeReply, ListExchangeRequest, ListQueueReply, ListQueueRequest,
+};
+use tonic::transport::Channel;
+
+use crate::macros::impl_retriable_request;
+
+pub mod call;
+
+#[derive(Clone)]
+pub struct AmqpServiceManager {
+    pub addr: String,
+}
+
+impl AmqpServiceManager {
+    pub fn new(addr: String) -> Self {
+        Self { addr }
+    }
+}
+
+#[tonic::async_trait]
+impl Manager for AmqpServiceManager {
+    type Connection = AmqpServiceClient<Channel>;
+    type Error = CommonError;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        match AmqpServiceClient::connect(format!("http://{}", self.addr.clone())).await {
+            Ok(client) => {
+                return Ok(client);
+            }
+            Err(err) => {
+                return Err(CommonError::CommonError(format!(
+                    "{},{}",
+                    err,
+                    self.addr.clone()
+                )))
+            }
+        };
+    }
+
+    async fn check(&self, conn: Self::Connection) -> Result<Self::Connection, Self::Error> {
+        Ok(conn)
+    }
+}
+
+impl_retriable_request!(
+    ListExchangeRequest,
+    AmqpServiceClient<Channel>,
+    ListExchangeReply,
+    placement_center_amqp_services_client,
+    list_exchange,
+    true
+);
+
+impl_retriable_request!(
+    CreateExchangeRequest,
+    AmqpServiceClient<Channel>,
+    CreateExchangeReply,
+    placement_center_amqp_services_client,
+    create_exchange,
+    true
+);
+
+impl_retriable_request!(
+    DeleteExchangeRequest,
+    AmqpServiceClient<Channel>,
+    DeleteExchangeReply,
+    placement_center_amqp_services_client,
+    delete_exchange,
+    true
+);
+
+impl_retriable_request!(
+    ListQueueRequest,
+    AmqpServiceClient<Channel>,
+    ListQueueReply,
+    placement_center_amqp_services_client,
+    list_queue,
+    true
+);
+
+impl_retriable_request!(
+    CreateQueueRequest,
+    AmqpServiceClient<Channel>,
+    CreateQueueReply,
+    placement_center_amqp_services_client,
+    create_queue,
+    true
+);
+
+impl_retriable_request!(
+    DeleteQueueRequest,
+    AmqpServiceClient<Channel>,
+    DeleteQueueReply,
+    placement_center_amqp_services_client,
+    delete_queue,
+    true
+);
@@ -14,8 +14,9 @@
 
 use common_base::error::common::CommonError;
 use protocol::placement_center::placement_center_kv::{
-    DeleteReply, DeleteRequest, ExistsReply, ExistsRequest, GetReply, GetRequest, SetReply,
-    SetRequest,
+    BatchDeleteReply, BatchDeleteRequest, BatchGetReply, BatchGetRequest, BatchSetReply,
+    BatchSetRequest, DeleteReply, DeleteRequest, ExistsReply, ExistsRequest, GetReply, GetRequest,
+    SetReply, SetRequest,
 };
 
 use crate::pool::ClientPool;
@@ -36,3 +37,21 @@ generate_kv_service_call!(placement_set, SetRequest, SetReply, Set);
 generate_kv_service_call!(placement_get, GetRequest, GetReply, Get);
 generate_kv_service_call!(placement_delete, DeleteRequest, DeleteReply, Delete);
 generate_kv_service_call!(placement_exists, ExistsRequest, ExistsReply, Exists);
+generate_kv_service_call!(
+    placement_batch_set,
+    BatchSetRequest,
+    BatchSetReply,
+    BatchSet
+);
+generate_kv_service_call!(
+    placement_batch_get,
+    BatchGetRequest,
+    BatchGetReply,
+    BatchGet
+);
+generate_kv_service_call!(
+    placement_batch_delete,
+    BatchDeleteRequest,
+    BatchDeleteReply,
+    BatchDelete
+);
@@ -16,8 +16,9 @@ use common_base::error::common::CommonError;
 use mobc::Manager;
 use protocol::placement_center::placement_center_kv::kv_service_client::KvServiceClient;
 use protocol::placement_center::placement_center_kv::{
-    DeleteReply, DeleteRequest, ExistsReply, ExistsRequest, GetReply, GetRequest, SetReply,
-    SetRequest,
+    BatchDeleteReply, BatchDeleteRequest, BatchGetReply, BatchGetRequest, BatchSetReply,
+    BatchSetRequest, DeleteReply, DeleteRequest, ExistsReply, ExistsRequest, GetReply, GetRequest,
+    SetReply, SetRequest,
 };
 use tonic::transport::Channel;
 
@@ -97,5 +98,32 @@ impl_retriable_request!(
     true
 );
 
+impl_retriable_request!(
+    BatchSetRequest,
+    KvServiceClient<Channel>,
+    BatchSetReply,
+    placement_center_kv_services_client,
+    batch_set,
+    true
+);
+
+impl_retriable_request!(
+    BatchGetRequest,
+    KvServiceClient<Channel>,
+    BatchGetReply,
+    placement_center_kv_services_client,
+    batch_get,
+    true
+);
+
+impl_retriable_request!(
+    BatchDeleteRequest,
+    KvServiceClient<Channel>,
+    BatchDeleteReply,
+    placement_center_kv_services_client,
+    batch_delete,
+    true
+);
+
 #[cfg(test)]
 mod tests {}
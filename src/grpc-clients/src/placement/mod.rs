@@ -73,6 +73,14 @@ pub enum PlacementCenterInterface {
     DeleteBlackList,
     ListBlackList,
 
+    // amqp service interface
+    ListExchange,
+    CreateExchange,
+    DeleteExchange,
+    ListQueue,
+    CreateQueue,
+    DeleteQueue,
+
     // Open Raft
     Vote,
     Append,
@@ -100,6 +108,12 @@ impl PlacementCenterInterface {
                 set.insert(PlacementCenterInterface::CreateBlackList);
                 set.insert(PlacementCenterInterface::DeleteBlackList);
 
+                // amqp service interface
+                set.insert(PlacementCenterInterface::CreateExchange);
+                set.insert(PlacementCenterInterface::DeleteExchange);
+                set.insert(PlacementCenterInterface::CreateQueue);
+                set.insert(PlacementCenterInterface::DeleteQueue);
+
                 // placement inner interface
                 set.insert(PlacementCenterInterface::RegisterNode);
                 set.insert(PlacementCenterInterface::UnRegisterNode);
@@ -117,6 +131,7 @@ impl PlacementCenterInterface {
     }
 }
 
+pub mod amqp;
 #[allow(clippy::module_inception)]
 pub mod inner;
 pub mod journal;
@@ -15,14 +15,16 @@
 use common_base::error::common::CommonError;
 use protocol::placement_center::placement_center_mqtt::{
     CreateAclReply, CreateAclRequest, CreateBlacklistReply, CreateBlacklistRequest,
-    CreateSessionReply, CreateSessionRequest, CreateTopicReply, CreateTopicRequest,
-    CreateUserReply, CreateUserRequest, DeleteAclReply, DeleteAclRequest, DeleteBlacklistReply,
-    DeleteBlacklistRequest, DeleteExclusiveTopicReply, DeleteExclusiveTopicRequest,
-    DeleteSessionReply, DeleteSessionRequest, DeleteTopicReply, DeleteTopicRequest,
-    DeleteUserReply, DeleteUserRequest, GetShareSubLeaderReply, GetShareSubLeaderRequest,
-    ListAclReply, ListAclRequest, ListBlacklistReply, ListBlacklistRequest, ListSessionReply,
-    ListSessionRequest, ListTopicReply, ListTopicRequest, ListUserReply, ListUserRequest,
-    SaveLastWillMessageReply, SaveLastWillMessageRequest, SetExclusiveTopicReply,
+    CreateBridgeReply, CreateBridgeRequest, CreateRuleReply, CreateRuleRequest, CreateSessionReply,
+    CreateSessionRequest, CreateTopicReply, CreateTopicRequest, CreateUserReply, CreateUserRequest,
+    DeleteAclReply, DeleteAclRequest, DeleteBlacklistReply, DeleteBlacklistRequest,
+    DeleteBridgeReply, DeleteBridgeRequest, DeleteExclusiveTopicReply, DeleteExclusiveTopicRequest,
+    DeleteRuleReply, DeleteRuleRequest, DeleteSessionReply, DeleteSessionRequest, DeleteTopicReply,
+    DeleteTopicRequest, DeleteUserReply, DeleteUserRequest, GetShareSubLeaderReply,
+    GetShareSubLeaderRequest, ListAclReply, ListAclRequest, ListBlacklistReply,
+    ListBlacklistRequest, ListBridgeReply, ListBridgeRequest, ListRuleReply, ListRuleRequest,
+    ListSessionReply, ListSessionRequest, ListTopicReply, ListTopicRequest, ListUserReply,
+    ListUserRequest, SaveLastWillMessageReply, SaveLastWillMessageRequest, SetExclusiveTopicReply,
     SetExclusiveTopicRequest, SetTopicRetainMessageReply, SetTopicRetainMessageRequest,
     UpdateSessionReply, UpdateSessionRequest,
 };
@@ -152,3 +154,19 @@ generate_mqtt_service_call!(
     DeleteBlacklistReply,
     DeleteBlacklist
 );
+generate_mqtt_service_call!(
+    create_bridge,
+    CreateBridgeRequest,
+    CreateBridgeReply,
+    CreateBridge
+);
+generate_mqtt_service_call!(list_bridge, ListBridgeRequest, ListBridgeReply, ListBridge);
+generate_mqtt_service_call!(
+    delete_bridge,
+    DeleteBridgeRequest,
+    DeleteBridgeReply,
+    DeleteBridge
+);
+generate_mqtt_service_call!(create_rule, CreateRuleRequest, CreateRuleReply, CreateRule);
+generate_mqtt_service_call!(list_rule, ListRuleRequest, ListRuleReply, ListRule);
+generate_mqtt_service_call!(delete_rule, DeleteRuleRequest, DeleteRuleReply, DeleteRule);
@@ -17,14 +17,16 @@ use mobc::Manager;
 use protocol::placement_center::placement_center_mqtt::mqtt_service_client::MqttServiceClient;
 use protocol::placement_center::placement_center_mqtt::{
     CreateAclReply, CreateAclRequest, CreateBlacklistReply, CreateBlacklistRequest,
-    CreateSessionReply, CreateSessionRequest, CreateTopicReply, CreateTopicRequest,
-    CreateUserReply, CreateUserRequest, DeleteAclReply, DeleteAclRequest, DeleteBlacklistReply,
-    DeleteBlacklistRequest, DeleteExclusiveTopicReply, DeleteExclusiveTopicRequest,
-    DeleteSessionReply, DeleteSessionRequest, DeleteTopicReply, DeleteTopicRequest,
-    DeleteUserReply, DeleteUserRequest, GetShareSubLeaderReply, GetShareSubLeaderRequest,
-    ListAclReply, ListAclRequest, ListBlacklistReply, ListBlacklistRequest, ListSessionReply,
-    ListSessionRequest, ListTopicReply, ListTopicRequest, ListUserReply, ListUserRequest,
-    SaveLastWillMessageReply, SaveLastWillMessageRequest, SetExclusiveTopicReply,
+    CreateBridgeReply, CreateBridgeRequest, CreateRuleReply, CreateRuleRequest, CreateSessionReply,
+    CreateSessionRequest, CreateTopicReply, CreateTopicRequest, CreateUserReply, CreateUserRequest,
+    DeleteAclReply, DeleteAclRequest, DeleteBlacklistReply, DeleteBlacklistRequest,
+    DeleteBridgeReply, DeleteBridgeRequest, DeleteExclusiveTopicReply, DeleteExclusiveTopicRequest,
+    DeleteRuleReply, DeleteRuleRequest, DeleteSessionReply, DeleteSessionRequest, DeleteTopicReply,
+    DeleteTopicRequest, DeleteUserReply, DeleteUserRequest, GetShareSubLeaderReply,
+    GetShareSubLeaderRequest, ListAclReply, ListAclRequest, ListBlacklistReply,
+    ListBlacklistRequest, ListBridgeReply, ListBridgeRequest, ListRuleReply, ListRuleRequest,
+    ListSessionReply, ListSessionRequest, ListTopicReply, ListTopicRequest, ListUserReply,
+    ListUserRequest, SaveLastWillMessageReply, SaveLastWillMessageRequest, SetExclusiveTopicReply,
     SetExclusiveTopicRequest, SetTopicRetainMessageReply, SetTopicRetainMessageRequest,
     UpdateSessionReply, UpdateSessionRequest,
 };
@@ -258,3 +260,57 @@ impl_retriable_request!(
     list_blacklist,
     true
 );
+
+impl_retriable_request!(
+    CreateBridgeRequest,
+    MqttServiceClient<Channel>,
+    CreateBridgeReply,
+    placement_center_mqtt_services_client,
+    create_bridge,
+    true
+);
+
+impl_retriable_request!(
+    DeleteBridgeRequest,
+    MqttServiceClient<Channel>,
+    DeleteBridgeReply,
+    placement_center_mqtt_services_client,
+    delete_bridge,
+    true
+);
+
+impl_retriable_request!(
+    ListBridgeRequest,
+    MqttServiceClient<Channel>,
+    ListBridgeReply,
+    placement_center_mqtt_services_client,
+    list_bridge,
+    true
+);
+
+impl_retriable_request!(
+    CreateRuleRequest,
+    MqttServiceClient<Channel>,
+    CreateRuleReply,
+    placement_center_mqtt_services_client,
+    create_rule,
+    true
+);
+
+impl_retriable_request!(
+    DeleteRuleRequest,
+    MqttServiceClient<Channel>,
+    DeleteRuleReply,
+    placement_center_mqtt_services_client,
+    delete_rule,
+    true
+);
+
+impl_retriable_request!(
+    ListRuleRequest,
+    MqttServiceClient<Channel>,
+    ListRuleReply,
+    placement_center_mqtt_services_client,
+    list_rule,
+    true
+);
@@ -22,6 +22,7 @@ use crate::journal::admin::JournalAdminServiceManager;
 use crate::journal::inner::JournalInnerServiceManager;
 use crate::mqtt::admin::MqttBrokerAdminServiceManager;
 use crate::mqtt::inner::MqttBrokerPlacementServiceManager;
+use crate::placement::amqp::AmqpServiceManager;
 use crate::placement::inner::PlacementServiceManager;
 use crate::placement::journal::JournalServiceManager;
 use crate::placement::kv::KvServiceManager;
@@ -33,6 +34,7 @@ pub struct ClientPool {
     max_open_connection: u64,
     // modules: placement center
     placement_center_inner_pools: DashMap<String, Pool<PlacementServiceManager>>,
+    placement_center_amqp_service_pools: DashMap<String, Pool<AmqpServiceManager>>,
     placement_center_journal_service_pools: DashMap<String, Pool<JournalServiceManager>>,
     placement_center_kv_service_pools: DashMap<String, Pool<KvServiceManager>>,
     placement_center_mqtt_service_pools: DashMap<String, Pool<MqttServiceManager>>,
@@ -55,6 +57,7 @@ impl ClientPool {
             max_open_connection,
             // modules: placement_center
             placement_center_inner_pools: DashMap::with_capacity(2),
+            placement_center_amqp_service_pools: DashMap::with_capacity(2),
             placement_center_journal_service_pools: DashMap::with_capacity(2),
             placement_center_kv_service_pools: DashMap::with_capacity(2),
             placement_center_mqtt_service_pools: DashMap::with_capacity(2),
@@ -197,6 +200,37 @@ impl ClientPool {
         ))
     }
 
+    pub async fn placement_center_amqp_services_client(
+        &self,
+        addr: &str,
+    ) -> Result<Connection<AmqpServiceManager>, CommonError> {
+        if !self.placement_center_amqp_service_pools.contains_key(addr) {
+            let manager = AmqpServiceManager::new(addr.to_owned());
+            let pool = Pool::builder()
+                .max_open(self.max_open_connection)
+                .build(manager);
+            self.placement_center_amqp_service_pools
+                .insert(addr.to_owned(), pool);
+        }
+        if let Some(pool) = self.placement_center_amqp_service_pools.get(addr) {
+            match pool.get().await {
+                Ok(conn) => {
+                    return Ok(conn);
+                }
+                Err(e) => {
+                    return Err(CommonError::NoAvailableGrpcConnection(
+                        "AmqpServices".to_string(),
+                        e.to_string(),
+                    ));
+                }
+            };
+        }
+        Err(CommonError::NoAvailableGrpcConnection(
+            "AmqpServices".to_string(),
+            "connection pool is not initialized".to_string(),
+        ))
+    }
+
     pub async fn placement_center_openraft_services_client(
         &self,
         addr: &str,
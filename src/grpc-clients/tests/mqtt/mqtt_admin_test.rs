@@ -17,12 +17,19 @@ mod tests {
     use std::sync::Arc;
 
     use grpc_clients::mqtt::admin::call::{
-        cluster_status, mqtt_broker_create_user, mqtt_broker_delete_user, mqtt_broker_list_user,
+        cluster_status, mqtt_broker_create_connector, mqtt_broker_create_rule,
+        mqtt_broker_create_user, mqtt_broker_delete_connector, mqtt_broker_delete_rule,
+        mqtt_broker_delete_user, mqtt_broker_list_connector, mqtt_broker_list_rule,
+        mqtt_broker_list_user, mqtt_broker_pause_connector, mqtt_broker_resume_connector,
     };
     use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::bridge::{MqttBridgeRule, MqttBridgeStatus, MqttBridgeType};
+    use metadata_struct::mqtt::rule::{MqttRule, MqttRuleAction};
     use metadata_struct::mqtt::user::MqttUser;
     use protocol::broker_mqtt::broker_mqtt_admin::{
-        ClusterStatusRequest, CreateUserRequest, DeleteUserRequest, ListUserRequest,
+        ClusterStatusRequest, CreateConnectorRequest, CreateRuleRequest, CreateUserRequest,
+        DeleteConnectorRequest, DeleteRuleRequest, DeleteUserRequest, ListConnectorRequest,
+        ListRuleRequest, ListUserRequest, PauseConnectorRequest, ResumeConnectorRequest,
     };
 
     use crate::common::get_mqtt_broker_addr;
@@ -54,6 +61,7 @@ mod tests {
             username: user_name.clone(),
             password: password.clone(),
             is_superuser: false,
+            password_hash: "sha256".to_string(),
         };
 
         match mqtt_broker_create_user(&client_pool, &addrs, user.clone()).await {
@@ -69,6 +77,7 @@ mod tests {
                 for raw in data.users {
                     let mqtt_user = serde_json::from_slice::<MqttUser>(raw.as_slice()).unwrap();
                     if user.username == mqtt_user.username {
+                        assert!(mqtt_user.password.starts_with("$sha256$"));
                         flag = true;
                     }
                 }
@@ -110,4 +119,174 @@ mod tests {
             }
         };
     }
+
+    async fn connector_status(
+        client_pool: &ClientPool,
+        addrs: &[String],
+        connector_name: &str,
+    ) -> Option<MqttBridgeStatus> {
+        let request = ListConnectorRequest {
+            connector_name: connector_name.to_string(),
+        };
+        match mqtt_broker_list_connector(client_pool, addrs, request).await {
+            Ok(data) => data
+                .connectors
+                .iter()
+                .map(|raw| MqttBridgeRule::decode(raw).unwrap())
+                .find(|bridge| bridge.name == connector_name)
+                .map(|bridge| bridge.status),
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn connector_test() {
+        let client_pool: Arc<ClientPool> = Arc::new(ClientPool::new(3));
+        let addrs = vec![get_mqtt_broker_addr()];
+        let connector_name = "sensor_to_redis".to_string();
+
+        let bridge = MqttBridgeRule {
+            name: connector_name.clone(),
+            bridge_type: MqttBridgeType::Redis,
+            topic_filters: vec!["/sensor/+".to_string()],
+            batch_size: 100,
+            config: r#"{"addr":"127.0.0.1:6379","key_template":"${topic}"}"#.to_string(),
+            ..Default::default()
+        };
+        let request = CreateConnectorRequest {
+            connector: bridge.encode().unwrap(),
+        };
+        match mqtt_broker_create_connector(&client_pool, &addrs, request.clone()).await {
+            Ok(_) => {}
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+        assert!(
+            mqtt_broker_create_connector(&client_pool, &addrs, request)
+                .await
+                .is_err(),
+            "a connector name can only be used once"
+        );
+        assert_eq!(
+            connector_status(&client_pool, &addrs, &connector_name).await,
+            Some(MqttBridgeStatus::Running)
+        );
+
+        let request = PauseConnectorRequest {
+            connector_name: connector_name.clone(),
+        };
+        match mqtt_broker_pause_connector(&client_pool, &addrs, request).await {
+            Ok(_) => {}
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+        assert_eq!(
+            connector_status(&client_pool, &addrs, &connector_name).await,
+            Some(MqttBridgeStatus::Paused)
+        );
+
+        let request = ResumeConnectorRequest {
+            connector_name: connector_name.clone(),
+        };
+        match mqtt_broker_resume_connector(&client_pool, &addrs, request).await {
+            Ok(_) => {}
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+        assert_eq!(
+            connector_status(&client_pool, &addrs, &connector_name).await,
+            Some(MqttBridgeStatus::Running)
+        );
+
+        let request = DeleteConnectorRequest {
+            connector_name: connector_name.clone(),
+        };
+        match mqtt_broker_delete_connector(&client_pool, &addrs, request).await {
+            Ok(_) => {}
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+        assert_eq!(
+            connector_status(&client_pool, &addrs, &connector_name).await,
+            None
+        );
+    }
+
+    async fn rule_exists(client_pool: &ClientPool, addrs: &[String], rule_name: &str) -> bool {
+        let request = ListRuleRequest {
+            rule_name: rule_name.to_string(),
+        };
+        match mqtt_broker_list_rule(client_pool, addrs, request).await {
+            Ok(data) => data
+                .rules
+                .iter()
+                .any(|raw| MqttRule::decode(raw).unwrap().name == rule_name),
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn rule_test() {
+        let client_pool: Arc<ClientPool> = Arc::new(ClientPool::new(3));
+        let addrs = vec![get_mqtt_broker_addr()];
+        let rule_name = "high_temp".to_string();
+
+        let mut rule = MqttRule {
+            name: rule_name.clone(),
+            sql: "SELECT payload.temp AS t FROM".to_string(),
+            actions: vec![MqttRuleAction::Republish {
+                topic: "alerts/temp".to_string(),
+                qos: 1,
+                retain: false,
+            }],
+            ..Default::default()
+        };
+        let request = CreateRuleRequest {
+            rule: rule.encode().unwrap(),
+        };
+        assert!(
+            mqtt_broker_create_rule(&client_pool, &addrs, request)
+                .await
+                .is_err(),
+            "a rule with invalid SQL must be rejected"
+        );
+
+        rule.sql =
+            r#"SELECT payload.temp AS t FROM "sensors/+/data" WHERE payload.temp > 80"#.to_string();
+        let request = CreateRuleRequest {
+            rule: rule.encode().unwrap(),
+        };
+        match mqtt_broker_create_rule(&client_pool, &addrs, request.clone()).await {
+            Ok(_) => {}
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+        assert!(
+            mqtt_broker_create_rule(&client_pool, &addrs, request)
+                .await
+                .is_err(),
+            "a rule name can only be used once"
+        );
+        assert!(rule_exists(&client_pool, &addrs, &rule_name).await);
+
+        let request = DeleteRuleRequest {
+            rule_name: rule_name.clone(),
+        };
+        match mqtt_broker_delete_rule(&client_pool, &addrs, request).await {
+            Ok(_) => {}
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+        assert!(!rule_exists(&client_pool, &addrs, &rule_name).await);
+    }
 }
@@ -16,7 +16,9 @@ mod cluster_test;
 mod kv_test;
 mod mqtt_acl_test;
 mod mqtt_blacklist_test;
+mod mqtt_bridge_test;
 mod mqtt_last_will_test;
+mod mqtt_rule_test;
 mod mqtt_session_test;
 mod mqtt_share_sub_test;
 mod mqtt_topic_test;
@@ -0,0 +1,99 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_base::tools::now_second;
+    use grpc_clients::placement::mqtt::call::{create_bridge, delete_bridge, list_bridge};
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::bridge::{MqttBridgeRule, MqttBridgeStatus, MqttBridgeType};
+    use protocol::placement_center::placement_center_mqtt::{
+        CreateBridgeRequest, DeleteBridgeRequest, ListBridgeRequest,
+    };
+
+    use crate::common::get_placement_addr;
+
+    #[tokio::test]
+    async fn mqtt_bridge_test() {
+        let client_pool: Arc<ClientPool> = Arc::new(ClientPool::new(3));
+        let addrs = vec![get_placement_addr()];
+        let cluster_name: String = "test_cluster".to_string();
+
+        let bridge = MqttBridgeRule {
+            name: "sensor_to_redis".to_string(),
+            bridge_type: MqttBridgeType::Redis,
+            topic_filters: vec!["/sensor/+".to_string()],
+            batch_size: 100,
+            config: r#"{"addr":"127.0.0.1:6379","key_template":"${topic}"}"#.to_string(),
+            status: MqttBridgeStatus::Running,
+            create_time: now_second(),
+        };
+
+        let request = CreateBridgeRequest {
+            cluster_name: cluster_name.clone(),
+            bridge: bridge.encode().unwrap(),
+        };
+        match create_bridge(&client_pool, &addrs, request).await {
+            Ok(_) => {}
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+
+        let request = ListBridgeRequest {
+            cluster_name: cluster_name.clone(),
+        };
+        match list_bridge(&client_pool, &addrs, request).await {
+            Ok(data) => {
+                let flag = data
+                    .bridges
+                    .iter()
+                    .any(|raw| MqttBridgeRule::decode(raw).unwrap() == bridge);
+                assert!(flag);
+            }
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+
+        let request = DeleteBridgeRequest {
+            cluster_name: cluster_name.clone(),
+            bridge_name: bridge.name.clone(),
+        };
+        match delete_bridge(&client_pool, &addrs, request).await {
+            Ok(_) => {}
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+
+        let request = ListBridgeRequest {
+            cluster_name: cluster_name.clone(),
+        };
+        match list_bridge(&client_pool, &addrs, request).await {
+            Ok(data) => {
+                let flag = data
+                    .bridges
+                    .iter()
+                    .any(|raw| MqttBridgeRule::decode(raw).unwrap().name == bridge.name);
+                assert!(!flag);
+            }
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+    }
+}
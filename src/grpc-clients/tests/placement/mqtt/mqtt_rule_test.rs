@@ -0,0 +1,100 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_base::tools::now_second;
+    use grpc_clients::placement::mqtt::call::{create_rule, delete_rule, list_rule};
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::rule::{MqttRule, MqttRuleAction};
+    use protocol::placement_center::placement_center_mqtt::{
+        CreateRuleRequest, DeleteRuleRequest, ListRuleRequest,
+    };
+
+    use crate::common::get_placement_addr;
+
+    #[tokio::test]
+    async fn mqtt_rule_test() {
+        let client_pool: Arc<ClientPool> = Arc::new(ClientPool::new(3));
+        let addrs = vec![get_placement_addr()];
+        let cluster_name: String = "test_cluster".to_string();
+
+        let rule = MqttRule {
+            name: "high_temp".to_string(),
+            sql: r#"SELECT payload.temp AS t, clientid FROM "sensors/+/data" WHERE payload.temp > 80"#
+                .to_string(),
+            actions: vec![MqttRuleAction::Republish {
+                topic: "alerts/${clientid}".to_string(),
+                qos: 1,
+                retain: false,
+            }],
+            create_time: now_second(),
+        };
+
+        let request = CreateRuleRequest {
+            cluster_name: cluster_name.clone(),
+            rule: rule.encode().unwrap(),
+        };
+        match create_rule(&client_pool, &addrs, request).await {
+            Ok(_) => {}
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+
+        let request = ListRuleRequest {
+            cluster_name: cluster_name.clone(),
+        };
+        match list_rule(&client_pool, &addrs, request).await {
+            Ok(data) => {
+                let flag = data
+                    .rules
+                    .iter()
+                    .any(|raw| MqttRule::decode(raw).unwrap() == rule);
+                assert!(flag);
+            }
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+
+        let request = DeleteRuleRequest {
+            cluster_name: cluster_name.clone(),
+            rule_name: rule.name.clone(),
+        };
+        match delete_rule(&client_pool, &addrs, request).await {
+            Ok(_) => {}
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+
+        let request = ListRuleRequest {
+            cluster_name: cluster_name.clone(),
+        };
+        match list_rule(&client_pool, &addrs, request).await {
+            Ok(data) => {
+                let flag = data
+                    .rules
+                    .iter()
+                    .any(|raw| MqttRule::decode(raw).unwrap().name == rule.name);
+                assert!(!flag);
+            }
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+    }
+}
@@ -59,6 +59,13 @@ impl MetadataCache {
         None
     }
 
+    pub fn get_quic_addr_by_node_id(&self, node_id: u64) -> Option<String> {
+        if let Some(node) = self.nodes.get(&node_id) {
+            return Some(node.quic_addr.clone());
+        }
+        None
+    }
+
     pub fn get_shard(&self, namespace: &str, shard: &str) -> Option<GetShardMetadataRespShard> {
         if let Some(shard) = self.shards.get(&shard_name_iden(namespace, shard)) {
             return Some(shard.clone());
@@ -29,6 +29,8 @@ use crate::async_reader::{
 };
 use crate::async_writer::{AsyncWriter, SenderMessage, SenderMessageResp};
 use crate::cache::get_active_segment;
+use crate::option::{options_validator, JournalClientOption, JournalTransport};
+use crate::quic::QuicConnector;
 use crate::service::{create_shard, delete_shard};
 
 #[derive(Default, Clone)]
@@ -49,8 +51,31 @@ pub struct JournalClient {
 
 impl JournalClient {
     pub fn new(addrs: Vec<String>) -> Self {
-        let metadata_cache = Arc::new(MetadataCache::new(addrs));
-        let connection_manager = Arc::new(ConnectionManager::new(metadata_cache.clone()));
+        let mut option = JournalClientOption::build();
+        option.set_addrs(addrs);
+        // tcp needs no extra setup, so building the client can not fail
+        JournalClient::build(option).unwrap()
+    }
+
+    pub fn with_option(option: JournalClientOption) -> Result<Self, JournalClientError> {
+        options_validator(&option)?;
+        JournalClient::build(option)
+    }
+
+    fn build(option: JournalClientOption) -> Result<Self, JournalClientError> {
+        let quic_connector = match option.transport {
+            JournalTransport::Tcp => None,
+            JournalTransport::Quic => Some(Arc::new(QuicConnector::from_ca_file(
+                &option.tls_ca,
+                option.tls_server_name.clone(),
+            )?)),
+        };
+
+        let metadata_cache = Arc::new(MetadataCache::new(option.addrs));
+        let connection_manager = Arc::new(ConnectionManager::new(
+            metadata_cache.clone(),
+            quic_connector,
+        ));
         let (stop_send, _) = broadcast::channel::<bool>(2);
         let writer = Arc::new(AsyncWriter::new(
             connection_manager.clone(),
@@ -62,13 +87,13 @@ impl JournalClient {
             connection_manager.clone(),
         ));
 
-        JournalClient {
+        Ok(JournalClient {
             metadata_cache,
             connection_manager,
             writer,
             reader,
             stop_send,
-        }
+        })
     }
 
     pub async fn connect(&self) -> Result<(), JournalClientError> {
@@ -21,32 +21,51 @@ use dashmap::DashMap;
 use futures::{SinkExt, StreamExt};
 use log::error;
 use protocol::journal_server::codec::{JournalEnginePacket, JournalServerCodec};
+use quinn::{Connection, VarInt};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpStream;
 use tokio::select;
 use tokio::sync::broadcast::Receiver;
+use tokio::sync::Mutex;
 use tokio::time::sleep;
 use tokio_util::codec::Framed;
 
 use crate::cache::MetadataCache;
 use crate::error::JournalClientError;
+use crate::quic::{QuicConnector, QuicStream};
+
+pub trait ClientIo: AsyncRead + AsyncWrite + Unpin + Send + Sync {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + Sync> ClientIo for T {}
+
+pub type ClientStream = Box<dyn ClientIo>;
 
 pub struct ClientConnection {
-    pub stream: Framed<TcpStream, JournalServerCodec>,
+    pub stream: Framed<ClientStream, JournalServerCodec>,
     pub last_active_time: u64,
 }
 
 pub struct NodeConnection {
     node_id: u64,
     metadata_cache: Arc<MetadataCache>,
+    quic_connector: Option<Arc<QuicConnector>>,
+    // Shared by the admin, write and read streams when QUIC is used
+    quic_connection: Mutex<Option<Connection>>,
     connection: DashMap<String, ClientConnection>,
 }
 
 impl NodeConnection {
-    pub fn new(node_id: u64, metadata_cache: Arc<MetadataCache>) -> Self {
+    pub fn new(
+        node_id: u64,
+        metadata_cache: Arc<MetadataCache>,
+        quic_connector: Option<Arc<QuicConnector>>,
+    ) -> Self {
         let connection = DashMap::with_capacity(2);
         NodeConnection {
             node_id,
             metadata_cache,
+            quic_connector,
+            quic_connection: Mutex::new(None),
             connection,
         }
     }
@@ -142,7 +161,12 @@ impl NodeConnection {
         }
     }
 
-    async fn open(&self) -> Result<Framed<TcpStream, JournalServerCodec>, JournalClientError> {
+    async fn open(&self) -> Result<Framed<ClientStream, JournalServerCodec>, JournalClientError> {
+        if let Some(quic_connector) = &self.quic_connector {
+            let stream = self.open_quic_stream(quic_connector).await?;
+            return Ok(Framed::new(Box::new(stream), JournalServerCodec::new()));
+        }
+
         let addr = if let Some(addr) = self.metadata_cache.get_tcp_addr_by_node_id(self.node_id) {
             addr
         } else {
@@ -150,7 +174,34 @@ impl NodeConnection {
         };
 
         let socket = TcpStream::connect(&addr).await?;
-        Ok(Framed::new(socket, JournalServerCodec::new()))
+        Ok(Framed::new(Box::new(socket), JournalServerCodec::new()))
+    }
+
+    async fn open_quic_stream(
+        &self,
+        quic_connector: &QuicConnector,
+    ) -> Result<QuicStream, JournalClientError> {
+        let mut quic_connection = self.quic_connection.lock().await;
+        if let Some(conn) = quic_connection.as_ref() {
+            if conn.close_reason().is_none() {
+                return QuicStream::open(conn).await;
+            }
+        }
+
+        let addr = match self.metadata_cache.get_quic_addr_by_node_id(self.node_id) {
+            Some(addr) if !addr.is_empty() => addr,
+            _ => return Err(JournalClientError::NodeNoAvailableAddr(self.node_id)),
+        };
+        let conn = quic_connector.connect(&addr).await?;
+        let stream = QuicStream::open(&conn).await?;
+        *quic_connection = Some(conn);
+        Ok(stream)
+    }
+
+    async fn close_quic(&self) {
+        if let Some(conn) = self.quic_connection.lock().await.take() {
+            conn.close(VarInt::from_u32(0), b"");
+        }
     }
 
     pub async fn init_conn(&self) -> Result<(), JournalClientError> {
@@ -186,16 +237,21 @@ pub struct ConnectionManager {
     node_conns: DashMap<u64, NodeConnection>,
     metadata_cache: Arc<MetadataCache>,
     admin_conn_atom: AtomicU64,
+    quic_connector: Option<Arc<QuicConnector>>,
 }
 
 impl ConnectionManager {
-    pub fn new(metadata_cache: Arc<MetadataCache>) -> Self {
+    pub fn new(
+        metadata_cache: Arc<MetadataCache>,
+        quic_connector: Option<Arc<QuicConnector>>,
+    ) -> Self {
         let node_conns = DashMap::with_capacity(2);
         let admin_conn_atom = AtomicU64::new(0);
         ConnectionManager {
             node_conns,
             metadata_cache,
             admin_conn_atom,
+            quic_connector,
         }
     }
 
@@ -206,7 +262,11 @@ impl ConnectionManager {
         let node_id = self.choose_admin_node();
 
         if !self.node_conns.contains_key(&node_id) {
-            let conn = NodeConnection::new(node_id, self.metadata_cache.clone());
+            let conn = NodeConnection::new(
+                node_id,
+                self.metadata_cache.clone(),
+                self.quic_connector.clone(),
+            );
             conn.init_conn().await?;
             self.node_conns.insert(node_id, conn);
         }
@@ -221,7 +281,11 @@ impl ConnectionManager {
         req_packet: JournalEnginePacket,
     ) -> Result<JournalEnginePacket, JournalClientError> {
         if !self.node_conns.contains_key(&node_id) {
-            let conn = NodeConnection::new(node_id, self.metadata_cache.clone());
+            let conn = NodeConnection::new(
+                node_id,
+                self.metadata_cache.clone(),
+                self.quic_connector.clone(),
+            );
             conn.init_conn().await?;
             self.node_conns.insert(node_id, conn);
         }
@@ -236,7 +300,11 @@ impl ConnectionManager {
         req_packet: JournalEnginePacket,
     ) -> Result<JournalEnginePacket, JournalClientError> {
         if !self.node_conns.contains_key(&node_id) {
-            let conn = NodeConnection::new(node_id, self.metadata_cache.clone());
+            let conn = NodeConnection::new(
+                node_id,
+                self.metadata_cache.clone(),
+                self.quic_connector.clone(),
+            );
             conn.init_conn().await?;
             self.node_conns.insert(node_id, conn);
         }
@@ -285,6 +353,7 @@ impl ConnectionManager {
                     error!("{}", e);
                 }
             }
+            node.close_quic().await;
         }
     }
 }
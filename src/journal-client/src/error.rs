@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use common_base::error::common::CommonError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -31,6 +32,9 @@ pub enum JournalClientError {
     #[error("{0}")]
     MpscSendErrorBool(#[from] tokio::sync::mpsc::error::SendError<bool>),
 
+    #[error("{0}")]
+    CommonError(#[from] CommonError),
+
     #[error("Node {0} has no available access address, may be cache data inconsistency, ready to trigger update node cache.")]
     NodeNoAvailableAddr(u64),
 
@@ -72,4 +76,13 @@ pub enum JournalClientError {
 
     #[error("The write request returns empty")]
     WriteReqReturnTmpty,
+
+    #[error("{0}")]
+    QuicConnectError(#[from] quinn::ConnectError),
+
+    #[error("{0}")]
+    QuicConnectionError(#[from] quinn::ConnectionError),
+
+    #[error("Invalid QUIC transport config, error message: {0}")]
+    QuicConfigError(String),
 }
@@ -14,10 +14,24 @@
 
 use common_base::error::common::CommonError;
 
+// Transport used between the client and the journal servers.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub enum JournalTransport {
+    #[default]
+    Tcp,
+    // One QUIC connection per node, with a stream for each of admin, write and read.
+    Quic,
+}
+
 #[derive(Default, Clone)]
 pub struct JournalClientOption {
     pub addrs: Vec<String>,
     pub line_ms: u64,
+    pub transport: JournalTransport,
+    // CA bundle (PEM) that signed the server certificates, required for QUIC
+    pub tls_ca: String,
+    // Name checked against the server certificate, defaults to the node host
+    pub tls_server_name: String,
 }
 
 impl JournalClientOption {
@@ -31,6 +45,18 @@ impl JournalClientOption {
     pub fn set_addrs(&mut self, addrs: Vec<String>) {
         self.addrs = addrs;
     }
+
+    pub fn set_transport(&mut self, transport: JournalTransport) {
+        self.transport = transport;
+    }
+
+    pub fn set_tls_ca(&mut self, tls_ca: String) {
+        self.tls_ca = tls_ca;
+    }
+
+    pub fn set_tls_server_name(&mut self, tls_server_name: String) {
+        self.tls_server_name = tls_server_name;
+    }
 }
 
 pub fn options_validator(option: &JournalClientOption) -> Result<(), CommonError> {
@@ -39,5 +65,10 @@ pub fn options_validator(option: &JournalClientOption) -> Result<(), CommonError
             "option.addrs".to_string(),
         ));
     }
+    if option.transport == JournalTransport::Quic && option.tls_ca.is_empty() {
+        return Err(CommonError::ParameterCannotBeNull(
+            "option.tls_ca".to_string(),
+        ));
+    }
     Ok(())
 }
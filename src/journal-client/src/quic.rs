@@ -0,0 +1,234 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use quinn::crypto::rustls::QuicClientConfig;
+use quinn::{Connection, Endpoint, RecvStream, SendStream};
+use rustls_pemfile::certs;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::lookup_host;
+use tokio_rustls::rustls::pki_types::CertificateDer;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+
+use crate::error::JournalClientError;
+
+pub const JOURNAL_QUIC_ALPN: &[u8] = b"journal";
+
+pub struct QuicConnector {
+    endpoint: Endpoint,
+    server_name: String,
+}
+
+impl QuicConnector {
+    pub fn new(
+        ca_certs: Vec<CertificateDer<'static>>,
+        server_name: String,
+    ) -> Result<Self, JournalClientError> {
+        let mut roots = RootCertStore::empty();
+        for cert in ca_certs {
+            roots
+                .add(cert)
+                .map_err(|e| JournalClientError::QuicConfigError(e.to_string()))?;
+        }
+        let mut tls_config = ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        tls_config.alpn_protocols = vec![JOURNAL_QUIC_ALPN.to_vec()];
+        let crypto = QuicClientConfig::try_from(tls_config)
+            .map_err(|e| JournalClientError::QuicConfigError(e.to_string()))?;
+
+        let mut endpoint = Endpoint::client(SocketAddr::from(([0, 0, 0, 0], 0)))?;
+        endpoint.set_default_client_config(quinn::ClientConfig::new(Arc::new(crypto)));
+        Ok(QuicConnector {
+            endpoint,
+            server_name,
+        })
+    }
+
+    pub fn from_ca_file(ca_path: &str, server_name: String) -> Result<Self, JournalClientError> {
+        let ca_certs =
+            certs(&mut BufReader::new(File::open(ca_path)?)).collect::<Result<Vec<_>, _>>()?;
+        QuicConnector::new(ca_certs, server_name)
+    }
+
+    pub async fn connect(&self, addr: &str) -> Result<Connection, JournalClientError> {
+        let socket_addr = match lookup_host(addr).await?.next() {
+            Some(socket_addr) => socket_addr,
+            None => {
+                return Err(JournalClientError::QuicConfigError(format!(
+                    "address {} can not be resolved",
+                    addr
+                )))
+            }
+        };
+        // The certificate is checked against the node host when no name is configured.
+        let server_name = if self.server_name.is_empty() {
+            addr_host(addr)
+        } else {
+            self.server_name.clone()
+        };
+        Ok(self.endpoint.connect(socket_addr, &server_name)?.await?)
+    }
+}
+
+// Host of a host:port address, IPv6 literals are returned without their brackets.
+fn addr_host(addr: &str) -> String {
+    if let Ok(socket_addr) = addr.parse::<SocketAddr>() {
+        return socket_addr.ip().to_string();
+    }
+    if let Ok(ip) = addr.parse::<IpAddr>() {
+        return ip.to_string();
+    }
+    match addr.rsplit_once(':') {
+        Some((host, _)) => host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string(),
+        None => addr.to_string(),
+    }
+}
+
+// One bidirectional QUIC stream, framed like a tcp connection.
+pub struct QuicStream {
+    send: SendStream,
+    recv: RecvStream,
+}
+
+impl QuicStream {
+    pub async fn open(connection: &Connection) -> Result<Self, JournalClientError> {
+        let (send, recv) = connection.open_bi().await?;
+        Ok(QuicStream { send, recv })
+    }
+}
+
+impl AsyncRead for QuicStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.recv).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for QuicStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        AsyncWrite::poll_write(Pin::new(&mut self.send), cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.send).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.send).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use quinn::crypto::rustls::QuicServerConfig;
+    use quinn::Endpoint;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+    use tokio_rustls::rustls::ServerConfig;
+
+    use super::{addr_host, QuicConnector, QuicStream, JOURNAL_QUIC_ALPN};
+
+    // Echoes every stream back until the client finishes it.
+    fn start_echo_server(
+        cert: CertificateDer<'static>,
+        key: PrivateKeyDer<'static>,
+    ) -> std::net::SocketAddr {
+        let mut tls_config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert], key)
+            .unwrap();
+        tls_config.alpn_protocols = vec![JOURNAL_QUIC_ALPN.to_vec()];
+        let server_config = quinn::ServerConfig::with_crypto(Arc::new(
+            QuicServerConfig::try_from(tls_config).unwrap(),
+        ));
+        let endpoint = Endpoint::server(server_config, "127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = endpoint.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Some(incoming) = endpoint.accept().await {
+                let conn = incoming.await.unwrap();
+                tokio::spawn(async move {
+                    while let Ok((mut send, mut recv)) = conn.accept_bi().await {
+                        tokio::spawn(async move {
+                            let data = recv.read_to_end(1024).await.unwrap();
+                            send.write_all(&data).await.unwrap();
+                            send.finish().unwrap();
+                        });
+                    }
+                });
+            }
+        });
+        addr
+    }
+
+    #[test]
+    fn addr_host_test() {
+        assert_eq!(addr_host("localhost:1228"), "localhost");
+        assert_eq!(addr_host("127.0.0.1:1228"), "127.0.0.1");
+        assert_eq!(addr_host("[::1]:1228"), "::1");
+        assert_eq!(addr_host("[fe80::1]:1228"), "fe80::1");
+        assert_eq!(addr_host("::1"), "::1");
+        assert_eq!(addr_host("localhost"), "localhost");
+    }
+
+    #[tokio::test]
+    async fn quic_connector_streams_test() {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert_der: CertificateDer<'static> = cert.cert.der().clone();
+        let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(cert.key_pair.serialize_der()));
+        let addr = start_echo_server(cert_der.clone(), key);
+
+        // the node address is an ip, so the certificate name has to be given explicitly
+        let connector =
+            QuicConnector::new(vec![cert_der.clone()], "localhost".to_string()).unwrap();
+        let conn = connector.connect(&addr.to_string()).await.unwrap();
+
+        // streams of one connection are independent of each other
+        let mut write_stream = QuicStream::open(&conn).await.unwrap();
+        let mut read_stream = QuicStream::open(&conn).await.unwrap();
+        read_stream.write_all(b"read").await.unwrap();
+        read_stream.shutdown().await.unwrap();
+        write_stream.write_all(b"write").await.unwrap();
+        write_stream.shutdown().await.unwrap();
+
+        let mut buf = Vec::new();
+        read_stream.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"read");
+        let mut buf = Vec::new();
+        write_stream.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"write");
+
+        // the ip of the node does not match the certificate
+        let connector = QuicConnector::new(vec![cert_der], "".to_string()).unwrap();
+        assert!(connector.connect(&addr.to_string()).await.is_err());
+    }
+}
@@ -44,6 +44,7 @@ pub async fn register_journal_node(
         data_fold: conf.storage.data_path.clone(),
         tcp_addr: format!("{}:{}", get_local_ip(), conf.network.tcp_port),
         tcps_addr: format!("{}:{}", get_local_ip(), conf.network.tcps_port),
+        quic_addr: format!("{}:{}", get_local_ip(), conf.network.quic_port),
     };
 
     let req = RegisterNodeRequest {
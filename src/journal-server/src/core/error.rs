@@ -55,6 +55,9 @@ pub enum JournalServerError {
     #[error("{0}")]
     ParseIntError(#[from] ParseIntError),
 
+    #[error("Failed to build the QUIC server config, error message: {0}")]
+    QuicConfigError(String),
+
     #[error("{0} request body cannot be empty")]
     RequestBodyNotEmpty(String),
 
@@ -38,6 +38,7 @@ impl ClusterHandler {
                 node_id: node.node_id,
                 tcp_addr: journal_extend.tcp_addr,
                 tcps_addr: journal_extend.tcps_addr,
+                quic_addr: journal_extend.quic_addr,
             });
         }
         Ok(result)
@@ -34,6 +34,7 @@ use segment::manager::{
 use segment::scroll::SegmentScrollManager;
 use server::connection_manager::ConnectionManager;
 use server::grpc::server::GrpcServer;
+use server::quic::server::start_quic_server;
 use server::tcp::server::start_tcp_server;
 use tokio::runtime::Runtime;
 use tokio::signal;
@@ -105,6 +106,8 @@ impl JournalServer {
 
         self.start_tcp_server();
 
+        self.start_quic_server();
+
         self.start_prometheus();
 
         self.init_node();
@@ -154,6 +157,28 @@ impl JournalServer {
         });
     }
 
+    fn start_quic_server(&self) {
+        let client_pool = self.client_pool.clone();
+        let connection_manager = self.connection_manager.clone();
+        let cache_manager = self.cache_manager.clone();
+        let stop_sx = self.stop_send.clone();
+        let offset_manager = self.offset_manager.clone();
+        let segment_file_manager = self.segment_file_manager.clone();
+        let rocksdb_engine_handler = self.rocksdb_engine_handler.clone();
+        self.server_runtime.spawn(async {
+            start_quic_server(
+                client_pool,
+                connection_manager,
+                cache_manager,
+                offset_manager,
+                segment_file_manager,
+                rocksdb_engine_handler,
+                stop_sx,
+            )
+            .await;
+        });
+    }
+
     fn start_prometheus(&self) {
         if self.config.prometheus.enable {
             let prometheus_port = self.config.prometheus.port;
@@ -24,6 +24,7 @@ static CONNECTION_ID_BUILD: AtomicU64 = AtomicU64::new(1);
 pub enum NetworkConnectionType {
     Tcp,
     Tls,
+    Quic,
 }
 
 impl fmt::Display for NetworkConnectionType {
@@ -34,6 +35,7 @@ impl fmt::Display for NetworkConnectionType {
             match self {
                 NetworkConnectionType::Tcp => "tcp",
                 NetworkConnectionType::Tls => "tls",
+                NetworkConnectionType::Quic => "quic",
             }
         )
     }
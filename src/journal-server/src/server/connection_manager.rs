@@ -19,6 +19,7 @@ use dashmap::DashMap;
 use futures::SinkExt;
 use log::{debug, error, info};
 use protocol::journal_server::codec::{JournalEnginePacket, JournalServerCodec};
+use tokio::io::AsyncWrite;
 use tokio::time::sleep;
 use tokio_util::codec::FramedWrite;
 
@@ -35,6 +36,7 @@ pub struct ConnectionManager {
             JournalServerCodec,
         >,
     >,
+    quic_write_list: DashMap<u64, FramedWrite<quinn::SendStream, JournalServerCodec>>,
 }
 
 impl ConnectionManager {
@@ -45,10 +47,12 @@ impl ConnectionManager {
             FramedWrite<tokio::io::WriteHalf<tokio::net::TcpStream>, JournalServerCodec>,
         > = DashMap::with_capacity(64);
         let tcp_tls_write_list = DashMap::with_capacity(64);
+        let quic_write_list = DashMap::with_capacity(64);
         ConnectionManager {
             connections,
             tcp_write_list,
             tcp_tls_write_list,
+            quic_write_list,
         }
     }
 
@@ -77,6 +81,14 @@ impl ConnectionManager {
         self.tcp_tls_write_list.insert(connection_id, write);
     }
 
+    pub fn add_quic_write(
+        &self,
+        connection_id: u64,
+        write: FramedWrite<quinn::SendStream, JournalServerCodec>,
+    ) {
+        self.quic_write_list.insert(connection_id, write);
+    }
+
     pub async fn _close_all_connect(&self) {
         for (connect_id, _) in self.connections.clone() {
             self.close_connect(connect_id).await;
@@ -111,6 +123,18 @@ impl ConnectionManager {
                 Err(e) => error!("{}", e),
             }
         }
+
+        if let Some((id, mut stream)) = self.quic_write_list.remove(&connection_id) {
+            match stream.close().await {
+                Ok(_) => {
+                    info!(
+                        "server closes the quic stream actively, connection id [{}]",
+                        id
+                    );
+                }
+                Err(e) => error!("{}", e),
+            }
+        }
     }
 
     pub async fn write_tcp_frame(
@@ -120,69 +144,39 @@ impl ConnectionManager {
     ) -> Result<(), CommonError> {
         debug!("response packet:{resp:?},connection_id:{connection_id}");
 
-        // write tls stream
         if let Some(connection) = self.get_connect(connection_id) {
+            // write tls stream
             if connection.connection_type == NetworkConnectionType::Tls {
-                return self.write_tcp_tls_frame(connection_id, resp).await;
+                return self
+                    .write_frame(&self.tcp_tls_write_list, connection_id, resp)
+                    .await;
             }
-        }
-
-        let mut times = 0;
-        let response_max_try_mut_times = 5;
-        let response_try_mut_sleep_time_ms = 1000;
-        loop {
-            match self.tcp_write_list.try_get_mut(&connection_id) {
-                dashmap::try_result::TryResult::Present(mut da) => {
-                    match da.send(resp.clone()).await {
-                        Ok(_) => {
-                            break;
-                        }
-                        Err(e) => {
-                            if times > response_max_try_mut_times {
-                                return Err(CommonError::CommonError(format!(
-                                    "Failed to write data to the Journal engine client, error message: {e:?}"
-                                )));
-                            }
-                        }
-                    }
-                }
-                dashmap::try_result::TryResult::Absent => {
-                    if times > response_max_try_mut_times {
-                        return Err(CommonError::CommonError(
-                            format!(
-                                "[write_frame]Connection management could not obtain an available tcp connection. Connection ID: {},len:{}",
-                                connection_id,
-                                self.tcp_write_list.len()
-                            )
-                        ));
-                    }
-                }
-                dashmap::try_result::TryResult::Locked => {
-                    if times > response_max_try_mut_times {
-                        return Err(CommonError::CommonError(
-                            format!(
-                                "[write_frame]Connection management failed to get tcp connection variable reference, connection ID: {}",connection_id
-                            )
-                        ));
-                    }
-                }
+            // write quic stream
+            if connection.connection_type == NetworkConnectionType::Quic {
+                return self
+                    .write_frame(&self.quic_write_list, connection_id, resp)
+                    .await;
             }
-            times += 1;
-            sleep(Duration::from_millis(response_try_mut_sleep_time_ms)).await
         }
-        Ok(())
+
+        self.write_frame(&self.tcp_write_list, connection_id, resp)
+            .await
     }
 
-    async fn write_tcp_tls_frame(
+    async fn write_frame<T>(
         &self,
+        write_list: &DashMap<u64, FramedWrite<T, JournalServerCodec>>,
         connection_id: u64,
         resp: JournalEnginePacket,
-    ) -> Result<(), CommonError> {
+    ) -> Result<(), CommonError>
+    where
+        T: AsyncWrite + Unpin,
+    {
         let mut times = 0;
         let response_max_try_mut_times = 5;
         let response_try_mut_sleep_time_ms = 1000;
         loop {
-            match self.tcp_tls_write_list.try_get_mut(&connection_id) {
+            match write_list.try_get_mut(&connection_id) {
                 dashmap::try_result::TryResult::Present(mut da) => {
                     match da.send(resp.clone()).await {
                         Ok(_) => {
@@ -191,7 +185,7 @@ impl ConnectionManager {
                         Err(e) => {
                             if times > response_max_try_mut_times {
                                 return Err(CommonError::CommonError(format!(
-                                    "Failed to write data to the journal engine client, error message: {e:?}"
+                                    "Failed to write data to the Journal engine client, error message: {e:?}"
                                 )));
                             }
                         }
@@ -203,7 +197,7 @@ impl ConnectionManager {
                             format!(
                                 "[write_frame]Connection management could not obtain an available tcp connection. Connection ID: {},len:{}",
                                 connection_id,
-                                self.tcp_write_list.len()
+                                write_list.len()
                             )
                         ));
                     }
@@ -0,0 +1,343 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use log::{debug, error, info};
+use protocol::journal_server::codec::JournalServerCodec;
+use quinn::crypto::rustls::QuicServerConfig;
+use quinn::{Connection, Endpoint, RecvStream, TransportConfig, VarInt};
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::ServerConfig;
+use tokio_util::codec::{FramedRead, FramedWrite};
+
+use crate::core::error::JournalServerError;
+use crate::server::connection::{NetworkConnection, NetworkConnectionType};
+use crate::server::connection_manager::ConnectionManager;
+use crate::server::packet::RequestPackage;
+
+pub const JOURNAL_QUIC_ALPN: &[u8] = b"journal";
+
+// Clients open one stream per request type (admin, write, read), leave room for
+// streams that are reopened while the old ones are still being torn down.
+const MAX_CONCURRENT_STREAMS: u32 = 16;
+
+pub(crate) fn build_quic_server_config(
+    certs: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+) -> Result<quinn::ServerConfig, JournalServerError> {
+    let mut tls_config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| JournalServerError::QuicConfigError(e.to_string()))?;
+    tls_config.alpn_protocols = vec![JOURNAL_QUIC_ALPN.to_vec()];
+
+    let crypto = QuicServerConfig::try_from(tls_config)
+        .map_err(|e| JournalServerError::QuicConfigError(e.to_string()))?;
+
+    let mut transport = TransportConfig::default();
+    transport
+        .max_concurrent_bidi_streams(VarInt::from_u32(MAX_CONCURRENT_STREAMS))
+        .max_concurrent_uni_streams(VarInt::from_u32(0));
+
+    let mut config = quinn::ServerConfig::with_crypto(Arc::new(crypto));
+    config.transport_config(Arc::new(transport));
+    Ok(config)
+}
+
+pub(crate) async fn acceptor_quic_process(
+    endpoint: Endpoint,
+    stop_sx: broadcast::Sender<bool>,
+    connection_manager: Arc<ConnectionManager>,
+    request_queue_sx: Sender<RequestPackage>,
+) {
+    let mut stop_rx = stop_sx.subscribe();
+    tokio::spawn(async move {
+        debug!("QUIC Server acceptor thread start successfully.");
+        loop {
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            endpoint.close(VarInt::from_u32(0), b"server stopped");
+                            debug!("QUIC Server acceptor thread stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                val = endpoint.accept()=>{
+                    let Some(incoming) = val else {
+                        break;
+                    };
+                    let connection_manager = connection_manager.clone();
+                    let request_queue_sx = request_queue_sx.clone();
+                    let stop_rx = stop_sx.subscribe();
+                    tokio::spawn(async move {
+                        match incoming.await {
+                            Ok(quic_connection) => {
+                                info!("accept quic connection:{:?}",quic_connection.remote_address());
+                                accept_stream_process(quic_connection,connection_manager,request_queue_sx,stop_rx).await;
+                            }
+                            Err(e) => {
+                                error!("QUIC accept failed to create connection with error message :{:?}",e);
+                            }
+                        }
+                    });
+                }
+            };
+        }
+    });
+}
+
+// Every bidirectional stream is registered as its own connection, so a large read
+// response never waits behind a write on the same QUIC connection.
+async fn accept_stream_process(
+    quic_connection: Connection,
+    connection_manager: Arc<ConnectionManager>,
+    request_queue_sx: Sender<RequestPackage>,
+    mut stop_rx: broadcast::Receiver<bool>,
+) {
+    let addr = quic_connection.remote_address();
+    let mut stream_connection_ids = Vec::new();
+    loop {
+        select! {
+            val = stop_rx.recv() =>{
+                if let Ok(flag) = val {
+                    if flag {
+                        break;
+                    }
+                }
+            }
+            val = quic_connection.accept_bi()=>{
+                match val {
+                    Ok((send_stream, recv_stream)) => {
+                        let codec = JournalServerCodec::new();
+                        let read_frame_stream = FramedRead::new(recv_stream, codec.clone());
+                        let write_frame_stream = FramedWrite::new(send_stream, codec);
+
+                        let (connection_stop_sx, connection_stop_rx) = mpsc::channel::<bool>(1);
+                        let connection = NetworkConnection::new(
+                            NetworkConnectionType::Quic,
+                            addr,
+                            Some(connection_stop_sx)
+                        );
+                        connection_manager.add_connection(connection.clone());
+                        connection_manager.add_quic_write(connection.connection_id, write_frame_stream);
+                        stream_connection_ids.push(connection.connection_id);
+
+                        read_quic_frame_process(read_frame_stream,connection,connection_manager.clone(),request_queue_sx.clone(),connection_stop_rx);
+                    }
+                    Err(e) => {
+                        debug!("QUIC connection from {} closed, reason: {}",addr,e);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    for connection_id in stream_connection_ids {
+        connection_manager.close_connect(connection_id).await;
+    }
+    quic_connection.close(VarInt::from_u32(0), b"");
+}
+
+fn read_quic_frame_process(
+    mut read_frame_stream: FramedRead<RecvStream, JournalServerCodec>,
+    connection: NetworkConnection,
+    connection_manager: Arc<ConnectionManager>,
+    request_queue_sx: Sender<RequestPackage>,
+    mut connection_stop_rx: Receiver<bool>,
+) {
+    tokio::spawn(async move {
+        loop {
+            select! {
+                val = connection_stop_rx.recv() =>{
+                    if let Some(flag) = val{
+                        if flag {
+                            debug!("QUIC stream 【{}】 acceptor thread stopped successfully.",connection.connection_id);
+                            break;
+                        }
+                    }
+                }
+                val = read_frame_stream.next()=>{
+                    match val {
+                        Some(Ok(pack)) => {
+                            debug!("revc quic packet:{:?}", pack);
+                            let package =
+                                RequestPackage::new(connection.connection_id, connection.addr, pack);
+
+                            if let Err(err) = request_queue_sx.send(package).await {
+                                error!("Failed to write data to the request queue, error message: {:?}",err);
+                            }
+                        }
+                        Some(Err(e)) => {
+                            debug!("QUIC stream parsing packet format error message :{:?}",e)
+                        }
+                        None => {
+                            debug!("QUIC stream 【{}】 finished by the client.",connection.connection_id);
+                            connection_manager.close_connect(connection.connection_id).await;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use futures::SinkExt;
+    use futures_util::StreamExt;
+    use protocol::journal_server::codec::{JournalEnginePacket, JournalServerCodec};
+    use protocol::journal_server::journal_engine::{
+        ApiKey, ApiVersion, ReadReq, ReadReqBody, ReadResp, ReadRespBody, ReqHeader, RespHeader,
+        WriteReq, WriteReqBody,
+    };
+    use quinn::crypto::rustls::QuicClientConfig;
+    use quinn::Endpoint;
+    use tokio::sync::{broadcast, mpsc};
+    use tokio::time::timeout;
+    use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+    use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+    use tokio_util::codec::{FramedRead, FramedWrite};
+
+    use super::{acceptor_quic_process, build_quic_server_config, JOURNAL_QUIC_ALPN};
+    use crate::server::connection_manager::ConnectionManager;
+    use crate::server::packet::RequestPackage;
+
+    fn req_header(api_key: ApiKey) -> Option<ReqHeader> {
+        Some(ReqHeader {
+            api_key: api_key.into(),
+            api_version: ApiVersion::V0.into(),
+        })
+    }
+
+    #[tokio::test]
+    async fn quic_stream_per_request_type_test() {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert_der: CertificateDer<'static> = cert.cert.der().clone();
+        let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(cert.key_pair.serialize_der()));
+
+        let server_config = build_quic_server_config(vec![cert_der.clone()], key).unwrap();
+        let server = Endpoint::server(server_config, "127.0.0.1:0".parse().unwrap()).unwrap();
+        let server_addr = server.local_addr().unwrap();
+
+        let connection_manager = Arc::new(ConnectionManager::new());
+        let (stop_sx, _) = broadcast::channel::<bool>(2);
+        let (request_queue_sx, mut request_queue_rx) = mpsc::channel::<RequestPackage>(100);
+        acceptor_quic_process(
+            server,
+            stop_sx.clone(),
+            connection_manager.clone(),
+            request_queue_sx,
+        )
+        .await;
+
+        let mut roots = RootCertStore::empty();
+        roots.add(cert_der).unwrap();
+        let mut tls_config = ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        tls_config.alpn_protocols = vec![JOURNAL_QUIC_ALPN.to_vec()];
+        let mut client = Endpoint::client("127.0.0.1:0".parse().unwrap()).unwrap();
+        client.set_default_client_config(quinn::ClientConfig::new(Arc::new(
+            QuicClientConfig::try_from(tls_config).unwrap(),
+        )));
+        let conn = client
+            .connect(server_addr, "localhost")
+            .unwrap()
+            .await
+            .unwrap();
+
+        let (write_send, _write_recv) = conn.open_bi().await.unwrap();
+        let (read_send, read_recv) = conn.open_bi().await.unwrap();
+        let mut write_stream = FramedWrite::new(write_send, JournalServerCodec::new());
+        let mut read_stream = FramedWrite::new(read_send, JournalServerCodec::new());
+        let mut read_resp_stream = FramedRead::new(read_recv, JournalServerCodec::new());
+
+        let write_req = JournalEnginePacket::WriteReq(WriteReq {
+            header: req_header(ApiKey::Write),
+            body: Some(WriteReqBody::default()),
+        });
+        let read_req = JournalEnginePacket::ReadReq(ReadReq {
+            header: req_header(ApiKey::Read),
+            body: Some(ReadReqBody::default()),
+        });
+        write_stream.send(write_req.clone()).await.unwrap();
+        read_stream.send(read_req.clone()).await.unwrap();
+
+        let mut write_connection_id = 0;
+        let mut read_connection_id = 0;
+        for _ in 0..2 {
+            let package = timeout(Duration::from_secs(5), request_queue_rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            if package.packet == write_req {
+                write_connection_id = package.connection_id;
+            } else {
+                assert_eq!(package.packet, read_req);
+                read_connection_id = package.connection_id;
+            }
+        }
+
+        // each stream is its own connection, the write stays unanswered while the read completes
+        assert_ne!(write_connection_id, 0);
+        assert_ne!(read_connection_id, 0);
+        assert_ne!(write_connection_id, read_connection_id);
+
+        let read_resp = JournalEnginePacket::ReadResp(ReadResp {
+            header: Some(RespHeader {
+                api_key: ApiKey::Read.into(),
+                api_version: ApiVersion::V0.into(),
+                error: None,
+            }),
+            body: Some(ReadRespBody::default()),
+        });
+        connection_manager
+            .write_tcp_frame(read_connection_id, read_resp.clone())
+            .await
+            .unwrap();
+        let resp = timeout(Duration::from_secs(5), read_resp_stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(resp, read_resp);
+
+        // finishing a stream releases its connection on the server
+        read_stream.close().await.unwrap();
+        timeout(Duration::from_secs(5), async {
+            while connection_manager.get_connect(read_connection_id).is_some() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert!(connection_manager
+            .get_connect(write_connection_id)
+            .is_some());
+
+        stop_sx.send(true).unwrap();
+    }
+}
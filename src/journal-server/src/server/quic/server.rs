@@ -0,0 +1,121 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+use common_base::config::journal_server::journal_server_conf;
+use grpc_clients::pool::ClientPool;
+use log::info;
+use quinn::Endpoint;
+use rocksdb_engine::RocksDBEngine;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::core::cache::CacheManager;
+use crate::core::offset::OffsetManager;
+use crate::handler::command::Command;
+use crate::segment::manager::SegmentFileManager;
+use crate::server::connection_manager::ConnectionManager;
+use crate::server::packet::{RequestPackage, ResponsePackage};
+use crate::server::quic::quic_server::{acceptor_quic_process, build_quic_server_config};
+use crate::server::tcp::handler::handler_process;
+use crate::server::tcp::response::response_process;
+use crate::server::tcp::tls_server::{load_certs, load_key};
+
+pub async fn start_quic_server(
+    client_pool: Arc<ClientPool>,
+    connection_manager: Arc<ConnectionManager>,
+    cache_manager: Arc<CacheManager>,
+    offset_manager: Arc<OffsetManager>,
+    segment_file_manager: Arc<SegmentFileManager>,
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+    stop_sx: broadcast::Sender<bool>,
+) {
+    let conf = journal_server_conf();
+    let command = Command::new(
+        client_pool.clone(),
+        cache_manager.clone(),
+        offset_manager,
+        segment_file_manager,
+        rocksdb_engine_handler,
+    );
+
+    let certs = match load_certs(Path::new(&conf.network.tls_cert)) {
+        Ok(data) => data,
+        Err(e) => {
+            panic!("{}", e.to_string());
+        }
+    };
+
+    let key = match load_key(Path::new(&conf.network.tls_key)) {
+        Ok(data) => data,
+        Err(e) => {
+            panic!("{}", e.to_string());
+        }
+    };
+
+    let server_config = match build_quic_server_config(certs, key) {
+        Ok(config) => config,
+        Err(e) => {
+            panic!("{}", e.to_string());
+        }
+    };
+
+    let addr: SocketAddr = format!("0.0.0.0:{}", conf.network.quic_port)
+        .parse()
+        .unwrap();
+    let endpoint = match Endpoint::server(server_config, addr) {
+        Ok(endpoint) => endpoint,
+        Err(e) => {
+            panic!("{}", e.to_string());
+        }
+    };
+
+    let (request_queue_sx, request_queue_rx) = mpsc::channel::<RequestPackage>(1000);
+    let (response_queue_sx, response_queue_rx) = mpsc::channel::<ResponsePackage>(1000);
+
+    acceptor_quic_process(
+        endpoint,
+        stop_sx.clone(),
+        connection_manager.clone(),
+        request_queue_sx,
+    )
+    .await;
+
+    handler_process(
+        conf.tcp_thread.handler_thread_num,
+        request_queue_rx,
+        connection_manager.clone(),
+        response_queue_sx,
+        stop_sx.clone(),
+        command,
+    )
+    .await;
+
+    response_process(
+        conf.tcp_thread.response_thread_num,
+        connection_manager,
+        cache_manager,
+        response_queue_rx,
+        client_pool,
+        stop_sx,
+    )
+    .await;
+
+    info!(
+        "Journal Engine QUIC Server started successfully, listening port: {}",
+        conf.network.quic_port
+    );
+}
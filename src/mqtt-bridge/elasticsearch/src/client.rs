@@ -0,0 +1,433 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use http_client::client::{HttpClient, HttpEndpoint, HttpResponse};
+use metadata_struct::mqtt::message::MqttMessage;
+use serde_json::Value;
+use tokio::time::{sleep, timeout};
+
+use crate::config::ElasticsearchBridgeConfig;
+use crate::error::ElasticsearchBridgeError;
+use crate::index::IndexTemplate;
+use crate::record::build_bulk_body;
+
+const BULK_RETRY_TIMES: u32 = 5;
+const BULK_RETRY_BACKOFF_MS: u64 = 100;
+
+// An item of a bulk request that Elasticsearch rejected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BulkItemError {
+    // position of the message in the batch
+    pub position: usize,
+    pub status: u16,
+    pub reason: String,
+}
+
+impl BulkItemError {
+    // Items rejected because the cluster is overloaded (429, es_rejected_execution_exception)
+    // or unavailable (5xx) may succeed later, anything else (e.g. a mapping or parse
+    // error) would be rejected again.
+    pub fn is_retryable(&self) -> bool {
+        self.status == 429
+            || self.status >= 500
+            || self.reason.starts_with("es_rejected_execution_exception")
+    }
+}
+
+pub struct ElasticsearchClient {
+    config: ElasticsearchBridgeConfig,
+    client: HttpClient,
+    endpoint: HttpEndpoint,
+    index_template: IndexTemplate,
+    authorization: Option<String>,
+}
+
+impl ElasticsearchClient {
+    pub fn new(config: ElasticsearchBridgeConfig) -> Result<Self, ElasticsearchBridgeError> {
+        let endpoint = HttpEndpoint::parse(&config.url)?;
+        let index_template = IndexTemplate::parse(&config.index)?;
+        let authorization = if config.username.is_empty() {
+            None
+        } else {
+            let credentials = format!("{}:{}", config.username, config.password);
+            Some(format!("Basic {}", STANDARD.encode(credentials.as_bytes())))
+        };
+
+        Ok(ElasticsearchClient {
+            client: HttpClient::new(&config.tls_ca)?,
+            config,
+            endpoint,
+            index_template,
+            authorization,
+        })
+    }
+
+    // Indexes the messages and returns the items that were rejected for good. Items
+    // that may succeed later are sent again with an increasing interval. An error
+    // means no item can be assumed indexed, the batch has to be sent again.
+    pub async fn bulk(
+        &self,
+        messages: &[MqttMessage],
+    ) -> Result<Vec<BulkItemError>, ElasticsearchBridgeError> {
+        let mut rejected = Vec::new();
+        // positions of the messages still to be indexed
+        let mut pending: Vec<usize> = (0..messages.len()).collect();
+        let mut times = 0;
+        loop {
+            let batch: Vec<MqttMessage> = pending.iter().map(|i| messages[*i].clone()).collect();
+            let mut retry = Vec::new();
+            let mut first_retry_error = None;
+            for error in self.bulk_once(&batch).await? {
+                let error = BulkItemError {
+                    position: pending[error.position],
+                    ..error
+                };
+                if error.is_retryable() {
+                    retry.push(error.position);
+                    if first_retry_error.is_none() {
+                        first_retry_error = Some(error.reason);
+                    }
+                } else {
+                    rejected.push(error);
+                }
+            }
+
+            if retry.is_empty() {
+                rejected.sort_by_key(|error| error.position);
+                return Ok(rejected);
+            }
+            if times >= BULK_RETRY_TIMES {
+                return Err(ElasticsearchBridgeError::RetriesExhausted(
+                    retry.len(),
+                    times,
+                    first_retry_error.unwrap_or_default(),
+                ));
+            }
+            sleep(Duration::from_millis(BULK_RETRY_BACKOFF_MS << times)).await;
+            times += 1;
+            pending = retry;
+        }
+    }
+
+    // Indexes the messages with one `_bulk` request and returns the rejected items.
+    async fn bulk_once(
+        &self,
+        messages: &[MqttMessage],
+    ) -> Result<Vec<BulkItemError>, ElasticsearchBridgeError> {
+        if messages.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let body = build_bulk_body(&self.index_template, messages);
+        let response = self
+            .request("POST", "/_bulk", Some("application/x-ndjson"), &body)
+            .await?;
+        parse_bulk_response(&response.body, messages.len())
+    }
+
+    // Fails if the cluster is unreachable or its health is red.
+    pub async fn check_health(&self) -> Result<(), ElasticsearchBridgeError> {
+        let response = self.request("GET", "/_cluster/health", None, &[]).await?;
+        let health: Value = serde_json::from_slice(&response.body)?;
+        match health["status"].as_str() {
+            Some("green") | Some("yellow") => Ok(()),
+            status => Err(ElasticsearchBridgeError::HttpStatus(
+                response.status,
+                format!("cluster health is {}", status.unwrap_or("unknown")),
+            )),
+        }
+    }
+
+    async fn request(
+        &self,
+        method: &str,
+        path: &str,
+        content_type: Option<&str>,
+        body: &[u8],
+    ) -> Result<HttpResponse, ElasticsearchBridgeError> {
+        let mut headers = Vec::new();
+        if let Some(content_type) = content_type {
+            headers.push(("Content-Type", content_type.to_string()));
+        }
+        if let Some(authorization) = &self.authorization {
+            headers.push(("Authorization", authorization.clone()));
+        }
+
+        let response = match timeout(
+            Duration::from_millis(self.config.timeout_ms),
+            self.client
+                .send(&self.endpoint, method, path, &headers, body),
+        )
+        .await
+        {
+            Ok(response) => response?,
+            Err(_) => {
+                return Err(ElasticsearchBridgeError::RequestTimeout(
+                    self.config.timeout_ms,
+                ))
+            }
+        };
+
+        if !(200..300).contains(&response.status) {
+            return Err(ElasticsearchBridgeError::HttpStatus(
+                response.status,
+                String::from_utf8_lossy(&response.body).to_string(),
+            ));
+        }
+        Ok(response)
+    }
+}
+
+pub fn parse_bulk_response(
+    body: &[u8],
+    item_count: usize,
+) -> Result<Vec<BulkItemError>, ElasticsearchBridgeError> {
+    let malformed = |reason: &str| ElasticsearchBridgeError::MalformedResponse(reason.to_string());
+
+    let response: Value = serde_json::from_slice(body)?;
+    if !response["errors"].as_bool().unwrap_or(false) {
+        return Ok(Vec::new());
+    }
+
+    let items = response["items"]
+        .as_array()
+        .ok_or_else(|| malformed("bulk response has no items"))?;
+    if items.len() != item_count {
+        return Err(malformed(&format!(
+            "bulk response has {} items, {} were sent",
+            items.len(),
+            item_count
+        )));
+    }
+
+    let mut errors = Vec::new();
+    for (position, item) in items.iter().enumerate() {
+        // every item holds the result of a single action, keyed by the action name
+        let result = item
+            .as_object()
+            .and_then(|action| action.values().next())
+            .ok_or_else(|| malformed("bulk response item is empty"))?;
+        let status = result["status"].as_u64().unwrap_or(0) as u16;
+        if (200..300).contains(&status) {
+            continue;
+        }
+
+        let reason = match result.get("error") {
+            Some(Value::Object(error)) => format!(
+                "{}: {}",
+                error
+                    .get("type")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown"),
+                error.get("reason").and_then(Value::as_str).unwrap_or("")
+            ),
+            Some(error) => error.to_string(),
+            None => "unknown error".to_string(),
+        };
+        errors.push(BulkItemError {
+            position,
+            status,
+            reason,
+        });
+    }
+    Ok(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use metadata_struct::mqtt::message::MqttMessage;
+    use serde_json::json;
+
+    use super::{parse_bulk_response, ElasticsearchClient};
+    use crate::config::ElasticsearchBridgeConfig;
+    use crate::error::ElasticsearchBridgeError;
+    use crate::mock::MockElasticsearchServer;
+
+    fn build_config(url: String) -> ElasticsearchBridgeConfig {
+        ElasticsearchBridgeConfig {
+            url,
+            index: "mqtt-${year}.${month}.${day}".to_string(),
+            timeout_ms: 1000,
+            ..Default::default()
+        }
+    }
+
+    fn build_message(client_id: &str, payload: &'static str) -> MqttMessage {
+        MqttMessage {
+            client_id: client_id.to_string(),
+            topic: Bytes::from("/sensor/1"),
+            payload: Bytes::from(payload),
+            create_time: 1704460800,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse_bulk_response_test() {
+        let body = json!({"took": 3, "errors": false, "items": [{"index": {"status": 201}}]});
+        assert!(parse_bulk_response(body.to_string().as_bytes(), 1)
+            .unwrap()
+            .is_empty());
+
+        let body = json!({"took": 3, "errors": true, "items": [
+            {"index": {"status": 201}},
+            {"index": {"status": 400, "error": {"type": "mapper_parsing_exception", "reason": "failed to parse"}}},
+            {"index": {"status": 429, "error": "rejected"}},
+        ]});
+        let errors = parse_bulk_response(body.to_string().as_bytes(), 3).unwrap();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].position, 1);
+        assert_eq!(errors[0].status, 400);
+        assert_eq!(
+            errors[0].reason,
+            "mapper_parsing_exception: failed to parse"
+        );
+        assert_eq!(errors[1].position, 2);
+        assert_eq!(errors[1].reason, "\"rejected\"");
+
+        assert!(parse_bulk_response(body.to_string().as_bytes(), 2).is_err());
+        assert!(parse_bulk_response(b"not json", 1).is_err());
+    }
+
+    #[tokio::test]
+    async fn bulk_test() {
+        let server = MockElasticsearchServer::start().await;
+        let mut config = build_config(server.url());
+        config.username = "elastic".to_string();
+        config.password = "secret".to_string();
+        let client = ElasticsearchClient::new(config).unwrap();
+
+        assert!(client.bulk(&[]).await.unwrap().is_empty());
+
+        let errors = client
+            .bulk(&[
+                build_message("c1", r#"{"value":1}"#),
+                build_message("c2", "not json"),
+            ])
+            .await
+            .unwrap();
+        assert!(errors.is_empty());
+
+        let documents = server.documents("mqtt-2024.01.05");
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[0]["value"], json!(1));
+        assert_eq!(documents[0]["mqtt"]["client_id"], json!("c1"));
+        assert_eq!(documents[1]["payload_base64"], json!("bm90IGpzb24="));
+        assert_eq!(
+            server.authorization(),
+            Some("Basic ZWxhc3RpYzpzZWNyZXQ=".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn bulk_item_rejected() {
+        let server = MockElasticsearchServer::start().await;
+        server.reject_client("c2");
+        let client = ElasticsearchClient::new(build_config(server.url())).unwrap();
+
+        let errors = client
+            .bulk(&[
+                build_message("c1", "1"),
+                build_message("c2", "2"),
+                build_message("c3", "3"),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].position, 1);
+        assert_eq!(errors[0].status, 400);
+        assert_eq!(server.documents("mqtt-2024.01.05").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn bulk_item_retried() {
+        let server = MockElasticsearchServer::start().await;
+        server.reject_client("c2");
+        server.throttle_client("c3", 2);
+        let client = ElasticsearchClient::new(build_config(server.url())).unwrap();
+
+        let errors = client
+            .bulk(&[
+                build_message("c1", "1"),
+                build_message("c2", "2"),
+                build_message("c3", "3"),
+            ])
+            .await
+            .unwrap();
+        // only the mapping error is given up on, the throttled item is indexed later
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].position, 1);
+        assert_eq!(errors[0].status, 400);
+
+        let documents = server.documents("mqtt-2024.01.05");
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[1]["mqtt"]["client_id"], json!("c3"));
+    }
+
+    #[tokio::test]
+    async fn bulk_retries_exhausted() {
+        let server = MockElasticsearchServer::start().await;
+        server.throttle_client("c1", u32::MAX);
+        let client = ElasticsearchClient::new(build_config(server.url())).unwrap();
+
+        match client.bulk(&[build_message("c1", "1")]).await {
+            Err(ElasticsearchBridgeError::RetriesExhausted(count, _, reason)) => {
+                assert_eq!(count, 1);
+                assert!(reason.starts_with("es_rejected_execution_exception"));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(server.documents("mqtt-2024.01.05").is_empty());
+    }
+
+    #[tokio::test]
+    async fn bulk_request_failed() {
+        let server = MockElasticsearchServer::start().await;
+        server.fail_next_requests(503, 1);
+        let client = ElasticsearchClient::new(build_config(server.url())).unwrap();
+
+        let messages = [build_message("c1", "1")];
+        match client.bulk(&messages).await {
+            Err(ElasticsearchBridgeError::HttpStatus(status, _)) => assert_eq!(status, 503),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(server.documents("mqtt-2024.01.05").is_empty());
+
+        assert!(client.bulk(&messages).await.unwrap().is_empty());
+        assert_eq!(server.documents("mqtt-2024.01.05").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn check_health_test() {
+        let server = MockElasticsearchServer::start().await;
+        let client = ElasticsearchClient::new(build_config(server.url())).unwrap();
+        client.check_health().await.unwrap();
+
+        server.set_health("red");
+        assert!(client.check_health().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn connect_failed() {
+        let client =
+            ElasticsearchClient::new(build_config("http://127.0.0.1:1".to_string())).unwrap();
+        assert!(client.bulk(&[build_message("c1", "1")]).await.is_err());
+        assert!(client.check_health().await.is_err());
+    }
+}
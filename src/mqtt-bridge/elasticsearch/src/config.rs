@@ -0,0 +1,53 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ElasticsearchBridgeConfig {
+    // http(s)://host:port[/path_prefix]
+    pub url: String,
+    // See `IndexTemplate` for the supported placeholders.
+    pub index: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    // CA bundle (PEM) trusted for https urls besides the system roots
+    #[serde(default)]
+    pub tls_ca: String,
+}
+
+fn default_timeout_ms() -> u64 {
+    30000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ElasticsearchBridgeConfig;
+
+    #[test]
+    fn config_from_json() {
+        let config: ElasticsearchBridgeConfig = serde_json::from_str(
+            r#"{"url":"http://127.0.0.1:9200","index":"mqtt-${year}.${month}.${day}"}"#,
+        )
+        .unwrap();
+        assert_eq!(config.url, "http://127.0.0.1:9200".to_string());
+        assert_eq!(config.index, "mqtt-${year}.${month}.${day}".to_string());
+        assert!(config.username.is_empty());
+        assert_eq!(config.timeout_ms, 30000);
+    }
+}
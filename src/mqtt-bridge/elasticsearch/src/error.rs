@@ -0,0 +1,40 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use http_client::error::HttpClientError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ElasticsearchBridgeError {
+    #[error("{0}")]
+    FromHttpClientError(#[from] HttpClientError),
+
+    #[error("{0}")]
+    SerdeJsonError(#[from] serde_json::Error),
+
+    #[error("Elasticsearch request timed out after {0} ms")]
+    RequestTimeout(u64),
+
+    #[error("Invalid index template [{0}], {1}")]
+    InvalidIndexTemplate(String, String),
+
+    #[error("Malformed HTTP response: {0}")]
+    MalformedResponse(String),
+
+    #[error("Elasticsearch returned HTTP status {0}: {1}")]
+    HttpStatus(u16, String),
+
+    #[error("Elasticsearch still rejected {0} items after {1} retries, first error: {2}")]
+    RetriesExhausted(usize, u32, String),
+}
@@ -0,0 +1,162 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::error::ElasticsearchBridgeError;
+
+#[derive(Clone, Debug, PartialEq)]
+enum TemplatePart {
+    Text(String),
+    Year,
+    Month,
+    Day,
+    Hour,
+}
+
+// Builds index names from the message create time (UTC). Supported placeholders:
+// `${year}`, `${month}`, `${day}` and `${hour}`, all but the year are zero padded
+// to two digits. `mqtt-${year}.${month}.${day}` renders to `mqtt-2024.01.05`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexTemplate {
+    parts: Vec<TemplatePart>,
+}
+
+impl IndexTemplate {
+    pub fn parse(template: &str) -> Result<Self, ElasticsearchBridgeError> {
+        let invalid = |reason: &str| {
+            ElasticsearchBridgeError::InvalidIndexTemplate(template.to_string(), reason.to_string())
+        };
+
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("${") {
+            if start > 0 {
+                parts.push(TemplatePart::Text(rest[..start].to_string()));
+            }
+            let end = if let Some(end) = rest[start..].find('}') {
+                start + end
+            } else {
+                return Err(invalid("placeholder is not closed"));
+            };
+
+            let part = match &rest[start + 2..end] {
+                "year" => TemplatePart::Year,
+                "month" => TemplatePart::Month,
+                "day" => TemplatePart::Day,
+                "hour" => TemplatePart::Hour,
+                name => return Err(invalid(&format!("unknown placeholder ${{{}}}", name))),
+            };
+            parts.push(part);
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(TemplatePart::Text(rest.to_string()));
+        }
+
+        if parts.is_empty() {
+            return Err(invalid("template is empty"));
+        }
+        if let Some(TemplatePart::Text(text)) = parts.first() {
+            if text.starts_with(['-', '_', '+']) {
+                return Err(invalid("index name must not start with '-', '_' or '+'"));
+            }
+        }
+        for part in parts.iter() {
+            if let TemplatePart::Text(text) = part {
+                if text.chars().any(|c| {
+                    c.is_ascii_uppercase()
+                        || matches!(
+                            c,
+                            '\\' | '/' | '*' | '?' | '"' | '<' | '>' | '|' | ' ' | ',' | '#' | ':'
+                        )
+                }) {
+                    return Err(invalid(
+                        "index name must be lowercase and must not contain \\ / * ? \" < > | space , # :",
+                    ));
+                }
+            }
+        }
+        Ok(IndexTemplate { parts })
+    }
+
+    pub fn render(&self, timestamp_sec: u64) -> String {
+        let (year, month, day) = civil_from_days(timestamp_sec / 86400);
+        let hour = timestamp_sec % 86400 / 3600;
+
+        let mut index = String::new();
+        for part in self.parts.iter() {
+            match part {
+                TemplatePart::Text(text) => index.push_str(text),
+                TemplatePart::Year => index.push_str(&year.to_string()),
+                TemplatePart::Month => index.push_str(&format!("{:02}", month)),
+                TemplatePart::Day => index.push_str(&format!("{:02}", day)),
+                TemplatePart::Hour => index.push_str(&format!("{:02}", hour)),
+            }
+        }
+        index
+    }
+}
+
+// Converts days since 1970-01-01 into a (year, month, day) of the proleptic
+// Gregorian calendar, see http://howardhinnant.github.io/date_algorithms.html
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z % 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{civil_from_days, IndexTemplate};
+
+    #[test]
+    fn civil_from_days_test() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(59), (1970, 3, 1));
+        // 2000-02-29, leap day of a century divisible by 400
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19722), (2023, 12, 31));
+        assert_eq!(civil_from_days(19723), (2024, 1, 1));
+    }
+
+    #[test]
+    fn render_test() {
+        // 2024-01-05 13:20:00 UTC
+        let timestamp = 1704460800;
+        let template = IndexTemplate::parse("mqtt-${year}.${month}.${day}").unwrap();
+        assert_eq!(template.render(timestamp), "mqtt-2024.01.05");
+
+        let template = IndexTemplate::parse("mqtt-${year}${month}${day}-${hour}").unwrap();
+        assert_eq!(template.render(timestamp), "mqtt-20240105-13");
+
+        let template = IndexTemplate::parse("mqtt").unwrap();
+        assert_eq!(template.render(timestamp), "mqtt");
+    }
+
+    #[test]
+    fn parse_invalid_template() {
+        assert!(IndexTemplate::parse("").is_err());
+        assert!(IndexTemplate::parse("mqtt-${year").is_err());
+        assert!(IndexTemplate::parse("mqtt-${week}").is_err());
+        assert!(IndexTemplate::parse("MQTT-${year}").is_err());
+        assert!(IndexTemplate::parse("_mqtt").is_err());
+        assert!(IndexTemplate::parse("mqtt/${year}").is_err());
+    }
+}
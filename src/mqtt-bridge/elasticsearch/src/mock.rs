@@ -0,0 +1,224 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An in-process HTTP stand-in implementing `POST /_bulk` with index actions and
+//! `GET /_cluster/health`, storing documents per index so tests can inspect them.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+#[derive(Default)]
+struct MockState {
+    documents: HashMap<String, Vec<Value>>,
+    // documents of these clients are rejected with a mapping error
+    rejected_clients: HashSet<String>,
+    // (client_id, remaining) documents rejected with 429 until remaining runs out
+    throttled_clients: HashMap<String, u32>,
+    // (status, remaining) of requests that fail as a whole
+    fail_requests: (u16, u32),
+    authorization: Option<String>,
+    health: String,
+}
+
+pub struct MockElasticsearchServer {
+    port: u16,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockElasticsearchServer {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let state = Arc::new(Mutex::new(MockState {
+            health: "green".to_string(),
+            ..Default::default()
+        }));
+
+        let server_state = state.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = server_state.clone();
+                tokio::spawn(async move {
+                    let _ = handle_connection(stream, state).await;
+                });
+            }
+        });
+        MockElasticsearchServer { port, state }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+
+    pub fn documents(&self, index: &str) -> Vec<Value> {
+        let state = self.state.lock().unwrap();
+        state.documents.get(index).cloned().unwrap_or_default()
+    }
+
+    pub fn authorization(&self) -> Option<String> {
+        self.state.lock().unwrap().authorization.clone()
+    }
+
+    pub fn reject_client(&self, client_id: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.rejected_clients.insert(client_id.to_string());
+    }
+
+    pub fn throttle_client(&self, client_id: &str, times: u32) {
+        let mut state = self.state.lock().unwrap();
+        state.throttled_clients.insert(client_id.to_string(), times);
+    }
+
+    pub fn set_health(&self, health: &str) {
+        self.state.lock().unwrap().health = health.to_string();
+    }
+
+    pub fn fail_next_requests(&self, status: u16, times: u32) {
+        self.state.lock().unwrap().fail_requests = (status, times);
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    state: Arc<Mutex<MockState>>,
+) -> std::io::Result<()> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 4096];
+    let header_end = loop {
+        if let Some(index) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break index;
+        }
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        data.extend_from_slice(&buf[..n]);
+    };
+
+    let header = String::from_utf8_lossy(&data[..header_end]).to_string();
+    let mut lines = header.split("\r\n");
+    let request_line = lines.next().unwrap_or_default().to_string();
+    let mut content_length = 0;
+    let mut authorization = None;
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':') {
+            match name.trim().to_ascii_lowercase().as_str() {
+                "content-length" => content_length = value.trim().parse().unwrap_or(0),
+                "authorization" => authorization = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+    }
+
+    let mut body = data[header_end + 4..].to_vec();
+    while body.len() < content_length {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        body.extend_from_slice(&buf[..n]);
+    }
+
+    let (status, response) = if request_line.starts_with("POST /_bulk ") {
+        handle_bulk(&body, authorization, &state)
+    } else if request_line.starts_with("GET /_cluster/health ") {
+        let health = state.lock().unwrap().health.clone();
+        (200, json!({"cluster_name": "mock", "status": health}))
+    } else {
+        (404, json!({"error": "not found"}))
+    };
+
+    let response = response.to_string();
+    let reply = format!(
+        "HTTP/1.1 {} Mock\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+        status,
+        response.len(),
+        response
+    );
+    stream.write_all(reply.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+fn handle_bulk(
+    body: &[u8],
+    authorization: Option<String>,
+    state: &Arc<Mutex<MockState>>,
+) -> (u16, Value) {
+    let mut state = state.lock().unwrap();
+    state.authorization = authorization;
+
+    if state.fail_requests.1 > 0 {
+        state.fail_requests.1 -= 1;
+        return (
+            state.fail_requests.0,
+            json!({"error": "unavailable", "status": state.fail_requests.0}),
+        );
+    }
+
+    let lines: Vec<Value> = match String::from_utf8_lossy(body)
+        .lines()
+        .map(serde_json::from_str)
+        .collect()
+    {
+        Ok(lines) => lines,
+        Err(e) => return (400, json!({"error": e.to_string(), "status": 400})),
+    };
+
+    let mut errors = false;
+    let mut items = Vec::new();
+    for pair in lines.chunks(2) {
+        let index = pair[0]["index"]["_index"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let document = pair.get(1).cloned().unwrap_or(Value::Null);
+        let client_id = document["mqtt"]["client_id"].as_str().unwrap_or_default();
+
+        let throttled = match state.throttled_clients.get_mut(client_id) {
+            Some(remaining) if *remaining > 0 => {
+                *remaining -= 1;
+                true
+            }
+            _ => false,
+        };
+
+        if throttled {
+            errors = true;
+            items.push(json!({"index": {
+                "_index": index,
+                "status": 429,
+                "error": {"type": "es_rejected_execution_exception", "reason": "rejected execution"},
+            }}));
+        } else if state.rejected_clients.contains(client_id) {
+            errors = true;
+            items.push(json!({"index": {
+                "_index": index,
+                "status": 400,
+                "error": {"type": "mapper_parsing_exception", "reason": "failed to parse"},
+            }}));
+        } else {
+            state
+                .documents
+                .entry(index.clone())
+                .or_default()
+                .push(document);
+            items.push(json!({"index": {"_index": index, "status": 201, "result": "created"}}));
+        }
+    }
+    (200, json!({"took": 1, "errors": errors, "items": items}))
+}
@@ -0,0 +1,143 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use metadata_struct::mqtt::message::MqttMessage;
+use serde_json::{json, Map, Value};
+
+use crate::index::IndexTemplate;
+
+// Field holding the metadata of the MQTT message in every document.
+pub const METADATA_FIELD: &str = "mqtt";
+// Field holding payloads that are valid JSON but not an object.
+pub const PAYLOAD_FIELD: &str = "payload";
+// Field holding payloads that are not valid JSON, base64 encoded.
+pub const PAYLOAD_BASE64_FIELD: &str = "payload_base64";
+
+// Converts an MQTT message into an Elasticsearch document. A JSON object payload
+// is used as the document itself, any other payload is stored in a single field.
+// The message metadata is added under `mqtt` and replaces a payload field of the
+// same name.
+pub fn build_document(message: &MqttMessage) -> Value {
+    let mut document = match serde_json::from_slice::<Value>(&message.payload) {
+        Ok(Value::Object(map)) => map,
+        Ok(value) => {
+            let mut map = Map::new();
+            map.insert(PAYLOAD_FIELD.to_string(), value);
+            map
+        }
+        Err(_) => {
+            let mut map = Map::new();
+            map.insert(
+                PAYLOAD_BASE64_FIELD.to_string(),
+                Value::String(STANDARD.encode(&message.payload)),
+            );
+            map
+        }
+    };
+
+    document.insert(
+        METADATA_FIELD.to_string(),
+        json!({
+            "topic": String::from_utf8_lossy(&message.topic),
+            "client_id": message.client_id,
+            "qos": message.qos as u8,
+            "create_time": message.create_time,
+        }),
+    );
+    Value::Object(document)
+}
+
+// Builds the NDJSON body of a `_bulk` request, one index action per message.
+// Items in the response are in the same order as the messages.
+pub fn build_bulk_body(index_template: &IndexTemplate, messages: &[MqttMessage]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for message in messages {
+        let action = json!({
+            "index": {
+                "_index": index_template.render(message.create_time),
+            }
+        });
+        body.extend_from_slice(action.to_string().as_bytes());
+        body.push(b'\n');
+        body.extend_from_slice(build_document(message).to_string().as_bytes());
+        body.push(b'\n');
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use metadata_struct::mqtt::message::MqttMessage;
+    use protocol::mqtt::common::QoS;
+    use serde_json::{json, Value};
+
+    use super::{build_bulk_body, build_document};
+    use crate::index::IndexTemplate;
+
+    fn build_message(payload: &'static [u8]) -> MqttMessage {
+        MqttMessage {
+            client_id: "client-1".to_string(),
+            qos: QoS::AtLeastOnce,
+            topic: Bytes::from("/sensor/1/temp"),
+            payload: Bytes::from_static(payload),
+            create_time: 1704460800,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn build_document_test() {
+        let metadata = json!({
+            "topic": "/sensor/1/temp",
+            "client_id": "client-1",
+            "qos": 1,
+            "create_time": 1704460800,
+        });
+
+        let document = build_document(&build_message(br#"{"value":21.5,"mqtt":"x"}"#));
+        assert_eq!(document, json!({"value": 21.5, "mqtt": metadata}));
+
+        let document = build_document(&build_message(b"21.5"));
+        assert_eq!(document, json!({"payload": 21.5, "mqtt": metadata}));
+
+        let document = build_document(&build_message(&[0xff, 0x00, 0xfe]));
+        assert_eq!(
+            document,
+            json!({"payload_base64": "/wD+", "mqtt": metadata})
+        );
+    }
+
+    #[test]
+    fn build_bulk_body_test() {
+        let template = IndexTemplate::parse("mqtt-${year}.${month}.${day}").unwrap();
+        let body = build_bulk_body(
+            &template,
+            &[build_message(br#"{"value":1}"#), build_message(b"abc")],
+        );
+
+        let lines: Vec<Value> = String::from_utf8(body)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], json!({"index": {"_index": "mqtt-2024.01.05"}}));
+        assert_eq!(lines[1]["value"], json!(1));
+        assert_eq!(lines[2], lines[0]);
+        assert_eq!(lines[3]["payload_base64"], json!("YWJj"));
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::KafkaBridgeError;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KafkaMessageKey {
+    #[default]
+    ClientId,
+    Topic,
+    None,
+}
+
+impl FromStr for KafkaMessageKey {
+    type Err = KafkaBridgeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "" | "client_id" => Ok(KafkaMessageKey::ClientId),
+            "topic" => Ok(KafkaMessageKey::Topic),
+            "none" => Ok(KafkaMessageKey::None),
+            _ => Err(KafkaBridgeError::InvalidMessageKey(s.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KafkaProducerConfig {
+    pub bootstrap_servers: Vec<String>,
+    pub topic: String,
+    #[serde(default = "default_client_id")]
+    pub client_id: String,
+    #[serde(default)]
+    pub key: KafkaMessageKey,
+    // -1: wait for all in-sync replicas, 1: leader only, 0: no acknowledgement
+    #[serde(default = "default_acks")]
+    pub acks: i16,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+}
+
+impl KafkaProducerConfig {
+    pub fn new(bootstrap_servers: Vec<String>, topic: String) -> Self {
+        KafkaProducerConfig {
+            bootstrap_servers,
+            topic,
+            client_id: default_client_id(),
+            key: KafkaMessageKey::ClientId,
+            acks: default_acks(),
+            timeout_ms: default_timeout_ms(),
+            max_retries: default_max_retries(),
+            retry_backoff_ms: default_retry_backoff_ms(),
+        }
+    }
+}
+
+fn default_client_id() -> String {
+    "robustmq-mqtt-bridge".to_string()
+}
+
+fn default_acks() -> i16 {
+    -1
+}
+
+fn default_timeout_ms() -> u64 {
+    30000
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_retry_backoff_ms() -> u64 {
+    100
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::{KafkaMessageKey, KafkaProducerConfig};
+
+    #[test]
+    fn message_key_from_str() {
+        assert_eq!(
+            KafkaMessageKey::from_str("").unwrap(),
+            KafkaMessageKey::ClientId
+        );
+        assert_eq!(
+            KafkaMessageKey::from_str("client_id").unwrap(),
+            KafkaMessageKey::ClientId
+        );
+        assert_eq!(
+            KafkaMessageKey::from_str("topic").unwrap(),
+            KafkaMessageKey::Topic
+        );
+        assert_eq!(
+            KafkaMessageKey::from_str("none").unwrap(),
+            KafkaMessageKey::None
+        );
+        assert!(KafkaMessageKey::from_str("payload").is_err());
+    }
+
+    #[test]
+    fn producer_config_from_json() {
+        let config: KafkaProducerConfig = serde_json::from_str(
+            r#"{"bootstrap_servers":["127.0.0.1:9092"],"topic":"mqtt","key":"topic"}"#,
+        )
+        .unwrap();
+        let mut expect =
+            KafkaProducerConfig::new(vec!["127.0.0.1:9092".to_string()], "mqtt".to_string());
+        expect.key = KafkaMessageKey::Topic;
+        assert_eq!(config, expect);
+    }
+}
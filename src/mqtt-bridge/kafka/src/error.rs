@@ -0,0 +1,78 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum KafkaBridgeError {
+    #[error("{0}")]
+    FromIoError(#[from] std::io::Error),
+
+    #[error("Kafka request timed out after {0} ms")]
+    RequestTimeout(u64),
+
+    #[error("No bootstrap server is available, last error: {0}")]
+    NoAvailableBootstrapServer(String),
+
+    #[error("Malformed response from Kafka broker: {0}")]
+    MalformedResponse(String),
+
+    #[error("Correlation id mismatch, expected {0}, received {1}")]
+    CorrelationIdMismatch(i32, i32),
+
+    #[error("Kafka topic [{0}] has no available partition")]
+    NoAvailablePartition(String),
+
+    #[error("Leader of partition {1} of topic [{0}] is not available")]
+    LeaderNotAvailable(String, i32),
+
+    #[error("Kafka broker returned error code {1} for topic [{0}]")]
+    TopicError(String, i16),
+
+    #[error("Kafka broker returned error code {2} for partition {1} of topic [{0}]")]
+    PartitionError(String, i32, i16),
+
+    #[error("Invalid message key type [{0}], optional: client_id, topic, none")]
+    InvalidMessageKey(String),
+}
+
+impl KafkaBridgeError {
+    // Errors that may disappear after refreshing metadata or waiting for a while.
+    pub fn is_retriable(&self) -> bool {
+        match self {
+            KafkaBridgeError::FromIoError(_)
+            | KafkaBridgeError::RequestTimeout(_)
+            | KafkaBridgeError::NoAvailableBootstrapServer(_)
+            | KafkaBridgeError::NoAvailablePartition(_)
+            | KafkaBridgeError::LeaderNotAvailable(_, _)
+            | KafkaBridgeError::CorrelationIdMismatch(_, _) => true,
+            KafkaBridgeError::TopicError(_, code)
+            | KafkaBridgeError::PartitionError(_, _, code) => is_retriable_error_code(*code),
+            KafkaBridgeError::MalformedResponse(_) | KafkaBridgeError::InvalidMessageKey(_) => {
+                false
+            }
+        }
+    }
+}
+
+// See https://kafka.apache.org/protocol#protocol_error_codes
+pub fn is_retriable_error_code(code: i16) -> bool {
+    matches!(
+        code,
+        // UNKNOWN_TOPIC_OR_PARTITION, LEADER_NOT_AVAILABLE, NOT_LEADER_OR_FOLLOWER,
+        // REQUEST_TIMED_OUT, NETWORK_EXCEPTION, NOT_ENOUGH_REPLICAS,
+        // NOT_ENOUGH_REPLICAS_AFTER_APPEND
+        3 | 5 | 6 | 7 | 13 | 19 | 20
+    )
+}
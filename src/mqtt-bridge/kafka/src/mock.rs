@@ -0,0 +1,275 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A single node Kafka stand-in that understands just enough of Metadata v1 and
+//! Produce v3 to exercise the producer, including crc verification of record batches.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::error::KafkaBridgeError;
+use crate::protocol::{
+    crc32c, get_array_len, get_bytes, get_i16, get_i32, get_i64, get_i8, get_nullable_string,
+    get_string, get_varint, put_string, KafkaRecord, API_KEY_METADATA, API_KEY_PRODUCE,
+};
+
+#[derive(Default)]
+struct MockState {
+    // (topic, [(partition, record)])
+    records: HashMap<String, Vec<(i32, KafkaRecord)>>,
+    fail_code: i16,
+    fail_times: u32,
+}
+
+pub struct MockKafkaServer {
+    port: u16,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockKafkaServer {
+    pub async fn start(partition_num: i32) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let state = Arc::new(Mutex::new(MockState::default()));
+
+        let server_state = state.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = server_state.clone();
+                tokio::spawn(async move {
+                    let _ = handle_connection(stream, port, partition_num, state).await;
+                });
+            }
+        });
+        MockKafkaServer { port, state }
+    }
+
+    pub fn addr(&self) -> String {
+        format!("127.0.0.1:{}", self.port)
+    }
+
+    pub fn records(&self, topic: &str) -> Vec<(i32, KafkaRecord)> {
+        let state = self.state.lock().unwrap();
+        state.records.get(topic).cloned().unwrap_or_default()
+    }
+
+    // Rejects every partition of the next `times` produce requests with `code`.
+    pub fn fail_next_produce(&self, code: i16, times: u32) {
+        let mut state = self.state.lock().unwrap();
+        state.fail_code = code;
+        state.fail_times = times;
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    port: u16,
+    partition_num: i32,
+    state: Arc<Mutex<MockState>>,
+) -> Result<(), KafkaBridgeError> {
+    loop {
+        let size = stream.read_i32().await?;
+        let mut buf = BytesMut::zeroed(size as usize);
+        stream.read_exact(&mut buf).await?;
+        let mut request = buf.freeze();
+
+        let api_key = get_i16(&mut request)?;
+        // api_version
+        get_i16(&mut request)?;
+        let correlation_id = get_i32(&mut request)?;
+        // client_id
+        get_nullable_string(&mut request)?;
+
+        let body = match api_key {
+            API_KEY_METADATA => Some(metadata_response(request, port, partition_num)?),
+            API_KEY_PRODUCE => produce_response(request, &state)?,
+            _ => {
+                return Err(KafkaBridgeError::MalformedResponse(format!(
+                    "unsupported api key {}",
+                    api_key
+                )))
+            }
+        };
+
+        if let Some(body) = body {
+            let mut response = BytesMut::new();
+            response.put_i32(body.len() as i32 + 4);
+            response.put_i32(correlation_id);
+            response.put_slice(&body);
+            stream.write_all(&response).await?;
+        }
+    }
+}
+
+fn metadata_response(
+    mut request: Bytes,
+    port: u16,
+    partition_num: i32,
+) -> Result<BytesMut, KafkaBridgeError> {
+    let mut topics = Vec::new();
+    let topic_num = get_array_len(&mut request)?;
+    for _ in 0..topic_num {
+        topics.push(get_string(&mut request)?);
+    }
+
+    let mut body = BytesMut::new();
+    body.put_i32(1);
+    body.put_i32(0);
+    put_string(&mut body, "127.0.0.1");
+    body.put_i32(port as i32);
+    body.put_i16(-1);
+    // controller_id
+    body.put_i32(0);
+    body.put_i32(topics.len() as i32);
+    for topic in topics {
+        body.put_i16(0);
+        put_string(&mut body, &topic);
+        body.put_i8(0);
+        body.put_i32(partition_num);
+        for partition in 0..partition_num {
+            body.put_i16(0);
+            body.put_i32(partition);
+            body.put_i32(0);
+            // replicas and isr
+            body.put_i32(1);
+            body.put_i32(0);
+            body.put_i32(1);
+            body.put_i32(0);
+        }
+    }
+    Ok(body)
+}
+
+fn produce_response(
+    mut request: Bytes,
+    state: &Arc<Mutex<MockState>>,
+) -> Result<Option<BytesMut>, KafkaBridgeError> {
+    // transactional_id
+    get_nullable_string(&mut request)?;
+    let acks = get_i16(&mut request)?;
+    // timeout
+    get_i32(&mut request)?;
+
+    let mut state = state.lock().unwrap();
+    let error_code = if state.fail_times > 0 {
+        state.fail_times -= 1;
+        state.fail_code
+    } else {
+        0
+    };
+
+    let mut body = BytesMut::new();
+    let topic_num = get_array_len(&mut request)?;
+    body.put_i32(topic_num as i32);
+    for _ in 0..topic_num {
+        let topic = get_string(&mut request)?;
+        put_string(&mut body, &topic);
+        let partition_num = get_array_len(&mut request)?;
+        body.put_i32(partition_num as i32);
+        for _ in 0..partition_num {
+            let partition = get_i32(&mut request)?;
+            let len = get_i32(&mut request)? as usize;
+            let records = decode_record_batch(get_bytes(&mut request, len)?)?;
+
+            let stored = state.records.entry(topic.clone()).or_default();
+            let base_offset = stored.len() as i64;
+            if error_code == 0 {
+                stored.extend(records.into_iter().map(|record| (partition, record)));
+            }
+
+            body.put_i32(partition);
+            body.put_i16(error_code);
+            body.put_i64(base_offset);
+            // log_append_time
+            body.put_i64(-1);
+        }
+    }
+    // throttle_time_ms
+    body.put_i32(0);
+
+    if acks == 0 {
+        return Ok(None);
+    }
+    Ok(Some(body))
+}
+
+fn decode_record_batch(mut buf: Bytes) -> Result<Vec<KafkaRecord>, KafkaBridgeError> {
+    // base_offset, batch_length, partition_leader_epoch
+    get_i64(&mut buf)?;
+    get_i32(&mut buf)?;
+    get_i32(&mut buf)?;
+    let magic = get_i8(&mut buf)?;
+    if magic != 2 {
+        return Err(KafkaBridgeError::MalformedResponse(format!(
+            "unsupported magic {}",
+            magic
+        )));
+    }
+    let crc = get_i32(&mut buf)? as u32;
+    if crc != crc32c(&buf) {
+        return Err(KafkaBridgeError::MalformedResponse(
+            "record batch crc mismatch".to_string(),
+        ));
+    }
+
+    // attributes, last_offset_delta
+    get_i16(&mut buf)?;
+    get_i32(&mut buf)?;
+    let first_timestamp = get_i64(&mut buf)?;
+    // max_timestamp, producer_id, producer_epoch, base_sequence
+    get_i64(&mut buf)?;
+    get_i64(&mut buf)?;
+    get_i16(&mut buf)?;
+    get_i32(&mut buf)?;
+
+    let mut records = Vec::new();
+    let record_num = get_i32(&mut buf)?;
+    for _ in 0..record_num {
+        let len = get_varint(&mut buf)? as usize;
+        let mut record = get_bytes(&mut buf, len)?;
+        // attributes
+        get_i8(&mut record)?;
+        let timestamp = first_timestamp + get_varint(&mut record)?;
+        // offset_delta
+        get_varint(&mut record)?;
+        let key_len = get_varint(&mut record)?;
+        let key = if key_len < 0 {
+            None
+        } else {
+            Some(get_bytes(&mut record, key_len as usize)?.to_vec())
+        };
+        let value_len = get_varint(&mut record)? as usize;
+        let value = get_bytes(&mut record, value_len)?.to_vec();
+        let mut headers = Vec::new();
+        let header_num = get_varint(&mut record)?;
+        for _ in 0..header_num {
+            let key_len = get_varint(&mut record)? as usize;
+            let key = String::from_utf8_lossy(&get_bytes(&mut record, key_len)?).to_string();
+            let value_len = get_varint(&mut record)? as usize;
+            let value = get_bytes(&mut record, value_len)?.to_vec();
+            headers.push((key, value));
+        }
+        records.push(KafkaRecord {
+            key,
+            value,
+            headers,
+            timestamp,
+        });
+    }
+    Ok(records)
+}
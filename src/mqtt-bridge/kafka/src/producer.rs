@@ -0,0 +1,522 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use bytes::{Bytes, BytesMut};
+use log::warn;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio::time::{sleep, timeout};
+
+use crate::config::KafkaProducerConfig;
+use crate::error::KafkaBridgeError;
+use crate::protocol::{
+    decode_metadata_response, decode_produce_response, decode_response_correlation_id,
+    encode_metadata_request, encode_produce_request, murmur2, KafkaRecord, PartitionMetadata,
+};
+
+// Produce and metadata responses of a single topic are far below this, a larger
+// size means the stream is out of sync or the peer is not a Kafka broker.
+const MAX_RESPONSE_SIZE: i32 = 16 * 1024 * 1024;
+
+struct BrokerConnection {
+    stream: TcpStream,
+}
+
+impl BrokerConnection {
+    async fn connect(addr: &str) -> Result<Self, KafkaBridgeError> {
+        let stream = TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+        Ok(BrokerConnection { stream })
+    }
+
+    async fn send(&mut self, data: &[u8]) -> Result<(), KafkaBridgeError> {
+        self.stream.write_all(data).await?;
+        Ok(())
+    }
+
+    async fn receive(&mut self, correlation_id: i32) -> Result<Bytes, KafkaBridgeError> {
+        let size = self.stream.read_i32().await?;
+        if size < 4 {
+            return Err(KafkaBridgeError::MalformedResponse(format!(
+                "response size {} is too small",
+                size
+            )));
+        }
+        if size > MAX_RESPONSE_SIZE {
+            return Err(KafkaBridgeError::MalformedResponse(format!(
+                "response size {} exceeds the limit of {} bytes",
+                size, MAX_RESPONSE_SIZE
+            )));
+        }
+        let mut buf = BytesMut::zeroed(size as usize);
+        self.stream.read_exact(&mut buf).await?;
+        let mut data = buf.freeze();
+        let response_correlation_id = decode_response_correlation_id(&mut data)?;
+        if response_correlation_id != correlation_id {
+            return Err(KafkaBridgeError::CorrelationIdMismatch(
+                correlation_id,
+                response_correlation_id,
+            ));
+        }
+        Ok(data)
+    }
+}
+
+#[derive(Default)]
+struct ProducerState {
+    // (node_id, addr)
+    brokers: HashMap<i32, String>,
+    partitions: Vec<PartitionMetadata>,
+    // (addr, connection)
+    connections: HashMap<String, BrokerConnection>,
+}
+
+impl ProducerState {
+    fn reset(&mut self) {
+        self.brokers.clear();
+        self.partitions.clear();
+        self.connections.clear();
+    }
+}
+
+// A minimal Kafka producer that writes to a single topic. Records with a key are
+// placed with the same murmur2 partitioner as the Java client, records without a
+// key are spread over the partitions in turn.
+pub struct KafkaProducer {
+    config: KafkaProducerConfig,
+    correlation_id: AtomicI32,
+    round_robin: AtomicUsize,
+    state: Mutex<ProducerState>,
+}
+
+impl KafkaProducer {
+    pub fn new(config: KafkaProducerConfig) -> Self {
+        KafkaProducer {
+            config,
+            correlation_id: AtomicI32::new(0),
+            round_robin: AtomicUsize::new(0),
+            state: Mutex::new(ProducerState::default()),
+        }
+    }
+
+    pub fn config(&self) -> &KafkaProducerConfig {
+        &self.config
+    }
+
+    // Fetches the metadata of the topic, fails if no bootstrap server can serve it.
+    pub async fn check_health(&self) -> Result<(), KafkaBridgeError> {
+        let mut state = self.state.lock().await;
+        self.refresh_metadata(&mut state).await
+    }
+
+    // Drops all broker connections, the next request reconnects.
+    pub async fn close(&self) {
+        self.state.lock().await.reset();
+    }
+
+    // Returns once every record has been acknowledged according to `acks`.
+    // Partitions that fail with a retriable error are retried up to `max_retries` times.
+    pub async fn send(&self, records: Vec<KafkaRecord>) -> Result<(), KafkaBridgeError> {
+        let mut pending = records;
+        let mut retry_times = 0;
+        loop {
+            if pending.is_empty() {
+                return Ok(());
+            }
+
+            let (failed, err) = match self.try_send(pending).await {
+                Ok(()) => return Ok(()),
+                Err(data) => data,
+            };
+
+            if !err.is_retriable() || retry_times >= self.config.max_retries {
+                return Err(err);
+            }
+
+            retry_times += 1;
+            warn!(
+                "Failed to send {} records to Kafka topic [{}], retry times: {}, error message: {}",
+                failed.len(),
+                self.config.topic,
+                retry_times,
+                err
+            );
+            self.state.lock().await.reset();
+            sleep(Duration::from_millis(
+                self.config.retry_backoff_ms * retry_times as u64,
+            ))
+            .await;
+            pending = failed;
+        }
+    }
+
+    async fn try_send(
+        &self,
+        records: Vec<KafkaRecord>,
+    ) -> Result<(), (Vec<KafkaRecord>, KafkaBridgeError)> {
+        let mut state = self.state.lock().await;
+        if state.partitions.is_empty() {
+            if let Err(e) = self.refresh_metadata(&mut state).await {
+                return Err((records, e));
+            }
+        }
+
+        // (leader, (partition, records))
+        let mut leader_data: BTreeMap<i32, BTreeMap<i32, Vec<KafkaRecord>>> = BTreeMap::new();
+        for record in records {
+            let partition = self.select_partition(&state.partitions, &record);
+            leader_data
+                .entry(partition.leader)
+                .or_default()
+                .entry(partition.partition)
+                .or_default()
+                .push(record);
+        }
+
+        let mut failed = Vec::new();
+        let mut last_err = None;
+        for (leader, partitions) in leader_data {
+            let partitions: Vec<(i32, Vec<KafkaRecord>)> = partitions.into_iter().collect();
+            let addr = if let Some(addr) = state.brokers.get(&leader) {
+                addr.clone()
+            } else {
+                let (partition, _) = partitions.first().unwrap();
+                last_err = Some(KafkaBridgeError::LeaderNotAvailable(
+                    self.config.topic.clone(),
+                    *partition,
+                ));
+                failed.extend(partitions.into_iter().flat_map(|(_, records)| records));
+                continue;
+            };
+
+            match self.produce(&mut state, &addr, &partitions).await {
+                Ok(errors) => {
+                    let errors: HashMap<i32, i16> = errors.into_iter().collect();
+                    for (partition, records) in partitions {
+                        if let Some(code) = errors.get(&partition) {
+                            last_err = Some(KafkaBridgeError::PartitionError(
+                                self.config.topic.clone(),
+                                partition,
+                                *code,
+                            ));
+                            failed.extend(records);
+                        }
+                    }
+                }
+                Err(e) => {
+                    state.connections.remove(&addr);
+                    last_err = Some(e);
+                    failed.extend(partitions.into_iter().flat_map(|(_, records)| records));
+                }
+            }
+        }
+
+        if let Some(e) = last_err {
+            return Err((failed, e));
+        }
+        Ok(())
+    }
+
+    // Returns the partitions that were rejected by the broker together with their error code.
+    async fn produce(
+        &self,
+        state: &mut ProducerState,
+        addr: &str,
+        partitions: &[(i32, Vec<KafkaRecord>)],
+    ) -> Result<Vec<(i32, i16)>, KafkaBridgeError> {
+        let correlation_id = self.next_correlation_id();
+        let request = encode_produce_request(
+            correlation_id,
+            &self.config.client_id,
+            self.config.acks,
+            self.config.timeout_ms as i32,
+            &self.config.topic,
+            partitions,
+        );
+
+        let acks = self.config.acks;
+        let conn = self.connection(state, addr).await?;
+        let data = self
+            .with_timeout(async {
+                conn.send(&request).await?;
+                // The broker does not answer at all when no acknowledgement is requested.
+                if acks == 0 {
+                    return Ok(None);
+                }
+                conn.receive(correlation_id).await.map(Some)
+            })
+            .await?;
+
+        let mut errors = Vec::new();
+        if let Some(data) = data {
+            for topic in decode_produce_response(data)? {
+                for partition in topic.partitions {
+                    if partition.error_code != 0 {
+                        errors.push((partition.partition, partition.error_code));
+                    }
+                }
+            }
+        }
+        Ok(errors)
+    }
+
+    async fn refresh_metadata(&self, state: &mut ProducerState) -> Result<(), KafkaBridgeError> {
+        let mut last_err = String::new();
+        for addr in self.config.bootstrap_servers.iter() {
+            match self.fetch_metadata(state, addr).await {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    state.connections.remove(addr);
+                    if !e.is_retriable() {
+                        return Err(e);
+                    }
+                    last_err = e.to_string();
+                }
+            }
+        }
+        Err(KafkaBridgeError::NoAvailableBootstrapServer(last_err))
+    }
+
+    async fn fetch_metadata(
+        &self,
+        state: &mut ProducerState,
+        addr: &str,
+    ) -> Result<(), KafkaBridgeError> {
+        let correlation_id = self.next_correlation_id();
+        let request = encode_metadata_request(
+            correlation_id,
+            &self.config.client_id,
+            &[self.config.topic.as_str()],
+        );
+
+        let conn = self.connection(state, addr).await?;
+        let data = self
+            .with_timeout(async {
+                conn.send(&request).await?;
+                conn.receive(correlation_id).await
+            })
+            .await?;
+        let metadata = decode_metadata_response(data)?;
+
+        let topic = if let Some(topic) = metadata
+            .topics
+            .into_iter()
+            .find(|topic| topic.name == self.config.topic)
+        {
+            topic
+        } else {
+            return Err(KafkaBridgeError::NoAvailablePartition(
+                self.config.topic.clone(),
+            ));
+        };
+
+        if topic.error_code != 0 {
+            return Err(KafkaBridgeError::TopicError(
+                self.config.topic.clone(),
+                topic.error_code,
+            ));
+        }
+
+        if topic.partitions.is_empty() {
+            return Err(KafkaBridgeError::NoAvailablePartition(
+                self.config.topic.clone(),
+            ));
+        }
+
+        state.brokers = metadata
+            .brokers
+            .iter()
+            .map(|broker| (broker.node_id, broker.addr()))
+            .collect();
+        let mut partitions = topic.partitions;
+        partitions.sort_by_key(|partition| partition.partition);
+        state.partitions = partitions;
+        Ok(())
+    }
+
+    fn select_partition<'a>(
+        &self,
+        partitions: &'a [PartitionMetadata],
+        record: &KafkaRecord,
+    ) -> &'a PartitionMetadata {
+        let index = if let Some(key) = &record.key {
+            (murmur2(key) & 0x7fffffff) as usize % partitions.len()
+        } else {
+            self.round_robin.fetch_add(1, Ordering::Relaxed) % partitions.len()
+        };
+        &partitions[index]
+    }
+
+    async fn connection<'a>(
+        &self,
+        state: &'a mut ProducerState,
+        addr: &str,
+    ) -> Result<&'a mut BrokerConnection, KafkaBridgeError> {
+        if !state.connections.contains_key(addr) {
+            let conn = self.with_timeout(BrokerConnection::connect(addr)).await?;
+            state.connections.insert(addr.to_string(), conn);
+        }
+        Ok(state.connections.get_mut(addr).unwrap())
+    }
+
+    async fn with_timeout<T>(
+        &self,
+        fut: impl std::future::Future<Output = Result<T, KafkaBridgeError>>,
+    ) -> Result<T, KafkaBridgeError> {
+        match timeout(Duration::from_millis(self.config.timeout_ms), fut).await {
+            Ok(res) => res,
+            Err(_) => Err(KafkaBridgeError::RequestTimeout(self.config.timeout_ms)),
+        }
+    }
+
+    fn next_correlation_id(&self) -> i32 {
+        self.correlation_id.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    use super::{BrokerConnection, KafkaProducer, MAX_RESPONSE_SIZE};
+    use crate::config::KafkaProducerConfig;
+    use crate::mock::MockKafkaServer;
+    use crate::protocol::KafkaRecord;
+
+    fn build_record(key: Option<&str>, value: &str) -> KafkaRecord {
+        KafkaRecord {
+            key: key.map(|k| k.as_bytes().to_vec()),
+            value: value.as_bytes().to_vec(),
+            headers: vec![("source".to_string(), b"mqtt".to_vec())],
+            timestamp: 1700000000000,
+        }
+    }
+
+    #[tokio::test]
+    async fn send_to_mock_server() {
+        let server = MockKafkaServer::start(3).await;
+        let config = KafkaProducerConfig::new(vec![server.addr()], "mqtt-data".to_string());
+        let producer = KafkaProducer::new(config);
+
+        let records = vec![
+            build_record(Some("client-1"), "m1"),
+            build_record(Some("client-1"), "m2"),
+            build_record(Some("client-2"), "m3"),
+            build_record(None, "m4"),
+        ];
+        producer.send(records.clone()).await.unwrap();
+
+        let received = server.records("mqtt-data");
+        assert_eq!(received.len(), 4);
+        for record in records.iter() {
+            assert!(received.iter().any(|(_, r)| r == record));
+        }
+
+        // records with the same key land on the same partition, in order
+        let client_1: Vec<(i32, KafkaRecord)> = received
+            .iter()
+            .filter(|(_, r)| r.key == Some(b"client-1".to_vec()))
+            .cloned()
+            .collect();
+        assert_eq!(client_1[0].0, client_1[1].0);
+        assert_eq!(client_1[0].1.value, b"m1".to_vec());
+        assert_eq!(client_1[1].1.value, b"m2".to_vec());
+    }
+
+    #[tokio::test]
+    async fn retry_on_retriable_error() {
+        let server = MockKafkaServer::start(1).await;
+        // NOT_LEADER_OR_FOLLOWER for the first two produce requests
+        server.fail_next_produce(6, 2);
+
+        let config = KafkaProducerConfig::new(vec![server.addr()], "mqtt-data".to_string());
+        let producer = KafkaProducer::new(config);
+        producer
+            .send(vec![build_record(Some("client-1"), "m1")])
+            .await
+            .unwrap();
+        assert_eq!(server.records("mqtt-data").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn give_up_after_max_retries() {
+        let server = MockKafkaServer::start(1).await;
+        server.fail_next_produce(6, 10);
+
+        let mut config = KafkaProducerConfig::new(vec![server.addr()], "mqtt-data".to_string());
+        config.max_retries = 2;
+        config.retry_backoff_ms = 1;
+        let producer = KafkaProducer::new(config);
+        assert!(producer
+            .send(vec![build_record(Some("client-1"), "m1")])
+            .await
+            .is_err());
+        assert!(server.records("mqtt-data").is_empty());
+    }
+
+    #[tokio::test]
+    async fn non_retriable_error() {
+        let server = MockKafkaServer::start(1).await;
+        // MESSAGE_TOO_LARGE
+        server.fail_next_produce(10, 1);
+
+        let config = KafkaProducerConfig::new(vec![server.addr()], "mqtt-data".to_string());
+        let producer = KafkaProducer::new(config);
+        assert!(producer.send(vec![build_record(None, "m1")]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn no_bootstrap_server() {
+        let mut config =
+            KafkaProducerConfig::new(vec!["127.0.0.1:1".to_string()], "mqtt-data".to_string());
+        config.max_retries = 0;
+        let producer = KafkaProducer::new(config);
+        assert!(producer.send(vec![build_record(None, "m1")]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn check_health() {
+        let server = MockKafkaServer::start(1).await;
+        let config = KafkaProducerConfig::new(vec![server.addr()], "mqtt-data".to_string());
+        let producer = KafkaProducer::new(config);
+        producer.check_health().await.unwrap();
+        producer.close().await;
+
+        let config =
+            KafkaProducerConfig::new(vec!["127.0.0.1:1".to_string()], "mqtt-data".to_string());
+        let producer = KafkaProducer::new(config);
+        assert!(producer.check_health().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn oversized_response_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream.write_i32(MAX_RESPONSE_SIZE + 1).await.unwrap();
+            stream.write_i32(1).await.unwrap();
+        });
+
+        let mut conn = BrokerConnection::connect(&addr).await.unwrap();
+        let err = conn.receive(1).await.unwrap_err();
+        assert!(err.to_string().contains("exceeds the limit"));
+    }
+}
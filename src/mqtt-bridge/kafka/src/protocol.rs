@@ -0,0 +1,517 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The subset of the Kafka wire protocol needed by the bridge: Metadata v1 to find
+//! partition leaders and Produce v3 carrying a v2 RecordBatch, which is the first
+//! record format that supports headers.
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use crate::error::KafkaBridgeError;
+
+pub const API_KEY_PRODUCE: i16 = 0;
+pub const API_KEY_METADATA: i16 = 3;
+pub const PRODUCE_API_VERSION: i16 = 3;
+pub const METADATA_API_VERSION: i16 = 1;
+
+const RECORD_BATCH_MAGIC: i8 = 2;
+// partitionLeaderEpoch + magic + crc
+const RECORD_BATCH_CRC_OFFSET: usize = 4 + 1 + 4;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KafkaRecord {
+    pub key: Option<Vec<u8>>,
+    pub value: Vec<u8>,
+    pub headers: Vec<(String, Vec<u8>)>,
+    // milliseconds since the unix epoch
+    pub timestamp: i64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BrokerMetadata {
+    pub node_id: i32,
+    pub host: String,
+    pub port: i32,
+}
+
+impl BrokerMetadata {
+    pub fn addr(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PartitionMetadata {
+    pub error_code: i16,
+    pub partition: i32,
+    pub leader: i32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TopicMetadata {
+    pub error_code: i16,
+    pub name: String,
+    pub partitions: Vec<PartitionMetadata>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MetadataResponse {
+    pub brokers: Vec<BrokerMetadata>,
+    pub topics: Vec<TopicMetadata>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PartitionProduceResponse {
+    pub partition: i32,
+    pub error_code: i16,
+    pub base_offset: i64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TopicProduceResponse {
+    pub topic: String,
+    pub partitions: Vec<PartitionProduceResponse>,
+}
+
+pub fn encode_metadata_request(correlation_id: i32, client_id: &str, topics: &[&str]) -> BytesMut {
+    let mut body = BytesMut::new();
+    encode_request_header(
+        &mut body,
+        API_KEY_METADATA,
+        METADATA_API_VERSION,
+        correlation_id,
+        client_id,
+    );
+    body.put_i32(topics.len() as i32);
+    for topic in topics {
+        put_string(&mut body, topic);
+    }
+    frame(body)
+}
+
+pub fn encode_produce_request(
+    correlation_id: i32,
+    client_id: &str,
+    acks: i16,
+    timeout_ms: i32,
+    topic: &str,
+    partitions: &[(i32, Vec<KafkaRecord>)],
+) -> BytesMut {
+    let mut body = BytesMut::new();
+    encode_request_header(
+        &mut body,
+        API_KEY_PRODUCE,
+        PRODUCE_API_VERSION,
+        correlation_id,
+        client_id,
+    );
+    // transactional_id
+    body.put_i16(-1);
+    body.put_i16(acks);
+    body.put_i32(timeout_ms);
+    body.put_i32(1);
+    put_string(&mut body, topic);
+    body.put_i32(partitions.len() as i32);
+    for (partition, records) in partitions {
+        body.put_i32(*partition);
+        let batch = encode_record_batch(records);
+        body.put_i32(batch.len() as i32);
+        body.put_slice(&batch);
+    }
+    frame(body)
+}
+
+pub fn decode_response_correlation_id(buf: &mut Bytes) -> Result<i32, KafkaBridgeError> {
+    get_i32(buf)
+}
+
+pub fn decode_metadata_response(mut buf: Bytes) -> Result<MetadataResponse, KafkaBridgeError> {
+    let mut response = MetadataResponse::default();
+
+    let broker_num = get_array_len(&mut buf)?;
+    for _ in 0..broker_num {
+        let node_id = get_i32(&mut buf)?;
+        let host = get_string(&mut buf)?;
+        let port = get_i32(&mut buf)?;
+        // rack
+        get_nullable_string(&mut buf)?;
+        response.brokers.push(BrokerMetadata {
+            node_id,
+            host,
+            port,
+        });
+    }
+
+    // controller_id
+    get_i32(&mut buf)?;
+
+    let topic_num = get_array_len(&mut buf)?;
+    for _ in 0..topic_num {
+        let error_code = get_i16(&mut buf)?;
+        let name = get_string(&mut buf)?;
+        // is_internal
+        get_i8(&mut buf)?;
+        let mut topic = TopicMetadata {
+            error_code,
+            name,
+            partitions: Vec::new(),
+        };
+        let partition_num = get_array_len(&mut buf)?;
+        for _ in 0..partition_num {
+            let error_code = get_i16(&mut buf)?;
+            let partition = get_i32(&mut buf)?;
+            let leader = get_i32(&mut buf)?;
+            // replicas and isr
+            for _ in 0..2 {
+                let len = get_array_len(&mut buf)?;
+                for _ in 0..len {
+                    get_i32(&mut buf)?;
+                }
+            }
+            topic.partitions.push(PartitionMetadata {
+                error_code,
+                partition,
+                leader,
+            });
+        }
+        response.topics.push(topic);
+    }
+    Ok(response)
+}
+
+pub fn decode_produce_response(
+    mut buf: Bytes,
+) -> Result<Vec<TopicProduceResponse>, KafkaBridgeError> {
+    let mut results = Vec::new();
+    let topic_num = get_array_len(&mut buf)?;
+    for _ in 0..topic_num {
+        let mut topic = TopicProduceResponse {
+            topic: get_string(&mut buf)?,
+            partitions: Vec::new(),
+        };
+        let partition_num = get_array_len(&mut buf)?;
+        for _ in 0..partition_num {
+            let partition = get_i32(&mut buf)?;
+            let error_code = get_i16(&mut buf)?;
+            let base_offset = get_i64(&mut buf)?;
+            // log_append_time
+            get_i64(&mut buf)?;
+            topic.partitions.push(PartitionProduceResponse {
+                partition,
+                error_code,
+                base_offset,
+            });
+        }
+        results.push(topic);
+    }
+    Ok(results)
+}
+
+pub fn encode_record_batch(records: &[KafkaRecord]) -> BytesMut {
+    let first_timestamp = records.iter().map(|r| r.timestamp).min().unwrap_or(0);
+    let max_timestamp = records.iter().map(|r| r.timestamp).max().unwrap_or(0);
+
+    // Everything from attributes to the end of the batch is covered by the crc.
+    let mut crc_body = BytesMut::new();
+    // attributes: no compression, create time
+    crc_body.put_i16(0);
+    crc_body.put_i32(records.len().saturating_sub(1) as i32);
+    crc_body.put_i64(first_timestamp);
+    crc_body.put_i64(max_timestamp);
+    // producer_id, producer_epoch, base_sequence: not an idempotent producer
+    crc_body.put_i64(-1);
+    crc_body.put_i16(-1);
+    crc_body.put_i32(-1);
+    crc_body.put_i32(records.len() as i32);
+    for (offset_delta, record) in records.iter().enumerate() {
+        encode_record(
+            &mut crc_body,
+            record,
+            record.timestamp - first_timestamp,
+            offset_delta as i64,
+        );
+    }
+
+    let mut batch = BytesMut::with_capacity(crc_body.len() + 8 + 4 + RECORD_BATCH_CRC_OFFSET);
+    // base_offset, assigned by the broker
+    batch.put_i64(0);
+    batch.put_i32((RECORD_BATCH_CRC_OFFSET + crc_body.len()) as i32);
+    // partition_leader_epoch
+    batch.put_i32(-1);
+    batch.put_i8(RECORD_BATCH_MAGIC);
+    batch.put_u32(crc32c(&crc_body));
+    batch.put_slice(&crc_body);
+    batch
+}
+
+fn encode_record(
+    buf: &mut BytesMut,
+    record: &KafkaRecord,
+    timestamp_delta: i64,
+    offset_delta: i64,
+) {
+    let mut body = BytesMut::new();
+    // attributes
+    body.put_i8(0);
+    put_varint(&mut body, timestamp_delta);
+    put_varint(&mut body, offset_delta);
+    if let Some(key) = &record.key {
+        put_varint(&mut body, key.len() as i64);
+        body.put_slice(key);
+    } else {
+        put_varint(&mut body, -1);
+    }
+    put_varint(&mut body, record.value.len() as i64);
+    body.put_slice(&record.value);
+    put_varint(&mut body, record.headers.len() as i64);
+    for (key, value) in record.headers.iter() {
+        put_varint(&mut body, key.len() as i64);
+        body.put_slice(key.as_bytes());
+        put_varint(&mut body, value.len() as i64);
+        body.put_slice(value);
+    }
+
+    put_varint(buf, body.len() as i64);
+    buf.put_slice(&body);
+}
+
+fn encode_request_header(
+    buf: &mut BytesMut,
+    api_key: i16,
+    api_version: i16,
+    correlation_id: i32,
+    client_id: &str,
+) {
+    buf.put_i16(api_key);
+    buf.put_i16(api_version);
+    buf.put_i32(correlation_id);
+    put_string(buf, client_id);
+}
+
+// Every Kafka request and response is prefixed with its size.
+fn frame(body: BytesMut) -> BytesMut {
+    let mut buf = BytesMut::with_capacity(body.len() + 4);
+    buf.put_i32(body.len() as i32);
+    buf.put_slice(&body);
+    buf
+}
+
+pub fn put_string(buf: &mut BytesMut, value: &str) {
+    buf.put_i16(value.len() as i16);
+    buf.put_slice(value.as_bytes());
+}
+
+// Zigzag encoded variable length integer, as used inside records.
+pub fn put_varint(buf: &mut BytesMut, value: i64) {
+    let mut v = ((value << 1) ^ (value >> 63)) as u64;
+    while v >= 0x80 {
+        buf.put_u8((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.put_u8(v as u8);
+}
+
+pub fn get_varint(buf: &mut Bytes) -> Result<i64, KafkaBridgeError> {
+    let mut value: u64 = 0;
+    let mut shift = 0;
+    loop {
+        if shift > 63 {
+            return Err(KafkaBridgeError::MalformedResponse(
+                "varint is too long".to_string(),
+            ));
+        }
+        let byte = get_i8(buf)? as u8;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    Ok(((value >> 1) as i64) ^ -((value & 1) as i64))
+}
+
+fn ensure_remaining(buf: &Bytes, len: usize) -> Result<(), KafkaBridgeError> {
+    if buf.remaining() < len {
+        return Err(KafkaBridgeError::MalformedResponse(format!(
+            "expected {} more bytes, only {} left",
+            len,
+            buf.remaining()
+        )));
+    }
+    Ok(())
+}
+
+pub fn get_i8(buf: &mut Bytes) -> Result<i8, KafkaBridgeError> {
+    ensure_remaining(buf, 1)?;
+    Ok(buf.get_i8())
+}
+
+pub fn get_i16(buf: &mut Bytes) -> Result<i16, KafkaBridgeError> {
+    ensure_remaining(buf, 2)?;
+    Ok(buf.get_i16())
+}
+
+pub fn get_i32(buf: &mut Bytes) -> Result<i32, KafkaBridgeError> {
+    ensure_remaining(buf, 4)?;
+    Ok(buf.get_i32())
+}
+
+pub fn get_i64(buf: &mut Bytes) -> Result<i64, KafkaBridgeError> {
+    ensure_remaining(buf, 8)?;
+    Ok(buf.get_i64())
+}
+
+pub fn get_array_len(buf: &mut Bytes) -> Result<usize, KafkaBridgeError> {
+    Ok(get_i32(buf)?.max(0) as usize)
+}
+
+pub fn get_bytes(buf: &mut Bytes, len: usize) -> Result<Bytes, KafkaBridgeError> {
+    ensure_remaining(buf, len)?;
+    Ok(buf.split_to(len))
+}
+
+pub fn get_string(buf: &mut Bytes) -> Result<String, KafkaBridgeError> {
+    Ok(get_nullable_string(buf)?.unwrap_or_default())
+}
+
+pub fn get_nullable_string(buf: &mut Bytes) -> Result<Option<String>, KafkaBridgeError> {
+    let len = get_i16(buf)?;
+    if len < 0 {
+        return Ok(None);
+    }
+    let data = get_bytes(buf, len as usize)?;
+    String::from_utf8(data.to_vec())
+        .map(Some)
+        .map_err(|e| KafkaBridgeError::MalformedResponse(e.to_string()))
+}
+
+// CRC-32C (Castagnoli), required by the v2 record batch format.
+pub fn crc32c(data: &[u8]) -> u32 {
+    const POLY: u32 = 0x82f6_3b78;
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ POLY
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+// The murmur2 hash used by the default Kafka partitioner, so that records carrying
+// the same key land on the same partition as they would with the Java client.
+pub fn murmur2(data: &[u8]) -> i32 {
+    const SEED: u32 = 0x9747_b28c;
+    const M: u32 = 0x5bd1_e995;
+    const R: u32 = 24;
+
+    let length = data.len();
+    let mut h: u32 = SEED ^ (length as u32);
+
+    let chunks = data.chunks_exact(4);
+    let tail = chunks.remainder();
+    for chunk in chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h = h.wrapping_mul(M);
+        h ^= k;
+    }
+
+    match tail.len() {
+        3 => {
+            h ^= (tail[2] as u32) << 16;
+            h ^= (tail[1] as u32) << 8;
+            h ^= tail[0] as u32;
+            h = h.wrapping_mul(M);
+        }
+        2 => {
+            h ^= (tail[1] as u32) << 8;
+            h ^= tail[0] as u32;
+            h = h.wrapping_mul(M);
+        }
+        1 => {
+            h ^= tail[0] as u32;
+            h = h.wrapping_mul(M);
+        }
+        _ => {}
+    }
+
+    h ^= h >> 13;
+    h = h.wrapping_mul(M);
+    h ^= h >> 15;
+    h as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{BufMut, Bytes, BytesMut};
+
+    use super::{crc32c, get_varint, murmur2, put_varint};
+
+    #[test]
+    fn crc32c_test() {
+        assert_eq!(crc32c(b""), 0);
+        assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+    }
+
+    #[test]
+    fn murmur2_test() {
+        // Values produced by org.apache.kafka.common.utils.Utils.murmur2
+        assert_eq!(murmur2(b"21"), -973932308);
+        assert_eq!(murmur2(b"foobar"), -790332482);
+        assert_eq!(murmur2(b"a-little-bit-long-string"), -985981536);
+        assert_eq!(murmur2(b"a-little-bit-longer-string"), -1486304829);
+        assert_eq!(
+            murmur2(b"lkjh234lh9fiuh90y23oiuhsafujhadof229phr9h19h89h8"),
+            -58897971
+        );
+        assert_eq!(murmur2(b"abc"), 479470107);
+    }
+
+    #[test]
+    fn varint_test() {
+        for value in [
+            0i64,
+            1,
+            -1,
+            63,
+            -64,
+            64,
+            300,
+            -300,
+            i32::MAX as i64,
+            i64::MIN,
+        ] {
+            let mut buf = BytesMut::new();
+            put_varint(&mut buf, value);
+            let mut data = buf.freeze();
+            assert_eq!(get_varint(&mut data).unwrap(), value);
+            assert!(data.is_empty());
+        }
+
+        let mut buf = BytesMut::new();
+        put_varint(&mut buf, 300);
+        assert_eq!(buf.to_vec(), vec![0xd8, 0x04]);
+
+        let mut buf = BytesMut::new();
+        buf.put_u8(0x80);
+        assert!(get_varint(&mut Bytes::from(buf.to_vec())).is_err());
+    }
+}
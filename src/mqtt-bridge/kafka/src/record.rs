@@ -0,0 +1,77 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use metadata_struct::mqtt::message::MqttMessage;
+
+use crate::config::KafkaMessageKey;
+use crate::protocol::KafkaRecord;
+
+// Converts an MQTT message into a Kafka record. The payload is forwarded untouched,
+// MQTT 5 user properties become record headers.
+pub fn build_kafka_record(message: &MqttMessage, key: &KafkaMessageKey) -> KafkaRecord {
+    let key = match key {
+        KafkaMessageKey::ClientId => Some(message.client_id.as_bytes().to_vec()),
+        KafkaMessageKey::Topic => Some(message.topic.to_vec()),
+        KafkaMessageKey::None => None,
+    };
+
+    let headers = message
+        .user_properties
+        .iter()
+        .map(|(k, v)| (k.clone(), v.as_bytes().to_vec()))
+        .collect();
+
+    KafkaRecord {
+        key,
+        value: message.payload.to_vec(),
+        headers,
+        timestamp: (message.create_time * 1000) as i64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use metadata_struct::mqtt::message::MqttMessage;
+
+    use super::build_kafka_record;
+    use crate::config::KafkaMessageKey;
+
+    #[test]
+    fn build_kafka_record_test() {
+        let message = MqttMessage {
+            client_id: "client-1".to_string(),
+            topic: Bytes::from("sensor/1/temp"),
+            payload: Bytes::from("21.5"),
+            user_properties: vec![("unit".to_string(), "celsius".to_string())],
+            create_time: 1700000000,
+            ..Default::default()
+        };
+
+        let record = build_kafka_record(&message, &KafkaMessageKey::ClientId);
+        assert_eq!(record.key, Some(b"client-1".to_vec()));
+        assert_eq!(record.value, b"21.5".to_vec());
+        assert_eq!(
+            record.headers,
+            vec![("unit".to_string(), b"celsius".to_vec())]
+        );
+        assert_eq!(record.timestamp, 1700000000000);
+
+        let record = build_kafka_record(&message, &KafkaMessageKey::Topic);
+        assert_eq!(record.key, Some(b"sensor/1/temp".to_vec()));
+
+        let record = build_kafka_record(&message, &KafkaMessageKey::None);
+        assert_eq!(record.key, None);
+    }
+}
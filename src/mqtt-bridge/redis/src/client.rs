@@ -0,0 +1,279 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use bytes::BytesMut;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio::time::timeout;
+
+use crate::config::RedisBridgeConfig;
+use crate::error::RedisBridgeError;
+use crate::resp::{decode_value, encode_command, RespValue};
+
+struct RedisConnection {
+    stream: TcpStream,
+    read_buf: BytesMut,
+}
+
+impl RedisConnection {
+    async fn connect(addr: &str) -> Result<Self, RedisBridgeError> {
+        let stream = TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+        Ok(RedisConnection {
+            stream,
+            read_buf: BytesMut::with_capacity(4096),
+        })
+    }
+
+    async fn execute(
+        &mut self,
+        commands: &[Vec<Vec<u8>>],
+    ) -> Result<Vec<RespValue>, RedisBridgeError> {
+        let mut buf = BytesMut::new();
+        for command in commands {
+            encode_command(&mut buf, command);
+        }
+        self.stream.write_all(&buf).await?;
+
+        let mut replies = Vec::with_capacity(commands.len());
+        while replies.len() < commands.len() {
+            replies.push(self.read_value().await?);
+        }
+        Ok(replies)
+    }
+
+    async fn read_value(&mut self) -> Result<RespValue, RedisBridgeError> {
+        loop {
+            if let Some(value) = decode_value(&mut self.read_buf)? {
+                return Ok(value);
+            }
+            if self.stream.read_buf(&mut self.read_buf).await? == 0 {
+                return Err(RedisBridgeError::FromIoError(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "connection closed by Redis server",
+                )));
+            }
+        }
+    }
+}
+
+// A single connection Redis client that sends commands in pipelines.
+// The connection is established lazily and dropped after any failure,
+// the next call reconnects.
+pub struct RedisClient {
+    config: RedisBridgeConfig,
+    conn: Mutex<Option<RedisConnection>>,
+}
+
+impl RedisClient {
+    pub fn new(config: RedisBridgeConfig) -> Self {
+        RedisClient {
+            config,
+            conn: Mutex::new(None),
+        }
+    }
+
+    // Sends all commands in one round trip. Fails if any command was rejected,
+    // the commands before and after it have still been executed by the server.
+    pub async fn pipeline(
+        &self,
+        commands: &[Vec<Vec<u8>>],
+    ) -> Result<Vec<RespValue>, RedisBridgeError> {
+        if commands.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut conn = self.conn.lock().await;
+        let result = match timeout(
+            Duration::from_millis(self.config.timeout_ms),
+            self.execute(&mut conn, commands),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(_) => Err(RedisBridgeError::RequestTimeout(self.config.timeout_ms)),
+        };
+
+        let replies = match result {
+            Ok(replies) => replies,
+            Err(e) => {
+                *conn = None;
+                return Err(e);
+            }
+        };
+
+        check_replies(&replies)?;
+        Ok(replies)
+    }
+
+    pub async fn ping(&self) -> Result<(), RedisBridgeError> {
+        let replies = self.pipeline(&[vec![b"PING".to_vec()]]).await?;
+        match replies.first() {
+            Some(RespValue::SimpleString(pong)) if pong == "PONG" => Ok(()),
+            reply => Err(RedisBridgeError::MalformedReply(format!(
+                "unexpected reply to PING: {:?}",
+                reply
+            ))),
+        }
+    }
+
+    // Drops the connection, the next call reconnects.
+    pub async fn close(&self) {
+        *self.conn.lock().await = None;
+    }
+
+    async fn execute(
+        &self,
+        conn: &mut Option<RedisConnection>,
+        commands: &[Vec<Vec<u8>>],
+    ) -> Result<Vec<RespValue>, RedisBridgeError> {
+        if conn.is_none() {
+            let mut new_conn = RedisConnection::connect(&self.config.addr).await?;
+            let handshake = self.handshake_commands();
+            if !handshake.is_empty() {
+                check_replies(&new_conn.execute(&handshake).await?)?;
+            }
+            *conn = Some(new_conn);
+        }
+        conn.as_mut().unwrap().execute(commands).await
+    }
+
+    fn handshake_commands(&self) -> Vec<Vec<Vec<u8>>> {
+        let mut commands = Vec::new();
+        if !self.config.password.is_empty() {
+            let mut auth = vec![b"AUTH".to_vec()];
+            if !self.config.username.is_empty() {
+                auth.push(self.config.username.as_bytes().to_vec());
+            }
+            auth.push(self.config.password.as_bytes().to_vec());
+            commands.push(auth);
+        }
+        if self.config.db > 0 {
+            commands.push(vec![
+                b"SELECT".to_vec(),
+                self.config.db.to_string().into_bytes(),
+            ]);
+        }
+        commands
+    }
+}
+
+fn check_replies(replies: &[RespValue]) -> Result<(), RedisBridgeError> {
+    for reply in replies {
+        if let RespValue::Error(e) = reply {
+            return Err(RedisBridgeError::ServerError(e.clone()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RedisClient;
+    use crate::config::RedisBridgeConfig;
+    use crate::mock::MockRedisServer;
+    use crate::resp::RespValue;
+
+    fn build_config(addr: String) -> RedisBridgeConfig {
+        RedisBridgeConfig {
+            addr,
+            key_template: "k".to_string(),
+            timeout_ms: 1000,
+            ..Default::default()
+        }
+    }
+
+    fn command(args: &[&str]) -> Vec<Vec<u8>> {
+        args.iter().map(|arg| arg.as_bytes().to_vec()).collect()
+    }
+
+    #[tokio::test]
+    async fn pipeline_test() {
+        let server = MockRedisServer::start(None).await;
+        let client = RedisClient::new(build_config(server.addr()));
+
+        let replies = client
+            .pipeline(&[
+                command(&["SET", "k1", "v1"]),
+                command(&["LPUSH", "l1", "a"]),
+                command(&["LPUSH", "l1", "b"]),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(
+            replies,
+            vec![
+                RespValue::SimpleString("OK".to_string()),
+                RespValue::Integer(1),
+                RespValue::Integer(2)
+            ]
+        );
+        assert_eq!(server.string("k1"), Some(b"v1".to_vec()));
+        assert_eq!(server.list("l1"), vec![b"b".to_vec(), b"a".to_vec()]);
+
+        // an unknown command is rejected by the server
+        assert!(client
+            .pipeline(&[command(&["HSET", "h", "f", "v"])])
+            .await
+            .is_err());
+        // and the client keeps working
+        client
+            .pipeline(&[command(&["SET", "k1", "v2"])])
+            .await
+            .unwrap();
+        assert_eq!(server.string("k1"), Some(b"v2".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn auth_test() {
+        let server = MockRedisServer::start(Some("pwd123".to_string())).await;
+
+        let client = RedisClient::new(build_config(server.addr()));
+        assert!(client
+            .pipeline(&[command(&["SET", "k1", "v1"])])
+            .await
+            .is_err());
+
+        let mut config = build_config(server.addr());
+        config.password = "pwd123".to_string();
+        let client = RedisClient::new(config);
+        client
+            .pipeline(&[command(&["SET", "k1", "v1"])])
+            .await
+            .unwrap();
+        assert_eq!(server.string("k1"), Some(b"v1".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn ping_test() {
+        let server = MockRedisServer::start(None).await;
+        let client = RedisClient::new(build_config(server.addr()));
+        client.ping().await.unwrap();
+        client.close().await;
+        client.ping().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn connect_failed() {
+        let client = RedisClient::new(build_config("127.0.0.1:1".to_string()));
+        assert!(client.ping().await.is_err());
+        assert!(client
+            .pipeline(&[command(&["SET", "k1", "v1"])])
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,108 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::RedisBridgeError;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedisCommand {
+    // XADD to a stream, the message is stored as field/value pairs
+    #[default]
+    Xadd,
+    // LPUSH the payload to a list
+    Lpush,
+    // PUBLISH the payload to a channel
+    Publish,
+    // SET the payload, so the key always holds the last value
+    Set,
+}
+
+impl FromStr for RedisCommand {
+    type Err = RedisBridgeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "xadd" => Ok(RedisCommand::Xadd),
+            "lpush" => Ok(RedisCommand::Lpush),
+            "publish" => Ok(RedisCommand::Publish),
+            "set" => Ok(RedisCommand::Set),
+            _ => Err(RedisBridgeError::InvalidCommand(s.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RedisBridgeConfig {
+    // host:port
+    pub addr: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default)]
+    pub db: u32,
+    #[serde(default)]
+    pub command: RedisCommand,
+    // See `KeyTemplate` for the supported placeholders.
+    pub key_template: String,
+    // Approximate MAXLEN of the stream for XADD, 0 means the stream is not trimmed.
+    #[serde(default)]
+    pub stream_max_len: u64,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_timeout_ms() -> u64 {
+    5000
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::{RedisBridgeConfig, RedisCommand};
+
+    #[test]
+    fn command_from_str() {
+        assert_eq!(RedisCommand::from_str("XADD").unwrap(), RedisCommand::Xadd);
+        assert_eq!(
+            RedisCommand::from_str("lpush").unwrap(),
+            RedisCommand::Lpush
+        );
+        assert_eq!(
+            RedisCommand::from_str("publish").unwrap(),
+            RedisCommand::Publish
+        );
+        assert_eq!(RedisCommand::from_str("set").unwrap(), RedisCommand::Set);
+        assert!(RedisCommand::from_str("hset").is_err());
+    }
+
+    #[test]
+    fn config_from_json() {
+        let config: RedisBridgeConfig = serde_json::from_str(
+            r#"{"addr":"127.0.0.1:6379","command":"set","key_template":"last:${topic}"}"#,
+        )
+        .unwrap();
+        assert_eq!(config.addr, "127.0.0.1:6379".to_string());
+        assert_eq!(config.command, RedisCommand::Set);
+        assert_eq!(config.db, 0);
+        assert!(config.password.is_empty());
+        assert_eq!(config.stream_max_len, 0);
+        assert_eq!(config.timeout_ms, 5000);
+    }
+}
@@ -0,0 +1,36 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum RedisBridgeError {
+    #[error("{0}")]
+    FromIoError(#[from] std::io::Error),
+
+    #[error("Redis request timed out after {0} ms")]
+    RequestTimeout(u64),
+
+    #[error("Malformed reply from Redis server: {0}")]
+    MalformedReply(String),
+
+    #[error("Redis server returned an error: {0}")]
+    ServerError(String),
+
+    #[error("Invalid Redis command [{0}], optional: xadd, lpush, publish, set")]
+    InvalidCommand(String),
+
+    #[error("Invalid key template [{0}], {1}")]
+    InvalidKeyTemplate(String, String),
+}
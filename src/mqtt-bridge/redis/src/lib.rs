@@ -11,3 +11,12 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+pub mod client;
+pub mod config;
+pub mod error;
+#[cfg(test)]
+mod mock;
+pub mod record;
+pub mod resp;
+pub mod template;
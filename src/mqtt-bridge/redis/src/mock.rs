@@ -0,0 +1,204 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An in-process RESP stand-in implementing the handful of commands the bridge
+//! uses, with an in-memory keyspace the tests can inspect.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use bytes::BytesMut;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::error::RedisBridgeError;
+use crate::resp::{decode_value, encode_value, RespValue};
+
+// (field, value) pairs of one stream entry
+pub type StreamEntry = Vec<(Vec<u8>, Vec<u8>)>;
+
+#[derive(Default)]
+struct MockState {
+    strings: HashMap<Vec<u8>, Vec<u8>>,
+    lists: HashMap<Vec<u8>, VecDeque<Vec<u8>>>,
+    streams: HashMap<Vec<u8>, Vec<StreamEntry>>,
+    published: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+pub struct MockRedisServer {
+    port: u16,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockRedisServer {
+    pub async fn start(password: Option<String>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let state = Arc::new(Mutex::new(MockState::default()));
+
+        let server_state = state.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = server_state.clone();
+                let password = password.clone();
+                tokio::spawn(async move {
+                    let _ = handle_connection(stream, password, state).await;
+                });
+            }
+        });
+        MockRedisServer { port, state }
+    }
+
+    pub fn addr(&self) -> String {
+        format!("127.0.0.1:{}", self.port)
+    }
+
+    pub fn string(&self, key: &str) -> Option<Vec<u8>> {
+        let state = self.state.lock().unwrap();
+        state.strings.get(key.as_bytes()).cloned()
+    }
+
+    pub fn list(&self, key: &str) -> Vec<Vec<u8>> {
+        let state = self.state.lock().unwrap();
+        state
+            .lists
+            .get(key.as_bytes())
+            .map(|list| list.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn stream(&self, key: &str) -> Vec<StreamEntry> {
+        let state = self.state.lock().unwrap();
+        state
+            .streams
+            .get(key.as_bytes())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn published(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.state.lock().unwrap().published.clone()
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    password: Option<String>,
+    state: Arc<Mutex<MockState>>,
+) -> Result<(), RedisBridgeError> {
+    let mut authenticated = password.is_none();
+    let mut read_buf = BytesMut::new();
+    loop {
+        let value = loop {
+            if let Some(value) = decode_value(&mut read_buf)? {
+                break value;
+            }
+            if stream.read_buf(&mut read_buf).await? == 0 {
+                return Ok(());
+            }
+        };
+
+        let args = match value {
+            RespValue::Array(Some(values)) => values
+                .into_iter()
+                .map(|value| match value {
+                    RespValue::BulkString(Some(data)) => data,
+                    _ => Vec::new(),
+                })
+                .collect::<Vec<Vec<u8>>>(),
+            _ => Vec::new(),
+        };
+
+        let reply = execute(&args, &password, &mut authenticated, &state);
+        let mut buf = BytesMut::new();
+        encode_value(&mut buf, &reply);
+        stream.write_all(&buf).await?;
+    }
+}
+
+fn execute(
+    args: &[Vec<u8>],
+    password: &Option<String>,
+    authenticated: &mut bool,
+    state: &Arc<Mutex<MockState>>,
+) -> RespValue {
+    let name = args
+        .first()
+        .map(|name| String::from_utf8_lossy(name).to_uppercase())
+        .unwrap_or_default();
+
+    if name == "AUTH" {
+        let given = args.last().map(|p| String::from_utf8_lossy(p).to_string());
+        if args.len() >= 2 && given == *password {
+            *authenticated = true;
+            return RespValue::SimpleString("OK".to_string());
+        }
+        return RespValue::Error("WRONGPASS invalid password".to_string());
+    }
+    if !*authenticated {
+        return RespValue::Error("NOAUTH Authentication required.".to_string());
+    }
+
+    let mut state = state.lock().unwrap();
+    match (name.as_str(), args.len()) {
+        ("SELECT", 2) => RespValue::SimpleString("OK".to_string()),
+        ("PING", 1) => RespValue::SimpleString("PONG".to_string()),
+        ("SET", 3) => {
+            state.strings.insert(args[1].clone(), args[2].clone());
+            RespValue::SimpleString("OK".to_string())
+        }
+        ("LPUSH", n) if n >= 3 => {
+            let list = state.lists.entry(args[1].clone()).or_default();
+            for value in args[2..].iter() {
+                list.push_front(value.clone());
+            }
+            RespValue::Integer(list.len() as i64)
+        }
+        ("PUBLISH", 3) => {
+            state.published.push((args[1].clone(), args[2].clone()));
+            RespValue::Integer(0)
+        }
+        ("XADD", n) if n >= 5 => {
+            let mut pos = 2;
+            let mut max_len = None;
+            if args[pos].eq_ignore_ascii_case(b"MAXLEN") {
+                pos += 1;
+                if args[pos] == b"~" || args[pos] == b"=" {
+                    pos += 1;
+                }
+                max_len = String::from_utf8_lossy(&args[pos]).parse::<usize>().ok();
+                pos += 1;
+            }
+            // the entry id, always `*` for the bridge
+            pos += 1;
+            if pos >= n || (n - pos) % 2 != 0 {
+                return RespValue::Error("ERR wrong number of arguments for 'xadd'".to_string());
+            }
+
+            let entry = args[pos..]
+                .chunks(2)
+                .map(|pair| (pair[0].clone(), pair[1].clone()))
+                .collect();
+            let entries = state.streams.entry(args[1].clone()).or_default();
+            entries.push(entry);
+            if let Some(max_len) = max_len {
+                while entries.len() > max_len {
+                    entries.remove(0);
+                }
+            }
+            RespValue::BulkString(Some(format!("{}-0", entries.len()).into_bytes()))
+        }
+        _ => RespValue::Error(format!("ERR unknown command '{}'", name)),
+    }
+}
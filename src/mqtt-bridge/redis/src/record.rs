@@ -0,0 +1,180 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use metadata_struct::mqtt::message::MqttMessage;
+
+use crate::config::{RedisBridgeConfig, RedisCommand};
+use crate::template::KeyTemplate;
+
+// Builds the Redis command that writes one MQTT message. LPUSH, PUBLISH and SET
+// carry the raw payload, XADD stores the payload next to the message metadata.
+pub fn build_redis_command(
+    config: &RedisBridgeConfig,
+    key_template: &KeyTemplate,
+    message: &MqttMessage,
+) -> Vec<Vec<u8>> {
+    let topic = String::from_utf8_lossy(&message.topic).to_string();
+    let key = key_template.render(&topic, &message.client_id).into_bytes();
+    let payload = message.payload.to_vec();
+
+    match config.command {
+        RedisCommand::Xadd => {
+            let mut command = vec![b"XADD".to_vec(), key];
+            if config.stream_max_len > 0 {
+                command.push(b"MAXLEN".to_vec());
+                command.push(b"~".to_vec());
+                command.push(config.stream_max_len.to_string().into_bytes());
+            }
+            command.push(b"*".to_vec());
+            command.push(b"topic".to_vec());
+            command.push(topic.into_bytes());
+            command.push(b"client_id".to_vec());
+            command.push(message.client_id.as_bytes().to_vec());
+            command.push(b"qos".to_vec());
+            command.push((message.qos as u8).to_string().into_bytes());
+            command.push(b"create_time".to_vec());
+            command.push(message.create_time.to_string().into_bytes());
+            command.push(b"payload".to_vec());
+            command.push(payload);
+            command
+        }
+        RedisCommand::Lpush => vec![b"LPUSH".to_vec(), key, payload],
+        RedisCommand::Publish => vec![b"PUBLISH".to_vec(), key, payload],
+        RedisCommand::Set => vec![b"SET".to_vec(), key, payload],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use metadata_struct::mqtt::message::MqttMessage;
+    use protocol::mqtt::common::QoS;
+
+    use super::build_redis_command;
+    use crate::client::RedisClient;
+    use crate::config::{RedisBridgeConfig, RedisCommand};
+    use crate::mock::MockRedisServer;
+    use crate::template::KeyTemplate;
+
+    fn to_strings(command: Vec<Vec<u8>>) -> Vec<String> {
+        command
+            .into_iter()
+            .map(|arg| String::from_utf8(arg).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn build_redis_command_test() {
+        let message = MqttMessage {
+            client_id: "c1".to_string(),
+            qos: QoS::AtLeastOnce,
+            topic: Bytes::from("/sensor/1/temp"),
+            payload: Bytes::from("21.5"),
+            create_time: 1700000000,
+            ..Default::default()
+        };
+        let template = KeyTemplate::parse("sensor:${topic[1]}").unwrap();
+        let mut config = RedisBridgeConfig {
+            command: RedisCommand::Set,
+            ..Default::default()
+        };
+        assert_eq!(
+            to_strings(build_redis_command(&config, &template, &message)),
+            vec!["SET", "sensor:1", "21.5"]
+        );
+
+        config.command = RedisCommand::Lpush;
+        assert_eq!(
+            to_strings(build_redis_command(&config, &template, &message)),
+            vec!["LPUSH", "sensor:1", "21.5"]
+        );
+
+        config.command = RedisCommand::Publish;
+        assert_eq!(
+            to_strings(build_redis_command(&config, &template, &message)),
+            vec!["PUBLISH", "sensor:1", "21.5"]
+        );
+
+        config.command = RedisCommand::Xadd;
+        config.stream_max_len = 1000;
+        assert_eq!(
+            to_strings(build_redis_command(&config, &template, &message)),
+            vec![
+                "XADD",
+                "sensor:1",
+                "MAXLEN",
+                "~",
+                "1000",
+                "*",
+                "topic",
+                "/sensor/1/temp",
+                "client_id",
+                "c1",
+                "qos",
+                "1",
+                "create_time",
+                "1700000000",
+                "payload",
+                "21.5"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn write_to_mock_server() {
+        let server = MockRedisServer::start(None).await;
+        let mut config = RedisBridgeConfig {
+            addr: server.addr(),
+            command: RedisCommand::Xadd,
+            timeout_ms: 1000,
+            ..Default::default()
+        };
+        let template = KeyTemplate::parse("stream:${client_id}").unwrap();
+        let messages: Vec<MqttMessage> = (0..3)
+            .map(|i| MqttMessage {
+                client_id: "c1".to_string(),
+                topic: Bytes::from("/sensor/1/temp"),
+                payload: Bytes::from(format!("{}", i)),
+                ..Default::default()
+            })
+            .collect();
+
+        let client = RedisClient::new(config.clone());
+        let commands: Vec<Vec<Vec<u8>>> = messages
+            .iter()
+            .map(|message| build_redis_command(&config, &template, message))
+            .collect();
+        client.pipeline(&commands).await.unwrap();
+
+        let entries = server.stream("stream:c1");
+        assert_eq!(entries.len(), 3);
+        assert!(entries[2].contains(&(b"payload".to_vec(), b"2".to_vec())));
+        assert!(entries[2].contains(&(b"topic".to_vec(), b"/sensor/1/temp".to_vec())));
+
+        config.command = RedisCommand::Publish;
+        let commands: Vec<Vec<Vec<u8>>> = messages
+            .iter()
+            .map(|message| build_redis_command(&config, &template, message))
+            .collect();
+        client.pipeline(&commands).await.unwrap();
+        assert_eq!(
+            server.published(),
+            vec![
+                (b"stream:c1".to_vec(), b"0".to_vec()),
+                (b"stream:c1".to_vec(), b"1".to_vec()),
+                (b"stream:c1".to_vec(), b"2".to_vec())
+            ]
+        );
+    }
+}
@@ -0,0 +1,205 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encoding of commands and decoding of replies in the Redis serialization
+//! protocol (RESP2). Commands are always sent as arrays of bulk strings.
+
+use bytes::{Buf, BufMut, BytesMut};
+
+use crate::error::RedisBridgeError;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RespValue {
+    SimpleString(String),
+    Error(String),
+    Integer(i64),
+    BulkString(Option<Vec<u8>>),
+    Array(Option<Vec<RespValue>>),
+}
+
+pub fn encode_command(buf: &mut BytesMut, args: &[Vec<u8>]) {
+    buf.put_slice(format!("*{}\r\n", args.len()).as_bytes());
+    for arg in args {
+        buf.put_slice(format!("${}\r\n", arg.len()).as_bytes());
+        buf.put_slice(arg);
+        buf.put_slice(b"\r\n");
+    }
+}
+
+pub fn encode_value(buf: &mut BytesMut, value: &RespValue) {
+    match value {
+        RespValue::SimpleString(s) => buf.put_slice(format!("+{}\r\n", s).as_bytes()),
+        RespValue::Error(s) => buf.put_slice(format!("-{}\r\n", s).as_bytes()),
+        RespValue::Integer(i) => buf.put_slice(format!(":{}\r\n", i).as_bytes()),
+        RespValue::BulkString(None) => buf.put_slice(b"$-1\r\n"),
+        RespValue::BulkString(Some(data)) => {
+            buf.put_slice(format!("${}\r\n", data.len()).as_bytes());
+            buf.put_slice(data);
+            buf.put_slice(b"\r\n");
+        }
+        RespValue::Array(None) => buf.put_slice(b"*-1\r\n"),
+        RespValue::Array(Some(values)) => {
+            buf.put_slice(format!("*{}\r\n", values.len()).as_bytes());
+            for value in values {
+                encode_value(buf, value);
+            }
+        }
+    }
+}
+
+// Decodes one value from the front of `buf`. Returns None and leaves `buf`
+// untouched when more data is needed.
+pub fn decode_value(buf: &mut BytesMut) -> Result<Option<RespValue>, RedisBridgeError> {
+    match parse_value(buf, 0)? {
+        Some((value, len)) => {
+            buf.advance(len);
+            Ok(Some(value))
+        }
+        None => Ok(None),
+    }
+}
+
+fn parse_value(buf: &[u8], start: usize) -> Result<Option<(RespValue, usize)>, RedisBridgeError> {
+    let (line, next) = if let Some(data) = read_line(buf, start) {
+        data
+    } else {
+        return Ok(None);
+    };
+    if line.is_empty() {
+        return Err(RedisBridgeError::MalformedReply("empty line".to_string()));
+    }
+
+    let content = String::from_utf8_lossy(&line[1..]).to_string();
+    match line[0] {
+        b'+' => Ok(Some((RespValue::SimpleString(content), next))),
+        b'-' => Ok(Some((RespValue::Error(content), next))),
+        b':' => Ok(Some((RespValue::Integer(parse_int(&content)?), next))),
+        b'$' => {
+            let len = parse_int(&content)?;
+            if len < 0 {
+                return Ok(Some((RespValue::BulkString(None), next)));
+            }
+            let end = next + len as usize;
+            if buf.len() < end + 2 {
+                return Ok(None);
+            }
+            if &buf[end..end + 2] != b"\r\n" {
+                return Err(RedisBridgeError::MalformedReply(
+                    "bulk string is not terminated by CRLF".to_string(),
+                ));
+            }
+            Ok(Some((
+                RespValue::BulkString(Some(buf[next..end].to_vec())),
+                end + 2,
+            )))
+        }
+        b'*' => {
+            let len = parse_int(&content)?;
+            if len < 0 {
+                return Ok(Some((RespValue::Array(None), next)));
+            }
+            let mut values = Vec::with_capacity(len as usize);
+            let mut pos = next;
+            for _ in 0..len {
+                match parse_value(buf, pos)? {
+                    Some((value, next_pos)) => {
+                        values.push(value);
+                        pos = next_pos;
+                    }
+                    None => return Ok(None),
+                }
+            }
+            Ok(Some((RespValue::Array(Some(values)), pos)))
+        }
+        flag => Err(RedisBridgeError::MalformedReply(format!(
+            "unknown type flag [{}]",
+            flag as char
+        ))),
+    }
+}
+
+// Returns the line starting at `start` without its CRLF, and the position after the CRLF.
+fn read_line(buf: &[u8], start: usize) -> Option<(&[u8], usize)> {
+    if start >= buf.len() {
+        return None;
+    }
+    buf[start..]
+        .windows(2)
+        .position(|w| w == b"\r\n")
+        .map(|pos| (&buf[start..start + pos], start + pos + 2))
+}
+
+fn parse_int(s: &str) -> Result<i64, RedisBridgeError> {
+    s.parse::<i64>()
+        .map_err(|e| RedisBridgeError::MalformedReply(format!("{}: {}", s, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use super::{decode_value, encode_command, encode_value, RespValue};
+
+    #[test]
+    fn encode_command_test() {
+        let mut buf = BytesMut::new();
+        encode_command(&mut buf, &[b"SET".to_vec(), b"k".to_vec(), b"v1".to_vec()]);
+        assert_eq!(&buf[..], b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$2\r\nv1\r\n");
+    }
+
+    #[test]
+    fn value_round_trip() {
+        let values = [
+            RespValue::SimpleString("OK".to_string()),
+            RespValue::Error("ERR wrong type".to_string()),
+            RespValue::Integer(-12),
+            RespValue::BulkString(None),
+            RespValue::BulkString(Some(b"a\r\nb".to_vec())),
+            RespValue::Array(None),
+            RespValue::Array(Some(vec![
+                RespValue::Integer(1),
+                RespValue::BulkString(Some(Vec::new())),
+            ])),
+        ];
+
+        let mut buf = BytesMut::new();
+        for value in values.iter() {
+            encode_value(&mut buf, value);
+        }
+        for value in values.iter() {
+            assert_eq!(decode_value(&mut buf).unwrap().unwrap(), *value);
+        }
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn decode_partial_value() {
+        let mut buf = BytesMut::from(&b"*2\r\n$5\r\nhel"[..]);
+        assert!(decode_value(&mut buf).unwrap().is_none());
+        assert_eq!(buf.len(), 11);
+
+        buf.extend_from_slice(b"lo\r\n:7\r\n");
+        assert_eq!(
+            decode_value(&mut buf).unwrap().unwrap(),
+            RespValue::Array(Some(vec![
+                RespValue::BulkString(Some(b"hello".to_vec())),
+                RespValue::Integer(7)
+            ]))
+        );
+        assert!(buf.is_empty());
+
+        let mut buf = BytesMut::from(&b"?x\r\n"[..]);
+        assert!(decode_value(&mut buf).is_err());
+    }
+}
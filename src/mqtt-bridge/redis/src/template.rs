@@ -0,0 +1,128 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::error::RedisBridgeError;
+
+#[derive(Clone, Debug, PartialEq)]
+enum TemplatePart {
+    Text(String),
+    Topic,
+    ClientId,
+    // index into the non-empty segments of the topic name
+    TopicSegment(usize),
+}
+
+// Builds Redis keys from the message. Supported placeholders:
+// `${topic}`, `${client_id}` and `${topic[N]}`, where N is the zero based index
+// of a segment of the topic name, empty segments are skipped. For the topic
+// `/sensor/1/temp`, `device:${topic[1]}:${topic[2]}` renders to `device:1:temp`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyTemplate {
+    parts: Vec<TemplatePart>,
+}
+
+impl KeyTemplate {
+    pub fn parse(template: &str) -> Result<Self, RedisBridgeError> {
+        let invalid = |reason: &str| {
+            RedisBridgeError::InvalidKeyTemplate(template.to_string(), reason.to_string())
+        };
+
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("${") {
+            if start > 0 {
+                parts.push(TemplatePart::Text(rest[..start].to_string()));
+            }
+            let end = if let Some(end) = rest[start..].find('}') {
+                start + end
+            } else {
+                return Err(invalid("placeholder is not closed"));
+            };
+
+            let name = &rest[start + 2..end];
+            let part = match name {
+                "topic" => TemplatePart::Topic,
+                "client_id" => TemplatePart::ClientId,
+                _ => {
+                    let index = name
+                        .strip_prefix("topic[")
+                        .and_then(|s| s.strip_suffix(']'))
+                        .and_then(|s| s.parse::<usize>().ok());
+                    if let Some(index) = index {
+                        TemplatePart::TopicSegment(index)
+                    } else {
+                        return Err(invalid(&format!("unknown placeholder ${{{}}}", name)));
+                    }
+                }
+            };
+            parts.push(part);
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(TemplatePart::Text(rest.to_string()));
+        }
+
+        if parts.is_empty() {
+            return Err(invalid("template is empty"));
+        }
+        Ok(KeyTemplate { parts })
+    }
+
+    pub fn render(&self, topic: &str, client_id: &str) -> String {
+        let segments: Vec<&str> = topic.split('/').filter(|s| !s.is_empty()).collect();
+        let mut key = String::new();
+        for part in self.parts.iter() {
+            match part {
+                TemplatePart::Text(text) => key.push_str(text),
+                TemplatePart::Topic => key.push_str(topic),
+                TemplatePart::ClientId => key.push_str(client_id),
+                TemplatePart::TopicSegment(index) => {
+                    if let Some(segment) = segments.get(*index) {
+                        key.push_str(segment);
+                    }
+                }
+            }
+        }
+        key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KeyTemplate;
+
+    #[test]
+    fn render_test() {
+        let template = KeyTemplate::parse("device:${topic[1]}:${topic[2]}").unwrap();
+        assert_eq!(template.render("/sensor/1/temp", "c1"), "device:1:temp");
+
+        let template = KeyTemplate::parse("${client_id}/${topic}").unwrap();
+        assert_eq!(template.render("sensor/1", "c1"), "c1/sensor/1");
+
+        let template = KeyTemplate::parse("mqtt_stream").unwrap();
+        assert_eq!(template.render("sensor/1", "c1"), "mqtt_stream");
+
+        // a missing segment renders as empty
+        let template = KeyTemplate::parse("k:${topic[5]}").unwrap();
+        assert_eq!(template.render("sensor/1", "c1"), "k:");
+    }
+
+    #[test]
+    fn parse_invalid_template() {
+        assert!(KeyTemplate::parse("").is_err());
+        assert!(KeyTemplate::parse("k:${topic").is_err());
+        assert!(KeyTemplate::parse("k:${payload}").is_err());
+        assert!(KeyTemplate::parse("k:${topic[a]}").is_err());
+    }
+}
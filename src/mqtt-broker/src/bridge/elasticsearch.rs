@@ -0,0 +1,179 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use axum::async_trait;
+use log::warn;
+use metadata_struct::adapter::record::Header;
+use metadata_struct::mqtt::message::MqttMessage;
+use mqtt_bridge_elasticsearch::client::{BulkItemError, ElasticsearchClient};
+use mqtt_bridge_elasticsearch::config::ElasticsearchBridgeConfig;
+use storage_adapter::storage::StorageAdapter;
+
+use super::{build_dead_letter_record, write_dead_letter, Connector};
+use crate::handler::error::MqttBrokerError;
+
+// Items Elasticsearch rejected because it is overloaded are sent again by the
+// client. Items rejected for good (e.g. mapping conflicts) are written to the
+// dead-letter shard of the bridge, with the rejection reason in the record header.
+pub struct ElasticsearchConnector<S> {
+    bridge_name: String,
+    client: ElasticsearchClient,
+    message_storage: Arc<S>,
+}
+
+impl<S> ElasticsearchConnector<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(
+        bridge_name: &str,
+        config: ElasticsearchBridgeConfig,
+        message_storage: Arc<S>,
+    ) -> Result<Self, MqttBrokerError> {
+        Ok(ElasticsearchConnector {
+            bridge_name: bridge_name.to_string(),
+            client: ElasticsearchClient::new(config)?,
+            message_storage,
+        })
+    }
+}
+
+#[async_trait]
+impl<S> Connector for ElasticsearchConnector<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    async fn start(&self) -> Result<(), MqttBrokerError> {
+        self.client.check_health().await?;
+        Ok(())
+    }
+
+    async fn stop(&self) -> Result<(), MqttBrokerError> {
+        Ok(())
+    }
+
+    async fn write_batch(&self, messages: &[MqttMessage]) -> Result<(), MqttBrokerError> {
+        let errors = self.client.bulk(messages).await?;
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        warn!(
+            "Elasticsearch rejected {} of {} messages of bridge [{}], first error: {}",
+            errors.len(),
+            messages.len(),
+            self.bridge_name,
+            errors[0].reason
+        );
+        write_rejected(&self.message_storage, &self.bridge_name, messages, &errors).await
+    }
+
+    async fn health(&self) -> Result<(), MqttBrokerError> {
+        self.client.check_health().await?;
+        Ok(())
+    }
+}
+
+async fn write_rejected<S>(
+    message_storage: &Arc<S>,
+    bridge_name: &str,
+    messages: &[MqttMessage],
+    errors: &[BulkItemError],
+) -> Result<(), MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let mut records = Vec::with_capacity(errors.len());
+    for error in errors {
+        let message = if let Some(message) = messages.get(error.position) {
+            message
+        } else {
+            continue;
+        };
+
+        records.push(build_dead_letter_record(
+            bridge_name,
+            message,
+            vec![
+                Header {
+                    name: "status".to_string(),
+                    value: error.status.to_string(),
+                },
+                Header {
+                    name: "reason".to_string(),
+                    value: error.reason.clone(),
+                },
+            ],
+        ));
+    }
+    write_dead_letter(message_storage, bridge_name, records).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_base::config::broker_mqtt::{init_broker_mqtt_conf_by_config, BrokerMqttConfig};
+    use metadata_struct::adapter::read_config::ReadConfig;
+    use metadata_struct::mqtt::message::MqttMessage;
+    use mqtt_bridge_elasticsearch::client::BulkItemError;
+    use storage_adapter::memory::MemoryStorageAdapter;
+    use storage_adapter::storage::StorageAdapter;
+
+    use super::write_rejected;
+    use crate::bridge::dead_letter_shard_name;
+
+    #[tokio::test]
+    async fn write_rejected_test() {
+        init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            cluster_name: "test".to_string(),
+            ..Default::default()
+        });
+
+        let message_storage = Arc::new(MemoryStorageAdapter::new());
+        let messages: Vec<MqttMessage> = (0..3)
+            .map(|i| MqttMessage {
+                client_id: format!("c{}", i),
+                ..Default::default()
+            })
+            .collect();
+        let errors = vec![BulkItemError {
+            position: 1,
+            status: 400,
+            reason: "mapper_parsing_exception: failed to parse".to_string(),
+        }];
+
+        write_rejected(&message_storage, "es", &messages, &errors)
+            .await
+            .unwrap();
+
+        let records = message_storage
+            .read_by_offset(
+                "test".to_string(),
+                dead_letter_shard_name("es"),
+                0,
+                ReadConfig::new(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].key, "es".to_string());
+        assert_eq!(records[0].header[0].value, "400".to_string());
+
+        let message = MqttMessage::decode_record(records[0].clone()).unwrap();
+        assert_eq!(message.client_id, "c1".to_string());
+    }
+}
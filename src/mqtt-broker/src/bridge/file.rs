@@ -0,0 +1,299 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::{Path, PathBuf};
+
+use axum::async_trait;
+use metadata_struct::mqtt::message::MqttMessage;
+use serde::{Deserialize, Serialize};
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use super::Connector;
+use crate::handler::error::MqttBrokerError;
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct FileBridgeConfig {
+    // directory the files are written to, created if it does not exist
+    pub path: String,
+    #[serde(default = "default_file_prefix")]
+    pub file_prefix: String,
+    // the active file is rotated once the next batch would grow it beyond this size
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+    // number of rotated files to keep, 0 keeps all of them
+    #[serde(default)]
+    pub max_files: u64,
+}
+
+fn default_file_prefix() -> String {
+    "mqtt".to_string()
+}
+
+fn default_max_file_size() -> u64 {
+    64 * 1024 * 1024
+}
+
+struct ActiveFile {
+    file: File,
+    size: u64,
+}
+
+// Appends every message as one line of JSON, in the same encoding as the
+// message storage, to `{file_prefix}.jsonl`. Rotated files are renamed to
+// `{file_prefix}.{seq}.jsonl` with an increasing sequence number.
+//
+// A batch is synced to disk before `write_batch` returns, so the offset the
+// connector task commits afterwards never covers data that is not on disk.
+pub struct FileConnector {
+    config: FileBridgeConfig,
+    active: Mutex<Option<ActiveFile>>,
+}
+
+impl FileConnector {
+    pub fn new(config: FileBridgeConfig) -> Result<Self, MqttBrokerError> {
+        if config.path.is_empty() {
+            return Err(MqttBrokerError::CommonError(
+                "File bridge path cannot be empty".to_string(),
+            ));
+        }
+        if config.file_prefix.is_empty() || config.file_prefix.contains('/') {
+            return Err(MqttBrokerError::CommonError(format!(
+                "Invalid file bridge prefix [{}]",
+                config.file_prefix
+            )));
+        }
+        Ok(FileConnector {
+            config,
+            active: Mutex::new(None),
+        })
+    }
+
+    fn active_path(&self) -> PathBuf {
+        Path::new(&self.config.path).join(format!("{}.jsonl", self.config.file_prefix))
+    }
+
+    fn rotated_path(&self, seq: u64) -> PathBuf {
+        Path::new(&self.config.path).join(format!("{}.{}.jsonl", self.config.file_prefix, seq))
+    }
+
+    async fn open(&self) -> Result<ActiveFile, MqttBrokerError> {
+        fs::create_dir_all(&self.config.path).await?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.active_path())
+            .await?;
+        let size = file.metadata().await?.len();
+        Ok(ActiveFile { file, size })
+    }
+
+    // Sequence numbers of the rotated files, in ascending order.
+    async fn rotated_files(&self) -> Result<Vec<u64>, MqttBrokerError> {
+        let prefix = format!("{}.", self.config.file_prefix);
+        let mut seqs = Vec::new();
+        let mut entries = fs::read_dir(&self.config.path).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if let Some(seq) = name
+                .strip_prefix(&prefix)
+                .and_then(|rest| rest.strip_suffix(".jsonl"))
+                .and_then(|seq| seq.parse::<u64>().ok())
+            {
+                seqs.push(seq);
+            }
+        }
+        seqs.sort();
+        Ok(seqs)
+    }
+
+    async fn rotate(&self) -> Result<(), MqttBrokerError> {
+        let mut seqs = self.rotated_files().await?;
+        let next_seq = seqs.last().map(|seq| seq + 1).unwrap_or(1);
+        fs::rename(self.active_path(), self.rotated_path(next_seq)).await?;
+        seqs.push(next_seq);
+
+        if self.config.max_files > 0 {
+            let max_files = self.config.max_files as usize;
+            if seqs.len() > max_files {
+                for seq in seqs[..seqs.len() - max_files].iter() {
+                    fs::remove_file(self.rotated_path(*seq)).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn write(
+        &self,
+        active: &mut Option<ActiveFile>,
+        data: &[u8],
+    ) -> Result<(), MqttBrokerError> {
+        if active.is_none() {
+            *active = Some(self.open().await?);
+        }
+
+        let size = active.as_ref().map(|file| file.size).unwrap_or(0);
+        if size > 0 && size + data.len() as u64 > self.config.max_file_size {
+            if let Some(file) = active.take() {
+                file.file.sync_all().await?;
+            }
+            self.rotate().await?;
+            *active = Some(self.open().await?);
+        }
+
+        if let Some(file) = active.as_mut() {
+            file.file.write_all(data).await?;
+            file.file.sync_data().await?;
+            file.size += data.len() as u64;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Connector for FileConnector {
+    async fn start(&self) -> Result<(), MqttBrokerError> {
+        let mut active = self.active.lock().await;
+        if active.is_none() {
+            *active = Some(self.open().await?);
+        }
+        Ok(())
+    }
+
+    async fn stop(&self) -> Result<(), MqttBrokerError> {
+        if let Some(file) = self.active.lock().await.take() {
+            file.file.sync_all().await?;
+        }
+        Ok(())
+    }
+
+    async fn write_batch(&self, messages: &[MqttMessage]) -> Result<(), MqttBrokerError> {
+        let mut data = Vec::new();
+        for message in messages {
+            data.extend_from_slice(&message.encode());
+            data.push(b'\n');
+        }
+
+        let mut active = self.active.lock().await;
+        if let Err(e) = self.write(&mut active, &data).await {
+            // The file is reopened by the next batch, the whole batch is written
+            // again since its offset was not committed.
+            *active = None;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    async fn health(&self) -> Result<(), MqttBrokerError> {
+        let metadata = fs::metadata(&self.config.path).await?;
+        if metadata.permissions().readonly() {
+            return Err(MqttBrokerError::CommonError(format!(
+                "File bridge path [{}] is read-only",
+                self.config.path
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::{read_to_string, remove_dir_all};
+
+    use bytes::Bytes;
+    use common_base::tools::unique_id;
+    use metadata_struct::mqtt::message::MqttMessage;
+
+    use super::{FileBridgeConfig, FileConnector};
+    use crate::bridge::Connector;
+
+    fn build_messages(start: u64, num: u64) -> Vec<MqttMessage> {
+        (start..start + num)
+            .map(|i| MqttMessage {
+                client_id: format!("c{}", i),
+                topic: Bytes::from("/sensor/1"),
+                payload: Bytes::from(format!("{}", i)),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn file_connector_test() {
+        let path = temp_dir().join(format!("file_bridge_{}", unique_id()));
+        let config = FileBridgeConfig {
+            path: path.to_string_lossy().to_string(),
+            file_prefix: "sensor".to_string(),
+            max_file_size: 1024,
+            max_files: 2,
+        };
+        let connector = FileConnector::new(config.clone()).unwrap();
+        connector.start().await.unwrap();
+        connector.health().await.unwrap();
+
+        connector.write_batch(&build_messages(0, 2)).await.unwrap();
+        let content = read_to_string(path.join("sensor.jsonl")).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let message = serde_json::from_str::<MqttMessage>(lines[1]).unwrap();
+        assert_eq!(message.client_id, "c1".to_string());
+        assert_eq!(message.payload, Bytes::from("1"));
+
+        // a restarted connector appends to the existing file
+        connector.stop().await.unwrap();
+        let connector = FileConnector::new(config).unwrap();
+        connector.write_batch(&build_messages(2, 1)).await.unwrap();
+        let content = read_to_string(path.join("sensor.jsonl")).unwrap();
+        assert_eq!(content.lines().count(), 3);
+
+        for i in 0..4 {
+            connector
+                .write_batch(&build_messages(3 + i * 4, 4))
+                .await
+                .unwrap();
+        }
+        let seqs = connector.rotated_files().await.unwrap();
+        assert_eq!(seqs.len(), 2);
+        assert!(seqs[0] > 1);
+        assert!(path.join("sensor.jsonl").exists());
+        assert!(!path.join("sensor.1.jsonl").exists());
+
+        // the newest message is always in the active file
+        let content = read_to_string(path.join("sensor.jsonl")).unwrap();
+        let last = serde_json::from_str::<MqttMessage>(content.lines().last().unwrap()).unwrap();
+        assert_eq!(last.client_id, "c18".to_string());
+
+        connector.stop().await.unwrap();
+        remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn file_connector_config_test() {
+        assert!(FileConnector::new(FileBridgeConfig {
+            path: "".to_string(),
+            file_prefix: "mqtt".to_string(),
+            max_file_size: 1024,
+            max_files: 0,
+        })
+        .is_err());
+
+        let config = serde_json::from_str::<FileBridgeConfig>(r#"{"path":"/tmp/mqtt"}"#).unwrap();
+        assert_eq!(config.file_prefix, "mqtt".to_string());
+        assert_eq!(config.max_file_size, 64 * 1024 * 1024);
+        assert_eq!(config.max_files, 0);
+    }
+}
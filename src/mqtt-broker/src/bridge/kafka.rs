@@ -0,0 +1,101 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::str::FromStr;
+
+use axum::async_trait;
+use common_base::config::broker_mqtt::KafkaBridge;
+use common_base::tools::now_second;
+use metadata_struct::mqtt::bridge::{MqttBridgeRule, MqttBridgeStatus, MqttBridgeType};
+use metadata_struct::mqtt::message::MqttMessage;
+use mqtt_bridge_kafka::config::{KafkaMessageKey, KafkaProducerConfig};
+use mqtt_bridge_kafka::producer::KafkaProducer;
+use mqtt_bridge_kafka::record::build_kafka_record;
+
+use super::Connector;
+use crate::handler::error::MqttBrokerError;
+
+pub struct KafkaConnector {
+    key: KafkaMessageKey,
+    producer: KafkaProducer,
+}
+
+impl KafkaConnector {
+    pub fn new(config: KafkaProducerConfig) -> Self {
+        KafkaConnector {
+            key: config.key.clone(),
+            producer: KafkaProducer::new(config),
+        }
+    }
+}
+
+#[async_trait]
+impl Connector for KafkaConnector {
+    async fn start(&self) -> Result<(), MqttBrokerError> {
+        self.producer.check_health().await?;
+        Ok(())
+    }
+
+    async fn stop(&self) -> Result<(), MqttBrokerError> {
+        self.producer.close().await;
+        Ok(())
+    }
+
+    async fn write_batch(&self, messages: &[MqttMessage]) -> Result<(), MqttBrokerError> {
+        let records = messages
+            .iter()
+            .map(|message| build_kafka_record(message, &self.key))
+            .collect();
+        self.producer.send(records).await?;
+        Ok(())
+    }
+
+    async fn health(&self) -> Result<(), MqttBrokerError> {
+        self.producer.check_health().await?;
+        Ok(())
+    }
+}
+
+// Converts a Kafka bridge of the broker config file into the bridge rule that is
+// stored in the placement center.
+pub fn kafka_bridge_rule(conf: &KafkaBridge) -> Result<MqttBridgeRule, MqttBrokerError> {
+    let mut config =
+        KafkaProducerConfig::new(conf.bootstrap_servers.clone(), conf.kafka_topic.clone());
+    config.client_id = format!("robustmq-bridge-{}", conf.name);
+    if let Some(key) = &conf.key {
+        config.key = KafkaMessageKey::from_str(key)?;
+    }
+    if let Some(acks) = conf.acks {
+        config.acks = acks;
+    }
+    if let Some(timeout_ms) = conf.timeout_ms {
+        config.timeout_ms = timeout_ms;
+    }
+    if let Some(max_retries) = conf.max_retries {
+        config.max_retries = max_retries;
+    }
+    if let Some(retry_backoff_ms) = conf.retry_backoff_ms {
+        config.retry_backoff_ms = retry_backoff_ms;
+    }
+
+    Ok(MqttBridgeRule {
+        name: conf.name.clone(),
+        bridge_type: MqttBridgeType::Kafka,
+        topic_filters: conf.topic_filters.clone(),
+        batch_size: conf.batch_size,
+        config: serde_json::to_string(&config)?,
+        status: MqttBridgeStatus::Running,
+        create_time: now_second(),
+    })
+}
@@ -0,0 +1,665 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_base::config::broker_mqtt::broker_mqtt_conf;
+use common_base::tools::now_second;
+use dashmap::DashMap;
+use grpc_clients::pool::ClientPool;
+use log::{error, info, warn};
+use metadata_struct::mqtt::bridge::{MqttBridgeRule, MqttBridgeStatus};
+use metadata_struct::mqtt::message::MqttMessage;
+use storage_adapter::storage::StorageAdapter;
+use tokio::select;
+use tokio::sync::broadcast::{self};
+use tokio::time::sleep;
+
+use super::kafka::kafka_bridge_rule;
+use super::{build_connector, Connector};
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::storage::bridge::BridgeStorage;
+use crate::storage::message::MessageStorage;
+use crate::subscribe::sub_common::{get_share_sub_leader, loop_commit_offset, path_match};
+
+const SYNC_BRIDGE_RULE_INTERVAL_SEC: u64 = 5;
+
+pub struct BridgeInfo {
+    pub rule: MqttBridgeRule,
+    pub connector: Arc<dyn Connector + Send + Sync>,
+}
+
+impl BridgeInfo {
+    pub fn new<S>(rule: MqttBridgeRule, message_storage: Arc<S>) -> Result<Self, MqttBrokerError>
+    where
+        S: StorageAdapter + Sync + Send + 'static + Clone,
+    {
+        let connector = build_connector(&rule, message_storage)?;
+        Ok(BridgeInfo { rule, connector })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.rule.name
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.rule.status == MqttBridgeStatus::Paused
+    }
+
+    pub fn is_match(&self, topic_name: &str) -> bool {
+        self.rule
+            .topic_filters
+            .iter()
+            .any(|filter| path_match(topic_name, filter))
+    }
+}
+
+// Each bridge is run by a single broker of the cluster, elected through the same
+// leader allocation as shared subscriptions. On that broker the bridge gets one
+// connector task, which reads every topic matching the bridge's filters with a
+// dedicated group per topic and only commits after the connector accepted the
+// batch, so messages are delivered at least once.
+//
+// Bridge rules are stored in the placement center and synchronized periodically.
+// Bridges from the broker config file are only written to the placement center at
+// startup, after that they are managed like any other rule.
+pub struct BridgeManager<S> {
+    client_pool: Arc<ClientPool>,
+    cache_manager: Arc<CacheManager>,
+    message_storage: Arc<S>,
+    // (bridge_name, BridgeInfo)
+    bridges: DashMap<String, Arc<BridgeInfo>>,
+    // (bridge_name, stop sender of the connector task)
+    connector_thread: DashMap<String, broadcast::Sender<bool>>,
+}
+
+impl<S> BridgeManager<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(
+        client_pool: Arc<ClientPool>,
+        cache_manager: Arc<CacheManager>,
+        message_storage: Arc<S>,
+    ) -> Self {
+        BridgeManager {
+            client_pool,
+            cache_manager,
+            message_storage,
+            bridges: DashMap::with_capacity(2),
+            connector_thread: DashMap::with_capacity(2),
+        }
+    }
+
+    pub fn add_bridge(&self, bridge: BridgeInfo) -> Result<(), MqttBrokerError> {
+        if self.bridges.contains_key(bridge.name()) {
+            return Err(MqttBrokerError::BridgeAlreadyExist(bridge.rule.name));
+        }
+        self.bridges
+            .insert(bridge.name().to_string(), Arc::new(bridge));
+        Ok(())
+    }
+
+    // Writes messages to a bridge right away, independent of the connector task.
+    // Used by rules forwarding their output to a bridge.
+    pub async fn forward(
+        &self,
+        bridge_name: &str,
+        messages: &[MqttMessage],
+    ) -> Result<(), MqttBrokerError> {
+        let bridge = match self.bridges.get(bridge_name) {
+            Some(bridge) => bridge.clone(),
+            None => return Err(MqttBrokerError::BridgeNotExist(bridge_name.to_string())),
+        };
+        if bridge.is_paused() {
+            return Err(MqttBrokerError::BridgePaused(bridge_name.to_string()));
+        }
+        bridge.connector.write_batch(messages).await
+    }
+
+    // Creates the bridges of the broker config file that the placement center does not
+    // know yet. Existing rules of the same name are kept, so changes made through the
+    // admin API survive a restart.
+    pub async fn seed_config_bridges(&self) -> Result<(), MqttBrokerError> {
+        let conf = broker_mqtt_conf();
+        if conf.bridge.kafka.is_empty() {
+            return Ok(());
+        }
+
+        let storage = BridgeStorage::new(self.client_pool.clone());
+        let names: HashSet<String> = storage
+            .list_bridge()
+            .await?
+            .into_iter()
+            .map(|rule| rule.name)
+            .collect();
+        for kafka in conf.bridge.kafka.iter() {
+            if names.contains(&kafka.name) {
+                continue;
+            }
+            match kafka_bridge_rule(kafka) {
+                Ok(rule) => {
+                    storage.save_bridge(rule).await?;
+                    info!(
+                        "Kafka bridge [{}] was created from the config file",
+                        kafka.name
+                    );
+                }
+                Err(e) => {
+                    error!("Kafka bridge [{}] failed to initialize, {}", kafka.name, e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn sync_bridge_rules(&self) -> Result<(), MqttBrokerError> {
+        let storage = BridgeStorage::new(self.client_pool.clone());
+        let rules = storage.list_bridge().await?;
+
+        let names: HashSet<String> = rules.iter().map(|rule| rule.name.clone()).collect();
+        let removed: Vec<String> = self
+            .bridges
+            .iter()
+            .filter(|raw| !names.contains(raw.key()))
+            .map(|raw| raw.key().clone())
+            .collect();
+        for name in removed {
+            self.stop_connector_thread(&name);
+            self.bridges.remove(&name);
+            info!("Bridge [{}] was removed", name);
+        }
+
+        for rule in rules {
+            let unchanged = if let Some(bridge) = self.bridges.get(&rule.name) {
+                bridge.rule == rule
+            } else {
+                false
+            };
+            if unchanged {
+                continue;
+            }
+
+            let name = rule.name.clone();
+            match BridgeInfo::new(rule, self.message_storage.clone()) {
+                Ok(bridge) => {
+                    // The task of the previous version is restarted by the next check.
+                    self.stop_connector_thread(&name);
+                    self.bridges.insert(name.clone(), Arc::new(bridge));
+                    info!("Bridge [{}] was loaded", name);
+                }
+                Err(e) => {
+                    error!("Bridge [{}] failed to initialize, {}", name, e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn start(&self, stop_send: broadcast::Sender<bool>) {
+        if let Err(e) = self.seed_config_bridges().await {
+            error!("Failed to create the bridges of the config file, {}", e);
+        }
+
+        let mut last_sync_time = 0;
+        loop {
+            if now_second() - last_sync_time >= SYNC_BRIDGE_RULE_INTERVAL_SEC {
+                if let Err(e) = self.sync_bridge_rules().await {
+                    error!("Failed to synchronize bridge rules, {}", e);
+                }
+                last_sync_time = now_second();
+            }
+
+            let mut stop_rx = stop_send.subscribe();
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            for bridge_name in self.bridges.iter().map(|raw| raw.key().clone()) {
+                                self.stop_connector_thread(&bridge_name);
+                            }
+                            info!("{}","Bridge connector thread stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                _ = self.check_connector_thread()=>{
+                }
+            }
+        }
+    }
+
+    async fn check_connector_thread(&self) {
+        let bridges: Vec<Arc<BridgeInfo>> = self.bridges.iter().map(|raw| raw.clone()).collect();
+        for bridge in bridges {
+            if bridge.is_paused() {
+                self.stop_connector_thread(bridge.name());
+                continue;
+            }
+
+            match self.is_leader(bridge.name()).await {
+                Ok(true) => {
+                    self.start_connector_thread(&bridge);
+                }
+                Ok(false) => {
+                    self.stop_connector_thread(bridge.name());
+                }
+                Err(e) => {
+                    error!(
+                        "Failed to get the leader of bridge [{}], {}",
+                        bridge.name(),
+                        e
+                    );
+                }
+            }
+        }
+        sleep(Duration::from_secs(1)).await;
+    }
+
+    async fn is_leader(&self, bridge_name: &str) -> Result<bool, MqttBrokerError> {
+        let conf = broker_mqtt_conf();
+        let reply =
+            get_share_sub_leader(self.client_pool.clone(), bridge_group_name(bridge_name)).await?;
+        Ok(reply.broker_id == conf.broker_id)
+    }
+
+    fn start_connector_thread(&self, bridge: &Arc<BridgeInfo>) {
+        if self.connector_thread.contains_key(bridge.name()) {
+            return;
+        }
+
+        let (stop_sx, stop_rx) = broadcast::channel(1);
+        self.connector_thread
+            .insert(bridge.name().to_string(), stop_sx);
+
+        let cache_manager = self.cache_manager.clone();
+        let message_storage = MessageStorage::new(self.message_storage.clone());
+        let bridge = bridge.clone();
+        tokio::spawn(async move {
+            connector_thread(bridge, cache_manager, message_storage, stop_rx).await;
+        });
+    }
+
+    fn stop_connector_thread(&self, bridge_name: &str) {
+        if let Some((_, stop_sx)) = self.connector_thread.remove(bridge_name) {
+            let _ = stop_sx.send(true);
+        }
+    }
+}
+
+async fn connector_thread<S>(
+    bridge: Arc<BridgeInfo>,
+    cache_manager: Arc<CacheManager>,
+    message_storage: MessageStorage<S>,
+    mut stop_rx: broadcast::Receiver<bool>,
+) where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    info!(
+        "Connector thread for bridge [{}] was started successfully",
+        bridge.name()
+    );
+
+    let mut started = false;
+    // (topic_id, offset to read next)
+    let mut offsets = HashMap::new();
+    loop {
+        select! {
+            val = stop_rx.recv() =>{
+                if let Ok(flag) = val {
+                    if flag {
+                        break;
+                    }
+                }
+            },
+            _ = push_round(&bridge, &cache_manager, &message_storage, &mut started, &mut offsets) => {}
+        }
+    }
+
+    if started {
+        if let Err(e) = bridge.connector.stop().await {
+            error!(
+                "Connector of bridge [{}] failed to stop, {}",
+                bridge.name(),
+                e
+            );
+        }
+    }
+    info!(
+        "Connector thread for bridge [{}] was stopped successfully",
+        bridge.name()
+    );
+}
+
+// Pushes one batch of every topic matching the bridge. A topic that fails keeps
+// its offset and is retried in the next round, without holding up the others.
+async fn push_round<S>(
+    bridge: &BridgeInfo,
+    cache_manager: &Arc<CacheManager>,
+    message_storage: &MessageStorage<S>,
+    started: &mut bool,
+    offsets: &mut HashMap<String, u64>,
+) where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    if !*started {
+        if let Err(e) = bridge.connector.start().await {
+            error!(
+                "Connector of bridge [{}] failed to start, {}",
+                bridge.name(),
+                e
+            );
+            sleep(Duration::from_secs(1)).await;
+            return;
+        }
+        *started = true;
+    }
+
+    let topics: Vec<(String, String)> = cache_manager
+        .topic_id_name
+        .iter()
+        .map(|raw| (raw.key().clone(), raw.value().clone()))
+        .collect();
+
+    let mut progress = false;
+    let mut failed = false;
+    for (topic_id, topic_name) in topics {
+        if !bridge.is_match(&topic_name) {
+            continue;
+        }
+
+        let group_id = bridge_topic_group_name(bridge.name(), &topic_id);
+        let offset = offsets.get(&topic_id).copied();
+        match push_message(bridge, message_storage, &topic_id, &group_id, offset).await {
+            Ok(next_offset) => {
+                if offset != Some(next_offset) {
+                    progress = true;
+                }
+                offsets.insert(topic_id, next_offset);
+            }
+            Err(e) => {
+                failed = true;
+                error!(
+                    "Bridge [{}] failed to push message of topic [{}], {}",
+                    bridge.name(),
+                    topic_name,
+                    e
+                );
+            }
+        }
+    }
+
+    if failed {
+        if let Err(e) = bridge.connector.health().await {
+            warn!(
+                "Connector of bridge [{}] is unhealthy, {}",
+                bridge.name(),
+                e
+            );
+        }
+        sleep(Duration::from_secs(1)).await;
+    } else if !progress {
+        sleep(Duration::from_millis(100)).await;
+    }
+}
+
+// Returns the offset to read next. The committed group offset is also the next
+// offset to read, so a restarted task continues right after the last batch.
+async fn push_message<S>(
+    bridge: &BridgeInfo,
+    message_storage: &MessageStorage<S>,
+    topic_id: &str,
+    group_id: &str,
+    offset: Option<u64>,
+) -> Result<u64, MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let offset = if let Some(offset) = offset {
+        offset
+    } else {
+        message_storage.get_group_offset(group_id).await?
+    };
+
+    let records = message_storage
+        .read_topic_message(topic_id, offset, bridge.rule.batch_size)
+        .await?;
+
+    let last_offset = if let Some(offset) = records.last().and_then(|record| record.offset) {
+        offset
+    } else {
+        return Ok(offset);
+    };
+
+    let mut messages = Vec::with_capacity(records.len());
+    for record in records {
+        match MqttMessage::decode_record(record) {
+            Ok(message) => messages.push(message),
+            Err(e) => {
+                error!(
+                    "Bridge [{}] skipped a record that is not an MQTT message, {}",
+                    bridge.name(),
+                    e
+                );
+            }
+        }
+    }
+
+    if !messages.is_empty() {
+        bridge.connector.write_batch(&messages).await?;
+    }
+
+    let next_offset = last_offset + 1;
+    loop_commit_offset(message_storage, topic_id, group_id, next_offset).await;
+    Ok(next_offset)
+}
+
+fn bridge_group_name(bridge_name: &str) -> String {
+    format!("system_bridge_{}", bridge_name)
+}
+
+fn bridge_topic_group_name(bridge_name: &str, topic_id: &str) -> String {
+    format!("system_bridge_{}_{}", bridge_name, topic_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use axum::async_trait;
+    use common_base::config::broker_mqtt::{init_broker_mqtt_conf_by_config, BrokerMqttConfig};
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::adapter::record::Record;
+    use metadata_struct::mqtt::bridge::MqttBridgeRule;
+    use metadata_struct::mqtt::message::MqttMessage;
+    use metadata_struct::mqtt::topic::MqttTopic;
+    use storage_adapter::memory::MemoryStorageAdapter;
+    use tokio::sync::{broadcast, Mutex};
+    use tokio::time::sleep;
+
+    use super::{connector_thread, push_message, BridgeInfo};
+    use crate::bridge::Connector;
+    use crate::handler::cache::CacheManager;
+    use crate::handler::error::MqttBrokerError;
+    use crate::storage::message::MessageStorage;
+
+    #[derive(Default)]
+    struct MemoryConnector {
+        fail: Mutex<bool>,
+        running: Mutex<bool>,
+        messages: Mutex<Vec<MqttMessage>>,
+    }
+
+    #[async_trait]
+    impl Connector for MemoryConnector {
+        async fn start(&self) -> Result<(), MqttBrokerError> {
+            *self.running.lock().await = true;
+            Ok(())
+        }
+
+        async fn stop(&self) -> Result<(), MqttBrokerError> {
+            *self.running.lock().await = false;
+            Ok(())
+        }
+
+        async fn write_batch(&self, messages: &[MqttMessage]) -> Result<(), MqttBrokerError> {
+            if *self.fail.lock().await {
+                return Err(MqttBrokerError::CommonError(
+                    "connector unavailable".to_string(),
+                ));
+            }
+            self.messages.lock().await.extend_from_slice(messages);
+            Ok(())
+        }
+
+        async fn health(&self) -> Result<(), MqttBrokerError> {
+            Ok(())
+        }
+    }
+
+    fn build_bridge(connector: Arc<MemoryConnector>, batch_size: u64) -> BridgeInfo {
+        BridgeInfo {
+            rule: MqttBridgeRule {
+                name: "b1".to_string(),
+                topic_filters: vec!["/sensor/+".to_string()],
+                batch_size,
+                ..Default::default()
+            },
+            connector,
+        }
+    }
+
+    async fn append_messages(
+        message_storage: &MessageStorage<MemoryStorageAdapter>,
+        topic_id: &str,
+        num: u64,
+    ) {
+        let mut records = Vec::new();
+        for i in 0..num {
+            let message = MqttMessage {
+                client_id: format!("c{}", i),
+                ..Default::default()
+            };
+            records.push(Record::build_byte(message.encode()));
+        }
+        message_storage
+            .append_topic_message(topic_id, records)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn push_message_test() {
+        init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            cluster_name: "test".to_string(),
+            ..Default::default()
+        });
+
+        let topic_id = "t1";
+        let group_id = "system_bridge_b1_t1";
+        let message_storage = MessageStorage::new(Arc::new(MemoryStorageAdapter::new()));
+        append_messages(&message_storage, topic_id, 5).await;
+
+        let connector = Arc::new(MemoryConnector::default());
+        let bridge = build_bridge(connector.clone(), 3);
+        assert!(bridge.is_match("/sensor/1"));
+        assert!(!bridge.is_match("/device/1"));
+
+        // a failed batch must not move the group offset
+        *connector.fail.lock().await = true;
+        assert!(
+            push_message(&bridge, &message_storage, topic_id, group_id, None)
+                .await
+                .is_err()
+        );
+        assert_eq!(message_storage.get_group_offset(group_id).await.unwrap(), 0);
+
+        *connector.fail.lock().await = false;
+        let offset = push_message(&bridge, &message_storage, topic_id, group_id, None)
+            .await
+            .unwrap();
+        assert_eq!(offset, 3);
+        assert_eq!(message_storage.get_group_offset(group_id).await.unwrap(), 3);
+
+        let offset = push_message(&bridge, &message_storage, topic_id, group_id, Some(offset))
+            .await
+            .unwrap();
+        assert_eq!(offset, 5);
+
+        let offset = push_message(&bridge, &message_storage, topic_id, group_id, Some(offset))
+            .await
+            .unwrap();
+        assert_eq!(offset, 5);
+
+        let messages = connector.messages.lock().await;
+        assert_eq!(messages.len(), 5);
+        assert_eq!(messages[0].client_id, "c0".to_string());
+        assert_eq!(messages[4].client_id, "c4".to_string());
+    }
+
+    #[tokio::test]
+    async fn connector_thread_test() {
+        init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            cluster_name: "test".to_string(),
+            ..Default::default()
+        });
+
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        let message_storage = MessageStorage::new(Arc::new(MemoryStorageAdapter::new()));
+        for (topic_id, topic_name) in [
+            ("t1", "/sensor/1"),
+            ("t2", "/sensor/2"),
+            ("t3", "/device/1"),
+        ] {
+            let topic = MqttTopic::new(
+                topic_id.to_string(),
+                "test".to_string(),
+                topic_name.to_string(),
+            );
+            cache_manager.add_topic(topic_name, &topic);
+            append_messages(&message_storage, topic_id, 2).await;
+        }
+
+        let connector = Arc::new(MemoryConnector::default());
+        let bridge = Arc::new(build_bridge(connector.clone(), 10));
+        let (stop_sx, stop_rx) = broadcast::channel(1);
+        let handle = tokio::spawn(connector_thread(
+            bridge,
+            cache_manager,
+            message_storage.clone(),
+            stop_rx,
+        ));
+
+        sleep(Duration::from_millis(300)).await;
+        assert!(*connector.running.lock().await);
+        // only the two matching topics are exported
+        assert_eq!(connector.messages.lock().await.len(), 4);
+        assert_eq!(
+            message_storage
+                .get_group_offset("system_bridge_b1_t1")
+                .await
+                .unwrap(),
+            2
+        );
+
+        stop_sx.send(true).unwrap();
+        handle.await.unwrap();
+        assert!(!*connector.running.lock().await);
+    }
+}
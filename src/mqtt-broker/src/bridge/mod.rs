@@ -12,3 +12,262 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::Arc;
+
+use axum::async_trait;
+use common_base::error::common::CommonError;
+use metadata_struct::adapter::record::{Header, Record};
+use metadata_struct::mqtt::bridge::{MqttBridgeRule, MqttBridgeType};
+use metadata_struct::mqtt::message::MqttMessage;
+use mqtt_bridge_elasticsearch::client::ElasticsearchClient;
+use mqtt_bridge_elasticsearch::config::ElasticsearchBridgeConfig;
+use mqtt_bridge_kafka::config::KafkaProducerConfig;
+use mqtt_bridge_redis::config::RedisBridgeConfig;
+use mqtt_bridge_redis::template::KeyTemplate;
+use storage_adapter::storage::{ShardConfig, StorageAdapter};
+
+use crate::bridge::elasticsearch::ElasticsearchConnector;
+use crate::bridge::file::{FileBridgeConfig, FileConnector};
+use crate::bridge::kafka::KafkaConnector;
+use crate::bridge::redis::RedisConnector;
+use crate::bridge::webhook::{WebhookBridgeConfig, WebhookConnector};
+use crate::handler::error::MqttBrokerError;
+use crate::storage::message::cluster_name;
+
+pub mod elasticsearch;
+pub mod file;
+pub mod kafka;
+pub mod manager;
+pub mod redis;
+pub mod webhook;
+
+// A target outside the broker that topic messages are exported to. The connector
+// task calls `start` before the first batch and `stop` once the bridge is paused,
+// deleted or taken over by another broker.
+#[async_trait]
+pub trait Connector {
+    async fn start(&self) -> Result<(), MqttBrokerError>;
+
+    async fn stop(&self) -> Result<(), MqttBrokerError>;
+
+    // Must only return Ok once the whole batch has been accepted,
+    // the caller commits the consumed offset right after it.
+    async fn write_batch(&self, messages: &[MqttMessage]) -> Result<(), MqttBrokerError>;
+
+    async fn health(&self) -> Result<(), MqttBrokerError>;
+}
+
+pub fn build_connector<S>(
+    rule: &MqttBridgeRule,
+    message_storage: Arc<S>,
+) -> Result<Arc<dyn Connector + Send + Sync>, MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    match rule.bridge_type {
+        MqttBridgeType::Kafka => {
+            let config = serde_json::from_str::<KafkaProducerConfig>(&rule.config)?;
+            Ok(Arc::new(KafkaConnector::new(config)))
+        }
+        MqttBridgeType::Redis => {
+            let config = serde_json::from_str::<RedisBridgeConfig>(&rule.config)?;
+            Ok(Arc::new(RedisConnector::new(config)?))
+        }
+        MqttBridgeType::Elasticsearch => {
+            let config = serde_json::from_str::<ElasticsearchBridgeConfig>(&rule.config)?;
+            Ok(Arc::new(ElasticsearchConnector::new(
+                &rule.name,
+                config,
+                message_storage,
+            )?))
+        }
+        MqttBridgeType::File => {
+            let config = serde_json::from_str::<FileBridgeConfig>(&rule.config)?;
+            Ok(Arc::new(FileConnector::new(config)?))
+        }
+        MqttBridgeType::Webhook => {
+            let config = serde_json::from_str::<WebhookBridgeConfig>(&rule.config)?;
+            Ok(Arc::new(WebhookConnector::new(&rule.name, config)?))
+        }
+    }
+}
+
+pub fn dead_letter_shard_name(bridge_name: &str) -> String {
+    format!("system_bridge_{}_dead_letter", bridge_name)
+}
+
+pub fn build_dead_letter_record(
+    bridge_name: &str,
+    message: &MqttMessage,
+    header: Vec<Header>,
+) -> Record {
+    let mut record = Record::build_byte(message.encode());
+    record.set_key(bridge_name.to_string());
+    record.set_header(header);
+    record
+}
+
+// Messages a bridge gave up on are kept in the dead-letter shard of the bridge,
+// the record header says why they were not delivered.
+pub async fn write_dead_letter<S>(
+    message_storage: &Arc<S>,
+    bridge_name: &str,
+    records: Vec<Record>,
+) -> Result<(), MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    if records.is_empty() {
+        return Ok(());
+    }
+
+    let namespace = cluster_name();
+    let shard_name = dead_letter_shard_name(bridge_name);
+    match message_storage
+        .batch_write(namespace.clone(), shard_name.clone(), records.clone())
+        .await
+    {
+        Ok(_) => Ok(()),
+        // The dead-letter shard is only created once a message is given up on.
+        Err(CommonError::ShardNotExist(_, _)) => {
+            message_storage
+                .create_shard(
+                    namespace.clone(),
+                    shard_name.clone(),
+                    ShardConfig::default(),
+                )
+                .await?;
+            message_storage
+                .batch_write(namespace, shard_name, records)
+                .await?;
+            Ok(())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+// Validates a rule before it is stored, without connecting to the target.
+pub fn check_bridge_rule(rule: &MqttBridgeRule) -> Result<(), MqttBrokerError> {
+    let invalid =
+        |reason: &str| MqttBrokerError::InvalidBridgeRule(rule.name.clone(), reason.to_string());
+
+    if rule.name.is_empty() {
+        return Err(invalid("name cannot be empty"));
+    }
+    if rule.topic_filters.is_empty() {
+        return Err(invalid("topic_filters cannot be empty"));
+    }
+    if rule.batch_size == 0 {
+        return Err(invalid("batch_size must be greater than 0"));
+    }
+
+    match rule.bridge_type {
+        MqttBridgeType::Kafka => {
+            let config = serde_json::from_str::<KafkaProducerConfig>(&rule.config)?;
+            if config.bootstrap_servers.is_empty() {
+                return Err(invalid("bootstrap_servers cannot be empty"));
+            }
+        }
+        MqttBridgeType::Redis => {
+            let config = serde_json::from_str::<RedisBridgeConfig>(&rule.config)?;
+            KeyTemplate::parse(&config.key_template)?;
+        }
+        MqttBridgeType::Elasticsearch => {
+            let config = serde_json::from_str::<ElasticsearchBridgeConfig>(&rule.config)?;
+            ElasticsearchClient::new(config)?;
+        }
+        MqttBridgeType::File => {
+            let config = serde_json::from_str::<FileBridgeConfig>(&rule.config)?;
+            FileConnector::new(config)?;
+        }
+        MqttBridgeType::Webhook => {
+            let config = serde_json::from_str::<WebhookBridgeConfig>(&rule.config)?;
+            WebhookConnector::new(&rule.name, config)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use metadata_struct::mqtt::bridge::{MqttBridgeRule, MqttBridgeStatus, MqttBridgeType};
+    use storage_adapter::memory::MemoryStorageAdapter;
+
+    use super::{build_connector, check_bridge_rule};
+
+    fn build_rule(bridge_type: MqttBridgeType, config: &str) -> MqttBridgeRule {
+        MqttBridgeRule {
+            name: "b1".to_string(),
+            bridge_type,
+            topic_filters: vec!["/sensor/+".to_string()],
+            batch_size: 100,
+            config: config.to_string(),
+            status: MqttBridgeStatus::Running,
+            create_time: 0,
+        }
+    }
+
+    #[test]
+    fn build_connector_test() {
+        let message_storage = Arc::new(MemoryStorageAdapter::new());
+        let valid = [
+            build_rule(
+                MqttBridgeType::Kafka,
+                r#"{"bootstrap_servers":["127.0.0.1:9092"],"topic":"mqtt"}"#,
+            ),
+            build_rule(
+                MqttBridgeType::Redis,
+                r#"{"addr":"127.0.0.1:6379","command":"lpush","key_template":"mqtt:${topic[1]}"}"#,
+            ),
+            build_rule(
+                MqttBridgeType::Elasticsearch,
+                r#"{"url":"http://127.0.0.1:9200","index":"mqtt-${year}.${month}"}"#,
+            ),
+            build_rule(MqttBridgeType::File, r#"{"path":"/tmp/robustmq/bridge"}"#),
+            build_rule(
+                MqttBridgeType::Webhook,
+                r#"{"url":"http://127.0.0.1:8080/hook","body_template":"{\"data\":${messages}}"}"#,
+            ),
+        ];
+        for rule in valid.iter() {
+            assert!(check_bridge_rule(rule).is_ok());
+            assert!(build_connector(rule, message_storage.clone()).is_ok());
+        }
+
+        let invalid = [
+            build_rule(
+                MqttBridgeType::Kafka,
+                r#"{"bootstrap_servers":[],"topic":"mqtt"}"#,
+            ),
+            build_rule(
+                MqttBridgeType::Redis,
+                r#"{"addr":"127.0.0.1:6379","key_template":"mqtt:${topic"}"#,
+            ),
+            build_rule(
+                MqttBridgeType::Elasticsearch,
+                r#"{"url":"http://127.0.0.1:9200","index":"MQTT"}"#,
+            ),
+            build_rule(MqttBridgeType::Elasticsearch, "{}"),
+            build_rule(MqttBridgeType::File, r#"{"path":""}"#),
+            build_rule(
+                MqttBridgeType::Webhook,
+                r#"{"url":"http://127.0.0.1:8080/hook","body_template":"${payload}"}"#,
+            ),
+        ];
+        for rule in invalid.iter() {
+            assert!(check_bridge_rule(rule).is_err());
+        }
+        assert!(build_connector(&invalid[1], message_storage.clone()).is_err());
+        assert!(build_connector(&invalid[3], message_storage.clone()).is_err());
+
+        let mut rule = valid[0].clone();
+        rule.topic_filters.clear();
+        assert!(check_bridge_rule(&rule).is_err());
+
+        let mut rule = valid[0].clone();
+        rule.batch_size = 0;
+        assert!(check_bridge_rule(&rule).is_err());
+    }
+}
@@ -0,0 +1,67 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::async_trait;
+use metadata_struct::mqtt::message::MqttMessage;
+use mqtt_bridge_redis::client::RedisClient;
+use mqtt_bridge_redis::config::RedisBridgeConfig;
+use mqtt_bridge_redis::record::build_redis_command;
+use mqtt_bridge_redis::template::KeyTemplate;
+
+use super::Connector;
+use crate::handler::error::MqttBrokerError;
+
+pub struct RedisConnector {
+    config: RedisBridgeConfig,
+    key_template: KeyTemplate,
+    client: RedisClient,
+}
+
+impl RedisConnector {
+    pub fn new(config: RedisBridgeConfig) -> Result<Self, MqttBrokerError> {
+        let key_template = KeyTemplate::parse(&config.key_template)?;
+        Ok(RedisConnector {
+            client: RedisClient::new(config.clone()),
+            config,
+            key_template,
+        })
+    }
+}
+
+#[async_trait]
+impl Connector for RedisConnector {
+    async fn start(&self) -> Result<(), MqttBrokerError> {
+        self.client.ping().await?;
+        Ok(())
+    }
+
+    async fn stop(&self) -> Result<(), MqttBrokerError> {
+        self.client.close().await;
+        Ok(())
+    }
+
+    async fn write_batch(&self, messages: &[MqttMessage]) -> Result<(), MqttBrokerError> {
+        let commands: Vec<Vec<Vec<u8>>> = messages
+            .iter()
+            .map(|message| build_redis_command(&self.config, &self.key_template, message))
+            .collect();
+        self.client.pipeline(&commands).await?;
+        Ok(())
+    }
+
+    async fn health(&self) -> Result<(), MqttBrokerError> {
+        self.client.ping().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,435 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use axum::async_trait;
+use http_client::client::{HttpClient, HttpEndpoint};
+use log::warn;
+use metadata_struct::mqtt::message::MqttMessage;
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpStream;
+use tokio::time::{sleep, timeout};
+
+use super::Connector;
+use crate::handler::error::MqttBrokerError;
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct WebhookBridgeConfig {
+    // http:// or https:// url the batches are posted to
+    pub url: String,
+    // CA bundle (PEM) trusted for https urls besides the system roots
+    #[serde(default)]
+    pub tls_ca: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    // `${messages}` is replaced with a JSON array of the batch, `${count}` with its
+    // length and `${bridge}` with the bridge name.
+    #[serde(default = "default_body_template")]
+    pub body_template: String,
+    #[serde(default = "default_content_type")]
+    pub content_type: String,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    // the backoff doubles after every failed attempt, up to max_backoff_ms
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+}
+
+fn default_body_template() -> String {
+    "${messages}".to_string()
+}
+
+fn default_content_type() -> String {
+    "application/json".to_string()
+}
+
+fn default_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_retry_backoff_ms() -> u64 {
+    200
+}
+
+fn default_max_backoff_ms() -> u64 {
+    10000
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum BodySegment {
+    Text(String),
+    Messages,
+    Count,
+    Bridge,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BodyTemplate {
+    segments: Vec<BodySegment>,
+}
+
+impl BodyTemplate {
+    pub fn parse(template: &str) -> Result<Self, MqttBrokerError> {
+        let invalid = |reason: String| {
+            MqttBrokerError::CommonError(format!(
+                "Invalid webhook body template [{}], {}",
+                template, reason
+            ))
+        };
+
+        let mut segments = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("${") {
+            if start > 0 {
+                segments.push(BodySegment::Text(rest[..start].to_string()));
+            }
+            let end = if let Some(end) = rest[start..].find('}') {
+                start + end
+            } else {
+                return Err(invalid("unclosed placeholder".to_string()));
+            };
+            segments.push(match &rest[start + 2..end] {
+                "messages" => BodySegment::Messages,
+                "count" => BodySegment::Count,
+                "bridge" => BodySegment::Bridge,
+                name => return Err(invalid(format!("unknown placeholder [{}]", name))),
+            });
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            segments.push(BodySegment::Text(rest.to_string()));
+        }
+        Ok(BodyTemplate { segments })
+    }
+
+    pub fn render(&self, bridge_name: &str, messages: &[MqttMessage]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for segment in self.segments.iter() {
+            match segment {
+                BodySegment::Text(text) => body.extend_from_slice(text.as_bytes()),
+                BodySegment::Messages => {
+                    body.push(b'[');
+                    for (i, message) in messages.iter().enumerate() {
+                        if i > 0 {
+                            body.push(b',');
+                        }
+                        body.extend_from_slice(&message.encode());
+                    }
+                    body.push(b']');
+                }
+                BodySegment::Count => body.extend_from_slice(messages.len().to_string().as_bytes()),
+                BodySegment::Bridge => body.extend_from_slice(bridge_name.as_bytes()),
+            }
+        }
+        body
+    }
+}
+
+// POSTs every batch to the configured url. Connection errors, timeouts, 429 and
+// 5xx responses are retried with exponential backoff, any other non-2xx status
+// fails the batch right away. A failed batch is not committed, so the connector
+// task sends it again later.
+pub struct WebhookConnector {
+    bridge_name: String,
+    config: WebhookBridgeConfig,
+    client: HttpClient,
+    endpoint: HttpEndpoint,
+    body_template: BodyTemplate,
+}
+
+impl WebhookConnector {
+    pub fn new(bridge_name: &str, config: WebhookBridgeConfig) -> Result<Self, MqttBrokerError> {
+        Ok(WebhookConnector {
+            bridge_name: bridge_name.to_string(),
+            client: HttpClient::new(&config.tls_ca)?,
+            endpoint: HttpEndpoint::parse(&config.url)?,
+            body_template: BodyTemplate::parse(&config.body_template)?,
+            config,
+        })
+    }
+
+    async fn post(&self, body: &[u8]) -> Result<(), WebhookError> {
+        let mut headers = vec![("Content-Type", self.config.content_type.clone())];
+        for (name, value) in self.config.headers.iter() {
+            headers.push((name.as_str(), value.clone()));
+        }
+        // HttpEndpoint keeps the path of the url as prefix of every request
+        let path = if self.endpoint.path_prefix.is_empty() {
+            "/"
+        } else {
+            ""
+        };
+
+        let request = self
+            .client
+            .send(&self.endpoint, "POST", path, &headers, body);
+        let response = match timeout(Duration::from_millis(self.config.timeout_ms), request).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(e)) => return Err(WebhookError::Retryable(e.to_string())),
+            Err(_) => {
+                return Err(WebhookError::Retryable(format!(
+                    "request timed out after {} ms",
+                    self.config.timeout_ms
+                )))
+            }
+        };
+
+        let reason = format!(
+            "HTTP status {}: {}",
+            response.status,
+            String::from_utf8_lossy(&response.body)
+        );
+        match response.status {
+            200..=299 => Ok(()),
+            429 | 500..=599 => Err(WebhookError::Retryable(reason)),
+            _ => Err(WebhookError::Fatal(reason)),
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .config
+            .retry_backoff_ms
+            .saturating_mul(1u64 << attempt.min(16));
+        Duration::from_millis(backoff.min(self.config.max_backoff_ms))
+    }
+}
+
+enum WebhookError {
+    Retryable(String),
+    Fatal(String),
+}
+
+#[async_trait]
+impl Connector for WebhookConnector {
+    async fn start(&self) -> Result<(), MqttBrokerError> {
+        self.health().await
+    }
+
+    async fn stop(&self) -> Result<(), MqttBrokerError> {
+        Ok(())
+    }
+
+    async fn write_batch(&self, messages: &[MqttMessage]) -> Result<(), MqttBrokerError> {
+        let body = self.body_template.render(&self.bridge_name, messages);
+        let mut attempt = 0;
+        loop {
+            let reason = match self.post(&body).await {
+                Ok(_) => return Ok(()),
+                Err(WebhookError::Fatal(reason)) => {
+                    return Err(MqttBrokerError::CommonError(format!(
+                        "Webhook [{}] rejected the batch, {}",
+                        self.config.url, reason
+                    )));
+                }
+                Err(WebhookError::Retryable(reason)) => reason,
+            };
+
+            if attempt >= self.config.max_retries {
+                return Err(MqttBrokerError::CommonError(format!(
+                    "Webhook [{}] failed after {} attempts, {}",
+                    self.config.url,
+                    attempt + 1,
+                    reason
+                )));
+            }
+
+            let backoff = self.backoff(attempt);
+            warn!(
+                "Webhook [{}] of bridge [{}] failed, retrying in {} ms, {}",
+                self.config.url,
+                self.bridge_name,
+                backoff.as_millis(),
+                reason
+            );
+            sleep(backoff).await;
+            attempt += 1;
+        }
+    }
+
+    async fn health(&self) -> Result<(), MqttBrokerError> {
+        // There is no generic health endpoint, a webhook is healthy if it accepts connections.
+        match timeout(
+            Duration::from_millis(self.config.timeout_ms),
+            TcpStream::connect(self.endpoint.addr()),
+        )
+        .await
+        {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(e.into()),
+            Err(_) => Err(MqttBrokerError::CommonError(format!(
+                "Connecting to webhook [{}] timed out",
+                self.config.url
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, VecDeque};
+    use std::sync::{Arc, Mutex};
+
+    use bytes::Bytes;
+    use metadata_struct::mqtt::message::MqttMessage;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::{BodyTemplate, WebhookBridgeConfig, WebhookConnector};
+    use crate::bridge::Connector;
+
+    // (request head, body) of every request the server received
+    type Requests = Arc<Mutex<Vec<(String, Vec<u8>)>>>;
+
+    // Answers every request with the next queued status, 200 once the queue is empty.
+    async fn start_server(statuses: Vec<u16>) -> (String, Requests) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let requests: Requests = Arc::new(Mutex::new(Vec::new()));
+        let statuses = Arc::new(Mutex::new(VecDeque::from(statuses)));
+
+        let server_requests = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut data = Vec::new();
+                let mut buf = [0u8; 1024];
+                let (head, body) = loop {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        break (String::new(), Vec::new());
+                    }
+                    data.extend_from_slice(&buf[..n]);
+                    let header_end =
+                        if let Some(index) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                            index
+                        } else {
+                            continue;
+                        };
+                    let head = String::from_utf8_lossy(&data[..header_end]).to_string();
+                    let len = head
+                        .lines()
+                        .find_map(|line| line.strip_prefix("Content-Length: "))
+                        .and_then(|len| len.parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if data.len() >= header_end + 4 + len {
+                        break (head, data[header_end + 4..header_end + 4 + len].to_vec());
+                    }
+                };
+                server_requests.lock().unwrap().push((head, body));
+
+                let status = statuses.lock().unwrap().pop_front().unwrap_or(200);
+                let response = format!(
+                    "HTTP/1.1 {} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        (addr, requests)
+    }
+
+    fn build_config(addr: &str) -> WebhookBridgeConfig {
+        serde_json::from_str(&format!(
+            r#"{{"url":"http://{}/hook","headers":{{"Authorization":"Bearer t1"}},"body_template":"{{\"bridge\":\"${{bridge}}\",\"count\":${{count}},\"messages\":${{messages}}}}","retry_backoff_ms":10}}"#,
+            addr
+        ))
+        .unwrap()
+    }
+
+    fn build_messages() -> Vec<MqttMessage> {
+        (0..2)
+            .map(|i| MqttMessage {
+                client_id: format!("c{}", i),
+                topic: Bytes::from("/sensor/1"),
+                payload: Bytes::from(format!("{}", i)),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn body_template_test() {
+        let messages = build_messages();
+        let template = BodyTemplate::parse("${messages}").unwrap();
+        let body = template.render("b1", &messages);
+        let decoded = serde_json::from_slice::<Vec<MqttMessage>>(&body).unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[1].client_id, "c1".to_string());
+
+        let template = BodyTemplate::parse("${bridge}:${count}").unwrap();
+        assert_eq!(template.render("b1", &messages), b"b1:2".to_vec());
+
+        assert!(BodyTemplate::parse("${message}").is_err());
+        assert!(BodyTemplate::parse("{\"a\":${count").is_err());
+    }
+
+    #[tokio::test]
+    async fn webhook_retry_test() {
+        let (addr, requests) = start_server(vec![503, 429]).await;
+        let connector = WebhookConnector::new("b1", build_config(&addr)).unwrap();
+        connector.start().await.unwrap();
+
+        connector.write_batch(&build_messages()).await.unwrap();
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+
+        let (head, body) = &requests[2];
+        assert!(head.starts_with("POST /hook HTTP/1.1"));
+        assert!(head.contains("Authorization: Bearer t1"));
+        assert!(head.contains("Content-Type: application/json"));
+        let body = serde_json::from_slice::<HashMap<String, serde_json::Value>>(body).unwrap();
+        assert_eq!(body["bridge"], "b1");
+        assert_eq!(body["count"], 2);
+        assert_eq!(body["messages"][0]["client_id"], "c0");
+    }
+
+    #[tokio::test]
+    async fn webhook_failure_test() {
+        // a client error is not retried
+        let (addr, requests) = start_server(vec![400]).await;
+        let connector = WebhookConnector::new("b1", build_config(&addr)).unwrap();
+        assert!(connector.write_batch(&build_messages()).await.is_err());
+        assert_eq!(requests.lock().unwrap().len(), 1);
+
+        // the server does not speak TLS, so an https url fails the handshake
+        let mut config = build_config(&addr);
+        config.url = format!("https://{}/hook", addr);
+        config.timeout_ms = 500;
+        config.max_retries = 0;
+        let connector = WebhookConnector::new("b1", config).unwrap();
+        assert!(connector.write_batch(&build_messages()).await.is_err());
+
+        // retries stop after max_retries
+        let (addr, requests) = start_server(vec![500, 500, 500]).await;
+        let mut config = build_config(&addr);
+        config.max_retries = 2;
+        let connector = WebhookConnector::new("b1", config).unwrap();
+        assert!(connector.write_batch(&build_messages()).await.is_err());
+        assert_eq!(requests.lock().unwrap().len(), 3);
+
+        // the next batch goes through once the webhook recovered
+        connector.write_batch(&build_messages()).await.unwrap();
+        assert_eq!(requests.lock().unwrap().len(), 4);
+    }
+}
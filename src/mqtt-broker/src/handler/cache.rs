@@ -13,11 +13,13 @@
 // limitations under the License.
 
 use std::collections::HashSet;
-use std::sync::Arc;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
 use common_base::config::broker_mqtt::broker_mqtt_conf;
 use common_base::tools::now_second;
+use common_base::utils::topic_trie::TopicTrie;
 use dashmap::DashMap;
 use grpc_clients::pool::ClientPool;
 use log::warn;
@@ -33,16 +35,22 @@ use protocol::broker_mqtt::broker_mqtt_inner::{
 };
 use protocol::mqtt::common::{MqttProtocol, PublishProperties, Subscribe, SubscribeProperties};
 use serde::{Deserialize, Serialize};
-use tokio::sync::broadcast::Sender;
+use tokio::sync::broadcast::{self, Sender};
 use tokio::time::sleep;
 
+use super::flow_control::RateLimiter;
 use crate::security::acl::metadata::AclMetadata;
+use crate::security::password::{hash_password_blocking, PasswordHashAlgorithm};
 use crate::security::AuthDriver;
 use crate::storage::cluster::ClusterStorage;
 use crate::storage::topic::TopicStorage;
 use crate::storage::user::UserStorage;
+use crate::subscribe::sub_common::sub_path_filter;
 use crate::subscribe::subscriber::SubscribeData;
 
+// Topic creation events buffered for the subscribe manager
+const TOPIC_CREATE_CHANNEL_SIZE: usize = 1000;
+
 #[derive(Clone, Serialize, Deserialize)]
 pub enum MetadataCacheAction {
     Set,
@@ -115,6 +123,9 @@ pub struct CacheManager {
     // (client_id, <path,SubscribeData>)
     pub subscribe_filter: DashMap<String, DashMap<String, SubscribeData>>,
 
+    // (filter, (client_id, path)), the filter is the path without the $share or $queue prefix
+    pub subscribe_index: Arc<RwLock<TopicTrie<(String, String)>>>,
+
     // (client_id, <path,bool>)
     pub subscribe_is_new: DashMap<String, DashMap<String, bool>>,
 
@@ -130,6 +141,12 @@ pub struct CacheManager {
     // (topic_id, topic_name)
     pub topic_id_name: DashMap<String, String>,
 
+    // trie of the topic names, queried with subscription filters
+    pub topic_index: Arc<RwLock<TopicTrie<String>>>,
+
+    // topics created by this broker, bound to the existing subscriptions by the subscribe manager
+    pub topic_create_sx: Sender<MqttTopic>,
+
     // (client_id, HeartbeatShard)
     pub heartbeat_data: DashMap<String, ConnectionLiveTime>,
 
@@ -139,8 +156,14 @@ pub struct CacheManager {
     // (client_id_pkid, QosPkidData)
     pub client_pkid_data: DashMap<String, ClientPkidData>,
 
+    // (client_id, is_redelivering), sessions with messages in the offline message queue
+    pub offline_message_client: DashMap<String, bool>,
+
     // acl metadata
     pub acl_metadata: AclMetadata,
+
+    // token buckets of the connection, publish and subscribe rate limits
+    pub rate_limiter: RateLimiter,
 }
 
 impl CacheManager {
@@ -153,14 +176,19 @@ impl CacheManager {
             session_info: DashMap::with_capacity(8),
             topic_info: DashMap::with_capacity(8),
             topic_id_name: DashMap::with_capacity(8),
+            topic_index: Arc::new(RwLock::new(TopicTrie::new())),
+            topic_create_sx: broadcast::channel(TOPIC_CREATE_CHANNEL_SIZE).0,
             connection_info: DashMap::with_capacity(8),
             subscribe_filter: DashMap::with_capacity(8),
+            subscribe_index: Arc::new(RwLock::new(TopicTrie::new())),
             subscribe_is_new: DashMap::with_capacity(8),
             publish_pkid_info: DashMap::with_capacity(8),
             heartbeat_data: DashMap::with_capacity(8),
             qos_ack_packet: DashMap::with_capacity(8),
             client_pkid_data: DashMap::with_capacity(8),
+            offline_message_client: DashMap::with_capacity(8),
             acl_metadata: AclMetadata::new(),
+            rate_limiter: RateLimiter::new(),
         }
     }
 
@@ -174,6 +202,10 @@ impl CacheManager {
         for filter in subscribe.filters {
             let mut is_new = false;
             let path = filter.path.clone();
+            self.subscribe_index
+                .write()
+                .unwrap()
+                .insert(&sub_path_filter(&path), (client_id.clone(), path.clone()));
             if let Some(data) = self.subscribe_filter.get_mut(&client_id) {
                 data.insert(
                     path.clone(),
@@ -214,9 +246,18 @@ impl CacheManager {
                     sub_list.remove(path);
                 }
             }
+            self.subscribe_index.write().unwrap().remove(
+                &sub_path_filter(path),
+                &(client_id.to_owned(), path.clone()),
+            );
         }
     }
 
+    // (client_id, path) of the subscriptions matching the topic
+    pub fn get_subscribe_by_topic(&self, topic_name: &str) -> Vec<(String, String)> {
+        self.subscribe_index.read().unwrap().match_topic(topic_name)
+    }
+
     pub fn get_session_info(&self, client_id: &str) -> Option<MqttSession> {
         if let Some(session) = self.session_info.get(client_id) {
             return Some(session.clone());
@@ -285,6 +326,23 @@ impl CacheManager {
         let t = topic.clone();
         self.topic_info.insert(topic_name.to_owned(), t.clone());
         self.topic_id_name.insert(t.topic_id, topic_name.to_owned());
+        self.topic_index
+            .write()
+            .unwrap()
+            .insert(topic_name, topic_name.to_owned());
+    }
+
+    pub fn notify_topic_created(&self, topic: &MqttTopic) {
+        // no receiver until the subscribe manager has started, its first sweep picks the topic up
+        let _ = self.topic_create_sx.send(topic.clone());
+    }
+
+    // Names of the topics matched by the subscription path
+    pub fn get_topic_names_by_filter(&self, sub_path: &str) -> Vec<String> {
+        self.topic_index
+            .read()
+            .unwrap()
+            .match_filter(&sub_path_filter(sub_path))
     }
 
     pub fn update_topic_retain_message(&self, topic_name: &str, retain_message: Option<Vec<u8>>) {
@@ -326,9 +384,15 @@ impl CacheManager {
 
     pub fn remove_session(&self, client_id: &str) {
         self.session_info.remove(client_id);
-        self.subscribe_filter.remove(client_id);
+        if let Some((_, sub_list)) = self.subscribe_filter.remove(client_id) {
+            let mut subscribe_index = self.subscribe_index.write().unwrap();
+            for (path, _) in sub_list {
+                subscribe_index.remove(&sub_path_filter(&path), &(client_id.to_owned(), path));
+            }
+        }
         self.subscribe_is_new.remove(client_id);
         self.publish_pkid_info.remove(client_id);
+        self.offline_message_client.remove(client_id);
         self.heartbeat_data.remove(client_id);
 
         for (key, _) in self.qos_ack_packet.clone() {
@@ -426,6 +490,18 @@ impl CacheManager {
         }
     }
 
+    // Reserves a pkid that is carried over from an earlier connection of the session
+    pub fn add_pkid_info(&self, client_id: &str, pkid: u16) {
+        if let Some(mut pkid_list) = self.publish_pkid_info.get_mut(client_id) {
+            if !pkid_list.contains(&pkid) {
+                pkid_list.push(pkid);
+            }
+        } else {
+            self.publish_pkid_info
+                .insert(client_id.to_owned(), vec![pkid]);
+        }
+    }
+
     pub fn remove_pkid_info(&self, client_id: &str, pkid: u16) {
         if let Some(mut pkid_list) = self.publish_pkid_info.get_mut(client_id) {
             pkid_list.retain(|x| *x == pkid);
@@ -509,9 +585,22 @@ impl CacheManager {
     pub async fn init_system_user(&self) {
         // init system user
         let conf = broker_mqtt_conf();
+        let algorithm = match PasswordHashAlgorithm::from_str(&conf.auth.password_hash) {
+            Ok(algorithm) => algorithm,
+            Err(e) => {
+                panic!("{}", e.to_string());
+            }
+        };
+        let password = match hash_password_blocking(&conf.system.default_password, algorithm).await
+        {
+            Ok(password) => password,
+            Err(e) => {
+                panic!("{}", e.to_string());
+            }
+        };
         let system_user_info = MqttUser {
             username: conf.system.default_user.clone(),
-            password: conf.system.default_password.clone(),
+            password,
             is_superuser: true,
         };
         let user_storage = UserStorage::new(self.client_pool.clone());
@@ -27,6 +27,7 @@ use crate::handler::cache::CacheManager;
 use crate::handler::response::{
     response_packet_mqtt_connect_fail, response_packet_mqtt_distinct_by_reason,
 };
+use crate::rule::engine::RuleEngine;
 use crate::security::AuthDriver;
 use crate::server::connection::NetworkConnection;
 use crate::server::connection_manager::ConnectionManager;
@@ -52,6 +53,7 @@ where
         client_pool: Arc<ClientPool>,
         connection_manager: Arc<ConnectionManager>,
         auth_driver: Arc<AuthDriver>,
+        rule_engine: Arc<RuleEngine<S>>,
     ) -> Self {
         let mqtt3_service = MqttService::new(
             MqttProtocol::Mqtt3,
@@ -61,6 +63,7 @@ where
             subscribe_manager.clone(),
             client_pool.clone(),
             auth_driver.clone(),
+            rule_engine.clone(),
         );
         let mqtt4_service = MqttService::new(
             MqttProtocol::Mqtt4,
@@ -70,6 +73,7 @@ where
             subscribe_manager.clone(),
             client_pool.clone(),
             auth_driver.clone(),
+            rule_engine.clone(),
         );
         let mqtt5_service = MqttService::new(
             MqttProtocol::Mqtt5,
@@ -79,6 +83,7 @@ where
             subscribe_manager.clone(),
             client_pool.clone(),
             auth_driver.clone(),
+            rule_engine.clone(),
         );
         Command {
             mqtt3_service,
@@ -95,8 +100,9 @@ where
         addr: SocketAddr,
         packet: MqttPacket,
     ) -> Option<MqttPacket> {
+        // AUTH continues the authentication exchange of a CONNECT that is not logged in yet
         let mut is_connect_pkg = false;
-        if let MqttPacket::Connect(_, _, _, _, _, _) = packet {
+        if let MqttPacket::Connect(_, _, _, _, _, _) | MqttPacket::Auth(_, _) = packet {
             is_connect_pkg = true;
         }
 
@@ -130,6 +136,7 @@ where
                                 last_will_peoperties,
                                 &login,
                                 addr,
+                                tcp_connection.tls_identity.clone(),
                             )
                             .await,
                     )
@@ -144,6 +151,7 @@ where
                                 last_will_peoperties,
                                 &login,
                                 addr,
+                                tcp_connection.tls_identity.clone(),
                             )
                             .await,
                     )
@@ -158,6 +166,7 @@ where
                                 last_will_peoperties,
                                 &login,
                                 addr,
+                                tcp_connection.tls_identity.clone(),
                             )
                             .await,
                     )
@@ -171,21 +180,27 @@ where
                 };
 
                 let ack_pkg = resp_pkg.unwrap();
-                if let MqttPacket::ConnAck(conn_ack, _) = ack_pkg.clone() {
-                    if conn_ack.code == ConnectReturnCode::Success {
-                        let username = if let Some(user) = login {
-                            user.username
-                        } else {
-                            "".to_string()
-                        };
-                        self.metadata_cache
-                            .login_success(tcp_connection.connection_id, username);
-                        info!("connect [{}] login success", tcp_connection.connection_id);
-                    }
-                }
+                self.log_login_success(tcp_connection.connection_id, &ack_pkg);
                 return Some(ack_pkg);
             }
 
+            MqttPacket::Auth(auth, auth_properties) => {
+                if tcp_connection.is_mqtt5() {
+                    let resp_pkg = self
+                        .mqtt5_service
+                        .auth(tcp_connection.connection_id, auth, auth_properties)
+                        .await;
+                    self.log_login_success(tcp_connection.connection_id, &resp_pkg);
+                    return Some(resp_pkg);
+                }
+
+                // AUTH only exists in MQTT 5
+                return Some(response_packet_mqtt_distinct_by_reason(
+                    &MqttProtocol::Mqtt5,
+                    Some(DisconnectReasonCode::ProtocolError),
+                ));
+            }
+
             MqttPacket::Publish(publish, publish_properties) => {
                 if tcp_connection.is_mqtt3() {
                     return self
@@ -455,6 +470,14 @@ where
         ))
     }
 
+    fn log_login_success(&self, connection_id: u64, packet: &MqttPacket) {
+        if let MqttPacket::ConnAck(conn_ack, _) = packet {
+            if conn_ack.code == ConnectReturnCode::Success {
+                info!("connect [{}] login success", connection_id);
+            }
+        }
+    }
+
     pub async fn check_login_status(&self, connection_id: u64) -> bool {
         self.metadata_cache.is_login(connection_id)
     }
@@ -26,6 +26,7 @@ use super::cache::CacheManager;
 use super::keep_alive::client_keep_live_time;
 use crate::server::connection_manager::ConnectionManager;
 use crate::storage::session::SessionStorage;
+use crate::subscribe::offline::is_offline_queue_enable;
 use crate::subscribe::subscribe_manager::SubscribeManager;
 
 pub const REQUEST_RESPONSE_PREFIX_NAME: &str = "/sys/request_response/";
@@ -87,7 +88,9 @@ pub fn build_connection(
         keep_alive,
         source_ip_addr: addr.to_string(),
     };
-    MQTTConnection::new(config)
+    let mut connection = MQTTConnection::new(config);
+    connection.clean_session = connect.clean_session;
+    connection
 }
 
 pub fn get_client_id(client_id: &str) -> (String, bool) {
@@ -120,12 +123,22 @@ pub async fn disconnect_connection(
     subscribe_manager
         .remove_exclusive_subscribe_by_client_id(client_id)
         .await?;
+    // A persistent session keeps its exclusive push threads running so that
+    // messages arriving while the client is offline go to the offline message queue
+    let persistent_session = cache_manager
+        .get_connection(connect_id)
+        .is_some_and(|conn| !conn.clean_session)
+        && is_offline_queue_enable(cache_manager);
     // Remove the connection cache
     cache_manager.remove_connection(connect_id);
     // Remove the client id bound connection information
     cache_manager.update_session_connect_id(client_id, None);
     // Once the connection is dropped, the push thread for the Client ID dimension is paused
-    subscribe_manager.stop_push_by_client_id(client_id);
+    if persistent_session {
+        subscribe_manager.stop_share_push_by_client_id(client_id);
+    } else {
+        subscribe_manager.stop_push_by_client_id(client_id);
+    }
 
     // Remove the Connect id of the Session in the Placement Center
     let session_storage = SessionStorage::new(client_pool.clone());
@@ -15,6 +15,10 @@
 use std::string::FromUtf8Error;
 
 use common_base::error::common::CommonError;
+use http_client::error::HttpClientError;
+use mqtt_bridge_elasticsearch::error::ElasticsearchBridgeError;
+use mqtt_bridge_kafka::error::KafkaBridgeError;
+use mqtt_bridge_redis::error::RedisBridgeError;
 use thiserror::Error;
 use tonic::Status;
 
@@ -38,6 +42,24 @@ pub enum MqttBrokerError {
     #[error("{0}")]
     FromMysqlError(#[from] mysql::Error),
 
+    #[error("{0}")]
+    FromOpensslError(#[from] openssl::error::ErrorStack),
+
+    #[error("{0}")]
+    FromKafkaBridgeError(#[from] KafkaBridgeError),
+
+    #[error("{0}")]
+    FromRedisBridgeError(#[from] RedisBridgeError),
+
+    #[error("{0}")]
+    FromElasticsearchBridgeError(#[from] ElasticsearchBridgeError),
+
+    #[error("{0}")]
+    FromQuicConnectionError(#[from] quinn::ConnectionError),
+
+    #[error("{0}")]
+    FromHttpClientError(#[from] HttpClientError),
+
     #[error("Topic alias is too long. alias is {0}")]
     TopicAliasTooLong(u16),
 
@@ -90,6 +112,51 @@ pub enum MqttBrokerError {
 
     #[error("invalid acl permission")]
     InvalidAclPermission,
+
+    #[error("Bridge [{0}] already exists")]
+    BridgeAlreadyExist(String),
+
+    #[error("Bridge [{0}] does not exist")]
+    BridgeNotExist(String),
+
+    #[error("Invalid bridge rule [{0}], {1}")]
+    InvalidBridgeRule(String, String),
+
+    #[error("Bridge [{0}] is paused")]
+    BridgePaused(String),
+
+    #[error("Rule [{0}] already exists")]
+    RuleAlreadyExist(String),
+
+    #[error("Invalid rule [{0}], {1}")]
+    InvalidRule(String, String),
+
+    #[error("Invalid rule SQL, {0}")]
+    InvalidRuleSql(String),
+
+    #[error("Client [{0}] is not authorized to publish to topic [{1}]")]
+    PublishNotAuthorized(String, String),
+
+    #[error("Authentication method [{0}] is not supported")]
+    BadAuthenticationMethod(String),
+
+    #[error("Authentication failed, {0}")]
+    AuthenticationFailed(String),
+
+    #[error("Connection [{0}] has no authentication exchange in progress")]
+    AuthExchangeNotFound(u64),
+
+    #[error("Invalid JWT, {0}")]
+    InvalidJwt(String),
+
+    #[error("Invalid peer certificate, {0}")]
+    InvalidPeerCertificate(String),
+
+    #[error("Password hash algorithm [{0}] is not supported")]
+    UnsupportedPasswordHash(String),
+
+    #[error("Invalid Redis auth configuration, {0}")]
+    InvalidRedisAuthConfig(String),
 }
 
 impl From<MqttBrokerError> for Status {
@@ -11,17 +11,450 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use std::sync::Arc;
+use std::time::Duration;
 
-use protocol::mqtt::common::{MqttProtocol, QoS};
+use dashmap::DashMap;
+use log::info;
+use metadata_struct::mqtt::cluster::MqttClusterDynamicConfigRateLimit;
+use protocol::mqtt::common::{MqttPacket, MqttProtocol, QoS};
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::time::{sleep, Instant};
+
+use super::cache::CacheManager;
+use crate::server::connection::NetworkConnectionType;
+
+const BUCKET_IDLE_TTL_SEC: u64 = 300;
+const BUCKET_SWEEP_INTERVAL_SEC: u64 = 60;
 
 pub fn is_flow_control(protocol: &MqttProtocol, qos: QoS) -> bool {
     protocol.is_mqtt5() && (qos == QoS::AtLeastOnce || qos == QoS::ExactlyOnce)
 }
 
-pub fn is_connection_rate_exceeded() -> bool {
-    false
+pub fn is_connection_rate_exceeded(
+    cache_manager: &Arc<CacheManager>,
+    network_type: &NetworkConnectionType,
+) -> bool {
+    let cluster = cache_manager.get_cluster_info();
+    !cache_manager.rate_limiter.try_acquire_connection(
+        &network_type.to_string(),
+        cluster.rate_limit.max_connection_rate as u64,
+    )
+}
+
+pub fn is_subscribe_rate_exceeded(cache_manager: &Arc<CacheManager>, client_id: &str) -> bool {
+    let cluster = cache_manager.get_cluster_info();
+    !cache_manager.rate_limiter.try_acquire_subscribe(
+        client_id,
+        cluster.rate_limit.max_client_subscribe_rate as u64,
+    )
+}
+
+pub fn is_publish_rate_exceeded(
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+    topic_name: &str,
+    payload_size: u64,
+) -> bool {
+    let cluster = cache_manager.get_cluster_info();
+    !cache_manager.rate_limiter.try_acquire_publish(
+        client_id,
+        topic_name,
+        payload_size,
+        &cluster.rate_limit,
+    )
+}
+
+// MQTT 3.1/3.1.1 have no reason code to reject a publish, so instead of rejecting
+// the packet the read loop of the connection is paused until the quota is refilled.
+pub async fn publish_throttle(
+    cache_manager: &Arc<CacheManager>,
+    connect_id: u64,
+    protocol_version: Option<u8>,
+    packet: &MqttPacket,
+) {
+    if !matches!(protocol_version, Some(3) | Some(4)) {
+        return;
+    }
+
+    let MqttPacket::Publish(publish, _) = packet else {
+        return;
+    };
+
+    let client_id = if let Some(conn) = cache_manager.connection_info.get(&connect_id) {
+        conn.client_id.clone()
+    } else {
+        return;
+    };
+
+    let cluster = cache_manager.get_cluster_info();
+    let topic_name = String::from_utf8_lossy(&publish.topic);
+    let wait = cache_manager.rate_limiter.acquire_publish(
+        &client_id,
+        &topic_name,
+        publish.payload.len() as u64,
+        &cluster.rate_limit,
+    );
+    if !wait.is_zero() {
+        sleep(wait).await;
+    }
+}
+
+// Buckets are kept across reconnects, so a client cannot reset its quota by
+// reconnecting. Idle buckets are swept periodically instead.
+pub async fn start_rate_limiter_sweep(
+    cache_manager: Arc<CacheManager>,
+    stop_send: broadcast::Sender<bool>,
+) {
+    let mut stop_rx = stop_send.subscribe();
+    loop {
+        select! {
+            val = stop_rx.recv() =>{
+                if let Ok(flag) = val {
+                    if flag {
+                        info!("{}","Rate limiter sweep thread stopped successfully.");
+                        break;
+                    }
+                }
+            }
+            _ = sleep(Duration::from_secs(BUCKET_SWEEP_INTERVAL_SEC))=>{
+                cache_manager
+                    .rate_limiter
+                    .remove_idle(Duration::from_secs(BUCKET_IDLE_TTL_SEC));
+            }
+        }
+    }
+}
+
+// A token bucket whose capacity is one second worth of tokens. A request is allowed
+// as long as there is a token left and may overdraw the bucket, so that a single
+// message larger than the byte rate is not rejected forever. Time is read from
+// the tokio clock, so tests can pause and advance it.
+#[derive(Clone)]
+pub struct TokenBucket {
+    rate: u64,
+    tokens: f64,
+    // Every use refills the bucket first, so this is also when it was last used
+    last_refill: Instant,
 }
 
-pub fn is_subscribe_rate_exceeded() -> bool {
-    false
+impl TokenBucket {
+    pub fn new(rate: u64) -> Self {
+        TokenBucket {
+            rate,
+            tokens: rate as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    pub fn refill(&mut self, rate: u64) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.last_refill = now;
+        if self.rate != rate {
+            self.rate = rate;
+        }
+        self.tokens = (self.tokens + elapsed * self.rate as f64).min(self.rate as f64);
+    }
+
+    pub fn has_token(&self) -> bool {
+        self.tokens >= 1.0
+    }
+
+    pub fn consume(&mut self, n: u64) {
+        self.tokens -= n as f64;
+    }
+
+    // An idle bucket that would be full again can be dropped, a new bucket
+    // for the same key starts full as well.
+    pub fn is_idle(&self, ttl: Duration) -> bool {
+        let elapsed = self.last_refill.elapsed();
+        elapsed >= ttl && self.tokens + elapsed.as_secs_f64() * self.rate as f64 >= self.rate as f64
+    }
+
+    // How long to wait until the debt of the bucket is paid off
+    pub fn wait_time(&self) -> Duration {
+        if self.tokens >= 0.0 || self.rate == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64(-self.tokens / self.rate as f64)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+    // (listener, TokenBucket)
+    connection_bucket: DashMap<String, TokenBucket>,
+
+    // (client_id, TokenBucket)
+    client_publish_bucket: DashMap<String, TokenBucket>,
+
+    // (client_id, TokenBucket)
+    client_publish_bytes_bucket: DashMap<String, TokenBucket>,
+
+    // (topic_name, TokenBucket)
+    topic_publish_bucket: DashMap<String, TokenBucket>,
+
+    // (client_id, TokenBucket)
+    client_subscribe_bucket: DashMap<String, TokenBucket>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        RateLimiter::default()
+    }
+
+    pub fn try_acquire_connection(&self, listener: &str, rate: u64) -> bool {
+        try_acquire(&self.connection_bucket, listener, rate, 1)
+    }
+
+    pub fn try_acquire_subscribe(&self, client_id: &str, rate: u64) -> bool {
+        try_acquire(&self.client_subscribe_bucket, client_id, rate, 1)
+    }
+
+    // Checks the client, client bytes and topic buckets together, nothing is
+    // consumed unless all of them have tokens left.
+    pub fn try_acquire_publish(
+        &self,
+        client_id: &str,
+        topic_name: &str,
+        payload_size: u64,
+        limit: &MqttClusterDynamicConfigRateLimit,
+    ) -> bool {
+        let buckets = self.publish_buckets(client_id, topic_name, payload_size, limit);
+        for (bucket, key, rate, _) in buckets.iter() {
+            if !has_token(bucket, key, *rate) {
+                return false;
+            }
+        }
+        for (bucket, key, rate, n) in buckets {
+            consume(bucket, key, rate, n);
+        }
+        true
+    }
+
+    // Consumes the publish quota unconditionally and returns how long the caller
+    // should wait before reading the next packet.
+    pub fn acquire_publish(
+        &self,
+        client_id: &str,
+        topic_name: &str,
+        payload_size: u64,
+        limit: &MqttClusterDynamicConfigRateLimit,
+    ) -> Duration {
+        let mut wait = Duration::ZERO;
+        for (bucket, key, rate, n) in
+            self.publish_buckets(client_id, topic_name, payload_size, limit)
+        {
+            wait = wait.max(consume(bucket, key, rate, n));
+        }
+        wait
+    }
+
+    pub fn remove_idle(&self, ttl: Duration) {
+        for buckets in [
+            &self.connection_bucket,
+            &self.client_publish_bucket,
+            &self.client_publish_bytes_bucket,
+            &self.topic_publish_bucket,
+            &self.client_subscribe_bucket,
+        ] {
+            buckets.retain(|_, bucket| !bucket.is_idle(ttl));
+        }
+    }
+
+    fn publish_buckets<'a>(
+        &'a self,
+        client_id: &'a str,
+        topic_name: &'a str,
+        payload_size: u64,
+        limit: &MqttClusterDynamicConfigRateLimit,
+    ) -> Vec<(&'a DashMap<String, TokenBucket>, &'a str, u64, u64)> {
+        vec![
+            (
+                &self.client_publish_bucket,
+                client_id,
+                limit.max_client_publish_rate as u64,
+                1,
+            ),
+            (
+                &self.client_publish_bytes_bucket,
+                client_id,
+                limit.max_client_publish_bytes_rate,
+                payload_size,
+            ),
+            (
+                &self.topic_publish_bucket,
+                topic_name,
+                limit.max_topic_publish_rate as u64,
+                1,
+            ),
+        ]
+    }
+}
+
+fn try_acquire(buckets: &DashMap<String, TokenBucket>, key: &str, rate: u64, n: u64) -> bool {
+    if !has_token(buckets, key, rate) {
+        return false;
+    }
+    consume(buckets, key, rate, n);
+    true
+}
+
+fn has_token(buckets: &DashMap<String, TokenBucket>, key: &str, rate: u64) -> bool {
+    if rate == 0 {
+        return true;
+    }
+    let mut bucket = buckets
+        .entry(key.to_owned())
+        .or_insert_with(|| TokenBucket::new(rate));
+    bucket.refill(rate);
+    bucket.has_token()
+}
+
+fn consume(buckets: &DashMap<String, TokenBucket>, key: &str, rate: u64, n: u64) -> Duration {
+    if rate == 0 {
+        return Duration::ZERO;
+    }
+    let mut bucket = buckets
+        .entry(key.to_owned())
+        .or_insert_with(|| TokenBucket::new(rate));
+    bucket.refill(rate);
+    bucket.consume(n);
+    bucket.wait_time()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use metadata_struct::mqtt::cluster::MqttClusterDynamicConfigRateLimit;
+    use tokio::time::{advance, Instant};
+
+    use super::{RateLimiter, TokenBucket};
+
+    #[tokio::test(start_paused = true)]
+    async fn token_bucket_test() {
+        let mut bucket = TokenBucket::new(2);
+        assert!(bucket.has_token());
+        bucket.consume(1);
+        bucket.consume(1);
+        assert!(!bucket.has_token());
+        assert_eq!(bucket.wait_time(), Duration::ZERO);
+
+        bucket.consume(2);
+        assert_eq!(bucket.tokens, -2.0);
+        assert_eq!(bucket.wait_time(), Duration::from_secs(1));
+
+        advance(Duration::from_millis(500)).await;
+        bucket.refill(2);
+        assert_eq!(bucket.tokens, -1.0);
+        assert!(!bucket.has_token());
+
+        // refilling stops at the capacity of the bucket
+        advance(Duration::from_millis(2000)).await;
+        bucket.refill(2);
+        assert_eq!(bucket.tokens, 2.0);
+        assert!(bucket.has_token());
+    }
+
+    #[test]
+    fn connection_rate_test() {
+        let limiter = RateLimiter::new();
+        assert!(limiter.try_acquire_connection("tcp", 2));
+        assert!(limiter.try_acquire_connection("tcp", 2));
+        assert!(!limiter.try_acquire_connection("tcp", 2));
+        assert!(limiter.try_acquire_connection("tls", 2));
+
+        // 0 means unlimited
+        for _ in 0..100 {
+            assert!(limiter.try_acquire_connection("quic", 0));
+        }
+    }
+
+    #[test]
+    fn publish_rate_test() {
+        let limiter = RateLimiter::new();
+        let limit = MqttClusterDynamicConfigRateLimit {
+            max_client_publish_rate: 10,
+            max_client_publish_bytes_rate: 100,
+            max_topic_publish_rate: 2,
+            ..Default::default()
+        };
+
+        assert!(limiter.try_acquire_publish("c1", "t1", 10, &limit));
+        assert!(limiter.try_acquire_publish("c2", "t1", 10, &limit));
+        assert!(!limiter.try_acquire_publish("c1", "t1", 10, &limit));
+
+        // a rejected publish does not consume the client quota
+        // the last bytes of the quota may be overdrawn by a larger message
+        assert!(limiter.try_acquire_publish("c1", "t2", 100, &limit));
+        assert!(!limiter.try_acquire_publish("c1", "t3", 10, &limit));
+
+        // the quota is kept while the buckets are in use
+        limiter.remove_idle(Duration::from_secs(60));
+        assert!(!limiter.try_acquire_publish("c1", "t3", 10, &limit));
+    }
+
+    #[test]
+    fn remove_idle_test() {
+        let limiter = RateLimiter::new();
+        let last_refill = Instant::now() - Duration::from_secs(10);
+        limiter.topic_publish_bucket.insert(
+            "idle".to_string(),
+            TokenBucket {
+                rate: 2,
+                tokens: 0.0,
+                last_refill,
+            },
+        );
+        // still paying off the debt of a large message
+        limiter.client_publish_bytes_bucket.insert(
+            "c1".to_string(),
+            TokenBucket {
+                rate: 2,
+                tokens: -100.0,
+                last_refill,
+            },
+        );
+        limiter.topic_publish_bucket.insert(
+            "recent".to_string(),
+            TokenBucket {
+                rate: 2,
+                tokens: 0.0,
+                last_refill: Instant::now(),
+            },
+        );
+
+        limiter.remove_idle(Duration::from_secs(5));
+        assert!(!limiter.topic_publish_bucket.contains_key("idle"));
+        assert!(limiter.topic_publish_bucket.contains_key("recent"));
+        assert!(limiter.client_publish_bytes_bucket.contains_key("c1"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn publish_throttle_test() {
+        let limiter = RateLimiter::new();
+        let limit = MqttClusterDynamicConfigRateLimit {
+            max_client_publish_bytes_rate: 100,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            limiter.acquire_publish("c1", "t1", 100, &limit),
+            Duration::ZERO
+        );
+        assert_eq!(
+            limiter.acquire_publish("c1", "t1", 50, &limit),
+            Duration::from_millis(500)
+        );
+
+        // once the debt is paid off the next publish does not wait
+        advance(Duration::from_millis(500)).await;
+        assert_eq!(
+            limiter.acquire_publish("c1", "t1", 0, &limit),
+            Duration::ZERO
+        );
+    }
 }
@@ -15,40 +15,42 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+use bytes::Bytes;
 use common_base::tools::now_second;
 use grpc_clients::pool::ClientPool;
-use log::{error, warn};
+use log::{error, info, warn};
 use metadata_struct::mqtt::message::MqttMessage;
 use protocol::mqtt::common::{
-    Connect, ConnectProperties, ConnectReturnCode, Disconnect, DisconnectProperties,
-    DisconnectReasonCode, LastWill, LastWillProperties, Login, MqttPacket, MqttProtocol, PingReq,
-    PubAck, PubAckProperties, PubAckReason, PubComp, PubCompProperties, PubCompReason, PubRec,
-    PubRecProperties, PubRecReason, PubRel, PubRelProperties, PubRelReason, Publish,
-    PublishProperties, QoS, Subscribe, SubscribeProperties, SubscribeReasonCode, UnsubAckReason,
-    Unsubscribe, UnsubscribeProperties,
+    Auth, AuthProperties, AuthReason, Connect, ConnectProperties, ConnectReturnCode, Disconnect,
+    DisconnectProperties, DisconnectReasonCode, LastWill, LastWillProperties, Login, MqttPacket,
+    MqttProtocol, PingReq, PubAck, PubAckProperties, PubAckReason, PubComp, PubCompProperties,
+    PubCompReason, PubRec, PubRecProperties, PubRecReason, PubRel, PubRelProperties, PubRelReason,
+    Publish, PublishProperties, QoS, Subscribe, SubscribeProperties, SubscribeReasonCode,
+    UnsubAckReason, Unsubscribe, UnsubscribeProperties,
 };
 use storage_adapter::storage::StorageAdapter;
 
 use super::connection::disconnect_connection;
-use super::flow_control::is_flow_control;
+use super::flow_control::{is_flow_control, is_publish_rate_exceeded};
 use super::message::build_message_expire;
 use super::retain::try_send_retain_message;
 use crate::handler::cache::{
     CacheManager, ConnectionLiveTime, QosAckPackageData, QosAckPackageType,
 };
 use crate::handler::connection::{build_connection, get_client_id};
+use crate::handler::error::MqttBrokerError;
 use crate::handler::lastwill::save_last_will_message;
 use crate::handler::pkid::{pkid_delete, pkid_exists, pkid_save};
 use crate::handler::response::{
-    response_packet_mqtt_connect_fail, response_packet_mqtt_connect_success,
-    response_packet_mqtt_distinct_by_reason, response_packet_mqtt_ping_resp,
-    response_packet_mqtt_puback_fail, response_packet_mqtt_puback_success,
-    response_packet_mqtt_pubcomp_fail, response_packet_mqtt_pubcomp_success,
-    response_packet_mqtt_pubrec_fail, response_packet_mqtt_pubrec_success,
-    response_packet_mqtt_pubrel_success, response_packet_mqtt_suback,
-    response_packet_mqtt_unsuback,
+    response_packet_mqtt_auth, response_packet_mqtt_connect_fail,
+    response_packet_mqtt_connect_success, response_packet_mqtt_distinct_by_reason,
+    response_packet_mqtt_ping_resp, response_packet_mqtt_puback_fail,
+    response_packet_mqtt_puback_success, response_packet_mqtt_pubcomp_fail,
+    response_packet_mqtt_pubcomp_success, response_packet_mqtt_pubrec_fail,
+    response_packet_mqtt_pubrec_success, response_packet_mqtt_pubrel_success,
+    response_packet_mqtt_suback, response_packet_mqtt_unsuback,
 };
-use crate::handler::retain::save_retain_message;
+use crate::handler::retain::apply_rules_and_save_retain;
 use crate::handler::session::{build_session, save_session};
 use crate::handler::topic::{get_topic_name, try_init_topic};
 use crate::handler::validator::{
@@ -58,9 +60,13 @@ use crate::observability::system_topic::event::{
     st_report_connected_event, st_report_disconnected_event, st_report_subscribed_event,
     st_report_unsubscribed_event,
 };
-use crate::security::AuthDriver;
+use crate::rule::engine::RuleEngine;
+use crate::security::enhanced::{AuthContext, AuthOutcome, PendingConnect};
+use crate::security::{AuthDriver, LoginIdentity, TlsIdentity};
 use crate::server::connection_manager::ConnectionManager;
 use crate::storage::message::MessageStorage;
+use crate::storage::offline::OfflineMessageStorage;
+use crate::subscribe::offline::clear_offline_message;
 use crate::subscribe::sub_common::{min_qos, path_contain_sub};
 use crate::subscribe::subscribe_manager::SubscribeManager;
 
@@ -73,12 +79,14 @@ pub struct MqttService<S> {
     subscribe_manager: Arc<SubscribeManager>,
     client_pool: Arc<ClientPool>,
     auth_driver: Arc<AuthDriver>,
+    rule_engine: Arc<RuleEngine<S>>,
 }
 
 impl<S> MqttService<S>
 where
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         protocol: MqttProtocol,
         cache_manager: Arc<CacheManager>,
@@ -87,6 +95,7 @@ where
         subscribe_manager: Arc<SubscribeManager>,
         client_pool: Arc<ClientPool>,
         auth_driver: Arc<AuthDriver>,
+        rule_engine: Arc<RuleEngine<S>>,
     ) -> Self {
         MqttService {
             protocol,
@@ -96,6 +105,7 @@ where
             subscribe_manager,
             client_pool,
             auth_driver,
+            rule_engine,
         }
     }
 
@@ -103,12 +113,13 @@ where
     pub async fn connect(
         &mut self,
         connect_id: u64,
-        connect: Connect,
+        mut connect: Connect,
         connect_properties: Option<ConnectProperties>,
         last_will: Option<LastWill>,
         last_will_properties: Option<LastWillProperties>,
         login: &Option<Login>,
         addr: SocketAddr,
+        tls_identity: Option<TlsIdentity>,
     ) -> MqttPacket {
         let cluster: metadata_struct::mqtt::cluster::MqttClusterDynamicConfig =
             self.cache_manager.get_cluster_info();
@@ -126,20 +137,62 @@ where
             return res;
         }
 
-        match self
+        if let Some(client_id) = self.auth_driver.certificate_client_id(&tls_identity) {
+            connect.client_id = client_id;
+        }
+
+        if let Some(method) = authentication_method(&connect_properties) {
+            let data = connect_properties
+                .as_ref()
+                .and_then(|properties| properties.authentication_data.clone());
+            let context = AuthContext {
+                client_id: connect.client_id.clone(),
+                username: login
+                    .as_ref()
+                    .map(|info| info.username.clone())
+                    .filter(|username| !username.is_empty()),
+            };
+            let pending = PendingConnect {
+                connect,
+                connect_properties: connect_properties.clone(),
+                last_will,
+                last_will_properties,
+                addr,
+            };
+            return match self
+                .auth_driver
+                .enhanced_auth()
+                .start(connect_id, &method, data, context, Some(pending))
+                .await
+            {
+                Ok(outcome) => {
+                    self.enhanced_auth_outcome(connect_id, method, outcome)
+                        .await
+                }
+                Err(e) => self.enhanced_auth_fail(connect_id, false, e),
+            };
+        }
+
+        let identity = match self
             .auth_driver
-            .check_login_auth(login, &connect_properties, &addr)
+            .check_login_auth(
+                &connect.client_id,
+                login,
+                &connect_properties,
+                &addr,
+                &self.protocol,
+                &tls_identity,
+            )
             .await
         {
-            Ok(flag) => {
-                if !flag {
-                    return response_packet_mqtt_connect_fail(
-                        &self.protocol,
-                        ConnectReturnCode::NotAuthorized,
-                        &connect_properties,
-                        None,
-                    );
-                }
+            Ok(Some(identity)) => identity,
+            Ok(None) => {
+                return response_packet_mqtt_connect_fail(
+                    &self.protocol,
+                    ConnectReturnCode::NotAuthorized,
+                    &connect_properties,
+                    None,
+                );
             }
             Err(e) => {
                 return response_packet_mqtt_connect_fail(
@@ -149,11 +202,41 @@ where
                     Some(e.to_string()),
                 );
             }
-        }
+        };
+
+        let pending = PendingConnect {
+            connect,
+            connect_properties,
+            last_will,
+            last_will_properties,
+            addr,
+        };
+        self.establish_connect(connect_id, pending, identity, None, None)
+            .await
+    }
+
+    // Completes a CONNECT whose client has been authenticated.
+    async fn establish_connect(
+        &self,
+        connect_id: u64,
+        pending: PendingConnect,
+        identity: LoginIdentity,
+        authentication_method: Option<String>,
+        authentication_data: Option<Bytes>,
+    ) -> MqttPacket {
+        let cluster = self.cache_manager.get_cluster_info();
+        let PendingConnect {
+            connect,
+            connect_properties,
+            last_will,
+            last_will_properties,
+            addr,
+            ..
+        } = pending;
 
         let (client_id, new_client_id) = get_client_id(&connect.client_id);
 
-        let connection = build_connection(
+        let mut connection = build_connection(
             connect_id,
             client_id.clone(),
             &cluster,
@@ -161,6 +244,9 @@ where
             &connect_properties,
             &addr,
         );
+        connection.authentication_method = authentication_method.clone();
+        connection.login_success(identity.username);
+        connection.acl = identity.acl;
 
         let (session, new_session) = match build_session(
             connect_id,
@@ -205,6 +291,19 @@ where
             }
         }
 
+        // A new session starts with an empty offline message queue
+        if new_session {
+            let offline_storage = OfflineMessageStorage::new(self.message_storage_adapter.clone());
+            if let Err(e) =
+                clear_offline_message(&offline_storage, &self.cache_manager, &client_id).await
+            {
+                error!(
+                    "Failed to clear the offline message queue of client {}, error message: {}",
+                    client_id, e
+                );
+            }
+        }
+
         match save_last_will_message(
             client_id.clone(),
             &last_will,
@@ -257,6 +356,182 @@ where
             new_session,
             connection.keep_alive,
             &connect_properties,
+            authentication_method,
+            authentication_data,
+        )
+    }
+
+    pub async fn auth(
+        &self,
+        connect_id: u64,
+        auth: Auth,
+        auth_properties: Option<AuthProperties>,
+    ) -> MqttPacket {
+        let is_login = self.cache_manager.is_login(connect_id);
+        let properties = auth_properties.unwrap_or_default();
+        let method = if let Some(method) = properties.authentication_method {
+            method
+        } else {
+            return self.enhanced_auth_protocol_error(
+                connect_id,
+                is_login,
+                "Authentication method is missing".to_string(),
+            );
+        };
+
+        let enhanced_auth = self.auth_driver.enhanced_auth();
+        let result = match auth.reason {
+            Some(AuthReason::ContinueAuthentication) => {
+                enhanced_auth
+                    .continue_auth(connect_id, &method, properties.authentication_data)
+                    .await
+            }
+            Some(AuthReason::ReAuthenticate) => {
+                // Only an established connection can re-authenticate, with the method it connected with.
+                let connection = self.cache_manager.get_connection(connect_id);
+                let current_method = connection
+                    .as_ref()
+                    .and_then(|connection| connection.authentication_method.clone());
+                if !is_login
+                    || current_method != Some(method.clone())
+                    || enhanced_auth.is_in_progress(connect_id)
+                {
+                    return self.enhanced_auth_protocol_error(
+                        connect_id,
+                        is_login,
+                        format!("Re-authentication with method [{}] is not allowed", method),
+                    );
+                }
+                let context = connection
+                    .map(|connection| AuthContext {
+                        client_id: connection.client_id,
+                        username: Some(connection.login_user)
+                            .filter(|username| !username.is_empty()),
+                    })
+                    .unwrap_or_default();
+                enhanced_auth
+                    .start(
+                        connect_id,
+                        &method,
+                        properties.authentication_data,
+                        context,
+                        None,
+                    )
+                    .await
+            }
+            _ => {
+                return self.enhanced_auth_protocol_error(
+                    connect_id,
+                    is_login,
+                    "Client cannot send AUTH with reason Success".to_string(),
+                );
+            }
+        };
+
+        match result {
+            Ok(outcome) => {
+                self.enhanced_auth_outcome(connect_id, method, outcome)
+                    .await
+            }
+            Err(e) => self.enhanced_auth_fail(connect_id, is_login, e),
+        }
+    }
+
+    async fn enhanced_auth_outcome(
+        &self,
+        connect_id: u64,
+        method: String,
+        outcome: AuthOutcome,
+    ) -> MqttPacket {
+        match outcome {
+            AuthOutcome::Continue(data) => {
+                response_packet_mqtt_auth(AuthReason::ContinueAuthentication, method, Some(data))
+            }
+            AuthOutcome::Success {
+                identity,
+                data,
+                connect: Some(pending),
+            } => {
+                self.establish_connect(connect_id, pending, identity, Some(method), data)
+                    .await
+            }
+            AuthOutcome::Success {
+                identity,
+                data,
+                connect: None,
+            } => {
+                // The identity of an established connection cannot change, the
+                // new credentials may carry different ACL rules.
+                let same_user = self
+                    .cache_manager
+                    .get_connection(connect_id)
+                    .is_some_and(|connection| connection.login_user == identity.username);
+                if !same_user {
+                    return self.enhanced_auth_fail(
+                        connect_id,
+                        true,
+                        MqttBrokerError::AuthenticationFailed(format!(
+                            "user [{}] does not match the connection",
+                            identity.username
+                        )),
+                    );
+                }
+                if let Some(mut connection) =
+                    self.cache_manager.connection_info.get_mut(&connect_id)
+                {
+                    connection.acl = identity.acl;
+                }
+                info!("connect [{}] re-authentication success", connect_id);
+                response_packet_mqtt_auth(AuthReason::Success, method, data)
+            }
+        }
+    }
+
+    // Before CONNACK the failure is reported with a CONNACK, afterwards the connection is closed.
+    fn enhanced_auth_fail(
+        &self,
+        connect_id: u64,
+        is_login: bool,
+        e: MqttBrokerError,
+    ) -> MqttPacket {
+        self.auth_driver.enhanced_auth().remove(connect_id);
+        if is_login {
+            warn!("connect [{}] re-authentication failed, {}", connect_id, e);
+            return response_packet_mqtt_distinct_by_reason(
+                &self.protocol,
+                Some(DisconnectReasonCode::NotAuthorized),
+            );
+        }
+
+        let code = match e {
+            MqttBrokerError::BadAuthenticationMethod(_) => {
+                ConnectReturnCode::BadAuthenticationMethod
+            }
+            MqttBrokerError::AuthExchangeNotFound(_) => ConnectReturnCode::ProtocolError,
+            _ => ConnectReturnCode::NotAuthorized,
+        };
+        response_packet_mqtt_connect_fail(&self.protocol, code, &None, Some(e.to_string()))
+    }
+
+    fn enhanced_auth_protocol_error(
+        &self,
+        connect_id: u64,
+        is_login: bool,
+        reason: String,
+    ) -> MqttPacket {
+        self.auth_driver.enhanced_auth().remove(connect_id);
+        if is_login {
+            warn!("connect [{}] {}", connect_id, reason);
+            return response_packet_mqtt_distinct_by_reason(
+                &self.protocol,
+                Some(DisconnectReasonCode::ProtocolError),
+            );
+        }
+        response_packet_mqtt_connect_fail(
+            &self.protocol,
+            ConnectReturnCode::ProtocolError,
+            &None,
+            Some(reason),
         )
     }
 
@@ -357,6 +632,38 @@ where
             }
         }
 
+        // MQTT 3.1/3.1.1 clients are throttled in the read loop instead
+        if self.protocol.is_mqtt5()
+            && is_publish_rate_exceeded(
+                &self.cache_manager,
+                &connection.client_id,
+                &topic_name,
+                publish.payload.len() as u64,
+            )
+        {
+            if is_flow_control(&self.protocol, publish.qos) {
+                connection.recv_qos_message_decr();
+            }
+
+            return match publish.qos {
+                QoS::AtMostOnce => None,
+                QoS::AtLeastOnce => Some(response_packet_mqtt_puback_fail(
+                    &self.protocol,
+                    &connection,
+                    publish.pkid,
+                    PubAckReason::QuotaExceeded,
+                    None,
+                )),
+                QoS::ExactlyOnce => Some(response_packet_mqtt_pubrec_fail(
+                    &self.protocol,
+                    &connection,
+                    publish.pkid,
+                    PubRecReason::QuotaExceeded,
+                    None,
+                )),
+            };
+        }
+
         let topic = match try_init_topic(
             &topic_name,
             &self.cache_manager,
@@ -393,18 +700,19 @@ where
 
         let client_id = connection.client_id.clone();
 
-        // Persisting retain message data
-        match save_retain_message(
+        // Persisting retain message data, a message dropped by a rule is neither retained nor stored
+        let dropped = match apply_rules_and_save_retain(
+            &self.rule_engine,
             &self.cache_manager,
             &self.client_pool,
-            topic_name.clone(),
-            &client_id,
+            &connection,
+            &topic_name,
             &publish,
             &publish_properties,
         )
         .await
         {
-            Ok(()) => {}
+            Ok(dropped) => dropped,
             Err(e) => {
                 if is_flow_control(&self.protocol, publish.qos) {
                     connection.recv_qos_message_decr();
@@ -428,13 +736,15 @@ where
                     ));
                 }
             }
-        }
+        };
 
         // Persisting stores message data
         let message_storage = MessageStorage::new(self.message_storage_adapter.clone());
 
         let message_expire = build_message_expire(&self.cache_manager, &publish_properties);
-        let offset = if let Some(record) =
+        let offset = if dropped {
+            "-1".to_string()
+        } else if let Some(record) =
             MqttMessage::build_record(&client_id, &publish, &publish_properties, message_expire)
         {
             match message_storage
@@ -845,6 +1155,7 @@ where
             self.client_pool.clone(),
             self.cache_manager.clone(),
             self.connection_manager.clone(),
+            self.message_storage_adapter.clone(),
         )
         .await;
 
@@ -965,6 +1276,7 @@ where
         disconnect: Disconnect,
         _: Option<DisconnectProperties>,
     ) -> Option<MqttPacket> {
+        self.auth_driver.enhanced_auth().remove(connect_id);
         let connection = if let Some(se) = self.cache_manager.connection_info.get(&connect_id) {
             se.clone()
         } else {
@@ -1004,3 +1316,9 @@ where
         None
     }
 }
+
+fn authentication_method(connect_properties: &Option<ConnectProperties>) -> Option<String> {
+    connect_properties
+        .as_ref()
+        .and_then(|properties| properties.authentication_method.clone())
+}
@@ -12,15 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use bytes::Bytes;
 use log::{error, warn};
 use metadata_struct::mqtt::cluster::MqttClusterDynamicConfig;
 use metadata_struct::mqtt::connection::MQTTConnection;
 use protocol::mqtt::common::{
-    ConnAck, ConnAckProperties, ConnectProperties, ConnectReturnCode, Disconnect,
-    DisconnectProperties, DisconnectReasonCode, MqttPacket, MqttProtocol, PingResp, PubAck,
-    PubAckProperties, PubAckReason, PubComp, PubCompProperties, PubCompReason, PubRec,
-    PubRecProperties, PubRecReason, PubRel, PubRelProperties, PubRelReason, SubAck,
-    SubAckProperties, SubscribeReasonCode, UnsubAck, UnsubAckProperties, UnsubAckReason,
+    Auth, AuthProperties, AuthReason, ConnAck, ConnAckProperties, ConnectProperties,
+    ConnectReturnCode, Disconnect, DisconnectProperties, DisconnectReasonCode, MqttPacket,
+    MqttProtocol, PingResp, PubAck, PubAckProperties, PubAckReason, PubComp, PubCompProperties,
+    PubCompReason, PubRec, PubRecProperties, PubRecReason, PubRel, PubRelProperties, PubRelReason,
+    SubAck, SubAckProperties, SubscribeReasonCode, UnsubAck, UnsubAckProperties, UnsubAckReason,
 };
 
 use super::connection::response_information;
@@ -37,6 +38,8 @@ pub fn response_packet_mqtt_connect_success(
     session_present: bool,
     keep_alive: u16,
     connect_properties: &Option<ConnectProperties>,
+    authentication_method: Option<String>,
+    authentication_data: Option<Bytes>,
 ) -> MqttPacket {
     if !protocol.is_mqtt5() {
         return MqttPacket::ConnAck(
@@ -76,8 +79,8 @@ pub fn response_packet_mqtt_connect_success(
         server_keep_alive: Some(keep_live_time(keep_alive)),
         response_information: response_information(connect_properties),
         server_reference: None,
-        authentication_method: None,
-        authentication_data: None,
+        authentication_method,
+        authentication_data,
     };
     MqttPacket::ConnAck(
         ConnAck {
@@ -314,6 +317,24 @@ pub fn response_packet_mqtt_suback(
     MqttPacket::SubAck(sub_ack, Some(properties))
 }
 
+pub fn response_packet_mqtt_auth(
+    reason: AuthReason,
+    authentication_method: String,
+    authentication_data: Option<Bytes>,
+) -> MqttPacket {
+    let properties = AuthProperties {
+        authentication_method: Some(authentication_method),
+        authentication_data,
+        ..Default::default()
+    };
+    MqttPacket::Auth(
+        Auth {
+            reason: Some(reason),
+        },
+        Some(properties),
+    )
+}
+
 pub fn response_packet_mqtt_ping_resp() -> MqttPacket {
     MqttPacket::PingResp(PingResp {})
 }
@@ -18,11 +18,13 @@ use bytes::Bytes;
 use common_base::tools::now_second;
 use grpc_clients::pool::ClientPool;
 use log::info;
+use metadata_struct::mqtt::connection::MQTTConnection;
 use metadata_struct::mqtt::message::MqttMessage;
 use protocol::mqtt::common::{
     MqttProtocol, Publish, PublishProperties, QoS, RetainForwardRule, Subscribe,
     SubscribeProperties,
 };
+use storage_adapter::storage::StorageAdapter;
 use tokio::sync::broadcast::{self};
 
 use super::cache::{CacheManager, QosAckPacketInfo};
@@ -32,8 +34,11 @@ use super::message::build_message_expire;
 use crate::observability::metrics::packets::{
     record_retain_recv_metrics, record_retain_sent_metrics,
 };
+use crate::rule::engine::RuleEngine;
 use crate::server::connection_manager::ConnectionManager;
+use crate::storage::offline::OfflineMessageStorage;
 use crate::storage::topic::TopicStorage;
+use crate::subscribe::offline::save_offline_message;
 use crate::subscribe::sub_common::{get_sub_topic_id_list, min_qos, publish_message_qos0};
 use crate::subscribe::sub_exclusive::{
     exclusive_publish_message_qos1, exclusive_publish_message_qos2,
@@ -75,7 +80,37 @@ pub async fn save_retain_message(
     Ok(())
 }
 
-pub async fn try_send_retain_message(
+// Rules run before the message is retained, so a message dropped by a rule never
+// becomes the retained message of its topic. Returns true when it was dropped.
+pub async fn apply_rules_and_save_retain<S>(
+    rule_engine: &Arc<RuleEngine<S>>,
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    connection: &MQTTConnection,
+    topic_name: &str,
+    publish: &Publish,
+    publish_properties: &Option<PublishProperties>,
+) -> Result<bool, MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    if rule_engine.apply(connection, topic_name, publish).await {
+        return Ok(true);
+    }
+    save_retain_message(
+        cache_manager,
+        client_pool,
+        topic_name.to_string(),
+        &connection.client_id,
+        publish,
+        publish_properties,
+    )
+    .await?;
+    Ok(false)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn try_send_retain_message<S>(
     protocol: MqttProtocol,
     client_id: String,
     subscribe: Subscribe,
@@ -83,9 +118,13 @@ pub async fn try_send_retain_message(
     client_pool: Arc<ClientPool>,
     cache_manager: Arc<CacheManager>,
     connection_manager: Arc<ConnectionManager>,
-) {
+    message_storage_adapter: Arc<S>,
+) where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
     tokio::spawn(async move {
         let (stop_sx, _) = broadcast::channel(1);
+        let offline_storage = OfflineMessageStorage::new(message_storage_adapter);
         if let Err(e) = send_retain_message(
             &protocol,
             &client_id,
@@ -94,6 +133,7 @@ pub async fn try_send_retain_message(
             &client_pool,
             &cache_manager,
             &connection_manager,
+            &offline_storage,
             &stop_sx,
         )
         .await
@@ -104,7 +144,7 @@ pub async fn try_send_retain_message(
 }
 
 #[allow(clippy::too_many_arguments)]
-async fn send_retain_message(
+async fn send_retain_message<S>(
     protocol: &MqttProtocol,
     client_id: &String,
     subscribe: &Subscribe,
@@ -112,8 +152,12 @@ async fn send_retain_message(
     client_pool: &Arc<ClientPool>,
     cache_manager: &Arc<CacheManager>,
     connection_manager: &Arc<ConnectionManager>,
+    offline_storage: &OfflineMessageStorage<S>,
     stop_sx: &broadcast::Sender<bool>,
-) -> Result<(), MqttBrokerError> {
+) -> Result<(), MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
     let mut sub_ids = Vec::new();
     if let Some(properties) = subscribe_properties {
         if let Some(id) = properties.subscription_identifier {
@@ -227,7 +271,7 @@ async fn send_retain_message(
                         },
                     );
 
-                    exclusive_publish_message_qos1(
+                    let offline_message = exclusive_publish_message_qos1(
                         cache_manager,
                         connection_manager,
                         &sub_pub_param,
@@ -238,6 +282,11 @@ async fn send_retain_message(
 
                     cache_manager.remove_pkid_info(client_id, pkid);
                     cache_manager.remove_ack_packet(client_id, pkid);
+
+                    if let Some(message) = offline_message {
+                        save_offline_message(offline_storage, cache_manager, client_id, message)
+                            .await?;
+                    }
                 }
 
                 QoS::ExactlyOnce => {
@@ -251,7 +300,7 @@ async fn send_retain_message(
                         },
                     );
 
-                    exclusive_publish_message_qos2(
+                    let offline_message = exclusive_publish_message_qos2(
                         cache_manager,
                         connection_manager,
                         &sub_pub_param,
@@ -262,6 +311,11 @@ async fn send_retain_message(
 
                     cache_manager.remove_pkid_info(client_id, pkid);
                     cache_manager.remove_ack_packet(client_id, pkid);
+
+                    if let Some(message) = offline_message {
+                        save_offline_message(offline_storage, cache_manager, client_id, message)
+                            .await?;
+                    }
                 }
             };
 
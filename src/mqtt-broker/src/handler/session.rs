@@ -40,7 +40,7 @@ pub async fn build_session(
     let is_contain_last_will = !last_will.is_none();
     let last_will_delay_interval = last_will_delay_interval(last_will_properties);
 
-    let (mut session, new_session) = if connect.clean_session {
+    let (mut session, new_session) = if !connect.clean_session {
         let session_storage = SessionStorage::new(client_pool.clone());
         match session_storage.get_session(client_id.clone()).await {
             Ok(Some(session)) => (session, false),
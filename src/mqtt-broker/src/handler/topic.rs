@@ -133,6 +133,9 @@ where
         message_storage_adapter
             .create_shard(namespace, shard_name, shard_config)
             .await?;
+
+        // Bind the topic to the matching subscriptions right away instead of waiting for the next sweep
+        metadata_cache.notify_topic_created(&topic);
         return Ok(topic);
     };
     Ok(topic)
@@ -61,6 +61,9 @@ impl UpdateUserCache {
                 error!("{}", e);
             }
         };
+        if let Err(e) = self.auth_driver.update_psk_cache().await {
+            error!("Failed to update the pre-shared keys, {}", e);
+        }
         sleep(Duration::from_secs(5)).await;
     }
 }
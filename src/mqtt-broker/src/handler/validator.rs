@@ -27,13 +27,12 @@ use protocol::mqtt::common::{
     LastWillProperties, Login, MqttPacket, MqttProtocol, PubAckReason, PubRecReason, Publish,
     PublishProperties, QoS, Subscribe, SubscribeReasonCode, UnsubAckReason, Unsubscribe,
 };
+use tokio::io::AsyncWrite;
 use tokio_util::codec::FramedWrite;
 
 use super::cache::CacheManager;
 use super::error::MqttBrokerError;
-use super::flow_control::{
-    is_connection_rate_exceeded, is_flow_control, is_subscribe_rate_exceeded,
-};
+use super::flow_control::{is_flow_control, is_subscribe_rate_exceeded};
 use super::pkid::pkid_exists;
 use super::response::{
     response_packet_mqtt_connect_fail, response_packet_mqtt_distinct_by_reason,
@@ -43,14 +42,19 @@ use super::response::{
 use super::topic::topic_name_validator;
 use crate::security::authentication_acl;
 use crate::security::login::is_ip_blacklist;
+use crate::server::connection::{NetworkConnectionType, TlsServerStream};
 use crate::server::connection_manager::ConnectionManager;
 use crate::subscribe::sub_common::sub_path_validator;
 
-pub async fn tcp_establish_connection_check(
+pub async fn establish_connection_check<T>(
     addr: &SocketAddr,
+    network_type: &NetworkConnectionType,
     connection_manager: &Arc<ConnectionManager>,
-    write_frame_stream: &mut FramedWrite<tokio::io::WriteHalf<tokio::net::TcpStream>, MqttCodec>,
-) -> bool {
+    write_frame_stream: &mut FramedWrite<T, MqttCodec>,
+) -> bool
+where
+    T: AsyncWrite + Unpin,
+{
     if connection_manager.tcp_connect_num_check() {
         let packet_wrapper = MqttPacketWrapper {
             protocol_version: MqttProtocol::Mqtt5.into(),
@@ -76,7 +80,7 @@ pub async fn tcp_establish_connection_check(
         return false;
     }
 
-    if is_connection_rate_exceeded() {
+    if connection_manager.connect_rate_check(network_type) {
         let packet_wrapper = MqttPacketWrapper {
             protocol_version: MqttProtocol::Mqtt5.into(),
             packet: response_packet_mqtt_distinct_by_reason(
@@ -103,64 +107,32 @@ pub async fn tcp_establish_connection_check(
     true
 }
 
-pub async fn tcp_tls_establish_connection_check(
+pub async fn tcp_establish_connection_check(
     addr: &SocketAddr,
     connection_manager: &Arc<ConnectionManager>,
-    write_frame_stream: &mut FramedWrite<
-        tokio::io::WriteHalf<tokio_rustls::server::TlsStream<tokio::net::TcpStream>>,
-        MqttCodec,
-    >,
+    write_frame_stream: &mut FramedWrite<tokio::io::WriteHalf<tokio::net::TcpStream>, MqttCodec>,
 ) -> bool {
-    if connection_manager.tcp_connect_num_check() {
-        let packet_wrapper = MqttPacketWrapper {
-            protocol_version: MqttProtocol::Mqtt5.into(),
-            packet: response_packet_mqtt_distinct_by_reason(
-                &MqttProtocol::Mqtt5,
-                Some(DisconnectReasonCode::QuotaExceeded),
-            ),
-        };
-        match write_frame_stream.send(packet_wrapper).await {
-            Ok(_) => {}
-            Err(e) => error!("{}", e),
-        }
-
-        match write_frame_stream.close().await {
-            Ok(_) => {
-                error!(
-                    "tcp connection failed to establish from IP: {}",
-                    addr.to_string()
-                );
-            }
-            Err(e) => error!("{}", e),
-        }
-        return false;
-    }
-
-    if is_connection_rate_exceeded() {
-        let packet_wrapper = MqttPacketWrapper {
-            protocol_version: MqttProtocol::Mqtt5.into(),
-            packet: response_packet_mqtt_distinct_by_reason(
-                &MqttProtocol::Mqtt5,
-                Some(DisconnectReasonCode::ConnectionRateExceeded),
-            ),
-        };
-        match write_frame_stream.send(packet_wrapper).await {
-            Ok(_) => {}
-            Err(e) => error!("{}", e),
-        }
+    establish_connection_check(
+        addr,
+        &NetworkConnectionType::Tcp,
+        connection_manager,
+        write_frame_stream,
+    )
+    .await
+}
 
-        match write_frame_stream.close().await {
-            Ok(_) => {
-                error!(
-                    "tcp connection failed to establish from IP: {}",
-                    addr.to_string()
-                );
-            }
-            Err(e) => error!("{}", e),
-        }
-        return false;
-    }
-    true
+pub async fn tcp_tls_establish_connection_check(
+    addr: &SocketAddr,
+    connection_manager: &Arc<ConnectionManager>,
+    write_frame_stream: &mut FramedWrite<tokio::io::WriteHalf<TlsServerStream>, MqttCodec>,
+) -> bool {
+    establish_connection_check(
+        addr,
+        &NetworkConnectionType::Tls,
+        connection_manager,
+        write_frame_stream,
+    )
+    .await
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -445,7 +417,7 @@ pub async fn publish_validator(
 
 pub async fn subscribe_validator(
     protocol: &MqttProtocol,
-    _cache_manager: &Arc<CacheManager>,
+    cache_manager: &Arc<CacheManager>,
     _client_pool: &Arc<ClientPool>,
     connection: &MQTTConnection,
     subscribe: &Subscribe,
@@ -507,7 +479,7 @@ pub async fn subscribe_validator(
         ));
     }
 
-    if is_subscribe_rate_exceeded() {
+    if is_subscribe_rate_exceeded(cache_manager, &connection.client_id) {
         return Some(response_packet_mqtt_suback(
             protocol,
             connection,
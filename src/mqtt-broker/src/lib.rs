@@ -12,38 +12,45 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
+use bridge::manager::BridgeManager;
 use common_base::config::broker_mqtt::broker_mqtt_conf;
 use common_base::runtime::create_runtime;
 use common_base::tools::now_second;
 use grpc_clients::pool::ClientPool;
 use handler::acl::UpdateAclCache;
 use handler::cache::CacheManager;
+use handler::flow_control::start_rate_limiter_sweep;
 use handler::heartbreat::{register_node, report_heartbeat};
 use handler::keep_alive::ClientKeepAlive;
 use handler::user::UpdateUserCache;
 use lazy_static::lazy_static;
 use log::{error, info};
 use observability::start_opservability;
+use rule::engine::RuleEngine;
 use security::AuthDriver;
 use server::connection_manager::ConnectionManager;
 use server::grpc::server::GrpcServer;
 use server::http::server::{start_http_server, HttpServerState};
+use server::quic::server::start_quic_server;
 use server::tcp::server::start_tcp_server;
 use server::websocket::server::{websocket_server, websockets_server, WebSocketServerState};
 use storage::cluster::ClusterStorage;
+use storage_adapter::journal::JournalStorageAdapter;
 use storage_adapter::memory::MemoryStorageAdapter;
-// use storage_adapter::mysql::MySQLStorageAdapter;
-// use storage_adapter::rocksdb::RocksDBStorageAdapter;
+use storage_adapter::mysql::MySQLStorageAdapter;
+use storage_adapter::placement::PlacementStorageAdapter;
+use storage_adapter::rocksdb::RocksDBStorageAdapter;
 use storage_adapter::storage::StorageAdapter;
-use storage_adapter::StorageType;
+use storage_adapter::{parse_addrs, validate_storage_config, StorageType};
+use subscribe::offline::OfflineMessageManager;
 use subscribe::sub_exclusive::SubscribeExclusive;
 use subscribe::sub_share_follower::SubscribeShareFollower;
 use subscribe::sub_share_leader::SubscribeShareLeader;
 use subscribe::subscribe_manager::SubscribeManager;
+use third_driver::mysql::build_mysql_conn_pool;
 use tokio::runtime::Runtime;
 use tokio::signal;
 use tokio::sync::broadcast::{self};
@@ -53,8 +60,10 @@ lazy_static! {
     pub static ref BROKER_START_TIME: u64 = now_second();
 }
 
+pub mod bridge;
 pub mod handler;
 pub mod observability;
+pub mod rule;
 pub mod security;
 mod server;
 pub mod storage;
@@ -67,38 +76,60 @@ pub fn start_mqtt_broker_server(stop_send: broadcast::Sender<bool>) {
         client_pool.clone(),
         conf.cluster_name.clone(),
     ));
-    // let storage_type = conf.storage.storage_type.clone();
-    let storage_type = StorageType::from_str(conf.storage.storage_type.as_str())
-        .expect("Storage type not supported");
+    let storage_type = match validate_storage_config(&conf.storage) {
+        Ok(storage_type) => storage_type,
+        Err(e) => {
+            panic!("Message data storage configuration error, {}", e);
+        }
+    };
     match storage_type {
         StorageType::Memory => {
             let message_storage_adapter = Arc::new(MemoryStorageAdapter::new());
             let server = MqttBroker::new(client_pool, message_storage_adapter, metadata_cache);
             server.start(stop_send);
         }
-        // StorageType::Mysql => {
-        //     if conf.storage.mysql_addr.is_empty() {
-        //         panic!("storaget type is [mysql],[storage.mysql_addr] cannot be empty");
-        //     }
-        //     let pool = build_mysql_conn_pool(&conf.storage.mysql_addr).unwrap();
-        //     let message_storage_adapter = Arc::new(MySQLStorageAdapter::new(pool.clone()));
-        //     let server: MqttBroker<MySQLStorageAdapter> =
-        //         MqttBroker::new(client_pool, message_storage_adapter, metadata_cache);
-        //     server.start(stop_send);
-        // }
-        // StorageType::RocksDB => {
-        //     if conf.storage.rocksdb_data_path.is_empty() {
-        //         panic!("storaget type is [rocksdb],[storage.rocksdb_path] cannot be empty");
-        //     }
-        //     let message_storage_adapter = Arc::new(RocksDBStorageAdapter::new(
-        //         conf.storage.rocksdb_data_path.as_str(),
-        //         conf.storage.rocksdb_max_open_files.unwrap_or(10000),
-        //     ));
-        //     let server = MqttBroker::new(client_pool, message_storage_adapter, metadata_cache);
-        //     server.start(stop_send);
-        // }
-        _ => {
-            panic!("Message data storage type configuration error, optional :mysql, memory");
+        StorageType::Journal => {
+            let message_storage_adapter = Arc::new(JournalStorageAdapter::new(
+                client_pool.clone(),
+                conf.cluster_name.clone(),
+                parse_addrs(&conf.storage.journal_addr),
+                conf.placement_center.clone(),
+            ));
+            let server = MqttBroker::new(client_pool, message_storage_adapter, metadata_cache);
+            server.start(stop_send);
+        }
+        StorageType::Mysql => {
+            let message_storage_adapter = match build_mysql_conn_pool(&conf.storage.mysql_addr)
+                .and_then(MySQLStorageAdapter::new)
+            {
+                Ok(adapter) => Arc::new(adapter),
+                Err(e) => {
+                    panic!("Failed to initialize MySQL message storage, {}", e);
+                }
+            };
+            let server = MqttBroker::new(client_pool, message_storage_adapter, metadata_cache);
+            server.start(stop_send);
+        }
+        StorageType::RocksDB => {
+            let message_storage_adapter = Arc::new(RocksDBStorageAdapter::new(
+                conf.storage.rocksdb_data_path.as_str(),
+                conf.storage.rocksdb_max_open_files.unwrap_or(10000),
+            ));
+            let server = MqttBroker::new(client_pool, message_storage_adapter, metadata_cache);
+            server.start(stop_send);
+        }
+        StorageType::Placement => {
+            let message_storage_adapter = Arc::new(PlacementStorageAdapter::new(
+                client_pool.clone(),
+                conf.cluster_name.clone(),
+                conf.placement_center.clone(),
+                conf.storage.placement.clone(),
+            ));
+            let server = MqttBroker::new(client_pool, message_storage_adapter, metadata_cache);
+            server.start(stop_send);
+        }
+        StorageType::Redis => {
+            unreachable!("rejected by validate_storage_config")
         }
     }
 }
@@ -111,6 +142,8 @@ pub struct MqttBroker<S> {
     subscribe_manager: Arc<SubscribeManager>,
     connection_manager: Arc<ConnectionManager>,
     auth_driver: Arc<AuthDriver>,
+    bridge_manager: Arc<BridgeManager<S>>,
+    rule_engine: Arc<RuleEngine<S>>,
 }
 
 impl<S> MqttBroker<S>
@@ -136,6 +169,20 @@ where
         let connection_manager = Arc::new(ConnectionManager::new(cache_manager.clone()));
 
         let auth_driver = Arc::new(AuthDriver::new(cache_manager.clone(), client_pool.clone()));
+
+        let bridge_manager = Arc::new(BridgeManager::new(
+            client_pool.clone(),
+            cache_manager.clone(),
+            message_storage_adapter.clone(),
+        ));
+
+        let rule_engine = Arc::new(RuleEngine::new(
+            client_pool.clone(),
+            cache_manager.clone(),
+            message_storage_adapter.clone(),
+            bridge_manager.clone(),
+            auth_driver.clone(),
+        ));
         MqttBroker {
             runtime,
             cache_manager,
@@ -144,6 +191,8 @@ where
             subscribe_manager,
             connection_manager,
             auth_driver,
+            bridge_manager,
+            rule_engine,
         }
     }
 
@@ -151,14 +200,19 @@ where
         self.register_node();
         self.start_grpc_server();
         self.start_mqtt_server(stop_send.clone());
+        self.start_quic_server(stop_send.clone());
         self.start_http_server();
         self.start_websocket_server(stop_send.clone());
         self.start_keep_alive_thread(stop_send.clone());
+        self.start_rate_limiter_sweep_thread(stop_send.clone());
         self.start_cluster_heartbeat_report(stop_send.clone());
         self.start_update_user_cache_thread(stop_send.clone());
         self.start_update_acl_cache_thread(stop_send.clone());
         self.start_push_server();
         self.start_system_topic_thread(stop_send.clone());
+        self.start_bridge_thread(stop_send.clone());
+        self.start_rule_engine_thread(stop_send.clone());
+        self.start_offline_message_thread(stop_send.clone());
         self.awaiting_stop(stop_send);
     }
 
@@ -169,6 +223,7 @@ where
         let client_pool = self.client_pool.clone();
         let connection_manager = self.connection_manager.clone();
         let auth_driver = self.auth_driver.clone();
+        let rule_engine = self.rule_engine.clone();
 
         self.runtime.spawn(async move {
             start_tcp_server(
@@ -179,6 +234,31 @@ where
                 client_pool,
                 stop_send,
                 auth_driver,
+                rule_engine,
+            )
+            .await
+        });
+    }
+
+    fn start_quic_server(&self, stop_send: broadcast::Sender<bool>) {
+        let cache = self.cache_manager.clone();
+        let message_storage_adapter = self.message_storage_adapter.clone();
+        let subscribe_manager = self.subscribe_manager.clone();
+        let client_pool = self.client_pool.clone();
+        let connection_manager = self.connection_manager.clone();
+        let auth_driver = self.auth_driver.clone();
+        let rule_engine = self.rule_engine.clone();
+
+        self.runtime.spawn(async move {
+            start_quic_server(
+                subscribe_manager,
+                cache,
+                connection_manager,
+                message_storage_adapter,
+                client_pool,
+                stop_send,
+                auth_driver,
+                rule_engine,
             )
             .await
         });
@@ -224,6 +304,7 @@ where
             self.message_storage_adapter.clone(),
             self.client_pool.clone(),
             self.auth_driver.clone(),
+            self.rule_engine.clone(),
             stop_send.clone(),
         );
         self.runtime
@@ -236,6 +317,7 @@ where
             self.message_storage_adapter.clone(),
             self.client_pool.clone(),
             self.auth_driver.clone(),
+            self.rule_engine.clone(),
             stop_send.clone(),
         );
 
@@ -303,6 +385,13 @@ where
         });
     }
 
+    fn start_rate_limiter_sweep_thread(&self, stop_send: broadcast::Sender<bool>) {
+        let cache_manager = self.cache_manager.clone();
+        self.runtime.spawn(async move {
+            start_rate_limiter_sweep(cache_manager, stop_send).await;
+        });
+    }
+
     fn start_update_user_cache_thread(&self, stop_send: broadcast::Sender<bool>) {
         let update_user_cache = UpdateUserCache::new(stop_send, self.auth_driver.clone());
 
@@ -334,6 +423,37 @@ where
         });
     }
 
+    fn start_bridge_thread(&self, stop_send: broadcast::Sender<bool>) {
+        let bridge_manager = self.bridge_manager.clone();
+        self.runtime.spawn(async move {
+            bridge_manager.start(stop_send).await;
+        });
+    }
+
+    fn start_rule_engine_thread(&self, stop_send: broadcast::Sender<bool>) {
+        let rule_engine = self.rule_engine.clone();
+        let action_stop_send = stop_send.clone();
+        self.runtime.spawn(async move {
+            rule_engine.start(stop_send).await;
+        });
+
+        let rule_engine = self.rule_engine.clone();
+        self.runtime.spawn(async move {
+            rule_engine.start_action_thread(action_stop_send).await;
+        });
+    }
+
+    fn start_offline_message_thread(&self, stop_send: broadcast::Sender<bool>) {
+        let offline_message_manager = OfflineMessageManager::new(
+            self.cache_manager.clone(),
+            self.connection_manager.clone(),
+            self.message_storage_adapter.clone(),
+        );
+        self.runtime.spawn(async move {
+            offline_message_manager.start(stop_send).await;
+        });
+    }
+
     pub fn awaiting_stop(&self, stop_send: broadcast::Sender<bool>) {
         self.runtime.spawn(async move {
             sleep(Duration::from_millis(5)).await;
@@ -392,5 +512,14 @@ where
             }
         }
         self.connection_manager.close_all_connect().await;
+
+        match self.message_storage_adapter.close().await {
+            Ok(()) => {
+                info!("Message storage adapter closed successfully");
+            }
+            Err(e) => {
+                error!("Failed to close message storage adapter, {}", e);
+            }
+        }
     }
 }
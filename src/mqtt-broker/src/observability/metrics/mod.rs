@@ -16,5 +16,6 @@ pub mod auth;
 pub mod events;
 pub mod packets;
 pub mod publish;
+pub mod rule;
 pub mod server;
 pub mod session;
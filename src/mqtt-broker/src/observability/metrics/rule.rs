@@ -0,0 +1,35 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use lazy_static::lazy_static;
+use prometheus::{register_int_gauge_vec, IntGaugeVec};
+
+use crate::handler::constant::METRICS_KEY_RULE_NAME;
+
+lazy_static! {
+    // Number of rule actions discarded because the action queue was full
+    static ref RULE_ACTION_DROPPED: IntGaugeVec = register_int_gauge_vec!(
+        "rule_action_dropped",
+        "Number of rule actions discarded because the action queue was full",
+        &[METRICS_KEY_RULE_NAME]
+    )
+    .unwrap();
+}
+
+pub fn record_rule_action_dropped(rule_name: &str) {
+    RULE_ACTION_DROPPED.with_label_values(&[rule_name]).inc();
+}
+
+pub fn rule_action_dropped(rule_name: &str) -> i64 {
+    RULE_ACTION_DROPPED.with_label_values(&[rule_name]).get()
+}
@@ -0,0 +1,943 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use common_base::tools::now_second;
+use dashmap::DashMap;
+use grpc_clients::pool::ClientPool;
+use log::{error, info, warn};
+use metadata_struct::adapter::record::Header;
+use metadata_struct::mqtt::connection::MQTTConnection;
+use metadata_struct::mqtt::message::MqttMessage;
+use metadata_struct::mqtt::rule::{MqttRule, MqttRuleAction};
+use protocol::mqtt::common::{qos, Publish};
+use serde_json::{json, Value};
+use storage_adapter::storage::StorageAdapter;
+use tokio::select;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{broadcast, mpsc, Mutex};
+use tokio::time::sleep;
+
+use crate::bridge::manager::BridgeManager;
+use crate::bridge::{build_dead_letter_record, write_dead_letter};
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::handler::message::build_message_expire;
+use crate::handler::retain::save_retain_message;
+use crate::handler::topic::{topic_name_validator, try_init_topic};
+use crate::observability::metrics::rule::record_rule_action_dropped;
+use crate::rule::sql::RuleSql;
+use crate::security::AuthDriver;
+use crate::storage::message::MessageStorage;
+use crate::storage::rule::RuleStorage;
+
+const SYNC_RULE_INTERVAL_SEC: u64 = 5;
+const RULE_ACTION_QUEUE_SIZE: usize = 1000;
+const BRIDGE_FORWARD_RETRY_TIMES: u64 = 3;
+const BRIDGE_FORWARD_RETRY_INTERVAL_MS: u64 = 100;
+
+pub struct RuleInfo {
+    pub rule: MqttRule,
+    pub sql: RuleSql,
+}
+
+// An action of a matching rule, waiting in the action queue.
+pub struct RuleActionTask {
+    pub rule_name: String,
+    pub action: MqttRuleAction,
+    // The publishing connection, republished messages are checked against its ACL
+    pub connection: MQTTConnection,
+    pub publish: Publish,
+    pub output: Value,
+}
+
+// Rules are stored in the placement center and synchronized periodically, every
+// broker evaluates them against the messages published to it. Only the drop action
+// takes effect before the publisher is acknowledged, the other actions are put in
+// a bounded queue and run by the action thread, so a slow target never delays the
+// acknowledgement. When the queue is full the actions are discarded and counted
+// in the rule_action_dropped metric.
+//
+// A bridge forward that keeps failing is written to the dead-letter shard of the
+// bridge. Republished messages must pass the publish ACL of the publishing client,
+// they are written to storage directly and are not evaluated by the rules again,
+// so a rule cannot trigger itself.
+pub struct RuleEngine<S> {
+    client_pool: Arc<ClientPool>,
+    cache_manager: Arc<CacheManager>,
+    message_storage_adapter: Arc<S>,
+    bridge_manager: Arc<BridgeManager<S>>,
+    auth_driver: Arc<AuthDriver>,
+    // (rule_name, RuleInfo)
+    rules: DashMap<String, Arc<RuleInfo>>,
+    action_sender: mpsc::Sender<RuleActionTask>,
+    // Taken by the action thread when it starts
+    action_receiver: Mutex<Option<mpsc::Receiver<RuleActionTask>>>,
+}
+
+impl<S> RuleEngine<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(
+        client_pool: Arc<ClientPool>,
+        cache_manager: Arc<CacheManager>,
+        message_storage_adapter: Arc<S>,
+        bridge_manager: Arc<BridgeManager<S>>,
+        auth_driver: Arc<AuthDriver>,
+    ) -> Self {
+        let (action_sender, action_receiver) = mpsc::channel(RULE_ACTION_QUEUE_SIZE);
+        RuleEngine {
+            client_pool,
+            cache_manager,
+            message_storage_adapter,
+            bridge_manager,
+            auth_driver,
+            rules: DashMap::with_capacity(2),
+            action_sender,
+            action_receiver: Mutex::new(Some(action_receiver)),
+        }
+    }
+
+    pub fn add_rule(&self, rule: MqttRule) -> Result<(), MqttBrokerError> {
+        let sql = RuleSql::parse(&rule.sql)?;
+        self.rules
+            .insert(rule.name.clone(), Arc::new(RuleInfo { rule, sql }));
+        Ok(())
+    }
+
+    pub async fn sync_rules(&self) -> Result<(), MqttBrokerError> {
+        let storage = RuleStorage::new(self.client_pool.clone());
+        let rules = storage.list_rule().await?;
+
+        let names: HashSet<String> = rules.iter().map(|rule| rule.name.clone()).collect();
+        self.rules.retain(|name, _| {
+            if names.contains(name) {
+                return true;
+            }
+            info!("Rule [{}] was removed", name);
+            false
+        });
+
+        for rule in rules {
+            let unchanged = if let Some(info) = self.rules.get(&rule.name) {
+                info.rule == rule
+            } else {
+                false
+            };
+            if unchanged {
+                continue;
+            }
+
+            let name = rule.name.clone();
+            match self.add_rule(rule) {
+                Ok(()) => {
+                    info!("Rule [{}] was loaded", name);
+                }
+                Err(e) => {
+                    error!("Rule [{}] failed to compile, {}", name, e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn start(&self, stop_send: broadcast::Sender<bool>) {
+        let mut stop_rx = stop_send.subscribe();
+        loop {
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("{}","Rule engine thread stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                _ = self.sync_rules_round()=>{
+                }
+            }
+        }
+    }
+
+    async fn sync_rules_round(&self) {
+        if let Err(e) = self.sync_rules().await {
+            error!("Failed to synchronize rules, {}", e);
+        }
+        sleep(Duration::from_secs(SYNC_RULE_INTERVAL_SEC)).await;
+    }
+
+    pub async fn start_action_thread(&self, stop_send: broadcast::Sender<bool>) {
+        let mut action_receiver = match self.action_receiver.lock().await.take() {
+            Some(receiver) => receiver,
+            None => {
+                error!("{}", "Rule action thread is already running.");
+                return;
+            }
+        };
+
+        let mut stop_rx = stop_send.subscribe();
+        loop {
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("{}","Rule action thread stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                val = action_receiver.recv()=>{
+                    match val {
+                        Some(task) => self.run_task(task).await,
+                        None => break,
+                    }
+                }
+            }
+        }
+    }
+
+    // Evaluates the rules matching the topic of a published message and queues the
+    // actions of the matching ones. Returns true when a matching rule drops the message.
+    pub async fn apply(
+        &self,
+        connection: &MQTTConnection,
+        topic_name: &str,
+        publish: &Publish,
+    ) -> bool {
+        if self.rules.is_empty() {
+            return false;
+        }
+
+        let rules: Vec<Arc<RuleInfo>> = self
+            .rules
+            .iter()
+            .filter(|raw| raw.sql.is_match(topic_name))
+            .map(|raw| raw.clone())
+            .collect();
+        if rules.is_empty() {
+            return false;
+        }
+
+        let context = build_context(connection, topic_name, publish);
+        let mut drop = false;
+        for info in rules {
+            let output = match info.sql.evaluate(&context) {
+                Some(output) => output,
+                None => continue,
+            };
+
+            for action in info.rule.actions.iter() {
+                if *action == MqttRuleAction::Drop {
+                    drop = true;
+                    continue;
+                }
+
+                let task = RuleActionTask {
+                    rule_name: info.rule.name.clone(),
+                    action: action.clone(),
+                    connection: connection.clone(),
+                    publish: publish.clone(),
+                    output: output.clone(),
+                };
+                match self.action_sender.try_send(task) {
+                    Ok(()) => {}
+                    Err(TrySendError::Full(task)) => {
+                        record_rule_action_dropped(&task.rule_name);
+                        warn!(
+                            "Rule action queue is full, action {:?} of rule [{}] was discarded",
+                            task.action, task.rule_name
+                        );
+                    }
+                    Err(TrySendError::Closed(task)) => {
+                        error!(
+                            "Rule action queue is closed, action {:?} of rule [{}] was discarded",
+                            task.action, task.rule_name
+                        );
+                    }
+                }
+            }
+        }
+        drop
+    }
+
+    async fn run_task(&self, task: RuleActionTask) {
+        let result = match &task.action {
+            MqttRuleAction::Republish { topic, qos, retain } => {
+                self.republish(&task.connection, topic, *qos, *retain, &task.output)
+                    .await
+            }
+            MqttRuleAction::Bridge { bridge_name } => self.forward(bridge_name, &task).await,
+            MqttRuleAction::Drop => Ok(()),
+        };
+        if let Err(e) = result {
+            error!(
+                "Rule [{}] failed to run action {:?}, {}",
+                task.rule_name, task.action, e
+            );
+        }
+    }
+
+    // Retries the forward with an increasing interval, then gives the message up
+    // to the dead-letter shard of the bridge.
+    async fn forward(
+        &self,
+        bridge_name: &str,
+        task: &RuleActionTask,
+    ) -> Result<(), MqttBrokerError> {
+        let publish = Publish {
+            payload: Bytes::from(serde_json::to_vec(&task.output)?),
+            ..task.publish.clone()
+        };
+        let expire = build_message_expire(&self.cache_manager, &None);
+        let message =
+            MqttMessage::build_message(&task.connection.client_id, &publish, &None, expire);
+
+        let mut times = 0;
+        let reason = loop {
+            match self
+                .bridge_manager
+                .forward(bridge_name, &[message.clone()])
+                .await
+            {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    times += 1;
+                    if times > BRIDGE_FORWARD_RETRY_TIMES {
+                        break e.to_string();
+                    }
+                    sleep(Duration::from_millis(
+                        BRIDGE_FORWARD_RETRY_INTERVAL_MS * times,
+                    ))
+                    .await;
+                }
+            }
+        };
+
+        warn!(
+            "Rule [{}] failed to forward a message to bridge [{}], it was written to the dead-letter shard, {}",
+            task.rule_name, bridge_name, reason
+        );
+        let record = build_dead_letter_record(
+            bridge_name,
+            &message,
+            vec![
+                Header {
+                    name: "rule".to_string(),
+                    value: task.rule_name.clone(),
+                },
+                Header {
+                    name: "reason".to_string(),
+                    value: reason,
+                },
+            ],
+        );
+        write_dead_letter(&self.message_storage_adapter, bridge_name, vec![record]).await
+    }
+
+    async fn republish(
+        &self,
+        connection: &MQTTConnection,
+        topic_template: &str,
+        qos_num: u8,
+        retain: bool,
+        output: &Value,
+    ) -> Result<(), MqttBrokerError> {
+        let topic_name = render_topic(topic_template, output);
+        topic_name_validator(&topic_name)?;
+        if topic_name.contains(['+', '#']) {
+            return Err(MqttBrokerError::TopicNameIncorrectlyFormatted(topic_name));
+        }
+        let qos = match qos(qos_num) {
+            Some(qos) => qos,
+            None => {
+                return Err(MqttBrokerError::CommonError(format!(
+                    "Invalid republish qos {}",
+                    qos_num
+                )));
+            }
+        };
+        if !self
+            .auth_driver
+            .allow_publish(connection, &topic_name, retain, qos)
+            .await
+        {
+            return Err(MqttBrokerError::PublishNotAuthorized(
+                connection.client_id.clone(),
+                topic_name,
+            ));
+        }
+        let client_id = &connection.client_id;
+
+        let publish = Publish {
+            dup: false,
+            qos,
+            pkid: 0,
+            retain,
+            topic: Bytes::from(topic_name.clone()),
+            payload: Bytes::from(serde_json::to_vec(output)?),
+        };
+        let topic = try_init_topic(
+            &topic_name,
+            &self.cache_manager,
+            &self.message_storage_adapter,
+            &self.client_pool,
+        )
+        .await?;
+        save_retain_message(
+            &self.cache_manager,
+            &self.client_pool,
+            topic_name,
+            client_id,
+            &publish,
+            &None,
+        )
+        .await?;
+
+        let expire = build_message_expire(&self.cache_manager, &None);
+        if let Some(record) = MqttMessage::build_record(client_id, &publish, &None, expire) {
+            let message_storage = MessageStorage::new(self.message_storage_adapter.clone());
+            message_storage
+                .append_topic_message(&topic.topic_id, vec![record])
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+// The columns a rule can select from. A payload that is not JSON is selected as a string.
+fn build_context(connection: &MQTTConnection, topic_name: &str, publish: &Publish) -> Value {
+    let payload = match serde_json::from_slice::<Value>(&publish.payload) {
+        Ok(payload) => payload,
+        Err(_) => Value::String(String::from_utf8_lossy(&publish.payload).to_string()),
+    };
+    json!({
+        "clientid": connection.client_id,
+        "username": connection.login_user,
+        "topic": topic_name,
+        "qos": publish.qos as u8,
+        "retain": publish.retain,
+        "payload": payload,
+        "timestamp": now_second(),
+    })
+}
+
+// Replaces ${field} with a selected field, strings are inserted without quotes.
+fn render_topic(template: &str, output: &Value) -> String {
+    let mut topic = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("${") {
+        let end = match rest[start..].find('}') {
+            Some(len) => start + len,
+            None => break,
+        };
+        topic.push_str(&rest[..start]);
+        match output.get(&rest[start + 2..end]) {
+            Some(Value::String(value)) => topic.push_str(value),
+            Some(Value::Null) | None => {}
+            Some(value) => topic.push_str(&value.to_string()),
+        }
+        rest = &rest[end + 1..];
+    }
+    topic.push_str(rest);
+    topic
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::async_trait;
+    use bytes::Bytes;
+    use common_base::config::broker_mqtt::{init_broker_mqtt_conf_by_config, BrokerMqttConfig};
+    use common_base::config::common::Auth;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::acl::mqtt_acl::{
+        MqttAcl, MqttAclAction, MqttAclPermission, MqttAclResourceType,
+    };
+    use metadata_struct::adapter::read_config::ReadConfig;
+    use metadata_struct::mqtt::bridge::MqttBridgeRule;
+    use metadata_struct::mqtt::connection::MQTTConnection;
+    use metadata_struct::mqtt::message::MqttMessage;
+    use metadata_struct::mqtt::rule::{MqttRule, MqttRuleAction};
+    use metadata_struct::mqtt::topic::MqttTopic;
+    use protocol::mqtt::common::{Publish, QoS};
+    use serde_json::json;
+    use storage_adapter::memory::MemoryStorageAdapter;
+    use storage_adapter::storage::StorageAdapter;
+    use tokio::sync::Mutex;
+
+    use super::{render_topic, RuleEngine, RULE_ACTION_QUEUE_SIZE};
+    use crate::bridge::manager::{BridgeInfo, BridgeManager};
+    use crate::bridge::{dead_letter_shard_name, Connector};
+    use crate::handler::cache::CacheManager;
+    use crate::handler::error::MqttBrokerError;
+    use crate::handler::retain::apply_rules_and_save_retain;
+    use crate::observability::metrics::rule::rule_action_dropped;
+    use crate::security::AuthDriver;
+    use crate::storage::message::MessageStorage;
+
+    #[derive(Default)]
+    struct MemoryConnector {
+        messages: Mutex<Vec<MqttMessage>>,
+    }
+
+    #[async_trait]
+    impl Connector for MemoryConnector {
+        async fn start(&self) -> Result<(), MqttBrokerError> {
+            Ok(())
+        }
+
+        async fn stop(&self) -> Result<(), MqttBrokerError> {
+            Ok(())
+        }
+
+        async fn write_batch(&self, messages: &[MqttMessage]) -> Result<(), MqttBrokerError> {
+            self.messages.lock().await.extend_from_slice(messages);
+            Ok(())
+        }
+
+        async fn health(&self) -> Result<(), MqttBrokerError> {
+            Ok(())
+        }
+    }
+
+    struct FailingConnector {}
+
+    #[async_trait]
+    impl Connector for FailingConnector {
+        async fn start(&self) -> Result<(), MqttBrokerError> {
+            Ok(())
+        }
+
+        async fn stop(&self) -> Result<(), MqttBrokerError> {
+            Ok(())
+        }
+
+        async fn write_batch(&self, _: &[MqttMessage]) -> Result<(), MqttBrokerError> {
+            Err(MqttBrokerError::CommonError("unavailable".to_string()))
+        }
+
+        async fn health(&self) -> Result<(), MqttBrokerError> {
+            Ok(())
+        }
+    }
+
+    fn build_auth_driver(
+        cache_manager: &Arc<CacheManager>,
+        client_pool: &Arc<ClientPool>,
+    ) -> Arc<AuthDriver> {
+        let auth = Auth {
+            storage_type: "placement".to_string(),
+            password_hash: "plain".to_string(),
+            ..Default::default()
+        };
+        Arc::new(AuthDriver::new_with_auth(
+            cache_manager.clone(),
+            client_pool.clone(),
+            auth,
+        ))
+    }
+
+    // Runs the queued actions the way the action thread does.
+    async fn run_queued_actions(engine: &RuleEngine<MemoryStorageAdapter>) {
+        let mut receiver = engine.action_receiver.lock().await;
+        let receiver = receiver.as_mut().unwrap();
+        while let Ok(task) = receiver.try_recv() {
+            engine.run_task(task).await;
+        }
+    }
+
+    #[test]
+    fn render_topic_test() {
+        let output = json!({"clientid": "c1", "t": 85, "missing": null});
+        assert_eq!(
+            render_topic("alerts/${clientid}/${t}", &output),
+            "alerts/c1/85"
+        );
+        assert_eq!(render_topic("alerts/${missing}x", &output), "alerts/x");
+        assert_eq!(
+            render_topic("alerts/${clientid", &output),
+            "alerts/${clientid"
+        );
+    }
+
+    #[tokio::test]
+    async fn apply_rule_test() {
+        init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            cluster_name: "test".to_string(),
+            ..Default::default()
+        });
+
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool.clone(), "test".to_string()));
+        let storage_adapter = Arc::new(MemoryStorageAdapter::new());
+        let bridge_manager = Arc::new(BridgeManager::new(
+            client_pool.clone(),
+            cache_manager.clone(),
+            storage_adapter.clone(),
+        ));
+        let connector = Arc::new(MemoryConnector::default());
+        bridge_manager
+            .add_bridge(BridgeInfo {
+                rule: MqttBridgeRule {
+                    name: "b1".to_string(),
+                    ..Default::default()
+                },
+                connector: connector.clone(),
+            })
+            .unwrap();
+
+        // The republish target exists, so no placement center is needed.
+        let alert_topic = MqttTopic::new(
+            "t1".to_string(),
+            "test".to_string(),
+            "alerts/c1".to_string(),
+        );
+        cache_manager.add_topic("alerts/c1", &alert_topic);
+
+        let auth_driver = build_auth_driver(&cache_manager, &client_pool);
+        let engine = RuleEngine::new(
+            client_pool,
+            cache_manager,
+            storage_adapter.clone(),
+            bridge_manager,
+            auth_driver,
+        );
+        engine
+            .add_rule(MqttRule {
+                name: "high_temp".to_string(),
+                sql: r#"SELECT payload.temp AS t, clientid FROM "sensors/+/data" WHERE payload.temp > 80"#
+                    .to_string(),
+                actions: vec![
+                    MqttRuleAction::Republish {
+                        topic: "alerts/${clientid}".to_string(),
+                        qos: 1,
+                        retain: false,
+                    },
+                    MqttRuleAction::Bridge {
+                        bridge_name: "b1".to_string(),
+                    },
+                    MqttRuleAction::Drop,
+                ],
+                create_time: 0,
+            })
+            .unwrap();
+
+        let connection = MQTTConnection {
+            client_id: "c1".to_string(),
+            ..Default::default()
+        };
+        let publish = |payload: &str| Publish {
+            qos: QoS::AtLeastOnce,
+            topic: Bytes::from("sensors/1/data"),
+            payload: Bytes::from(payload.to_string()),
+            ..Default::default()
+        };
+
+        // not matching the WHERE clause or the topic filter
+        assert!(
+            !engine
+                .apply(&connection, "sensors/1/data", &publish(r#"{"temp":20}"#))
+                .await
+        );
+        assert!(
+            !engine
+                .apply(&connection, "sensors/1/status", &publish(r#"{"temp":90}"#))
+                .await
+        );
+        run_queued_actions(&engine).await;
+        assert!(connector.messages.lock().await.is_empty());
+
+        assert!(
+            engine
+                .apply(&connection, "sensors/1/data", &publish(r#"{"temp":90}"#))
+                .await
+        );
+        // the actions only run once the action thread takes them from the queue
+        assert!(connector.messages.lock().await.is_empty());
+        run_queued_actions(&engine).await;
+
+        let expected = json!({"t": 90, "clientid": "c1"});
+        let messages = connector.messages.lock().await;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].topic, Bytes::from("sensors/1/data"));
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&messages[0].payload).unwrap(),
+            expected
+        );
+
+        let message_storage = MessageStorage::new(storage_adapter);
+        let records = message_storage
+            .read_topic_message("t1", 0, 10)
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        let message = MqttMessage::decode_record(records[0].clone()).unwrap();
+        assert_eq!(message.topic, Bytes::from("alerts/c1"));
+        assert_eq!(message.qos, QoS::AtLeastOnce);
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&message.payload).unwrap(),
+            expected
+        );
+    }
+
+    #[tokio::test]
+    async fn forward_dead_letter_test() {
+        init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            cluster_name: "test".to_string(),
+            ..Default::default()
+        });
+
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool.clone(), "test".to_string()));
+        let storage_adapter = Arc::new(MemoryStorageAdapter::new());
+        let bridge_manager = Arc::new(BridgeManager::new(
+            client_pool.clone(),
+            cache_manager.clone(),
+            storage_adapter.clone(),
+        ));
+        bridge_manager
+            .add_bridge(BridgeInfo {
+                rule: MqttBridgeRule {
+                    name: "b1".to_string(),
+                    ..Default::default()
+                },
+                connector: Arc::new(FailingConnector {}),
+            })
+            .unwrap();
+
+        let auth_driver = build_auth_driver(&cache_manager, &client_pool);
+        let engine = RuleEngine::new(
+            client_pool,
+            cache_manager,
+            storage_adapter.clone(),
+            bridge_manager,
+            auth_driver,
+        );
+        engine
+            .add_rule(MqttRule {
+                name: "forward".to_string(),
+                sql: r#"SELECT payload FROM "sensors/#""#.to_string(),
+                actions: vec![MqttRuleAction::Bridge {
+                    bridge_name: "b1".to_string(),
+                }],
+                create_time: 0,
+            })
+            .unwrap();
+
+        let connection = MQTTConnection {
+            client_id: "c1".to_string(),
+            ..Default::default()
+        };
+        let publish = Publish {
+            topic: Bytes::from("sensors/1/data"),
+            payload: Bytes::from(r#"{"temp":90}"#),
+            ..Default::default()
+        };
+        assert!(!engine.apply(&connection, "sensors/1/data", &publish).await);
+        run_queued_actions(&engine).await;
+
+        let records = storage_adapter
+            .read_by_offset(
+                "test".to_string(),
+                dead_letter_shard_name("b1"),
+                0,
+                ReadConfig::new(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].header[0].value, "forward".to_string());
+
+        let message = MqttMessage::decode_record(records[0].clone()).unwrap();
+        assert_eq!(message.client_id, "c1".to_string());
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&message.payload).unwrap(),
+            json!({"payload": {"temp": 90}})
+        );
+    }
+
+    #[tokio::test]
+    async fn action_queue_full_test() {
+        init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            cluster_name: "test".to_string(),
+            ..Default::default()
+        });
+
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool.clone(), "test".to_string()));
+        let storage_adapter = Arc::new(MemoryStorageAdapter::new());
+        let bridge_manager = Arc::new(BridgeManager::new(
+            client_pool.clone(),
+            cache_manager.clone(),
+            storage_adapter.clone(),
+        ));
+        let auth_driver = build_auth_driver(&cache_manager, &client_pool);
+        let engine = RuleEngine::new(
+            client_pool,
+            cache_manager,
+            storage_adapter,
+            bridge_manager,
+            auth_driver,
+        );
+        engine
+            .add_rule(MqttRule {
+                name: "queue_full".to_string(),
+                sql: r#"SELECT * FROM "sensors/#""#.to_string(),
+                actions: vec![MqttRuleAction::Bridge {
+                    bridge_name: "b1".to_string(),
+                }],
+                create_time: 0,
+            })
+            .unwrap();
+
+        let connection = MQTTConnection {
+            client_id: "c1".to_string(),
+            ..Default::default()
+        };
+        let publish = Publish {
+            topic: Bytes::from("sensors/1/data"),
+            payload: Bytes::from(r#"{"temp":90}"#),
+            ..Default::default()
+        };
+        // Nothing takes the actions from the queue, so the ones past its size are counted
+        for _ in 0..RULE_ACTION_QUEUE_SIZE + 2 {
+            engine.apply(&connection, "sensors/1/data", &publish).await;
+        }
+        assert_eq!(rule_action_dropped("queue_full"), 2);
+    }
+
+    #[tokio::test]
+    async fn republish_acl_test() {
+        init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            cluster_name: "test".to_string(),
+            ..Default::default()
+        });
+
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool.clone(), "test".to_string()));
+        let storage_adapter = Arc::new(MemoryStorageAdapter::new());
+        let bridge_manager = Arc::new(BridgeManager::new(
+            client_pool.clone(),
+            cache_manager.clone(),
+            storage_adapter.clone(),
+        ));
+        cache_manager.add_acl(MqttAcl {
+            resource_type: MqttAclResourceType::ClientId,
+            resource_name: "c1".to_string(),
+            topic: "alerts/c1".to_string(),
+            ip: "*".to_string(),
+            action: MqttAclAction::Publish,
+            permission: MqttAclPermission::Deny,
+        });
+
+        let auth_driver = build_auth_driver(&cache_manager, &client_pool);
+        let engine = RuleEngine::new(
+            client_pool,
+            cache_manager,
+            storage_adapter,
+            bridge_manager,
+            auth_driver,
+        );
+
+        let connection = MQTTConnection {
+            client_id: "c1".to_string(),
+            ..Default::default()
+        };
+        let output = json!({"clientid": "c1"});
+        let res = engine
+            .republish(&connection, "alerts/${clientid}", 1, false, &output)
+            .await;
+        assert!(matches!(
+            res,
+            Err(MqttBrokerError::PublishNotAuthorized(client_id, topic_name))
+                if client_id == "c1" && topic_name == "alerts/c1"
+        ));
+    }
+
+    #[tokio::test]
+    async fn drop_rule_skips_retain_test() {
+        init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            cluster_name: "test".to_string(),
+            ..Default::default()
+        });
+
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool.clone(), "test".to_string()));
+        let storage_adapter = Arc::new(MemoryStorageAdapter::new());
+        let bridge_manager = Arc::new(BridgeManager::new(
+            client_pool.clone(),
+            cache_manager.clone(),
+            storage_adapter.clone(),
+        ));
+        let topic = MqttTopic::new(
+            "t1".to_string(),
+            "test".to_string(),
+            "sensors/1/data".to_string(),
+        );
+        cache_manager.add_topic("sensors/1/data", &topic);
+
+        let auth_driver = build_auth_driver(&cache_manager, &client_pool);
+        let engine = Arc::new(RuleEngine::new(
+            client_pool.clone(),
+            cache_manager.clone(),
+            storage_adapter,
+            bridge_manager,
+            auth_driver,
+        ));
+        engine
+            .add_rule(MqttRule {
+                name: "drop".to_string(),
+                sql: r#"SELECT * FROM "sensors/#""#.to_string(),
+                actions: vec![MqttRuleAction::Drop],
+                create_time: 0,
+            })
+            .unwrap();
+
+        let connection = MQTTConnection {
+            client_id: "c1".to_string(),
+            ..Default::default()
+        };
+        let publish = Publish {
+            retain: true,
+            topic: Bytes::from("sensors/1/data"),
+            payload: Bytes::from(r#"{"temp":90}"#),
+            ..Default::default()
+        };
+
+        // Saving a retained message needs the placement center, which this test
+        // does not have, so reaching it would fail.
+        let dropped = apply_rules_and_save_retain(
+            &engine,
+            &cache_manager,
+            &client_pool,
+            &connection,
+            "sensors/1/data",
+            &publish,
+            &None,
+        )
+        .await
+        .unwrap();
+        assert!(dropped);
+        assert!(cache_manager
+            .get_topic_by_name("sensors/1/data")
+            .unwrap()
+            .retain_message
+            .is_none());
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use metadata_struct::mqtt::rule::{MqttRule, MqttRuleAction};
+
+use crate::handler::error::MqttBrokerError;
+use crate::rule::sql::RuleSql;
+
+pub mod engine;
+pub mod sql;
+
+// Validates a rule before it is stored, bridges referenced by the rule are only
+// resolved when the rule runs since they can be created afterwards.
+pub fn check_rule(rule: &MqttRule) -> Result<(), MqttBrokerError> {
+    let invalid =
+        |reason: &str| MqttBrokerError::InvalidRule(rule.name.clone(), reason.to_string());
+
+    if rule.name.is_empty() {
+        return Err(invalid("name cannot be empty"));
+    }
+    if let Err(e) = RuleSql::parse(&rule.sql) {
+        return Err(invalid(&e.to_string()));
+    }
+    if rule.actions.is_empty() {
+        return Err(invalid("actions cannot be empty"));
+    }
+
+    for action in rule.actions.iter() {
+        match action {
+            MqttRuleAction::Republish { topic, qos, .. } => {
+                if topic.is_empty() {
+                    return Err(invalid("republish topic cannot be empty"));
+                }
+                if topic.contains(['+', '#']) {
+                    return Err(invalid("republish topic cannot contain wildcards"));
+                }
+                if *qos > 2 {
+                    return Err(invalid("republish qos must be 0, 1 or 2"));
+                }
+            }
+            MqttRuleAction::Bridge { bridge_name } => {
+                if bridge_name.is_empty() {
+                    return Err(invalid("bridge_name cannot be empty"));
+                }
+            }
+            MqttRuleAction::Drop => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use metadata_struct::mqtt::rule::{MqttRule, MqttRuleAction};
+
+    use super::check_rule;
+
+    fn build_rule(sql: &str, actions: Vec<MqttRuleAction>) -> MqttRule {
+        MqttRule {
+            name: "r1".to_string(),
+            sql: sql.to_string(),
+            actions,
+            create_time: 0,
+        }
+    }
+
+    #[test]
+    fn check_rule_test() {
+        let sql = r#"SELECT payload.temp AS t FROM "sensors/+/data" WHERE payload.temp > 80"#;
+        let republish = |topic: &str, qos: u8| MqttRuleAction::Republish {
+            topic: topic.to_string(),
+            qos,
+            retain: false,
+        };
+
+        let valid = [
+            build_rule(sql, vec![republish("alerts/${clientid}", 1)]),
+            build_rule(
+                sql,
+                vec![
+                    MqttRuleAction::Bridge {
+                        bridge_name: "b1".to_string(),
+                    },
+                    MqttRuleAction::Drop,
+                ],
+            ),
+        ];
+        for rule in valid.iter() {
+            assert!(check_rule(rule).is_ok());
+        }
+
+        let invalid = [
+            build_rule("SELECT FROM", vec![MqttRuleAction::Drop]),
+            build_rule(sql, vec![]),
+            build_rule(sql, vec![republish("", 0)]),
+            build_rule(sql, vec![republish("alerts/#", 0)]),
+            build_rule(sql, vec![republish("alerts", 3)]),
+            build_rule(
+                sql,
+                vec![MqttRuleAction::Bridge {
+                    bridge_name: "".to_string(),
+                }],
+            ),
+        ];
+        for rule in invalid.iter() {
+            assert!(check_rule(rule).is_err());
+        }
+    }
+}
@@ -0,0 +1,707 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::cmp::Ordering;
+
+use serde_json::{Map, Number, Value};
+
+use crate::handler::error::MqttBrokerError;
+use crate::subscribe::sub_common::path_match;
+
+// A parsed rule statement:
+//
+//   SELECT <* | expr [AS alias], ...> FROM "<topic filter>", ... [WHERE <expr>]
+//
+// Expressions support column paths (payload.a.b, payload.list[0]), number,
+// string ('..' or ".."), TRUE, FALSE and NULL literals, arithmetic, the
+// comparisons = != <> > >= < <=, AND, OR, NOT and parentheses. A path that
+// does not exist evaluates to NULL and NULL never satisfies a comparison.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleSql {
+    pub fields: SelectFields,
+    pub topic_filters: Vec<String>,
+    pub condition: Option<Expr>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SelectFields {
+    All,
+    Fields(Vec<SelectField>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelectField {
+    pub expr: Expr,
+    pub alias: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum UnaryOp {
+    Not,
+    Neg,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BinaryOp {
+    And,
+    Or,
+    Eq,
+    NotEq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    Column(Vec<PathSegment>),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(Box<Expr>, BinaryOp, Box<Expr>),
+}
+
+impl RuleSql {
+    pub fn parse(sql: &str) -> Result<Self, MqttBrokerError> {
+        let tokens = tokenize(sql)?;
+        Parser { tokens, pos: 0 }.parse_statement()
+    }
+
+    pub fn is_match(&self, topic_name: &str) -> bool {
+        self.topic_filters
+            .iter()
+            .any(|filter| path_match(topic_name, filter))
+    }
+
+    // Returns the selected fields, or None when the WHERE clause does not hold.
+    pub fn evaluate(&self, context: &Value) -> Option<Value> {
+        if let Some(condition) = &self.condition {
+            if !is_true(&condition.evaluate(context)) {
+                return None;
+            }
+        }
+
+        match &self.fields {
+            SelectFields::All => Some(context.clone()),
+            SelectFields::Fields(fields) => {
+                let mut output = Map::new();
+                for field in fields {
+                    output.insert(field.alias.clone(), field.expr.evaluate(context));
+                }
+                Some(Value::Object(output))
+            }
+        }
+    }
+}
+
+impl Expr {
+    pub fn evaluate(&self, context: &Value) -> Value {
+        match self {
+            Expr::Literal(value) => value.clone(),
+            Expr::Column(path) => {
+                let mut current = context;
+                for segment in path {
+                    let next = match segment {
+                        PathSegment::Key(key) => current.get(key),
+                        PathSegment::Index(index) => current.get(index),
+                    };
+                    match next {
+                        Some(value) => current = value,
+                        None => return Value::Null,
+                    }
+                }
+                current.clone()
+            }
+            Expr::Unary(UnaryOp::Not, expr) => Value::Bool(!is_true(&expr.evaluate(context))),
+            Expr::Unary(UnaryOp::Neg, expr) => match expr.evaluate(context).as_f64() {
+                Some(n) => number_value(-n),
+                None => Value::Null,
+            },
+            Expr::Binary(left, BinaryOp::And, right) => {
+                Value::Bool(is_true(&left.evaluate(context)) && is_true(&right.evaluate(context)))
+            }
+            Expr::Binary(left, BinaryOp::Or, right) => {
+                Value::Bool(is_true(&left.evaluate(context)) || is_true(&right.evaluate(context)))
+            }
+            Expr::Binary(left, op, right) => {
+                binary(&left.evaluate(context), op, &right.evaluate(context))
+            }
+        }
+    }
+}
+
+fn is_true(value: &Value) -> bool {
+    *value == Value::Bool(true)
+}
+
+fn binary(left: &Value, op: &BinaryOp, right: &Value) -> Value {
+    let has_null = left.is_null() || right.is_null();
+    match op {
+        // NULL never satisfies a comparison, not even NULL = NULL or NULL != 1
+        BinaryOp::Eq
+        | BinaryOp::NotEq
+        | BinaryOp::Gt
+        | BinaryOp::Ge
+        | BinaryOp::Lt
+        | BinaryOp::Le
+            if has_null =>
+        {
+            Value::Bool(false)
+        }
+        BinaryOp::Eq => Value::Bool(is_equal(left, right)),
+        BinaryOp::NotEq => Value::Bool(!is_equal(left, right)),
+        BinaryOp::Gt => Value::Bool(compare(left, right) == Some(Ordering::Greater)),
+        BinaryOp::Ge => Value::Bool(matches!(
+            compare(left, right),
+            Some(Ordering::Greater | Ordering::Equal)
+        )),
+        BinaryOp::Lt => Value::Bool(compare(left, right) == Some(Ordering::Less)),
+        BinaryOp::Le => Value::Bool(matches!(
+            compare(left, right),
+            Some(Ordering::Less | Ordering::Equal)
+        )),
+        BinaryOp::Add => {
+            if let (Value::String(l), Value::String(r)) = (left, right) {
+                return Value::String(format!("{}{}", l, r));
+            }
+            arithmetic(left, right, |l, r| Some(l + r))
+        }
+        BinaryOp::Sub => arithmetic(left, right, |l, r| Some(l - r)),
+        BinaryOp::Mul => arithmetic(left, right, |l, r| Some(l * r)),
+        BinaryOp::Div => arithmetic(
+            left,
+            right,
+            |l, r| if r == 0.0 { None } else { Some(l / r) },
+        ),
+        BinaryOp::Mod => arithmetic(
+            left,
+            right,
+            |l, r| if r == 0.0 { None } else { Some(l % r) },
+        ),
+        BinaryOp::And | BinaryOp::Or => unreachable!("evaluated with short-circuit"),
+    }
+}
+
+fn is_equal(left: &Value, right: &Value) -> bool {
+    match (left.as_f64(), right.as_f64()) {
+        (Some(l), Some(r)) => l == r,
+        _ => left == right,
+    }
+}
+
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(l), Value::Number(r)) => l.as_f64()?.partial_cmp(&r.as_f64()?),
+        (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+        (Value::Bool(l), Value::Bool(r)) => Some(l.cmp(r)),
+        _ => None,
+    }
+}
+
+fn arithmetic(left: &Value, right: &Value, f: impl Fn(f64, f64) -> Option<f64>) -> Value {
+    match (left.as_f64(), right.as_f64()) {
+        (Some(l), Some(r)) => f(l, r).map(number_value).unwrap_or(Value::Null),
+        _ => Value::Null,
+    }
+}
+
+// Whole numbers are kept as integers so that 1 + 1 is selected as 2 and not 2.0.
+fn number_value(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < 9_007_199_254_740_992.0 {
+        return Value::Number(Number::from(n as i64));
+    }
+    Number::from_f64(n)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Str(String),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: [&str; 18] = [
+    "!=", "<>", ">=", "<=", ",", ".", "(", ")", "[", "]", "*", "+", "-", "/", "%", "=", ">", "<",
+];
+
+fn syntax_error(reason: String) -> MqttBrokerError {
+    MqttBrokerError::InvalidRuleSql(reason)
+}
+
+fn tokenize(sql: &str) -> Result<Vec<Token>, MqttBrokerError> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    'outer: while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            continue;
+        }
+
+        if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let number = text
+                .parse::<f64>()
+                .map_err(|_| syntax_error(format!("invalid number {}", text)))?;
+            tokens.push(Token::Number(number));
+            continue;
+        }
+
+        // A quote inside a string is escaped by doubling it.
+        if c == '\'' || c == '"' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                if i >= chars.len() {
+                    return Err(syntax_error("unterminated string".to_string()));
+                }
+                if chars[i] == c {
+                    if i + 1 < chars.len() && chars[i + 1] == c {
+                        text.push(c);
+                        i += 2;
+                        continue;
+                    }
+                    i += 1;
+                    break;
+                }
+                text.push(chars[i]);
+                i += 1;
+            }
+            tokens.push(Token::Str(text));
+            continue;
+        }
+
+        for symbol in SYMBOLS {
+            let len = symbol.len();
+            if i + len <= chars.len() && chars[i..i + len].iter().copied().eq(symbol.chars()) {
+                tokens.push(Token::Symbol(symbol));
+                i += len;
+                continue 'outer;
+            }
+        }
+        return Err(syntax_error(format!("unexpected character '{}'", c)));
+    }
+    Ok(tokens)
+}
+
+const KEYWORDS: [&str; 10] = [
+    "SELECT", "FROM", "WHERE", "AS", "AND", "OR", "NOT", "TRUE", "FALSE", "NULL",
+];
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn parse_statement(&mut self) -> Result<RuleSql, MqttBrokerError> {
+        self.expect_keyword("SELECT")?;
+        let fields = if self.eat_symbol("*") {
+            SelectFields::All
+        } else {
+            let mut fields = vec![self.parse_field()?];
+            while self.eat_symbol(",") {
+                fields.push(self.parse_field()?);
+            }
+            SelectFields::Fields(fields)
+        };
+
+        self.expect_keyword("FROM")?;
+        let mut topic_filters = vec![self.parse_topic_filter()?];
+        while self.eat_symbol(",") {
+            topic_filters.push(self.parse_topic_filter()?);
+        }
+
+        let condition = if self.eat_keyword("WHERE") {
+            Some(self.parse_or()?)
+        } else {
+            None
+        };
+
+        if let Some(token) = self.peek() {
+            return Err(syntax_error(format!(
+                "unexpected {:?} after statement",
+                token
+            )));
+        }
+        Ok(RuleSql {
+            fields,
+            topic_filters,
+            condition,
+        })
+    }
+
+    fn parse_field(&mut self) -> Result<SelectField, MqttBrokerError> {
+        let expr = self.parse_or()?;
+        let alias = if self.eat_keyword("AS") {
+            match self.next() {
+                Some(Token::Ident(name)) if !is_keyword(&name) => name,
+                Some(Token::Str(name)) if !name.is_empty() => name,
+                other => return Err(syntax_error(format!("expected alias, found {:?}", other))),
+            }
+        } else if let Expr::Column(path) = &expr {
+            path_name(path)
+        } else {
+            return Err(syntax_error(
+                "selected expressions other than columns need an alias".to_string(),
+            ));
+        };
+        Ok(SelectField { expr, alias })
+    }
+
+    fn parse_topic_filter(&mut self) -> Result<String, MqttBrokerError> {
+        match self.next() {
+            Some(Token::Str(filter)) if !filter.is_empty() => Ok(filter),
+            other => Err(syntax_error(format!(
+                "expected quoted topic filter, found {:?}",
+                other
+            ))),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, MqttBrokerError> {
+        let mut left = self.parse_and()?;
+        while self.eat_keyword("OR") {
+            let right = self.parse_and()?;
+            left = Expr::Binary(Box::new(left), BinaryOp::Or, Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, MqttBrokerError> {
+        let mut left = self.parse_not()?;
+        while self.eat_keyword("AND") {
+            let right = self.parse_not()?;
+            left = Expr::Binary(Box::new(left), BinaryOp::And, Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, MqttBrokerError> {
+        if self.eat_keyword("NOT") {
+            return Ok(Expr::Unary(UnaryOp::Not, Box::new(self.parse_not()?)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, MqttBrokerError> {
+        let left = self.parse_additive()?;
+        let op = match self.peek() {
+            Some(Token::Symbol("=")) => BinaryOp::Eq,
+            Some(Token::Symbol("!=")) | Some(Token::Symbol("<>")) => BinaryOp::NotEq,
+            Some(Token::Symbol(">")) => BinaryOp::Gt,
+            Some(Token::Symbol(">=")) => BinaryOp::Ge,
+            Some(Token::Symbol("<")) => BinaryOp::Lt,
+            Some(Token::Symbol("<=")) => BinaryOp::Le,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        let right = self.parse_additive()?;
+        Ok(Expr::Binary(Box::new(left), op, Box::new(right)))
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, MqttBrokerError> {
+        let mut left = self.parse_multiplicative()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Symbol("+")) => BinaryOp::Add,
+                Some(Token::Symbol("-")) => BinaryOp::Sub,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.parse_multiplicative()?;
+            left = Expr::Binary(Box::new(left), op, Box::new(right));
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, MqttBrokerError> {
+        let mut left = self.parse_unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Symbol("*")) => BinaryOp::Mul,
+                Some(Token::Symbol("/")) => BinaryOp::Div,
+                Some(Token::Symbol("%")) => BinaryOp::Mod,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.parse_unary()?;
+            left = Expr::Binary(Box::new(left), op, Box::new(right));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, MqttBrokerError> {
+        if self.eat_symbol("-") {
+            return Ok(Expr::Unary(UnaryOp::Neg, Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, MqttBrokerError> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Literal(number_value(n))),
+            Some(Token::Str(text)) => Ok(Expr::Literal(Value::String(text))),
+            Some(Token::Symbol("(")) => {
+                let expr = self.parse_or()?;
+                self.expect_symbol(")")?;
+                Ok(expr)
+            }
+            Some(Token::Ident(name)) => match name.to_uppercase().as_str() {
+                "TRUE" => Ok(Expr::Literal(Value::Bool(true))),
+                "FALSE" => Ok(Expr::Literal(Value::Bool(false))),
+                "NULL" => Ok(Expr::Literal(Value::Null)),
+                keyword if KEYWORDS.contains(&keyword) => {
+                    Err(syntax_error(format!("unexpected keyword {}", name)))
+                }
+                _ => self.parse_path(name),
+            },
+            other => Err(syntax_error(format!(
+                "expected expression, found {:?}",
+                other
+            ))),
+        }
+    }
+
+    fn parse_path(&mut self, first: String) -> Result<Expr, MqttBrokerError> {
+        let mut path = vec![PathSegment::Key(first)];
+        loop {
+            if self.eat_symbol(".") {
+                match self.next() {
+                    Some(Token::Ident(key)) => path.push(PathSegment::Key(key)),
+                    other => {
+                        return Err(syntax_error(format!(
+                            "expected field name after '.', found {:?}",
+                            other
+                        )))
+                    }
+                }
+            } else if self.eat_symbol("[") {
+                match self.next() {
+                    Some(Token::Number(n)) if n >= 0.0 && n.fract() == 0.0 => {
+                        path.push(PathSegment::Index(n as usize))
+                    }
+                    other => {
+                        return Err(syntax_error(format!(
+                            "expected array index, found {:?}",
+                            other
+                        )))
+                    }
+                }
+                self.expect_symbol("]")?;
+            } else {
+                return Ok(Expr::Column(path));
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        if token.is_some() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), MqttBrokerError> {
+        if self.eat_symbol(symbol) {
+            return Ok(());
+        }
+        Err(syntax_error(format!(
+            "expected '{}', found {:?}",
+            symbol,
+            self.peek()
+        )))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(s)) if s.eq_ignore_ascii_case(keyword)) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), MqttBrokerError> {
+        if self.eat_keyword(keyword) {
+            return Ok(());
+        }
+        Err(syntax_error(format!(
+            "expected {}, found {:?}",
+            keyword,
+            self.peek()
+        )))
+    }
+}
+
+fn is_keyword(name: &str) -> bool {
+    KEYWORDS.contains(&name.to_uppercase().as_str())
+}
+
+fn path_name(path: &[PathSegment]) -> String {
+    let mut name = String::new();
+    for segment in path {
+        match segment {
+            PathSegment::Key(key) => {
+                if !name.is_empty() {
+                    name.push('.');
+                }
+                name.push_str(key);
+            }
+            PathSegment::Index(index) => name.push_str(&format!("[{}]", index)),
+        }
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{RuleSql, SelectFields};
+
+    #[test]
+    fn select_where_test() {
+        let sql = RuleSql::parse(
+            r#"SELECT payload.temp AS t, clientid FROM "sensors/+/data" WHERE payload.temp > 80"#,
+        )
+        .unwrap();
+        assert_eq!(sql.topic_filters, vec!["sensors/+/data".to_string()]);
+        assert!(sql.is_match("sensors/1/data"));
+        assert!(!sql.is_match("sensors/1/status"));
+
+        let context = json!({"clientid": "c1", "payload": {"temp": 85.5}});
+        assert_eq!(
+            sql.evaluate(&context),
+            Some(json!({"t": 85.5, "clientid": "c1"}))
+        );
+
+        let context = json!({"clientid": "c1", "payload": {"temp": 20}});
+        assert_eq!(sql.evaluate(&context), None);
+
+        // A missing field never satisfies a comparison.
+        let context = json!({"clientid": "c1", "payload": "not json"});
+        assert_eq!(sql.evaluate(&context), None);
+    }
+
+    #[test]
+    fn expression_test() {
+        let sql = RuleSql::parse(
+            "select payload.values[1] * 2 + 1 as v, payload.a.b, 'x' + topic AS name \
+             from 'a/#', 'b/#' \
+             where (payload.kind = 'alarm' or payload.kind <> \"info\") and not retain",
+        )
+        .unwrap();
+        assert_eq!(sql.topic_filters.len(), 2);
+
+        let context = json!({
+            "topic": "a/1",
+            "retain": false,
+            "payload": {"kind": "alarm", "values": [1, 4], "a": {"b": true}},
+        });
+        assert_eq!(
+            sql.evaluate(&context),
+            Some(json!({"v": 9, "payload.a.b": true, "name": "xa/1"}))
+        );
+
+        let context = json!({"topic": "a/1", "retain": true, "payload": {"kind": "alarm"}});
+        assert_eq!(sql.evaluate(&context), None);
+
+        let context = json!({"topic": "a/1", "retain": false, "payload": {"kind": "info"}});
+        assert_eq!(sql.evaluate(&context), None);
+    }
+
+    #[test]
+    fn null_comparison_test() {
+        let context = json!({"payload": {"a": null, "b": 1}});
+        for condition in [
+            "payload.a = NULL",
+            "payload.a != NULL",
+            "payload.a <> 1",
+            "payload.a != 1",
+            "payload.a = 1",
+            "payload.a > 1",
+            "payload.a >= 1",
+            "payload.a < 1",
+            "payload.a <= 1",
+            "payload.missing != payload.b",
+            "NULL = NULL",
+        ] {
+            let sql = RuleSql::parse(&format!("SELECT * FROM \"#\" WHERE {}", condition)).unwrap();
+            assert_eq!(sql.evaluate(&context), None, "{}", condition);
+        }
+
+        let sql = RuleSql::parse("SELECT * FROM \"#\" WHERE payload.b != 2").unwrap();
+        assert_eq!(sql.evaluate(&context), Some(context.clone()));
+    }
+
+    #[test]
+    fn select_all_test() {
+        let sql = RuleSql::parse(r##"SELECT * FROM "#""##).unwrap();
+        assert_eq!(sql.fields, SelectFields::All);
+        assert!(sql.condition.is_none());
+
+        let context = json!({"topic": "a", "payload": 1});
+        assert_eq!(sql.evaluate(&context), Some(context.clone()));
+    }
+
+    #[test]
+    fn syntax_error_test() {
+        assert!(RuleSql::parse("SELECT FROM \"a\"").is_err());
+        assert!(RuleSql::parse("SELECT a").is_err());
+        assert!(RuleSql::parse("SELECT a FROM b").is_err());
+        assert!(RuleSql::parse("SELECT a + 1 FROM \"a\"").is_err());
+        assert!(RuleSql::parse("SELECT a FROM \"a\" WHERE").is_err());
+        assert!(RuleSql::parse("SELECT a FROM \"a\" WHERE (a > 1").is_err());
+        assert!(RuleSql::parse("SELECT a FROM \"a\" WHERE a = 'x").is_err());
+        assert!(RuleSql::parse("SELECT a FROM \"a\" LIMIT 1").is_err());
+    }
+}
@@ -147,6 +147,13 @@ fn is_acl_deny(
     topic_name: &str,
     action: MqttAclAction,
 ) -> bool {
+    // check the connection's own acl, the first matching rule decides
+    for raw in connection.acl.iter() {
+        if topic_filter_match(topic_name, &raw.topic) && action_match(&raw.action, &action) {
+            return raw.permission == MqttAclPermission::Deny;
+        }
+    }
+
     // check user acl
     if let Some(acl_list) = cache_mamanger
         .acl_metadata
@@ -189,6 +196,32 @@ fn topic_match(topic_name: &str, match_topic_name: &str) -> bool {
     topic_name == match_topic_name
 }
 
+// Matches MQTT topic filters with `+` and `#` wildcards.
+fn topic_filter_match(topic_name: &str, topic_filter: &str) -> bool {
+    if topic_filter == WILDCARD_RESOURCE {
+        return true;
+    }
+    let mut names = topic_name.split('/');
+    for filter in topic_filter.split('/') {
+        if filter == "#" {
+            return true;
+        }
+        match names.next() {
+            Some(name) if filter == "+" || filter == name => {}
+            _ => return false,
+        }
+    }
+    names.next().is_none()
+}
+
+fn action_match(rule_action: &MqttAclAction, action: &MqttAclAction) -> bool {
+    if rule_action == action || *rule_action == MqttAclAction::All {
+        return true;
+    }
+    *rule_action == MqttAclAction::PubSub
+        && (*action == MqttAclAction::Publish || *action == MqttAclAction::Subscribe)
+}
+
 fn ip_match(source_ip_addr: &str, ip_role: &str) -> bool {
     if ip_role == WILDCARD_RESOURCE {
         return true;
@@ -217,7 +250,9 @@ mod test {
     use metadata_struct::mqtt::connection::{ConnectionConfig, MQTTConnection};
     use metadata_struct::mqtt::user::MqttUser;
 
-    use super::{ip_match, is_acl_deny, is_blacklist, is_super_user, topic_match};
+    use super::{
+        ip_match, is_acl_deny, is_blacklist, is_super_user, topic_filter_match, topic_match,
+    };
     use crate::handler::cache::CacheManager;
     use crate::handler::constant::WILDCARD_RESOURCE;
 
@@ -642,6 +677,86 @@ mod test {
         ));
     }
 
+    #[tokio::test]
+    pub async fn check_connection_acl_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cluster_name = "test".to_string();
+        let cache_manager = Arc::new(CacheManager::new(client_pool, cluster_name));
+        let config = ConnectionConfig {
+            connect_id: 1,
+            client_id: "client_id-1".to_string(),
+            receive_maximum: 3,
+            max_packet_size: 3,
+            topic_alias_max: 3,
+            request_problem_info: 1,
+            keep_alive: 2,
+            source_ip_addr: "127.0.0.1".to_string(),
+        };
+        let mut connection = MQTTConnection::new(config);
+        connection.login_success("loboxu".to_string());
+
+        let client_id = connection.client_id.clone();
+        let rule = |topic: &str, action, permission| MqttAcl {
+            resource_type: MqttAclResourceType::ClientId,
+            resource_name: client_id.clone(),
+            topic: topic.to_string(),
+            ip: WILDCARD_RESOURCE.to_string(),
+            action,
+            permission,
+        };
+        connection.acl = vec![
+            rule("tp/+/data", MqttAclAction::PubSub, MqttAclPermission::Allow),
+            rule(
+                WILDCARD_RESOURCE,
+                MqttAclAction::All,
+                MqttAclPermission::Deny,
+            ),
+        ];
+
+        // the global acl is not consulted once a connection rule matches
+        cache_manager.add_acl(rule(
+            "tp/1/data",
+            MqttAclAction::Publish,
+            MqttAclPermission::Deny,
+        ));
+        assert!(!is_acl_deny(
+            &cache_manager,
+            &connection,
+            "tp/1/data",
+            MqttAclAction::Publish
+        ));
+        assert!(!is_acl_deny(
+            &cache_manager,
+            &connection,
+            "tp/1/data",
+            MqttAclAction::Subscribe
+        ));
+        assert!(is_acl_deny(
+            &cache_manager,
+            &connection,
+            "tp/1/data",
+            MqttAclAction::Retain
+        ));
+        assert!(is_acl_deny(
+            &cache_manager,
+            &connection,
+            "tp/1/other",
+            MqttAclAction::Publish
+        ));
+    }
+
+    #[tokio::test]
+    pub async fn topic_filter_match_test() {
+        assert!(topic_filter_match("t1", WILDCARD_RESOURCE));
+        assert!(topic_filter_match("t1/a", "t1/a"));
+        assert!(topic_filter_match("t1/a", "t1/+"));
+        assert!(topic_filter_match("t1/a/b", "t1/#"));
+        assert!(topic_filter_match("t1", "t1/#"));
+        assert!(!topic_filter_match("t1/a/b", "t1/+"));
+        assert!(!topic_filter_match("t1", "t1/+"));
+        assert!(!topic_filter_match("t2/a", "t1/#"));
+    }
+
     #[tokio::test]
     pub async fn topic_match_test() {
         let topic_name = "t1";
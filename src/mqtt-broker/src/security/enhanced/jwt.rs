@@ -0,0 +1,132 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use axum::async_trait;
+use bytes::Bytes;
+
+use super::{AuthContext, AuthExchange, AuthStep, EnhancedAuthenticator};
+use crate::handler::error::MqttBrokerError;
+use crate::security::login::jwt::JwtAuthenticator;
+
+pub const JWT: &str = "JWT";
+
+// The token is the authentication data of CONNECT (or of AUTH when
+// re-authenticating), the exchange finishes in one step.
+pub struct JwtEnhancedAuthenticator {
+    authenticator: Arc<JwtAuthenticator>,
+}
+
+impl JwtEnhancedAuthenticator {
+    pub fn new(authenticator: Arc<JwtAuthenticator>) -> Self {
+        JwtEnhancedAuthenticator { authenticator }
+    }
+}
+
+impl EnhancedAuthenticator for JwtEnhancedAuthenticator {
+    fn method(&self) -> &str {
+        JWT
+    }
+
+    fn new_exchange(&self, context: AuthContext) -> Box<dyn AuthExchange + Send + Sync> {
+        Box::new(JwtExchange {
+            authenticator: self.authenticator.clone(),
+            context,
+        })
+    }
+}
+
+struct JwtExchange {
+    authenticator: Arc<JwtAuthenticator>,
+    context: AuthContext,
+}
+
+#[async_trait]
+impl AuthExchange for JwtExchange {
+    async fn step(&mut self, data: Option<Bytes>) -> Result<AuthStep, MqttBrokerError> {
+        let token = if let Some(data) = data {
+            String::from_utf8(data.to_vec())?
+        } else {
+            return Err(MqttBrokerError::InvalidJwt(
+                "authentication data is missing".to_string(),
+            ));
+        };
+
+        let identity = self.authenticator.verify(
+            &token,
+            &self.context.client_id,
+            self.context.username.as_deref(),
+        )?;
+        Ok(AuthStep::Success {
+            identity,
+            data: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use common_base::config::common::JwtAuth;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::json;
+
+    use super::JwtEnhancedAuthenticator;
+    use crate::security::enhanced::{AuthContext, AuthStep, EnhancedAuthenticator};
+    use crate::security::login::jwt::JwtAuthenticator;
+
+    #[tokio::test]
+    async fn jwt_exchange_test() {
+        let authenticator = JwtEnhancedAuthenticator::new(Arc::new(
+            JwtAuthenticator::new(JwtAuth {
+                secret: "secret".to_string(),
+                client_id_claim: "clientid".to_string(),
+                acl_claim: "acl".to_string(),
+                ..Default::default()
+            })
+            .unwrap(),
+        ));
+        let token = encode(
+            &Header::default(),
+            &json!({"clientid": "c1"}),
+            &EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+
+        let context = AuthContext {
+            client_id: "c1".to_string(),
+            username: Some("lobo".to_string()),
+        };
+        let mut exchange = authenticator.new_exchange(context.clone());
+        match exchange.step(Some(Bytes::from(token.clone()))).await {
+            Ok(AuthStep::Success { identity, data }) => {
+                assert_eq!(identity.username, "lobo".to_string());
+                assert!(data.is_none());
+            }
+            _ => panic!("the token should be accepted"),
+        }
+
+        let mut exchange = authenticator.new_exchange(context);
+        assert!(exchange.step(None).await.is_err());
+
+        let mut exchange = authenticator.new_exchange(AuthContext {
+            client_id: "c2".to_string(),
+            username: None,
+        });
+        assert!(exchange.step(Some(Bytes::from(token))).await.is_err());
+    }
+}
@@ -0,0 +1,316 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::async_trait;
+use bytes::Bytes;
+use common_base::tools::now_second;
+use dashmap::DashMap;
+use protocol::mqtt::common::{Connect, ConnectProperties, LastWill, LastWillProperties};
+
+use crate::handler::error::MqttBrokerError;
+use crate::security::LoginIdentity;
+
+pub mod jwt;
+pub mod scram;
+
+// An exchange that has not made progress for this long is dropped, e.g. because
+// the client disconnected halfway through it.
+const AUTH_EXCHANGE_TIMEOUT_SEC: u64 = 60;
+
+pub enum AuthStep {
+    // The data is sent to the client in an AUTH packet, the exchange goes on
+    // with the client's answer.
+    Continue(Bytes),
+    Success {
+        identity: LoginIdentity,
+        data: Option<Bytes>,
+    },
+}
+
+// What the broker knows about the client before the exchange starts, from CONNECT
+// or from the established connection when it re-authenticates.
+#[derive(Clone, Debug, Default)]
+pub struct AuthContext {
+    pub client_id: String,
+    pub username: Option<String>,
+}
+
+// One challenge/response exchange of an authentication method, it lives from the
+// first authentication data until the authenticator accepted or rejected the client.
+#[async_trait]
+pub trait AuthExchange {
+    async fn step(&mut self, data: Option<Bytes>) -> Result<AuthStep, MqttBrokerError>;
+}
+
+// Implements an MQTT 5 authentication method, selected by the client with the
+// Authentication Method property of CONNECT.
+pub trait EnhancedAuthenticator {
+    fn method(&self) -> &str;
+
+    fn new_exchange(&self, context: AuthContext) -> Box<dyn AuthExchange + Send + Sync>;
+}
+
+// CONNECT is only completed once its authentication exchange succeeded.
+pub struct PendingConnect {
+    pub connect: Connect,
+    pub connect_properties: Option<ConnectProperties>,
+    pub last_will: Option<LastWill>,
+    pub last_will_properties: Option<LastWillProperties>,
+    pub addr: SocketAddr,
+}
+
+pub enum AuthOutcome {
+    Continue(Bytes),
+    // `connect` is None when an established connection re-authenticated.
+    Success {
+        identity: LoginIdentity,
+        data: Option<Bytes>,
+        connect: Option<PendingConnect>,
+    },
+}
+
+struct AuthSession {
+    method: String,
+    exchange: Box<dyn AuthExchange + Send + Sync>,
+    connect: Option<PendingConnect>,
+    update_time: u64,
+}
+
+pub struct EnhancedAuthManager {
+    authenticators: HashMap<String, Arc<dyn EnhancedAuthenticator + Send + Sync>>,
+    // (connect_id, exchange in progress)
+    sessions: DashMap<u64, AuthSession>,
+}
+
+impl Default for EnhancedAuthManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EnhancedAuthManager {
+    pub fn new() -> Self {
+        EnhancedAuthManager {
+            authenticators: HashMap::new(),
+            sessions: DashMap::with_capacity(8),
+        }
+    }
+
+    pub fn is_registered(&self, method: &str) -> bool {
+        self.authenticators.contains_key(method)
+    }
+
+    pub fn register(&mut self, authenticator: Arc<dyn EnhancedAuthenticator + Send + Sync>) {
+        self.authenticators
+            .insert(authenticator.method().to_string(), authenticator);
+    }
+
+    // Starts the exchange of a CONNECT (`connect` is Some) or of a re-authentication.
+    pub async fn start(
+        &self,
+        connect_id: u64,
+        method: &str,
+        data: Option<Bytes>,
+        context: AuthContext,
+        connect: Option<PendingConnect>,
+    ) -> Result<AuthOutcome, MqttBrokerError> {
+        let authenticator = if let Some(authenticator) = self.authenticators.get(method) {
+            authenticator
+        } else {
+            return Err(MqttBrokerError::BadAuthenticationMethod(method.to_string()));
+        };
+
+        self.remove_expired_sessions();
+        let session = AuthSession {
+            method: method.to_string(),
+            exchange: authenticator.new_exchange(context),
+            connect,
+            update_time: now_second(),
+        };
+        self.step(connect_id, session, data).await
+    }
+
+    pub async fn continue_auth(
+        &self,
+        connect_id: u64,
+        method: &str,
+        data: Option<Bytes>,
+    ) -> Result<AuthOutcome, MqttBrokerError> {
+        let session = if let Some((_, session)) = self.sessions.remove(&connect_id) {
+            session
+        } else {
+            return Err(MqttBrokerError::AuthExchangeNotFound(connect_id));
+        };
+
+        // The method cannot change in the middle of an exchange.
+        if session.method != method {
+            return Err(MqttBrokerError::BadAuthenticationMethod(method.to_string()));
+        }
+        self.step(connect_id, session, data).await
+    }
+
+    pub fn is_in_progress(&self, connect_id: u64) -> bool {
+        self.sessions.contains_key(&connect_id)
+    }
+
+    pub fn remove(&self, connect_id: u64) {
+        self.sessions.remove(&connect_id);
+    }
+
+    async fn step(
+        &self,
+        connect_id: u64,
+        mut session: AuthSession,
+        data: Option<Bytes>,
+    ) -> Result<AuthOutcome, MqttBrokerError> {
+        // A failed step ends the exchange, the session is not put back.
+        match session.exchange.step(data).await? {
+            AuthStep::Continue(data) => {
+                session.update_time = now_second();
+                self.sessions.insert(connect_id, session);
+                Ok(AuthOutcome::Continue(data))
+            }
+            AuthStep::Success { identity, data } => Ok(AuthOutcome::Success {
+                identity,
+                data,
+                connect: session.connect,
+            }),
+        }
+    }
+
+    fn remove_expired_sessions(&self) {
+        let now = now_second();
+        self.sessions
+            .retain(|_, session| now - session.update_time < AUTH_EXCHANGE_TIMEOUT_SEC);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::async_trait;
+    use bytes::Bytes;
+
+    use super::{
+        AuthContext, AuthExchange, AuthOutcome, AuthStep, EnhancedAuthManager,
+        EnhancedAuthenticator,
+    };
+    use crate::handler::error::MqttBrokerError;
+    use crate::security::LoginIdentity;
+
+    // Accepts the client once it echoed the challenge.
+    struct EchoAuthenticator;
+
+    struct EchoExchange {
+        challenged: bool,
+    }
+
+    #[async_trait]
+    impl AuthExchange for EchoExchange {
+        async fn step(&mut self, data: Option<Bytes>) -> Result<AuthStep, MqttBrokerError> {
+            if !self.challenged {
+                self.challenged = true;
+                return Ok(AuthStep::Continue(Bytes::from("challenge")));
+            }
+            if data == Some(Bytes::from("challenge")) {
+                return Ok(AuthStep::Success {
+                    identity: LoginIdentity {
+                        username: "u1".to_string(),
+                        ..Default::default()
+                    },
+                    data: None,
+                });
+            }
+            Err(MqttBrokerError::AuthenticationFailed(
+                "wrong answer".to_string(),
+            ))
+        }
+    }
+
+    impl EnhancedAuthenticator for EchoAuthenticator {
+        fn method(&self) -> &str {
+            "ECHO"
+        }
+
+        fn new_exchange(&self, _: AuthContext) -> Box<dyn AuthExchange + Send + Sync> {
+            Box::new(EchoExchange { challenged: false })
+        }
+    }
+
+    #[tokio::test]
+    async fn enhanced_auth_manager_test() {
+        let mut manager = EnhancedAuthManager::new();
+        manager.register(Arc::new(EchoAuthenticator));
+
+        assert!(matches!(
+            manager
+                .start(1, "PLAIN", None, AuthContext::default(), None)
+                .await,
+            Err(MqttBrokerError::BadAuthenticationMethod(_))
+        ));
+        assert!(matches!(
+            manager.continue_auth(1, "ECHO", None).await,
+            Err(MqttBrokerError::AuthExchangeNotFound(1))
+        ));
+
+        let outcome = manager
+            .start(1, "ECHO", None, AuthContext::default(), None)
+            .await
+            .unwrap();
+        assert!(matches!(outcome, AuthOutcome::Continue(_)));
+        assert!(manager.is_in_progress(1));
+
+        // the method must not change during the exchange
+        assert!(manager
+            .continue_auth(1, "PLAIN", Some(Bytes::from("challenge")))
+            .await
+            .is_err());
+        assert!(!manager.is_in_progress(1));
+
+        manager
+            .start(1, "ECHO", None, AuthContext::default(), None)
+            .await
+            .unwrap();
+        match manager
+            .continue_auth(1, "ECHO", Some(Bytes::from("challenge")))
+            .await
+            .unwrap()
+        {
+            AuthOutcome::Success {
+                identity, connect, ..
+            } => {
+                assert_eq!(identity.username, "u1".to_string());
+                assert!(connect.is_none());
+            }
+            AuthOutcome::Continue(_) => panic!("exchange should be finished"),
+        }
+        assert!(!manager.is_in_progress(1));
+
+        // a wrong answer ends the exchange
+        manager
+            .start(2, "ECHO", None, AuthContext::default(), None)
+            .await
+            .unwrap();
+        assert!(manager
+            .continue_auth(2, "ECHO", Some(Bytes::from("other")))
+            .await
+            .is_err());
+        assert!(!manager.is_in_progress(2));
+    }
+}
@@ -0,0 +1,521 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! SCRAM-SHA-256 (RFC 5802, RFC 7677) over the MQTT 5 AUTH exchange:
+//!
+//! 1. CONNECT carries the client-first-message `n,,n=<user>,r=<client nonce>`
+//! 2. AUTH (Continue) carries the server-first-message `r=<nonce>,s=<salt>,i=<iterations>`
+//! 3. AUTH (Continue) carries the client-final-message `c=<gs2 header>,r=<nonce>,p=<proof>`
+//! 4. CONNACK carries the server-final-message `v=<server signature>`
+
+use std::num::NonZeroU32;
+use std::sync::Arc;
+
+use axum::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use bytes::Bytes;
+use log::warn;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{digest, hmac, pbkdf2};
+
+use super::{AuthContext, AuthExchange, AuthStep, EnhancedAuthenticator};
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::security::password::{scram_salted_password, PasswordHashAlgorithm};
+use crate::security::LoginIdentity;
+
+pub const SCRAM_SHA_256: &str = "SCRAM-SHA-256";
+
+const DEFAULT_ITERATIONS: u32 = 4096;
+const NONCE_LEN: usize = 18;
+const SALT_LEN: usize = 16;
+
+// Users are looked up in the user cache. Plaintext records derive the salted password
+// with a fresh salt on every exchange, PBKDF2-SHA256 records already hold it together
+// with their salt and iteration count. bcrypt and sha256 records can not be used.
+pub struct ScramSha256Authenticator {
+    cache_manager: Arc<CacheManager>,
+    iterations: u32,
+}
+
+impl ScramSha256Authenticator {
+    pub fn new(cache_manager: Arc<CacheManager>) -> Self {
+        ScramSha256Authenticator {
+            cache_manager,
+            iterations: DEFAULT_ITERATIONS,
+        }
+    }
+}
+
+impl EnhancedAuthenticator for ScramSha256Authenticator {
+    fn method(&self) -> &str {
+        SCRAM_SHA_256
+    }
+
+    // The username is part of the SCRAM messages.
+    fn new_exchange(&self, _: AuthContext) -> Box<dyn AuthExchange + Send + Sync> {
+        Box::new(ScramExchange {
+            cache_manager: self.cache_manager.clone(),
+            iterations: self.iterations,
+            state: ScramState::ClientFirst,
+        })
+    }
+}
+
+enum ScramState {
+    ClientFirst,
+    ClientFinal {
+        username: String,
+        gs2_header: String,
+        client_first_bare: String,
+        server_first: String,
+        nonce: String,
+        // None for unknown users, the exchange still runs to the end so that
+        // clients cannot tell unknown users from wrong passwords.
+        salted_password: Option<Vec<u8>>,
+    },
+    Finished,
+}
+
+struct ScramExchange {
+    cache_manager: Arc<CacheManager>,
+    iterations: u32,
+    state: ScramState,
+}
+
+impl ScramExchange {
+    // (salt, iterations, salted password) announced to the client, unusable records get
+    // a random salt so that the exchange looks the same as for a known user.
+    fn credentials(
+        &self,
+        username: &str,
+    ) -> Result<(Vec<u8>, u32, Option<Vec<u8>>), MqttBrokerError> {
+        let stored = if let Some(user) = self.cache_manager.user_info.get(username) {
+            user.password.clone()
+        } else {
+            return Ok((random_bytes(SALT_LEN)?, self.iterations, None));
+        };
+
+        match PasswordHashAlgorithm::of(&stored) {
+            PasswordHashAlgorithm::Plain => {
+                let salt = random_bytes(SALT_LEN)?;
+                let salted = salted_password(&stored, &salt, self.iterations);
+                return Ok((salt, self.iterations, Some(salted)));
+            }
+            PasswordHashAlgorithm::Pbkdf2 => {
+                if let Some((salt, iterations, salted)) = scram_salted_password(&stored) {
+                    return Ok((salt, iterations, Some(salted)));
+                }
+                warn!("user [{}] has a malformed pbkdf2 password record", username);
+            }
+            algorithm => warn!(
+                "user [{}] password is hashed with {}, {} needs a plain or pbkdf2 record",
+                username, algorithm, SCRAM_SHA_256
+            ),
+        }
+        Ok((random_bytes(SALT_LEN)?, self.iterations, None))
+    }
+}
+
+#[async_trait]
+impl AuthExchange for ScramExchange {
+    async fn step(&mut self, data: Option<Bytes>) -> Result<AuthStep, MqttBrokerError> {
+        let message = match data {
+            Some(data) => String::from_utf8(data.to_vec())?,
+            None => return Err(failed("authentication data is missing")),
+        };
+
+        match std::mem::replace(&mut self.state, ScramState::Finished) {
+            ScramState::ClientFirst => {
+                let client_first = parse_client_first(&message)?;
+                let (salt, iterations, salted_password) =
+                    self.credentials(&client_first.username)?;
+
+                let nonce = format!(
+                    "{}{}",
+                    client_first.nonce,
+                    STANDARD.encode(random_bytes(NONCE_LEN)?)
+                );
+                let server_first =
+                    format!("r={},s={},i={}", nonce, STANDARD.encode(&salt), iterations);
+
+                self.state = ScramState::ClientFinal {
+                    username: client_first.username,
+                    gs2_header: client_first.gs2_header,
+                    client_first_bare: client_first.bare,
+                    server_first: server_first.clone(),
+                    nonce,
+                    salted_password,
+                };
+                Ok(AuthStep::Continue(Bytes::from(server_first)))
+            }
+            ScramState::ClientFinal {
+                username,
+                gs2_header,
+                client_first_bare,
+                server_first,
+                nonce,
+                salted_password,
+            } => {
+                let client_final = parse_client_final(&message)?;
+                if client_final.channel_binding != STANDARD.encode(&gs2_header) {
+                    return Err(failed("channel binding does not match"));
+                }
+                if client_final.nonce != nonce {
+                    return Err(failed("nonce does not match"));
+                }
+
+                let salted_password = if let Some(salted_password) = salted_password {
+                    salted_password
+                } else {
+                    return Err(failed("invalid username or password"));
+                };
+
+                let auth_message = format!(
+                    "{},{},{}",
+                    client_first_bare, server_first, client_final.without_proof
+                );
+                if !verify_client_proof(&salted_password, &auth_message, &client_final.proof) {
+                    return Err(failed("invalid username or password"));
+                }
+
+                let server_final = format!(
+                    "v={}",
+                    STANDARD.encode(server_signature(&salted_password, &auth_message))
+                );
+                Ok(AuthStep::Success {
+                    identity: LoginIdentity {
+                        username,
+                        ..Default::default()
+                    },
+                    data: Some(Bytes::from(server_final)),
+                })
+            }
+            ScramState::Finished => Err(failed("exchange is already finished")),
+        }
+    }
+}
+
+struct ClientFirst {
+    gs2_header: String,
+    bare: String,
+    username: String,
+    nonce: String,
+}
+
+struct ClientFinal {
+    channel_binding: String,
+    nonce: String,
+    proof: Vec<u8>,
+    without_proof: String,
+}
+
+fn failed(reason: &str) -> MqttBrokerError {
+    MqttBrokerError::AuthenticationFailed(format!("{} {}", SCRAM_SHA_256, reason))
+}
+
+// Values of the comma separated `key=value` attributes, in order.
+fn attributes(message: &str) -> Vec<(&str, &str)> {
+    message
+        .split(',')
+        .filter_map(|attr| attr.split_once('='))
+        .collect()
+}
+
+fn parse_client_first(message: &str) -> Result<ClientFirst, MqttBrokerError> {
+    // gs2-header is `<cbind-flag>,[a=<authzid>],`, channel binding is not supported
+    let mut parts = message.splitn(3, ',');
+    let cbind_flag = parts.next().unwrap_or_default();
+    let authzid = parts.next().unwrap_or_default();
+    let bare = if let Some(bare) = parts.next() {
+        bare
+    } else {
+        return Err(failed("malformed client-first-message"));
+    };
+    if cbind_flag != "n" && cbind_flag != "y" {
+        return Err(failed("channel binding is not supported"));
+    }
+
+    let mut username = None;
+    let mut nonce = None;
+    for (key, value) in attributes(bare) {
+        match key {
+            "n" => username = Some(value.replace("=2C", ",").replace("=3D", "=")),
+            "r" => nonce = Some(value.to_string()),
+            "m" => return Err(failed("mandatory extensions are not supported")),
+            _ => {}
+        }
+    }
+
+    match (username, nonce) {
+        (Some(username), Some(nonce)) if !username.is_empty() && !nonce.is_empty() => {
+            Ok(ClientFirst {
+                gs2_header: format!("{},{},", cbind_flag, authzid),
+                bare: bare.to_string(),
+                username,
+                nonce,
+            })
+        }
+        _ => Err(failed("malformed client-first-message")),
+    }
+}
+
+fn parse_client_final(message: &str) -> Result<ClientFinal, MqttBrokerError> {
+    let (without_proof, proof) = if let Some(index) = message.rfind(",p=") {
+        (&message[..index], &message[index + 3..])
+    } else {
+        return Err(failed("malformed client-final-message"));
+    };
+
+    let mut channel_binding = None;
+    let mut nonce = None;
+    for (key, value) in attributes(without_proof) {
+        match key {
+            "c" => channel_binding = Some(value.to_string()),
+            "r" => nonce = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    let proof = STANDARD
+        .decode(proof)
+        .map_err(|_| failed("malformed client proof"))?;
+    match (channel_binding, nonce) {
+        (Some(channel_binding), Some(nonce)) => Ok(ClientFinal {
+            channel_binding,
+            nonce,
+            proof,
+            without_proof: without_proof.to_string(),
+        }),
+        _ => Err(failed("malformed client-final-message")),
+    }
+}
+
+fn random_bytes(len: usize) -> Result<Vec<u8>, MqttBrokerError> {
+    let mut data = vec![0u8; len];
+    SystemRandom::new()
+        .fill(&mut data)
+        .map_err(|_| MqttBrokerError::CommonError("failed to generate random bytes".to_string()))?;
+    Ok(data)
+}
+
+fn salted_password(password: &str, salt: &[u8], iterations: u32) -> Vec<u8> {
+    let mut salted = vec![0u8; digest::SHA256_OUTPUT_LEN];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(iterations).unwrap_or(NonZeroU32::MIN),
+        salt,
+        password.as_bytes(),
+        &mut salted,
+    );
+    salted
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    hmac::sign(&key, data).as_ref().to_vec()
+}
+
+fn client_proof(salted_password: &[u8], auth_message: &str) -> Vec<u8> {
+    let client_key = hmac_sha256(salted_password, b"Client Key");
+    let stored_key = digest::digest(&digest::SHA256, &client_key);
+    let client_signature = hmac_sha256(stored_key.as_ref(), auth_message.as_bytes());
+    client_key
+        .iter()
+        .zip(client_signature.iter())
+        .map(|(a, b)| a ^ b)
+        .collect()
+}
+
+fn verify_client_proof(salted_password: &[u8], auth_message: &str, proof: &[u8]) -> bool {
+    let expected = client_proof(salted_password, auth_message);
+    if expected.len() != proof.len() {
+        return false;
+    }
+    // compare in constant time
+    expected
+        .iter()
+        .zip(proof.iter())
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+        == 0
+}
+
+fn server_signature(salted_password: &[u8], auth_message: &str) -> Vec<u8> {
+    let server_key = hmac_sha256(salted_password, b"Server Key");
+    hmac_sha256(&server_key, auth_message.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use bytes::Bytes;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::user::MqttUser;
+
+    use super::{
+        attributes, client_proof, salted_password, server_signature, ScramSha256Authenticator,
+    };
+    use crate::handler::cache::CacheManager;
+    use crate::security::enhanced::{AuthContext, AuthStep, EnhancedAuthenticator};
+    use crate::security::password::{hash_password, PasswordHashAlgorithm};
+
+    fn build_authenticator() -> ScramSha256Authenticator {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        cache_manager.add_user(MqttUser {
+            username: "lobo".to_string(),
+            password: "pwd123".to_string(),
+            is_superuser: false,
+        });
+        for (username, algorithm) in [
+            ("pbkdf2_user", PasswordHashAlgorithm::Pbkdf2),
+            ("bcrypt_user", PasswordHashAlgorithm::Bcrypt),
+        ] {
+            cache_manager.add_user(MqttUser {
+                username: username.to_string(),
+                password: hash_password("pwd123", algorithm).unwrap(),
+                is_superuser: false,
+            });
+        }
+        ScramSha256Authenticator::new(cache_manager)
+    }
+
+    // Runs the client side of the exchange, returns the server-final-message.
+    async fn authenticate(
+        authenticator: &ScramSha256Authenticator,
+        username: &str,
+        password: &str,
+    ) -> Option<(String, String)> {
+        let mut exchange = authenticator.new_exchange(AuthContext::default());
+        let client_first_bare = format!("n={},r=fyko+d2lbbFgONRv9qkxdawL", username);
+        let server_first = match exchange
+            .step(Some(Bytes::from(format!("n,,{}", client_first_bare))))
+            .await
+            .unwrap()
+        {
+            AuthStep::Continue(data) => String::from_utf8(data.to_vec()).unwrap(),
+            AuthStep::Success { .. } => panic!("the server must send a challenge"),
+        };
+
+        let attrs = attributes(&server_first);
+        let nonce = attrs[0].1;
+        assert!(nonce.starts_with("fyko+d2lbbFgONRv9qkxdawL"));
+        let salt = STANDARD.decode(attrs[1].1).unwrap();
+        let iterations = attrs[2].1.parse::<u32>().unwrap();
+
+        let salted = salted_password(password, &salt, iterations);
+        let without_proof = format!("c=biws,r={}", nonce);
+        let auth_message = format!("{},{},{}", client_first_bare, server_first, without_proof);
+        let proof = STANDARD.encode(client_proof(&salted, &auth_message));
+        let client_final = format!("{},p={}", without_proof, proof);
+
+        match exchange.step(Some(Bytes::from(client_final))).await {
+            Ok(AuthStep::Success { identity, data }) => {
+                let server_final = String::from_utf8(data.unwrap().to_vec()).unwrap();
+                let expected = format!(
+                    "v={}",
+                    STANDARD.encode(server_signature(&salted, &auth_message))
+                );
+                assert_eq!(server_final, expected);
+                Some((identity.username, server_final))
+            }
+            Ok(AuthStep::Continue(_)) => panic!("the exchange must finish"),
+            Err(_) => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn scram_sha_256_test() {
+        let authenticator = build_authenticator();
+        let (username, _) = authenticate(&authenticator, "lobo", "pwd123")
+            .await
+            .unwrap();
+        assert_eq!(username, "lobo".to_string());
+
+        assert!(authenticate(&authenticator, "lobo", "pwd1111")
+            .await
+            .is_none());
+        assert!(authenticate(&authenticator, "nobody", "pwd123")
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn scram_hashed_password_test() {
+        let authenticator = build_authenticator();
+        let (username, _) = authenticate(&authenticator, "pbkdf2_user", "pwd123")
+            .await
+            .unwrap();
+        assert_eq!(username, "pbkdf2_user".to_string());
+        assert!(authenticate(&authenticator, "pbkdf2_user", "pwd1111")
+            .await
+            .is_none());
+
+        // the salted password can not be derived from a bcrypt record
+        assert!(authenticate(&authenticator, "bcrypt_user", "pwd123")
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn scram_malformed_message_test() {
+        let authenticator = build_authenticator();
+        for message in [
+            None,
+            Some("n=lobo,r=abc"),
+            Some("p=tls-unique,,n=lobo,r=abc"),
+            Some("n,,n=lobo"),
+            Some("n,,m=ext,n=lobo,r=abc"),
+        ] {
+            let mut exchange = authenticator.new_exchange(AuthContext::default());
+            assert!(exchange
+                .step(message.map(|message| Bytes::from(message.to_string())))
+                .await
+                .is_err());
+        }
+
+        // a tampered nonce is rejected
+        let mut exchange = authenticator.new_exchange(AuthContext::default());
+        exchange
+            .step(Some(Bytes::from("n,,n=lobo,r=abc")))
+            .await
+            .unwrap();
+        assert!(exchange
+            .step(Some(Bytes::from("c=biws,r=abcdef,p=AAAA")))
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn rfc7677_test_vector() {
+        // https://www.rfc-editor.org/rfc/rfc7677#section-3
+        let salt = STANDARD.decode("W22ZaJ0SNY7soEsUEjb6gQ==").unwrap();
+        let salted = salted_password("pencil", &salt, 4096);
+        let auth_message = "n=user,r=rOprNGfwEbeRWgbNEkqO,\
+            r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096,\
+            c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0";
+        assert_eq!(
+            STANDARD.encode(client_proof(&salted, auth_message)),
+            "dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ="
+        );
+        assert_eq!(
+            STANDARD.encode(server_signature(&salted, auth_message)),
+            "6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4="
+        );
+    }
+}
@@ -11,3 +11,415 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+//! Delegates authentication and authorization to an HTTP service. The broker
+//! POSTs a JSON description of the client (and of the topic for ACL checks), the
+//! service answers 200 with `{"result": "allow" | "deny" | "ignore"}` or 204 for
+//! allow. Any other status is treated as ignore, leaving the decision to the
+//! other providers.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::async_trait;
+use common_base::config::common::HttpAuth;
+use common_base::tools::now_second;
+use dashmap::DashMap;
+use http_client::client::{HttpClient, HttpEndpoint};
+use log::warn;
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use tokio::time::timeout;
+
+use super::Authentication;
+use crate::handler::error::MqttBrokerError;
+
+// Bounds the memory of the result cache, expired results are dropped beyond it.
+const MAX_CACHE_SIZE: usize = 10000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HttpAuthResult {
+    Allow,
+    Deny,
+    Ignore,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct HttpAuthRequest {
+    pub client_id: String,
+    pub username: String,
+    pub password: String,
+    pub peer_addr: String,
+    pub protocol_version: u8,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct HttpAclRequest {
+    pub client_id: String,
+    pub username: String,
+    pub peer_addr: String,
+    pub topic: String,
+    // publish or subscribe
+    pub action: String,
+    pub qos: u8,
+    pub retain: bool,
+}
+
+#[derive(Deserialize)]
+struct HttpAuthResponse {
+    result: String,
+}
+
+pub struct HttpAuthenticator {
+    config: HttpAuth,
+    client: HttpClient,
+    endpoint: HttpEndpoint,
+    acl_endpoint: Option<HttpEndpoint>,
+    timeout_result: HttpAuthResult,
+    // (sha256 of endpoint and request, (result, expire time))
+    cache: DashMap<Vec<u8>, (HttpAuthResult, u64)>,
+}
+
+impl HttpAuthenticator {
+    pub fn new(config: HttpAuth) -> Result<Self, MqttBrokerError> {
+        let timeout_result = match config.timeout_policy.as_str() {
+            "deny" => HttpAuthResult::Deny,
+            "ignore" => HttpAuthResult::Ignore,
+            policy => {
+                return Err(MqttBrokerError::CommonError(format!(
+                    "Invalid HTTP auth timeout policy [{}], expected deny or ignore",
+                    policy
+                )));
+            }
+        };
+        let acl_endpoint = if config.acl_url.is_empty() {
+            None
+        } else {
+            Some(HttpEndpoint::parse(&config.acl_url)?)
+        };
+        Ok(HttpAuthenticator {
+            client: HttpClient::new(&config.tls_ca)?,
+            endpoint: HttpEndpoint::parse(&config.url)?,
+            acl_endpoint,
+            timeout_result,
+            cache: DashMap::with_capacity(8),
+            config,
+        })
+    }
+
+    pub async fn authenticate(&self, request: &HttpAuthRequest) -> HttpAuthResult {
+        self.call(
+            &self.endpoint,
+            serde_json::to_vec(request).unwrap_or_default(),
+        )
+        .await
+    }
+
+    // Ignore when no ACL endpoint is configured.
+    pub async fn authorize(&self, request: &HttpAclRequest) -> HttpAuthResult {
+        if let Some(endpoint) = &self.acl_endpoint {
+            return self
+                .call(endpoint, serde_json::to_vec(request).unwrap_or_default())
+                .await;
+        }
+        HttpAuthResult::Ignore
+    }
+
+    async fn call(&self, endpoint: &HttpEndpoint, body: Vec<u8>) -> HttpAuthResult {
+        // the request contains the password, only its digest is kept
+        let mut key_data = endpoint.addr().into_bytes();
+        key_data.extend_from_slice(endpoint.path_prefix.as_bytes());
+        key_data.extend_from_slice(&body);
+        let key = digest::digest(&digest::SHA256, &key_data).as_ref().to_vec();
+
+        if let Some(cached) = self.cache.get(&key) {
+            if cached.1 > now_second() {
+                return cached.0;
+            }
+        }
+
+        let result = match self.post(endpoint, &body).await {
+            Ok(result) => result,
+            Err(e) => {
+                warn!("HTTP auth request to {} failed, {}", endpoint.addr(), e);
+                return self.timeout_result;
+            }
+        };
+
+        if self.config.cache_ttl_sec > 0 && result != HttpAuthResult::Ignore {
+            if self.cache.len() >= MAX_CACHE_SIZE {
+                let now = now_second();
+                self.cache.retain(|_, (_, expire)| *expire > now);
+            }
+            self.cache
+                .insert(key, (result, now_second() + self.config.cache_ttl_sec));
+        }
+        result
+    }
+
+    async fn post(
+        &self,
+        endpoint: &HttpEndpoint,
+        body: &[u8],
+    ) -> Result<HttpAuthResult, MqttBrokerError> {
+        let mut headers = vec![("Content-Type", "application/json".to_string())];
+        for (name, value) in self.config.headers.iter() {
+            headers.push((name.as_str(), value.clone()));
+        }
+        // HttpEndpoint keeps the path of the url as prefix of every request
+        let path = if endpoint.path_prefix.is_empty() {
+            "/"
+        } else {
+            ""
+        };
+
+        let request = self.client.send(endpoint, "POST", path, &headers, body);
+        let response = match timeout(Duration::from_millis(self.config.timeout_ms), request).await {
+            Ok(response) => response?,
+            Err(_) => {
+                return Err(MqttBrokerError::CommonError(format!(
+                    "request timed out after {} ms",
+                    self.config.timeout_ms
+                )));
+            }
+        };
+
+        match response.status {
+            200 => {
+                let data = serde_json::from_slice::<HttpAuthResponse>(&response.body)?;
+                Ok(match data.result.to_lowercase().as_str() {
+                    "allow" => HttpAuthResult::Allow,
+                    "deny" => HttpAuthResult::Deny,
+                    _ => HttpAuthResult::Ignore,
+                })
+            }
+            204 => Ok(HttpAuthResult::Allow),
+            status => {
+                warn!(
+                    "HTTP auth endpoint {} answered status {}, the request is ignored",
+                    endpoint.addr(),
+                    status
+                );
+                Ok(HttpAuthResult::Ignore)
+            }
+        }
+    }
+}
+
+pub struct HttpAuthentication {
+    authenticator: Arc<HttpAuthenticator>,
+    request: HttpAuthRequest,
+}
+
+impl HttpAuthentication {
+    pub fn new(authenticator: Arc<HttpAuthenticator>, request: HttpAuthRequest) -> Self {
+        HttpAuthentication {
+            authenticator,
+            request,
+        }
+    }
+}
+
+#[async_trait]
+impl Authentication for HttpAuthentication {
+    // Like Plaintext, a client the service does not decide on is reported as an
+    // unknown user so that the caller falls back to the other providers.
+    async fn apply(&self) -> Result<bool, MqttBrokerError> {
+        match self.authenticator.authenticate(&self.request).await {
+            HttpAuthResult::Allow => Ok(true),
+            HttpAuthResult::Deny => Ok(false),
+            HttpAuthResult::Ignore => Err(MqttBrokerError::UserDoesNotExist),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use common_base::config::common::HttpAuth;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::time::sleep;
+
+    use super::{
+        HttpAclRequest, HttpAuthRequest, HttpAuthResult, HttpAuthentication, HttpAuthenticator,
+    };
+    use crate::handler::error::MqttBrokerError;
+    use crate::security::login::Authentication;
+
+    // Decides on the username of the request body: "allow", "deny" and "ignore"
+    // answer with that result, "empty" with 204, "error" with 500 and "slow"
+    // does not answer in time. Returns the address and the request counter.
+    async fn start_server() -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let count = Arc::new(AtomicUsize::new(0));
+
+        let server_count = count.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                server_count.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut data = Vec::new();
+                    let mut buf = [0u8; 1024];
+                    let body = loop {
+                        let n = stream.read(&mut buf).await.unwrap();
+                        if n == 0 {
+                            return;
+                        }
+                        data.extend_from_slice(&buf[..n]);
+                        let header_end =
+                            if let Some(index) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                                index
+                            } else {
+                                continue;
+                            };
+                        let head = String::from_utf8_lossy(&data[..header_end]).to_string();
+                        let len = head
+                            .lines()
+                            .find_map(|line| line.strip_prefix("Content-Length: "))
+                            .and_then(|len| len.parse::<usize>().ok())
+                            .unwrap_or(0);
+                        if data.len() >= header_end + 4 + len {
+                            break data[header_end + 4..header_end + 4 + len].to_vec();
+                        }
+                    };
+
+                    let body: HashMap<String, serde_json::Value> =
+                        serde_json::from_slice(&body).unwrap();
+                    let username = body["username"].as_str().unwrap().to_string();
+                    let (status, content) = match username.as_str() {
+                        "allow" | "deny" | "ignore" => {
+                            (200, format!("{{\"result\":\"{}\"}}", username))
+                        }
+                        "empty" => (204, String::new()),
+                        "slow" => {
+                            sleep(Duration::from_millis(500)).await;
+                            (200, "{\"result\":\"allow\"}".to_string())
+                        }
+                        _ => (500, String::new()),
+                    };
+                    let response = format!(
+                        "HTTP/1.1 {} Status\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status,
+                        content.len(),
+                        content
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        (addr, count)
+    }
+
+    fn build_config(addr: &str, cache_ttl_sec: u64, timeout_policy: &str) -> HttpAuth {
+        HttpAuth {
+            url: format!("http://{}/mqtt/auth", addr),
+            acl_url: format!("http://{}/mqtt/acl", addr),
+            headers: HashMap::new(),
+            timeout_ms: 100,
+            cache_ttl_sec,
+            timeout_policy: timeout_policy.to_string(),
+            tls_ca: "".to_string(),
+        }
+    }
+
+    fn auth_request(username: &str) -> HttpAuthRequest {
+        HttpAuthRequest {
+            client_id: "c1".to_string(),
+            username: username.to_string(),
+            password: "pwd123".to_string(),
+            peer_addr: "127.0.0.1".to_string(),
+            protocol_version: 5,
+        }
+    }
+
+    #[tokio::test]
+    async fn http_auth_result_test() {
+        let (addr, _) = start_server().await;
+        let authenticator =
+            Arc::new(HttpAuthenticator::new(build_config(&addr, 0, "deny")).unwrap());
+
+        for (username, result) in [
+            ("allow", HttpAuthResult::Allow),
+            ("deny", HttpAuthResult::Deny),
+            ("ignore", HttpAuthResult::Ignore),
+            ("empty", HttpAuthResult::Allow),
+            ("error", HttpAuthResult::Ignore),
+            ("slow", HttpAuthResult::Deny),
+        ] {
+            assert_eq!(
+                authenticator.authenticate(&auth_request(username)).await,
+                result
+            );
+        }
+
+        let request = HttpAclRequest {
+            username: "deny".to_string(),
+            topic: "t1".to_string(),
+            action: "publish".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            authenticator.authorize(&request).await,
+            HttpAuthResult::Deny
+        );
+
+        let auth = HttpAuthentication::new(authenticator.clone(), auth_request("allow"));
+        assert!(auth.apply().await.unwrap());
+        let auth = HttpAuthentication::new(authenticator.clone(), auth_request("deny"));
+        assert!(!auth.apply().await.unwrap());
+        let auth = HttpAuthentication::new(authenticator, auth_request("ignore"));
+        assert!(matches!(
+            auth.apply().await,
+            Err(MqttBrokerError::UserDoesNotExist)
+        ));
+    }
+
+    #[tokio::test]
+    async fn http_auth_cache_test() {
+        let (addr, count) = start_server().await;
+        let authenticator = HttpAuthenticator::new(build_config(&addr, 60, "ignore")).unwrap();
+
+        for _ in 0..3 {
+            assert_eq!(
+                authenticator.authenticate(&auth_request("allow")).await,
+                HttpAuthResult::Allow
+            );
+        }
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        // ignore and failures are not cached
+        for _ in 0..2 {
+            assert_eq!(
+                authenticator.authenticate(&auth_request("ignore")).await,
+                HttpAuthResult::Ignore
+            );
+            assert_eq!(
+                authenticator.authenticate(&auth_request("slow")).await,
+                HttpAuthResult::Ignore
+            );
+        }
+        assert_eq!(count.load(Ordering::SeqCst), 5);
+
+        // the password is part of the cache key
+        let mut request = auth_request("allow");
+        request.password = "other".to_string();
+        authenticator.authenticate(&request).await;
+        assert_eq!(count.load(Ordering::SeqCst), 6);
+    }
+
+    #[test]
+    fn http_auth_config_test() {
+        assert!(HttpAuthenticator::new(build_config("127.0.0.1:80", 0, "allow")).is_err());
+        assert!(HttpAuthenticator::new(build_config("127.0.0.1:80", 0, "ignore")).is_ok());
+
+        let mut config = build_config("127.0.0.1:80", 0, "deny");
+        config.url = "https://127.0.0.1/auth".to_string();
+        assert!(HttpAuthenticator::new(config).is_err());
+    }
+}
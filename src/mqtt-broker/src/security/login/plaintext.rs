@@ -19,6 +19,7 @@ use axum::async_trait;
 use super::Authentication;
 use crate::handler::cache::CacheManager;
 use crate::handler::error::MqttBrokerError;
+use crate::security::password::verify_password_blocking;
 
 pub struct Plaintext {
     username: String,
@@ -39,10 +40,12 @@ impl Plaintext {
 #[async_trait]
 impl Authentication for Plaintext {
     async fn apply(&self) -> Result<bool, MqttBrokerError> {
-        if let Some(user) = self.cache_manager.user_info.get(&self.username) {
-            return Ok(user.password == self.password);
-        }
-        return Err(MqttBrokerError::UserDoesNotExist);
+        let stored = if let Some(user) = self.cache_manager.user_info.get(&self.username) {
+            user.password.clone()
+        } else {
+            return Err(MqttBrokerError::UserDoesNotExist);
+        };
+        verify_password_blocking(&stored, &self.password).await
     }
 }
 
@@ -58,6 +61,7 @@ mod test {
     use super::Plaintext;
     use crate::handler::cache::CacheManager;
     use crate::security::login::Authentication;
+    use crate::security::password::{hash_password, PasswordHashAlgorithm};
 
     #[tokio::test]
     pub async fn plaintext_test() {
@@ -94,5 +98,24 @@ mod test {
         let pt = Plaintext::new(login.username, login.password, cache_manager.clone());
         let res = pt.apply().await.unwrap();
         assert!(!res);
+
+        let user = MqttUser {
+            username: "robustmq".to_string(),
+            password: hash_password("pwd123", PasswordHashAlgorithm::Pbkdf2).unwrap(),
+            is_superuser: false,
+        };
+        cache_manager.add_user(user);
+        let pt = Plaintext::new(
+            "robustmq".to_string(),
+            "pwd123".to_string(),
+            cache_manager.clone(),
+        );
+        assert!(pt.apply().await.unwrap());
+        let pt = Plaintext::new(
+            "robustmq".to_string(),
+            "pwd1111".to_string(),
+            cache_manager.clone(),
+        );
+        assert!(!pt.apply().await.unwrap());
     }
 }
@@ -11,3 +11,156 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::sync::Arc;
+
+use common_base::config::common::PskAuth;
+use dashmap::DashMap;
+use grpc_clients::pool::ClientPool;
+
+use crate::handler::error::MqttBrokerError;
+use crate::storage::psk::PskStorage;
+
+// Pre-shared keys of the TLS-PSK clients, looked up by identity during the handshake.
+pub struct PskStore {
+    config: PskAuth,
+    keys: DashMap<String, Vec<u8>>,
+}
+
+impl PskStore {
+    pub fn new(config: PskAuth) -> Self {
+        PskStore {
+            config,
+            keys: DashMap::with_capacity(8),
+        }
+    }
+
+    pub fn key(&self, identity: &str) -> Option<Vec<u8>> {
+        self.keys.get(identity).map(|key| key.clone())
+    }
+
+    // Reloads the keys from the file and the placement center. The current keys are kept
+    // when any source fails to load.
+    pub async fn reload(&self, client_pool: Arc<ClientPool>) -> Result<(), MqttBrokerError> {
+        let mut keys = HashMap::new();
+        if !self.config.file.is_empty() {
+            keys.extend(parse_psk_file(&fs::read_to_string(&self.config.file)?)?);
+        }
+        if self.config.placement {
+            for (identity, key) in PskStorage::new(client_pool).list_psk().await? {
+                keys.insert(identity.clone(), decode_psk(&identity, &key)?);
+            }
+        }
+        self.replace(keys);
+        Ok(())
+    }
+
+    pub(crate) fn replace(&self, keys: HashMap<String, Vec<u8>>) {
+        let identities: HashSet<String> = keys.keys().cloned().collect();
+        for (identity, key) in keys {
+            self.keys.insert(identity, key);
+        }
+        self.keys
+            .retain(|identity, _| identities.contains(identity));
+    }
+}
+
+// One "identity:hex key" entry per line, blank lines and lines starting with # are skipped.
+pub fn parse_psk_file(content: &str) -> Result<HashMap<String, Vec<u8>>, MqttBrokerError> {
+    let mut keys = HashMap::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (identity, key) = line
+            .rsplit_once(':')
+            .ok_or_else(|| MqttBrokerError::CommonError(format!("invalid PSK entry [{}]", line)))?;
+        let identity = identity.trim();
+        if identity.is_empty() {
+            return Err(MqttBrokerError::CommonError(format!(
+                "invalid PSK entry [{}], the identity is empty",
+                line
+            )));
+        }
+        keys.insert(identity.to_string(), decode_psk(identity, key.trim())?);
+    }
+    Ok(keys)
+}
+
+fn decode_psk(identity: &str, key: &str) -> Result<Vec<u8>, MqttBrokerError> {
+    let invalid = || {
+        MqttBrokerError::CommonError(format!(
+            "the key of PSK identity [{}] is not a hex string",
+            identity
+        ))
+    };
+    if key.is_empty() {
+        return Err(invalid());
+    }
+    (0..key.len())
+        .step_by(2)
+        .map(|i| {
+            key.get(i..i + 2)
+                .filter(|pair| pair.bytes().all(|b| b.is_ascii_hexdigit()))
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(invalid)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use common_base::config::common::PskAuth;
+
+    use super::{parse_psk_file, PskStore};
+
+    #[test]
+    fn parse_psk_file_test() {
+        let content = "
+            # sensors
+            sensor-01:00112233445566778899aabbccddeeff
+            sensor-02 : 0A0B0C0D
+
+            urn:dev:ops:3230-10:0102
+        ";
+        let keys = parse_psk_file(content).unwrap();
+        assert_eq!(keys.len(), 3);
+        assert_eq!(
+            keys["sensor-01"],
+            vec![
+                0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+                0xee, 0xff
+            ]
+        );
+        assert_eq!(keys["sensor-02"], vec![0x0a, 0x0b, 0x0c, 0x0d]);
+        // the key follows the last colon
+        assert_eq!(keys["urn:dev:ops:3230-10"], vec![0x01, 0x02]);
+
+        assert!(parse_psk_file("sensor-01").is_err());
+        assert!(parse_psk_file(":0011").is_err());
+        assert!(parse_psk_file("sensor-01:").is_err());
+        assert!(parse_psk_file("sensor-01:001").is_err());
+        assert!(parse_psk_file("sensor-01:zz11").is_err());
+        assert!(parse_psk_file("sensor-01:+1").is_err());
+        assert!(parse_psk_file("sensor-01:ü1").is_err());
+    }
+
+    #[test]
+    fn psk_store_replace_test() {
+        let store = PskStore::new(PskAuth::default());
+        store.replace(HashMap::from([
+            ("sensor-01".to_string(), vec![1]),
+            ("sensor-02".to_string(), vec![2]),
+        ]));
+        assert_eq!(store.key("sensor-01"), Some(vec![1]));
+
+        store.replace(HashMap::from([("sensor-02".to_string(), vec![3])]));
+        assert_eq!(store.key("sensor-01"), None);
+        assert_eq!(store.key("sensor-02"), Some(vec![3]));
+    }
+}
@@ -11,3 +11,213 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use common_base::config::common::X509Auth;
+use serde::{Deserialize, Serialize};
+use x509_parser::extensions::GeneralName;
+use x509_parser::parse_x509_certificate;
+
+use crate::handler::error::MqttBrokerError;
+
+const CERTIFICATE_FIELDS: [&str; 4] = ["cn", "san_dns", "san_email", "san_uri"];
+
+// Identity fields of a client certificate that passed TLS verification.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PeerCertificate {
+    pub common_name: Option<String>,
+    pub san_dns: Vec<String>,
+    pub san_email: Vec<String>,
+    pub san_uri: Vec<String>,
+}
+
+impl PeerCertificate {
+    pub fn from_der(der: &[u8]) -> Result<Self, MqttBrokerError> {
+        let (_, cert) = parse_x509_certificate(der)
+            .map_err(|e| MqttBrokerError::InvalidPeerCertificate(e.to_string()))?;
+
+        let common_name = cert
+            .subject()
+            .iter_common_name()
+            .next()
+            .and_then(|cn| cn.as_str().ok())
+            .map(|cn| cn.to_string());
+
+        let mut peer = PeerCertificate {
+            common_name,
+            ..Default::default()
+        };
+
+        let san = cert
+            .subject_alternative_name()
+            .map_err(|e| MqttBrokerError::InvalidPeerCertificate(e.to_string()))?;
+        if let Some(san) = san {
+            for name in san.value.general_names.iter() {
+                match name {
+                    GeneralName::DNSName(dns) => peer.san_dns.push(dns.to_string()),
+                    GeneralName::RFC822Name(email) => peer.san_email.push(email.to_string()),
+                    GeneralName::URI(uri) => peer.san_uri.push(uri.to_string()),
+                    _ => {}
+                }
+            }
+        }
+        Ok(peer)
+    }
+
+    // The first value of a field, cn, san_dns, san_email or san_uri.
+    pub fn field(&self, field: &str) -> Option<String> {
+        match field {
+            "cn" => self.common_name.clone(),
+            "san_dns" => self.san_dns.first().cloned(),
+            "san_email" => self.san_email.first().cloned(),
+            "san_uri" => self.san_uri.first().cloned(),
+            _ => None,
+        }
+        .filter(|value| !value.is_empty())
+    }
+}
+
+// Maps a trusted client certificate to the username and client id of the connection.
+pub struct X509Authenticator {
+    config: X509Auth,
+}
+
+impl X509Authenticator {
+    pub fn new(config: X509Auth) -> Result<Self, MqttBrokerError> {
+        if !CERTIFICATE_FIELDS.contains(&config.username_field.as_str()) {
+            return Err(MqttBrokerError::CommonError(format!(
+                "unsupported x509 username_field [{}]",
+                config.username_field
+            )));
+        }
+        if !config.client_id_field.is_empty()
+            && !CERTIFICATE_FIELDS.contains(&config.client_id_field.as_str())
+        {
+            return Err(MqttBrokerError::CommonError(format!(
+                "unsupported x509 client_id_field [{}]",
+                config.client_id_field
+            )));
+        }
+        Ok(X509Authenticator { config })
+    }
+
+    pub fn username(&self, peer: &PeerCertificate) -> Option<String> {
+        peer.field(&self.config.username_field)
+    }
+
+    // None keeps the client id sent in CONNECT.
+    pub fn client_id(&self, peer: &PeerCertificate) -> Option<String> {
+        if self.config.client_id_field.is_empty() {
+            return None;
+        }
+        peer.field(&self.config.client_id_field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common_base::config::common::X509Auth;
+    use rustls_pemfile::certs;
+
+    use super::{PeerCertificate, X509Authenticator};
+
+    // CN=device-01 with a DNS, an email and an URI subject alternative name
+    const DEVICE_CERT: &str = "-----BEGIN CERTIFICATE-----
+MIICADCCAaWgAwIBAgIUPHypSmhHe7CeP4LpaTOCNmiL2GIwCgYIKoZIzj0EAwIw
+JzERMA8GA1UECgwIUm9idXN0TVExEjAQBgNVBAMMCWRldmljZS0wMTAgFw0yNjEw
+MTgwOTQ5MTVaGA8yMTI2MDkyNDA5NDkxNVowJzERMA8GA1UECgwIUm9idXN0TVEx
+EjAQBgNVBAMMCWRldmljZS0wMTBZMBMGByqGSM49AgEGCCqGSM49AwEHA0IABI5l
+/hLhpugu1lxxeljJhFT4Us2JSzg54Ds64eT+cdRlO0VpIjOvSu2EUf60tCHZoayj
+DrLK9izbXSoX/ZYsewajgawwgakwHQYDVR0OBBYEFGXiomqp2vFGYAnelGClYvh1
+5duWMB8GA1UdIwQYMBaAFGXiomqp2vFGYAnelGClYvh15duWMA8GA1UdEwEB/wQF
+MAMBAf8wVgYDVR0RBE8wTYIWZGV2aWNlLTAxLnJvYnVzdG1xLmNvbYEWZGV2aWNl
+LTAxQHJvYnVzdG1xLmNvbYYbc3BpZmZlOi8vcm9idXN0bXEvZGV2aWNlLTAxMAoG
+CCqGSM49BAMCA0kAMEYCIQDjNxK14v5/2DqwbOTXPLGYhuC9+0yvbxE+8ZKHXL9c
+JQIhANlbURGcf2e5xhCiifW/8kqYMu5jlytA4Snkgh163V6r
+-----END CERTIFICATE-----
+";
+
+    // O=RobustMQ, no common name and no subject alternative name
+    const ANONYMOUS_CERT: &str = "-----BEGIN CERTIFICATE-----
+MIIBfTCCASOgAwIBAgIURve7kUowwPa2XPgK4oBf/ENi9d8wCgYIKoZIzj0EAwIw
+EzERMA8GA1UECgwIUm9idXN0TVEwIBcNMjYxMDE4MDk0OTE1WhgPMjEyNjA5MjQw
+OTQ5MTVaMBMxETAPBgNVBAoMCFJvYnVzdE1RMFkwEwYHKoZIzj0CAQYIKoZIzj0D
+AQcDQgAETq0g2E3TOgETbV042g3lyN23/PFMjdAaCe92nG9pQQdSeWcVL4dXcK20
+vJiWrraopnkrwlVQWAbTqyWZ2qoCVaNTMFEwHQYDVR0OBBYEFIlVxfbBsuzufw01
+8v1xzKS7Q7JyMB8GA1UdIwQYMBaAFIlVxfbBsuzufw018v1xzKS7Q7JyMA8GA1Ud
+EwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIhANfD8aGDS40EcPTo5al8rVOA
+xNKXlzHzEiuZEXR13/UYAiBc8Mn0vcHUetXQ+k4PNRvJujUWtWmSOfNhNeyNf7Fz
+0Q==
+-----END CERTIFICATE-----
+";
+
+    fn peer_certificate(pem: &str) -> PeerCertificate {
+        let der = certs(&mut pem.as_bytes()).next().unwrap().unwrap();
+        PeerCertificate::from_der(der.as_ref()).unwrap()
+    }
+
+    #[test]
+    fn peer_certificate_test() {
+        let peer = peer_certificate(DEVICE_CERT);
+        assert_eq!(peer.common_name, Some("device-01".to_string()));
+        assert_eq!(peer.san_dns, vec!["device-01.robustmq.com".to_string()]);
+        assert_eq!(peer.san_email, vec!["device-01@robustmq.com".to_string()]);
+        assert_eq!(
+            peer.san_uri,
+            vec!["spiffe://robustmq/device-01".to_string()]
+        );
+        assert_eq!(peer.field("cn"), Some("device-01".to_string()));
+        assert_eq!(
+            peer.field("san_dns"),
+            Some("device-01.robustmq.com".to_string())
+        );
+        assert_eq!(peer.field("unknown"), None);
+
+        let peer = peer_certificate(ANONYMOUS_CERT);
+        assert_eq!(peer, PeerCertificate::default());
+        assert_eq!(peer.field("cn"), None);
+
+        assert!(PeerCertificate::from_der(b"not a certificate").is_err());
+    }
+
+    #[test]
+    fn x509_authenticator_test() {
+        let peer = peer_certificate(DEVICE_CERT);
+
+        let authenticator = X509Authenticator::new(X509Auth {
+            username_field: "cn".to_string(),
+            client_id_field: "".to_string(),
+        })
+        .unwrap();
+        assert_eq!(authenticator.username(&peer), Some("device-01".to_string()));
+        assert_eq!(authenticator.client_id(&peer), None);
+
+        let authenticator = X509Authenticator::new(X509Auth {
+            username_field: "san_email".to_string(),
+            client_id_field: "san_uri".to_string(),
+        })
+        .unwrap();
+        assert_eq!(
+            authenticator.username(&peer),
+            Some("device-01@robustmq.com".to_string())
+        );
+        assert_eq!(
+            authenticator.client_id(&peer),
+            Some("spiffe://robustmq/device-01".to_string())
+        );
+        assert_eq!(
+            authenticator.username(&peer_certificate(ANONYMOUS_CERT)),
+            None
+        );
+
+        assert!(X509Authenticator::new(X509Auth {
+            username_field: "ou".to_string(),
+            client_id_field: "".to_string(),
+        })
+        .is_err());
+        assert!(X509Authenticator::new(X509Auth {
+            username_field: "cn".to_string(),
+            client_id_field: "serial".to_string(),
+        })
+        .is_err());
+    }
+}
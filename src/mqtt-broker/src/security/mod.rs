@@ -22,16 +22,29 @@ use axum::async_trait;
 use common_base::config::broker_mqtt::broker_mqtt_conf;
 use common_base::config::common::Auth;
 use dashmap::DashMap;
+use enhanced::jwt::JwtEnhancedAuthenticator;
+use enhanced::scram::ScramSha256Authenticator;
+use enhanced::EnhancedAuthManager;
 use grpc_clients::pool::ClientPool;
+use log::warn;
+use login::http::{
+    HttpAclRequest, HttpAuthRequest, HttpAuthResult, HttpAuthentication, HttpAuthenticator,
+};
+use login::jwt::{is_jwt, JwtAuthenticator};
 use login::plaintext::Plaintext;
+use login::psk::PskStore;
+use login::x509::{PeerCertificate, X509Authenticator};
 use login::Authentication;
 use metadata_struct::acl::mqtt_acl::{MqttAcl, MqttAclAction, MqttAclResourceType};
 use metadata_struct::acl::mqtt_blacklist::MqttAclBlackList;
 use metadata_struct::mqtt::connection::MQTTConnection;
 use metadata_struct::mqtt::user::MqttUser;
 use mysql::MySQLAuthStorageAdapter;
+use password::{hash_password_blocking, PasswordHashAlgorithm};
 use placement::PlacementAuthStorageAdapter;
-use protocol::mqtt::common::{ConnectProperties, Login, QoS, Subscribe};
+use protocol::mqtt::common::{ConnectProperties, Login, MqttProtocol, QoS, Subscribe};
+use redis::RedisAuthStorageAdapter;
+use serde::{Deserialize, Serialize};
 use storage_adapter::StorageType;
 
 use crate::handler::cache::CacheManager;
@@ -39,8 +52,10 @@ use crate::handler::error::MqttBrokerError;
 use crate::subscribe::sub_common::get_sub_topic_id_list;
 
 pub mod acl;
+pub mod enhanced;
 pub mod login;
 pub mod mysql;
+pub mod password;
 pub mod placement;
 pub mod redis;
 
@@ -67,25 +82,114 @@ pub trait AuthStorageAdapter {
     async fn delete_blacklist(&self, blacklist: MqttAclBlackList) -> Result<(), MqttBrokerError>;
 }
 
+// Who a client authenticated as.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LoginIdentity {
+    pub username: String,
+    // ACL rules that only apply to this connection
+    pub acl: Vec<MqttAcl>,
+}
+
+// What the TLS handshake proved about a client.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TlsIdentity {
+    // Client certificate of a mutual TLS connection
+    Certificate(PeerCertificate),
+    // Identity of a TLS-PSK connection
+    Psk(String),
+}
+
 pub struct AuthDriver {
     cache_manager: Arc<CacheManager>,
     client_pool: Arc<ClientPool>,
     driver: Arc<dyn AuthStorageAdapter + Send + 'static + Sync>,
+    jwt: Option<Arc<JwtAuthenticator>>,
+    http: Option<Arc<HttpAuthenticator>>,
+    x509: Option<Arc<X509Authenticator>>,
+    psk: Option<Arc<PskStore>>,
+    password_hash: PasswordHashAlgorithm,
+    enhanced_auth: EnhancedAuthManager,
 }
 
 impl AuthDriver {
     pub fn new(cache_manager: Arc<CacheManager>, client_pool: Arc<ClientPool>) -> AuthDriver {
         let conf = broker_mqtt_conf();
-        let driver = match build_driver(client_pool.clone(), conf.auth.clone()) {
+        AuthDriver::new_with_auth(cache_manager, client_pool, conf.auth.clone())
+    }
+
+    pub fn new_with_auth(
+        cache_manager: Arc<CacheManager>,
+        client_pool: Arc<ClientPool>,
+        auth: Auth,
+    ) -> AuthDriver {
+        let driver = match build_driver(client_pool.clone(), auth.clone()) {
             Ok(driver) => driver,
             Err(e) => {
                 panic!("{}", e.to_string());
             }
         };
+        let jwt = if let Some(jwt_conf) = auth.jwt.clone() {
+            match JwtAuthenticator::new(jwt_conf) {
+                Ok(jwt) => Some(Arc::new(jwt)),
+                Err(e) => {
+                    panic!("{}", e.to_string());
+                }
+            }
+        } else {
+            None
+        };
+
+        let http = if let Some(http_conf) = auth.http.clone() {
+            match HttpAuthenticator::new(http_conf) {
+                Ok(http) => Some(Arc::new(http)),
+                Err(e) => {
+                    panic!("{}", e.to_string());
+                }
+            }
+        } else {
+            None
+        };
+
+        let x509 = if let Some(x509_conf) = auth.x509.clone() {
+            match X509Authenticator::new(x509_conf) {
+                Ok(x509) => Some(Arc::new(x509)),
+                Err(e) => {
+                    panic!("{}", e.to_string());
+                }
+            }
+        } else {
+            None
+        };
+
+        let psk = auth
+            .psk
+            .clone()
+            .map(|psk_conf| Arc::new(PskStore::new(psk_conf)));
+
+        let password_hash = match PasswordHashAlgorithm::from_str(&auth.password_hash) {
+            Ok(algorithm) => algorithm,
+            Err(e) => {
+                panic!("{}", e.to_string());
+            }
+        };
+
+        let mut enhanced_auth = EnhancedAuthManager::new();
+        enhanced_auth.register(Arc::new(ScramSha256Authenticator::new(
+            cache_manager.clone(),
+        )));
+        if let Some(jwt) = jwt.clone() {
+            enhanced_auth.register(Arc::new(JwtEnhancedAuthenticator::new(jwt)));
+        }
         AuthDriver {
             cache_manager,
             driver,
             client_pool,
+            jwt,
+            http,
+            x509,
+            psk,
+            password_hash,
+            enhanced_auth,
         }
     }
 
@@ -95,6 +199,43 @@ impl AuthDriver {
         Ok(())
     }
 
+    pub fn enhanced_auth(&self) -> &EnhancedAuthManager {
+        &self.enhanced_auth
+    }
+
+    // Hashes a new password, with the configured algorithm when none is given.
+    pub async fn hash_password(
+        &self,
+        password: &str,
+        algorithm: &str,
+    ) -> Result<String, MqttBrokerError> {
+        let algorithm = if algorithm.is_empty() {
+            self.password_hash
+        } else {
+            PasswordHashAlgorithm::from_str(algorithm)?
+        };
+        hash_password_blocking(password, algorithm).await
+    }
+
+    // The client id taken from the client certificate, when configured.
+    pub fn certificate_client_id(&self, tls_identity: &Option<TlsIdentity>) -> Option<String> {
+        match (&self.x509, tls_identity) {
+            (Some(x509), Some(TlsIdentity::Certificate(peer))) => x509.client_id(peer),
+            _ => None,
+        }
+    }
+
+    pub fn psk_store(&self) -> Option<Arc<PskStore>> {
+        self.psk.clone()
+    }
+
+    pub async fn update_psk_cache(&self) -> Result<(), MqttBrokerError> {
+        if let Some(psk) = &self.psk {
+            psk.reload(self.client_pool.clone()).await?;
+        }
+        Ok(())
+    }
+
     pub async fn read_all_user(&self) -> Result<DashMap<String, MqttUser>, MqttBrokerError> {
         self.driver.read_all_user().await
     }
@@ -140,25 +281,103 @@ impl AuthDriver {
         Ok(())
     }
 
+    // Returns None when the client is not allowed to connect.
     pub async fn check_login_auth(
         &self,
+        client_id: &str,
         login: &Option<Login>,
         _: &Option<ConnectProperties>,
-        _: &SocketAddr,
-    ) -> Result<bool, MqttBrokerError> {
+        addr: &SocketAddr,
+        protocol: &MqttProtocol,
+        tls_identity: &Option<TlsIdentity>,
+    ) -> Result<Option<LoginIdentity>, MqttBrokerError> {
         let cluster = self.cache_manager.get_cluster_info();
 
         if cluster.security.secret_free_login {
-            return Ok(true);
+            let username = if let Some(info) = login {
+                info.username.clone()
+            } else {
+                "".to_string()
+            };
+            return Ok(Some(LoginIdentity {
+                username,
+                ..Default::default()
+            }));
+        }
+
+        // A certificate or a pre-shared key checked during the TLS handshake logs in without a password.
+        match tls_identity {
+            Some(TlsIdentity::Certificate(peer)) => {
+                if let Some(x509) = &self.x509 {
+                    if let Some(username) = x509.username(peer) {
+                        return Ok(Some(LoginIdentity {
+                            username,
+                            ..Default::default()
+                        }));
+                    }
+                    warn!(
+                        "client [{}] certificate has no username field, falling back to password login",
+                        client_id
+                    );
+                }
+            }
+            Some(TlsIdentity::Psk(identity)) => {
+                return Ok(Some(LoginIdentity {
+                    username: identity.clone(),
+                    ..Default::default()
+                }));
+            }
+            None => {}
         }
 
         if let Some(info) = login {
-            return self
+            if let Some(jwt) = &self.jwt {
+                // A token that fails verification is rejected, it is not tried as a password.
+                if is_jwt(&info.password) {
+                    return match jwt.verify(&info.password, client_id, Some(&info.username)) {
+                        Ok(identity) => Ok(Some(identity)),
+                        Err(e) => {
+                            warn!("client [{}] JWT login failed, {}", client_id, e);
+                            Ok(None)
+                        }
+                    };
+                }
+            }
+
+            if let Some(http) = &self.http {
+                let request = HttpAuthRequest {
+                    client_id: client_id.to_string(),
+                    username: info.username.clone(),
+                    password: info.password.clone(),
+                    peer_addr: addr.ip().to_string(),
+                    protocol_version: protocol.clone().into(),
+                };
+                match HttpAuthentication::new(http.clone(), request).apply().await {
+                    Ok(true) => {
+                        return Ok(Some(LoginIdentity {
+                            username: info.username.clone(),
+                            ..Default::default()
+                        }));
+                    }
+                    Ok(false) => return Ok(None),
+                    // ignored by the service
+                    Err(MqttBrokerError::UserDoesNotExist) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+
+            if self
                 .plaintext_check_login(&info.username, &info.password)
-                .await;
+                .await?
+            {
+                return Ok(Some(LoginIdentity {
+                    username: info.username.clone(),
+                    ..Default::default()
+                }));
+            }
         }
 
-        Ok(false)
+        Ok(None)
     }
 
     pub async fn save_acl(&self, acl: MqttAcl) -> Result<(), MqttBrokerError> {
@@ -214,14 +433,18 @@ impl AuthDriver {
         retain: bool,
         qos: QoS,
     ) -> bool {
-        is_allow_acl(
+        if !is_allow_acl(
             &self.cache_manager,
             connection,
             topic_name,
             MqttAclAction::Publish,
             retain,
             qos,
-        )
+        ) {
+            return false;
+        }
+        self.http_allow_acl(connection, topic_name, "publish", qos, retain)
+            .await
     }
 
     pub async fn allow_subscribe(
@@ -243,10 +466,43 @@ impl AuthDriver {
                     return false;
                 }
             }
+
+            if !self
+                .http_allow_acl(connection, &filter.path, "subscribe", filter.qos, false)
+                .await
+            {
+                return false;
+            }
         }
         true
     }
 
+    // Only an explicit deny of the HTTP service rejects the request.
+    async fn http_allow_acl(
+        &self,
+        connection: &MQTTConnection,
+        topic: &str,
+        action: &str,
+        qos: QoS,
+        retain: bool,
+    ) -> bool {
+        let http = if let Some(http) = &self.http {
+            http
+        } else {
+            return true;
+        };
+        let request = HttpAclRequest {
+            client_id: connection.client_id.clone(),
+            username: connection.login_user.clone(),
+            peer_addr: peer_ip(&connection.source_ip_addr),
+            topic: topic.to_string(),
+            action: action.to_string(),
+            qos: qos.into(),
+            retain,
+        };
+        http.authorize(&request).await != HttpAuthResult::Deny
+    }
+
     async fn plaintext_check_login(
         &self,
         username: &str,
@@ -260,13 +516,14 @@ impl AuthDriver {
         match plaintext.apply().await {
             Ok(flag) => {
                 if flag {
+                    self.upgrade_plaintext_password(username, password).await;
                     return Ok(true);
                 }
             }
             Err(e) => {
                 // If the user does not exist, try to get the user information from the storage layer
                 if e.to_string() == MqttBrokerError::UserDoesNotExist.to_string() {
-                    return self.try_get_check_user_by_driver(username).await;
+                    return self.try_get_check_user_by_driver(username, password).await;
                 }
                 return Err(e);
             }
@@ -274,23 +531,68 @@ impl AuthDriver {
         Ok(false)
     }
 
-    async fn try_get_check_user_by_driver(&self, username: &str) -> Result<bool, MqttBrokerError> {
+    async fn try_get_check_user_by_driver(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<bool, MqttBrokerError> {
         if let Some(user) = self.driver.get_user(username.to_owned()).await? {
             self.cache_manager.add_user(user.clone());
 
             let plaintext = Plaintext::new(
                 user.username.clone(),
-                user.password.clone(),
+                password.to_owned(),
                 self.cache_manager.clone(),
             );
 
             if plaintext.apply().await? {
+                self.upgrade_plaintext_password(username, password).await;
                 return Ok(true);
             }
         }
 
         Ok(false)
     }
+
+    // Plaintext records written before passwords were hashed are rewritten with the
+    // configured algorithm the first time their owner logs in.
+    async fn upgrade_plaintext_password(&self, username: &str, password: &str) {
+        if self.password_hash == PasswordHashAlgorithm::Plain {
+            return;
+        }
+        let mut user = if let Some(user) = self.cache_manager.user_info.get(username) {
+            user.clone()
+        } else {
+            return;
+        };
+        if PasswordHashAlgorithm::of(&user.password) != PasswordHashAlgorithm::Plain {
+            return;
+        }
+
+        user.password = match hash_password_blocking(password, self.password_hash).await {
+            Ok(hashed) => hashed,
+            Err(e) => {
+                warn!("failed to hash the password of user [{}], {}", username, e);
+                return;
+            }
+        };
+        match self.driver.save_user(user.clone()).await {
+            Ok(()) => self.cache_manager.add_user(user),
+            Err(e) => warn!(
+                "failed to migrate the plaintext password of user [{}], {}",
+                username, e
+            ),
+        }
+    }
+}
+
+// The connection keeps ip:port, HTTP requests carry the ip only so that the result
+// cache is shared by the connections of a client.
+fn peer_ip(source_ip_addr: &str) -> String {
+    match source_ip_addr.parse::<SocketAddr>() {
+        Ok(addr) => addr.ip().to_string(),
+        Err(_) => source_ip_addr.to_string(),
+    }
 }
 
 pub fn build_driver(
@@ -309,6 +611,16 @@ pub fn build_driver(
         return Ok(Arc::new(driver));
     }
 
+    if matches!(storage_type, StorageType::Redis) {
+        let config = auth.redis.clone().ok_or_else(|| {
+            MqttBrokerError::InvalidRedisAuthConfig(
+                "the [auth.redis] section is missing".to_string(),
+            )
+        })?;
+        let driver = RedisAuthStorageAdapter::new(config)?;
+        return Ok(Arc::new(driver));
+    }
+
     Err(MqttBrokerError::UnavailableStorageType)
 }
 
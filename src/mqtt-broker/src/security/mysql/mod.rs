@@ -93,7 +93,7 @@ impl AuthStorageAdapter for MySQLAuthStorageAdapter {
     async fn save_user(&self, user_info: MqttUser) -> Result<(), MqttBrokerError> {
         let mut conn = self.pool.get_conn()?;
         let sql = format!(
-            "insert into {} ( `username`, `password`, `is_superuser`, `salt`) values ('{}', '{}', '{}', null);",
+            "insert into {} ( `username`, `password`, `is_superuser`, `salt`) values ('{}', '{}', '{}', null) on duplicate key update `password` = values(`password`), `is_superuser` = values(`is_superuser`);",
             self.table_user(),
             user_info.username,
             user_info.password,
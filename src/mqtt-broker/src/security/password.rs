@@ -0,0 +1,323 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::num::NonZeroU32;
+use std::str::FromStr;
+
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use base64::Engine;
+use common_base::tools::constant_time_eq;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{digest, pbkdf2};
+
+use crate::handler::error::MqttBrokerError;
+
+const BCRYPT_COST: u32 = 10;
+const PBKDF2_ITERATIONS: u32 = 100_000;
+const SALT_LEN: usize = 16;
+
+const PBKDF2_TAG: &str = "$pbkdf2-sha256$";
+const SHA256_TAG: &str = "$sha256$";
+const BCRYPT_TAGS: [&str; 3] = ["$2a$", "$2b$", "$2y$"];
+
+// How a stored password is protected. Hashed passwords carry their algorithm tag and salt,
+// records without a tag are plaintext passwords written before hashing was supported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PasswordHashAlgorithm {
+    Plain,
+    Bcrypt,
+    Pbkdf2,
+    Sha256,
+}
+
+impl PasswordHashAlgorithm {
+    pub fn of(stored: &str) -> Self {
+        if BCRYPT_TAGS.iter().any(|tag| stored.starts_with(tag)) {
+            PasswordHashAlgorithm::Bcrypt
+        } else if stored.starts_with(PBKDF2_TAG) {
+            PasswordHashAlgorithm::Pbkdf2
+        } else if stored.starts_with(SHA256_TAG) {
+            PasswordHashAlgorithm::Sha256
+        } else {
+            PasswordHashAlgorithm::Plain
+        }
+    }
+}
+
+impl FromStr for PasswordHashAlgorithm {
+    type Err = MqttBrokerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(PasswordHashAlgorithm::Plain),
+            "bcrypt" => Ok(PasswordHashAlgorithm::Bcrypt),
+            "pbkdf2" => Ok(PasswordHashAlgorithm::Pbkdf2),
+            "sha256" => Ok(PasswordHashAlgorithm::Sha256),
+            _ => Err(MqttBrokerError::UnsupportedPasswordHash(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for PasswordHashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                PasswordHashAlgorithm::Plain => "plain",
+                PasswordHashAlgorithm::Bcrypt => "bcrypt",
+                PasswordHashAlgorithm::Pbkdf2 => "pbkdf2",
+                PasswordHashAlgorithm::Sha256 => "sha256",
+            }
+        )
+    }
+}
+
+pub fn hash_password(
+    password: &str,
+    algorithm: PasswordHashAlgorithm,
+) -> Result<String, MqttBrokerError> {
+    match algorithm {
+        PasswordHashAlgorithm::Plain => Ok(password.to_string()),
+        PasswordHashAlgorithm::Bcrypt => bcrypt::hash(password, BCRYPT_COST)
+            .map_err(|e| MqttBrokerError::CommonError(e.to_string())),
+        PasswordHashAlgorithm::Pbkdf2 => {
+            let salt = random_salt()?;
+            let hash = pbkdf2_sha256(password, &salt, PBKDF2_ITERATIONS);
+            Ok(format!(
+                "{}i={}${}${}",
+                PBKDF2_TAG,
+                PBKDF2_ITERATIONS,
+                STANDARD_NO_PAD.encode(salt),
+                STANDARD_NO_PAD.encode(hash)
+            ))
+        }
+        PasswordHashAlgorithm::Sha256 => {
+            let salt = random_salt()?;
+            Ok(format!(
+                "{}{}${}",
+                SHA256_TAG,
+                STANDARD_NO_PAD.encode(salt),
+                STANDARD_NO_PAD.encode(salted_sha256(password, &salt))
+            ))
+        }
+    }
+}
+
+// bcrypt and pbkdf2 are slow on purpose, callers on the async workers hash and verify
+// passwords on the blocking pool instead.
+pub async fn hash_password_blocking(
+    password: &str,
+    algorithm: PasswordHashAlgorithm,
+) -> Result<String, MqttBrokerError> {
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || hash_password(&password, algorithm))
+        .await
+        .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?
+}
+
+pub async fn verify_password_blocking(
+    stored: &str,
+    password: &str,
+) -> Result<bool, MqttBrokerError> {
+    let stored = stored.to_string();
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || verify_password(&stored, &password))
+        .await
+        .map_err(|e| MqttBrokerError::CommonError(e.to_string()))
+}
+
+// Checks a password against a stored record, a malformed record never matches.
+pub fn verify_password(stored: &str, password: &str) -> bool {
+    match PasswordHashAlgorithm::of(stored) {
+        PasswordHashAlgorithm::Plain => constant_time_eq(stored.as_bytes(), password.as_bytes()),
+        PasswordHashAlgorithm::Bcrypt => bcrypt::verify(password, stored).unwrap_or(false),
+        PasswordHashAlgorithm::Pbkdf2 => verify_pbkdf2(&stored[PBKDF2_TAG.len()..], password),
+        PasswordHashAlgorithm::Sha256 => verify_sha256(&stored[SHA256_TAG.len()..], password),
+    }
+}
+
+// "i=<iterations>$<salt>$<hash>"
+fn verify_pbkdf2(encoded: &str, password: &str) -> bool {
+    let (iterations, salt, hash) = if let Some(record) = parse_pbkdf2(encoded) {
+        record
+    } else {
+        return false;
+    };
+    pbkdf2::verify(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        &salt,
+        password.as_bytes(),
+        &hash,
+    )
+    .is_ok()
+}
+
+fn parse_pbkdf2(encoded: &str) -> Option<(NonZeroU32, Vec<u8>, Vec<u8>)> {
+    let parts: Vec<&str> = encoded.split('$').collect();
+    if parts.len() != 3 {
+        return None;
+    }
+    let iterations = parts[0]
+        .strip_prefix("i=")
+        .and_then(|i| i.parse::<u32>().ok())
+        .and_then(NonZeroU32::new)?;
+    let salt = STANDARD_NO_PAD.decode(parts[1]).ok()?;
+    let hash = STANDARD_NO_PAD.decode(parts[2]).ok()?;
+    Some((iterations, salt, hash))
+}
+
+// A PBKDF2-SHA256 hash is the SCRAM-SHA-256 SaltedPassword for the same salt and
+// iteration count, so SCRAM can run against these records without the password.
+// Returns (salt, iterations, salted password).
+pub fn scram_salted_password(stored: &str) -> Option<(Vec<u8>, u32, Vec<u8>)> {
+    if PasswordHashAlgorithm::of(stored) != PasswordHashAlgorithm::Pbkdf2 {
+        return None;
+    }
+    let (iterations, salt, hash) = parse_pbkdf2(&stored[PBKDF2_TAG.len()..])?;
+    if hash.len() != digest::SHA256_OUTPUT_LEN {
+        return None;
+    }
+    Some((salt, iterations.get(), hash))
+}
+
+// "<salt>$<hash>"
+fn verify_sha256(encoded: &str, password: &str) -> bool {
+    let (salt, hash) = match encoded.split_once('$') {
+        Some((salt, hash)) => (salt, hash),
+        None => return false,
+    };
+    match (STANDARD_NO_PAD.decode(salt), STANDARD_NO_PAD.decode(hash)) {
+        (Ok(salt), Ok(hash)) => constant_time_eq(&salted_sha256(password, &salt), &hash),
+        _ => false,
+    }
+}
+
+fn pbkdf2_sha256(password: &str, salt: &[u8], iterations: u32) -> Vec<u8> {
+    let mut hash = vec![0u8; digest::SHA256_OUTPUT_LEN];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(iterations).unwrap(),
+        salt,
+        password.as_bytes(),
+        &mut hash,
+    );
+    hash
+}
+
+fn salted_sha256(password: &str, salt: &[u8]) -> Vec<u8> {
+    let mut context = digest::Context::new(&digest::SHA256);
+    context.update(salt);
+    context.update(password.as_bytes());
+    context.finish().as_ref().to_vec()
+}
+
+fn random_salt() -> Result<[u8; SALT_LEN], MqttBrokerError> {
+    let mut salt = [0u8; SALT_LEN];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .map_err(|_| MqttBrokerError::CommonError("failed to generate a salt".to_string()))?;
+    Ok(salt)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::{hash_password, scram_salted_password, verify_password, PasswordHashAlgorithm};
+
+    #[test]
+    fn hash_password_test() {
+        for algorithm in [
+            PasswordHashAlgorithm::Bcrypt,
+            PasswordHashAlgorithm::Pbkdf2,
+            PasswordHashAlgorithm::Sha256,
+        ] {
+            let stored = hash_password("pwd123", algorithm).unwrap();
+            assert_ne!(stored, "pwd123");
+            assert_eq!(PasswordHashAlgorithm::of(&stored), algorithm);
+            assert!(verify_password(&stored, "pwd123"));
+            assert!(!verify_password(&stored, "pwd1234"));
+            assert!(!verify_password(&stored, ""));
+
+            // every hash has its own salt
+            assert_ne!(stored, hash_password("pwd123", algorithm).unwrap());
+        }
+
+        let stored = hash_password("pwd123", PasswordHashAlgorithm::Plain).unwrap();
+        assert_eq!(stored, "pwd123");
+        assert!(verify_password(&stored, "pwd123"));
+    }
+
+    #[test]
+    fn verify_password_test() {
+        // records written before hashing was supported
+        assert_eq!(
+            PasswordHashAlgorithm::of("robustmq@2024"),
+            PasswordHashAlgorithm::Plain
+        );
+        assert!(verify_password("robustmq@2024", "robustmq@2024"));
+        assert!(!verify_password("robustmq@2024", "robustmq@2025"));
+
+        // python: hashlib.pbkdf2_hmac("sha256", b"pwd123", b"robustmq-salt-01", 1000)
+        let stored = "$pbkdf2-sha256$i=1000$cm9idXN0bXEtc2FsdC0wMQ$\
+                      Tszm/jSvHYm1NAX8ATzyDN80HarVmwHN6uZ4MpyeAa4";
+        assert!(verify_password(stored, "pwd123"));
+
+        // python: hashlib.sha256(b"robustmq-salt-01" + b"pwd123")
+        let stored = "$sha256$cm9idXN0bXEtc2FsdC0wMQ$p4ikc9o9V/W7TI9sgwHz9XoTuCMFGn86y/iSVYO+cXE";
+        assert!(verify_password(stored, "pwd123"));
+
+        // malformed records never match
+        assert!(!verify_password("$sha256$", ""));
+        assert!(!verify_password("$sha256$not base64$", "pwd123"));
+        assert!(!verify_password(
+            "$pbkdf2-sha256$i=0$c2FsdA$aGFzaA",
+            "pwd123"
+        ));
+        assert!(!verify_password("$pbkdf2-sha256$c2FsdA$aGFzaA", "pwd123"));
+        assert!(!verify_password("$2b$10$invalid", "pwd123"));
+    }
+
+    #[test]
+    fn scram_salted_password_test() {
+        let stored = "$pbkdf2-sha256$i=1000$cm9idXN0bXEtc2FsdC0wMQ$\
+                      Tszm/jSvHYm1NAX8ATzyDN80HarVmwHN6uZ4MpyeAa4";
+        let (salt, iterations, salted) = scram_salted_password(stored).unwrap();
+        assert_eq!(salt, b"robustmq-salt-01".to_vec());
+        assert_eq!(iterations, 1000);
+        assert_eq!(salted.len(), 32);
+
+        for algorithm in [
+            PasswordHashAlgorithm::Plain,
+            PasswordHashAlgorithm::Bcrypt,
+            PasswordHashAlgorithm::Sha256,
+        ] {
+            let stored = hash_password("pwd123", algorithm).unwrap();
+            assert!(scram_salted_password(&stored).is_none());
+        }
+        assert!(scram_salted_password("$pbkdf2-sha256$i=1000$c2FsdA$aGFzaA").is_none());
+    }
+
+    #[test]
+    fn password_hash_algorithm_test() {
+        for name in ["plain", "bcrypt", "pbkdf2", "sha256"] {
+            let algorithm = PasswordHashAlgorithm::from_str(name).unwrap();
+            assert_eq!(algorithm.to_string(), name);
+        }
+        assert!(PasswordHashAlgorithm::from_str("md5").is_err());
+    }
+}
@@ -0,0 +1,241 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An in-process RESP stand-in implementing the hash, set and SCAN commands
+//! the Redis auth storage uses.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+
+use bytes::BytesMut;
+use mqtt_bridge_redis::error::RedisBridgeError;
+use mqtt_bridge_redis::resp::{decode_value, encode_value, RespValue};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+#[derive(Default)]
+struct MockState {
+    hashes: BTreeMap<Vec<u8>, BTreeMap<Vec<u8>, Vec<u8>>>,
+    sets: BTreeMap<Vec<u8>, BTreeSet<Vec<u8>>>,
+}
+
+pub struct MockRedisServer {
+    port: u16,
+}
+
+impl MockRedisServer {
+    pub async fn start(password: Option<String>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let state = Arc::new(Mutex::new(MockState::default()));
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = state.clone();
+                let password = password.clone();
+                tokio::spawn(async move {
+                    let _ = handle_connection(stream, password, state).await;
+                });
+            }
+        });
+        MockRedisServer { port }
+    }
+
+    pub fn addr(&self) -> String {
+        format!("127.0.0.1:{}", self.port)
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    password: Option<String>,
+    state: Arc<Mutex<MockState>>,
+) -> Result<(), RedisBridgeError> {
+    let mut authenticated = password.is_none();
+    let mut read_buf = BytesMut::new();
+    loop {
+        let value = loop {
+            if let Some(value) = decode_value(&mut read_buf)? {
+                break value;
+            }
+            if stream.read_buf(&mut read_buf).await? == 0 {
+                return Ok(());
+            }
+        };
+
+        let args = match value {
+            RespValue::Array(Some(values)) => values
+                .into_iter()
+                .map(|value| match value {
+                    RespValue::BulkString(Some(data)) => data,
+                    _ => Vec::new(),
+                })
+                .collect::<Vec<Vec<u8>>>(),
+            _ => Vec::new(),
+        };
+
+        let reply = execute(&args, &password, &mut authenticated, &state);
+        let mut buf = BytesMut::new();
+        encode_value(&mut buf, &reply);
+        stream.write_all(&buf).await?;
+    }
+}
+
+fn ok() -> RespValue {
+    RespValue::SimpleString("OK".to_string())
+}
+
+fn bulk(data: Vec<u8>) -> RespValue {
+    RespValue::BulkString(Some(data))
+}
+
+fn wrong_arguments(name: &str) -> RespValue {
+    RespValue::Error(format!(
+        "ERR wrong number of arguments for '{}'",
+        name.to_lowercase()
+    ))
+}
+
+fn execute(
+    args: &[Vec<u8>],
+    password: &Option<String>,
+    authenticated: &mut bool,
+    state: &Arc<Mutex<MockState>>,
+) -> RespValue {
+    let name = args
+        .first()
+        .map(|name| String::from_utf8_lossy(name).to_uppercase())
+        .unwrap_or_default();
+
+    if name == "AUTH" {
+        let given = args.last().map(|p| String::from_utf8_lossy(p).to_string());
+        if args.len() >= 2 && given == *password {
+            *authenticated = true;
+            return ok();
+        }
+        return RespValue::Error("WRONGPASS invalid password".to_string());
+    }
+    if !*authenticated {
+        return RespValue::Error("NOAUTH Authentication required.".to_string());
+    }
+
+    let mut state = state.lock().unwrap();
+    match (name.as_str(), args.len()) {
+        ("SELECT", 2) => ok(),
+        ("PING", 1) => RespValue::SimpleString("PONG".to_string()),
+        ("HSET", n) if n >= 4 && n % 2 == 0 => {
+            let hash = state.hashes.entry(args[1].clone()).or_default();
+            let mut added = 0;
+            for pair in args[2..].chunks(2) {
+                if hash.insert(pair[0].clone(), pair[1].clone()).is_none() {
+                    added += 1;
+                }
+            }
+            RespValue::Integer(added)
+        }
+        ("HMGET", n) if n >= 3 => {
+            let hash = state.hashes.get(&args[1]);
+            RespValue::Array(Some(
+                args[2..]
+                    .iter()
+                    .map(|field| RespValue::BulkString(hash.and_then(|h| h.get(field)).cloned()))
+                    .collect(),
+            ))
+        }
+        ("DEL", n) if n >= 2 => {
+            let mut removed = 0;
+            for key in args[1..].iter() {
+                if state.hashes.remove(key).is_some() || state.sets.remove(key).is_some() {
+                    removed += 1;
+                }
+            }
+            RespValue::Integer(removed)
+        }
+        ("SADD", n) if n >= 3 => {
+            let set = state.sets.entry(args[1].clone()).or_default();
+            let added = args[2..]
+                .iter()
+                .filter(|member| set.insert(member.to_vec()))
+                .count();
+            RespValue::Integer(added as i64)
+        }
+        ("SREM", n) if n >= 3 => {
+            let Some(set) = state.sets.get_mut(&args[1]) else {
+                return RespValue::Integer(0);
+            };
+            let removed = args[2..]
+                .iter()
+                .filter(|member| set.remove(*member))
+                .count();
+            if set.is_empty() {
+                state.sets.remove(&args[1]);
+            }
+            RespValue::Integer(removed as i64)
+        }
+        ("SMEMBERS", 2) => RespValue::Array(Some(
+            state
+                .sets
+                .get(&args[1])
+                .map(|set| set.iter().cloned().map(bulk).collect())
+                .unwrap_or_default(),
+        )),
+        ("SCAN", 6) => scan(&state, args),
+        (name, _) => match name {
+            "SELECT" | "PING" | "HSET" | "HMGET" | "DEL" | "SADD" | "SREM" | "SMEMBERS"
+            | "SCAN" => wrong_arguments(name),
+            _ => RespValue::Error(format!("ERR unknown command '{}'", name)),
+        },
+    }
+}
+
+// SCAN cursor MATCH pattern COUNT count, the cursor is the offset in the ordered keyspace
+fn scan(state: &MockState, args: &[Vec<u8>]) -> RespValue {
+    let cursor = String::from_utf8_lossy(&args[1])
+        .parse::<usize>()
+        .unwrap_or_default();
+    let count = String::from_utf8_lossy(&args[5])
+        .parse::<usize>()
+        .unwrap_or(10);
+    let keys: BTreeSet<&Vec<u8>> = state.hashes.keys().chain(state.sets.keys()).collect();
+
+    let page: Vec<&Vec<u8>> = keys.iter().skip(cursor).take(count).copied().collect();
+    let next = if cursor + count >= keys.len() {
+        0
+    } else {
+        cursor + count
+    };
+    let matched = page
+        .into_iter()
+        .filter(|key| glob_match(&args[3], key))
+        .cloned()
+        .map(bulk)
+        .collect();
+    RespValue::Array(Some(vec![
+        bulk(next.to_string().into_bytes()),
+        RespValue::Array(Some(matched)),
+    ]))
+}
+
+// Supports `*`, `?` and backslash escapes.
+fn glob_match(pattern: &[u8], value: &[u8]) -> bool {
+    match pattern.first() {
+        None => value.is_empty(),
+        Some(b'*') => (0..=value.len()).any(|i| glob_match(&pattern[1..], &value[i..])),
+        Some(b'?') => !value.is_empty() && glob_match(&pattern[1..], &value[1..]),
+        Some(b'\\') if pattern.len() > 1 => {
+            value.first() == Some(&pattern[1]) && glob_match(&pattern[2..], &value[1..])
+        }
+        Some(c) => value.first() == Some(c) && glob_match(&pattern[1..], &value[1..]),
+    }
+}
@@ -11,3 +11,551 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use axum::async_trait;
+use common_base::config::common::RedisAuth;
+use dashmap::DashMap;
+use metadata_struct::acl::mqtt_acl::MqttAcl;
+use metadata_struct::acl::mqtt_blacklist::MqttAclBlackList;
+use metadata_struct::mqtt::user::MqttUser;
+use mqtt_bridge_redis::client::RedisClient;
+use mqtt_bridge_redis::config::RedisBridgeConfig;
+use mqtt_bridge_redis::error::RedisBridgeError;
+use mqtt_bridge_redis::resp::RespValue;
+
+use super::AuthStorageAdapter;
+use crate::handler::error::MqttBrokerError;
+
+#[cfg(test)]
+mod mock;
+
+const USERNAME_PLACEHOLDER: &str = "${username}";
+const SCAN_COUNT: usize = 100;
+
+// Users are stored one hash per user under `user_key`, ACL rules and blacklist
+// entries as JSON members of the `acl_key` and `blacklist_key` sets.
+pub struct RedisAuthStorageAdapter {
+    client: RedisClient,
+    config: RedisAuth,
+    // The parts of `user_key` before and after `${username}`
+    user_key_prefix: String,
+    user_key_suffix: String,
+}
+
+impl RedisAuthStorageAdapter {
+    pub fn new(config: RedisAuth) -> Result<Self, MqttBrokerError> {
+        if config.addr.is_empty() {
+            return Err(MqttBrokerError::InvalidRedisAuthConfig(
+                "addr cannot be empty".to_string(),
+            ));
+        }
+        let (prefix, suffix) = match config.user_key.split_once(USERNAME_PLACEHOLDER) {
+            Some((prefix, suffix)) if !suffix.contains(USERNAME_PLACEHOLDER) => (prefix, suffix),
+            _ => {
+                return Err(MqttBrokerError::InvalidRedisAuthConfig(format!(
+                    "user_key [{}] must contain {} exactly once",
+                    config.user_key, USERNAME_PLACEHOLDER
+                )));
+            }
+        };
+        if config.password_field.is_empty() || config.superuser_field.is_empty() {
+            return Err(MqttBrokerError::InvalidRedisAuthConfig(
+                "password_field and superuser_field cannot be empty".to_string(),
+            ));
+        }
+
+        let client = RedisClient::new(RedisBridgeConfig {
+            addr: config.addr.clone(),
+            username: config.username.clone(),
+            password: config.password.clone(),
+            db: config.db,
+            timeout_ms: config.timeout_ms,
+            ..Default::default()
+        });
+        Ok(RedisAuthStorageAdapter {
+            client,
+            user_key_prefix: prefix.to_string(),
+            user_key_suffix: suffix.to_string(),
+            config,
+        })
+    }
+
+    fn user_key(&self, username: &str) -> String {
+        format!(
+            "{}{}{}",
+            self.user_key_prefix, username, self.user_key_suffix
+        )
+    }
+
+    // SCAN pattern matching the key of every user
+    fn user_key_pattern(&self) -> String {
+        format!(
+            "{}*{}",
+            escape_glob(&self.user_key_prefix),
+            escape_glob(&self.user_key_suffix)
+        )
+    }
+
+    fn username_of(&self, key: &[u8]) -> Option<String> {
+        let key = std::str::from_utf8(key).ok()?;
+        let username = key
+            .strip_prefix(&self.user_key_prefix)?
+            .strip_suffix(&self.user_key_suffix)?;
+        if username.is_empty() {
+            return None;
+        }
+        Some(username.to_string())
+    }
+
+    async fn user_keys(&self) -> Result<Vec<Vec<u8>>, MqttBrokerError> {
+        let pattern = self.user_key_pattern();
+        let count = SCAN_COUNT.to_string();
+        let mut cursor = "0".to_string();
+        let mut keys = Vec::new();
+        loop {
+            let replies = self
+                .client
+                .pipeline(&[command(&[
+                    "SCAN", &cursor, "MATCH", &pattern, "COUNT", &count,
+                ])])
+                .await?;
+            let (next, batch) = parse_scan_reply(replies.into_iter().next())?;
+            keys.extend(batch);
+            if next == "0" {
+                break;
+            }
+            cursor = next;
+        }
+        // SCAN may return a key more than once
+        keys.sort();
+        keys.dedup();
+        Ok(keys)
+    }
+
+    fn hmget_command(&self, key: &str) -> Vec<Vec<u8>> {
+        command(&[
+            "HMGET",
+            key,
+            &self.config.password_field,
+            &self.config.superuser_field,
+        ])
+    }
+
+    async fn members(&self, key: &str) -> Result<Vec<Vec<u8>>, MqttBrokerError> {
+        let replies = self.client.pipeline(&[command(&["SMEMBERS", key])]).await?;
+        let mut members = Vec::new();
+        for value in into_array(replies.into_iter().next())? {
+            members.push(into_bulk_string(value)?.unwrap_or_default());
+        }
+        Ok(members)
+    }
+
+    async fn update_set(
+        &self,
+        operation: &str,
+        key: &str,
+        member: Vec<u8>,
+    ) -> Result<(), MqttBrokerError> {
+        let mut args = command(&[operation, key]);
+        args.push(member);
+        self.client.pipeline(&[args]).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AuthStorageAdapter for RedisAuthStorageAdapter {
+    async fn read_all_user(&self) -> Result<DashMap<String, MqttUser>, MqttBrokerError> {
+        let mut usernames = Vec::new();
+        let mut commands = Vec::new();
+        for key in self.user_keys().await? {
+            if let Some(username) = self.username_of(&key) {
+                commands.push(self.hmget_command(&self.user_key(&username)));
+                usernames.push(username);
+            }
+        }
+
+        let results = DashMap::with_capacity(usernames.len());
+        let replies = self.client.pipeline(&commands).await?;
+        for (username, reply) in usernames.into_iter().zip(replies) {
+            // the user may have been deleted since the scan
+            if let Some(user) = parse_user(username.clone(), reply)? {
+                results.insert(username, user);
+            }
+        }
+        return Ok(results);
+    }
+
+    async fn get_user(&self, username: String) -> Result<Option<MqttUser>, MqttBrokerError> {
+        let replies = self
+            .client
+            .pipeline(&[self.hmget_command(&self.user_key(&username))])
+            .await?;
+        match replies.into_iter().next() {
+            Some(reply) => parse_user(username, reply),
+            None => Ok(None),
+        }
+    }
+
+    async fn save_user(&self, user_info: MqttUser) -> Result<(), MqttBrokerError> {
+        let is_superuser = if user_info.is_superuser { "1" } else { "0" };
+        self.client
+            .pipeline(&[command(&[
+                "HSET",
+                &self.user_key(&user_info.username),
+                &self.config.password_field,
+                &user_info.password,
+                &self.config.superuser_field,
+                is_superuser,
+            ])])
+            .await?;
+        return Ok(());
+    }
+
+    async fn delete_user(&self, username: String) -> Result<(), MqttBrokerError> {
+        self.client
+            .pipeline(&[command(&["DEL", &self.user_key(&username)])])
+            .await?;
+        return Ok(());
+    }
+
+    async fn read_all_acl(&self) -> Result<Vec<MqttAcl>, MqttBrokerError> {
+        let mut results = Vec::new();
+        for member in self.members(&self.config.acl_key).await? {
+            results.push(MqttAcl::decode(&member)?);
+        }
+        return Ok(results);
+    }
+
+    async fn save_acl(&self, acl: MqttAcl) -> Result<(), MqttBrokerError> {
+        self.update_set("SADD", &self.config.acl_key, acl.encode()?)
+            .await
+    }
+
+    async fn delete_acl(&self, acl: MqttAcl) -> Result<(), MqttBrokerError> {
+        self.update_set("SREM", &self.config.acl_key, acl.encode()?)
+            .await
+    }
+
+    async fn read_all_blacklist(&self) -> Result<Vec<MqttAclBlackList>, MqttBrokerError> {
+        let mut results = Vec::new();
+        for member in self.members(&self.config.blacklist_key).await? {
+            results.push(MqttAclBlackList::decode(&member)?);
+        }
+        return Ok(results);
+    }
+
+    async fn save_blacklist(&self, blacklist: MqttAclBlackList) -> Result<(), MqttBrokerError> {
+        self.update_set("SADD", &self.config.blacklist_key, blacklist.encode()?)
+            .await
+    }
+
+    async fn delete_blacklist(&self, blacklist: MqttAclBlackList) -> Result<(), MqttBrokerError> {
+        self.update_set("SREM", &self.config.blacklist_key, blacklist.encode()?)
+            .await
+    }
+}
+
+fn command(args: &[&str]) -> Vec<Vec<u8>> {
+    args.iter().map(|arg| arg.as_bytes().to_vec()).collect()
+}
+
+fn escape_glob(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn malformed(reply: &str) -> MqttBrokerError {
+    RedisBridgeError::MalformedReply(reply.to_string()).into()
+}
+
+fn into_array(reply: Option<RespValue>) -> Result<Vec<RespValue>, MqttBrokerError> {
+    match reply {
+        Some(RespValue::Array(values)) => Ok(values.unwrap_or_default()),
+        reply => Err(malformed(&format!("expected an array, got {:?}", reply))),
+    }
+}
+
+fn into_bulk_string(value: RespValue) -> Result<Option<Vec<u8>>, MqttBrokerError> {
+    match value {
+        RespValue::BulkString(data) => Ok(data),
+        value => Err(malformed(&format!(
+            "expected a bulk string, got {:?}",
+            value
+        ))),
+    }
+}
+
+fn parse_scan_reply(reply: Option<RespValue>) -> Result<(String, Vec<Vec<u8>>), MqttBrokerError> {
+    let mut values = into_array(reply)?.into_iter();
+    let cursor = match values.next().map(into_bulk_string).transpose()?.flatten() {
+        Some(cursor) => String::from_utf8_lossy(&cursor).to_string(),
+        None => return Err(malformed("SCAN reply without cursor")),
+    };
+    let mut keys = Vec::new();
+    for value in into_array(values.next())? {
+        if let Some(key) = into_bulk_string(value)? {
+            keys.push(key);
+        }
+    }
+    Ok((cursor, keys))
+}
+
+// Parses the HMGET reply of a user, a missing password means there is no such user.
+fn parse_user(username: String, reply: RespValue) -> Result<Option<MqttUser>, MqttBrokerError> {
+    let mut fields = into_array(Some(reply))?.into_iter();
+    let password = match fields.next().map(into_bulk_string).transpose()?.flatten() {
+        Some(password) => String::from_utf8(password)
+            .map_err(|e| malformed(&format!("password of user [{}], {}", username, e)))?,
+        None => return Ok(None),
+    };
+    let is_superuser = match fields.next().map(into_bulk_string).transpose()?.flatten() {
+        Some(value) => {
+            let value = String::from_utf8_lossy(&value);
+            value == "1" || value.eq_ignore_ascii_case("true")
+        }
+        None => false,
+    };
+    Ok(Some(MqttUser {
+        username,
+        password,
+        is_superuser,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use common_base::config::common::RedisAuth;
+    use common_base::config::default_mqtt::{
+        default_redis_auth_acl_key, default_redis_auth_blacklist_key,
+        default_redis_auth_password_field, default_redis_auth_superuser_field,
+        default_redis_auth_user_key,
+    };
+    use metadata_struct::acl::mqtt_acl::{
+        MqttAcl, MqttAclAction, MqttAclPermission, MqttAclResourceType,
+    };
+    use metadata_struct::acl::mqtt_blacklist::{MqttAclBlackList, MqttAclBlackListType};
+    use metadata_struct::mqtt::user::MqttUser;
+    use mqtt_bridge_redis::client::RedisClient;
+    use mqtt_bridge_redis::config::RedisBridgeConfig;
+
+    use super::mock::MockRedisServer;
+    use super::{command, RedisAuthStorageAdapter};
+    use crate::security::AuthStorageAdapter;
+
+    fn build_config(addr: String) -> RedisAuth {
+        RedisAuth {
+            addr,
+            timeout_ms: 1000,
+            user_key: default_redis_auth_user_key(),
+            password_field: default_redis_auth_password_field(),
+            superuser_field: default_redis_auth_superuser_field(),
+            acl_key: default_redis_auth_acl_key(),
+            blacklist_key: default_redis_auth_blacklist_key(),
+            ..Default::default()
+        }
+    }
+
+    fn user(username: &str, is_superuser: bool) -> MqttUser {
+        MqttUser {
+            username: username.to_string(),
+            password: format!("{}@pwd", username),
+            is_superuser,
+        }
+    }
+
+    #[test]
+    fn user_key_test() {
+        let adapter =
+            RedisAuthStorageAdapter::new(build_config("127.0.0.1:6379".to_string())).unwrap();
+        assert_eq!(adapter.user_key("alice"), "mqtt_user:alice");
+        assert_eq!(adapter.user_key_pattern(), "mqtt_user:*");
+        assert_eq!(
+            adapter.username_of(b"mqtt_user:alice"),
+            Some("alice".to_string())
+        );
+        assert_eq!(adapter.username_of(b"mqtt_user:"), None);
+        assert_eq!(adapter.username_of(b"other:alice"), None);
+
+        let mut config = build_config("127.0.0.1:6379".to_string());
+        config.user_key = "dev[ice]:${username}:auth".to_string();
+        let adapter = RedisAuthStorageAdapter::new(config).unwrap();
+        assert_eq!(adapter.user_key_pattern(), "dev\\[ice\\]:*:auth");
+        assert_eq!(
+            adapter.username_of(b"dev[ice]:bob:auth"),
+            Some("bob".to_string())
+        );
+
+        let mut config = build_config("127.0.0.1:6379".to_string());
+        config.user_key = "mqtt_user".to_string();
+        assert!(RedisAuthStorageAdapter::new(config).is_err());
+
+        let mut config = build_config("127.0.0.1:6379".to_string());
+        config.user_key = "${username}:${username}".to_string();
+        assert!(RedisAuthStorageAdapter::new(config).is_err());
+
+        assert!(RedisAuthStorageAdapter::new(build_config(String::new())).is_err());
+    }
+
+    #[tokio::test]
+    async fn user_test() {
+        let server = MockRedisServer::start(None).await;
+        let adapter = RedisAuthStorageAdapter::new(build_config(server.addr())).unwrap();
+
+        assert!(adapter.read_all_user().await.unwrap().is_empty());
+        assert!(adapter
+            .get_user("alice".to_string())
+            .await
+            .unwrap()
+            .is_none());
+
+        // more users than a single SCAN page
+        for i in 0..150 {
+            adapter
+                .save_user(user(&format!("device-{}", i), false))
+                .await
+                .unwrap();
+        }
+        adapter.save_user(user("alice", true)).await.unwrap();
+
+        let users = adapter.read_all_user().await.unwrap();
+        assert_eq!(users.len(), 151);
+        assert_eq!(*users.get("alice").unwrap(), user("alice", true));
+        assert_eq!(*users.get("device-99").unwrap(), user("device-99", false));
+
+        let mut updated = user("alice", false);
+        updated.password = "new-password".to_string();
+        adapter.save_user(updated.clone()).await.unwrap();
+        assert_eq!(
+            adapter.get_user("alice".to_string()).await.unwrap(),
+            Some(updated)
+        );
+
+        adapter.delete_user("alice".to_string()).await.unwrap();
+        assert!(adapter
+            .get_user("alice".to_string())
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(adapter.read_all_user().await.unwrap().len(), 150);
+    }
+
+    #[tokio::test]
+    async fn existing_user_hash_test() {
+        let server = MockRedisServer::start(Some("pwd123".to_string())).await;
+        let client = RedisClient::new(RedisBridgeConfig {
+            addr: server.addr(),
+            password: "pwd123".to_string(),
+            timeout_ms: 1000,
+            ..Default::default()
+        });
+        client
+            .pipeline(&[
+                command(&[
+                    "HSET",
+                    "device:sensor-1:auth",
+                    "secret",
+                    "s1",
+                    "admin",
+                    "true",
+                    "model",
+                    "th-01",
+                ]),
+                command(&["HSET", "device:sensor-2:auth", "secret", "s2"]),
+                command(&["HSET", "device:sensor-3:meta", "secret", "s3"]),
+                command(&["HSET", "mqtt_user:sensor-4", "secret", "s4"]),
+            ])
+            .await
+            .unwrap();
+
+        let mut config = build_config(server.addr());
+        config.user_key = "device:${username}:auth".to_string();
+        config.password_field = "secret".to_string();
+        config.superuser_field = "admin".to_string();
+        assert!(RedisAuthStorageAdapter::new(config.clone())
+            .unwrap()
+            .read_all_user()
+            .await
+            .is_err());
+
+        config.password = "pwd123".to_string();
+        let adapter = RedisAuthStorageAdapter::new(config).unwrap();
+        let users = adapter.read_all_user().await.unwrap();
+        assert_eq!(users.len(), 2);
+        let sensor = users.get("sensor-1").unwrap();
+        assert_eq!(sensor.password, "s1");
+        assert!(sensor.is_superuser);
+        assert!(!users.get("sensor-2").unwrap().is_superuser);
+    }
+
+    #[tokio::test]
+    async fn acl_test() {
+        let server = MockRedisServer::start(None).await;
+        let adapter = RedisAuthStorageAdapter::new(build_config(server.addr())).unwrap();
+
+        let acl = MqttAcl {
+            resource_type: MqttAclResourceType::User,
+            resource_name: "alice".to_string(),
+            topic: "sensor/#".to_string(),
+            ip: "*".to_string(),
+            action: MqttAclAction::Publish,
+            permission: MqttAclPermission::Allow,
+        };
+        let mut deny = acl.clone();
+        deny.resource_type = MqttAclResourceType::ClientId;
+        deny.resource_name = "client-1".to_string();
+        deny.permission = MqttAclPermission::Deny;
+
+        adapter.save_acl(acl.clone()).await.unwrap();
+        adapter.save_acl(deny.clone()).await.unwrap();
+        // saving the same rule twice keeps a single copy
+        adapter.save_acl(acl.clone()).await.unwrap();
+        let acls = adapter.read_all_acl().await.unwrap();
+        assert_eq!(acls.len(), 2);
+        assert!(acls.contains(&acl));
+        assert!(acls.contains(&deny));
+
+        adapter.delete_acl(acl).await.unwrap();
+        assert_eq!(adapter.read_all_acl().await.unwrap(), vec![deny]);
+    }
+
+    #[tokio::test]
+    async fn blacklist_test() {
+        let server = MockRedisServer::start(None).await;
+        let adapter = RedisAuthStorageAdapter::new(build_config(server.addr())).unwrap();
+
+        let blacklist = MqttAclBlackList {
+            blacklist_type: MqttAclBlackListType::ClientId,
+            resource_name: "client-1".to_string(),
+            end_time: 1_900_000_000,
+            desc: "flooding".to_string(),
+        };
+        let mut ip = blacklist.clone();
+        ip.blacklist_type = MqttAclBlackListType::Ip;
+        ip.resource_name = "10.0.0.1".to_string();
+
+        adapter.save_blacklist(blacklist.clone()).await.unwrap();
+        adapter.save_blacklist(ip.clone()).await.unwrap();
+        let list = adapter.read_all_blacklist().await.unwrap();
+        assert_eq!(list.len(), 2);
+        assert!(list.contains(&blacklist));
+
+        adapter.delete_blacklist(blacklist).await.unwrap();
+        assert_eq!(adapter.read_all_blacklist().await.unwrap(), vec![ip]);
+    }
+
+    #[tokio::test]
+    async fn connect_failed_test() {
+        let adapter =
+            RedisAuthStorageAdapter::new(build_config("127.0.0.1:1".to_string())).unwrap();
+        assert!(adapter.read_all_user().await.is_err());
+        assert!(adapter.get_user("alice".to_string()).await.is_err());
+        assert!(adapter.read_all_acl().await.is_err());
+    }
+}
@@ -19,15 +19,26 @@ use std::sync::atomic::AtomicU64;
 use log::error;
 use protocol::mqtt::common::MqttProtocol;
 use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::sync::mpsc;
+
+use crate::security::TlsIdentity;
 static CONNECTION_ID_BUILD: AtomicU64 = AtomicU64::new(1);
 
+pub trait TlsIo: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T> TlsIo for T where T: AsyncRead + AsyncWrite + Send + Unpin {}
+
+// A server side TLS stream, terminated by rustls, or by OpenSSL when TLS-PSK is enabled.
+pub type TlsServerStream = Box<dyn TlsIo>;
+
 #[derive(Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
 pub enum NetworkConnectionType {
     Tcp,
     Tls,
     WebSocket,
     WebSockets,
+    Quic,
 }
 
 impl fmt::Display for NetworkConnectionType {
@@ -40,6 +51,7 @@ impl fmt::Display for NetworkConnectionType {
                 NetworkConnectionType::Tls => "tls",
                 NetworkConnectionType::WebSocket => "websocket",
                 NetworkConnectionType::WebSockets => "websockets",
+                NetworkConnectionType::Quic => "quic",
             }
         )
     }
@@ -51,6 +63,8 @@ pub struct NetworkConnection {
     pub connection_id: u64,
     pub protocol: Option<MqttProtocol>,
     pub addr: SocketAddr,
+    #[serde(default)]
+    pub tls_identity: Option<TlsIdentity>,
     #[serde(skip_serializing, skip_deserializing)]
     pub connection_stop_sx: Option<mpsc::Sender<bool>>,
 }
@@ -67,6 +81,7 @@ impl NetworkConnection {
             connection_id,
             protocol: None,
             addr,
+            tls_identity: None,
             connection_stop_sx,
         }
     }
@@ -103,6 +118,7 @@ impl NetworkConnection {
     pub fn is_tcp(&self) -> bool {
         self.connection_type == NetworkConnectionType::Tcp
             || self.connection_type == NetworkConnectionType::Tls
+            || self.connection_type == NetworkConnectionType::Quic
     }
 
     pub async fn stop_connection(&self) {
@@ -22,25 +22,22 @@ use futures::SinkExt;
 use log::{error, info};
 use protocol::mqtt::codec::{MqttCodec, MqttPacketWrapper};
 use protocol::mqtt::common::MqttProtocol;
+use tokio::io::AsyncWrite;
 use tokio::time::sleep;
 use tokio_util::codec::FramedWrite;
 
-use super::connection::{NetworkConnection, NetworkConnectionType};
+use super::connection::{NetworkConnection, NetworkConnectionType, TlsServerStream};
 use crate::handler::cache::CacheManager;
 use crate::handler::error::MqttBrokerError;
+use crate::handler::flow_control::is_connection_rate_exceeded;
 use crate::observability::metrics::packets::record_sent_metrics;
 
 pub struct ConnectionManager {
     connections: DashMap<u64, NetworkConnection>,
     tcp_write_list:
         DashMap<u64, FramedWrite<tokio::io::WriteHalf<tokio::net::TcpStream>, MqttCodec>>,
-    tcp_tls_write_list: DashMap<
-        u64,
-        FramedWrite<
-            tokio::io::WriteHalf<tokio_rustls::server::TlsStream<tokio::net::TcpStream>>,
-            MqttCodec,
-        >,
-    >,
+    tcp_tls_write_list: DashMap<u64, FramedWrite<tokio::io::WriteHalf<TlsServerStream>, MqttCodec>>,
+    quic_write_list: DashMap<u64, FramedWrite<quinn::SendStream, MqttCodec>>,
     websocket_write_list: DashMap<u64, SplitSink<WebSocket, Message>>,
     cache_manager: Arc<CacheManager>,
 }
@@ -50,11 +47,13 @@ impl ConnectionManager {
         let connections = DashMap::with_capacity(64);
         let tcp_write_list = DashMap::with_capacity(64);
         let tcp_tls_write_list = DashMap::with_capacity(64);
+        let quic_write_list = DashMap::with_capacity(64);
         let websocket_write_list = DashMap::with_capacity(64);
         ConnectionManager {
             connections,
             tcp_write_list,
             tcp_tls_write_list,
+            quic_write_list,
             cache_manager,
             websocket_write_list,
         }
@@ -80,14 +79,19 @@ impl ConnectionManager {
     pub fn add_tcp_tls_write(
         &self,
         connection_id: u64,
-        write: FramedWrite<
-            tokio::io::WriteHalf<tokio_rustls::server::TlsStream<tokio::net::TcpStream>>,
-            MqttCodec,
-        >,
+        write: FramedWrite<tokio::io::WriteHalf<TlsServerStream>, MqttCodec>,
     ) {
         self.tcp_tls_write_list.insert(connection_id, write);
     }
 
+    pub fn add_quic_write(
+        &self,
+        connection_id: u64,
+        write: FramedWrite<quinn::SendStream, MqttCodec>,
+    ) {
+        self.quic_write_list.insert(connection_id, write);
+    }
+
     pub fn add_websocket_write(&self, connection_id: u64, write: SplitSink<WebSocket, Message>) {
         self.websocket_write_list.insert(connection_id, write);
     }
@@ -127,6 +131,18 @@ impl ConnectionManager {
             }
         }
 
+        if let Some((id, mut stream)) = self.quic_write_list.remove(&connection_id) {
+            match stream.close().await {
+                Ok(_) => {
+                    info!(
+                        "server closes the quic connection actively, connection id [{}]",
+                        id
+                    );
+                }
+                Err(e) => error!("{}", e),
+            }
+        }
+
         if let Some((id, mut stream)) = self.websocket_write_list.remove(&connection_id) {
             match stream.close().await {
                 Ok(_) => {
@@ -209,76 +225,34 @@ impl ConnectionManager {
 
         if let Some(connection) = self.get_connect(connection_id) {
             if connection.connection_type == NetworkConnectionType::Tls {
-                return self.write_tcp_tls_frame(connection_id, resp).await;
+                return self
+                    .write_frame(&self.tcp_tls_write_list, connection_id, resp)
+                    .await;
+            }
+            if connection.connection_type == NetworkConnectionType::Quic {
+                return self
+                    .write_frame(&self.quic_write_list, connection_id, resp)
+                    .await;
             }
         }
 
-        let mut times = 0;
-        let cluster = self.cache_manager.get_cluster_info();
-        loop {
-            match self.tcp_write_list.try_get_mut(&connection_id) {
-                dashmap::try_result::TryResult::Present(mut da) => {
-                    match da.send(resp.clone()).await {
-                        Ok(_) => {
-                            // write tls stream
-                            let network_type =
-                                if let Some(connection) = self.get_connect(connection_id) {
-                                    connection.connection_type.to_string()
-                                } else {
-                                    "".to_string()
-                                };
-
-                            record_sent_metrics(&resp, network_type);
-                            break;
-                        }
-                        Err(e) => {
-                            if times > cluster.network.response_max_try_mut_times {
-                                return Err(MqttBrokerError::CommonError(format!(
-                                    "Failed to write data to the mqtt tcp client, error message: {e:?}"
-                                )));
-                            }
-                        }
-                    }
-                }
-                dashmap::try_result::TryResult::Absent => {
-                    if times > cluster.network.response_max_try_mut_times {
-                        return Err(MqttBrokerError::CommonError(
-                            format!(
-                                "[write_frame]Connection management could not obtain an available tcp connection. Connection ID: {},len:{}",
-                                connection_id,
-                                self.tcp_write_list.len()
-                            )
-                        ));
-                    }
-                }
-                dashmap::try_result::TryResult::Locked => {
-                    if times > cluster.network.response_max_try_mut_times {
-                        return Err(MqttBrokerError::CommonError(
-                            format!(
-                                "[write_frame]Connection management failed to get tcp connection variable reference, connection ID: {}",connection_id
-                            )
-                        ));
-                    }
-                }
-            }
-            times += 1;
-            sleep(Duration::from_millis(
-                cluster.network.response_try_mut_sleep_time_ms,
-            ))
+        self.write_frame(&self.tcp_write_list, connection_id, resp)
             .await
-        }
-        Ok(())
     }
 
-    async fn write_tcp_tls_frame(
+    async fn write_frame<T>(
         &self,
+        write_list: &DashMap<u64, FramedWrite<T, MqttCodec>>,
         connection_id: u64,
         resp: MqttPacketWrapper,
-    ) -> Result<(), MqttBrokerError> {
+    ) -> Result<(), MqttBrokerError>
+    where
+        T: AsyncWrite + Unpin,
+    {
         let mut times = 0;
         let cluster = self.cache_manager.get_cluster_info();
         loop {
-            match self.tcp_tls_write_list.try_get_mut(&connection_id) {
+            match write_list.try_get_mut(&connection_id) {
                 dashmap::try_result::TryResult::Present(mut da) => {
                     match da.send(resp.clone()).await {
                         Ok(_) => {
@@ -307,7 +281,7 @@ impl ConnectionManager {
                             format!(
                                 "[write_frame]Connection management could not obtain an available tcp connection. Connection ID: {},len:{}",
                                 connection_id,
-                                self.tcp_write_list.len()
+                                write_list.len()
                             )
                         ));
                     }
@@ -339,6 +313,10 @@ impl ConnectionManager {
         false
     }
 
+    pub fn connect_rate_check(&self, network_type: &NetworkConnectionType) -> bool {
+        is_connection_rate_exceeded(&self.cache_manager, network_type)
+    }
+
     pub fn get_connect(&self, connect_id: u64) -> Option<NetworkConnection> {
         if let Some(connect) = self.connections.get(&connect_id) {
             return Some(connect.clone());
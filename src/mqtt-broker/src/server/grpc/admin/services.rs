@@ -15,30 +15,40 @@
 use std::sync::Arc;
 
 use common_base::config::broker_mqtt::broker_mqtt_conf;
-use common_base::tools::serialize_value;
+use common_base::tools::{now_second, serialize_value};
 use common_base::utils::file_utils::get_project_root;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::acl::mqtt_acl::MqttAcl;
 use metadata_struct::acl::mqtt_blacklist::{MqttAclBlackList, MqttAclBlackListType};
+use metadata_struct::mqtt::bridge::{MqttBridgeRule, MqttBridgeStatus};
+use metadata_struct::mqtt::rule::MqttRule;
 use metadata_struct::mqtt::user::MqttUser;
 use protocol::broker_mqtt::broker_mqtt_admin::mqtt_broker_admin_service_server::MqttBrokerAdminService;
 use protocol::broker_mqtt::broker_mqtt_admin::{
     ClusterStatusReply, ClusterStatusRequest, CreateAclReply, CreateAclRequest,
-    CreateBlacklistReply, CreateBlacklistRequest, CreateUserReply, CreateUserRequest,
-    DeleteAclReply, DeleteAclRequest, DeleteBlacklistReply, DeleteBlacklistRequest,
-    DeleteUserReply, DeleteUserRequest, EnableSlowSubScribeReply, EnableSlowSubscribeRequest,
-    ListAclReply, ListAclRequest, ListBlacklistReply, ListBlacklistRequest, ListConnectionRaw,
-    ListConnectionReply, ListConnectionRequest, ListSlowSubScribeRaw, ListSlowSubscribeReply,
-    ListSlowSubscribeRequest, ListTopicReply, ListTopicRequest, ListUserReply, ListUserRequest,
-    MqttTopic,
+    CreateBlacklistReply, CreateBlacklistRequest, CreateConnectorReply, CreateConnectorRequest,
+    CreateRuleReply, CreateRuleRequest, CreateUserReply, CreateUserRequest, DeleteAclReply,
+    DeleteAclRequest, DeleteBlacklistReply, DeleteBlacklistRequest, DeleteConnectorReply,
+    DeleteConnectorRequest, DeleteRuleReply, DeleteRuleRequest, DeleteUserReply, DeleteUserRequest,
+    EnableSlowSubScribeReply, EnableSlowSubscribeRequest, ListAclReply, ListAclRequest,
+    ListBlacklistReply, ListBlacklistRequest, ListConnectionRaw, ListConnectionReply,
+    ListConnectionRequest, ListConnectorReply, ListConnectorRequest, ListRuleReply,
+    ListRuleRequest, ListSlowSubScribeRaw, ListSlowSubscribeReply, ListSlowSubscribeRequest,
+    ListTopicReply, ListTopicRequest, ListUserReply, ListUserRequest, MqttTopic,
+    PauseConnectorReply, PauseConnectorRequest, ResumeConnectorReply, ResumeConnectorRequest,
 };
 use tonic::{Request, Response, Status};
 
+use crate::bridge::check_bridge_rule;
 use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
 use crate::observability::slow::sub::{read_slow_sub_record, SlowSubData};
+use crate::rule::check_rule;
 use crate::security::AuthDriver;
 use crate::server::connection_manager::ConnectionManager;
+use crate::storage::bridge::BridgeStorage;
 use crate::storage::cluster::ClusterStorage;
+use crate::storage::rule::RuleStorage;
 
 pub struct GrpcAdminServices {
     client_pool: Arc<ClientPool>,
@@ -58,6 +68,27 @@ impl GrpcAdminServices {
             connection_manager,
         }
     }
+
+    // Connectors are bridge rules in the placement center, every broker picks up
+    // the new status with its next rule synchronization.
+    async fn set_connector_status(
+        &self,
+        connector_name: &str,
+        status: MqttBridgeStatus,
+    ) -> Result<(), MqttBrokerError> {
+        let storage = BridgeStorage::new(self.client_pool.clone());
+        let mut bridge = match storage.get_bridge(connector_name).await? {
+            Some(bridge) => bridge,
+            None => {
+                return Err(MqttBrokerError::BridgeNotExist(connector_name.to_string()));
+            }
+        };
+        if bridge.status == status {
+            return Ok(());
+        }
+        bridge.status = status;
+        storage.save_bridge(bridge).await
+    }
 }
 
 #[tonic::async_trait]
@@ -92,13 +123,20 @@ impl MqttBrokerAdminService for GrpcAdminServices {
         request: Request<CreateUserRequest>,
     ) -> Result<Response<CreateUserReply>, Status> {
         let req = request.into_inner();
+        let auth_driver = AuthDriver::new(self.cache_manager.clone(), self.client_pool.clone());
+        let password = match auth_driver
+            .hash_password(&req.password, &req.password_hash)
+            .await
+        {
+            Ok(password) => password,
+            Err(e) => return Err(Status::cancelled(e.to_string())),
+        };
         let mqtt_user = MqttUser {
             username: req.username,
-            password: req.password,
+            password,
             is_superuser: req.is_superuser,
         };
 
-        let auth_driver = AuthDriver::new(self.cache_manager.clone(), self.client_pool.clone());
         match auth_driver.save_user(mqtt_user).await {
             Ok(_) => Ok(Response::new(CreateUserReply::default())),
             Err(e) => Err(Status::cancelled(e.to_string())),
@@ -376,4 +414,160 @@ impl MqttBrokerAdminService for GrpcAdminServices {
 
         Ok(Response::new(reply))
     }
+
+    // --- connector ---
+    async fn mqtt_broker_list_connector(
+        &self,
+        request: Request<ListConnectorRequest>,
+    ) -> Result<Response<ListConnectorReply>, Status> {
+        let req = request.into_inner();
+        let storage = BridgeStorage::new(self.client_pool.clone());
+        let bridges = match storage.list_bridge().await {
+            Ok(data) => data,
+            Err(e) => return Err(Status::cancelled(e.to_string())),
+        };
+
+        let mut connectors = Vec::new();
+        for bridge in bridges {
+            if !req.connector_name.is_empty() && bridge.name != req.connector_name {
+                continue;
+            }
+            match bridge.encode() {
+                Ok(data) => connectors.push(data),
+                Err(e) => return Err(Status::cancelled(e.to_string())),
+            }
+        }
+        Ok(Response::new(ListConnectorReply { connectors }))
+    }
+
+    async fn mqtt_broker_create_connector(
+        &self,
+        request: Request<CreateConnectorRequest>,
+    ) -> Result<Response<CreateConnectorReply>, Status> {
+        let req = request.into_inner();
+        let mut bridge = match MqttBridgeRule::decode(&req.connector) {
+            Ok(bridge) => bridge,
+            Err(e) => return Err(Status::invalid_argument(e.to_string())),
+        };
+        if let Err(e) = check_bridge_rule(&bridge) {
+            return Err(Status::invalid_argument(e.to_string()));
+        }
+
+        let storage = BridgeStorage::new(self.client_pool.clone());
+        if storage.get_bridge(&bridge.name).await?.is_some() {
+            return Err(Status::already_exists(
+                MqttBrokerError::BridgeAlreadyExist(bridge.name).to_string(),
+            ));
+        }
+
+        bridge.create_time = now_second();
+        match storage.save_bridge(bridge).await {
+            Ok(_) => Ok(Response::new(CreateConnectorReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn mqtt_broker_pause_connector(
+        &self,
+        request: Request<PauseConnectorRequest>,
+    ) -> Result<Response<PauseConnectorReply>, Status> {
+        let req = request.into_inner();
+        match self
+            .set_connector_status(&req.connector_name, MqttBridgeStatus::Paused)
+            .await
+        {
+            Ok(_) => Ok(Response::new(PauseConnectorReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn mqtt_broker_resume_connector(
+        &self,
+        request: Request<ResumeConnectorRequest>,
+    ) -> Result<Response<ResumeConnectorReply>, Status> {
+        let req = request.into_inner();
+        match self
+            .set_connector_status(&req.connector_name, MqttBridgeStatus::Running)
+            .await
+        {
+            Ok(_) => Ok(Response::new(ResumeConnectorReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn mqtt_broker_delete_connector(
+        &self,
+        request: Request<DeleteConnectorRequest>,
+    ) -> Result<Response<DeleteConnectorReply>, Status> {
+        let req = request.into_inner();
+        let storage = BridgeStorage::new(self.client_pool.clone());
+        match storage.delete_bridge(&req.connector_name).await {
+            Ok(_) => Ok(Response::new(DeleteConnectorReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    // --- rule ---
+    async fn mqtt_broker_list_rule(
+        &self,
+        request: Request<ListRuleRequest>,
+    ) -> Result<Response<ListRuleReply>, Status> {
+        let req = request.into_inner();
+        let storage = RuleStorage::new(self.client_pool.clone());
+        let list = match storage.list_rule().await {
+            Ok(data) => data,
+            Err(e) => return Err(Status::cancelled(e.to_string())),
+        };
+
+        let mut rules = Vec::new();
+        for rule in list {
+            if !req.rule_name.is_empty() && rule.name != req.rule_name {
+                continue;
+            }
+            match rule.encode() {
+                Ok(data) => rules.push(data),
+                Err(e) => return Err(Status::cancelled(e.to_string())),
+            }
+        }
+        Ok(Response::new(ListRuleReply { rules }))
+    }
+
+    async fn mqtt_broker_create_rule(
+        &self,
+        request: Request<CreateRuleRequest>,
+    ) -> Result<Response<CreateRuleReply>, Status> {
+        let req = request.into_inner();
+        let mut rule = match MqttRule::decode(&req.rule) {
+            Ok(rule) => rule,
+            Err(e) => return Err(Status::invalid_argument(e.to_string())),
+        };
+        if let Err(e) = check_rule(&rule) {
+            return Err(Status::invalid_argument(e.to_string()));
+        }
+
+        let storage = RuleStorage::new(self.client_pool.clone());
+        if storage.get_rule(&rule.name).await?.is_some() {
+            return Err(Status::already_exists(
+                MqttBrokerError::RuleAlreadyExist(rule.name).to_string(),
+            ));
+        }
+
+        rule.create_time = now_second();
+        match storage.save_rule(rule).await {
+            Ok(_) => Ok(Response::new(CreateRuleReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn mqtt_broker_delete_rule(
+        &self,
+        request: Request<DeleteRuleRequest>,
+    ) -> Result<Response<DeleteRuleReply>, Status> {
+        let req = request.into_inner();
+        let storage = RuleStorage::new(self.client_pool.clone());
+        match storage.delete_rule(&req.rule_name).await {
+            Ok(_) => Ok(Response::new(DeleteRuleReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
 }
@@ -15,7 +15,7 @@
 use std::sync::Arc;
 
 use grpc_clients::pool::ClientPool;
-use log::debug;
+use log::{debug, warn};
 use metadata_struct::mqtt::lastwill::LastWillData;
 use protocol::broker_mqtt::broker_mqtt_inner::mqtt_broker_inner_service_server::MqttBrokerInnerService;
 use protocol::broker_mqtt::broker_mqtt_inner::{
@@ -27,6 +27,8 @@ use tonic::{Request, Response, Status};
 
 use crate::handler::cache::{update_cache_metadata, CacheManager};
 use crate::handler::lastwill::send_last_will_message;
+use crate::storage::offline::OfflineMessageStorage;
+use crate::subscribe::offline::clear_offline_message;
 use crate::subscribe::subscribe_manager::SubscribeManager;
 
 pub struct GrpcInnerServices<S> {
@@ -79,10 +81,19 @@ where
         if req.client_id.is_empty() {
             return Err(Status::cancelled("Client ID cannot be empty".to_string()));
         }
+        let offline_storage = OfflineMessageStorage::new(self.message_storage_adapter.clone());
         for client_id in req.client_id {
             self.subscribe_manager
                 .remove_exclusive_subscribe_by_client_id(&client_id)
                 .await?;
+            if let Err(e) =
+                clear_offline_message(&offline_storage, &self.cache_manager, &client_id).await
+            {
+                warn!(
+                    "Failed to clear the offline message queue of client {}, error message: {}",
+                    client_id, e
+                );
+            }
             self.cache_manager.remove_session(&client_id);
             self.subscribe_manager.stop_push_by_client_id(&client_id);
         }
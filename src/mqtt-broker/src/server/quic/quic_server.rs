@@ -0,0 +1,476 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_base::config::broker_mqtt::Network;
+use futures_util::StreamExt;
+use log::{debug, error, info};
+use protocol::mqtt::codec::MqttCodec;
+use quinn::crypto::rustls::QuicServerConfig;
+use quinn::{Connection, Endpoint, Incoming, RecvStream, TransportConfig, VarInt};
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::time::sleep;
+use tokio_rustls::rustls::pki_types::CertificateDer;
+use tokio_util::codec::{FramedRead, FramedWrite};
+
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::handler::flow_control::publish_throttle;
+use crate::handler::validator::establish_connection_check;
+use crate::observability::metrics::packets::{
+    record_received_error_metrics, record_received_metrics,
+};
+use crate::observability::slow::request::try_record_total_request_ms;
+use crate::security::login::x509::PeerCertificate;
+use crate::security::TlsIdentity;
+use crate::server::connection::{NetworkConnection, NetworkConnectionType};
+use crate::server::connection_manager::ConnectionManager;
+use crate::server::packet::RequestPackage;
+use crate::server::tcp::tls_server::build_tls_server_config;
+
+pub const MQTT_QUIC_ALPN: &[u8] = b"mqtt";
+
+/// Builds the QUIC server config on top of the TLS settings of the `tcps` listener.
+/// Each connection carries MQTT on a single bidirectional stream and clients are
+/// allowed to migrate to a new address. 0-RTT stays disabled, since early data can
+/// be replayed by an attacker and would reach the handler before the handshake.
+pub(crate) fn build_quic_server_config(
+    network: &Network,
+) -> Result<quinn::ServerConfig, MqttBrokerError> {
+    let mut tls_config = build_tls_server_config(network)?;
+    tls_config.alpn_protocols = vec![MQTT_QUIC_ALPN.to_vec()];
+
+    let crypto = QuicServerConfig::try_from(tls_config)
+        .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
+
+    let mut transport = TransportConfig::default();
+    transport
+        .max_concurrent_bidi_streams(VarInt::from_u32(1))
+        .max_concurrent_uni_streams(VarInt::from_u32(0));
+
+    let mut config = quinn::ServerConfig::with_crypto(Arc::new(crypto));
+    config.transport_config(Arc::new(transport)).migration(true);
+    Ok(config)
+}
+
+pub(crate) async fn acceptor_quic_process(
+    endpoint: Endpoint,
+    stop_sx: broadcast::Sender<bool>,
+    connection_manager: Arc<ConnectionManager>,
+    request_queue_sx: Sender<RequestPackage>,
+    cache_manager: Arc<CacheManager>,
+) {
+    let mut stop_rx = stop_sx.subscribe();
+    tokio::spawn(async move {
+        debug!("QUIC Server acceptor thread start successfully.");
+        loop {
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            endpoint.close(VarInt::from_u32(0), b"server stopped");
+                            debug!("QUIC Server acceptor thread stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                val = endpoint.accept()=>{
+                    let Some(incoming) = val else {
+                        break;
+                    };
+                    let connection_manager = connection_manager.clone();
+                    let request_queue_sx = request_queue_sx.clone();
+                    let cache_manager = cache_manager.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = accept_quic_connection(incoming, connection_manager, request_queue_sx, cache_manager).await {
+                            error!("QUIC accept failed to create connection with error message :{:?}",e);
+                        }
+                    });
+                }
+            };
+        }
+    });
+}
+
+async fn accept_quic_connection(
+    incoming: Incoming,
+    connection_manager: Arc<ConnectionManager>,
+    request_queue_sx: Sender<RequestPackage>,
+    cache_manager: Arc<CacheManager>,
+) -> Result<(), MqttBrokerError> {
+    let quic_connection = incoming.accept()?.await?;
+    let addr = quic_connection.remote_address();
+    info!("accept quic connection:{:?}", addr);
+
+    let tls_identity = match quic_connection
+        .peer_identity()
+        .and_then(|identity| identity.downcast::<Vec<CertificateDer<'static>>>().ok())
+        .and_then(|certs| certs.first().cloned())
+    {
+        Some(cert) => Some(TlsIdentity::Certificate(PeerCertificate::from_der(
+            cert.as_ref(),
+        )?)),
+        None => None,
+    };
+
+    let (send_stream, recv_stream) = quic_connection.accept_bi().await?;
+    let codec = MqttCodec::new(None);
+    let read_frame_stream = FramedRead::new(recv_stream, codec.clone());
+    let mut write_frame_stream = FramedWrite::new(send_stream, codec);
+
+    if !establish_connection_check(
+        &addr,
+        &NetworkConnectionType::Quic,
+        &connection_manager,
+        &mut write_frame_stream,
+    )
+    .await
+    {
+        quic_connection.close(VarInt::from_u32(0), b"");
+        return Ok(());
+    }
+
+    let (connection_stop_sx, connection_stop_rx) = mpsc::channel::<bool>(1);
+    let mut connection =
+        NetworkConnection::new(NetworkConnectionType::Quic, addr, Some(connection_stop_sx));
+    connection.tls_identity = tls_identity;
+    connection_manager.add_connection(connection.clone());
+    connection_manager.add_quic_write(connection.connection_id, write_frame_stream);
+
+    read_quic_frame_process(
+        quic_connection,
+        read_frame_stream,
+        connection,
+        request_queue_sx,
+        connection_stop_rx,
+        cache_manager,
+    );
+    Ok(())
+}
+
+fn read_quic_frame_process(
+    quic_connection: Connection,
+    mut read_frame_stream: FramedRead<RecvStream, MqttCodec>,
+    connection: NetworkConnection,
+    request_queue_sx: Sender<RequestPackage>,
+    mut connection_stop_rx: Receiver<bool>,
+    cache_manager: Arc<CacheManager>,
+) {
+    let network_type = NetworkConnectionType::Quic;
+    tokio::spawn(async move {
+        loop {
+            select! {
+                val = connection_stop_rx.recv() =>{
+                    if let Some(flag) = val{
+                        if flag {
+                            quic_connection.close(VarInt::from_u32(0), b"");
+                            debug!("QUIC connection 【{}】 acceptor thread stopped successfully.",connection.connection_id);
+                            break;
+                        }
+                    }
+                }
+                val = read_frame_stream.next()=>{
+                    if let Some(pkg) = val {
+                        match pkg {
+                            Ok(pack) => {
+                                record_received_metrics(&connection, &pack, &network_type);
+                                debug!("Received quic packet:{:?}", pack);
+                                publish_throttle(&cache_manager, connection.connection_id, read_frame_stream.decoder().protocol_version, &pack).await;
+                                let package =
+                                    RequestPackage::new(connection.connection_id, connection.addr, pack);
+
+                                match request_queue_sx.send(package.clone()).await {
+                                    Ok(_) => {
+                                        try_record_total_request_ms(cache_manager.clone(),package.clone());
+                                    }
+                                    Err(err) => error!("Failed to write data to the request queue, error message: {:?}",err),
+                                }
+                            }
+                            Err(e) => {
+                                record_received_error_metrics(network_type.clone());
+                                debug!("QUIC connection parsing packet format error message :{:?}",e)
+                            }
+                        }
+                    } else {
+                        sleep(Duration::from_millis(10)).await;
+                    }
+                }
+                reason = quic_connection.closed() =>{
+                    debug!("QUIC connection 【{}】 closed, reason: {}",connection.connection_id,reason);
+                    break;
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{SocketAddr, UdpSocket};
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use common_base::config::broker_mqtt::Network;
+    use futures::SinkExt;
+    use futures_util::StreamExt;
+    use grpc_clients::pool::ClientPool;
+    use protocol::mqtt::codec::{MqttCodec, MqttPacketWrapper};
+    use protocol::mqtt::common::{
+        ConnAck, Connect, ConnectReturnCode, MqttPacket, PingReq, PingResp,
+    };
+    use quinn::crypto::rustls::QuicClientConfig;
+    use quinn::{Endpoint, RecvStream, SendStream};
+    use tokio::sync::{broadcast, mpsc};
+    use tokio::time::timeout;
+    use tokio_rustls::rustls::client::danger::{
+        HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+    };
+    use tokio_rustls::rustls::crypto::{
+        verify_tls12_signature, verify_tls13_signature, CryptoProvider,
+    };
+    use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+    use tokio_rustls::rustls::{ClientConfig, DigitallySignedStruct, Error, SignatureScheme};
+    use tokio_util::codec::{FramedRead, FramedWrite};
+
+    use super::{acceptor_quic_process, build_quic_server_config, MQTT_QUIC_ALPN};
+    use crate::handler::cache::CacheManager;
+    use crate::server::connection_manager::ConnectionManager;
+    use crate::server::packet::RequestPackage;
+    use crate::server::tcp::tls_server::load_certs;
+
+    fn certs_dir() -> String {
+        format!("{}/../../config/example/certs", env!("CARGO_MANIFEST_DIR"))
+    }
+
+    // The example certificate has a fixed validity period, so the client pins it
+    // instead of checking the chain against the current time.
+    #[derive(Debug)]
+    struct PinnedCertVerifier {
+        cert: CertificateDer<'static>,
+        provider: Arc<CryptoProvider>,
+    }
+
+    impl ServerCertVerifier for PinnedCertVerifier {
+        fn verify_server_cert(
+            &self,
+            end_entity: &CertificateDer<'_>,
+            _intermediates: &[CertificateDer<'_>],
+            _server_name: &ServerName<'_>,
+            _ocsp_response: &[u8],
+            _now: UnixTime,
+        ) -> Result<ServerCertVerified, Error> {
+            if end_entity.as_ref() != self.cert.as_ref() {
+                return Err(Error::General("unexpected server certificate".to_string()));
+            }
+            Ok(ServerCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, Error> {
+            verify_tls12_signature(
+                message,
+                cert,
+                dss,
+                &self.provider.signature_verification_algorithms,
+            )
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, Error> {
+            verify_tls13_signature(
+                message,
+                cert,
+                dss,
+                &self.provider.signature_verification_algorithms,
+            )
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.provider
+                .signature_verification_algorithms
+                .supported_schemes()
+        }
+    }
+
+    fn client_endpoint() -> Endpoint {
+        let cert = load_certs(Path::new(&format!("{}/cert.pem", certs_dir())))
+            .unwrap()
+            .remove(0);
+        let builder = ClientConfig::builder();
+        let verifier = PinnedCertVerifier {
+            cert,
+            provider: builder.crypto_provider().clone(),
+        };
+        let mut tls_config = builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth();
+        tls_config.alpn_protocols = vec![MQTT_QUIC_ALPN.to_vec()];
+        tls_config.enable_early_data = true;
+
+        let crypto = QuicClientConfig::try_from(tls_config).unwrap();
+        let mut endpoint = Endpoint::client("127.0.0.1:0".parse().unwrap()).unwrap();
+        endpoint.set_default_client_config(quinn::ClientConfig::new(Arc::new(crypto)));
+        endpoint
+    }
+
+    fn connect_packet(client_id: &str) -> MqttPacketWrapper {
+        MqttPacketWrapper {
+            protocol_version: 4,
+            packet: MqttPacket::Connect(
+                4,
+                Connect {
+                    keep_alive: 30,
+                    client_id: client_id.to_string(),
+                    clean_session: true,
+                },
+                None,
+                None,
+                None,
+                None,
+            ),
+        }
+    }
+
+    async fn recv_request(request_queue_rx: &mut mpsc::Receiver<RequestPackage>) -> RequestPackage {
+        timeout(Duration::from_secs(5), request_queue_rx.recv())
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    // The codec reports a partial frame as an error, skip those like the read loop does.
+    async fn recv_packet(read: &mut FramedRead<RecvStream, MqttCodec>) -> MqttPacket {
+        timeout(Duration::from_secs(5), async {
+            loop {
+                if let Some(Ok(packet)) = read.next().await {
+                    return packet;
+                }
+            }
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn quic_connect_and_migrate_test() {
+        let network = Network {
+            tls_cert: format!("{}/cert.pem", certs_dir()),
+            tls_key: format!("{}/key.pem", certs_dir()),
+            tls_client_auth: "none".to_string(),
+            ..Default::default()
+        };
+        let server_config = build_quic_server_config(&network).unwrap();
+        let server = Endpoint::server(server_config, "127.0.0.1:0".parse().unwrap()).unwrap();
+        let server_addr: SocketAddr = server.local_addr().unwrap();
+
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test-cluster".to_string()));
+        let connection_manager = Arc::new(ConnectionManager::new(cache_manager.clone()));
+        let (stop_sx, _) = broadcast::channel::<bool>(2);
+        let (request_queue_sx, mut request_queue_rx) = mpsc::channel::<RequestPackage>(100);
+        acceptor_quic_process(
+            server,
+            stop_sx.clone(),
+            connection_manager.clone(),
+            request_queue_sx,
+            cache_manager,
+        )
+        .await;
+
+        // full handshake, CONNECT goes up the request queue and CONNACK comes back on the same stream
+        let client = client_endpoint();
+        let conn = client
+            .connect(server_addr, "localhost")
+            .unwrap()
+            .await
+            .unwrap();
+        let (send, recv) = conn.open_bi().await.unwrap();
+        let mut write: FramedWrite<SendStream, MqttCodec> =
+            FramedWrite::new(send, MqttCodec::new(Some(4)));
+        let mut read: FramedRead<RecvStream, MqttCodec> =
+            FramedRead::new(recv, MqttCodec::new(Some(4)));
+
+        write.send(connect_packet("quic-client")).await.unwrap();
+        let package = recv_request(&mut request_queue_rx).await;
+        assert!(matches!(package.packet, MqttPacket::Connect(..)));
+        let connection = connection_manager
+            .get_connect(package.connection_id)
+            .unwrap();
+        assert_eq!(connection.connection_type.to_string(), "quic");
+
+        let connack = ConnAck {
+            session_present: false,
+            code: ConnectReturnCode::Success,
+        };
+        connection_manager
+            .write_tcp_frame(
+                package.connection_id,
+                MqttPacketWrapper {
+                    protocol_version: 4,
+                    packet: MqttPacket::ConnAck(connack.clone(), None),
+                },
+            )
+            .await
+            .unwrap();
+        let resp = recv_packet(&mut read).await;
+        assert_eq!(resp, MqttPacket::ConnAck(connack, None));
+
+        // the client keeps talking after moving to a new local address
+        client
+            .rebind(UdpSocket::bind("127.0.0.1:0").unwrap())
+            .unwrap();
+        write
+            .send(MqttPacketWrapper {
+                protocol_version: 4,
+                packet: MqttPacket::PingReq(PingReq),
+            })
+            .await
+            .unwrap();
+        let package = recv_request(&mut request_queue_rx).await;
+        assert_eq!(package.packet, MqttPacket::PingReq(PingReq));
+        connection_manager
+            .write_tcp_frame(
+                package.connection_id,
+                MqttPacketWrapper {
+                    protocol_version: 4,
+                    packet: MqttPacket::PingResp(PingResp),
+                },
+            )
+            .await
+            .unwrap();
+        let resp = recv_packet(&mut read).await;
+        assert_eq!(resp, MqttPacket::PingResp(PingResp));
+        conn.close(0u32.into(), b"");
+
+        // the session ticket from the first connection does not allow 0-RTT data
+        let connecting = client.connect(server_addr, "localhost").unwrap();
+        assert!(connecting.into_0rtt().is_err());
+
+        stop_sx.send(true).unwrap();
+    }
+}
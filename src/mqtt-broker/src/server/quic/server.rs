@@ -0,0 +1,113 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use common_base::config::broker_mqtt::broker_mqtt_conf;
+use grpc_clients::pool::ClientPool;
+use log::info;
+use quinn::Endpoint;
+use storage_adapter::storage::StorageAdapter;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::handler::cache::CacheManager;
+use crate::handler::command::Command;
+use crate::rule::engine::RuleEngine;
+use crate::security::AuthDriver;
+use crate::server::connection_manager::ConnectionManager;
+use crate::server::packet::{RequestPackage, ResponsePackage};
+use crate::server::quic::quic_server::{acceptor_quic_process, build_quic_server_config};
+use crate::server::tcp::handler::handler_process;
+use crate::server::tcp::response::response_process;
+use crate::subscribe::subscribe_manager::SubscribeManager;
+
+#[allow(clippy::too_many_arguments)]
+pub async fn start_quic_server<S>(
+    subscribe_manager: Arc<SubscribeManager>,
+    cache_manager: Arc<CacheManager>,
+    connection_manager: Arc<ConnectionManager>,
+    message_storage_adapter: Arc<S>,
+    client_pool: Arc<ClientPool>,
+    stop_sx: broadcast::Sender<bool>,
+    auth_driver: Arc<AuthDriver>,
+    rule_engine: Arc<RuleEngine<S>>,
+) where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let conf = broker_mqtt_conf();
+    let command = Command::new(
+        cache_manager.clone(),
+        message_storage_adapter,
+        subscribe_manager.clone(),
+        client_pool.clone(),
+        connection_manager.clone(),
+        auth_driver,
+        rule_engine,
+    );
+
+    let server_config = match build_quic_server_config(&conf.network) {
+        Ok(config) => config,
+        Err(e) => {
+            panic!("{}", e.to_string());
+        }
+    };
+    let addr: SocketAddr = format!("0.0.0.0:{}", conf.network.quic_port)
+        .parse()
+        .unwrap();
+    let endpoint = match Endpoint::server(server_config, addr) {
+        Ok(endpoint) => endpoint,
+        Err(e) => {
+            panic!("{}", e.to_string());
+        }
+    };
+
+    let (request_queue_sx, request_queue_rx) = mpsc::channel::<RequestPackage>(1000);
+    let (response_queue_sx, response_queue_rx) = mpsc::channel::<ResponsePackage>(1000);
+
+    acceptor_quic_process(
+        endpoint,
+        stop_sx.clone(),
+        connection_manager.clone(),
+        request_queue_sx,
+        cache_manager.clone(),
+    )
+    .await;
+
+    handler_process(
+        conf.tcp_thread.handler_thread_num,
+        request_queue_rx,
+        connection_manager.clone(),
+        response_queue_sx,
+        stop_sx.clone(),
+        command,
+    )
+    .await;
+
+    response_process(
+        conf.tcp_thread.response_thread_num,
+        connection_manager,
+        cache_manager,
+        subscribe_manager,
+        response_queue_rx,
+        client_pool,
+        stop_sx,
+    )
+    .await;
+
+    info!(
+        "MQTT QUIC Server started successfully, listening port: {}",
+        conf.network.quic_port
+    );
+}
@@ -12,8 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-mod handler;
-mod response;
+pub(crate) mod handler;
+pub(crate) mod response;
 pub mod server;
 mod tcp_server;
-mod tls_server;
+mod tls_psk;
+pub(crate) mod tls_server;
@@ -16,13 +16,15 @@ use std::sync::Arc;
 
 use common_base::config::broker_mqtt::broker_mqtt_conf;
 use grpc_clients::pool::ClientPool;
-use log::info;
+use log::{error, info};
 use storage_adapter::storage::StorageAdapter;
 use tokio::net::TcpListener;
 use tokio::sync::{broadcast, mpsc};
 
 use crate::handler::cache::CacheManager;
 use crate::handler::command::Command;
+use crate::rule::engine::RuleEngine;
+use crate::security::login::psk::PskStore;
 use crate::security::AuthDriver;
 use crate::server::connection::NetworkConnectionType;
 use crate::server::connection_manager::ConnectionManager;
@@ -33,6 +35,7 @@ use crate::server::tcp::tcp_server::acceptor_process;
 use crate::server::tcp::tls_server::acceptor_tls_process;
 use crate::subscribe::subscribe_manager::SubscribeManager;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn start_tcp_server<S>(
     subscribe_manager: Arc<SubscribeManager>,
     cache_manager: Arc<CacheManager>,
@@ -41,6 +44,7 @@ pub async fn start_tcp_server<S>(
     client_pool: Arc<ClientPool>,
     stop_sx: broadcast::Sender<bool>,
     auth_driver: Arc<AuthDriver>,
+    rule_engine: Arc<RuleEngine<S>>,
 ) where
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
@@ -52,6 +56,7 @@ pub async fn start_tcp_server<S>(
         client_pool.clone(),
         connection_manager.clone(),
         auth_driver.clone(),
+        rule_engine,
     );
 
     let proc_config = ProcessorConfig {
@@ -80,7 +85,9 @@ pub async fn start_tcp_server<S>(
         cache_manager,
         client_pool,
     );
-    server.start_tls(conf.network.tcps_port).await;
+    server
+        .start_tls(conf.network.tcps_port, auth_driver.psk_store())
+        .await;
 }
 
 // U: codec: encoder + decoder
@@ -180,7 +187,12 @@ where
         info!("MQTT TCP Server started successfully, listening port: {port}");
     }
 
-    pub async fn start_tls(&mut self, port: u32) {
+    pub async fn start_tls(&mut self, port: u32, psk_store: Option<Arc<PskStore>>) {
+        if let Some(psk) = &psk_store {
+            if let Err(e) = psk.reload(self.client_pool.clone()).await {
+                error!("Failed to load the TLS-PSK keys, {}", e);
+            }
+        }
         let listener = match TcpListener::bind(format!("0.0.0.0:{}", port)).await {
             Ok(tl) => tl,
             Err(e) => {
@@ -199,6 +211,8 @@ where
             self.network_connection_type.clone(),
             self.connection_manager.clone(),
             request_queue_sx,
+            self.cache_manager.clone(),
+            psk_store,
         )
         .await;
 
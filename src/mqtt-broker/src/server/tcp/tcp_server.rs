@@ -26,6 +26,7 @@ use tokio::{io, select};
 use tokio_util::codec::{FramedRead, FramedWrite};
 
 use crate::handler::cache::CacheManager;
+use crate::handler::flow_control::publish_throttle;
 use crate::handler::validator::tcp_establish_connection_check;
 use crate::observability::metrics::packets::{
     record_received_error_metrics, record_received_metrics,
@@ -138,6 +139,7 @@ fn read_frame_process(
                                 record_received_metrics(&connection, &pack, &network_type);
 
                                 info!("revc tcp packet:{:?}", pack);
+                                publish_throttle(&cache_manager, connection.connection_id, read_frame_stream.decoder().protocol_version, &pack).await;
                                 let package =
                                     RequestPackage::new(connection.connection_id, connection.addr, pack);
 
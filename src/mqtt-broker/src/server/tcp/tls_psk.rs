@@ -0,0 +1,212 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+
+use common_base::config::broker_mqtt::Network;
+use log::warn;
+use openssl::ex_data::Index;
+use openssl::ssl::{Ssl, SslAcceptor, SslFiletype, SslMethod, SslVersion};
+use tokio::net::TcpStream;
+use tokio_openssl::SslStream;
+
+use crate::handler::error::MqttBrokerError;
+use crate::security::login::psk::PskStore;
+
+// TLS 1.2 PSK suites, TLS 1.3 uses the pre-shared key with its own suites.
+const PSK_CIPHERS: &str = "ECDHE-PSK-CHACHA20-POLY1305:ECDHE-PSK-AES128-CBC-SHA256:\
+                           PSK-AES256-GCM-SHA384:PSK-AES128-GCM-SHA256:PSK-CHACHA20-POLY1305";
+const CERT_CIPHERS: &str = "ECDHE+AESGCM:ECDHE+CHACHA20";
+
+// Where the PSK callback leaves the identity of the client.
+fn psk_identity_index() -> Result<Index<Ssl, String>, MqttBrokerError> {
+    static INDEX: OnceLock<Index<Ssl, String>> = OnceLock::new();
+    if let Some(index) = INDEX.get() {
+        return Ok(*index);
+    }
+    let index = Ssl::new_ex_index::<String>()?;
+    Ok(*INDEX.get_or_init(|| index))
+}
+
+// rustls has no support for external pre-shared keys, so TLS-PSK listeners are served by
+// OpenSSL. The server certificate is optional, without it only PSK suites are offered.
+pub(crate) fn build_psk_acceptor(
+    network: &Network,
+    psk_store: Arc<PskStore>,
+) -> Result<SslAcceptor, MqttBrokerError> {
+    if network.tls_client_auth != "none" {
+        return Err(MqttBrokerError::CommonError(
+            "client certificates can not be verified on a TLS-PSK listener".to_string(),
+        ));
+    }
+    let index = psk_identity_index()?;
+
+    let mut builder = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls_server())?;
+    builder.set_min_proto_version(Some(SslVersion::TLS1_2))?;
+    if network.tls_cert.is_empty() {
+        builder.set_cipher_list(PSK_CIPHERS)?;
+    } else {
+        builder.set_certificate_chain_file(&network.tls_cert)?;
+        builder.set_private_key_file(&network.tls_key, SslFiletype::PEM)?;
+        builder.check_private_key()?;
+        builder.set_cipher_list(&format!("{}:{}", PSK_CIPHERS, CERT_CIPHERS))?;
+    }
+
+    builder.set_psk_server_callback(move |ssl, identity, psk| {
+        let identity = match identity.and_then(|identity| std::str::from_utf8(identity).ok()) {
+            Some(identity) => identity,
+            None => return Ok(0),
+        };
+        let key = match psk_store.key(identity) {
+            Some(key) if key.len() <= psk.len() => key,
+            _ => {
+                warn!("unknown TLS-PSK identity [{}]", identity);
+                return Ok(0);
+            }
+        };
+        psk[..key.len()].copy_from_slice(&key);
+        ssl.set_ex_data(index, identity.to_string());
+        Ok(key.len())
+    });
+    Ok(builder.build())
+}
+
+// Completes the handshake, returns the PSK identity when the client used a pre-shared key.
+pub(crate) async fn accept_psk(
+    acceptor: &SslAcceptor,
+    stream: TcpStream,
+) -> Result<(SslStream<TcpStream>, Option<String>), MqttBrokerError> {
+    let ssl = Ssl::new(acceptor.context())?;
+    let mut stream = SslStream::new(ssl, stream)?;
+    Pin::new(&mut stream)
+        .accept()
+        .await
+        .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
+    let identity = stream.ssl().ex_data(psk_identity_index()?).cloned();
+    Ok((stream, identity))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::Pin;
+    use std::sync::Arc;
+
+    use common_base::config::broker_mqtt::Network;
+    use common_base::config::common::PskAuth;
+    use openssl::ssl::{SslConnector, SslMethod, SslVerifyMode, SslVersion};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_openssl::SslStream;
+
+    use super::{accept_psk, build_psk_acceptor, PSK_CIPHERS};
+    use crate::security::login::psk::{parse_psk_file, PskStore};
+
+    async fn psk_connect(
+        port: u16,
+        version: SslVersion,
+        identity: &'static str,
+        key: &'static [u8],
+    ) -> Result<SslStream<TcpStream>, String> {
+        let mut builder = SslConnector::builder(SslMethod::tls_client()).unwrap();
+        builder.set_max_proto_version(Some(version)).unwrap();
+        builder.set_cipher_list(PSK_CIPHERS).unwrap();
+        builder.set_verify(SslVerifyMode::NONE);
+        builder.set_psk_client_callback(move |_, _, identity_buf, psk_buf| {
+            identity_buf[..identity.len()].copy_from_slice(identity.as_bytes());
+            identity_buf[identity.len()] = 0;
+            psk_buf[..key.len()].copy_from_slice(key);
+            Ok(key.len())
+        });
+        let ssl = builder
+            .build()
+            .configure()
+            .unwrap()
+            .verify_hostname(false)
+            .into_ssl("localhost")
+            .unwrap();
+
+        let tcp = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let mut stream = SslStream::new(ssl, tcp).unwrap();
+        Pin::new(&mut stream)
+            .connect()
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(stream)
+    }
+
+    #[tokio::test]
+    async fn psk_handshake_test() {
+        let store = PskStore::new(PskAuth::default());
+        store.replace(parse_psk_file("sensor-01:00112233445566778899aabbccddeeff").unwrap());
+        let network = Network {
+            tls_client_auth: "none".to_string(),
+            ..Default::default()
+        };
+        let acceptor = Arc::new(build_psk_acceptor(&network, Arc::new(store)).unwrap());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let mut identities = Vec::new();
+            for _ in 0..3 {
+                let (stream, _) = listener.accept().await.unwrap();
+                match accept_psk(&acceptor, stream).await {
+                    Ok((mut stream, identity)) => {
+                        let mut buf = [0u8; 4];
+                        stream.read_exact(&mut buf).await.unwrap();
+                        stream.write_all(&buf).await.unwrap();
+                        identities.push(identity);
+                    }
+                    Err(_) => identities.push(None),
+                }
+            }
+            identities
+        });
+
+        let key = b"\x00\x11\x22\x33\x44\x55\x66\x77\x88\x99\xaa\xbb\xcc\xdd\xee\xff";
+        for version in [SslVersion::TLS1_2, SslVersion::TLS1_3] {
+            let mut stream = psk_connect(port, version, "sensor-01", key).await.unwrap();
+            stream.write_all(b"ping").await.unwrap();
+            let mut buf = [0u8; 4];
+            stream.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"ping");
+        }
+        assert!(
+            psk_connect(port, SslVersion::TLS1_2, "sensor-01", b"wrong key")
+                .await
+                .is_err()
+        );
+
+        let identities = server.await.unwrap();
+        assert_eq!(
+            identities,
+            vec![
+                Some("sensor-01".to_string()),
+                Some("sensor-01".to_string()),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn psk_acceptor_client_auth_test() {
+        let network = Network {
+            tls_client_auth: "required".to_string(),
+            ..Default::default()
+        };
+        let store = Arc::new(PskStore::new(PskAuth::default()));
+        assert!(build_psk_acceptor(&network, store).is_err());
+    }
+}
@@ -18,28 +18,39 @@ use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 
-use common_base::config::broker_mqtt::broker_mqtt_conf;
+use common_base::config::broker_mqtt::{broker_mqtt_conf, Network};
 use futures_util::StreamExt;
 use log::{debug, error, info};
+use openssl::ssl::SslAcceptor;
 use protocol::mqtt::codec::MqttCodec;
-use rustls_pemfile::{certs, private_key};
-use tokio::net::TcpListener;
+use rustls_pemfile::{certs, crls, private_key};
+use tokio::net::{TcpListener, TcpStream};
 use tokio::select;
 use tokio::sync::mpsc::{Receiver, Sender};
 use tokio::sync::{broadcast, mpsc};
 use tokio::time::sleep;
-use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
-use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::rustls::pki_types::{
+    CertificateDer, CertificateRevocationListDer, PrivateKeyDer,
+};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
 use tokio_rustls::TlsAcceptor;
 use tokio_util::codec::{FramedRead, FramedWrite};
 
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::handler::flow_control::publish_throttle;
 use crate::handler::validator::tcp_tls_establish_connection_check;
 use crate::observability::metrics::packets::{
     record_received_error_metrics, record_received_metrics,
 };
-use crate::server::connection::{NetworkConnection, NetworkConnectionType};
+use crate::security::login::psk::PskStore;
+use crate::security::login::x509::PeerCertificate;
+use crate::security::TlsIdentity;
+use crate::server::connection::{NetworkConnection, NetworkConnectionType, TlsServerStream};
 use crate::server::connection_manager::ConnectionManager;
 use crate::server::packet::RequestPackage;
+use crate::server::tcp::tls_psk::{accept_psk, build_psk_acceptor};
 
 pub(crate) fn load_certs(path: &Path) -> io::Result<Vec<CertificateDer<'static>>> {
     certs(&mut BufReader::new(File::open(path)?)).collect()
@@ -54,6 +65,104 @@ pub(crate) fn load_key(path: &Path) -> io::Result<PrivateKeyDer<'static>> {
         ))
 }
 
+pub(crate) fn load_crls(path: &Path) -> io::Result<Vec<CertificateRevocationListDer<'static>>> {
+    crls(&mut BufReader::new(File::open(path)?)).collect()
+}
+
+pub(crate) fn build_tls_server_config(network: &Network) -> Result<ServerConfig, MqttBrokerError> {
+    let certs = load_certs(Path::new(&network.tls_cert))?;
+    let key = load_key(Path::new(&network.tls_key))?;
+
+    let builder = match network.tls_client_auth.as_str() {
+        "none" => ServerConfig::builder().with_no_client_auth(),
+        "optional" | "required" => {
+            if network.tls_ca.is_empty() {
+                return Err(MqttBrokerError::CommonError(
+                    "tls_ca is required to verify client certificates".to_string(),
+                ));
+            }
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(Path::new(&network.tls_ca))? {
+                roots
+                    .add(cert)
+                    .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
+            }
+
+            let mut verifier = WebPkiClientVerifier::builder(Arc::new(roots));
+            if !network.tls_crl.is_empty() {
+                // Only the client certificate is checked, its issuer must be covered by the CRL file.
+                verifier = verifier
+                    .with_crls(load_crls(Path::new(&network.tls_crl))?)
+                    .only_check_end_entity_revocation();
+            }
+            if network.tls_client_auth == "optional" {
+                verifier = verifier.allow_unauthenticated();
+            }
+            let verifier = verifier
+                .build()
+                .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
+            ServerConfig::builder().with_client_cert_verifier(verifier)
+        }
+        mode => {
+            return Err(MqttBrokerError::CommonError(format!(
+                "unsupported tls_client_auth [{}], expected none, optional or required",
+                mode
+            )));
+        }
+    };
+
+    builder
+        .with_single_cert(certs, key)
+        .map_err(|e| MqttBrokerError::CommonError(e.to_string()))
+}
+
+#[derive(Clone)]
+enum ServerTlsAcceptor {
+    Rustls(TlsAcceptor),
+    Psk(Arc<SslAcceptor>),
+}
+
+impl ServerTlsAcceptor {
+    fn new(network: &Network, psk_store: Option<Arc<PskStore>>) -> Result<Self, MqttBrokerError> {
+        if let Some(psk_store) = psk_store {
+            let acceptor = build_psk_acceptor(network, psk_store)?;
+            return Ok(ServerTlsAcceptor::Psk(Arc::new(acceptor)));
+        }
+        let config = build_tls_server_config(network)?;
+        Ok(ServerTlsAcceptor::Rustls(TlsAcceptor::from(Arc::new(
+            config,
+        ))))
+    }
+
+    async fn accept(
+        &self,
+        stream: TcpStream,
+    ) -> Result<(TlsServerStream, Option<TlsIdentity>), MqttBrokerError> {
+        match self {
+            ServerTlsAcceptor::Rustls(acceptor) => {
+                let stream = acceptor.accept(stream).await?;
+                // The certificate has been verified against the CA bundle and the CRL during the handshake.
+                let identity = match stream
+                    .get_ref()
+                    .1
+                    .peer_certificates()
+                    .and_then(|certs| certs.first())
+                {
+                    Some(cert) => Some(TlsIdentity::Certificate(PeerCertificate::from_der(
+                        cert.as_ref(),
+                    )?)),
+                    None => None,
+                };
+                Ok((Box::new(stream), identity))
+            }
+            ServerTlsAcceptor::Psk(acceptor) => {
+                let (stream, identity) = accept_psk(acceptor, stream).await?;
+                Ok((Box::new(stream), identity.map(TlsIdentity::Psk)))
+            }
+        }
+    }
+}
+
 pub(crate) async fn acceptor_tls_process(
     accept_thread_num: usize,
     listener_arc: Arc<TcpListener>,
@@ -61,34 +170,18 @@ pub(crate) async fn acceptor_tls_process(
     network_connection_type: NetworkConnectionType,
     connection_manager: Arc<ConnectionManager>,
     request_queue_sx: Sender<RequestPackage>,
+    cache_manager: Arc<CacheManager>,
+    psk_store: Option<Arc<PskStore>>,
 ) {
     let conf = broker_mqtt_conf();
 
-    let certs = match load_certs(Path::new(&conf.network.tls_cert)) {
+    let tls_acceptor = match ServerTlsAcceptor::new(&conf.network, psk_store) {
         Ok(data) => data,
         Err(e) => {
             panic!("{}", e.to_string());
         }
     };
 
-    let key = match load_key(Path::new(&conf.network.tls_key)) {
-        Ok(data) => data,
-        Err(e) => {
-            panic!("{}", e.to_string());
-        }
-    };
-
-    let config = match ServerConfig::builder()
-        .with_no_client_auth()
-        .with_single_cert(certs, key)
-    {
-        Ok(data) => data,
-        Err(e) => {
-            panic!("{}", e.to_string());
-        }
-    };
-    let tls_acceptor = TlsAcceptor::from(Arc::new(config));
-
     for index in 1..=accept_thread_num {
         let listener = listener_arc.clone();
         let connection_manager = connection_manager.clone();
@@ -96,6 +189,7 @@ pub(crate) async fn acceptor_tls_process(
         let raw_request_queue_sx = request_queue_sx.clone();
         let raw_tls_acceptor = tls_acceptor.clone();
         let network_type = network_connection_type.clone();
+        let cache_manager = cache_manager.clone();
         tokio::spawn(async move {
             debug!("TCP Server acceptor thread {} start successfully.", index);
             loop {
@@ -112,7 +206,7 @@ pub(crate) async fn acceptor_tls_process(
                         match val{
                             Ok((stream, addr)) => {
                                 info!("accept tcp tls connection:{:?}",addr);
-                                let stream = match raw_tls_acceptor.accept(stream).await{
+                                let (stream, tls_identity) = match raw_tls_acceptor.accept(stream).await{
                                     Ok(da) => da,
                                     Err(e) => {
                                         error!("Tls Accepter failed to read Stream with error message :{e:?}");
@@ -129,15 +223,16 @@ pub(crate) async fn acceptor_tls_process(
                                 }
 
                                 let (connection_stop_sx, connection_stop_rx) = mpsc::channel::<bool>(1);
-                                let connection = NetworkConnection::new(
+                                let mut connection = NetworkConnection::new(
                                     crate::server::connection::NetworkConnectionType::Tls,
                                     addr,
                                     Some(connection_stop_sx.clone())
                                 );
+                                connection.tls_identity = tls_identity;
                                 connection_manager.add_connection(connection.clone());
                                 connection_manager.add_tcp_tls_write(connection.connection_id, write_frame_stream);
 
-                                read_tls_frame_process(read_frame_stream,connection,raw_request_queue_sx.clone(),connection_stop_rx, network_type.clone());
+                                read_tls_frame_process(read_frame_stream,connection,raw_request_queue_sx.clone(),connection_stop_rx, network_type.clone(),cache_manager.clone());
                             }
                             Err(e) => {
                                 error!("TCP accept failed to create connection with error message :{:?}",e);
@@ -151,14 +246,12 @@ pub(crate) async fn acceptor_tls_process(
 }
 
 pub(crate) fn read_tls_frame_process(
-    mut read_frame_stream: FramedRead<
-        tokio::io::ReadHalf<tokio_rustls::server::TlsStream<tokio::net::TcpStream>>,
-        MqttCodec,
-    >,
+    mut read_frame_stream: FramedRead<tokio::io::ReadHalf<TlsServerStream>, MqttCodec>,
     connection: NetworkConnection,
     request_queue_sx: Sender<RequestPackage>,
     mut connection_stop_rx: Receiver<bool>,
     network_type: NetworkConnectionType,
+    cache_manager: Arc<CacheManager>,
 ) {
     tokio::spawn(async move {
         loop {
@@ -177,6 +270,7 @@ pub(crate) fn read_tls_frame_process(
                             Ok(pack) => {
                                 record_received_metrics(&connection, &pack, &network_type);
                                 info!("revc tcp tls packet:{:?}", pack);
+                                publish_throttle(&cache_manager, connection.connection_id, read_frame_stream.decoder().protocol_version, &pack).await;
                                 let package =
                                     RequestPackage::new(connection.connection_id, connection.addr, pack);
                                 match request_queue_sx.send(package).await {
@@ -198,3 +292,40 @@ pub(crate) fn read_tls_frame_process(
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use common_base::config::broker_mqtt::Network;
+
+    use super::build_tls_server_config;
+
+    fn network(tls_client_auth: &str, tls_ca: &str) -> Network {
+        let certs = format!("{}/../../config/example/certs", env!("CARGO_MANIFEST_DIR"));
+        Network {
+            tls_cert: format!("{}/cert.pem", certs),
+            tls_key: format!("{}/key.pem", certs),
+            tls_ca: if tls_ca.is_empty() {
+                "".to_string()
+            } else {
+                format!("{}/{}", certs, tls_ca)
+            },
+            tls_client_auth: tls_client_auth.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn build_tls_server_config_test() {
+        assert!(build_tls_server_config(&network("none", "")).is_ok());
+        assert!(build_tls_server_config(&network("optional", "ca.pem")).is_ok());
+        assert!(build_tls_server_config(&network("required", "ca.pem")).is_ok());
+
+        // client certificates can not be verified without a CA bundle
+        assert!(build_tls_server_config(&network("required", "")).is_err());
+        assert!(build_tls_server_config(&network("request", "ca.pem")).is_err());
+
+        let mut conf = network("required", "ca.pem");
+        conf.tls_crl = format!("{}/crl.pem", env!("CARGO_MANIFEST_DIR"));
+        assert!(build_tls_server_config(&conf).is_err());
+    }
+}
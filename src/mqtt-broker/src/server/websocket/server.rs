@@ -37,6 +37,8 @@ use tokio::sync::broadcast::{self};
 
 use crate::handler::cache::CacheManager;
 use crate::handler::command::Command;
+use crate::handler::flow_control::publish_throttle;
+use crate::rule::engine::RuleEngine;
 use crate::security::AuthDriver;
 use crate::server::connection::NetworkConnection;
 use crate::server::connection_manager::ConnectionManager;
@@ -53,12 +55,14 @@ pub struct WebSocketServerState<S> {
     stop_sx: broadcast::Sender<bool>,
     connection_manager: Arc<ConnectionManager>,
     auth_driver: Arc<AuthDriver>,
+    rule_engine: Arc<RuleEngine<S>>,
 }
 
 impl<S> WebSocketServerState<S>
 where
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         sucscribe_manager: Arc<SubscribeManager>,
         cache_manager: Arc<CacheManager>,
@@ -66,6 +70,7 @@ where
         message_storage_adapter: Arc<S>,
         client_pool: Arc<ClientPool>,
         auth_driver: Arc<AuthDriver>,
+        rule_engine: Arc<RuleEngine<S>>,
         stop_sx: broadcast::Sender<bool>,
     ) -> Self {
         Self {
@@ -75,6 +80,7 @@ where
             message_storage_adapter,
             client_pool,
             auth_driver,
+            rule_engine,
             stop_sx,
         }
     }
@@ -169,6 +175,7 @@ where
         state.client_pool.clone(),
         state.connection_manager.clone(),
         state.auth_driver.clone(),
+        state.rule_engine.clone(),
     );
     let codec = MqttCodec::new(None);
     ws.protocols(["mqtt", "mqttv3.1"])
@@ -179,6 +186,7 @@ where
                 command,
                 codec,
                 state.connection_manager.clone(),
+                state.cache_manager.clone(),
                 state.stop_sx.clone(),
             )
         })
@@ -190,6 +198,7 @@ async fn handle_socket<S>(
     mut command: Command<S>,
     mut codec: MqttCodec,
     connection_manager: Arc<ConnectionManager>,
+    cache_manager: Arc<CacheManager>,
     stop_sx: broadcast::Sender<bool>,
 ) where
     S: StorageAdapter + Sync + Send + 'static + Clone,
@@ -224,6 +233,7 @@ async fn handle_socket<S>(
                             match codec.decode_data(&mut buf) {
                                 Ok(Some(packet)) => {
                                     info!("recv websocket packet:{packet:?}");
+                                    publish_throttle(&cache_manager, tcp_connection.connection_id, codec.protocol_version, &packet).await;
                                     if let Some(resp_pkg) = command
                                         .apply(
                                             connection_manager.clone(),
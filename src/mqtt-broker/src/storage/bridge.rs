@@ -0,0 +1,76 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::config::broker_mqtt::broker_mqtt_conf;
+use grpc_clients::placement::mqtt::call::{create_bridge, delete_bridge, list_bridge};
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::bridge::MqttBridgeRule;
+use protocol::placement_center::placement_center_mqtt::{
+    CreateBridgeRequest, DeleteBridgeRequest, ListBridgeRequest,
+};
+
+use crate::handler::error::MqttBrokerError;
+
+pub struct BridgeStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl BridgeStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        BridgeStorage { client_pool }
+    }
+
+    pub async fn list_bridge(&self) -> Result<Vec<MqttBridgeRule>, MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = ListBridgeRequest {
+            cluster_name: config.cluster_name.clone(),
+        };
+        let reply = list_bridge(&self.client_pool, &config.placement_center, request).await?;
+        let mut list = Vec::new();
+        for raw in reply.bridges {
+            list.push(MqttBridgeRule::decode(&raw)?);
+        }
+        Ok(list)
+    }
+
+    pub async fn get_bridge(
+        &self,
+        bridge_name: &str,
+    ) -> Result<Option<MqttBridgeRule>, MqttBrokerError> {
+        let list = self.list_bridge().await?;
+        Ok(list.into_iter().find(|bridge| bridge.name == bridge_name))
+    }
+
+    pub async fn save_bridge(&self, bridge: MqttBridgeRule) -> Result<(), MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = CreateBridgeRequest {
+            cluster_name: config.cluster_name.clone(),
+            bridge: bridge.encode()?,
+        };
+        create_bridge(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    pub async fn delete_bridge(&self, bridge_name: &str) -> Result<(), MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = DeleteBridgeRequest {
+            cluster_name: config.cluster_name.clone(),
+            bridge_name: bridge_name.to_string(),
+        };
+        delete_bridge(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+}
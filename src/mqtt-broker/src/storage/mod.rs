@@ -14,8 +14,12 @@
 
 pub mod acl;
 pub mod blacklist;
+pub mod bridge;
 pub mod cluster;
 pub mod message;
+pub mod offline;
+pub mod psk;
+pub mod rule;
 pub mod session;
 pub mod topic;
 pub mod user;
@@ -0,0 +1,410 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use common_base::error::common::CommonError;
+use metadata_struct::adapter::read_config::ReadConfig;
+use metadata_struct::adapter::record::Record;
+use metadata_struct::mqtt::offline_message::MqttOfflineMessage;
+use storage_adapter::storage::{ShardConfig, StorageAdapter};
+
+use super::message::cluster_name;
+
+const CLEAR_READ_BATCH_SIZE: u64 = 100;
+
+// Each session owns a shard of offline messages, the consumer group of the
+// same name records the offset of the first message that is not yet delivered.
+fn offline_shard_name(client_id: &str) -> String {
+    format!("offline_message_{}", client_id)
+}
+
+#[derive(Clone)]
+pub struct OfflineMessageStorage<T> {
+    storage_adapter: Arc<T>,
+}
+
+impl<T> OfflineMessageStorage<T>
+where
+    T: StorageAdapter + Send + Sync + 'static,
+{
+    pub fn new(storage_adapter: Arc<T>) -> Self {
+        OfflineMessageStorage { storage_adapter }
+    }
+
+    // The shard is created when the first message of the session is queued, and again
+    // when the storage layer no longer has it.
+    pub async fn append_message(
+        &self,
+        client_id: &str,
+        message: &MqttOfflineMessage,
+    ) -> Result<u64, CommonError> {
+        let record = Record::build_byte(message.encode()?);
+        let namespace = cluster_name();
+        let shard_name = offline_shard_name(client_id);
+        if let Ok(offset) = self
+            .storage_adapter
+            .write(namespace.clone(), shard_name.clone(), record.clone())
+            .await
+        {
+            return Ok(offset);
+        }
+
+        self.storage_adapter
+            .create_shard(
+                namespace.clone(),
+                shard_name.clone(),
+                ShardConfig::default(),
+            )
+            .await?;
+        self.storage_adapter
+            .write(namespace, shard_name, record)
+            .await
+    }
+
+    pub async fn read_message(
+        &self,
+        client_id: &str,
+        offset: u64,
+        record_num: u64,
+    ) -> Result<Vec<(u64, MqttOfflineMessage)>, CommonError> {
+        let mut read_config = ReadConfig::new();
+        read_config.max_record_num = record_num;
+
+        let records = self
+            .storage_adapter
+            .read_by_offset(
+                cluster_name(),
+                offline_shard_name(client_id),
+                offset,
+                read_config,
+            )
+            .await?;
+
+        let mut results = Vec::new();
+        for record in records {
+            let offset = record.offset.unwrap_or_default();
+            results.push((offset, MqttOfflineMessage::decode(&record.data)?));
+        }
+        Ok(results)
+    }
+
+    // Offset of the first queued message from `offset`
+    pub async fn first_offset(
+        &self,
+        client_id: &str,
+        offset: u64,
+    ) -> Result<Option<u64>, CommonError> {
+        Ok(self
+            .read_message(client_id, offset, 1)
+            .await?
+            .first()
+            .map(|(offset, _)| *offset))
+    }
+
+    // Whether at least `num` messages are queued from `offset`
+    pub async fn contains_messages(
+        &self,
+        client_id: &str,
+        offset: u64,
+        num: u64,
+    ) -> Result<bool, CommonError> {
+        let first = if let Some(first) = self.first_offset(client_id, offset).await? {
+            first
+        } else {
+            return Ok(false);
+        };
+        let last = first + num.max(1) - 1;
+        Ok(!self.read_message(client_id, last, 1).await?.is_empty())
+    }
+
+    pub async fn get_read_offset(&self, client_id: &str) -> Result<u64, CommonError> {
+        let offset_data = self
+            .storage_adapter
+            .get_offset_by_group(offline_shard_name(client_id))
+            .await?;
+
+        if let Some(offset) = offset_data.first() {
+            return Ok(offset.offset);
+        }
+        Ok(0)
+    }
+
+    pub async fn commit_read_offset(
+        &self,
+        client_id: &str,
+        offset: u64,
+    ) -> Result<(), CommonError> {
+        let shard_name = offline_shard_name(client_id);
+        let mut offset_data = HashMap::new();
+        offset_data.insert(shard_name.clone(), offset);
+
+        self.storage_adapter
+            .commit_offset(shard_name, cluster_name(), offset_data)
+            .await
+    }
+
+    // Moves the read offset past every queued message. Offsets keep growing, so a
+    // message appended meanwhile is either skipped with the rest or kept after them.
+    pub async fn clear(&self, client_id: &str) -> Result<(), CommonError> {
+        let offset = self.get_read_offset(client_id).await?;
+        let mut end = offset;
+        loop {
+            let messages = self
+                .read_message(client_id, end, CLEAR_READ_BATCH_SIZE)
+                .await?;
+            if let Some((last, _)) = messages.last() {
+                end = last + 1;
+            } else {
+                break;
+            }
+        }
+        if end > offset {
+            self.commit_read_offset(client_id, end).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use axum::async_trait;
+    use bytes::Bytes;
+    use common_base::config::broker_mqtt::{init_broker_mqtt_conf_by_config, BrokerMqttConfig};
+    use common_base::error::common::CommonError;
+    use dashmap::DashMap;
+    use metadata_struct::adapter::read_config::ReadConfig;
+    use metadata_struct::adapter::record::Record;
+    use metadata_struct::mqtt::message::MqttMessage;
+    use metadata_struct::mqtt::offline_message::MqttOfflineMessage;
+    use storage_adapter::memory::MemoryStorageAdapter;
+    use storage_adapter::storage::{ShardConfig, ShardOffset, StorageAdapter};
+
+    use super::OfflineMessageStorage;
+
+    #[tokio::test]
+    async fn offline_message_storage_test() {
+        init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            cluster_name: "test".to_string(),
+            ..Default::default()
+        });
+
+        let storage = OfflineMessageStorage::new(Arc::new(MemoryStorageAdapter::new()));
+        let client_id = "c1";
+        assert_eq!(storage.get_read_offset(client_id).await.unwrap(), 0);
+        assert!(!storage.contains_messages(client_id, 0, 1).await.unwrap());
+
+        for i in 0..3 {
+            let message = MqttOfflineMessage {
+                message: MqttMessage {
+                    pkid: i,
+                    payload: Bytes::from(format!("m{}", i)),
+                    ..Default::default()
+                },
+                wait_pubcomp: false,
+            };
+            let offset = storage.append_message(client_id, &message).await.unwrap();
+            assert_eq!(offset, i as u64);
+        }
+        assert!(storage.contains_messages(client_id, 0, 3).await.unwrap());
+        assert!(!storage.contains_messages(client_id, 0, 4).await.unwrap());
+
+        storage.commit_read_offset(client_id, 2).await.unwrap();
+        let offset = storage.get_read_offset(client_id).await.unwrap();
+        assert_eq!(offset, 2);
+        let messages = storage.read_message(client_id, offset, 10).await.unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].0, 2);
+        assert_eq!(messages[0].1.message.payload, Bytes::from("m2"));
+
+        storage.clear(client_id).await.unwrap();
+        let offset = storage.get_read_offset(client_id).await.unwrap();
+        assert_eq!(offset, 3);
+        assert!(storage
+            .read_message(client_id, offset, 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // offsets keep growing after a clear
+        let offset = storage
+            .append_message(client_id, &MqttOfflineMessage::default())
+            .await
+            .unwrap();
+        assert_eq!(offset, 3);
+        assert!(storage.contains_messages(client_id, 3, 1).await.unwrap());
+    }
+
+    // Rejects writes to shards that were not created, like the journal engine
+    struct CreatedShardsAdapter {
+        adapter: MemoryStorageAdapter,
+        shards: DashMap<String, bool>,
+    }
+
+    #[async_trait]
+    impl StorageAdapter for CreatedShardsAdapter {
+        async fn create_shard(
+            &self,
+            namespace: String,
+            shard_name: String,
+            shard_config: ShardConfig,
+        ) -> Result<(), CommonError> {
+            self.shards.insert(shard_name.clone(), true);
+            self.adapter
+                .create_shard(namespace, shard_name, shard_config)
+                .await
+        }
+
+        async fn delete_shard(
+            &self,
+            namespace: String,
+            shard_name: String,
+        ) -> Result<(), CommonError> {
+            self.shards.remove(&shard_name);
+            self.adapter.delete_shard(namespace, shard_name).await
+        }
+
+        async fn write(
+            &self,
+            namespace: String,
+            shard_name: String,
+            data: Record,
+        ) -> Result<u64, CommonError> {
+            if !self.shards.contains_key(&shard_name) {
+                return Err(CommonError::CommonError(format!(
+                    "shard {} does not exist",
+                    shard_name
+                )));
+            }
+            self.adapter.write(namespace, shard_name, data).await
+        }
+
+        async fn batch_write(
+            &self,
+            namespace: String,
+            shard_name: String,
+            data: Vec<Record>,
+        ) -> Result<Vec<u64>, CommonError> {
+            self.adapter.batch_write(namespace, shard_name, data).await
+        }
+
+        async fn read_by_offset(
+            &self,
+            namespace: String,
+            shard_name: String,
+            offset: u64,
+            read_config: ReadConfig,
+        ) -> Result<Vec<Record>, CommonError> {
+            self.adapter
+                .read_by_offset(namespace, shard_name, offset, read_config)
+                .await
+        }
+
+        async fn read_by_tag(
+            &self,
+            namespace: String,
+            shard_name: String,
+            offset: u64,
+            tag: String,
+            read_config: ReadConfig,
+        ) -> Result<Vec<Record>, CommonError> {
+            self.adapter
+                .read_by_tag(namespace, shard_name, offset, tag, read_config)
+                .await
+        }
+
+        async fn read_by_key(
+            &self,
+            namespace: String,
+            shard_name: String,
+            offset: u64,
+            key: String,
+            read_config: ReadConfig,
+        ) -> Result<Vec<Record>, CommonError> {
+            self.adapter
+                .read_by_key(namespace, shard_name, offset, key, read_config)
+                .await
+        }
+
+        async fn get_offset_by_timestamp(
+            &self,
+            namespace: String,
+            shard_name: String,
+            timestamp: u64,
+        ) -> Result<Option<ShardOffset>, CommonError> {
+            self.adapter
+                .get_offset_by_timestamp(namespace, shard_name, timestamp)
+                .await
+        }
+
+        async fn get_offset_by_group(
+            &self,
+            group_name: String,
+        ) -> Result<Vec<ShardOffset>, CommonError> {
+            self.adapter.get_offset_by_group(group_name).await
+        }
+
+        async fn commit_offset(
+            &self,
+            group_name: String,
+            namespace: String,
+            offset: HashMap<String, u64>,
+        ) -> Result<(), CommonError> {
+            self.adapter
+                .commit_offset(group_name, namespace, offset)
+                .await
+        }
+
+        async fn close(&self) -> Result<(), CommonError> {
+            self.adapter.close().await
+        }
+    }
+
+    #[tokio::test]
+    async fn offline_message_shard_create_test() {
+        init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            cluster_name: "test".to_string(),
+            ..Default::default()
+        });
+
+        let adapter = Arc::new(CreatedShardsAdapter {
+            adapter: MemoryStorageAdapter::new(),
+            shards: DashMap::with_capacity(2),
+        });
+        let storage = OfflineMessageStorage::new(adapter.clone());
+        let client_id = "c2";
+        for i in 0..2 {
+            let offset = storage
+                .append_message(client_id, &MqttOfflineMessage::default())
+                .await
+                .unwrap();
+            assert_eq!(offset, i);
+        }
+        assert_eq!(adapter.shards.len(), 1);
+
+        // the shard is created again when the storage layer lost it
+        adapter
+            .delete_shard("test".to_string(), "offline_message_c2".to_string())
+            .await
+            .unwrap();
+        storage
+            .append_message(client_id, &MqttOfflineMessage::default())
+            .await
+            .unwrap();
+        assert!(storage.contains_messages(client_id, 0, 1).await.unwrap());
+    }
+}
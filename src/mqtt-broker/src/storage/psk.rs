@@ -0,0 +1,50 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use common_base::config::broker_mqtt::broker_mqtt_conf;
+use grpc_clients::placement::kv::call::placement_get;
+use grpc_clients::pool::ClientPool;
+use protocol::placement_center::placement_center_kv::GetRequest;
+
+use crate::handler::error::MqttBrokerError;
+
+// The pre-shared keys of a cluster are one JSON object mapping each identity to its hex key.
+pub fn psk_storage_key(cluster_name: &str) -> String {
+    format!("/mqtt/psk/{}", cluster_name)
+}
+
+pub struct PskStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl PskStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        PskStorage { client_pool }
+    }
+
+    pub async fn list_psk(&self) -> Result<HashMap<String, String>, MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = GetRequest {
+            key: psk_storage_key(&config.cluster_name),
+        };
+        let reply = placement_get(&self.client_pool, &config.placement_center, request).await?;
+        if reply.value.is_empty() {
+            return Ok(HashMap::new());
+        }
+        Ok(serde_json::from_str(&reply.value)?)
+    }
+}
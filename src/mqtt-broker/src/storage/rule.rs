@@ -0,0 +1,72 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::sync::Arc;
+
+use common_base::config::broker_mqtt::broker_mqtt_conf;
+use grpc_clients::placement::mqtt::call::{create_rule, delete_rule, list_rule};
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::rule::MqttRule;
+use protocol::placement_center::placement_center_mqtt::{
+    CreateRuleRequest, DeleteRuleRequest, ListRuleRequest,
+};
+
+use crate::handler::error::MqttBrokerError;
+
+pub struct RuleStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl RuleStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        RuleStorage { client_pool }
+    }
+
+    pub async fn list_rule(&self) -> Result<Vec<MqttRule>, MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = ListRuleRequest {
+            cluster_name: config.cluster_name.clone(),
+        };
+        let reply = list_rule(&self.client_pool, &config.placement_center, request).await?;
+        let mut list = Vec::new();
+        for raw in reply.rules {
+            list.push(MqttRule::decode(&raw)?);
+        }
+        Ok(list)
+    }
+
+    pub async fn get_rule(&self, rule_name: &str) -> Result<Option<MqttRule>, MqttBrokerError> {
+        let list = self.list_rule().await?;
+        Ok(list.into_iter().find(|rule| rule.name == rule_name))
+    }
+
+    pub async fn save_rule(&self, rule: MqttRule) -> Result<(), MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = CreateRuleRequest {
+            cluster_name: config.cluster_name.clone(),
+            rule: rule.encode()?,
+        };
+        create_rule(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    pub async fn delete_rule(&self, rule_name: &str) -> Result<(), MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = DeleteRuleRequest {
+            cluster_name: config.cluster_name.clone(),
+            rule_name: rule_name.to_string(),
+        };
+        delete_rule(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+}
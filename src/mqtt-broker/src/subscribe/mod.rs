@@ -15,6 +15,7 @@
 use protocol::mqtt::common::{Publish, PublishProperties};
 use subscriber::Subscriber;
 
+pub mod offline;
 pub mod sub_common;
 pub mod sub_exclusive;
 pub mod sub_share_follower;
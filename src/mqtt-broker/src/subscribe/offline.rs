@@ -0,0 +1,545 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_base::tools::now_second;
+use dashmap::DashMap;
+use futures::future::join_all;
+use log::{error, info, warn};
+use metadata_struct::mqtt::cluster::OfflineMessageDropPolicy;
+use metadata_struct::mqtt::message::MqttMessage;
+use metadata_struct::mqtt::offline_message::MqttOfflineMessage;
+use protocol::mqtt::common::{MqttPacket, PubRel, PubRelReason, Publish, PublishProperties, QoS};
+use storage_adapter::storage::StorageAdapter;
+use tokio::select;
+use tokio::sync::broadcast::{self, Receiver};
+use tokio::time::{sleep, timeout};
+
+use super::sub_common::write_packet_to_client;
+use super::SubPublishParam;
+use crate::handler::cache::{CacheManager, QosAckPackageData, QosAckPackageType, QosAckPacketInfo};
+use crate::handler::error::MqttBrokerError;
+use crate::handler::message::{build_message_expire, is_message_expire};
+use crate::server::connection_manager::ConnectionManager;
+use crate::server::packet::ResponsePackage;
+use crate::storage::offline::OfflineMessageStorage;
+
+const OFFLINE_MESSAGE_CHECK_INTERVAL_MS: u64 = 1000;
+
+const OFFLINE_MESSAGE_ACK_TIMEOUT_SEC: u64 = 30;
+
+pub fn is_offline_queue_enable(cache_manager: &Arc<CacheManager>) -> bool {
+    cache_manager.get_cluster_info().offline_message.enable
+}
+
+// Builds the queued copy of a QoS 1/2 message when its client is offline
+pub fn try_build_offline_message(
+    cache_manager: &Arc<CacheManager>,
+    sub_pub_param: &SubPublishParam,
+    dup: bool,
+    wait_pubcomp: bool,
+) -> Option<MqttOfflineMessage> {
+    let client_id = &sub_pub_param.subscribe.client_id;
+    if !is_offline_queue_enable(cache_manager) || cache_manager.get_connect_id(client_id).is_some()
+    {
+        return None;
+    }
+
+    let mut publish = sub_pub_param.publish.clone();
+    publish.dup = dup;
+    // The pushed properties already carry the absolute expiry time of the message
+    let expiry_interval = if let Some(expiry) = sub_pub_param
+        .properties
+        .as_ref()
+        .and_then(|properties| properties.message_expiry_interval)
+    {
+        expiry as u64
+    } else {
+        build_message_expire(cache_manager, &None)
+    };
+
+    Some(MqttOfflineMessage {
+        message: MqttMessage::build_message(
+            client_id,
+            &publish,
+            &sub_pub_param.properties,
+            expiry_interval,
+        ),
+        wait_pubcomp,
+    })
+}
+
+pub async fn save_offline_message<S>(
+    storage: &OfflineMessageStorage<S>,
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+    message: MqttOfflineMessage,
+) -> Result<(), MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let config = cache_manager.get_cluster_info().offline_message;
+    if config.max_queue_len > 0 {
+        let offset = storage.get_read_offset(client_id).await?;
+        if storage
+            .contains_messages(client_id, offset, config.max_queue_len)
+            .await?
+        {
+            match config.drop_policy {
+                OfflineMessageDropPolicy::DropNewest => {
+                    warn!(
+                        "Offline message queue of client [{}] is full, the new message is dropped",
+                        client_id
+                    );
+                    return Ok(());
+                }
+                OfflineMessageDropPolicy::DropOldest => {
+                    if let Some(first) = storage.first_offset(client_id, offset).await? {
+                        storage.commit_read_offset(client_id, first + 1).await?;
+                    }
+                }
+            }
+        }
+    }
+
+    storage.append_message(client_id, &message).await?;
+    cache_manager
+        .offline_message_client
+        .entry(client_id.to_owned())
+        .or_insert(false);
+    Ok(())
+}
+
+// Discards the queue of a session that starts clean
+pub async fn clear_offline_message<S>(
+    storage: &OfflineMessageStorage<S>,
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+) -> Result<(), MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    cache_manager.offline_message_client.remove(client_id);
+    storage.clear(client_id).await?;
+    Ok(())
+}
+
+// Messages queued while the client was offline are delivered before new ones
+pub async fn wait_offline_message_redelivery(cache_manager: &Arc<CacheManager>, client_id: &str) {
+    while cache_manager.get_connect_id(client_id).is_some()
+        && cache_manager.offline_message_client.contains_key(client_id)
+    {
+        sleep(Duration::from_millis(100)).await;
+    }
+}
+
+pub struct OfflineMessageManager<S> {
+    cache_manager: Arc<CacheManager>,
+    connection_manager: Arc<ConnectionManager>,
+    storage: OfflineMessageStorage<S>,
+    // (connect_id, bool), connections whose queue was checked after connecting
+    checked_connection: DashMap<u64, bool>,
+}
+
+impl<S> OfflineMessageManager<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(
+        cache_manager: Arc<CacheManager>,
+        connection_manager: Arc<ConnectionManager>,
+        message_storage_adapter: Arc<S>,
+    ) -> Self {
+        OfflineMessageManager {
+            cache_manager,
+            connection_manager,
+            storage: OfflineMessageStorage::new(message_storage_adapter),
+            checked_connection: DashMap::with_capacity(8),
+        }
+    }
+
+    pub async fn start(&self, stop_send: broadcast::Sender<bool>) {
+        let mut stop_rx = stop_send.subscribe();
+        loop {
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("{}","Offline message thread stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                _ = self.redelivery_round()=>{
+                }
+            }
+        }
+    }
+
+    async fn redelivery_round(&self) {
+        self.try_redelivery();
+        sleep(Duration::from_millis(OFFLINE_MESSAGE_CHECK_INTERVAL_MS)).await;
+    }
+
+    // A connection is checked once after it logs in, the queue may have been filled by
+    // another broker before a session takeover. Afterwards only the sessions that this
+    // broker queued messages for are redelivered.
+    fn try_redelivery(&self) {
+        self.checked_connection
+            .retain(|connect_id, _| self.cache_manager.connection_info.contains_key(connect_id));
+
+        for conn in self.cache_manager.connection_info.iter() {
+            if !conn.is_login || conn.clean_session {
+                continue;
+            }
+
+            let queued = self
+                .cache_manager
+                .offline_message_client
+                .get(&conn.client_id);
+            if queued.as_ref().is_some_and(|redelivering| **redelivering) {
+                continue;
+            }
+            if queued.is_none() && self.checked_connection.contains_key(&conn.connect_id) {
+                continue;
+            }
+            drop(queued);
+
+            self.checked_connection.insert(conn.connect_id, true);
+            self.cache_manager
+                .offline_message_client
+                .insert(conn.client_id.clone(), true);
+
+            let cache_manager = self.cache_manager.clone();
+            let connection_manager = self.connection_manager.clone();
+            let storage = self.storage.clone();
+            let client_id = conn.client_id.clone();
+            let connect_id = conn.connect_id;
+            tokio::spawn(async move {
+                if let Err(e) = redelivery_offline_message(
+                    &cache_manager,
+                    &connection_manager,
+                    &storage,
+                    &client_id,
+                    connect_id,
+                )
+                .await
+                {
+                    error!(
+                        "Redelivery of offline messages to client [{}] failed, {}",
+                        client_id, e
+                    );
+                }
+                if let Some(mut redelivering) =
+                    cache_manager.offline_message_client.get_mut(&client_id)
+                {
+                    *redelivering = false;
+                }
+            });
+        }
+    }
+}
+
+// Resends the queue in batches of at most receive_max messages, the read offset
+// only moves past messages the client has acknowledged.
+async fn redelivery_offline_message<S>(
+    cache_manager: &Arc<CacheManager>,
+    connection_manager: &Arc<ConnectionManager>,
+    storage: &OfflineMessageStorage<S>,
+    client_id: &str,
+    connect_id: u64,
+) -> Result<(), MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let mut retry = false;
+    loop {
+        if cache_manager.get_connect_id(client_id) != Some(connect_id) {
+            return Ok(());
+        }
+
+        let receive_max = if let Some(conn) = cache_manager.get_connection(connect_id) {
+            conn.client_max_receive_maximum.max(1)
+        } else {
+            return Ok(());
+        };
+
+        let offset = storage.get_read_offset(client_id).await?;
+        let messages = storage
+            .read_message(client_id, offset, receive_max as u64)
+            .await?;
+        if messages.is_empty() {
+            cache_manager.offline_message_client.remove(client_id);
+            return Ok(());
+        }
+
+        let last_offset = messages.last().unwrap().0;
+        let mut tasks = Vec::new();
+        for (message_offset, message) in messages {
+            tasks.push(async move {
+                if is_message_expire(&message.message) {
+                    return (message_offset, true);
+                }
+                let acked = resend_offline_message(
+                    cache_manager,
+                    connection_manager,
+                    client_id,
+                    connect_id,
+                    message,
+                    retry,
+                )
+                .await;
+                (message_offset, acked)
+            });
+        }
+
+        let mut commit_offset = last_offset + 1;
+        for (message_offset, acked) in join_all(tasks).await {
+            if !acked {
+                commit_offset = commit_offset.min(message_offset);
+            }
+        }
+        if commit_offset > offset {
+            storage.commit_read_offset(client_id, commit_offset).await?;
+        }
+        retry = commit_offset <= last_offset;
+    }
+}
+
+async fn resend_offline_message(
+    cache_manager: &Arc<CacheManager>,
+    connection_manager: &Arc<ConnectionManager>,
+    client_id: &str,
+    connect_id: u64,
+    offline_message: MqttOfflineMessage,
+    retry: bool,
+) -> bool {
+    let message = offline_message.message;
+    let pkid = if message.pkid > 0 {
+        cache_manager.add_pkid_info(client_id, message.pkid);
+        message.pkid
+    } else {
+        cache_manager.get_pkid(client_id).await
+    };
+
+    let (wait_ack_sx, _) = broadcast::channel(2);
+    let mut wait_ack_rx = wait_ack_sx.subscribe();
+    cache_manager.add_ack_packet(
+        client_id,
+        pkid,
+        QosAckPacketInfo {
+            sx: wait_ack_sx,
+            create_time: now_second(),
+        },
+    );
+
+    let acked = if offline_message.wait_pubcomp {
+        send_pubrel(connection_manager, connect_id, pkid).await
+            && wait_ack(&mut wait_ack_rx, QosAckPackageType::PubComp, pkid).await
+    } else {
+        let qos = message.qos;
+        let contain_properties = connection_manager
+            .get_connect_protocol(connect_id)
+            .is_some_and(|protocol| protocol.is_mqtt5());
+        let properties = if contain_properties {
+            Some(PublishProperties {
+                payload_format_indicator: message.format_indicator,
+                message_expiry_interval: Some(message.expiry_interval as u32),
+                topic_alias: None,
+                response_topic: message.response_topic,
+                correlation_data: message.correlation_data,
+                user_properties: message.user_properties,
+                subscription_identifiers: message.subscription_identifiers,
+                content_type: message.content_type,
+            })
+        } else {
+            None
+        };
+        let publish = Publish {
+            dup: message.dup || retry,
+            qos,
+            pkid,
+            retain: message.retain,
+            topic: message.topic,
+            payload: message.payload,
+        };
+
+        let resp = ResponsePackage {
+            connection_id: connect_id,
+            packet: MqttPacket::Publish(publish, properties),
+        };
+        if !write_packet(resp, connection_manager).await {
+            false
+        } else {
+            match qos {
+                QoS::AtMostOnce => true,
+                QoS::AtLeastOnce => {
+                    wait_ack(&mut wait_ack_rx, QosAckPackageType::PubAck, pkid).await
+                }
+                QoS::ExactlyOnce => {
+                    wait_ack(&mut wait_ack_rx, QosAckPackageType::PubRec, pkid).await
+                        && send_pubrel(connection_manager, connect_id, pkid).await
+                        && wait_ack(&mut wait_ack_rx, QosAckPackageType::PubComp, pkid).await
+                }
+            }
+        }
+    };
+
+    cache_manager.remove_pkid_info(client_id, pkid);
+    cache_manager.remove_ack_packet(client_id, pkid);
+    acked
+}
+
+async fn send_pubrel(
+    connection_manager: &Arc<ConnectionManager>,
+    connect_id: u64,
+    pkid: u16,
+) -> bool {
+    let resp = ResponsePackage {
+        connection_id: connect_id,
+        packet: MqttPacket::PubRel(
+            PubRel {
+                pkid,
+                reason: Some(PubRelReason::Success),
+            },
+            None,
+        ),
+    };
+    write_packet(resp, connection_manager).await
+}
+
+async fn write_packet(resp: ResponsePackage, connection_manager: &Arc<ConnectionManager>) -> bool {
+    match write_packet_to_client(resp, connection_manager).await {
+        Ok(written) => written,
+        Err(e) => {
+            error!("Failed to resend offline message, failure message: {}", e);
+            false
+        }
+    }
+}
+
+async fn wait_ack(
+    wait_ack_rx: &mut Receiver<QosAckPackageData>,
+    ack_type: QosAckPackageType,
+    pkid: u16,
+) -> bool {
+    let res = timeout(
+        Duration::from_secs(OFFLINE_MESSAGE_ACK_TIMEOUT_SEC),
+        async {
+            loop {
+                match wait_ack_rx.recv().await {
+                    Ok(data) => {
+                        if data.ack_type == ack_type && data.pkid == pkid {
+                            return true;
+                        }
+                    }
+                    Err(_) => return false,
+                }
+            }
+        },
+    );
+    res.await.unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use common_base::config::broker_mqtt::{init_broker_mqtt_conf_by_config, BrokerMqttConfig};
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::cluster::{
+        MqttClusterDynamicConfig, MqttClusterDynamicConfigOfflineMessage, OfflineMessageDropPolicy,
+    };
+    use metadata_struct::mqtt::message::MqttMessage;
+    use metadata_struct::mqtt::offline_message::MqttOfflineMessage;
+    use storage_adapter::memory::MemoryStorageAdapter;
+
+    use super::{clear_offline_message, save_offline_message};
+    use crate::handler::cache::CacheManager;
+    use crate::storage::offline::OfflineMessageStorage;
+
+    fn build_cache_manager(drop_policy: OfflineMessageDropPolicy) -> Arc<CacheManager> {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        cache_manager.cluster_info.insert(
+            "test".to_string(),
+            MqttClusterDynamicConfig {
+                offline_message: MqttClusterDynamicConfigOfflineMessage {
+                    enable: true,
+                    max_queue_len: 2,
+                    drop_policy,
+                },
+                ..Default::default()
+            },
+        );
+        cache_manager
+    }
+
+    async fn queued_payloads(
+        storage: &OfflineMessageStorage<MemoryStorageAdapter>,
+        client_id: &str,
+    ) -> Vec<Bytes> {
+        let offset = storage.get_read_offset(client_id).await.unwrap();
+        storage
+            .read_message(client_id, offset, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(_, message)| message.message.payload)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn save_offline_message_drop_policy_test() {
+        init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            cluster_name: "test".to_string(),
+            ..Default::default()
+        });
+
+        for (drop_policy, expect) in [
+            (OfflineMessageDropPolicy::DropOldest, ["m1", "m2"]),
+            (OfflineMessageDropPolicy::DropNewest, ["m0", "m1"]),
+        ] {
+            let cache_manager = build_cache_manager(drop_policy);
+            let storage = OfflineMessageStorage::new(Arc::new(MemoryStorageAdapter::new()));
+            let client_id = "c1";
+
+            for i in 0..3 {
+                let message = MqttOfflineMessage {
+                    message: MqttMessage {
+                        payload: Bytes::from(format!("m{}", i)),
+                        ..Default::default()
+                    },
+                    wait_pubcomp: false,
+                };
+                save_offline_message(&storage, &cache_manager, client_id, message)
+                    .await
+                    .unwrap();
+            }
+            assert!(cache_manager.offline_message_client.contains_key(client_id));
+            assert_eq!(
+                queued_payloads(&storage, client_id).await,
+                expect.map(Bytes::from).to_vec()
+            );
+
+            clear_offline_message(&storage, &cache_manager, client_id)
+                .await
+                .unwrap();
+            assert!(!cache_manager.offline_message_client.contains_key(client_id));
+            assert!(queued_payloads(&storage, client_id).await.is_empty());
+        }
+    }
+}
@@ -20,6 +20,7 @@ use bytes::BytesMut;
 use common_base::config::broker_mqtt::broker_mqtt_conf;
 use common_base::error::common::CommonError;
 use common_base::tools::now_mills;
+use common_base::utils::topic_trie::topic_match;
 use grpc_clients::placement::mqtt::call::{
     placement_delete_exclusive_topic, placement_get_share_sub_leader,
     placement_set_nx_exclusive_topic,
@@ -73,35 +74,22 @@ pub fn sub_path_validator(sub_path: String) -> bool {
     true
 }
 
-pub fn path_regex_match(topic_name: String, sub_path: String) -> bool {
-    let path = if is_share_sub(sub_path.clone()) {
-        let (_, group_path) = decode_share_info(sub_path);
-        group_path
-    } else {
-        sub_path
-    };
-
-    // Path perfect matching
-    if topic_name == path {
-        return true;
-    }
-
-    if path.contains("+") {
-        let sub_regex = path.replace("+", "[^+*/]+");
-        let re = Regex::new(&sub_regex.to_string()).unwrap();
-        return re.is_match(&topic_name);
-    }
+pub fn path_match(topic_name: &str, sub_path: &str) -> bool {
+    topic_match(topic_name, &sub_path_filter(sub_path))
+}
 
-    if path.contains("#") {
-        if path.split("/").last().unwrap() != "#" {
-            return false;
+// The topic filter of a subscription path, $share/{group}/a/b and $queue/a/b both filter /a/b.
+pub fn sub_path_filter(sub_path: &str) -> String {
+    if let Some(path) = sub_path.strip_prefix(SHARE_SUB_PREFIX) {
+        if let Some((_, path)) = path.trim_start_matches('/').split_once('/') {
+            return format!("/{}", path);
+        }
+    } else if let Some(path) = sub_path.strip_prefix(QUEUE_SUB_PREFIX) {
+        if let Some(path) = path.strip_prefix('/') {
+            return format!("/{}", path);
         }
-        let sub_regex = path.replace("#", "[^+#]+");
-        let re = Regex::new(&sub_regex.to_string()).unwrap();
-        return re.is_match(&topic_name);
     }
-
-    false
+    sub_path.to_string()
 }
 
 pub fn min_qos(qos: QoS, sub_qos: QoS) -> QoS {
@@ -116,9 +104,9 @@ pub async fn get_sub_topic_id_list(
     sub_path: &str,
 ) -> Vec<String> {
     let mut result = Vec::new();
-    for (topic_id, topic_name) in metadata_cache.topic_id_name.clone() {
-        if path_regex_match(topic_name.clone(), sub_path.to_string()) {
-            result.push(topic_id);
+    for topic_name in metadata_cache.get_topic_names_by_filter(sub_path) {
+        if let Some(topic) = metadata_cache.topic_info.get(&topic_name) {
+            result.push(topic.topic_id.clone());
         }
     }
     result
@@ -207,29 +195,7 @@ pub async fn publish_message_to_client(
     connection_manager: &Arc<ConnectionManager>,
     metadata_cache: &Arc<CacheManager>,
 ) -> Result<(), MqttBrokerError> {
-    if let Some(protocol) = connection_manager.get_connect_protocol(resp.connection_id) {
-        let response: MqttPacketWrapper = MqttPacketWrapper {
-            protocol_version: protocol.clone().into(),
-            packet: resp.packet,
-        };
-
-        if connection_manager.is_websocket(resp.connection_id) {
-            let mut codec = MqttCodec::new(Some(protocol.into()));
-            let mut buff = BytesMut::new();
-            match codec.encode_data(response.clone(), &mut buff) {
-                Ok(()) => {}
-                Err(e) => {
-                    error!("Websocket encode back packet failed with error message: {e:?}");
-                }
-            }
-            connection_manager
-                .write_websocket_frame(resp.connection_id, response, Message::Binary(buff.to_vec()))
-                .await?;
-        } else {
-            connection_manager
-                .write_tcp_frame(resp.connection_id, response)
-                .await?
-        }
+    if write_packet_to_client(resp, connection_manager).await? {
         // record slow sub data
         if metadata_cache.get_slow_sub_config().enable && sub_pub_param.create_time > 0 {
             let slow_data = SlowSubData::build(
@@ -245,6 +211,43 @@ pub async fn publish_message_to_client(
     Ok(())
 }
 
+// Returns false when the connection does not exist or has not finished connecting
+pub async fn write_packet_to_client(
+    resp: ResponsePackage,
+    connection_manager: &Arc<ConnectionManager>,
+) -> Result<bool, MqttBrokerError> {
+    let protocol =
+        if let Some(protocol) = connection_manager.get_connect_protocol(resp.connection_id) {
+            protocol
+        } else {
+            return Ok(false);
+        };
+
+    let response: MqttPacketWrapper = MqttPacketWrapper {
+        protocol_version: protocol.clone().into(),
+        packet: resp.packet,
+    };
+
+    if connection_manager.is_websocket(resp.connection_id) {
+        let mut codec = MqttCodec::new(Some(protocol.into()));
+        let mut buff = BytesMut::new();
+        match codec.encode_data(response.clone(), &mut buff) {
+            Ok(()) => {}
+            Err(e) => {
+                error!("Websocket encode back packet failed with error message: {e:?}");
+            }
+        }
+        connection_manager
+            .write_websocket_frame(resp.connection_id, response, Message::Binary(buff.to_vec()))
+            .await?;
+    } else {
+        connection_manager
+            .write_tcp_frame(resp.connection_id, response)
+            .await?
+    }
+    Ok(true)
+}
+
 pub async fn qos2_send_publish(
     connection_manager: &Arc<ConnectionManager>,
     metadata_cache: &Arc<CacheManager>,
@@ -485,12 +488,12 @@ mod tests {
     use common_base::tools::unique_id;
     use grpc_clients::pool::ClientPool;
     use metadata_struct::mqtt::topic::MqttTopic;
-    use protocol::mqtt::common::QoS;
+    use protocol::mqtt::common::{Filter, MqttProtocol, QoS, RetainForwardRule, Subscribe};
 
     use crate::handler::cache::CacheManager;
     use crate::subscribe::sub_common::{
-        decode_share_info, get_sub_topic_id_list, is_share_sub, min_qos, path_regex_match,
-        sub_path_validator,
+        decode_share_info, get_sub_topic_id_list, is_share_sub, min_qos, path_match,
+        sub_path_filter, sub_path_validator,
     };
 
     #[tokio::test]
@@ -537,58 +540,58 @@ mod tests {
         assert_eq!(topic_name, "/finance/#".to_string());
     }
     #[test]
-    fn path_regex_match_test() {
+    fn path_match_test() {
         let topic_name = "/loboxu/test".to_string();
         let sub_regex = "/loboxu/#".to_string();
-        assert!(path_regex_match(topic_name, sub_regex));
+        assert!(path_match(&topic_name, &sub_regex));
 
         let topic_name = "/topic/test".to_string();
         let sub_regex = "/topic/test".to_string();
-        assert!(path_regex_match(topic_name, sub_regex));
+        assert!(path_match(&topic_name, &sub_regex));
 
         let topic_name = r"/sensor/1/temperature".to_string();
         let sub_regex = r"/sensor/+/temperature".to_string();
-        assert!(path_regex_match(topic_name, sub_regex));
+        assert!(path_match(&topic_name, &sub_regex));
 
         let topic_name = r"/sensor/1/2/temperature3".to_string();
         let sub_regex = r"/sensor/+/temperature".to_string();
-        assert!(!path_regex_match(topic_name, sub_regex));
+        assert!(!path_match(&topic_name, &sub_regex));
 
         let topic_name = r"/sensor/temperature3".to_string();
         let sub_regex = r"/sensor/+/temperature".to_string();
-        assert!(!path_regex_match(topic_name, sub_regex));
+        assert!(!path_match(&topic_name, &sub_regex));
 
         let topic_name = r"/sensor/temperature3".to_string();
         let sub_regex = r"/sensor/+".to_string();
-        assert!(path_regex_match(topic_name, sub_regex));
+        assert!(path_match(&topic_name, &sub_regex));
 
         let topic_name = r"/sensor/temperature3/tmpq".to_string();
         let sub_regex = r"/sensor/#".to_string();
-        assert!(path_regex_match(topic_name, sub_regex));
+        assert!(path_match(&topic_name, &sub_regex));
 
         let topic_name = "/topic/test".to_string();
         let sub_regex = "$share/groupname/topic/test".to_string();
-        assert!(path_regex_match(topic_name, sub_regex));
+        assert!(path_match(&topic_name, &sub_regex));
 
         let topic_name = r"/sensor/1/temperature".to_string();
         let sub_regex = r"$share/groupname/sensor/+/temperature".to_string();
-        assert!(path_regex_match(topic_name, sub_regex));
+        assert!(path_match(&topic_name, &sub_regex));
 
         let topic_name = r"/sensor/1/2/temperature3".to_string();
         let sub_regex = r"$share/groupname/sensor/+/temperature".to_string();
-        assert!(!path_regex_match(topic_name, sub_regex));
+        assert!(!path_match(&topic_name, &sub_regex));
 
         let topic_name = r"/sensor/temperature3".to_string();
         let sub_regex = r"$share/groupname/sensor/+/temperature".to_string();
-        assert!(!path_regex_match(topic_name, sub_regex));
+        assert!(!path_match(&topic_name, &sub_regex));
 
         let topic_name = r"/sensor/temperature3".to_string();
         let sub_regex = r"$share/groupname/sensor/+".to_string();
-        assert!(path_regex_match(topic_name, sub_regex));
+        assert!(path_match(&topic_name, &sub_regex));
 
         let topic_name = r"/sensor/temperature3/tmpq".to_string();
         let sub_regex = r"$share/groupname/sensor/#".to_string();
-        assert!(path_regex_match(topic_name, sub_regex));
+        assert!(path_match(&topic_name, &sub_regex));
     }
 
     #[test]
@@ -614,6 +617,105 @@ mod tests {
         let result = get_sub_topic_id_list(&metadata_cache, &sub_path).await;
         assert!(result.len() == 1);
         assert_eq!(result.first().unwrap().clone(), topic.topic_id);
+
+        let other_name = "/test/other".to_string();
+        let other = MqttTopic::new(unique_id(), "c1".to_string(), other_name.clone());
+        metadata_cache.add_topic(&other_name, &other);
+        let sys_name = "$SYS/brokers".to_string();
+        let sys = MqttTopic::new(unique_id(), "c1".to_string(), sys_name.clone());
+        metadata_cache.add_topic(&sys_name, &sys);
+
+        let mut result = get_sub_topic_id_list(&metadata_cache, "/test/+").await;
+        result.sort();
+        let mut expect = vec![topic.topic_id.clone(), other.topic_id.clone()];
+        expect.sort();
+        assert_eq!(result, expect);
+        assert_eq!(
+            get_sub_topic_id_list(&metadata_cache, "$share/g1/test/other").await,
+            vec![other.topic_id.clone()]
+        );
+        assert_eq!(
+            get_sub_topic_id_list(&metadata_cache, "$queue/test/other").await,
+            vec![other.topic_id.clone()]
+        );
+        assert_eq!(get_sub_topic_id_list(&metadata_cache, "#").await.len(), 2);
+        assert_eq!(
+            get_sub_topic_id_list(&metadata_cache, "$SYS/#").await,
+            vec![sys.topic_id]
+        );
+    }
+
+    #[test]
+    fn subscribe_index_test() {
+        let client_pool: Arc<ClientPool> = Arc::new(ClientPool::new(100));
+        let metadata_cache = CacheManager::new(client_pool, "test-cluster".to_string());
+        let filter = |path: &str| Filter {
+            path: path.to_string(),
+            qos: QoS::AtLeastOnce,
+            nolocal: false,
+            preserve_retain: false,
+            retain_forward_rule: RetainForwardRule::OnEverySubscribe,
+        };
+        let subscribe = |paths: &[&str]| Subscribe {
+            packet_identifier: 1,
+            filters: paths.iter().map(|path| filter(path)).collect(),
+        };
+        metadata_cache.add_client_subscribe(
+            "c1".to_string(),
+            MqttProtocol::Mqtt5,
+            subscribe(&["/sensor/+/temperature", "$share/g1/sensor/#"]),
+            None,
+        );
+        metadata_cache.add_client_subscribe(
+            "c2".to_string(),
+            MqttProtocol::Mqtt4,
+            subscribe(&["/sensor/1/temperature"]),
+            None,
+        );
+
+        let mut result = metadata_cache.get_subscribe_by_topic("/sensor/1/temperature");
+        result.sort();
+        assert_eq!(
+            result,
+            vec![
+                ("c1".to_string(), "$share/g1/sensor/#".to_string()),
+                ("c1".to_string(), "/sensor/+/temperature".to_string()),
+                ("c2".to_string(), "/sensor/1/temperature".to_string()),
+            ]
+        );
+        assert_eq!(
+            metadata_cache.get_subscribe_by_topic("/sensor"),
+            vec![("c1".to_string(), "$share/g1/sensor/#".to_string())]
+        );
+
+        metadata_cache.remove_filter_by_pkid("c1", &["/sensor/+/temperature".to_string()]);
+        assert_eq!(
+            metadata_cache
+                .get_subscribe_by_topic("/sensor/1/temperature")
+                .len(),
+            2
+        );
+
+        metadata_cache.remove_session("c1");
+        assert_eq!(
+            metadata_cache.get_subscribe_by_topic("/sensor/1/temperature"),
+            vec![("c2".to_string(), "/sensor/1/temperature".to_string())]
+        );
+    }
+
+    #[test]
+    fn sub_path_filter_test() {
+        assert_eq!(
+            sub_path_filter("/sensor/+/temperature"),
+            "/sensor/+/temperature"
+        );
+        assert_eq!(sub_path_filter("$share/g1/sensor/#"), "/sensor/#");
+        assert_eq!(sub_path_filter("$queue/sensor/#"), "/sensor/#");
+        assert_eq!(sub_path_filter("$SYS/#"), "$SYS/#");
+        assert_eq!(
+            sub_path_filter("$share/g1/sensor/#"),
+            decode_share_info("$share/g1/sensor/#".to_string()).1
+        );
     }
 
     #[tokio::test]
@@ -20,12 +20,17 @@ use common_base::tools::now_second;
 use log::{debug, error, info};
 use metadata_struct::adapter::record::Record;
 use metadata_struct::mqtt::message::MqttMessage;
+use metadata_struct::mqtt::offline_message::MqttOfflineMessage;
 use protocol::mqtt::common::{MqttPacket, MqttProtocol, Publish, PublishProperties, QoS};
 use storage_adapter::storage::StorageAdapter;
 use tokio::select;
 use tokio::sync::broadcast::{self};
 use tokio::time::sleep;
 
+use super::offline::{
+    is_offline_queue_enable, save_offline_message, try_build_offline_message,
+    wait_offline_message_redelivery,
+};
 use super::sub_common::{
     loop_commit_offset, min_qos, publish_message_qos0, publish_message_to_client,
     qos2_send_publish, qos2_send_pubrel, wait_packet_ack,
@@ -38,6 +43,7 @@ use crate::handler::message::is_message_expire;
 use crate::server::connection_manager::ConnectionManager;
 use crate::server::packet::ResponsePackage;
 use crate::storage::message::MessageStorage;
+use crate::storage::offline::OfflineMessageStorage;
 use crate::subscribe::SubPublishParam;
 
 pub struct SubscribeExclusive<S> {
@@ -105,6 +111,7 @@ where
             let (sub_thread_stop_sx, mut sub_thread_stop_rx) = broadcast::channel(1);
 
             let message_storage = MessageStorage::new(self.message_storage.clone());
+            let offline_storage = OfflineMessageStorage::new(self.message_storage.clone());
             let cache_manager = self.cache_manager.clone();
             let connection_manager = self.connection_manager.clone();
             let subscribe_manager = self.subscribe_manager.clone();
@@ -153,6 +160,7 @@ where
                         val = pub_message(
                                 &connection_manager,
                                 &message_storage,
+                                &offline_storage,
                                 &cache_manager,
                                 &subscriber,
                                 &group_id,
@@ -191,6 +199,7 @@ where
 async fn pub_message<S>(
     connection_manager: &Arc<ConnectionManager>,
     message_storage: &MessageStorage<S>,
+    offline_storage: &OfflineMessageStorage<S>,
     cache_manager: &Arc<CacheManager>,
     subscriber: &Subscriber,
     group_id: &str,
@@ -232,16 +241,23 @@ where
             continue;
         };
 
+        wait_offline_message_redelivery(cache_manager, &client_id).await;
+
         let pkid = sub_pub_param.pkid;
         match qos {
             QoS::AtMostOnce => {
-                publish_message_qos0(
-                    cache_manager,
-                    connection_manager,
-                    &sub_pub_param,
-                    sub_thread_stop_sx,
-                )
-                .await;
+                // QoS 0 messages are not queued for an offline client
+                if cache_manager.get_connect_id(&client_id).is_some()
+                    || !is_offline_queue_enable(cache_manager)
+                {
+                    publish_message_qos0(
+                        cache_manager,
+                        connection_manager,
+                        &sub_pub_param,
+                        sub_thread_stop_sx,
+                    )
+                    .await;
+                }
             }
 
             QoS::AtLeastOnce => {
@@ -255,7 +271,7 @@ where
                     },
                 );
 
-                exclusive_publish_message_qos1(
+                let offline_message = exclusive_publish_message_qos1(
                     cache_manager,
                     connection_manager,
                     &sub_pub_param,
@@ -266,6 +282,11 @@ where
 
                 cache_manager.remove_pkid_info(&client_id, pkid);
                 cache_manager.remove_ack_packet(&client_id, pkid);
+
+                if let Some(message) = offline_message {
+                    save_offline_message(offline_storage, cache_manager, &client_id, message)
+                        .await?;
+                }
             }
 
             QoS::ExactlyOnce => {
@@ -279,7 +300,7 @@ where
                     },
                 );
 
-                exclusive_publish_message_qos2(
+                let offline_message = exclusive_publish_message_qos2(
                     cache_manager,
                     connection_manager,
                     &sub_pub_param,
@@ -290,6 +311,11 @@ where
 
                 cache_manager.remove_pkid_info(&client_id, pkid);
                 cache_manager.remove_ack_packet(&client_id, pkid);
+
+                if let Some(message) = offline_message {
+                    save_offline_message(offline_storage, cache_manager, &client_id, message)
+                        .await?;
+                }
             }
         }
 
@@ -372,19 +398,20 @@ async fn buile_pub_message(
 // When the subscribed QOS is 1, we need to keep retrying to send the message to the client.
 // To avoid messages that are not successfully pushed to the client. When the client Session expires,
 // the push thread will exit automatically and will not attempt to push again.
+// If the client goes offline before the PUBACK, the message is returned to be put in the offline queue.
 pub async fn exclusive_publish_message_qos1(
     metadata_cache: &Arc<CacheManager>,
     connection_manager: &Arc<ConnectionManager>,
     sub_pub_param: &SubPublishParam,
     stop_sx: &broadcast::Sender<bool>,
     wait_puback_sx: &broadcast::Sender<QosAckPackageData>,
-) -> Result<(), MqttBrokerError> {
+) -> Result<Option<MqttOfflineMessage>, MqttBrokerError> {
     let mut retry_times = 0;
     let mut publish = sub_pub_param.publish.clone();
     loop {
         if let Ok(flag) = stop_sx.subscribe().try_recv() {
             if flag {
-                return Ok(());
+                return Ok(None);
             }
         }
 
@@ -392,13 +419,18 @@ pub async fn exclusive_publish_message_qos1(
             if let Some(id) = metadata_cache.get_connect_id(&sub_pub_param.subscribe.client_id) {
                 id
             } else {
+                if let Some(message) =
+                    try_build_offline_message(metadata_cache, sub_pub_param, retry_times > 0, false)
+                {
+                    return Ok(Some(message));
+                }
                 sleep(Duration::from_secs(1)).await;
                 continue;
             };
 
         if let Some(conn) = metadata_cache.get_connection(connect_id) {
             if publish.payload.len() > (conn.max_packet_size as usize) {
-                return Ok(());
+                return Ok(None);
             }
         }
 
@@ -431,7 +463,7 @@ pub async fn exclusive_publish_message_qos1(
                 if let Some(data) = wait_packet_ack(wait_puback_sx).await {
                     if data.ack_type == QosAckPackageType::PubAck && data.pkid == sub_pub_param.pkid
                     {
-                        return Ok(());
+                        return Ok(None);
                     }
                 }
             }
@@ -450,21 +482,25 @@ pub async fn exclusive_publish_message_qos1(
 // wait pubrec message
 // send pubrel message
 // wait pubcomp message
+// If the client goes offline before the PUBCOMP, the message is returned to be put in the offline queue.
 pub async fn exclusive_publish_message_qos2(
     metadata_cache: &Arc<CacheManager>,
     connection_manager: &Arc<ConnectionManager>,
     sub_pub_param: &SubPublishParam,
     stop_sx: &broadcast::Sender<bool>,
     wait_ack_sx: &broadcast::Sender<QosAckPackageData>,
-) -> Result<(), MqttBrokerError> {
+) -> Result<Option<MqttOfflineMessage>, MqttBrokerError> {
     // 1. send Publish to Client
+    if let Some(message) = try_build_offline_message(metadata_cache, sub_pub_param, false, false) {
+        return Ok(Some(message));
+    }
     qos2_send_publish(connection_manager, metadata_cache, sub_pub_param, stop_sx).await?;
 
     // 2. wait PubRec ack
     loop {
         if let Ok(flag) = stop_sx.subscribe().try_recv() {
             if flag {
-                return Ok(());
+                return Ok(None);
             }
         }
         if let Some(data) = wait_packet_ack(wait_ack_sx).await {
@@ -472,6 +508,11 @@ pub async fn exclusive_publish_message_qos2(
                 break;
             }
         } else {
+            if let Some(message) =
+                try_build_offline_message(metadata_cache, sub_pub_param, true, false)
+            {
+                return Ok(Some(message));
+            }
             qos2_send_publish(connection_manager, metadata_cache, sub_pub_param, stop_sx).await?;
         }
         sleep(Duration::from_millis(1)).await;
@@ -492,11 +533,16 @@ pub async fn exclusive_publish_message_qos2(
                 break;
             }
         } else {
+            if let Some(message) =
+                try_build_offline_message(metadata_cache, sub_pub_param, true, true)
+            {
+                return Ok(Some(message));
+            }
             qos2_send_pubrel(metadata_cache, sub_pub_param, connection_manager, stop_sx).await;
         }
         sleep(Duration::from_millis(1)).await;
     }
-    Ok(())
+    Ok(None)
 }
 
 fn build_group_name(subscriber: &Subscriber) -> String {
@@ -20,22 +20,27 @@ use common_base::error::common::CommonError;
 use common_base::utils::topic_util;
 use dashmap::DashMap;
 use grpc_clients::pool::ClientPool;
-use log::{error, info};
+use log::{error, info, warn};
 use metadata_struct::mqtt::cluster::AvailableFlag;
 use protocol::mqtt::common::{
     Filter, MqttProtocol, Subscribe, SubscribeProperties, SubscribeReasonCode, Unsubscribe,
 };
 use serde::{Deserialize, Serialize};
+use tokio::select;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::broadcast::Sender;
-use tokio::time::sleep;
+use tokio::time::{interval, MissedTickBehavior};
 
 use super::sub_common::{
     decode_queue_info, decode_share_info, delete_exclusive_topic, get_share_sub_leader,
-    is_queue_sub, is_share_sub, path_regex_match, set_nx_exclusive_topic,
+    is_queue_sub, is_share_sub, path_match, set_nx_exclusive_topic,
 };
 use crate::handler::cache::CacheManager;
 use crate::subscribe::subscriber::Subscriber;
 
+// New topics are bound as they are created, the sweep only reconciles missed events
+const SUBSCRIBE_RECONCILE_INTERVAL_SEC: u64 = 60;
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ShareSubShareSub {
     pub client_id: String,
@@ -114,32 +119,64 @@ impl SubscribeManager {
 
     pub async fn start(&self) {
         info!("Subscribe manager thread started successfully.");
+        let mut topic_create_rx = self.metadata_cache.topic_create_sx.subscribe();
+        let mut reconcile = interval(Duration::from_secs(SUBSCRIBE_RECONCILE_INTERVAL_SEC));
+        reconcile.set_missed_tick_behavior(MissedTickBehavior::Delay);
         loop {
-            self.parse_subscribe_by_new_topic().await;
-            sleep(Duration::from_secs(10)).await;
+            select! {
+                val = topic_create_rx.recv() => {
+                    match val {
+                        Ok(topic) => {
+                            self.parse_subscribe_by_topic(&topic.topic_name, &topic.topic_id)
+                                .await;
+                        }
+                        Err(RecvError::Lagged(num)) => {
+                            warn!(
+                                "Subscribe manager missed {} topic creation events, reconciling all topics.",
+                                num
+                            );
+                            self.parse_subscribe_by_new_topic().await;
+                        }
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+                _ = reconcile.tick() => {
+                    self.parse_subscribe_by_new_topic().await;
+                }
+            }
         }
     }
 
     pub async fn parse_subscribe_by_new_topic(&self) {
         for (topic_name, topic) in self.metadata_cache.topic_info.clone() {
-            for (client_id, sub_list) in self.metadata_cache.subscribe_filter.clone() {
-                for (_, data) in sub_list {
-                    let subscribe = Subscribe {
-                        packet_identifier: 0,
-                        filters: vec![data.filter],
-                    };
-                    let subscribe_properties = data.subscribe_properties;
-                    self.parse_subscribe(
-                        topic_name.clone(),
-                        topic.topic_id.clone(),
-                        client_id.clone(),
-                        data.protocol.clone(),
-                        subscribe,
-                        subscribe_properties,
-                    )
-                    .await;
-                }
-            }
+            self.parse_subscribe_by_topic(&topic_name, &topic.topic_id)
+                .await;
+        }
+    }
+
+    // Binds a topic to the existing subscriptions whose filter matches it
+    pub async fn parse_subscribe_by_topic(&self, topic_name: &str, topic_id: &str) {
+        for (client_id, path) in self.metadata_cache.get_subscribe_by_topic(topic_name) {
+            let data = match self.metadata_cache.subscribe_filter.get(&client_id) {
+                Some(sub_list) => match sub_list.get(&path) {
+                    Some(data) => data.clone(),
+                    None => continue,
+                },
+                None => continue,
+            };
+            let subscribe = Subscribe {
+                packet_identifier: 0,
+                filters: vec![data.filter],
+            };
+            self.parse_subscribe(
+                topic_name.to_owned(),
+                topic_id.to_owned(),
+                client_id,
+                data.protocol,
+                subscribe,
+                data.subscribe_properties,
+            )
+            .await;
         }
     }
 
@@ -150,26 +187,43 @@ impl SubscribeManager {
         subscribe: Subscribe,
         subscribe_properties: Option<SubscribeProperties>,
     ) {
-        for (topic_name, topic) in self.metadata_cache.topic_info.clone() {
-            self.parse_subscribe(
-                topic_name,
-                topic.topic_id,
-                client_id.clone(),
-                protocol.clone(),
-                subscribe.clone(),
-                subscribe_properties.clone(),
-            )
-            .await;
+        for filter in subscribe.filters.iter() {
+            let subscribe = Subscribe {
+                packet_identifier: subscribe.packet_identifier,
+                filters: vec![filter.clone()],
+            };
+            for topic_name in self.metadata_cache.get_topic_names_by_filter(&filter.path) {
+                let topic_id = match self.metadata_cache.topic_info.get(&topic_name) {
+                    Some(topic) => topic.topic_id.clone(),
+                    None => continue,
+                };
+                self.parse_subscribe(
+                    topic_name,
+                    topic_id,
+                    client_id.clone(),
+                    protocol.clone(),
+                    subscribe.clone(),
+                    subscribe_properties.clone(),
+                )
+                .await;
+            }
         }
     }
 
     pub fn stop_push_by_client_id(&self, client_id: &str) {
+        self.stop_exclusive_push_by_client_id(client_id);
+        self.stop_share_push_by_client_id(client_id);
+    }
+
+    pub fn stop_exclusive_push_by_client_id(&self, client_id: &str) {
         for (key, subscriber) in self.exclusive_subscribe.clone() {
             if subscriber.client_id == *client_id {
                 self.exclusive_subscribe.remove(&key);
             }
         }
+    }
 
+    pub fn stop_share_push_by_client_id(&self, client_id: &str) {
         for (key, share_sub) in self.share_leader_subscribe.clone() {
             for (sub_key, subscriber) in share_sub.sub_list {
                 if subscriber.client_id == *client_id {
@@ -187,61 +241,63 @@ impl SubscribeManager {
     }
 
     pub fn remove_subscribe(&self, client_id: &str, filter_path: &[String]) {
-        for (topic_name, _) in self.metadata_cache.topic_info.clone() {
-            for path in filter_path {
-                if !path_regex_match(topic_name.clone(), path.clone()) {
-                    continue;
-                }
+        for path in filter_path {
+            if self
+                .metadata_cache
+                .get_topic_names_by_filter(path)
+                .is_empty()
+            {
+                continue;
+            }
 
-                if is_share_sub(path.clone()) {
-                    let (group_name, sub_name) = decode_share_info(path.clone());
-                    // share leader
-                    for (key, data) in self.share_leader_subscribe.clone() {
-                        let mut flag = false;
-                        for (sub_key, share_sub) in data.sub_list {
-                            if share_sub.client_id == *client_id
-                                && (share_sub.group_name.is_some()
-                                    && share_sub.group_name.unwrap() == group_name)
-                                && share_sub.sub_path == sub_name
-                            {
-                                let mut_data = self.share_leader_subscribe.get_mut(&key).unwrap();
-                                mut_data.sub_list.remove(&sub_key);
-                                flag = true;
-                            }
+            if is_share_sub(path.clone()) {
+                let (group_name, sub_name) = decode_share_info(path.clone());
+                // share leader
+                for (key, data) in self.share_leader_subscribe.clone() {
+                    let mut flag = false;
+                    for (sub_key, share_sub) in data.sub_list {
+                        if share_sub.client_id == *client_id
+                            && (share_sub.group_name.is_some()
+                                && share_sub.group_name.unwrap() == group_name)
+                            && share_sub.sub_path == sub_name
+                        {
+                            let mut_data = self.share_leader_subscribe.get_mut(&key).unwrap();
+                            mut_data.sub_list.remove(&sub_key);
+                            flag = true;
                         }
+                    }
 
-                        if flag {
-                            if let Some(sx) = self.share_leader_push_thread.get(&key) {
-                                match sx.send(true) {
-                                    Ok(_) => {}
-                                    Err(e) => error!("{}", e),
-                                }
+                    if flag {
+                        if let Some(sx) = self.share_leader_push_thread.get(&key) {
+                            match sx.send(true) {
+                                Ok(_) => {}
+                                Err(e) => error!("{}", e),
                             }
                         }
                     }
+                }
 
-                    // share follower
-                    for (key, data) in self.share_follower_subscribe.clone() {
-                        if data.client_id == *client_id && data.filter.path == *path {
-                            self.share_follower_subscribe.remove(&key);
-                            if let Some(sx) = self.share_follower_resub_thread.get(&key) {
-                                match sx.send(true) {
-                                    Ok(_) => {}
-                                    Err(e) => error!("{}", e),
-                                }
+                // share follower
+                for (key, data) in self.share_follower_subscribe.clone() {
+                    if data.client_id == *client_id && data.filter.path == *path {
+                        self.share_follower_subscribe.remove(&key);
+                        if let Some(sx) = self.share_follower_resub_thread.get(&key) {
+                            match sx.send(true) {
+                                Ok(_) => {}
+                                Err(e) => error!("{}", e),
                             }
                         }
                     }
-                } else {
-                    for (key, subscriber) in self.exclusive_subscribe.clone() {
-                        if subscriber.client_id == *client_id && subscriber.sub_path == *path {
-                            if let Some(sx) = self.exclusive_push_thread.get(&key) {
-                                match sx.send(true) {
-                                    Ok(_) => {}
-                                    Err(e) => error!("{}", e),
-                                }
-                                self.exclusive_subscribe.remove(&key);
+                }
+            } else {
+                for (key, subscriber) in self.exclusive_subscribe.clone() {
+                    if subscriber.client_id == *client_id && subscriber.sub_path == *path {
+                        if let Some(sx) = self.exclusive_push_thread.get(&key) {
+                            match sx.send(true) {
+                                Ok(_) => {}
+                                Err(e) => error!("{}", e),
                             }
+                            self.exclusive_subscribe.remove(&key);
                         }
                     }
                 }
@@ -340,7 +396,7 @@ impl SubscribeManager {
 
     async fn parse_share_queue_subscribe_common(&self, req: &ParseShareQueueSubscribeRequest) {
         let conf = broker_mqtt_conf();
-        if path_regex_match(req.topic_name.clone(), req.sub_name.clone()) {
+        if path_match(&req.topic_name, &req.sub_name) {
             match get_share_sub_leader(self.client_pool.clone(), req.group_name.clone()).await {
                 Ok(reply) => {
                     if reply.broker_id == conf.broker_id {
@@ -516,7 +572,7 @@ impl SubscribeManager {
         sub_identifier: Option<usize>,
         filter: Filter,
     ) {
-        if path_regex_match(topic_name.clone(), filter.path.clone()) {
+        if path_match(&topic_name, &filter.path) {
             let key = self.exclusive_key(&client_id, &filter.path, &topic_id);
             let sub = Subscriber {
                 protocol: protocol.clone(),
@@ -557,3 +613,89 @@ impl SubscribeManager {
         format!("{}_{}_{}", client_id, group_name, topic_id)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use common_base::tools::unique_id;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::topic::MqttTopic;
+    use protocol::mqtt::common::{Filter, MqttProtocol, QoS, RetainForwardRule, Subscribe};
+    use tokio::time::sleep;
+
+    use super::SubscribeManager;
+    use crate::handler::cache::CacheManager;
+
+    fn subscribe(path: &str) -> Subscribe {
+        Subscribe {
+            packet_identifier: 1,
+            filters: vec![Filter {
+                path: path.to_string(),
+                qos: QoS::AtLeastOnce,
+                nolocal: false,
+                preserve_retain: false,
+                retain_forward_rule: RetainForwardRule::OnEverySubscribe,
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn topic_create_event_test() {
+        let client_pool = Arc::new(ClientPool::new(100));
+        let cache_manager = Arc::new(CacheManager::new(
+            client_pool.clone(),
+            "test-cluster".to_string(),
+        ));
+        let subscribe_manager = Arc::new(SubscribeManager::new(cache_manager.clone(), client_pool));
+
+        cache_manager.add_client_subscribe(
+            "c1".to_string(),
+            MqttProtocol::Mqtt5,
+            subscribe("/a/+"),
+            None,
+        );
+        subscribe_manager
+            .add_subscribe(
+                "c1".to_string(),
+                MqttProtocol::Mqtt5,
+                subscribe("/a/+"),
+                None,
+            )
+            .await;
+        assert!(subscribe_manager.exclusive_subscribe.is_empty());
+
+        let manager = subscribe_manager.clone();
+        tokio::spawn(async move { manager.start().await });
+        // let the first reconcile sweep finish
+        sleep(Duration::from_millis(100)).await;
+
+        let topic = MqttTopic::new(unique_id(), "test-cluster".to_string(), "/a/b".to_string());
+        cache_manager.add_topic(&topic.topic_name, &topic);
+        cache_manager.notify_topic_created(&topic);
+        let other = MqttTopic::new(unique_id(), "test-cluster".to_string(), "/b/c".to_string());
+        cache_manager.add_topic(&other.topic_name, &other);
+        cache_manager.notify_topic_created(&other);
+
+        let mut bound = false;
+        for _ in 0..50 {
+            if !subscribe_manager.exclusive_subscribe.is_empty() {
+                bound = true;
+                break;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+        assert!(bound);
+        // bound long before the next reconcile sweep
+        let subscriber = subscribe_manager
+            .exclusive_subscribe
+            .iter()
+            .map(|raw| raw.value().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(subscriber.len(), 1);
+        assert_eq!(subscriber[0].client_id, "c1");
+        assert_eq!(subscriber[0].topic_id, topic.topic_id);
+        assert_eq!(subscriber[0].sub_path, "/a/+");
+    }
+}
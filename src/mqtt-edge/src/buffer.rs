@@ -0,0 +1,274 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::{Arc, Mutex};
+
+use common_base::error::common::CommonError;
+use log::warn;
+use rocksdb::BoundColumnFamily;
+use rocksdb_engine::RocksDBEngine;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+use crate::error::MqttEdgeError;
+
+pub const EDGE_BUFFER_COLUMN_FAMILY: &str = "edge_buffer";
+const UPLINK_KEY_PREFIX: &str = "/uplink/";
+
+// A message waiting to be published to the central cluster, the topic is already mapped
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EdgeMessage {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    pub qos: u8,
+    pub retain: bool,
+    pub create_time: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    DropOldest,
+    DropNewest,
+}
+
+impl OverflowPolicy {
+    pub fn parse(policy: &str) -> Result<Self, MqttEdgeError> {
+        match policy {
+            "drop_oldest" => Ok(OverflowPolicy::DropOldest),
+            "drop_newest" => Ok(OverflowPolicy::DropNewest),
+            _ => Err(MqttEdgeError::InvalidOverflowPolicy(policy.to_string())),
+        }
+    }
+}
+
+// Bounded, persistent FIFO of uplink messages. Messages are stored under a
+// zero padded sequence so they replay in publish order after a restart.
+pub struct UplinkBuffer {
+    rocksdb_engine: RocksDBEngine,
+    // (first sequence still stored, next sequence to assign)
+    offsets: Mutex<(u64, u64)>,
+    max_messages: u64,
+    overflow_policy: OverflowPolicy,
+    notify: Notify,
+}
+
+impl UplinkBuffer {
+    pub fn new(
+        data_path: &str,
+        max_open_files: i32,
+        max_messages: u64,
+        overflow_policy: &str,
+    ) -> Result<Self, MqttEdgeError> {
+        let overflow_policy = OverflowPolicy::parse(overflow_policy)?;
+        let rocksdb_engine = RocksDBEngine::new(
+            data_path,
+            max_open_files,
+            vec![EDGE_BUFFER_COLUMN_FAMILY.to_string()],
+        );
+        let buffer = UplinkBuffer {
+            rocksdb_engine,
+            offsets: Mutex::new((0, 0)),
+            max_messages: max_messages.max(1),
+            overflow_policy,
+            notify: Notify::new(),
+        };
+        buffer.recover()?;
+        Ok(buffer)
+    }
+
+    // Rebuild the offsets from the messages left over by the previous run
+    fn recover(&self) -> Result<(), MqttEdgeError> {
+        let cf = self.cf()?;
+        let mut head = u64::MAX;
+        let mut tail = 0;
+        for (key, _) in self.rocksdb_engine.read_prefix(cf, UPLINK_KEY_PREFIX)? {
+            if let Some(seq) = parse_seq(&key) {
+                head = head.min(seq);
+                tail = tail.max(seq + 1);
+            }
+        }
+        if tail > 0 {
+            *self.offsets.lock().unwrap() = (head, tail);
+        }
+        Ok(())
+    }
+
+    pub fn push(&self, message: EdgeMessage) -> Result<u64, MqttEdgeError> {
+        let cf = self.cf()?;
+        let mut offsets = self.offsets.lock().unwrap();
+        if offsets.1 - offsets.0 >= self.max_messages {
+            match self.overflow_policy {
+                OverflowPolicy::DropOldest => {
+                    self.rocksdb_engine
+                        .delete(cf.clone(), &seq_key(offsets.0))?;
+                    offsets.0 += 1;
+                    warn!(
+                        "Edge buffer reached {} messages, the oldest message was dropped",
+                        self.max_messages
+                    );
+                }
+                OverflowPolicy::DropNewest => {
+                    return Err(MqttEdgeError::BufferFull(message.topic));
+                }
+            }
+        }
+        let seq = offsets.1;
+        self.rocksdb_engine.write(cf, &seq_key(seq), &message)?;
+        offsets.1 += 1;
+        drop(offsets);
+        self.notify.notify_one();
+        Ok(seq)
+    }
+
+    // Up to `limit` of the oldest messages, in publish order
+    pub fn read_batch(&self, limit: usize) -> Result<Vec<(u64, EdgeMessage)>, MqttEdgeError> {
+        let cf = self.cf()?;
+        let (head, tail) = *self.offsets.lock().unwrap();
+        let end = tail.min(head.saturating_add(limit as u64));
+        let mut results = Vec::new();
+        for seq in head..end {
+            // the message may have been dropped by the overflow policy meanwhile
+            if let Some(message) = self
+                .rocksdb_engine
+                .read::<EdgeMessage>(cf.clone(), &seq_key(seq))?
+            {
+                results.push((seq, message));
+            }
+        }
+        Ok(results)
+    }
+
+    // Remove every message up to and including `seq` once the cluster has acknowledged it
+    pub fn remove_until(&self, seq: u64) -> Result<(), MqttEdgeError> {
+        let cf = self.cf()?;
+        let mut offsets = self.offsets.lock().unwrap();
+        let end = offsets.1.min(seq.saturating_add(1));
+        while offsets.0 < end {
+            self.rocksdb_engine
+                .delete(cf.clone(), &seq_key(offsets.0))?;
+            offsets.0 += 1;
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> u64 {
+        let (head, tail) = *self.offsets.lock().unwrap();
+        tail - head
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Resolves once a message is pushed after the last wakeup
+    pub async fn wait_for_message(&self) {
+        self.notify.notified().await
+    }
+
+    fn cf(&self) -> Result<Arc<BoundColumnFamily<'_>>, MqttEdgeError> {
+        match self.rocksdb_engine.cf_handle(EDGE_BUFFER_COLUMN_FAMILY) {
+            Some(cf) => Ok(cf),
+            None => Err(CommonError::RocksDBFamilyNotAvailable(
+                EDGE_BUFFER_COLUMN_FAMILY.to_string(),
+            )
+            .into()),
+        }
+    }
+}
+
+fn seq_key(seq: u64) -> String {
+    format!("{}{:020}", UPLINK_KEY_PREFIX, seq)
+}
+
+fn parse_seq(key: &str) -> Option<u64> {
+    key.strip_prefix(UPLINK_KEY_PREFIX)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_dir_all;
+
+    use common_base::tools::unique_id;
+
+    use super::{EdgeMessage, UplinkBuffer};
+
+    fn build_message(topic: &str) -> EdgeMessage {
+        EdgeMessage {
+            topic: topic.to_string(),
+            payload: topic.as_bytes().to_vec(),
+            qos: 1,
+            retain: false,
+            create_time: 0,
+        }
+    }
+
+    fn data_path() -> String {
+        format!("/tmp/robustmq-test/mqtt-edge/{}", unique_id())
+    }
+
+    #[test]
+    fn replay_order_test() {
+        let path = data_path();
+        {
+            let buffer = UplinkBuffer::new(&path, 100, 100, "drop_oldest").unwrap();
+            for i in 0..5 {
+                buffer.push(build_message(&format!("t/{}", i))).unwrap();
+            }
+            buffer.remove_until(1).unwrap();
+            assert_eq!(buffer.len(), 3);
+        }
+
+        // messages survive a restart and keep their order
+        let buffer = UplinkBuffer::new(&path, 100, 100, "drop_oldest").unwrap();
+        assert_eq!(buffer.len(), 3);
+        let batch = buffer.read_batch(2).unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch[0].0, 2);
+        assert_eq!(batch[0].1.topic, "t/2");
+        assert_eq!(batch[1].1.topic, "t/3");
+
+        let seq = buffer.push(build_message("t/5")).unwrap();
+        assert_eq!(seq, 5);
+        buffer.remove_until(seq).unwrap();
+        assert!(buffer.is_empty());
+        drop(buffer);
+        remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn overflow_policy_test() {
+        let path = data_path();
+        {
+            let buffer = UplinkBuffer::new(&path, 100, 2, "drop_oldest").unwrap();
+            for i in 0..3 {
+                buffer.push(build_message(&format!("t/{}", i))).unwrap();
+            }
+            let batch = buffer.read_batch(10).unwrap();
+            let topics: Vec<String> = batch.into_iter().map(|(_, m)| m.topic).collect();
+            assert_eq!(topics, vec!["t/1".to_string(), "t/2".to_string()]);
+        }
+        remove_dir_all(&path).unwrap();
+
+        {
+            let buffer = UplinkBuffer::new(&path, 100, 2, "drop_newest").unwrap();
+            buffer.push(build_message("t/0")).unwrap();
+            buffer.push(build_message("t/1")).unwrap();
+            assert!(buffer.push(build_message("t/2")).is_err());
+            assert_eq!(buffer.len(), 2);
+        }
+        remove_dir_all(&path).unwrap();
+
+        assert!(UplinkBuffer::new(&path, 100, 2, "drop_all").is_err());
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::BytesMut;
+use protocol::mqtt::codec::{MqttCodec, MqttPacketWrapper};
+use protocol::mqtt::common::{Error, MqttPacket};
+use tokio_util::codec;
+
+// MqttCodec reports a partially received packet as an error, which would end
+// a framed stream. The edge waits for the rest of the packet instead.
+pub struct EdgeCodec {
+    inner: MqttCodec,
+}
+
+impl EdgeCodec {
+    pub fn new(protocol_version: Option<u8>) -> Self {
+        EdgeCodec {
+            inner: MqttCodec::new(protocol_version),
+        }
+    }
+}
+
+impl codec::Decoder for EdgeCodec {
+    type Item = MqttPacket;
+    type Error = Error;
+    fn decode(&mut self, stream: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.inner.decode_data(stream) {
+            Err(Error::InsufficientBytes(_)) => Ok(None),
+            result => result,
+        }
+    }
+}
+
+impl codec::Encoder<MqttPacketWrapper> for EdgeCodec {
+    type Error = Error;
+    fn encode(
+        &mut self,
+        packet_wrapper: MqttPacketWrapper,
+        buffer: &mut BytesMut,
+    ) -> Result<(), Self::Error> {
+        self.inner.encode_data(packet_wrapper, buffer)
+    }
+}
@@ -0,0 +1,49 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::error::common::CommonError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum MqttEdgeError {
+    #[error("{0}")]
+    FromIoError(#[from] std::io::Error),
+
+    #[error("{0}")]
+    FromCommonError(#[from] CommonError),
+
+    #[error("{0}")]
+    FromCodecError(#[from] protocol::mqtt::common::Error),
+
+    #[error("Invalid buffer overflow policy {0}, expected drop_oldest or drop_newest")]
+    InvalidOverflowPolicy(String),
+
+    #[error("Local buffer is full, message for topic {0} was dropped")]
+    BufferFull(String),
+
+    #[error("Uplink connection was refused by {0}, reason {1}")]
+    UplinkConnectRefused(String, String),
+
+    #[error("Uplink subscription to {0} was rejected")]
+    UplinkSubscribeRejected(String),
+
+    #[error("{0} did not send any packet within the keep alive interval")]
+    KeepAliveTimeout(String),
+
+    #[error("Expected {0} as the first packet")]
+    UnexpectedPacket(String),
+
+    #[error("Connection was closed by the peer")]
+    ConnectionClosed,
+}
@@ -11,3 +11,125 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use buffer::UplinkBuffer;
+use common_base::config::mqtt_edge::mqtt_edge_conf;
+use common_base::runtime::create_runtime;
+use log::{error, info};
+use mapping::TopicMapper;
+use router::LocalRouter;
+use server::tcp::start_tcp_server;
+use tokio::runtime::Runtime;
+use tokio::signal;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+use uplink::UplinkClient;
+
+pub mod buffer;
+mod codec;
+pub mod error;
+pub mod mapping;
+pub mod router;
+mod server;
+pub mod uplink;
+
+pub fn start_mqtt_edge(stop_send: broadcast::Sender<bool>) {
+    let server = MqttEdge::new();
+    server.start(stop_send);
+}
+
+// Accepts local MQTT clients and forwards the mapped topics to the central
+// cluster through a local store, so messages survive a broken uplink.
+pub struct MqttEdge {
+    runtime: Runtime,
+    router: Arc<LocalRouter>,
+    mapper: Arc<TopicMapper>,
+    buffer: Arc<UplinkBuffer>,
+}
+
+impl Default for MqttEdge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MqttEdge {
+    pub fn new() -> Self {
+        let conf = mqtt_edge_conf();
+        let runtime = create_runtime("mqtt-edge-runtime", conf.runtime_worker_threads);
+        let buffer = match UplinkBuffer::new(
+            &conf.buffer.data_path,
+            conf.buffer.rocksdb_max_open_files,
+            conf.buffer.max_messages,
+            &conf.buffer.overflow_policy,
+        ) {
+            Ok(buffer) => Arc::new(buffer),
+            Err(e) => {
+                panic!("Failed to open the MQTT Edge buffer, {}", e);
+            }
+        };
+        MqttEdge {
+            runtime,
+            router: Arc::new(LocalRouter::new()),
+            mapper: Arc::new(TopicMapper::new(conf.mappings.clone())),
+            buffer,
+        }
+    }
+
+    pub fn start(&self, stop_send: broadcast::Sender<bool>) {
+        self.start_tcp_server(stop_send.clone());
+        self.start_uplink(stop_send.clone());
+        self.awaiting_stop(stop_send);
+    }
+
+    fn start_tcp_server(&self, stop_send: broadcast::Sender<bool>) {
+        let router = self.router.clone();
+        let mapper = self.mapper.clone();
+        let buffer = self.buffer.clone();
+        self.runtime
+            .spawn(async move { start_tcp_server(router, mapper, buffer, stop_send).await });
+    }
+
+    fn start_uplink(&self, stop_send: broadcast::Sender<bool>) {
+        let conf = mqtt_edge_conf();
+        let uplink = UplinkClient::new(
+            &conf.edge_id,
+            conf.uplink.clone(),
+            self.router.clone(),
+            self.mapper.clone(),
+            self.buffer.clone(),
+        );
+        self.runtime
+            .spawn(async move { uplink.start(stop_send).await });
+    }
+
+    fn awaiting_stop(&self, stop_send: broadcast::Sender<bool>) {
+        let buffered = self.buffer.len();
+        self.runtime.spawn(async move {
+            sleep(Duration::from_millis(5)).await;
+            info!(
+                "MQTT Edge service started successfully, {} messages buffered...",
+                buffered
+            );
+        });
+
+        // Wait for the stop signal
+        self.runtime.block_on(async move {
+            signal::ctrl_c().await.expect("failed to listen for event");
+            match stop_send.send(true) {
+                Ok(_) => {
+                    info!(
+                        "{}",
+                        "When ctrl + c is received, the service starts to stop"
+                    );
+                }
+                Err(_) => {
+                    error!("Failed to send stop signal");
+                }
+            }
+        });
+    }
+}
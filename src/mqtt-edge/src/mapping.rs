@@ -0,0 +1,151 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::config::mqtt_edge::{MappingDirection, TopicMapping};
+use common_base::utils::topic_trie::topic_match;
+
+// Translates topics between the edge and the central cluster
+pub struct TopicMapper {
+    mappings: Vec<TopicMapping>,
+}
+
+impl TopicMapper {
+    pub fn new(mappings: Vec<TopicMapping>) -> Self {
+        TopicMapper { mappings }
+    }
+
+    // Remote topic and QoS of a message published on the edge, None if it stays local.
+    // The first matching mapping wins.
+    pub fn to_remote(&self, topic: &str) -> Option<(String, u8)> {
+        self.mappings
+            .iter()
+            .filter(|mapping| mapping.direction != MappingDirection::Downlink)
+            .find_map(|mapping| {
+                let rest = topic.strip_prefix(&mapping.local_prefix)?;
+                if !topic_match(rest, &mapping.filter) {
+                    return None;
+                }
+                Some((
+                    format!("{}{}", mapping.remote_prefix, rest),
+                    mapping.qos.min(1),
+                ))
+            })
+    }
+
+    // Local topic of a message received from the central cluster
+    pub fn to_local(&self, topic: &str) -> Option<String> {
+        self.mappings
+            .iter()
+            .filter(|mapping| mapping.direction != MappingDirection::Uplink)
+            .find_map(|mapping| {
+                let rest = topic.strip_prefix(&mapping.remote_prefix)?;
+                if !topic_match(rest, &mapping.filter) {
+                    return None;
+                }
+                Some(format!("{}{}", mapping.local_prefix, rest))
+            })
+    }
+
+    // Filters the edge subscribes to upstream
+    pub fn remote_filters(&self) -> Vec<(String, u8)> {
+        self.mappings
+            .iter()
+            .filter(|mapping| mapping.direction != MappingDirection::Uplink)
+            .map(|mapping| {
+                (
+                    format!("{}{}", mapping.remote_prefix, mapping.filter),
+                    mapping.qos.min(1),
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common_base::config::mqtt_edge::{MappingDirection, TopicMapping};
+
+    use super::TopicMapper;
+
+    fn build_mapper() -> TopicMapper {
+        TopicMapper::new(vec![
+            TopicMapping {
+                direction: MappingDirection::Uplink,
+                local_prefix: "sensors/".to_string(),
+                remote_prefix: "factory-1/sensors/".to_string(),
+                filter: "+/temperature".to_string(),
+                qos: 1,
+            },
+            TopicMapping {
+                direction: MappingDirection::Downlink,
+                local_prefix: "commands/".to_string(),
+                remote_prefix: "factory-1/commands/".to_string(),
+                filter: "#".to_string(),
+                qos: 2,
+            },
+            TopicMapping {
+                direction: MappingDirection::Both,
+                local_prefix: "".to_string(),
+                remote_prefix: "factory-1/".to_string(),
+                filter: "shared/#".to_string(),
+                qos: 0,
+            },
+        ])
+    }
+
+    #[test]
+    fn to_remote_test() {
+        let mapper = build_mapper();
+        assert_eq!(
+            mapper.to_remote("sensors/line-1/temperature"),
+            Some(("factory-1/sensors/line-1/temperature".to_string(), 1))
+        );
+        assert_eq!(mapper.to_remote("sensors/line-1/pressure"), None);
+        assert_eq!(mapper.to_remote("commands/line-1/stop"), None);
+        assert_eq!(
+            mapper.to_remote("shared/state"),
+            Some(("factory-1/shared/state".to_string(), 0))
+        );
+    }
+
+    #[test]
+    fn to_local_test() {
+        let mapper = build_mapper();
+        assert_eq!(
+            mapper.to_local("factory-1/commands/line-1/stop"),
+            Some("commands/line-1/stop".to_string())
+        );
+        assert_eq!(
+            mapper.to_local("factory-1/sensors/line-1/temperature"),
+            None
+        );
+        assert_eq!(mapper.to_local("factory-2/commands/line-1/stop"), None);
+        assert_eq!(
+            mapper.to_local("factory-1/shared/state"),
+            Some("shared/state".to_string())
+        );
+    }
+
+    #[test]
+    fn remote_filters_test() {
+        let mapper = build_mapper();
+        assert_eq!(
+            mapper.remote_filters(),
+            vec![
+                ("factory-1/commands/#".to_string(), 1),
+                ("factory-1/shared/#".to_string(), 0)
+            ]
+        );
+    }
+}
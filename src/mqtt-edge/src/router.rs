@@ -0,0 +1,270 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::{Arc, RwLock};
+
+use bytes::Bytes;
+use common_base::utils::topic_trie::{topic_match, TopicTrie};
+use dashmap::DashMap;
+use log::debug;
+use protocol::mqtt::codec::MqttPacketWrapper;
+use protocol::mqtt::common::{qos, MqttPacket, Publish};
+use tokio::sync::mpsc;
+
+// A client connected to the edge listener
+pub struct LocalClient {
+    pub client_id: String,
+    pub protocol_version: u8,
+    // Distinguishes a reconnect with the same client id from the connection it replaced
+    pub session_id: u64,
+    sender: mpsc::Sender<MqttPacketWrapper>,
+    pkid: AtomicU16,
+    // filter -> granted QoS
+    subscriptions: DashMap<String, u8>,
+}
+
+impl LocalClient {
+    fn next_pkid(&self) -> u16 {
+        loop {
+            let pkid = self.pkid.fetch_add(1, Ordering::Relaxed);
+            if pkid != 0 {
+                return pkid;
+            }
+        }
+    }
+
+    fn deliver(&self, topic: &str, payload: &Bytes, qos_level: u8, retain: bool) {
+        let qos_level = qos_level.min(1);
+        let publish = Publish {
+            dup: false,
+            qos: qos(qos_level).unwrap(),
+            pkid: if qos_level > 0 { self.next_pkid() } else { 0 },
+            retain,
+            topic: Bytes::from(topic.to_string()),
+            payload: payload.clone(),
+        };
+        let wrapper = MqttPacketWrapper {
+            protocol_version: self.protocol_version,
+            packet: MqttPacket::Publish(publish, None),
+        };
+        // a slow local client loses messages instead of stalling the publisher
+        if let Err(e) = self.sender.try_send(wrapper) {
+            debug!(
+                "Edge client {} is not keeping up, message on {} dropped, {}",
+                self.client_id, topic, e
+            );
+        }
+    }
+}
+
+// In-process fan-out between the clients of the edge. Deliveries are capped at
+// QoS 1 and are not retried, the edge only guarantees delivery towards the cluster.
+#[derive(Default)]
+pub struct LocalRouter {
+    clients: DashMap<String, Arc<LocalClient>>,
+    subscriptions: RwLock<TopicTrie<String>>,
+    retained: DashMap<String, (Bytes, u8)>,
+}
+
+impl LocalRouter {
+    pub fn new() -> Self {
+        LocalRouter::default()
+    }
+
+    // Register a connection, a previous connection with the same client id loses its subscriptions
+    pub fn register(
+        &self,
+        client_id: &str,
+        protocol_version: u8,
+        session_id: u64,
+        sender: mpsc::Sender<MqttPacketWrapper>,
+    ) -> Arc<LocalClient> {
+        let client = Arc::new(LocalClient {
+            client_id: client_id.to_string(),
+            protocol_version,
+            session_id,
+            sender,
+            pkid: AtomicU16::new(1),
+            subscriptions: DashMap::new(),
+        });
+        if let Some(previous) = self.clients.insert(client_id.to_string(), client.clone()) {
+            self.clear_subscriptions(&previous);
+        }
+        client
+    }
+
+    pub fn unregister(&self, client_id: &str, session_id: u64) {
+        if let Some((_, client)) = self
+            .clients
+            .remove_if(client_id, |_, client| client.session_id == session_id)
+        {
+            self.clear_subscriptions(&client);
+        }
+    }
+
+    // Returns false if the session was taken over by another connection
+    pub fn subscribe(&self, client_id: &str, session_id: u64, filter: &str, qos_level: u8) -> bool {
+        let client = match self.current_client(client_id, session_id) {
+            Some(client) => client,
+            None => return false,
+        };
+        client.subscriptions.insert(filter.to_string(), qos_level);
+        self.subscriptions
+            .write()
+            .unwrap()
+            .insert(filter, client_id.to_string());
+
+        for entry in self.retained.iter() {
+            if topic_match(entry.key(), filter) {
+                let (payload, retained_qos) = entry.value();
+                client.deliver(entry.key(), payload, qos_level.min(*retained_qos), true);
+            }
+        }
+        true
+    }
+
+    // Whether the filter was subscribed, None if the session was taken over
+    pub fn unsubscribe(&self, client_id: &str, session_id: u64, filter: &str) -> Option<bool> {
+        let client = self.current_client(client_id, session_id)?;
+        if client.subscriptions.remove(filter).is_none() {
+            return Some(false);
+        }
+        self.subscriptions
+            .write()
+            .unwrap()
+            .remove(filter, &client_id.to_string());
+        Some(true)
+    }
+
+    // Deliver a message to the local subscribers, returns how many clients received it
+    pub fn publish_local(&self, topic: &str, payload: Bytes, qos_level: u8, retain: bool) -> usize {
+        if retain {
+            if payload.is_empty() {
+                self.retained.remove(topic);
+            } else {
+                self.retained
+                    .insert(topic.to_string(), (payload.clone(), qos_level));
+            }
+        }
+
+        let client_ids = self.subscriptions.read().unwrap().match_topic(topic);
+        let mut delivered = 0;
+        for client_id in client_ids {
+            let client = match self.clients.get(&client_id) {
+                Some(client) => client.clone(),
+                None => continue,
+            };
+            // overlapping filters of one client result in a single delivery at the highest QoS
+            let granted = client
+                .subscriptions
+                .iter()
+                .filter(|entry| topic_match(topic, entry.key()))
+                .map(|entry| *entry.value())
+                .max();
+            if let Some(granted) = granted {
+                client.deliver(topic, &payload, qos_level.min(granted), false);
+                delivered += 1;
+            }
+        }
+        delivered
+    }
+
+    fn current_client(&self, client_id: &str, session_id: u64) -> Option<Arc<LocalClient>> {
+        self.clients
+            .get(client_id)
+            .filter(|client| client.session_id == session_id)
+            .map(|client| client.clone())
+    }
+
+    fn clear_subscriptions(&self, client: &LocalClient) {
+        let mut subscriptions = self.subscriptions.write().unwrap();
+        for entry in client.subscriptions.iter() {
+            subscriptions.remove(entry.key(), &client.client_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use protocol::mqtt::codec::MqttPacketWrapper;
+    use protocol::mqtt::common::{MqttPacket, QoS};
+    use tokio::sync::mpsc;
+
+    use super::LocalRouter;
+
+    fn publish_of(wrapper: MqttPacketWrapper) -> (String, QoS, bool) {
+        match wrapper.packet {
+            MqttPacket::Publish(publish, _) => (
+                String::from_utf8(publish.topic.to_vec()).unwrap(),
+                publish.qos,
+                publish.retain,
+            ),
+            packet => panic!("unexpected packet {:?}", packet),
+        }
+    }
+
+    #[tokio::test]
+    async fn publish_local_test() {
+        let router = LocalRouter::new();
+        let (sender, mut receiver) = mpsc::channel(10);
+        router.register("c1", 4, 1, sender);
+        assert!(router.subscribe("c1", 1, "sensors/#", 0));
+        assert!(router.subscribe("c1", 1, "sensors/+/temperature", 1));
+
+        let delivered = router.publish_local("sensors/l1/temperature", Bytes::from("20"), 2, false);
+        assert_eq!(delivered, 1);
+        assert_eq!(
+            publish_of(receiver.recv().await.unwrap()),
+            (
+                "sensors/l1/temperature".to_string(),
+                QoS::AtLeastOnce,
+                false
+            )
+        );
+
+        assert_eq!(router.publish_local("other", Bytes::from("1"), 0, false), 0);
+        assert_eq!(router.unsubscribe("c1", 1, "sensors/#"), Some(true));
+        assert_eq!(router.unsubscribe("c1", 1, "sensors/#"), Some(false));
+        assert_eq!(
+            router.publish_local("sensors/l1/pressure", Bytes::from("1"), 0, false),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn retained_and_reconnect_test() {
+        let router = LocalRouter::new();
+        router.publish_local("state/l1", Bytes::from("on"), 1, true);
+
+        let (sender, mut receiver) = mpsc::channel(10);
+        router.register("c1", 5, 1, sender);
+        router.subscribe("c1", 1, "state/+", 0);
+        assert_eq!(
+            publish_of(receiver.recv().await.unwrap()),
+            ("state/l1".to_string(), QoS::AtMostOnce, true)
+        );
+
+        // the replacing connection starts without subscriptions and the old one can no longer touch it
+        let (sender, _receiver) = mpsc::channel(10);
+        router.register("c1", 5, 2, sender);
+        assert!(!router.subscribe("c1", 1, "state/+", 0));
+        router.unregister("c1", 1);
+        assert_eq!(router.publish_local("state/l1", Bytes::new(), 0, true), 0);
+        assert!(router.subscribe("c1", 2, "state/+", 0));
+        router.unregister("c1", 2);
+        assert!(!router.subscribe("c1", 2, "state/+", 0));
+    }
+}
@@ -0,0 +1,372 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_base::tools::{now_second, unique_id};
+use futures::{SinkExt, StreamExt};
+use log::{debug, info, warn};
+use protocol::mqtt::codec::MqttPacketWrapper;
+use protocol::mqtt::common::{
+    is_mqtt5, qos, ConnAck, ConnAckProperties, Connect, ConnectReturnCode, MqttPacket, PingResp,
+    PubAck, PubAckReason, PubComp, PubCompReason, PubRec, PubRecReason, Publish, QoS, SubAck,
+    Subscribe, SubscribeReasonCode, UnsubAck, UnsubAckReason, Unsubscribe,
+};
+use tokio::net::tcp::OwnedReadHalf;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tokio_util::codec::{FramedRead, FramedWrite};
+
+use crate::buffer::{EdgeMessage, UplinkBuffer};
+use crate::codec::EdgeCodec;
+use crate::error::MqttEdgeError;
+use crate::mapping::TopicMapper;
+use crate::router::LocalRouter;
+
+static SESSION_ID: AtomicU64 = AtomicU64::new(1);
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub async fn handle_connection(
+    stream: TcpStream,
+    addr: SocketAddr,
+    router: Arc<LocalRouter>,
+    mapper: Arc<TopicMapper>,
+    buffer: Arc<UplinkBuffer>,
+) {
+    let (r_stream, w_stream) = stream.into_split();
+    let mut reader = FramedRead::new(r_stream, EdgeCodec::new(None));
+    let mut writer = FramedWrite::new(w_stream, EdgeCodec::new(None));
+
+    let (protocol_version, connect) = match read_connect(&mut reader).await {
+        Ok(connect) => connect,
+        Err(e) => {
+            info!("MQTT Edge connection from {} was rejected, {}", addr, e);
+            return;
+        }
+    };
+
+    let assigned_client_id = connect.client_id.is_empty();
+    let client_id = if assigned_client_id {
+        unique_id()
+    } else {
+        connect.client_id.clone()
+    };
+    let properties = if is_mqtt5(protocol_version) && assigned_client_id {
+        Some(ConnAckProperties {
+            assigned_client_identifier: Some(client_id.clone()),
+            ..Default::default()
+        })
+    } else {
+        None
+    };
+    let connack = MqttPacketWrapper {
+        protocol_version,
+        packet: MqttPacket::ConnAck(
+            ConnAck {
+                session_present: false,
+                code: ConnectReturnCode::Success,
+            },
+            properties,
+        ),
+    };
+    if let Err(e) = writer.send(connack).await {
+        debug!("MQTT Edge connection {} write failed, {}", client_id, e);
+        return;
+    }
+
+    let (sender, mut receiver) = mpsc::channel::<MqttPacketWrapper>(1000);
+    let writer_client_id = client_id.clone();
+    let writer_task = tokio::spawn(async move {
+        while let Some(packet) = receiver.recv().await {
+            if let Err(e) = writer.send(packet).await {
+                debug!(
+                    "MQTT Edge connection {} write failed, {}",
+                    writer_client_id, e
+                );
+                return;
+            }
+        }
+    });
+
+    let session_id = SESSION_ID.fetch_add(1, Ordering::Relaxed);
+    router.register(&client_id, protocol_version, session_id, sender.clone());
+    info!(
+        "MQTT Edge client {} connected from {}, protocol version {}",
+        client_id, addr, protocol_version
+    );
+
+    let mut connection = EdgeConnection {
+        client_id: client_id.clone(),
+        session_id,
+        protocol_version,
+        sender,
+        router: router.clone(),
+        mapper,
+        buffer,
+        pending_qos2: HashSet::new(),
+    };
+
+    // the client is considered gone after one and a half keep alive intervals of silence
+    let keep_alive = Duration::from_millis(connect.keep_alive as u64 * 1500);
+    loop {
+        let next = if connect.keep_alive > 0 {
+            match timeout(keep_alive, reader.next()).await {
+                Ok(next) => next,
+                Err(_) => {
+                    info!("{}", MqttEdgeError::KeepAliveTimeout(client_id.clone()));
+                    break;
+                }
+            }
+        } else {
+            reader.next().await
+        };
+
+        match next {
+            Some(Ok(packet)) => {
+                if !connection.handle_packet(packet).await {
+                    break;
+                }
+            }
+            Some(Err(e)) => {
+                debug!("MQTT Edge connection {} parse failed, {}", client_id, e);
+                break;
+            }
+            None => break,
+        }
+    }
+
+    router.unregister(&client_id, session_id);
+    drop(connection);
+    let _ = writer_task.await;
+    info!("MQTT Edge client {} disconnected", client_id);
+}
+
+async fn read_connect(
+    reader: &mut FramedRead<OwnedReadHalf, EdgeCodec>,
+) -> Result<(u8, Connect), MqttEdgeError> {
+    match timeout(CONNECT_TIMEOUT, reader.next()).await {
+        Ok(Some(Ok(MqttPacket::Connect(protocol_version, connect, ..)))) => {
+            Ok((protocol_version, connect))
+        }
+        Ok(Some(Ok(_))) => Err(MqttEdgeError::UnexpectedPacket("CONNECT".to_string())),
+        Ok(Some(Err(e))) => Err(e.into()),
+        Ok(None) => Err(MqttEdgeError::ConnectionClosed),
+        Err(_) => Err(MqttEdgeError::KeepAliveTimeout("Client".to_string())),
+    }
+}
+
+struct EdgeConnection {
+    client_id: String,
+    session_id: u64,
+    protocol_version: u8,
+    sender: mpsc::Sender<MqttPacketWrapper>,
+    router: Arc<LocalRouter>,
+    mapper: Arc<TopicMapper>,
+    buffer: Arc<UplinkBuffer>,
+    // QoS 2 packet ids received but not yet released
+    pending_qos2: HashSet<u16>,
+}
+
+impl EdgeConnection {
+    // Returns false when the connection should be closed
+    async fn handle_packet(&mut self, packet: MqttPacket) -> bool {
+        match packet {
+            MqttPacket::Publish(publish, _) => self.publish(publish).await,
+            MqttPacket::PubRel(pubrel, _) => {
+                let reason = if self.pending_qos2.remove(&pubrel.pkid) {
+                    PubCompReason::Success
+                } else {
+                    PubCompReason::PacketIdentifierNotFound
+                };
+                let reason = self.is_mqtt5().then_some(reason);
+                self.send(MqttPacket::PubComp(
+                    PubComp {
+                        pkid: pubrel.pkid,
+                        reason,
+                    },
+                    None,
+                ))
+                .await
+            }
+            MqttPacket::Subscribe(subscribe, _) => self.subscribe(subscribe).await,
+            MqttPacket::Unsubscribe(unsubscribe, _) => self.unsubscribe(unsubscribe).await,
+            MqttPacket::PingReq(_) => self.send(MqttPacket::PingResp(PingResp)).await,
+            // acks of the local deliveries, which are not retried
+            MqttPacket::PubAck(..) | MqttPacket::PubComp(..) => true,
+            MqttPacket::Disconnect(..) => false,
+            packet => {
+                debug!(
+                    "MQTT Edge client {} sent an unsupported packet {:?}",
+                    self.client_id, packet
+                );
+                false
+            }
+        }
+    }
+
+    async fn publish(&mut self, publish: Publish) -> bool {
+        let topic = match String::from_utf8(publish.topic.to_vec()) {
+            Ok(topic) => topic,
+            Err(e) => {
+                debug!(
+                    "MQTT Edge client {} sent an invalid topic, {}",
+                    self.client_id, e
+                );
+                return false;
+            }
+        };
+
+        // a retransmitted QoS 2 message that was already accepted
+        if publish.qos == QoS::ExactlyOnce && self.pending_qos2.contains(&publish.pkid) {
+            return self.send_pubrec(publish.pkid, PubRecReason::Success).await;
+        }
+
+        self.router.publish_local(
+            &topic,
+            publish.payload.clone(),
+            publish.qos.into(),
+            publish.retain,
+        );
+
+        let stored = match self.forward(&topic, &publish) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("{}", e);
+                false
+            }
+        };
+
+        match publish.qos {
+            QoS::AtMostOnce => true,
+            QoS::AtLeastOnce => {
+                // a v4 client gets no ack and retries the message later
+                let reason = if stored {
+                    PubAckReason::Success
+                } else if self.is_mqtt5() {
+                    PubAckReason::QuotaExceeded
+                } else {
+                    return true;
+                };
+                let reason = self.is_mqtt5().then_some(reason);
+                self.send(MqttPacket::PubAck(
+                    PubAck {
+                        pkid: publish.pkid,
+                        reason,
+                    },
+                    None,
+                ))
+                .await
+            }
+            QoS::ExactlyOnce => {
+                if stored {
+                    self.pending_qos2.insert(publish.pkid);
+                    self.send_pubrec(publish.pkid, PubRecReason::Success).await
+                } else if self.is_mqtt5() {
+                    self.send_pubrec(publish.pkid, PubRecReason::QuotaExceeded)
+                        .await
+                } else {
+                    true
+                }
+            }
+        }
+    }
+
+    // Store the message for the uplink if a mapping covers its topic
+    fn forward(&self, topic: &str, publish: &Publish) -> Result<(), MqttEdgeError> {
+        let (remote_topic, remote_qos) = match self.mapper.to_remote(topic) {
+            Some(mapped) => mapped,
+            None => return Ok(()),
+        };
+        self.buffer.push(EdgeMessage {
+            topic: remote_topic,
+            payload: publish.payload.to_vec(),
+            qos: remote_qos,
+            retain: publish.retain,
+            create_time: now_second(),
+        })?;
+        Ok(())
+    }
+
+    async fn subscribe(&self, subscribe: Subscribe) -> bool {
+        let mut return_codes = Vec::new();
+        for filter in subscribe.filters {
+            if filter.path.is_empty() {
+                return_codes.push(SubscribeReasonCode::Failure);
+                continue;
+            }
+            let granted = u8::from(filter.qos).min(1);
+            if !self
+                .router
+                .subscribe(&self.client_id, self.session_id, &filter.path, granted)
+            {
+                return false;
+            }
+            return_codes.push(SubscribeReasonCode::Success(qos(granted).unwrap()));
+        }
+        self.send(MqttPacket::SubAck(
+            SubAck {
+                pkid: subscribe.packet_identifier,
+                return_codes,
+            },
+            None,
+        ))
+        .await
+    }
+
+    async fn unsubscribe(&self, unsubscribe: Unsubscribe) -> bool {
+        let mut reasons = Vec::new();
+        for filter in unsubscribe.filters.iter() {
+            match self
+                .router
+                .unsubscribe(&self.client_id, self.session_id, filter)
+            {
+                Some(true) => reasons.push(UnsubAckReason::Success),
+                Some(false) => reasons.push(UnsubAckReason::NoSubscriptionExisted),
+                None => return false,
+            }
+        }
+        self.send(MqttPacket::UnsubAck(
+            UnsubAck {
+                pkid: unsubscribe.pkid,
+                reasons,
+            },
+            None,
+        ))
+        .await
+    }
+
+    async fn send_pubrec(&self, pkid: u16, reason: PubRecReason) -> bool {
+        let reason = self.is_mqtt5().then_some(reason);
+        self.send(MqttPacket::PubRec(PubRec { pkid, reason }, None))
+            .await
+    }
+
+    async fn send(&self, packet: MqttPacket) -> bool {
+        let wrapper = MqttPacketWrapper {
+            protocol_version: self.protocol_version,
+            packet,
+        };
+        self.sender.send(wrapper).await.is_ok()
+    }
+
+    fn is_mqtt5(&self) -> bool {
+        is_mqtt5(self.protocol_version)
+    }
+}
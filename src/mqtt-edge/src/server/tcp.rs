@@ -0,0 +1,69 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::config::mqtt_edge::mqtt_edge_conf;
+use log::{error, info};
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+
+use super::connection::handle_connection;
+use crate::buffer::UplinkBuffer;
+use crate::mapping::TopicMapper;
+use crate::router::LocalRouter;
+
+pub async fn start_tcp_server(
+    router: Arc<LocalRouter>,
+    mapper: Arc<TopicMapper>,
+    buffer: Arc<UplinkBuffer>,
+    stop_send: broadcast::Sender<bool>,
+) {
+    let conf = mqtt_edge_conf();
+    let addr = format!("0.0.0.0:{}", conf.network.tcp_port);
+    let listener = match TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            panic!("MQTT Edge failed to bind {}, {}", addr, e);
+        }
+    };
+    info!("MQTT Edge TCP Server bind addr:{}", addr);
+
+    let mut stop_rx = stop_send.subscribe();
+    loop {
+        tokio::select! {
+            val = stop_rx.recv() => {
+                if let Ok(true) = val {
+                    info!("MQTT Edge TCP Server acceptor stopped");
+                    break;
+                }
+            }
+            val = listener.accept() => {
+                match val {
+                    Ok((stream, addr)) => {
+                        let router = router.clone();
+                        let mapper = mapper.clone();
+                        let buffer = buffer.clone();
+                        tokio::spawn(async move {
+                            handle_connection(stream, addr, router, mapper, buffer).await;
+                        });
+                    }
+                    Err(e) => {
+                        error!("MQTT Edge accept failed, {}", e);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,427 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use common_base::config::mqtt_edge::Uplink;
+use futures::{SinkExt, StreamExt};
+use log::{error, info, warn};
+use protocol::mqtt::codec::MqttPacketWrapper;
+use protocol::mqtt::common::{
+    qos, Connect, ConnectProperties, ConnectReturnCode, Filter, Login, MqttPacket, PingReq, PubAck,
+    PubAckReason, PubComp, PubCompReason, PubRec, PubRecReason, Publish, QoS, RetainForwardRule,
+    Subscribe, SubscribeReasonCode,
+};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::broadcast;
+use tokio::time::{interval, sleep, timeout};
+use tokio_util::codec::{FramedRead, FramedWrite};
+
+use crate::buffer::UplinkBuffer;
+use crate::codec::EdgeCodec;
+use crate::error::MqttEdgeError;
+use crate::mapping::TopicMapper;
+use crate::router::LocalRouter;
+
+const UPLINK_PROTOCOL_VERSION: u8 = 5;
+const CONNACK_TIMEOUT: Duration = Duration::from_secs(10);
+const SUBSCRIBE_PACKET_ID: u16 = 1;
+
+type UplinkReader = FramedRead<OwnedReadHalf, EdgeCodec>;
+type UplinkWriter = FramedWrite<OwnedWriteHalf, EdgeCodec>;
+
+// MQTT 5 client session towards the central cluster. Buffered messages are sent
+// in sequence order, a batch of up to `max_inflight` messages is only removed
+// from the buffer once every QoS 1 message of it has been acknowledged, so a
+// broken link replays the unacknowledged tail after reconnecting.
+pub struct UplinkClient {
+    conf: Uplink,
+    client_id: String,
+    router: Arc<LocalRouter>,
+    mapper: Arc<TopicMapper>,
+    buffer: Arc<UplinkBuffer>,
+}
+
+// State of one connection to the cluster
+struct UplinkSession {
+    reader: UplinkReader,
+    writer: UplinkWriter,
+    next_pkid: u16,
+    // packet id -> buffer sequence of the QoS 1 messages awaiting PubAck
+    inflight: HashMap<u16, u64>,
+    // last sequence of the batch being sent
+    batch_end: Option<u64>,
+    // QoS 2 downlink packet ids waiting for PubRel
+    pending_qos2: HashSet<u16>,
+    last_received: Instant,
+}
+
+impl UplinkClient {
+    pub fn new(
+        edge_id: &str,
+        conf: Uplink,
+        router: Arc<LocalRouter>,
+        mapper: Arc<TopicMapper>,
+        buffer: Arc<UplinkBuffer>,
+    ) -> Self {
+        let client_id = if conf.client_id.is_empty() {
+            edge_id.to_string()
+        } else {
+            conf.client_id.clone()
+        };
+        UplinkClient {
+            conf,
+            client_id,
+            router,
+            mapper,
+            buffer,
+        }
+    }
+
+    pub async fn start(&self, stop_send: broadcast::Sender<bool>) {
+        let mut stop_rx = stop_send.subscribe();
+        let reconnect_interval = Duration::from_millis(self.conf.reconnect_interval_ms);
+        loop {
+            tokio::select! {
+                val = stop_rx.recv() => {
+                    if let Ok(true) = val {
+                        break;
+                    }
+                }
+                res = self.run_session() => {
+                    if let Err(e) = res {
+                        warn!(
+                            "MQTT Edge uplink to {} is down, {} messages buffered, {}",
+                            self.conf.addr,
+                            self.buffer.len(),
+                            e
+                        );
+                    }
+                }
+            }
+
+            tokio::select! {
+                val = stop_rx.recv() => {
+                    if let Ok(true) = val {
+                        break;
+                    }
+                }
+                _ = sleep(reconnect_interval) => {}
+            }
+        }
+        info!("MQTT Edge uplink stopped");
+    }
+
+    async fn run_session(&self) -> Result<(), MqttEdgeError> {
+        let mut session = self.connect().await?;
+        info!(
+            "MQTT Edge uplink connected to {} as {}, {} messages buffered",
+            self.conf.addr,
+            self.client_id,
+            self.buffer.len()
+        );
+        self.subscribe(&mut session).await?;
+
+        let keep_alive = Duration::from_secs(self.conf.keep_alive.max(1) as u64);
+        let mut ping = interval(keep_alive);
+        loop {
+            if session.batch_end.is_some() && session.inflight.is_empty() {
+                self.buffer
+                    .remove_until(session.batch_end.take().unwrap())?;
+            }
+            if session.batch_end.is_none() && self.send_batch(&mut session).await? {
+                continue;
+            }
+
+            tokio::select! {
+                _ = self.buffer.wait_for_message(), if session.batch_end.is_none() => {}
+                _ = ping.tick() => {
+                    // the cluster answers PingReq, silence means the link is gone
+                    if session.last_received.elapsed() > keep_alive * 3 / 2 {
+                        return Err(MqttEdgeError::KeepAliveTimeout(self.conf.addr.clone()));
+                    }
+                    send(&mut session.writer, MqttPacket::PingReq(PingReq)).await?;
+                }
+                val = session.reader.next() => {
+                    match val {
+                        Some(Ok(packet)) => {
+                            session.last_received = Instant::now();
+                            self.handle_packet(&mut session, packet).await?;
+                        }
+                        Some(Err(e)) => return Err(e.into()),
+                        None => return Err(MqttEdgeError::ConnectionClosed),
+                    }
+                }
+            }
+        }
+    }
+
+    async fn connect(&self) -> Result<UplinkSession, MqttEdgeError> {
+        let stream = TcpStream::connect(&self.conf.addr).await?;
+        let (r_stream, w_stream) = stream.into_split();
+        let mut reader = FramedRead::new(r_stream, EdgeCodec::new(Some(UPLINK_PROTOCOL_VERSION)));
+        let mut writer = FramedWrite::new(w_stream, EdgeCodec::new(Some(UPLINK_PROTOCOL_VERSION)));
+
+        let login = if self.conf.username.is_empty() {
+            None
+        } else {
+            Some(Login {
+                username: self.conf.username.clone(),
+                password: self.conf.password.clone(),
+            })
+        };
+        // a persistent session keeps the downlink messages while the edge is offline
+        let properties = ConnectProperties {
+            session_expiry_interval: Some(self.conf.session_expiry_interval),
+            ..Default::default()
+        };
+        let connect = Connect {
+            keep_alive: self.conf.keep_alive,
+            client_id: self.client_id.clone(),
+            clean_session: false,
+        };
+        send(
+            &mut writer,
+            MqttPacket::Connect(
+                UPLINK_PROTOCOL_VERSION,
+                connect,
+                Some(properties),
+                None,
+                None,
+                login,
+            ),
+        )
+        .await?;
+
+        match timeout(CONNACK_TIMEOUT, reader.next()).await {
+            Ok(Some(Ok(MqttPacket::ConnAck(connack, _)))) => {
+                if connack.code != ConnectReturnCode::Success {
+                    return Err(MqttEdgeError::UplinkConnectRefused(
+                        self.conf.addr.clone(),
+                        format!("{:?}", connack.code),
+                    ));
+                }
+            }
+            Ok(Some(Ok(_))) => {
+                return Err(MqttEdgeError::UnexpectedPacket("CONNACK".to_string()));
+            }
+            Ok(Some(Err(e))) => return Err(e.into()),
+            Ok(None) => return Err(MqttEdgeError::ConnectionClosed),
+            Err(_) => return Err(MqttEdgeError::KeepAliveTimeout(self.conf.addr.clone())),
+        }
+
+        Ok(UplinkSession {
+            reader,
+            writer,
+            next_pkid: 1,
+            inflight: HashMap::new(),
+            batch_end: None,
+            pending_qos2: HashSet::new(),
+            last_received: Instant::now(),
+        })
+    }
+
+    // The SubAck is checked in handle_packet, queued downlink messages may arrive before it
+    async fn subscribe(&self, session: &mut UplinkSession) -> Result<(), MqttEdgeError> {
+        let filters: Vec<Filter> = self
+            .mapper
+            .remote_filters()
+            .into_iter()
+            .map(|(path, qos_level)| Filter {
+                path,
+                qos: qos(qos_level).unwrap(),
+                // keeps messages of bidirectional mappings from coming back to the edge
+                nolocal: true,
+                preserve_retain: false,
+                retain_forward_rule: RetainForwardRule::OnEverySubscribe,
+            })
+            .collect();
+        if filters.is_empty() {
+            return Ok(());
+        }
+        let subscribe = Subscribe {
+            packet_identifier: SUBSCRIBE_PACKET_ID,
+            filters,
+        };
+        send(&mut session.writer, MqttPacket::Subscribe(subscribe, None)).await
+    }
+
+    // Send the oldest buffered messages, returns false if the buffer is empty
+    async fn send_batch(&self, session: &mut UplinkSession) -> Result<bool, MqttEdgeError> {
+        let batch = self.buffer.read_batch(self.conf.max_inflight.max(1))?;
+        let last_seq = match batch.last() {
+            Some((seq, _)) => *seq,
+            None => return Ok(false),
+        };
+        for (seq, message) in batch {
+            let pkid = if message.qos > 0 {
+                let pkid = session.next_pkid();
+                session.inflight.insert(pkid, seq);
+                pkid
+            } else {
+                0
+            };
+            let publish = Publish {
+                dup: false,
+                qos: qos(message.qos).unwrap_or(QoS::AtLeastOnce),
+                pkid,
+                retain: message.retain,
+                topic: Bytes::from(message.topic),
+                payload: Bytes::from(message.payload),
+            };
+            session
+                .writer
+                .feed(wrap(MqttPacket::Publish(publish, None)))
+                .await?;
+        }
+        session.writer.flush().await?;
+        session.batch_end = Some(last_seq);
+        Ok(true)
+    }
+
+    async fn handle_packet(
+        &self,
+        session: &mut UplinkSession,
+        packet: MqttPacket,
+    ) -> Result<(), MqttEdgeError> {
+        match packet {
+            MqttPacket::PubAck(puback, _) => {
+                if let Some(seq) = session.inflight.remove(&puback.pkid) {
+                    if let Some(reason) = puback.reason {
+                        if reason != PubAckReason::Success
+                            && reason != PubAckReason::NoMatchingSubscribers
+                        {
+                            // retrying a rejected message would block the buffer forever
+                            warn!(
+                                "MQTT Edge uplink message {} was rejected by the cluster, {:?}",
+                                seq, reason
+                            );
+                        }
+                    }
+                }
+            }
+            MqttPacket::Publish(publish, _) => {
+                self.downlink(session, publish).await?;
+            }
+            MqttPacket::PubRel(pubrel, _) => {
+                session.pending_qos2.remove(&pubrel.pkid);
+                let pubcomp = PubComp {
+                    pkid: pubrel.pkid,
+                    reason: Some(PubCompReason::Success),
+                };
+                send(&mut session.writer, MqttPacket::PubComp(pubcomp, None)).await?;
+            }
+            MqttPacket::SubAck(suback, _) => {
+                let filters = self.mapper.remote_filters();
+                for (index, code) in suback.return_codes.iter().enumerate() {
+                    if !matches!(code, SubscribeReasonCode::Success(_)) {
+                        let filter = filters
+                            .get(index)
+                            .map(|(path, _)| path.clone())
+                            .unwrap_or_default();
+                        error!("{}", MqttEdgeError::UplinkSubscribeRejected(filter));
+                    }
+                }
+            }
+            MqttPacket::PingResp(_) => {}
+            MqttPacket::Disconnect(disconnect, _) => {
+                info!(
+                    "MQTT Edge uplink was disconnected by the cluster, {:?}",
+                    disconnect.reason_code
+                );
+                return Err(MqttEdgeError::ConnectionClosed);
+            }
+            packet => {
+                warn!(
+                    "MQTT Edge uplink received an unexpected packet {:?}",
+                    packet
+                );
+            }
+        }
+        Ok(())
+    }
+
+    // Messages from the cluster are only delivered locally, never forwarded back upstream
+    async fn downlink(
+        &self,
+        session: &mut UplinkSession,
+        publish: Publish,
+    ) -> Result<(), MqttEdgeError> {
+        let duplicate =
+            publish.qos == QoS::ExactlyOnce && session.pending_qos2.contains(&publish.pkid);
+        if !duplicate {
+            let topic = String::from_utf8_lossy(&publish.topic).to_string();
+            match self.mapper.to_local(&topic) {
+                Some(local_topic) => {
+                    self.router.publish_local(
+                        &local_topic,
+                        publish.payload.clone(),
+                        publish.qos.into(),
+                        publish.retain,
+                    );
+                }
+                None => {
+                    warn!("MQTT Edge uplink received {} without a mapping", topic);
+                }
+            }
+        }
+
+        match publish.qos {
+            QoS::AtMostOnce => Ok(()),
+            QoS::AtLeastOnce => {
+                let puback = PubAck {
+                    pkid: publish.pkid,
+                    reason: Some(PubAckReason::Success),
+                };
+                send(&mut session.writer, MqttPacket::PubAck(puback, None)).await
+            }
+            QoS::ExactlyOnce => {
+                session.pending_qos2.insert(publish.pkid);
+                let pubrec = PubRec {
+                    pkid: publish.pkid,
+                    reason: Some(PubRecReason::Success),
+                };
+                send(&mut session.writer, MqttPacket::PubRec(pubrec, None)).await
+            }
+        }
+    }
+}
+
+impl UplinkSession {
+    fn next_pkid(&mut self) -> u16 {
+        loop {
+            let pkid = self.next_pkid;
+            self.next_pkid = self.next_pkid.wrapping_add(1);
+            if pkid != 0 && pkid != SUBSCRIBE_PACKET_ID && !self.inflight.contains_key(&pkid) {
+                return pkid;
+            }
+        }
+    }
+}
+
+fn wrap(packet: MqttPacket) -> MqttPacketWrapper {
+    MqttPacketWrapper {
+        protocol_version: UPLINK_PROTOCOL_VERSION,
+        packet,
+    }
+}
+
+async fn send(writer: &mut UplinkWriter, packet: MqttPacket) -> Result<(), MqttEdgeError> {
+    writer.send(wrap(packet)).await?;
+    Ok(())
+}
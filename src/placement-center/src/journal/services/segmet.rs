@@ -500,6 +500,7 @@ mod tests {
             data_fold: vec!["/tmp/t1".to_string(), "/tmp/t2".to_string()],
             tcp_addr: "127.0.0.1:3110".to_string(),
             tcps_addr: "127.0.0.1:3110".to_string(),
+            quic_addr: "127.0.0.1:3112".to_string(),
         };
 
         let node = BrokerNode {
@@ -19,11 +19,13 @@ use common_base::config::placement_center::placement_center_conf;
 use grpc_clients::pool::ClientPool;
 use log::info;
 use openraft::Raft;
+use protocol::placement_center::placement_center_amqp::amqp_service_server::AmqpServiceServer;
 use protocol::placement_center::placement_center_inner::placement_center_service_server::PlacementCenterServiceServer;
 use protocol::placement_center::placement_center_journal::engine_service_server::EngineServiceServer;
 use protocol::placement_center::placement_center_kv::kv_service_server::KvServiceServer;
 use protocol::placement_center::placement_center_mqtt::mqtt_service_server::MqttServiceServer;
 use protocol::placement_center::placement_center_openraft::open_raft_service_server::OpenRaftServiceServer;
+use server::grpc::service_amqp::GrpcAmqpService;
 use server::grpc::service_inner::GrpcPlacementService;
 use server::grpc::service_journal::GrpcEngineService;
 use server::grpc::service_kv::GrpcKvService;
@@ -211,12 +213,18 @@ impl PlacementCenter {
             self.rocksdb_engine_handler.clone(),
         );
 
+        let amqp_handler = GrpcAmqpService::new(
+            raft_machine_apply.clone(),
+            self.rocksdb_engine_handler.clone(),
+        );
+
         tokio::spawn(async move {
             info!("RobustMQ Meta Grpc Server start success. bind addr:{}", ip);
             Server::builder()
                 .add_service(PlacementCenterServiceServer::new(placement_handler))
                 .add_service(KvServiceServer::new(kv_handler))
                 .add_service(MqttServiceServer::new(mqtt_handler))
+                .add_service(AmqpServiceServer::new(amqp_handler))
                 .add_service(EngineServiceServer::new(engine_handler))
                 .add_service(OpenRaftServiceServer::new(openraft_handler))
                 .serve(ip)
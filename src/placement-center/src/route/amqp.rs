@@ -0,0 +1,70 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use metadata_struct::amqp::exchange::AmqpExchange;
+use metadata_struct::amqp::queue::AmqpQueue;
+use prost::Message as _;
+use protocol::placement_center::placement_center_amqp::{
+    CreateExchangeRequest, CreateQueueRequest, DeleteExchangeRequest, DeleteQueueRequest,
+};
+
+use crate::core::error::PlacementCenterError;
+use crate::storage::amqp::exchange::AmqpExchangeStorage;
+use crate::storage::amqp::queue::AmqpQueueStorage;
+use crate::storage::rocksdb::RocksDBEngine;
+
+#[derive(Debug, Clone)]
+pub struct DataRouteAmqp {
+    pub rocksdb_engine_handler: Arc<RocksDBEngine>,
+}
+
+impl DataRouteAmqp {
+    pub fn new(rocksdb_engine_handler: Arc<RocksDBEngine>) -> Self {
+        DataRouteAmqp {
+            rocksdb_engine_handler,
+        }
+    }
+
+    pub fn create_exchange(&self, value: Vec<u8>) -> Result<(), PlacementCenterError> {
+        let req = CreateExchangeRequest::decode(value.as_ref())?;
+        let storage = AmqpExchangeStorage::new(self.rocksdb_engine_handler.clone());
+        let exchange = serde_json::from_slice::<AmqpExchange>(&req.content)?;
+        storage.save(&req.cluster_name, exchange)?;
+        Ok(())
+    }
+
+    pub fn delete_exchange(&self, value: Vec<u8>) -> Result<(), PlacementCenterError> {
+        let req = DeleteExchangeRequest::decode(value.as_ref())?;
+        let storage = AmqpExchangeStorage::new(self.rocksdb_engine_handler.clone());
+        storage.delete(&req.cluster_name, &req.exchange_name)?;
+        Ok(())
+    }
+
+    pub fn create_queue(&self, value: Vec<u8>) -> Result<(), PlacementCenterError> {
+        let req = CreateQueueRequest::decode(value.as_ref())?;
+        let storage = AmqpQueueStorage::new(self.rocksdb_engine_handler.clone());
+        let queue = serde_json::from_slice::<AmqpQueue>(&req.content)?;
+        storage.save(&req.cluster_name, queue)?;
+        Ok(())
+    }
+
+    pub fn delete_queue(&self, value: Vec<u8>) -> Result<(), PlacementCenterError> {
+        let req = DeleteQueueRequest::decode(value.as_ref())?;
+        let storage = AmqpQueueStorage::new(self.rocksdb_engine_handler.clone());
+        storage.delete(&req.cluster_name, &req.queue_name)?;
+        Ok(())
+    }
+}
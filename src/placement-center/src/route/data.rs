@@ -74,6 +74,20 @@ pub enum StorageDataType {
     MqttDeleteAcl,
     MqttSetBlacklist,
     MqttDeleteBlacklist,
+    MqttSetBridge,
+    MqttDeleteBridge,
+    MqttSetRule,
+    MqttDeleteRule,
     MqttSetNxExclusiveTopic,
     MqttDeleteExclusiveTopic,
+
+    // amqp
+    AmqpSetExchange,
+    AmqpDeleteExchange,
+    AmqpSetQueue,
+    AmqpDeleteQueue,
+
+    // kv batches, added last so that the variants above keep their index in the Raft log
+    KvBatchSet,
+    KvBatchDelete,
 }
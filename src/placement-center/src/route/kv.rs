@@ -15,7 +15,9 @@
 use std::sync::Arc;
 
 use prost::Message as _;
-use protocol::placement_center::placement_center_kv::{DeleteRequest, SetRequest};
+use protocol::placement_center::placement_center_kv::{
+    BatchDeleteRequest, BatchSetRequest, DeleteRequest, SetRequest,
+};
 
 use crate::core::error::PlacementCenterError;
 use crate::storage::placement::kv::KvStorage;
@@ -40,4 +42,23 @@ impl DataRouteKv {
         let req: DeleteRequest = DeleteRequest::decode(value.as_ref())?;
         Ok(self.kv_storage.delete(req.key)?)
     }
+
+    pub fn batch_set(&self, value: Vec<u8>) -> Result<bool, PlacementCenterError> {
+        let req: BatchSetRequest = BatchSetRequest::decode(value.as_ref())?;
+        let pairs = req.pairs.into_iter().map(|pair| (pair.key, pair.value));
+        Ok(self.kv_storage.batch_set(
+            pairs.collect(),
+            req.delete_keys,
+            &req.check_key,
+            &req.check_value,
+        )?)
+    }
+
+    pub fn batch_delete(&self, value: Vec<u8>) -> Result<(), PlacementCenterError> {
+        let req: BatchDeleteRequest = BatchDeleteRequest::decode(value.as_ref())?;
+        for key in req.keys {
+            self.kv_storage.delete(key)?;
+        }
+        Ok(())
+    }
 }
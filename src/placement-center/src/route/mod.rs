@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod amqp;
 pub mod apply;
 pub mod cluster;
 pub mod data;
@@ -30,6 +31,7 @@ use log::{error, info};
 use crate::core::cache::PlacementCacheManager;
 use crate::core::error::PlacementCenterError;
 use crate::journal::cache::JournalCacheManager;
+use crate::route::amqp::DataRouteAmqp;
 use crate::route::cluster::DataRouteCluster;
 use crate::route::journal::DataRouteJournal;
 use crate::route::kv::DataRouteKv;
@@ -40,6 +42,7 @@ use crate::storage::rocksdb::{RocksDBEngine, DB_COLUMN_FAMILY_CLUSTER};
 pub struct DataRoute {
     route_kv: DataRouteKv,
     route_mqtt: DataRouteMqtt,
+    route_amqp: DataRouteAmqp,
     route_journal: DataRouteJournal,
     route_cluster: DataRouteCluster,
     rocksdb_engine_handler: Arc<RocksDBEngine>,
@@ -53,6 +56,7 @@ impl DataRoute {
     ) -> DataRoute {
         let route_kv = DataRouteKv::new(rocksdb_engine_handler.clone());
         let route_mqtt = DataRouteMqtt::new(rocksdb_engine_handler.clone());
+        let route_amqp = DataRouteAmqp::new(rocksdb_engine_handler.clone());
         let route_cluster =
             DataRouteCluster::new(rocksdb_engine_handler.clone(), cluster_cache.clone());
         let route_journal =
@@ -60,6 +64,7 @@ impl DataRoute {
         DataRoute {
             route_kv,
             route_mqtt,
+            route_amqp,
             route_journal,
             route_cluster,
             rocksdb_engine_handler,
@@ -81,6 +86,14 @@ impl DataRoute {
                 self.route_kv.delete(storage_data.value)?;
                 Ok(None)
             }
+            StorageDataType::KvBatchSet => {
+                let success = self.route_kv.batch_set(storage_data.value)?;
+                Ok(Some(vec_util::bool_to_vec(success)))
+            }
+            StorageDataType::KvBatchDelete => {
+                self.route_kv.batch_delete(storage_data.value)?;
+                Ok(None)
+            }
             StorageDataType::ClusterAddNode => {
                 self.route_cluster.add_node(storage_data.value).await?;
                 Ok(None)
@@ -210,6 +223,40 @@ impl DataRoute {
                 self.route_mqtt.delete_exclusive_topic(storage_data.value)?;
                 Ok(None)
             }
+            StorageDataType::MqttSetBridge => {
+                self.route_mqtt.create_bridge(storage_data.value)?;
+                Ok(None)
+            }
+            StorageDataType::MqttDeleteBridge => {
+                self.route_mqtt.delete_bridge(storage_data.value)?;
+                Ok(None)
+            }
+            StorageDataType::MqttSetRule => {
+                self.route_mqtt.create_rule(storage_data.value)?;
+                Ok(None)
+            }
+            StorageDataType::MqttDeleteRule => {
+                self.route_mqtt.delete_rule(storage_data.value)?;
+                Ok(None)
+            }
+
+            // AMQP Broker
+            StorageDataType::AmqpSetExchange => {
+                self.route_amqp.create_exchange(storage_data.value)?;
+                Ok(None)
+            }
+            StorageDataType::AmqpDeleteExchange => {
+                self.route_amqp.delete_exchange(storage_data.value)?;
+                Ok(None)
+            }
+            StorageDataType::AmqpSetQueue => {
+                self.route_amqp.create_queue(storage_data.value)?;
+                Ok(None)
+            }
+            StorageDataType::AmqpDeleteQueue => {
+                self.route_amqp.delete_queue(storage_data.value)?;
+                Ok(None)
+            }
         }
     }
 
@@ -14,17 +14,22 @@
 
 use std::sync::Arc;
 
+use metadata_struct::mqtt::bridge::MqttBridgeRule;
+use metadata_struct::mqtt::rule::MqttRule;
 use metadata_struct::mqtt::session::MqttSession;
 use metadata_struct::mqtt::topic::MqttTopic;
 use prost::Message as _;
 use protocol::placement_center::placement_center_mqtt::{
-    CreateSessionRequest, CreateUserRequest, DeleteExclusiveTopicRequest, DeleteSessionRequest,
+    CreateBridgeRequest, CreateRuleRequest, CreateSessionRequest, CreateUserRequest,
+    DeleteBridgeRequest, DeleteExclusiveTopicRequest, DeleteRuleRequest, DeleteSessionRequest,
     DeleteTopicRequest, DeleteUserRequest, SaveLastWillMessageRequest, SetExclusiveTopicRequest,
     UpdateSessionRequest,
 };
 
 use crate::core::error::PlacementCenterError;
+use crate::storage::mqtt::bridge::MqttBridgeStorage;
 use crate::storage::mqtt::lastwill::MqttLastWillStorage;
+use crate::storage::mqtt::rule::MqttRuleStorage;
 use crate::storage::mqtt::session::MqttSessionStorage;
 use crate::storage::mqtt::topic::MqttTopicStorage;
 use crate::storage::mqtt::user::MqttUserStorage;
@@ -136,4 +141,34 @@ impl DataRouteMqtt {
         let storage = MqttTopicStorage::new(self.rocksdb_engine_handler.clone());
         storage.delete_exclisve_topic(&req.cluster_name, &req.topic_name)
     }
+
+    pub fn create_bridge(&self, value: Vec<u8>) -> Result<(), PlacementCenterError> {
+        let req = CreateBridgeRequest::decode(value.as_ref())?;
+        let storage = MqttBridgeStorage::new(self.rocksdb_engine_handler.clone());
+        let bridge = serde_json::from_slice::<MqttBridgeRule>(&req.bridge)?;
+        storage.save(&req.cluster_name, bridge)?;
+        Ok(())
+    }
+
+    pub fn delete_bridge(&self, value: Vec<u8>) -> Result<(), PlacementCenterError> {
+        let req = DeleteBridgeRequest::decode(value.as_ref())?;
+        let storage = MqttBridgeStorage::new(self.rocksdb_engine_handler.clone());
+        storage.delete(&req.cluster_name, &req.bridge_name)?;
+        Ok(())
+    }
+
+    pub fn create_rule(&self, value: Vec<u8>) -> Result<(), PlacementCenterError> {
+        let req = CreateRuleRequest::decode(value.as_ref())?;
+        let storage = MqttRuleStorage::new(self.rocksdb_engine_handler.clone());
+        let rule = serde_json::from_slice::<MqttRule>(&req.rule)?;
+        storage.save(&req.cluster_name, rule)?;
+        Ok(())
+    }
+
+    pub fn delete_rule(&self, value: Vec<u8>) -> Result<(), PlacementCenterError> {
+        let req = DeleteRuleRequest::decode(value.as_ref())?;
+        let storage = MqttRuleStorage::new(self.rocksdb_engine_handler.clone());
+        storage.delete(&req.cluster_name, &req.rule_name)?;
+        Ok(())
+    }
 }
@@ -0,0 +1,176 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use prost::Message;
+use protocol::placement_center::placement_center_amqp::amqp_service_server::AmqpService;
+use protocol::placement_center::placement_center_amqp::{
+    CreateExchangeReply, CreateExchangeRequest, CreateQueueReply, CreateQueueRequest,
+    DeleteExchangeReply, DeleteExchangeRequest, DeleteQueueReply, DeleteQueueRequest,
+    ListExchangeReply, ListExchangeRequest, ListQueueReply, ListQueueRequest,
+};
+use tonic::{Request, Response, Status};
+
+use crate::route::apply::RaftMachineApply;
+use crate::route::data::{StorageData, StorageDataType};
+use crate::storage::amqp::exchange::AmqpExchangeStorage;
+use crate::storage::amqp::queue::AmqpQueueStorage;
+use crate::storage::rocksdb::RocksDBEngine;
+
+pub struct GrpcAmqpService {
+    raft_machine_apply: Arc<RaftMachineApply>,
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+}
+
+impl GrpcAmqpService {
+    pub fn new(
+        raft_machine_apply: Arc<RaftMachineApply>,
+        rocksdb_engine_handler: Arc<RocksDBEngine>,
+    ) -> Self {
+        GrpcAmqpService {
+            raft_machine_apply,
+            rocksdb_engine_handler,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl AmqpService for GrpcAmqpService {
+    async fn list_exchange(
+        &self,
+        request: Request<ListExchangeRequest>,
+    ) -> Result<Response<ListExchangeReply>, Status> {
+        let req = request.into_inner();
+        let exchange_storage = AmqpExchangeStorage::new(self.rocksdb_engine_handler.clone());
+        match exchange_storage.list(&req.cluster_name) {
+            Ok(list) => {
+                let mut exchanges = Vec::new();
+                for exchange in list {
+                    match exchange.encode() {
+                        Ok(data) => {
+                            exchanges.push(data);
+                        }
+                        Err(e) => {
+                            return Err(Status::cancelled(e.to_string()));
+                        }
+                    }
+                }
+                return Ok(Response::new(ListExchangeReply { exchanges }));
+            }
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        }
+    }
+
+    async fn create_exchange(
+        &self,
+        request: Request<CreateExchangeRequest>,
+    ) -> Result<Response<CreateExchangeReply>, Status> {
+        let req = request.into_inner();
+        let data = StorageData::new(
+            StorageDataType::AmqpSetExchange,
+            CreateExchangeRequest::encode_to_vec(&req),
+        );
+
+        match self.raft_machine_apply.client_write(data).await {
+            Ok(_) => return Ok(Response::new(CreateExchangeReply::default())),
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        }
+    }
+
+    async fn delete_exchange(
+        &self,
+        request: Request<DeleteExchangeRequest>,
+    ) -> Result<Response<DeleteExchangeReply>, Status> {
+        let req = request.into_inner();
+        let data = StorageData::new(
+            StorageDataType::AmqpDeleteExchange,
+            DeleteExchangeRequest::encode_to_vec(&req),
+        );
+
+        match self.raft_machine_apply.client_write(data).await {
+            Ok(_) => return Ok(Response::new(DeleteExchangeReply::default())),
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        }
+    }
+
+    async fn list_queue(
+        &self,
+        request: Request<ListQueueRequest>,
+    ) -> Result<Response<ListQueueReply>, Status> {
+        let req = request.into_inner();
+        let queue_storage = AmqpQueueStorage::new(self.rocksdb_engine_handler.clone());
+        match queue_storage.list(&req.cluster_name) {
+            Ok(list) => {
+                let mut queues = Vec::new();
+                for queue in list {
+                    match queue.encode() {
+                        Ok(data) => {
+                            queues.push(data);
+                        }
+                        Err(e) => {
+                            return Err(Status::cancelled(e.to_string()));
+                        }
+                    }
+                }
+                return Ok(Response::new(ListQueueReply { queues }));
+            }
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        }
+    }
+
+    async fn create_queue(
+        &self,
+        request: Request<CreateQueueRequest>,
+    ) -> Result<Response<CreateQueueReply>, Status> {
+        let req = request.into_inner();
+        let data = StorageData::new(
+            StorageDataType::AmqpSetQueue,
+            CreateQueueRequest::encode_to_vec(&req),
+        );
+
+        match self.raft_machine_apply.client_write(data).await {
+            Ok(_) => return Ok(Response::new(CreateQueueReply::default())),
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        }
+    }
+
+    async fn delete_queue(
+        &self,
+        request: Request<DeleteQueueRequest>,
+    ) -> Result<Response<DeleteQueueReply>, Status> {
+        let req = request.into_inner();
+        let data = StorageData::new(
+            StorageDataType::AmqpDeleteQueue,
+            DeleteQueueRequest::encode_to_vec(&req),
+        );
+
+        match self.raft_machine_apply.client_write(data).await {
+            Ok(_) => return Ok(Response::new(DeleteQueueReply::default())),
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        }
+    }
+}
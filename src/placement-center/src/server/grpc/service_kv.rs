@@ -15,14 +15,17 @@
 use std::sync::Arc;
 
 use common_base::error::common::CommonError;
+use common_base::utils::vec_util;
 use prost::Message;
 use protocol::placement_center::placement_center_kv::kv_service_server::KvService;
 use protocol::placement_center::placement_center_kv::{
-    DeleteReply, DeleteRequest, ExistsReply, ExistsRequest, GetReply, GetRequest, SetReply,
-    SetRequest,
+    BatchDeleteReply, BatchDeleteRequest, BatchGetReply, BatchGetRequest, BatchSetReply,
+    BatchSetRequest, DeleteReply, DeleteRequest, ExistsReply, ExistsRequest, GetReply, GetRequest,
+    KvPair, SetReply, SetRequest,
 };
 use tonic::{Request, Response, Status};
 
+use crate::core::error::PlacementCenterError;
 use crate::route::apply::RaftMachineApply;
 use crate::route::data::{StorageData, StorageDataType};
 use crate::storage::placement::kv::KvStorage;
@@ -136,4 +139,74 @@ impl KvService for GrpcKvService {
             }
         }
     }
+
+    async fn batch_set(
+        &self,
+        request: Request<BatchSetRequest>,
+    ) -> Result<Response<BatchSetReply>, Status> {
+        let req = request.into_inner();
+
+        if req
+            .pairs
+            .iter()
+            .any(|pair| pair.key.is_empty() || pair.value.is_empty())
+        {
+            return Err(Status::cancelled(
+                CommonError::ParameterCannotBeNull("key or value".to_string()).to_string(),
+            ));
+        }
+
+        // All pairs are applied by one Raft proposal
+        let data = StorageData::new(
+            StorageDataType::KvBatchSet,
+            BatchSetRequest::encode_to_vec(&req),
+        );
+        match self.raft_machine_apply.client_write(data).await {
+            Ok(Some(resp)) => {
+                let mut reply = BatchSetReply::default();
+                if let Some(value) = resp.data.value {
+                    reply.success = vec_util::vec_to_bool(&value);
+                }
+                Ok(Response::new(reply))
+            }
+            Ok(None) => Err(Status::cancelled(
+                PlacementCenterError::ExecutionResultIsEmpty.to_string(),
+            )),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn batch_get(
+        &self,
+        request: Request<BatchGetRequest>,
+    ) -> Result<Response<BatchGetReply>, Status> {
+        let req = request.into_inner();
+
+        let kv_storage = KvStorage::new(self.rocksdb_engine_handler.clone());
+        match kv_storage.batch_get(req.keys) {
+            Ok(pairs) => Ok(Response::new(BatchGetReply {
+                pairs: pairs
+                    .into_iter()
+                    .map(|(key, value)| KvPair { key, value })
+                    .collect(),
+            })),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn batch_delete(
+        &self,
+        request: Request<BatchDeleteRequest>,
+    ) -> Result<Response<BatchDeleteReply>, Status> {
+        let req = request.into_inner();
+
+        let data = StorageData::new(
+            StorageDataType::KvBatchDelete,
+            BatchDeleteRequest::encode_to_vec(&req),
+        );
+        match self.raft_machine_apply.client_write(data).await {
+            Ok(_) => Ok(Response::new(BatchDeleteReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
 }
@@ -19,14 +19,16 @@ use prost::Message;
 use protocol::placement_center::placement_center_mqtt::mqtt_service_server::MqttService;
 use protocol::placement_center::placement_center_mqtt::{
     CreateAclReply, CreateAclRequest, CreateBlacklistReply, CreateBlacklistRequest,
-    CreateSessionReply, CreateSessionRequest, CreateTopicReply, CreateTopicRequest,
-    CreateUserReply, CreateUserRequest, DeleteAclReply, DeleteAclRequest, DeleteBlacklistReply,
-    DeleteBlacklistRequest, DeleteExclusiveTopicReply, DeleteExclusiveTopicRequest,
-    DeleteSessionReply, DeleteSessionRequest, DeleteTopicReply, DeleteTopicRequest,
-    DeleteUserReply, DeleteUserRequest, GetShareSubLeaderReply, GetShareSubLeaderRequest,
-    ListAclReply, ListAclRequest, ListBlacklistReply, ListBlacklistRequest, ListSessionReply,
-    ListSessionRequest, ListTopicReply, ListTopicRequest, ListUserReply, ListUserRequest,
-    SaveLastWillMessageReply, SaveLastWillMessageRequest, SetExclusiveTopicReply,
+    CreateBridgeReply, CreateBridgeRequest, CreateRuleReply, CreateRuleRequest, CreateSessionReply,
+    CreateSessionRequest, CreateTopicReply, CreateTopicRequest, CreateUserReply, CreateUserRequest,
+    DeleteAclReply, DeleteAclRequest, DeleteBlacklistReply, DeleteBlacklistRequest,
+    DeleteBridgeReply, DeleteBridgeRequest, DeleteExclusiveTopicReply, DeleteExclusiveTopicRequest,
+    DeleteRuleReply, DeleteRuleRequest, DeleteSessionReply, DeleteSessionRequest, DeleteTopicReply,
+    DeleteTopicRequest, DeleteUserReply, DeleteUserRequest, GetShareSubLeaderReply,
+    GetShareSubLeaderRequest, ListAclReply, ListAclRequest, ListBlacklistReply,
+    ListBlacklistRequest, ListBridgeReply, ListBridgeRequest, ListRuleReply, ListRuleRequest,
+    ListSessionReply, ListSessionRequest, ListTopicReply, ListTopicRequest, ListUserReply,
+    ListUserRequest, SaveLastWillMessageReply, SaveLastWillMessageRequest, SetExclusiveTopicReply,
     SetExclusiveTopicRequest, SetTopicRetainMessageReply, SetTopicRetainMessageRequest,
     UpdateSessionReply, UpdateSessionRequest,
 };
@@ -41,6 +43,8 @@ use crate::route::data::{StorageData, StorageDataType};
 use crate::server::grpc::validate::ValidateExt;
 use crate::storage::mqtt::acl::AclStorage;
 use crate::storage::mqtt::blacklist::MqttBlackListStorage;
+use crate::storage::mqtt::bridge::MqttBridgeStorage;
+use crate::storage::mqtt::rule::MqttRuleStorage;
 use crate::storage::mqtt::session::MqttSessionStorage;
 use crate::storage::mqtt::topic::MqttTopicStorage;
 use crate::storage::mqtt::user::MqttUserStorage;
@@ -555,4 +559,130 @@ impl MqttService for GrpcMqttService {
             }
         }
     }
+
+    async fn list_bridge(
+        &self,
+        request: Request<ListBridgeRequest>,
+    ) -> Result<Response<ListBridgeReply>, Status> {
+        let req = request.into_inner();
+        let bridge_storage = MqttBridgeStorage::new(self.rocksdb_engine_handler.clone());
+        match bridge_storage.list(&req.cluster_name) {
+            Ok(list) => {
+                let mut bridges = Vec::new();
+                for bridge in list {
+                    match bridge.encode() {
+                        Ok(data) => {
+                            bridges.push(data);
+                        }
+                        Err(e) => {
+                            return Err(Status::cancelled(e.to_string()));
+                        }
+                    }
+                }
+                return Ok(Response::new(ListBridgeReply { bridges }));
+            }
+            Err(e) => {
+                return Err(Status::internal(e.to_string()));
+            }
+        }
+    }
+
+    async fn create_bridge(
+        &self,
+        request: Request<CreateBridgeRequest>,
+    ) -> Result<Response<CreateBridgeReply>, Status> {
+        let req = request.into_inner();
+        let data = StorageData::new(
+            StorageDataType::MqttSetBridge,
+            CreateBridgeRequest::encode_to_vec(&req),
+        );
+
+        match self.raft_machine_apply.client_write(data).await {
+            Ok(_) => return Ok(Response::new(CreateBridgeReply::default())),
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        }
+    }
+
+    async fn delete_bridge(
+        &self,
+        request: Request<DeleteBridgeRequest>,
+    ) -> Result<Response<DeleteBridgeReply>, Status> {
+        let req = request.into_inner();
+        let data = StorageData::new(
+            StorageDataType::MqttDeleteBridge,
+            DeleteBridgeRequest::encode_to_vec(&req),
+        );
+
+        match self.raft_machine_apply.client_write(data).await {
+            Ok(_) => return Ok(Response::new(DeleteBridgeReply::default())),
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        }
+    }
+
+    async fn list_rule(
+        &self,
+        request: Request<ListRuleRequest>,
+    ) -> Result<Response<ListRuleReply>, Status> {
+        let req = request.into_inner();
+        let rule_storage = MqttRuleStorage::new(self.rocksdb_engine_handler.clone());
+        match rule_storage.list(&req.cluster_name) {
+            Ok(list) => {
+                let mut rules = Vec::new();
+                for rule in list {
+                    match rule.encode() {
+                        Ok(data) => {
+                            rules.push(data);
+                        }
+                        Err(e) => {
+                            return Err(Status::cancelled(e.to_string()));
+                        }
+                    }
+                }
+                return Ok(Response::new(ListRuleReply { rules }));
+            }
+            Err(e) => {
+                return Err(Status::internal(e.to_string()));
+            }
+        }
+    }
+
+    async fn create_rule(
+        &self,
+        request: Request<CreateRuleRequest>,
+    ) -> Result<Response<CreateRuleReply>, Status> {
+        let req = request.into_inner();
+        let data = StorageData::new(
+            StorageDataType::MqttSetRule,
+            CreateRuleRequest::encode_to_vec(&req),
+        );
+
+        match self.raft_machine_apply.client_write(data).await {
+            Ok(_) => return Ok(Response::new(CreateRuleReply::default())),
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        }
+    }
+
+    async fn delete_rule(
+        &self,
+        request: Request<DeleteRuleRequest>,
+    ) -> Result<Response<DeleteRuleReply>, Status> {
+        let req = request.into_inner();
+        let data = StorageData::new(
+            StorageDataType::MqttDeleteRule,
+            DeleteRuleRequest::encode_to_vec(&req),
+        );
+
+        match self.raft_machine_apply.client_write(data).await {
+            Ok(_) => return Ok(Response::new(DeleteRuleReply::default())),
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        }
+    }
 }
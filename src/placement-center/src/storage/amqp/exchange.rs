@@ -0,0 +1,69 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::error::common::CommonError;
+use metadata_struct::amqp::exchange::AmqpExchange;
+
+use crate::storage::engine::{
+    engine_delete_by_cluster, engine_get_by_cluster, engine_prefix_list_by_cluster,
+    engine_save_by_cluster,
+};
+use crate::storage::keys::{storage_key_amqp_exchange, storage_key_amqp_exchange_prefix};
+use crate::storage::rocksdb::RocksDBEngine;
+
+pub struct AmqpExchangeStorage {
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+}
+
+impl AmqpExchangeStorage {
+    pub fn new(rocksdb_engine_handler: Arc<RocksDBEngine>) -> Self {
+        AmqpExchangeStorage {
+            rocksdb_engine_handler,
+        }
+    }
+
+    pub fn save(&self, cluster_name: &str, exchange: AmqpExchange) -> Result<(), CommonError> {
+        let key = storage_key_amqp_exchange(cluster_name, &exchange.exchange_name);
+        engine_save_by_cluster(self.rocksdb_engine_handler.clone(), key, exchange)
+    }
+
+    pub fn list(&self, cluster_name: &str) -> Result<Vec<AmqpExchange>, CommonError> {
+        let prefix_key = storage_key_amqp_exchange_prefix(cluster_name);
+        let data = engine_prefix_list_by_cluster(self.rocksdb_engine_handler.clone(), prefix_key)?;
+        let mut results = Vec::new();
+        for raw in data {
+            results.push(serde_json::from_slice::<AmqpExchange>(&raw.data)?);
+        }
+        Ok(results)
+    }
+
+    pub fn get(
+        &self,
+        cluster_name: &str,
+        exchange_name: &str,
+    ) -> Result<Option<AmqpExchange>, CommonError> {
+        let key = storage_key_amqp_exchange(cluster_name, exchange_name);
+        if let Some(data) = engine_get_by_cluster(self.rocksdb_engine_handler.clone(), key)? {
+            return Ok(Some(serde_json::from_slice::<AmqpExchange>(&data.data)?));
+        }
+        Ok(None)
+    }
+
+    pub fn delete(&self, cluster_name: &str, exchange_name: &str) -> Result<(), CommonError> {
+        let key = storage_key_amqp_exchange(cluster_name, exchange_name);
+        engine_delete_by_cluster(self.rocksdb_engine_handler.clone(), key)
+    }
+}
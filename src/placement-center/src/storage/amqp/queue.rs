@@ -0,0 +1,124 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::error::common::CommonError;
+use metadata_struct::amqp::queue::AmqpQueue;
+
+use crate::storage::engine::{
+    engine_delete_by_cluster, engine_get_by_cluster, engine_prefix_list_by_cluster,
+    engine_save_by_cluster,
+};
+use crate::storage::keys::{storage_key_amqp_queue, storage_key_amqp_queue_prefix};
+use crate::storage::rocksdb::RocksDBEngine;
+
+pub struct AmqpQueueStorage {
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+}
+
+impl AmqpQueueStorage {
+    pub fn new(rocksdb_engine_handler: Arc<RocksDBEngine>) -> Self {
+        AmqpQueueStorage {
+            rocksdb_engine_handler,
+        }
+    }
+
+    pub fn save(&self, cluster_name: &str, queue: AmqpQueue) -> Result<(), CommonError> {
+        let key = storage_key_amqp_queue(cluster_name, &queue.queue_name);
+        engine_save_by_cluster(self.rocksdb_engine_handler.clone(), key, queue)
+    }
+
+    pub fn list(&self, cluster_name: &str) -> Result<Vec<AmqpQueue>, CommonError> {
+        let prefix_key = storage_key_amqp_queue_prefix(cluster_name);
+        let data = engine_prefix_list_by_cluster(self.rocksdb_engine_handler.clone(), prefix_key)?;
+        let mut results = Vec::new();
+        for raw in data {
+            results.push(serde_json::from_slice::<AmqpQueue>(&raw.data)?);
+        }
+        Ok(results)
+    }
+
+    pub fn get(
+        &self,
+        cluster_name: &str,
+        queue_name: &str,
+    ) -> Result<Option<AmqpQueue>, CommonError> {
+        let key = storage_key_amqp_queue(cluster_name, queue_name);
+        if let Some(data) = engine_get_by_cluster(self.rocksdb_engine_handler.clone(), key)? {
+            return Ok(Some(serde_json::from_slice::<AmqpQueue>(&data.data)?));
+        }
+        Ok(None)
+    }
+
+    pub fn delete(&self, cluster_name: &str, queue_name: &str) -> Result<(), CommonError> {
+        let key = storage_key_amqp_queue(cluster_name, queue_name);
+        engine_delete_by_cluster(self.rocksdb_engine_handler.clone(), key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_dir_all;
+    use std::sync::Arc;
+
+    use common_base::config::placement_center::placement_center_test_conf;
+    use metadata_struct::amqp::queue::{AmqpBinding, AmqpQueue};
+
+    use crate::storage::amqp::queue::AmqpQueueStorage;
+    use crate::storage::rocksdb::{column_family_list, RocksDBEngine};
+
+    #[tokio::test]
+    async fn queue_storage_test() {
+        let config = placement_center_test_conf();
+
+        let rs = Arc::new(RocksDBEngine::new(
+            config.rocksdb.data_path.as_str(),
+            config.rocksdb.max_open_files.unwrap(),
+            column_family_list(),
+        ));
+        let queue_storage = AmqpQueueStorage::new(rs);
+        let cluster_name = "test_cluster".to_string();
+
+        let mut queue = AmqpQueue {
+            queue_name: "orders".to_string(),
+            durable: true,
+            ..Default::default()
+        };
+        queue.add_binding(AmqpBinding {
+            exchange_name: "amq.topic".to_string(),
+            routing_key: "order.*".to_string(),
+            ..Default::default()
+        });
+        queue_storage.save(&cluster_name, queue.clone()).unwrap();
+
+        let mut queue2 = queue.clone();
+        queue2.queue_name = "invoices".to_string();
+        queue_storage.save(&cluster_name, queue2).unwrap();
+
+        assert_eq!(queue_storage.list(&cluster_name).unwrap().len(), 2);
+
+        let res = queue_storage.get(&cluster_name, "orders").unwrap().unwrap();
+        assert_eq!(res, queue);
+
+        queue_storage.delete(&cluster_name, "orders").unwrap();
+        assert!(queue_storage
+            .get(&cluster_name, "orders")
+            .unwrap()
+            .is_none());
+        assert_eq!(queue_storage.list(&cluster_name).unwrap().len(), 1);
+
+        remove_dir_all(config.rocksdb.data_path).unwrap();
+    }
+}
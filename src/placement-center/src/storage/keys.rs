@@ -221,3 +221,35 @@ pub fn storage_key_mqtt_blacklist(
 pub fn storage_key_mqtt_blacklist_prefix(cluster_name: &str) -> String {
     format!("/mqtt/blacklist/{}/", cluster_name)
 }
+
+pub fn storage_key_mqtt_bridge(cluster_name: &str, bridge_name: &str) -> String {
+    format!("/mqtt/bridge/{}/{}", cluster_name, bridge_name)
+}
+
+pub fn storage_key_mqtt_bridge_prefix(cluster_name: &str) -> String {
+    format!("/mqtt/bridge/{}/", cluster_name)
+}
+
+pub fn storage_key_mqtt_rule(cluster_name: &str, rule_name: &str) -> String {
+    format!("/mqtt/rule/{}/{}", cluster_name, rule_name)
+}
+
+pub fn storage_key_mqtt_rule_prefix(cluster_name: &str) -> String {
+    format!("/mqtt/rule/{}/", cluster_name)
+}
+
+pub fn storage_key_amqp_exchange(cluster_name: &str, exchange_name: &str) -> String {
+    format!("/amqp/exchange/{}/{}", cluster_name, exchange_name)
+}
+
+pub fn storage_key_amqp_exchange_prefix(cluster_name: &str) -> String {
+    format!("/amqp/exchange/{}/", cluster_name)
+}
+
+pub fn storage_key_amqp_queue(cluster_name: &str, queue_name: &str) -> String {
+    format!("/amqp/queue/{}/{}", cluster_name, queue_name)
+}
+
+pub fn storage_key_amqp_queue_prefix(cluster_name: &str) -> String {
+    format!("/amqp/queue/{}/", cluster_name)
+}
@@ -28,6 +28,7 @@
  * limitations under the License.
  */
 
+pub mod amqp;
 pub mod engine;
 pub mod journal;
 pub mod keys;
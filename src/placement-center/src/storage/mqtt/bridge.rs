@@ -0,0 +1,130 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::error::common::CommonError;
+use metadata_struct::mqtt::bridge::MqttBridgeRule;
+
+use crate::storage::engine::{
+    engine_delete_by_cluster, engine_get_by_cluster, engine_prefix_list_by_cluster,
+    engine_save_by_cluster,
+};
+use crate::storage::keys::{storage_key_mqtt_bridge, storage_key_mqtt_bridge_prefix};
+use crate::storage::rocksdb::RocksDBEngine;
+
+pub struct MqttBridgeStorage {
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+}
+
+impl MqttBridgeStorage {
+    pub fn new(rocksdb_engine_handler: Arc<RocksDBEngine>) -> Self {
+        MqttBridgeStorage {
+            rocksdb_engine_handler,
+        }
+    }
+
+    pub fn save(&self, cluster_name: &str, bridge: MqttBridgeRule) -> Result<(), CommonError> {
+        let key = storage_key_mqtt_bridge(cluster_name, &bridge.name);
+        engine_save_by_cluster(self.rocksdb_engine_handler.clone(), key, bridge)
+    }
+
+    pub fn list(&self, cluster_name: &str) -> Result<Vec<MqttBridgeRule>, CommonError> {
+        let prefix_key = storage_key_mqtt_bridge_prefix(cluster_name);
+        let data = engine_prefix_list_by_cluster(self.rocksdb_engine_handler.clone(), prefix_key)?;
+        let mut results = Vec::new();
+        for raw in data {
+            results.push(serde_json::from_slice::<MqttBridgeRule>(&raw.data)?);
+        }
+        Ok(results)
+    }
+
+    pub fn get(
+        &self,
+        cluster_name: &str,
+        bridge_name: &str,
+    ) -> Result<Option<MqttBridgeRule>, CommonError> {
+        let key = storage_key_mqtt_bridge(cluster_name, bridge_name);
+        if let Some(data) = engine_get_by_cluster(self.rocksdb_engine_handler.clone(), key)? {
+            return Ok(Some(serde_json::from_slice::<MqttBridgeRule>(&data.data)?));
+        }
+        Ok(None)
+    }
+
+    pub fn delete(&self, cluster_name: &str, bridge_name: &str) -> Result<(), CommonError> {
+        let key = storage_key_mqtt_bridge(cluster_name, bridge_name);
+        engine_delete_by_cluster(self.rocksdb_engine_handler.clone(), key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_dir_all;
+    use std::sync::Arc;
+
+    use common_base::config::placement_center::placement_center_test_conf;
+    use metadata_struct::mqtt::bridge::{MqttBridgeRule, MqttBridgeStatus, MqttBridgeType};
+
+    use crate::storage::mqtt::bridge::MqttBridgeStorage;
+    use crate::storage::rocksdb::{column_family_list, RocksDBEngine};
+
+    #[tokio::test]
+    async fn bridge_storage_test() {
+        let config = placement_center_test_conf();
+
+        let rs = Arc::new(RocksDBEngine::new(
+            config.rocksdb.data_path.as_str(),
+            config.rocksdb.max_open_files.unwrap(),
+            column_family_list(),
+        ));
+        let bridge_storage = MqttBridgeStorage::new(rs);
+        let cluster_name = "test_cluster".to_string();
+
+        let bridge = MqttBridgeRule {
+            name: "sensor_to_redis".to_string(),
+            bridge_type: MqttBridgeType::Redis,
+            topic_filters: vec!["/sensor/+".to_string()],
+            batch_size: 100,
+            config: r#"{"addr":"127.0.0.1:6379","key_template":"${topic}"}"#.to_string(),
+            status: MqttBridgeStatus::Running,
+            create_time: 1,
+        };
+        bridge_storage.save(&cluster_name, bridge.clone()).unwrap();
+
+        let mut bridge2 = bridge.clone();
+        bridge2.name = "sensor_to_kafka".to_string();
+        bridge2.bridge_type = MqttBridgeType::Kafka;
+        bridge_storage.save(&cluster_name, bridge2).unwrap();
+
+        let res = bridge_storage.list(&cluster_name).unwrap();
+        assert_eq!(res.len(), 2);
+
+        let res = bridge_storage
+            .get(&cluster_name, "sensor_to_redis")
+            .unwrap()
+            .unwrap();
+        assert_eq!(res, bridge);
+
+        bridge_storage
+            .delete(&cluster_name, "sensor_to_redis")
+            .unwrap();
+        assert!(bridge_storage
+            .get(&cluster_name, "sensor_to_redis")
+            .unwrap()
+            .is_none());
+        assert_eq!(bridge_storage.list(&cluster_name).unwrap().len(), 1);
+
+        remove_dir_all(config.rocksdb.data_path).unwrap();
+    }
+}
@@ -26,7 +26,9 @@
 
 pub mod acl;
 pub mod blacklist;
+pub mod bridge;
 pub mod lastwill;
+pub mod rule;
 pub mod session;
 pub mod topic;
 pub mod user;
@@ -52,4 +52,118 @@ impl KvStorage {
     pub fn exists(&self, key: String) -> Result<bool, CommonError> {
         engine_exists_by_cluster(self.rocksdb_engine_handler.clone(), key)
     }
+
+    // Returns false without writing anything when check_key does not hold check_value.
+    // check_key is written last, so replaying a batch that was cut short by a crash
+    // applies it again, and replaying a complete one fails the check.
+    pub fn batch_set(
+        &self,
+        pairs: Vec<(String, String)>,
+        delete_keys: Vec<String>,
+        check_key: &str,
+        check_value: &str,
+    ) -> Result<bool, CommonError> {
+        if !check_key.is_empty() {
+            let current = self.get(check_key.to_string())?.unwrap_or_default();
+            if current != check_value {
+                return Ok(false);
+            }
+        }
+
+        let mut check_pair = None;
+        for (key, value) in pairs {
+            if key == check_key {
+                check_pair = Some(value);
+                continue;
+            }
+            self.set(key, value)?;
+        }
+        for key in delete_keys {
+            self.delete(key)?;
+        }
+        if let Some(value) = check_pair {
+            self.set(check_key.to_string(), value)?;
+        }
+        Ok(true)
+    }
+
+    pub fn batch_get(&self, keys: Vec<String>) -> Result<Vec<(String, String)>, CommonError> {
+        let mut results = Vec::with_capacity(keys.len());
+        for key in keys {
+            if let Some(value) = self.get(key.clone())? {
+                results.push((key, value));
+            }
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_dir_all;
+    use std::sync::Arc;
+
+    use common_base::config::placement_center::placement_center_test_conf;
+
+    use crate::storage::placement::kv::KvStorage;
+    use crate::storage::rocksdb::{column_family_list, RocksDBEngine};
+
+    #[tokio::test]
+    async fn batch_set_test() {
+        let config = placement_center_test_conf();
+
+        let rs = Arc::new(RocksDBEngine::new(
+            config.rocksdb.data_path.as_str(),
+            config.rocksdb.max_open_files.unwrap(),
+            column_family_list(),
+        ));
+        let kv_storage = KvStorage::new(rs);
+        let pair = |key: &str, value: &str| (key.to_string(), value.to_string());
+
+        // the meta key must not exist yet
+        assert!(kv_storage
+            .batch_set(
+                vec![
+                    pair("/kv/r/0", "a"),
+                    pair("/kv/r/1", "b"),
+                    pair("/kv/meta", "2")
+                ],
+                Vec::new(),
+                "/kv/meta",
+                "",
+            )
+            .unwrap());
+        // a writer that read an outdated meta changes nothing
+        assert!(!kv_storage
+            .batch_set(
+                vec![pair("/kv/r/0", "x"), pair("/kv/meta", "1")],
+                Vec::new(),
+                "/kv/meta",
+                "",
+            )
+            .unwrap());
+        assert!(kv_storage
+            .batch_set(
+                vec![pair("/kv/r/2", "c"), pair("/kv/meta", "3")],
+                vec!["/kv/r/0".to_string()],
+                "/kv/meta",
+                "2",
+            )
+            .unwrap());
+
+        let keys = ["/kv/r/0", "/kv/r/1", "/kv/r/2", "/kv/meta"]
+            .iter()
+            .map(|key| key.to_string())
+            .collect();
+        assert_eq!(
+            kv_storage.batch_get(keys).unwrap(),
+            vec![
+                pair("/kv/r/1", "b"),
+                pair("/kv/r/2", "c"),
+                pair("/kv/meta", "3")
+            ]
+        );
+
+        remove_dir_all(config.rocksdb.data_path).unwrap();
+    }
 }
@@ -11,3 +11,190 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use tokio_util::codec;
+
+use super::common::*;
+use super::types::{read_basic_properties, read_u16, read_u64, write_basic_properties};
+
+#[derive(Debug, Clone)]
+pub struct AmqpCodec {
+    frame_max: u32,
+}
+
+impl Default for AmqpCodec {
+    fn default() -> Self {
+        AmqpCodec::new()
+    }
+}
+
+impl AmqpCodec {
+    pub fn new() -> AmqpCodec {
+        AmqpCodec {
+            frame_max: DEFAULT_FRAME_MAX,
+        }
+    }
+
+    /// Applies the frame-max agreed in connection.tune/tune-ok; 0 means no limit
+    pub fn set_frame_max(&mut self, frame_max: u32) {
+        self.frame_max = frame_max;
+    }
+
+    pub fn frame_max(&self) -> u32 {
+        self.frame_max
+    }
+
+    fn check_frame_size(&self, frame_size: usize) -> Result<(), Error> {
+        if self.frame_max != 0 && frame_size > self.frame_max as usize {
+            return Err(Error::FrameTooLarge(frame_size, self.frame_max));
+        }
+        Ok(())
+    }
+
+    fn decode_protocol_header(src: &mut BytesMut) -> Result<Option<AmqpFrame>, Error> {
+        // only "AMQP" %d0 is fixed, the version is checked by the broker so it can
+        // answer an unsupported one with its own header
+        let len = PROTOCOL_HEADER.len();
+        let prefix = src.len().min(5);
+        if src[..prefix] != PROTOCOL_HEADER[..prefix] {
+            return Err(Error::InvalidProtocolHeader(src[..prefix].to_vec()));
+        }
+        if src.len() < len {
+            return Ok(None);
+        }
+        let header = src.split_to(len);
+        Ok(Some(AmqpFrame::ProtocolHeader(ProtocolHeader {
+            major: header[5],
+            minor: header[6],
+            revision: header[7],
+        })))
+    }
+
+    fn decode_payload(
+        frame_type: u8,
+        channel: u16,
+        mut payload: Bytes,
+    ) -> Result<AmqpFrame, Error> {
+        let frame = match frame_type {
+            FRAME_METHOD => AmqpFrame::Method(channel, Method::read(&mut payload)?),
+            FRAME_HEADER => {
+                let class_id = read_u16(&mut payload)?;
+                // weight, unused and always 0
+                read_u16(&mut payload)?;
+                let body_size = read_u64(&mut payload)?;
+                let properties = read_basic_properties(&mut payload)?;
+                AmqpFrame::Header(
+                    channel,
+                    ContentHeader {
+                        class_id,
+                        body_size,
+                        properties,
+                    },
+                )
+            }
+            FRAME_BODY => return Ok(AmqpFrame::Body(channel, payload)),
+            FRAME_HEARTBEAT => {
+                if channel != 0 {
+                    return Err(Error::InvalidHeartbeatChannel(channel));
+                }
+                AmqpFrame::Heartbeat
+            }
+            _ => return Err(Error::InvalidFrameType(frame_type)),
+        };
+        if payload.has_remaining() {
+            return Err(Error::TrailingBytes(payload.remaining()));
+        }
+        Ok(frame)
+    }
+}
+
+impl codec::Decoder for AmqpCodec {
+    type Item = AmqpFrame;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.is_empty() {
+            return Ok(None);
+        }
+        if src[0] == b'A' {
+            return AmqpCodec::decode_protocol_header(src);
+        }
+        let frame_type = src[0];
+        if !matches!(
+            frame_type,
+            FRAME_METHOD | FRAME_HEADER | FRAME_BODY | FRAME_HEARTBEAT
+        ) {
+            return Err(Error::InvalidFrameType(frame_type));
+        }
+        if src.len() < FRAME_HEADER_SIZE {
+            return Ok(None);
+        }
+
+        let channel = u16::from_be_bytes([src[1], src[2]]);
+        let size = u32::from_be_bytes([src[3], src[4], src[5], src[6]]) as usize;
+        let frame_size = size + FRAME_OVERHEAD;
+        self.check_frame_size(frame_size)?;
+        if src.len() < frame_size {
+            src.reserve(frame_size - src.len());
+            return Ok(None);
+        }
+
+        let frame_end = src[frame_size - 1];
+        if frame_end != FRAME_END {
+            return Err(Error::InvalidFrameEnd(frame_end));
+        }
+        src.advance(FRAME_HEADER_SIZE);
+        let payload = src.split_to(size).freeze();
+        src.advance(1);
+        AmqpCodec::decode_payload(frame_type, channel, payload).map(Some)
+    }
+}
+
+impl codec::Encoder<AmqpFrame> for AmqpCodec {
+    type Error = Error;
+
+    fn encode(&mut self, item: AmqpFrame, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let (frame_type, channel) = match &item {
+            AmqpFrame::ProtocolHeader(header) => {
+                dst.put_slice(b"AMQP\x00");
+                dst.put_u8(header.major);
+                dst.put_u8(header.minor);
+                dst.put_u8(header.revision);
+                return Ok(());
+            }
+            AmqpFrame::Method(channel, _) => (FRAME_METHOD, *channel),
+            AmqpFrame::Header(channel, _) => (FRAME_HEADER, *channel),
+            AmqpFrame::Body(channel, _) => (FRAME_BODY, *channel),
+            AmqpFrame::Heartbeat => (FRAME_HEARTBEAT, 0),
+        };
+
+        let start = dst.len();
+        dst.put_u8(frame_type);
+        dst.put_u16(channel);
+        dst.put_u32(0);
+        let result = match item {
+            AmqpFrame::Method(_, method) => method.write(dst),
+            AmqpFrame::Header(_, header) => {
+                dst.put_u16(header.class_id);
+                dst.put_u16(0);
+                dst.put_u64(header.body_size);
+                write_basic_properties(dst, &header.properties)
+            }
+            AmqpFrame::Body(_, body) => {
+                dst.put_slice(&body);
+                Ok(())
+            }
+            _ => Ok(()),
+        };
+        let size = dst.len() - start - FRAME_HEADER_SIZE;
+        if let Err(e) = result.and_then(|_| self.check_frame_size(size + FRAME_OVERHEAD)) {
+            // drop the partially written frame so the buffer stays aligned
+            dst.truncate(start);
+            return Err(e);
+        }
+        dst[start + 3..start + FRAME_HEADER_SIZE].copy_from_slice(&(size as u32).to_be_bytes());
+        dst.put_u8(FRAME_END);
+        Ok(())
+    }
+}
@@ -0,0 +1,474 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::string::FromUtf8Error;
+
+use bytes::Bytes;
+
+/// Frame type octets defined by AMQP 0-9-1
+pub const FRAME_METHOD: u8 = 1;
+pub const FRAME_HEADER: u8 = 2;
+pub const FRAME_BODY: u8 = 3;
+pub const FRAME_HEARTBEAT: u8 = 8;
+pub const FRAME_END: u8 = 0xCE;
+
+/// type(1) + channel(2) + size(4)
+pub const FRAME_HEADER_SIZE: usize = 7;
+/// Frame header plus the trailing frame-end octet
+pub const FRAME_OVERHEAD: usize = FRAME_HEADER_SIZE + 1;
+/// Smallest frame-max a peer is allowed to negotiate
+pub const FRAME_MIN_SIZE: u32 = 4096;
+pub const DEFAULT_FRAME_MAX: u32 = 131072;
+
+pub const PROTOCOL_HEADER: &[u8; 8] = b"AMQP\x00\x00\x09\x01";
+
+pub const CLASS_CONNECTION: u16 = 10;
+pub const CLASS_CHANNEL: u16 = 20;
+pub const CLASS_EXCHANGE: u16 = 40;
+pub const CLASS_QUEUE: u16 = 50;
+pub const CLASS_BASIC: u16 = 60;
+pub const CLASS_CONFIRM: u16 = 85;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AmqpFrame {
+    ProtocolHeader(ProtocolHeader),
+    Method(u16, Method),
+    Header(u16, ContentHeader),
+    Body(u16, Bytes),
+    Heartbeat,
+}
+
+impl AmqpFrame {
+    pub fn channel(&self) -> u16 {
+        match self {
+            AmqpFrame::Method(channel, _)
+            | AmqpFrame::Header(channel, _)
+            | AmqpFrame::Body(channel, _) => *channel,
+            AmqpFrame::ProtocolHeader(_) | AmqpFrame::Heartbeat => 0,
+        }
+    }
+}
+
+/// "AMQP" %d0 major minor revision, sent by the client before any frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolHeader {
+    pub major: u8,
+    pub minor: u8,
+    pub revision: u8,
+}
+
+impl Default for ProtocolHeader {
+    fn default() -> Self {
+        ProtocolHeader {
+            major: 0,
+            minor: 9,
+            revision: 1,
+        }
+    }
+}
+
+impl ProtocolHeader {
+    pub fn is_supported(&self) -> bool {
+        *self == ProtocolHeader::default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ContentHeader {
+    pub class_id: u16,
+    pub body_size: u64,
+    pub properties: BasicProperties,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicProperties {
+    pub content_type: Option<String>,
+    pub content_encoding: Option<String>,
+    pub headers: Option<FieldTable>,
+    pub delivery_mode: Option<u8>,
+    pub priority: Option<u8>,
+    pub correlation_id: Option<String>,
+    pub reply_to: Option<String>,
+    pub expiration: Option<String>,
+    pub message_id: Option<String>,
+    pub timestamp: Option<u64>,
+    pub kind: Option<String>,
+    pub user_id: Option<String>,
+    pub app_id: Option<String>,
+    pub cluster_id: Option<String>,
+}
+
+pub type FieldTable = BTreeMap<String, FieldValue>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecimalValue {
+    pub scale: u8,
+    pub value: u32,
+}
+
+/// Field value types, using the tags RabbitMQ and most clients agree on
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Boolean(bool),
+    ShortShortInt(i8),
+    ShortShortUInt(u8),
+    ShortInt(i16),
+    ShortUInt(u16),
+    LongInt(i32),
+    LongUInt(u32),
+    LongLongInt(i64),
+    Float(f32),
+    Double(f64),
+    Decimal(DecimalValue),
+    LongString(Bytes),
+    FieldArray(Vec<FieldValue>),
+    Timestamp(u64),
+    FieldTable(FieldTable),
+    Void,
+    ByteArray(Bytes),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Method {
+    ConnectionStart(ConnectionStart),
+    ConnectionStartOk(ConnectionStartOk),
+    ConnectionSecure(ConnectionSecure),
+    ConnectionSecureOk(ConnectionSecureOk),
+    ConnectionTune(ConnectionTune),
+    ConnectionTuneOk(ConnectionTune),
+    ConnectionOpen(ConnectionOpen),
+    ConnectionOpenOk,
+    ConnectionClose(Close),
+    ConnectionCloseOk,
+    ConnectionBlocked(ConnectionBlocked),
+    ConnectionUnblocked,
+
+    ChannelOpen,
+    ChannelOpenOk,
+    ChannelFlow(ChannelFlow),
+    ChannelFlowOk(ChannelFlow),
+    ChannelClose(Close),
+    ChannelCloseOk,
+
+    ExchangeDeclare(ExchangeDeclare),
+    ExchangeDeclareOk,
+    ExchangeDelete(ExchangeDelete),
+    ExchangeDeleteOk,
+    ExchangeBind(ExchangeBind),
+    ExchangeBindOk,
+    ExchangeUnbind(ExchangeBind),
+    ExchangeUnbindOk,
+
+    QueueDeclare(QueueDeclare),
+    QueueDeclareOk(QueueDeclareOk),
+    QueueBind(QueueBind),
+    QueueBindOk,
+    QueuePurge(QueuePurge),
+    QueuePurgeOk(MessageCount),
+    QueueDelete(QueueDelete),
+    QueueDeleteOk(MessageCount),
+    QueueUnbind(QueueUnbind),
+    QueueUnbindOk,
+
+    BasicQos(BasicQos),
+    BasicQosOk,
+    BasicConsume(BasicConsume),
+    BasicConsumeOk(ConsumerTag),
+    BasicCancel(BasicCancel),
+    BasicCancelOk(ConsumerTag),
+    BasicPublish(BasicPublish),
+    BasicReturn(BasicReturn),
+    BasicDeliver(BasicDeliver),
+    BasicGet(BasicGet),
+    BasicGetOk(BasicGetOk),
+    BasicGetEmpty,
+    BasicAck(BasicAck),
+    BasicReject(BasicReject),
+    BasicRecoverAsync(BasicRecover),
+    BasicRecover(BasicRecover),
+    BasicRecoverOk,
+    BasicNack(BasicNack),
+
+    ConfirmSelect(ConfirmSelect),
+    ConfirmSelectOk,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ConnectionStart {
+    pub version_major: u8,
+    pub version_minor: u8,
+    pub server_properties: FieldTable,
+    pub mechanisms: Bytes,
+    pub locales: Bytes,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ConnectionStartOk {
+    pub client_properties: FieldTable,
+    pub mechanism: String,
+    pub response: Bytes,
+    pub locale: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ConnectionSecure {
+    pub challenge: Bytes,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ConnectionSecureOk {
+    pub response: Bytes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ConnectionTune {
+    pub channel_max: u16,
+    pub frame_max: u32,
+    pub heartbeat: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ConnectionOpen {
+    pub virtual_host: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ConnectionBlocked {
+    pub reason: String,
+}
+
+/// Shared by connection.close and channel.close
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Close {
+    pub reply_code: u16,
+    pub reply_text: String,
+    pub class_id: u16,
+    pub method_id: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChannelFlow {
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExchangeDeclare {
+    pub exchange: String,
+    pub kind: String,
+    pub passive: bool,
+    pub durable: bool,
+    pub auto_delete: bool,
+    pub internal: bool,
+    pub no_wait: bool,
+    pub arguments: FieldTable,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExchangeDelete {
+    pub exchange: String,
+    pub if_unused: bool,
+    pub no_wait: bool,
+}
+
+/// Shared by exchange.bind and exchange.unbind
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExchangeBind {
+    pub destination: String,
+    pub source: String,
+    pub routing_key: String,
+    pub no_wait: bool,
+    pub arguments: FieldTable,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QueueDeclare {
+    pub queue: String,
+    pub passive: bool,
+    pub durable: bool,
+    pub exclusive: bool,
+    pub auto_delete: bool,
+    pub no_wait: bool,
+    pub arguments: FieldTable,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QueueDeclareOk {
+    pub queue: String,
+    pub message_count: u32,
+    pub consumer_count: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QueueBind {
+    pub queue: String,
+    pub exchange: String,
+    pub routing_key: String,
+    pub no_wait: bool,
+    pub arguments: FieldTable,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QueuePurge {
+    pub queue: String,
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MessageCount {
+    pub message_count: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QueueDelete {
+    pub queue: String,
+    pub if_unused: bool,
+    pub if_empty: bool,
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QueueUnbind {
+    pub queue: String,
+    pub exchange: String,
+    pub routing_key: String,
+    pub arguments: FieldTable,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BasicQos {
+    pub prefetch_size: u32,
+    pub prefetch_count: u16,
+    pub global: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicConsume {
+    pub queue: String,
+    pub consumer_tag: String,
+    pub no_local: bool,
+    pub no_ack: bool,
+    pub exclusive: bool,
+    pub no_wait: bool,
+    pub arguments: FieldTable,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ConsumerTag {
+    pub consumer_tag: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicCancel {
+    pub consumer_tag: String,
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicPublish {
+    pub exchange: String,
+    pub routing_key: String,
+    pub mandatory: bool,
+    pub immediate: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicReturn {
+    pub reply_code: u16,
+    pub reply_text: String,
+    pub exchange: String,
+    pub routing_key: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicDeliver {
+    pub consumer_tag: String,
+    pub delivery_tag: u64,
+    pub redelivered: bool,
+    pub exchange: String,
+    pub routing_key: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicGet {
+    pub queue: String,
+    pub no_ack: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicGetOk {
+    pub delivery_tag: u64,
+    pub redelivered: bool,
+    pub exchange: String,
+    pub routing_key: String,
+    pub message_count: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BasicAck {
+    pub delivery_tag: u64,
+    pub multiple: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BasicReject {
+    pub delivery_tag: u64,
+    pub requeue: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BasicRecover {
+    pub requeue: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BasicNack {
+    pub delivery_tag: u64,
+    pub multiple: bool,
+    pub requeue: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ConfirmSelect {
+    pub no_wait: bool,
+}
+
+/// Error during serialization and deserialization
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Invalid protocol header {0:?}")]
+    InvalidProtocolHeader(Vec<u8>),
+    #[error("Invalid frame type = {0}")]
+    InvalidFrameType(u8),
+    #[error("Invalid frame end octet = {0}")]
+    InvalidFrameEnd(u8),
+    #[error("Frame size {0} exceeds the negotiated frame max {1}")]
+    FrameTooLarge(usize, u32),
+    #[error("Unknown method, class id = {0}, method id = {1}")]
+    UnknownMethod(u16, u16),
+    #[error("Invalid field value type = {0}")]
+    InvalidFieldType(u8),
+    #[error("Heartbeat frame must be sent on channel 0, got channel {0}")]
+    InvalidHeartbeatChannel(u16),
+    #[error("Short string length {0} exceeds 255 bytes")]
+    ShortStringTooLong(usize),
+    #[error("Short string is not valid utf-8")]
+    StringNotUtf8(#[from] FromUtf8Error),
+    #[error("Frame payload is malformed, {0} more bytes required")]
+    InsufficientBytes(usize),
+    #[error("Frame payload has {0} trailing bytes")]
+    TrailingBytes(usize),
+    #[error("I/O: {0}")]
+    Io(#[from] std::io::Error),
+}
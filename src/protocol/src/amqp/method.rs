@@ -0,0 +1,651 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+use super::common::*;
+use super::types::*;
+
+impl Method {
+    /// (class id, method id) as assigned by the AMQP 0-9-1 specification
+    pub fn id(&self) -> (u16, u16) {
+        match self {
+            Method::ConnectionStart(_) => (CLASS_CONNECTION, 10),
+            Method::ConnectionStartOk(_) => (CLASS_CONNECTION, 11),
+            Method::ConnectionSecure(_) => (CLASS_CONNECTION, 20),
+            Method::ConnectionSecureOk(_) => (CLASS_CONNECTION, 21),
+            Method::ConnectionTune(_) => (CLASS_CONNECTION, 30),
+            Method::ConnectionTuneOk(_) => (CLASS_CONNECTION, 31),
+            Method::ConnectionOpen(_) => (CLASS_CONNECTION, 40),
+            Method::ConnectionOpenOk => (CLASS_CONNECTION, 41),
+            Method::ConnectionClose(_) => (CLASS_CONNECTION, 50),
+            Method::ConnectionCloseOk => (CLASS_CONNECTION, 51),
+            Method::ConnectionBlocked(_) => (CLASS_CONNECTION, 60),
+            Method::ConnectionUnblocked => (CLASS_CONNECTION, 61),
+
+            Method::ChannelOpen => (CLASS_CHANNEL, 10),
+            Method::ChannelOpenOk => (CLASS_CHANNEL, 11),
+            Method::ChannelFlow(_) => (CLASS_CHANNEL, 20),
+            Method::ChannelFlowOk(_) => (CLASS_CHANNEL, 21),
+            Method::ChannelClose(_) => (CLASS_CHANNEL, 40),
+            Method::ChannelCloseOk => (CLASS_CHANNEL, 41),
+
+            Method::ExchangeDeclare(_) => (CLASS_EXCHANGE, 10),
+            Method::ExchangeDeclareOk => (CLASS_EXCHANGE, 11),
+            Method::ExchangeDelete(_) => (CLASS_EXCHANGE, 20),
+            Method::ExchangeDeleteOk => (CLASS_EXCHANGE, 21),
+            Method::ExchangeBind(_) => (CLASS_EXCHANGE, 30),
+            Method::ExchangeBindOk => (CLASS_EXCHANGE, 31),
+            Method::ExchangeUnbind(_) => (CLASS_EXCHANGE, 40),
+            Method::ExchangeUnbindOk => (CLASS_EXCHANGE, 51),
+
+            Method::QueueDeclare(_) => (CLASS_QUEUE, 10),
+            Method::QueueDeclareOk(_) => (CLASS_QUEUE, 11),
+            Method::QueueBind(_) => (CLASS_QUEUE, 20),
+            Method::QueueBindOk => (CLASS_QUEUE, 21),
+            Method::QueuePurge(_) => (CLASS_QUEUE, 30),
+            Method::QueuePurgeOk(_) => (CLASS_QUEUE, 31),
+            Method::QueueDelete(_) => (CLASS_QUEUE, 40),
+            Method::QueueDeleteOk(_) => (CLASS_QUEUE, 41),
+            Method::QueueUnbind(_) => (CLASS_QUEUE, 50),
+            Method::QueueUnbindOk => (CLASS_QUEUE, 51),
+
+            Method::BasicQos(_) => (CLASS_BASIC, 10),
+            Method::BasicQosOk => (CLASS_BASIC, 11),
+            Method::BasicConsume(_) => (CLASS_BASIC, 20),
+            Method::BasicConsumeOk(_) => (CLASS_BASIC, 21),
+            Method::BasicCancel(_) => (CLASS_BASIC, 30),
+            Method::BasicCancelOk(_) => (CLASS_BASIC, 31),
+            Method::BasicPublish(_) => (CLASS_BASIC, 40),
+            Method::BasicReturn(_) => (CLASS_BASIC, 50),
+            Method::BasicDeliver(_) => (CLASS_BASIC, 60),
+            Method::BasicGet(_) => (CLASS_BASIC, 70),
+            Method::BasicGetOk(_) => (CLASS_BASIC, 71),
+            Method::BasicGetEmpty => (CLASS_BASIC, 72),
+            Method::BasicAck(_) => (CLASS_BASIC, 80),
+            Method::BasicReject(_) => (CLASS_BASIC, 90),
+            Method::BasicRecoverAsync(_) => (CLASS_BASIC, 100),
+            Method::BasicRecover(_) => (CLASS_BASIC, 110),
+            Method::BasicRecoverOk => (CLASS_BASIC, 111),
+            Method::BasicNack(_) => (CLASS_BASIC, 120),
+
+            Method::ConfirmSelect(_) => (CLASS_CONFIRM, 10),
+            Method::ConfirmSelectOk => (CLASS_CONFIRM, 11),
+        }
+    }
+
+    /// Whether a content header and body frames follow this method
+    pub fn has_content(&self) -> bool {
+        matches!(
+            self,
+            Method::BasicPublish(_)
+                | Method::BasicReturn(_)
+                | Method::BasicDeliver(_)
+                | Method::BasicGetOk(_)
+        )
+    }
+
+    pub(crate) fn read(buf: &mut Bytes) -> Result<Method, Error> {
+        let class_id = read_u16(buf)?;
+        let method_id = read_u16(buf)?;
+        let method = match (class_id, method_id) {
+            (CLASS_CONNECTION, 10) => Method::ConnectionStart(ConnectionStart {
+                version_major: read_u8(buf)?,
+                version_minor: read_u8(buf)?,
+                server_properties: read_field_table(buf)?,
+                mechanisms: read_long_string(buf)?,
+                locales: read_long_string(buf)?,
+            }),
+            (CLASS_CONNECTION, 11) => Method::ConnectionStartOk(ConnectionStartOk {
+                client_properties: read_field_table(buf)?,
+                mechanism: read_short_string(buf)?,
+                response: read_long_string(buf)?,
+                locale: read_short_string(buf)?,
+            }),
+            (CLASS_CONNECTION, 20) => Method::ConnectionSecure(ConnectionSecure {
+                challenge: read_long_string(buf)?,
+            }),
+            (CLASS_CONNECTION, 21) => Method::ConnectionSecureOk(ConnectionSecureOk {
+                response: read_long_string(buf)?,
+            }),
+            (CLASS_CONNECTION, 30) => Method::ConnectionTune(read_tune(buf)?),
+            (CLASS_CONNECTION, 31) => Method::ConnectionTuneOk(read_tune(buf)?),
+            (CLASS_CONNECTION, 40) => {
+                let virtual_host = read_short_string(buf)?;
+                // reserved capabilities and insist bit
+                read_short_string(buf)?;
+                read_u8(buf)?;
+                Method::ConnectionOpen(ConnectionOpen { virtual_host })
+            }
+            (CLASS_CONNECTION, 41) => {
+                read_short_string(buf)?;
+                Method::ConnectionOpenOk
+            }
+            (CLASS_CONNECTION, 50) => Method::ConnectionClose(read_close(buf)?),
+            (CLASS_CONNECTION, 51) => Method::ConnectionCloseOk,
+            (CLASS_CONNECTION, 60) => Method::ConnectionBlocked(ConnectionBlocked {
+                reason: read_short_string(buf)?,
+            }),
+            (CLASS_CONNECTION, 61) => Method::ConnectionUnblocked,
+
+            (CLASS_CHANNEL, 10) => {
+                read_short_string(buf)?;
+                Method::ChannelOpen
+            }
+            (CLASS_CHANNEL, 11) => {
+                read_long_string(buf)?;
+                Method::ChannelOpenOk
+            }
+            (CLASS_CHANNEL, 20) => {
+                let [active] = read_bits(buf)?;
+                Method::ChannelFlow(ChannelFlow { active })
+            }
+            (CLASS_CHANNEL, 21) => {
+                let [active] = read_bits(buf)?;
+                Method::ChannelFlowOk(ChannelFlow { active })
+            }
+            (CLASS_CHANNEL, 40) => Method::ChannelClose(read_close(buf)?),
+            (CLASS_CHANNEL, 41) => Method::ChannelCloseOk,
+
+            (CLASS_EXCHANGE, 10) => {
+                read_u16(buf)?;
+                let exchange = read_short_string(buf)?;
+                let kind = read_short_string(buf)?;
+                let [passive, durable, auto_delete, internal, no_wait] = read_bits(buf)?;
+                Method::ExchangeDeclare(ExchangeDeclare {
+                    exchange,
+                    kind,
+                    passive,
+                    durable,
+                    auto_delete,
+                    internal,
+                    no_wait,
+                    arguments: read_field_table(buf)?,
+                })
+            }
+            (CLASS_EXCHANGE, 11) => Method::ExchangeDeclareOk,
+            (CLASS_EXCHANGE, 20) => {
+                read_u16(buf)?;
+                let exchange = read_short_string(buf)?;
+                let [if_unused, no_wait] = read_bits(buf)?;
+                Method::ExchangeDelete(ExchangeDelete {
+                    exchange,
+                    if_unused,
+                    no_wait,
+                })
+            }
+            (CLASS_EXCHANGE, 21) => Method::ExchangeDeleteOk,
+            (CLASS_EXCHANGE, 30) => Method::ExchangeBind(read_exchange_bind(buf)?),
+            (CLASS_EXCHANGE, 31) => Method::ExchangeBindOk,
+            (CLASS_EXCHANGE, 40) => Method::ExchangeUnbind(read_exchange_bind(buf)?),
+            (CLASS_EXCHANGE, 51) => Method::ExchangeUnbindOk,
+
+            (CLASS_QUEUE, 10) => {
+                read_u16(buf)?;
+                let queue = read_short_string(buf)?;
+                let [passive, durable, exclusive, auto_delete, no_wait] = read_bits(buf)?;
+                Method::QueueDeclare(QueueDeclare {
+                    queue,
+                    passive,
+                    durable,
+                    exclusive,
+                    auto_delete,
+                    no_wait,
+                    arguments: read_field_table(buf)?,
+                })
+            }
+            (CLASS_QUEUE, 11) => Method::QueueDeclareOk(QueueDeclareOk {
+                queue: read_short_string(buf)?,
+                message_count: read_u32(buf)?,
+                consumer_count: read_u32(buf)?,
+            }),
+            (CLASS_QUEUE, 20) => {
+                read_u16(buf)?;
+                let queue = read_short_string(buf)?;
+                let exchange = read_short_string(buf)?;
+                let routing_key = read_short_string(buf)?;
+                let [no_wait] = read_bits(buf)?;
+                Method::QueueBind(QueueBind {
+                    queue,
+                    exchange,
+                    routing_key,
+                    no_wait,
+                    arguments: read_field_table(buf)?,
+                })
+            }
+            (CLASS_QUEUE, 21) => Method::QueueBindOk,
+            (CLASS_QUEUE, 30) => {
+                read_u16(buf)?;
+                let queue = read_short_string(buf)?;
+                let [no_wait] = read_bits(buf)?;
+                Method::QueuePurge(QueuePurge { queue, no_wait })
+            }
+            (CLASS_QUEUE, 31) => Method::QueuePurgeOk(read_message_count(buf)?),
+            (CLASS_QUEUE, 40) => {
+                read_u16(buf)?;
+                let queue = read_short_string(buf)?;
+                let [if_unused, if_empty, no_wait] = read_bits(buf)?;
+                Method::QueueDelete(QueueDelete {
+                    queue,
+                    if_unused,
+                    if_empty,
+                    no_wait,
+                })
+            }
+            (CLASS_QUEUE, 41) => Method::QueueDeleteOk(read_message_count(buf)?),
+            (CLASS_QUEUE, 50) => {
+                read_u16(buf)?;
+                Method::QueueUnbind(QueueUnbind {
+                    queue: read_short_string(buf)?,
+                    exchange: read_short_string(buf)?,
+                    routing_key: read_short_string(buf)?,
+                    arguments: read_field_table(buf)?,
+                })
+            }
+            (CLASS_QUEUE, 51) => Method::QueueUnbindOk,
+
+            (CLASS_BASIC, 10) => {
+                let prefetch_size = read_u32(buf)?;
+                let prefetch_count = read_u16(buf)?;
+                let [global] = read_bits(buf)?;
+                Method::BasicQos(BasicQos {
+                    prefetch_size,
+                    prefetch_count,
+                    global,
+                })
+            }
+            (CLASS_BASIC, 11) => Method::BasicQosOk,
+            (CLASS_BASIC, 20) => {
+                read_u16(buf)?;
+                let queue = read_short_string(buf)?;
+                let consumer_tag = read_short_string(buf)?;
+                let [no_local, no_ack, exclusive, no_wait] = read_bits(buf)?;
+                Method::BasicConsume(BasicConsume {
+                    queue,
+                    consumer_tag,
+                    no_local,
+                    no_ack,
+                    exclusive,
+                    no_wait,
+                    arguments: read_field_table(buf)?,
+                })
+            }
+            (CLASS_BASIC, 21) => Method::BasicConsumeOk(read_consumer_tag(buf)?),
+            (CLASS_BASIC, 30) => {
+                let consumer_tag = read_short_string(buf)?;
+                let [no_wait] = read_bits(buf)?;
+                Method::BasicCancel(BasicCancel {
+                    consumer_tag,
+                    no_wait,
+                })
+            }
+            (CLASS_BASIC, 31) => Method::BasicCancelOk(read_consumer_tag(buf)?),
+            (CLASS_BASIC, 40) => {
+                read_u16(buf)?;
+                let exchange = read_short_string(buf)?;
+                let routing_key = read_short_string(buf)?;
+                let [mandatory, immediate] = read_bits(buf)?;
+                Method::BasicPublish(BasicPublish {
+                    exchange,
+                    routing_key,
+                    mandatory,
+                    immediate,
+                })
+            }
+            (CLASS_BASIC, 50) => Method::BasicReturn(BasicReturn {
+                reply_code: read_u16(buf)?,
+                reply_text: read_short_string(buf)?,
+                exchange: read_short_string(buf)?,
+                routing_key: read_short_string(buf)?,
+            }),
+            (CLASS_BASIC, 60) => {
+                let consumer_tag = read_short_string(buf)?;
+                let delivery_tag = read_u64(buf)?;
+                let [redelivered] = read_bits(buf)?;
+                Method::BasicDeliver(BasicDeliver {
+                    consumer_tag,
+                    delivery_tag,
+                    redelivered,
+                    exchange: read_short_string(buf)?,
+                    routing_key: read_short_string(buf)?,
+                })
+            }
+            (CLASS_BASIC, 70) => {
+                read_u16(buf)?;
+                let queue = read_short_string(buf)?;
+                let [no_ack] = read_bits(buf)?;
+                Method::BasicGet(BasicGet { queue, no_ack })
+            }
+            (CLASS_BASIC, 71) => {
+                let delivery_tag = read_u64(buf)?;
+                let [redelivered] = read_bits(buf)?;
+                Method::BasicGetOk(BasicGetOk {
+                    delivery_tag,
+                    redelivered,
+                    exchange: read_short_string(buf)?,
+                    routing_key: read_short_string(buf)?,
+                    message_count: read_u32(buf)?,
+                })
+            }
+            (CLASS_BASIC, 72) => {
+                read_short_string(buf)?;
+                Method::BasicGetEmpty
+            }
+            (CLASS_BASIC, 80) => {
+                let delivery_tag = read_u64(buf)?;
+                let [multiple] = read_bits(buf)?;
+                Method::BasicAck(BasicAck {
+                    delivery_tag,
+                    multiple,
+                })
+            }
+            (CLASS_BASIC, 90) => {
+                let delivery_tag = read_u64(buf)?;
+                let [requeue] = read_bits(buf)?;
+                Method::BasicReject(BasicReject {
+                    delivery_tag,
+                    requeue,
+                })
+            }
+            (CLASS_BASIC, 100) => {
+                let [requeue] = read_bits(buf)?;
+                Method::BasicRecoverAsync(BasicRecover { requeue })
+            }
+            (CLASS_BASIC, 110) => {
+                let [requeue] = read_bits(buf)?;
+                Method::BasicRecover(BasicRecover { requeue })
+            }
+            (CLASS_BASIC, 111) => Method::BasicRecoverOk,
+            (CLASS_BASIC, 120) => {
+                let delivery_tag = read_u64(buf)?;
+                let [multiple, requeue] = read_bits(buf)?;
+                Method::BasicNack(BasicNack {
+                    delivery_tag,
+                    multiple,
+                    requeue,
+                })
+            }
+
+            (CLASS_CONFIRM, 10) => {
+                let [no_wait] = read_bits(buf)?;
+                Method::ConfirmSelect(ConfirmSelect { no_wait })
+            }
+            (CLASS_CONFIRM, 11) => Method::ConfirmSelectOk,
+
+            (class_id, method_id) => return Err(Error::UnknownMethod(class_id, method_id)),
+        };
+        Ok(method)
+    }
+
+    pub(crate) fn write(&self, buf: &mut BytesMut) -> Result<(), Error> {
+        let (class_id, method_id) = self.id();
+        buf.put_u16(class_id);
+        buf.put_u16(method_id);
+
+        match self {
+            Method::ConnectionStart(start) => {
+                buf.put_u8(start.version_major);
+                buf.put_u8(start.version_minor);
+                write_field_table(buf, &start.server_properties)?;
+                write_long_string(buf, &start.mechanisms);
+                write_long_string(buf, &start.locales);
+            }
+            Method::ConnectionStartOk(start_ok) => {
+                write_field_table(buf, &start_ok.client_properties)?;
+                write_short_string(buf, &start_ok.mechanism)?;
+                write_long_string(buf, &start_ok.response);
+                write_short_string(buf, &start_ok.locale)?;
+            }
+            Method::ConnectionSecure(secure) => write_long_string(buf, &secure.challenge),
+            Method::ConnectionSecureOk(secure_ok) => write_long_string(buf, &secure_ok.response),
+            Method::ConnectionTune(tune) | Method::ConnectionTuneOk(tune) => {
+                buf.put_u16(tune.channel_max);
+                buf.put_u32(tune.frame_max);
+                buf.put_u16(tune.heartbeat);
+            }
+            Method::ConnectionOpen(open) => {
+                write_short_string(buf, &open.virtual_host)?;
+                write_short_string(buf, "")?;
+                write_bits(buf, &[false]);
+            }
+            Method::ConnectionOpenOk | Method::ChannelOpen | Method::BasicGetEmpty => {
+                write_short_string(buf, "")?;
+            }
+            Method::ConnectionClose(close) | Method::ChannelClose(close) => {
+                buf.put_u16(close.reply_code);
+                write_short_string(buf, &close.reply_text)?;
+                buf.put_u16(close.class_id);
+                buf.put_u16(close.method_id);
+            }
+            Method::ConnectionBlocked(blocked) => write_short_string(buf, &blocked.reason)?,
+            Method::ChannelOpenOk => write_long_string(buf, b""),
+            Method::ChannelFlow(flow) | Method::ChannelFlowOk(flow) => {
+                write_bits(buf, &[flow.active]);
+            }
+
+            Method::ExchangeDeclare(declare) => {
+                buf.put_u16(0);
+                write_short_string(buf, &declare.exchange)?;
+                write_short_string(buf, &declare.kind)?;
+                write_bits(
+                    buf,
+                    &[
+                        declare.passive,
+                        declare.durable,
+                        declare.auto_delete,
+                        declare.internal,
+                        declare.no_wait,
+                    ],
+                );
+                write_field_table(buf, &declare.arguments)?;
+            }
+            Method::ExchangeDelete(delete) => {
+                buf.put_u16(0);
+                write_short_string(buf, &delete.exchange)?;
+                write_bits(buf, &[delete.if_unused, delete.no_wait]);
+            }
+            Method::ExchangeBind(bind) | Method::ExchangeUnbind(bind) => {
+                buf.put_u16(0);
+                write_short_string(buf, &bind.destination)?;
+                write_short_string(buf, &bind.source)?;
+                write_short_string(buf, &bind.routing_key)?;
+                write_bits(buf, &[bind.no_wait]);
+                write_field_table(buf, &bind.arguments)?;
+            }
+
+            Method::QueueDeclare(declare) => {
+                buf.put_u16(0);
+                write_short_string(buf, &declare.queue)?;
+                write_bits(
+                    buf,
+                    &[
+                        declare.passive,
+                        declare.durable,
+                        declare.exclusive,
+                        declare.auto_delete,
+                        declare.no_wait,
+                    ],
+                );
+                write_field_table(buf, &declare.arguments)?;
+            }
+            Method::QueueDeclareOk(declare_ok) => {
+                write_short_string(buf, &declare_ok.queue)?;
+                buf.put_u32(declare_ok.message_count);
+                buf.put_u32(declare_ok.consumer_count);
+            }
+            Method::QueueBind(bind) => {
+                buf.put_u16(0);
+                write_short_string(buf, &bind.queue)?;
+                write_short_string(buf, &bind.exchange)?;
+                write_short_string(buf, &bind.routing_key)?;
+                write_bits(buf, &[bind.no_wait]);
+                write_field_table(buf, &bind.arguments)?;
+            }
+            Method::QueuePurge(purge) => {
+                buf.put_u16(0);
+                write_short_string(buf, &purge.queue)?;
+                write_bits(buf, &[purge.no_wait]);
+            }
+            Method::QueuePurgeOk(count) | Method::QueueDeleteOk(count) => {
+                buf.put_u32(count.message_count);
+            }
+            Method::QueueDelete(delete) => {
+                buf.put_u16(0);
+                write_short_string(buf, &delete.queue)?;
+                write_bits(buf, &[delete.if_unused, delete.if_empty, delete.no_wait]);
+            }
+            Method::QueueUnbind(unbind) => {
+                buf.put_u16(0);
+                write_short_string(buf, &unbind.queue)?;
+                write_short_string(buf, &unbind.exchange)?;
+                write_short_string(buf, &unbind.routing_key)?;
+                write_field_table(buf, &unbind.arguments)?;
+            }
+
+            Method::BasicQos(qos) => {
+                buf.put_u32(qos.prefetch_size);
+                buf.put_u16(qos.prefetch_count);
+                write_bits(buf, &[qos.global]);
+            }
+            Method::BasicConsume(consume) => {
+                buf.put_u16(0);
+                write_short_string(buf, &consume.queue)?;
+                write_short_string(buf, &consume.consumer_tag)?;
+                write_bits(
+                    buf,
+                    &[
+                        consume.no_local,
+                        consume.no_ack,
+                        consume.exclusive,
+                        consume.no_wait,
+                    ],
+                );
+                write_field_table(buf, &consume.arguments)?;
+            }
+            Method::BasicConsumeOk(tag) | Method::BasicCancelOk(tag) => {
+                write_short_string(buf, &tag.consumer_tag)?;
+            }
+            Method::BasicCancel(cancel) => {
+                write_short_string(buf, &cancel.consumer_tag)?;
+                write_bits(buf, &[cancel.no_wait]);
+            }
+            Method::BasicPublish(publish) => {
+                buf.put_u16(0);
+                write_short_string(buf, &publish.exchange)?;
+                write_short_string(buf, &publish.routing_key)?;
+                write_bits(buf, &[publish.mandatory, publish.immediate]);
+            }
+            Method::BasicReturn(ret) => {
+                buf.put_u16(ret.reply_code);
+                write_short_string(buf, &ret.reply_text)?;
+                write_short_string(buf, &ret.exchange)?;
+                write_short_string(buf, &ret.routing_key)?;
+            }
+            Method::BasicDeliver(deliver) => {
+                write_short_string(buf, &deliver.consumer_tag)?;
+                buf.put_u64(deliver.delivery_tag);
+                write_bits(buf, &[deliver.redelivered]);
+                write_short_string(buf, &deliver.exchange)?;
+                write_short_string(buf, &deliver.routing_key)?;
+            }
+            Method::BasicGet(get) => {
+                buf.put_u16(0);
+                write_short_string(buf, &get.queue)?;
+                write_bits(buf, &[get.no_ack]);
+            }
+            Method::BasicGetOk(get_ok) => {
+                buf.put_u64(get_ok.delivery_tag);
+                write_bits(buf, &[get_ok.redelivered]);
+                write_short_string(buf, &get_ok.exchange)?;
+                write_short_string(buf, &get_ok.routing_key)?;
+                buf.put_u32(get_ok.message_count);
+            }
+            Method::BasicAck(ack) => {
+                buf.put_u64(ack.delivery_tag);
+                write_bits(buf, &[ack.multiple]);
+            }
+            Method::BasicReject(reject) => {
+                buf.put_u64(reject.delivery_tag);
+                write_bits(buf, &[reject.requeue]);
+            }
+            Method::BasicRecoverAsync(recover) | Method::BasicRecover(recover) => {
+                write_bits(buf, &[recover.requeue]);
+            }
+            Method::BasicNack(nack) => {
+                buf.put_u64(nack.delivery_tag);
+                write_bits(buf, &[nack.multiple, nack.requeue]);
+            }
+
+            Method::ConfirmSelect(select) => write_bits(buf, &[select.no_wait]),
+
+            Method::ConnectionCloseOk
+            | Method::ConnectionUnblocked
+            | Method::ChannelCloseOk
+            | Method::ExchangeDeclareOk
+            | Method::ExchangeDeleteOk
+            | Method::ExchangeBindOk
+            | Method::ExchangeUnbindOk
+            | Method::QueueBindOk
+            | Method::QueueUnbindOk
+            | Method::BasicQosOk
+            | Method::BasicRecoverOk
+            | Method::ConfirmSelectOk => {}
+        }
+        Ok(())
+    }
+}
+
+fn read_tune(buf: &mut Bytes) -> Result<ConnectionTune, Error> {
+    Ok(ConnectionTune {
+        channel_max: read_u16(buf)?,
+        frame_max: read_u32(buf)?,
+        heartbeat: read_u16(buf)?,
+    })
+}
+
+fn read_close(buf: &mut Bytes) -> Result<Close, Error> {
+    Ok(Close {
+        reply_code: read_u16(buf)?,
+        reply_text: read_short_string(buf)?,
+        class_id: read_u16(buf)?,
+        method_id: read_u16(buf)?,
+    })
+}
+
+fn read_exchange_bind(buf: &mut Bytes) -> Result<ExchangeBind, Error> {
+    read_u16(buf)?;
+    let destination = read_short_string(buf)?;
+    let source = read_short_string(buf)?;
+    let routing_key = read_short_string(buf)?;
+    let [no_wait] = read_bits(buf)?;
+    Ok(ExchangeBind {
+        destination,
+        source,
+        routing_key,
+        no_wait,
+        arguments: read_field_table(buf)?,
+    })
+}
+
+fn read_message_count(buf: &mut Bytes) -> Result<MessageCount, Error> {
+    Ok(MessageCount {
+        message_count: read_u32(buf)?,
+    })
+}
+
+fn read_consumer_tag(buf: &mut Bytes) -> Result<ConsumerTag, Error> {
+    Ok(ConsumerTag {
+        consumer_tag: read_short_string(buf)?,
+    })
+}
@@ -13,3 +13,6 @@
 // limitations under the License.
 
 pub mod codec;
+pub mod common;
+mod method;
+mod types;
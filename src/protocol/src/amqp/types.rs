@@ -0,0 +1,377 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use super::common::{BasicProperties, DecimalValue, Error, FieldTable, FieldValue};
+
+fn ensure(buf: &Bytes, len: usize) -> Result<(), Error> {
+    if buf.remaining() < len {
+        return Err(Error::InsufficientBytes(len - buf.remaining()));
+    }
+    Ok(())
+}
+
+pub(crate) fn read_u8(buf: &mut Bytes) -> Result<u8, Error> {
+    ensure(buf, 1)?;
+    Ok(buf.get_u8())
+}
+
+pub(crate) fn read_u16(buf: &mut Bytes) -> Result<u16, Error> {
+    ensure(buf, 2)?;
+    Ok(buf.get_u16())
+}
+
+pub(crate) fn read_u32(buf: &mut Bytes) -> Result<u32, Error> {
+    ensure(buf, 4)?;
+    Ok(buf.get_u32())
+}
+
+pub(crate) fn read_u64(buf: &mut Bytes) -> Result<u64, Error> {
+    ensure(buf, 8)?;
+    Ok(buf.get_u64())
+}
+
+pub(crate) fn read_bytes(buf: &mut Bytes, len: usize) -> Result<Bytes, Error> {
+    ensure(buf, len)?;
+    Ok(buf.split_to(len))
+}
+
+pub(crate) fn read_short_string(buf: &mut Bytes) -> Result<String, Error> {
+    let len = read_u8(buf)? as usize;
+    let bytes = read_bytes(buf, len)?;
+    Ok(String::from_utf8(bytes.to_vec())?)
+}
+
+pub(crate) fn read_long_string(buf: &mut Bytes) -> Result<Bytes, Error> {
+    let len = read_u32(buf)? as usize;
+    read_bytes(buf, len)
+}
+
+/// Consecutive bit arguments share one octet, least significant bit first
+pub(crate) fn read_bits<const N: usize>(buf: &mut Bytes) -> Result<[bool; N], Error> {
+    let flags = read_u8(buf)?;
+    let mut bits = [false; N];
+    for (i, bit) in bits.iter_mut().enumerate() {
+        *bit = flags & (1 << i) != 0;
+    }
+    Ok(bits)
+}
+
+pub(crate) fn read_field_table(buf: &mut Bytes) -> Result<FieldTable, Error> {
+    let len = read_u32(buf)? as usize;
+    let mut table_buf = read_bytes(buf, len)?;
+    let mut table = FieldTable::new();
+    while table_buf.has_remaining() {
+        let name = read_short_string(&mut table_buf)?;
+        let value = read_field_value(&mut table_buf)?;
+        table.insert(name, value);
+    }
+    Ok(table)
+}
+
+fn read_field_array(buf: &mut Bytes) -> Result<Vec<FieldValue>, Error> {
+    let len = read_u32(buf)? as usize;
+    let mut array_buf = read_bytes(buf, len)?;
+    let mut array = Vec::new();
+    while array_buf.has_remaining() {
+        array.push(read_field_value(&mut array_buf)?);
+    }
+    Ok(array)
+}
+
+fn read_field_value(buf: &mut Bytes) -> Result<FieldValue, Error> {
+    let value = match read_u8(buf)? {
+        b't' => FieldValue::Boolean(read_u8(buf)? != 0),
+        b'b' => FieldValue::ShortShortInt(read_u8(buf)? as i8),
+        b'B' => FieldValue::ShortShortUInt(read_u8(buf)?),
+        b's' => FieldValue::ShortInt(read_u16(buf)? as i16),
+        b'u' => FieldValue::ShortUInt(read_u16(buf)?),
+        b'I' => FieldValue::LongInt(read_u32(buf)? as i32),
+        b'i' => FieldValue::LongUInt(read_u32(buf)?),
+        b'l' => FieldValue::LongLongInt(read_u64(buf)? as i64),
+        b'f' => FieldValue::Float(f32::from_bits(read_u32(buf)?)),
+        b'd' => FieldValue::Double(f64::from_bits(read_u64(buf)?)),
+        b'D' => FieldValue::Decimal(DecimalValue {
+            scale: read_u8(buf)?,
+            value: read_u32(buf)?,
+        }),
+        b'S' => FieldValue::LongString(read_long_string(buf)?),
+        b'A' => FieldValue::FieldArray(read_field_array(buf)?),
+        b'T' => FieldValue::Timestamp(read_u64(buf)?),
+        b'F' => FieldValue::FieldTable(read_field_table(buf)?),
+        b'V' => FieldValue::Void,
+        b'x' => FieldValue::ByteArray(read_long_string(buf)?),
+        tag => return Err(Error::InvalidFieldType(tag)),
+    };
+    Ok(value)
+}
+
+pub(crate) fn write_short_string(buf: &mut BytesMut, value: &str) -> Result<(), Error> {
+    if value.len() > u8::MAX as usize {
+        return Err(Error::ShortStringTooLong(value.len()));
+    }
+    buf.put_u8(value.len() as u8);
+    buf.put_slice(value.as_bytes());
+    Ok(())
+}
+
+pub(crate) fn write_long_string(buf: &mut BytesMut, value: &[u8]) {
+    buf.put_u32(value.len() as u32);
+    buf.put_slice(value);
+}
+
+pub(crate) fn write_bits(buf: &mut BytesMut, bits: &[bool]) {
+    let flags = bits
+        .iter()
+        .enumerate()
+        .fold(0u8, |flags, (i, bit)| flags | ((*bit as u8) << i));
+    buf.put_u8(flags);
+}
+
+/// Writes a u32 length prefix, lets `f` fill the body, then patches the length in
+fn write_sized<F>(buf: &mut BytesMut, f: F) -> Result<(), Error>
+where
+    F: FnOnce(&mut BytesMut) -> Result<(), Error>,
+{
+    let start = buf.len();
+    buf.put_u32(0);
+    f(buf)?;
+    let len = (buf.len() - start - 4) as u32;
+    buf[start..start + 4].copy_from_slice(&len.to_be_bytes());
+    Ok(())
+}
+
+pub(crate) fn write_field_table(buf: &mut BytesMut, table: &FieldTable) -> Result<(), Error> {
+    write_sized(buf, |buf| {
+        for (name, value) in table {
+            write_short_string(buf, name)?;
+            write_field_value(buf, value)?;
+        }
+        Ok(())
+    })
+}
+
+fn write_field_value(buf: &mut BytesMut, value: &FieldValue) -> Result<(), Error> {
+    match value {
+        FieldValue::Boolean(v) => {
+            buf.put_u8(b't');
+            buf.put_u8(*v as u8);
+        }
+        FieldValue::ShortShortInt(v) => {
+            buf.put_u8(b'b');
+            buf.put_i8(*v);
+        }
+        FieldValue::ShortShortUInt(v) => {
+            buf.put_u8(b'B');
+            buf.put_u8(*v);
+        }
+        FieldValue::ShortInt(v) => {
+            buf.put_u8(b's');
+            buf.put_i16(*v);
+        }
+        FieldValue::ShortUInt(v) => {
+            buf.put_u8(b'u');
+            buf.put_u16(*v);
+        }
+        FieldValue::LongInt(v) => {
+            buf.put_u8(b'I');
+            buf.put_i32(*v);
+        }
+        FieldValue::LongUInt(v) => {
+            buf.put_u8(b'i');
+            buf.put_u32(*v);
+        }
+        FieldValue::LongLongInt(v) => {
+            buf.put_u8(b'l');
+            buf.put_i64(*v);
+        }
+        FieldValue::Float(v) => {
+            buf.put_u8(b'f');
+            buf.put_f32(*v);
+        }
+        FieldValue::Double(v) => {
+            buf.put_u8(b'd');
+            buf.put_f64(*v);
+        }
+        FieldValue::Decimal(v) => {
+            buf.put_u8(b'D');
+            buf.put_u8(v.scale);
+            buf.put_u32(v.value);
+        }
+        FieldValue::LongString(v) => {
+            buf.put_u8(b'S');
+            write_long_string(buf, v);
+        }
+        FieldValue::FieldArray(values) => {
+            buf.put_u8(b'A');
+            write_sized(buf, |buf| {
+                for value in values {
+                    write_field_value(buf, value)?;
+                }
+                Ok(())
+            })?;
+        }
+        FieldValue::Timestamp(v) => {
+            buf.put_u8(b'T');
+            buf.put_u64(*v);
+        }
+        FieldValue::FieldTable(table) => {
+            buf.put_u8(b'F');
+            write_field_table(buf, table)?;
+        }
+        FieldValue::Void => buf.put_u8(b'V'),
+        FieldValue::ByteArray(v) => {
+            buf.put_u8(b'x');
+            write_long_string(buf, v);
+        }
+    }
+    Ok(())
+}
+
+// Property flag bits, highest bit first, as laid out in the basic class
+const FLAG_CONTENT_TYPE: u16 = 1 << 15;
+const FLAG_CONTENT_ENCODING: u16 = 1 << 14;
+const FLAG_HEADERS: u16 = 1 << 13;
+const FLAG_DELIVERY_MODE: u16 = 1 << 12;
+const FLAG_PRIORITY: u16 = 1 << 11;
+const FLAG_CORRELATION_ID: u16 = 1 << 10;
+const FLAG_REPLY_TO: u16 = 1 << 9;
+const FLAG_EXPIRATION: u16 = 1 << 8;
+const FLAG_MESSAGE_ID: u16 = 1 << 7;
+const FLAG_TIMESTAMP: u16 = 1 << 6;
+const FLAG_TYPE: u16 = 1 << 5;
+const FLAG_USER_ID: u16 = 1 << 4;
+const FLAG_APP_ID: u16 = 1 << 3;
+const FLAG_CLUSTER_ID: u16 = 1 << 2;
+
+pub(crate) fn read_basic_properties(buf: &mut Bytes) -> Result<BasicProperties, Error> {
+    let flags = read_u16(buf)?;
+    let short_string = |buf: &mut Bytes, flag: u16| -> Result<Option<String>, Error> {
+        if flags & flag == 0 {
+            return Ok(None);
+        }
+        read_short_string(buf).map(Some)
+    };
+
+    let content_type = short_string(buf, FLAG_CONTENT_TYPE)?;
+    let content_encoding = short_string(buf, FLAG_CONTENT_ENCODING)?;
+    let headers = if flags & FLAG_HEADERS != 0 {
+        Some(read_field_table(buf)?)
+    } else {
+        None
+    };
+    let delivery_mode = if flags & FLAG_DELIVERY_MODE != 0 {
+        Some(read_u8(buf)?)
+    } else {
+        None
+    };
+    let priority = if flags & FLAG_PRIORITY != 0 {
+        Some(read_u8(buf)?)
+    } else {
+        None
+    };
+    let correlation_id = short_string(buf, FLAG_CORRELATION_ID)?;
+    let reply_to = short_string(buf, FLAG_REPLY_TO)?;
+    let expiration = short_string(buf, FLAG_EXPIRATION)?;
+    let message_id = short_string(buf, FLAG_MESSAGE_ID)?;
+    let timestamp = if flags & FLAG_TIMESTAMP != 0 {
+        Some(read_u64(buf)?)
+    } else {
+        None
+    };
+    let kind = short_string(buf, FLAG_TYPE)?;
+    let user_id = short_string(buf, FLAG_USER_ID)?;
+    let app_id = short_string(buf, FLAG_APP_ID)?;
+    let cluster_id = short_string(buf, FLAG_CLUSTER_ID)?;
+
+    Ok(BasicProperties {
+        content_type,
+        content_encoding,
+        headers,
+        delivery_mode,
+        priority,
+        correlation_id,
+        reply_to,
+        expiration,
+        message_id,
+        timestamp,
+        kind,
+        user_id,
+        app_id,
+        cluster_id,
+    })
+}
+
+fn write_optional_short_string(
+    buf: &mut BytesMut,
+    flags: &mut u16,
+    flag: u16,
+    value: &Option<String>,
+) -> Result<(), Error> {
+    if let Some(value) = value {
+        *flags |= flag;
+        write_short_string(buf, value)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_basic_properties(
+    buf: &mut BytesMut,
+    properties: &BasicProperties,
+) -> Result<(), Error> {
+    let flag_index = buf.len();
+    buf.put_u16(0);
+    let mut flags = 0u16;
+
+    write_optional_short_string(buf, &mut flags, FLAG_CONTENT_TYPE, &properties.content_type)?;
+    write_optional_short_string(
+        buf,
+        &mut flags,
+        FLAG_CONTENT_ENCODING,
+        &properties.content_encoding,
+    )?;
+    if let Some(headers) = &properties.headers {
+        flags |= FLAG_HEADERS;
+        write_field_table(buf, headers)?;
+    }
+    if let Some(delivery_mode) = properties.delivery_mode {
+        flags |= FLAG_DELIVERY_MODE;
+        buf.put_u8(delivery_mode);
+    }
+    if let Some(priority) = properties.priority {
+        flags |= FLAG_PRIORITY;
+        buf.put_u8(priority);
+    }
+    write_optional_short_string(
+        buf,
+        &mut flags,
+        FLAG_CORRELATION_ID,
+        &properties.correlation_id,
+    )?;
+    write_optional_short_string(buf, &mut flags, FLAG_REPLY_TO, &properties.reply_to)?;
+    write_optional_short_string(buf, &mut flags, FLAG_EXPIRATION, &properties.expiration)?;
+    write_optional_short_string(buf, &mut flags, FLAG_MESSAGE_ID, &properties.message_id)?;
+    if let Some(timestamp) = properties.timestamp {
+        flags |= FLAG_TIMESTAMP;
+        buf.put_u64(timestamp);
+    }
+    write_optional_short_string(buf, &mut flags, FLAG_TYPE, &properties.kind)?;
+    write_optional_short_string(buf, &mut flags, FLAG_USER_ID, &properties.user_id)?;
+    write_optional_short_string(buf, &mut flags, FLAG_APP_ID, &properties.app_id)?;
+    write_optional_short_string(buf, &mut flags, FLAG_CLUSTER_ID, &properties.cluster_id)?;
+
+    buf[flag_index..flag_index + 2].copy_from_slice(&flags.to_be_bytes());
+    Ok(())
+}
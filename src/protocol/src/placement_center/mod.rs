@@ -15,6 +15,10 @@
 #![cfg_attr(any(), rustfmt::skip)]
 #![allow(clippy::all)]
 
+pub mod placement_center_amqp {
+    tonic::include_proto!("placement.center.amqp");
+}
+
 pub mod placement_center_journal {
     tonic::include_proto!("placement.center.journal");
 }
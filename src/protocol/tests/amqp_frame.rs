@@ -0,0 +1,467 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Bytes, BytesMut};
+    use futures::{SinkExt, StreamExt};
+    use protocol::amqp::codec::AmqpCodec;
+    use protocol::amqp::common::*;
+    use tokio::io;
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_util::codec::{Decoder, Encoder, Framed, FramedRead, FramedWrite};
+
+    #[tokio::test]
+    async fn amqp_frame_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let (r_stream, w_stream) = io::split(stream);
+                let mut read_frame_stream = FramedRead::new(r_stream, AmqpCodec::new());
+                let mut write_frame_stream = FramedWrite::new(w_stream, AmqpCodec::new());
+                while let Some(Ok(frame)) = read_frame_stream.next().await {
+                    write_frame_stream.send(frame).await.unwrap();
+                }
+            }
+        });
+
+        let socket = TcpStream::connect(addr).await.unwrap();
+        let mut stream = Framed::new(socket, AmqpCodec::new());
+        for frame in build_frames() {
+            stream.send(frame.clone()).await.unwrap();
+            match stream.next().await {
+                Some(Ok(resp)) => assert_eq!(resp, frame),
+                other => panic!("unexpected response: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn amqp_frame_round_trip_byte_by_byte() {
+        let frames = build_frames();
+        let mut codec = AmqpCodec::new();
+        let mut wire = BytesMut::new();
+        for frame in frames.iter() {
+            codec.encode(frame.clone(), &mut wire).unwrap();
+        }
+
+        // feed one byte at a time so every frame is first seen partially
+        let mut src = BytesMut::new();
+        let mut decoded = Vec::new();
+        for byte in wire.iter() {
+            src.extend_from_slice(&[*byte]);
+            if let Some(frame) = codec.decode(&mut src).unwrap() {
+                decoded.push(frame);
+            }
+        }
+        assert!(src.is_empty());
+        assert_eq!(decoded, frames);
+    }
+
+    #[test]
+    fn amqp_frame_wire_format() {
+        let mut codec = AmqpCodec::new();
+
+        let mut dst = BytesMut::new();
+        codec
+            .encode(
+                AmqpFrame::ProtocolHeader(ProtocolHeader::default()),
+                &mut dst,
+            )
+            .unwrap();
+        assert_eq!(&dst[..], b"AMQP\x00\x00\x09\x01");
+
+        let mut dst = BytesMut::new();
+        codec.encode(AmqpFrame::Heartbeat, &mut dst).unwrap();
+        assert_eq!(&dst[..], &[8, 0, 0, 0, 0, 0, 0, 0xCE]);
+
+        let mut dst = BytesMut::new();
+        let tune = ConnectionTune {
+            channel_max: 2047,
+            frame_max: 131072,
+            heartbeat: 60,
+        };
+        codec
+            .encode(AmqpFrame::Method(0, Method::ConnectionTune(tune)), &mut dst)
+            .unwrap();
+        assert_eq!(
+            &dst[..],
+            &[1, 0, 0, 0, 0, 0, 12, 0, 10, 0, 30, 0x07, 0xFF, 0, 2, 0, 0, 0, 60, 0xCE]
+        );
+
+        // bits are packed lsb first: durable | auto-delete
+        let mut dst = BytesMut::new();
+        let declare = QueueDeclare {
+            queue: "q".to_string(),
+            durable: true,
+            auto_delete: true,
+            ..Default::default()
+        };
+        codec
+            .encode(
+                AmqpFrame::Method(1, Method::QueueDeclare(declare)),
+                &mut dst,
+            )
+            .unwrap();
+        assert_eq!(
+            &dst[..],
+            &[1, 0, 1, 0, 0, 0, 13, 0, 50, 0, 10, 0, 0, 1, b'q', 0b1010, 0, 0, 0, 0, 0xCE]
+        );
+    }
+
+    #[test]
+    fn amqp_frame_decode_error() {
+        let mut codec = AmqpCodec::new();
+
+        let mut src = BytesMut::from(&b"HTTP/1.1"[..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::InvalidFrameType(b'H'))
+        ));
+
+        let mut src = BytesMut::from(&b"AMQX"[..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::InvalidProtocolHeader(_))
+        ));
+
+        // an unsupported version still decodes so the broker can reply with its own
+        let mut src = BytesMut::from(&b"AMQP\x00\x01\x00\x00"[..]);
+        match codec.decode(&mut src).unwrap() {
+            Some(AmqpFrame::ProtocolHeader(header)) => assert!(!header.is_supported()),
+            other => panic!("unexpected frame: {:?}", other),
+        }
+
+        let mut src = BytesMut::from(&[8u8, 0, 0, 0, 0, 0, 0, 0xFF][..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::InvalidFrameEnd(0xFF))
+        ));
+
+        let mut src = BytesMut::from(&[8u8, 0, 1, 0, 0, 0, 0, 0xCE][..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::InvalidHeartbeatChannel(1))
+        ));
+
+        let mut src = BytesMut::from(&[1u8, 0, 0, 0, 0, 0, 4, 0, 90, 0, 10, 0xCE][..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::UnknownMethod(90, 10))
+        ));
+
+        // method payload shorter than its arguments
+        let mut src = BytesMut::from(&[1u8, 0, 0, 0, 0, 0, 5, 0, 10, 0, 30, 0, 0xCE][..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::InsufficientBytes(_))
+        ));
+
+        codec.set_frame_max(FRAME_MIN_SIZE);
+        let mut src = BytesMut::from(&[3u8, 0, 1, 0, 0, 0x10, 0][..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::FrameTooLarge(_, FRAME_MIN_SIZE))
+        ));
+
+        let mut dst = BytesMut::new();
+        let body = AmqpFrame::Body(1, Bytes::from(vec![0u8; FRAME_MIN_SIZE as usize]));
+        assert!(matches!(
+            codec.encode(body, &mut dst),
+            Err(Error::FrameTooLarge(_, FRAME_MIN_SIZE))
+        ));
+        assert!(dst.is_empty());
+    }
+
+    /// One frame per supported method plus content and heartbeat frames
+    fn build_frames() -> Vec<AmqpFrame> {
+        let mut arguments = FieldTable::new();
+        arguments.insert("x-bool".to_string(), FieldValue::Boolean(true));
+        arguments.insert("x-i8".to_string(), FieldValue::ShortShortInt(-8));
+        arguments.insert("x-u8".to_string(), FieldValue::ShortShortUInt(8));
+        arguments.insert("x-i16".to_string(), FieldValue::ShortInt(-16));
+        arguments.insert("x-u16".to_string(), FieldValue::ShortUInt(16));
+        arguments.insert("x-i32".to_string(), FieldValue::LongInt(-32));
+        arguments.insert("x-u32".to_string(), FieldValue::LongUInt(32));
+        arguments.insert("x-i64".to_string(), FieldValue::LongLongInt(-64));
+        arguments.insert("x-f32".to_string(), FieldValue::Float(1.5));
+        arguments.insert("x-f64".to_string(), FieldValue::Double(2.25));
+        arguments.insert(
+            "x-decimal".to_string(),
+            FieldValue::Decimal(DecimalValue {
+                scale: 2,
+                value: 314,
+            }),
+        );
+        arguments.insert(
+            "x-string".to_string(),
+            FieldValue::LongString(Bytes::from("robustmq")),
+        );
+        arguments.insert(
+            "x-array".to_string(),
+            FieldValue::FieldArray(vec![FieldValue::LongInt(1), FieldValue::Void]),
+        );
+        arguments.insert("x-timestamp".to_string(), FieldValue::Timestamp(1700000000));
+        let mut capabilities = FieldTable::new();
+        capabilities.insert("publisher_confirms".to_string(), FieldValue::Boolean(true));
+        arguments.insert(
+            "capabilities".to_string(),
+            FieldValue::FieldTable(capabilities),
+        );
+        arguments.insert("x-void".to_string(), FieldValue::Void);
+        arguments.insert(
+            "x-bytes".to_string(),
+            FieldValue::ByteArray(Bytes::from_static(&[0, 1, 2])),
+        );
+
+        let close = Close {
+            reply_code: 320,
+            reply_text: "CONNECTION_FORCED".to_string(),
+            class_id: 0,
+            method_id: 0,
+        };
+        let tune = ConnectionTune {
+            channel_max: 2047,
+            frame_max: DEFAULT_FRAME_MAX,
+            heartbeat: 60,
+        };
+        let exchange_bind = ExchangeBind {
+            destination: "dest".to_string(),
+            source: "src".to_string(),
+            routing_key: "a.b".to_string(),
+            no_wait: true,
+            arguments: arguments.clone(),
+        };
+
+        let methods = vec![
+            Method::ConnectionStart(ConnectionStart {
+                version_major: 0,
+                version_minor: 9,
+                server_properties: arguments.clone(),
+                mechanisms: Bytes::from("PLAIN AMQPLAIN"),
+                locales: Bytes::from("en_US"),
+            }),
+            Method::ConnectionStartOk(ConnectionStartOk {
+                client_properties: arguments.clone(),
+                mechanism: "PLAIN".to_string(),
+                response: Bytes::from("\0admin\0pwd123"),
+                locale: "en_US".to_string(),
+            }),
+            Method::ConnectionSecure(ConnectionSecure {
+                challenge: Bytes::from("challenge"),
+            }),
+            Method::ConnectionSecureOk(ConnectionSecureOk {
+                response: Bytes::from("response"),
+            }),
+            Method::ConnectionTune(tune),
+            Method::ConnectionTuneOk(tune),
+            Method::ConnectionOpen(ConnectionOpen {
+                virtual_host: "/".to_string(),
+            }),
+            Method::ConnectionOpenOk,
+            Method::ConnectionClose(close.clone()),
+            Method::ConnectionCloseOk,
+            Method::ConnectionBlocked(ConnectionBlocked {
+                reason: "low on memory".to_string(),
+            }),
+            Method::ConnectionUnblocked,
+            Method::ChannelOpen,
+            Method::ChannelOpenOk,
+            Method::ChannelFlow(ChannelFlow { active: true }),
+            Method::ChannelFlowOk(ChannelFlow { active: false }),
+            Method::ChannelClose(close),
+            Method::ChannelCloseOk,
+            Method::ExchangeDeclare(ExchangeDeclare {
+                exchange: "ex".to_string(),
+                kind: "topic".to_string(),
+                passive: false,
+                durable: true,
+                auto_delete: false,
+                internal: true,
+                no_wait: true,
+                arguments: arguments.clone(),
+            }),
+            Method::ExchangeDeclareOk,
+            Method::ExchangeDelete(ExchangeDelete {
+                exchange: "ex".to_string(),
+                if_unused: true,
+                no_wait: false,
+            }),
+            Method::ExchangeDeleteOk,
+            Method::ExchangeBind(exchange_bind.clone()),
+            Method::ExchangeBindOk,
+            Method::ExchangeUnbind(exchange_bind),
+            Method::ExchangeUnbindOk,
+            Method::QueueDeclare(QueueDeclare {
+                queue: "q1".to_string(),
+                passive: true,
+                durable: false,
+                exclusive: true,
+                auto_delete: false,
+                no_wait: true,
+                arguments: arguments.clone(),
+            }),
+            Method::QueueDeclareOk(QueueDeclareOk {
+                queue: "q1".to_string(),
+                message_count: 10,
+                consumer_count: 2,
+            }),
+            Method::QueueBind(QueueBind {
+                queue: "q1".to_string(),
+                exchange: "ex".to_string(),
+                routing_key: "a.*".to_string(),
+                no_wait: false,
+                arguments: arguments.clone(),
+            }),
+            Method::QueueBindOk,
+            Method::QueuePurge(QueuePurge {
+                queue: "q1".to_string(),
+                no_wait: true,
+            }),
+            Method::QueuePurgeOk(MessageCount { message_count: 3 }),
+            Method::QueueDelete(QueueDelete {
+                queue: "q1".to_string(),
+                if_unused: false,
+                if_empty: true,
+                no_wait: true,
+            }),
+            Method::QueueDeleteOk(MessageCount { message_count: 0 }),
+            Method::QueueUnbind(QueueUnbind {
+                queue: "q1".to_string(),
+                exchange: "ex".to_string(),
+                routing_key: "a.*".to_string(),
+                arguments: arguments.clone(),
+            }),
+            Method::QueueUnbindOk,
+            Method::BasicQos(BasicQos {
+                prefetch_size: 0,
+                prefetch_count: 100,
+                global: true,
+            }),
+            Method::BasicQosOk,
+            Method::BasicConsume(BasicConsume {
+                queue: "q1".to_string(),
+                consumer_tag: "ctag-1".to_string(),
+                no_local: false,
+                no_ack: true,
+                exclusive: false,
+                no_wait: true,
+                arguments,
+            }),
+            Method::BasicConsumeOk(ConsumerTag {
+                consumer_tag: "ctag-1".to_string(),
+            }),
+            Method::BasicCancel(BasicCancel {
+                consumer_tag: "ctag-1".to_string(),
+                no_wait: true,
+            }),
+            Method::BasicCancelOk(ConsumerTag {
+                consumer_tag: "ctag-1".to_string(),
+            }),
+            Method::BasicReturn(BasicReturn {
+                reply_code: 312,
+                reply_text: "NO_ROUTE".to_string(),
+                exchange: "ex".to_string(),
+                routing_key: "a.b".to_string(),
+            }),
+            Method::BasicDeliver(BasicDeliver {
+                consumer_tag: "ctag-1".to_string(),
+                delivery_tag: u64::MAX,
+                redelivered: true,
+                exchange: "ex".to_string(),
+                routing_key: "a.b".to_string(),
+            }),
+            Method::BasicGet(BasicGet {
+                queue: "q1".to_string(),
+                no_ack: true,
+            }),
+            Method::BasicGetOk(BasicGetOk {
+                delivery_tag: 7,
+                redelivered: false,
+                exchange: "ex".to_string(),
+                routing_key: "a.b".to_string(),
+                message_count: 9,
+            }),
+            Method::BasicGetEmpty,
+            Method::BasicAck(BasicAck {
+                delivery_tag: 7,
+                multiple: true,
+            }),
+            Method::BasicReject(BasicReject {
+                delivery_tag: 8,
+                requeue: true,
+            }),
+            Method::BasicRecoverAsync(BasicRecover { requeue: true }),
+            Method::BasicRecover(BasicRecover { requeue: false }),
+            Method::BasicRecoverOk,
+            Method::BasicNack(BasicNack {
+                delivery_tag: 9,
+                multiple: false,
+                requeue: true,
+            }),
+            Method::ConfirmSelect(ConfirmSelect { no_wait: false }),
+            Method::ConfirmSelectOk,
+        ];
+
+        let mut frames = vec![AmqpFrame::ProtocolHeader(ProtocolHeader::default())];
+        frames.extend(
+            methods
+                .into_iter()
+                .map(|method| AmqpFrame::Method(1, method)),
+        );
+
+        // a published message: method, content header, then body frames
+        let mut headers = FieldTable::new();
+        headers.insert(
+            "trace".to_string(),
+            FieldValue::LongString(Bytes::from("1")),
+        );
+        let body = Bytes::from("hello robustmq");
+        frames.push(AmqpFrame::Method(
+            2,
+            Method::BasicPublish(BasicPublish {
+                exchange: "ex".to_string(),
+                routing_key: "a.b".to_string(),
+                mandatory: true,
+                immediate: false,
+            }),
+        ));
+        frames.push(AmqpFrame::Header(
+            2,
+            ContentHeader {
+                class_id: CLASS_BASIC,
+                body_size: body.len() as u64,
+                properties: BasicProperties {
+                    content_type: Some("text/plain".to_string()),
+                    headers: Some(headers),
+                    delivery_mode: Some(2),
+                    priority: Some(5),
+                    correlation_id: Some("corr".to_string()),
+                    reply_to: Some("amq.rabbitmq.reply-to".to_string()),
+                    message_id: Some("msg-1".to_string()),
+                    timestamp: Some(1700000000),
+                    app_id: Some("test".to_string()),
+                    ..Default::default()
+                },
+            },
+        ));
+        frames.push(AmqpFrame::Body(2, body.slice(..5)));
+        frames.push(AmqpFrame::Body(2, body.slice(5..)));
+        frames.push(AmqpFrame::Header(2, ContentHeader::default()));
+        frames.push(AmqpFrame::Heartbeat);
+        frames
+    }
+}
@@ -27,6 +27,7 @@ use crate::storage::{ShardConfig, ShardOffset, StorageAdapter};
 
 pub mod offset;
 
+#[derive(Clone)]
 pub struct JournalStorageAdapter {
     cluster_name: String,
     client: JournalClient,
@@ -1,24 +1,25 @@
 CREATE DATABASE test;
 USE test;
 
-CREATE TABLE `storage_stream_record` (
-`id` int(11) unsigned NOT NULL AUTO_INCREMENT,
-`msgid` varchar(64) DEFAULT NULL,
-`header` String DEFAULT NULL,
-`msg_key` String DEFAULT NULL,
+CREATE TABLE `storage_record_{namespace}_{shard_name}` (
+`id` bigint(20) unsigned NOT NULL AUTO_INCREMENT,
+`record_key` varchar(128) NOT NULL DEFAULT '',
+`header` text,
+`tags` json,
 `payload` blob,
-`create_time` int(11) NOT NULL,
-PRIMARY KEY (`id`)
+`create_time` bigint(20) unsigned NOT NULL,
+PRIMARY KEY (`id`),
+index record_key(record_key),
+index create_time(create_time)
 ) ENGINE=InnoDB DEFAULT CHARSET=utf8MB4;
 
-CREATE TABLE `storage_kv` (
+CREATE TABLE `storage_group_offset` (
 `id` int(11) unsigned NOT NULL AUTO_INCREMENT,
-`data_key` varchar(128) DEFAULT NULL,
-`data_value` blob,
-`create_time` int(11) NOT NULL,
+`group_name` varchar(128) NOT NULL,
+`namespace` varchar(128) NOT NULL,
+`shard_name` varchar(128) NOT NULL,
+`offset` bigint(20) unsigned NOT NULL,
 `update_time` int(11) NOT NULL,
 PRIMARY KEY (`id`),
-unique index data_key(data_key)
+unique index group_shard(group_name, namespace, shard_name)
 ) ENGINE=InnoDB DEFAULT CHARSET=utf8MB4;
- 
-
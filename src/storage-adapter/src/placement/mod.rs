@@ -0,0 +1,593 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use axum::async_trait;
+use common_base::config::common::PlacementStorage;
+use common_base::error::common::CommonError;
+use common_base::tools::now_second;
+use dashmap::DashMap;
+use grpc_clients::placement::kv::call::{
+    placement_batch_delete, placement_batch_get, placement_batch_set, placement_get,
+};
+use grpc_clients::pool::ClientPool;
+use metadata_struct::adapter::read_config::ReadConfig;
+use metadata_struct::adapter::record::Record;
+use protocol::placement_center::placement_center_kv::{
+    BatchDeleteRequest, BatchGetRequest, BatchSetRequest, GetRequest, KvPair,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::journal::offset::PlaceOffsetManager;
+use crate::storage::{ShardConfig, ShardOffset, StorageAdapter};
+
+// Attempts at the shard meta compare-and-set before a write gives up
+const MAX_WRITE_RETRIES: usize = 10;
+// Keys per batch get or batch delete request
+const KV_BATCH_SIZE: usize = 100;
+// Time index step when no retention time is configured
+const DEFAULT_TIME_INDEX_STEP_SEC: u64 = 60;
+const MAX_TIME_INDEX_LEN: usize = 256;
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ShardMeta {
+    // Oldest offset still retained
+    pub start_offset: u64,
+    pub next_offset: u64,
+    // (offset, timestamp) of the first record of each time step, oldest first.
+    // Lets retention and timestamp lookups skip reading the records themselves.
+    #[serde(default)]
+    pub time_index: Vec<(u64, u64)>,
+}
+
+// Stores message data as keys in the placement center's Raft-replicated RocksDB.
+// Each write sends the records and the new shard meta in one Raft proposal that only
+// applies if the meta is unchanged, so brokers sharing a shard never reuse an offset.
+// Every write is a Raft proposal, so it is meant for small deployments.
+#[derive(Clone)]
+pub struct PlacementStorageAdapter {
+    client_pool: Arc<ClientPool>,
+    addrs: Vec<String>,
+    cluster_name: String,
+    config: PlacementStorage,
+    offset_manager: PlaceOffsetManager,
+    // Only saves writers on this broker from losing the compare-and-set to each other
+    shard_lock: Arc<DashMap<String, Arc<Mutex<()>>>>,
+}
+
+impl PlacementStorageAdapter {
+    pub fn new(
+        client_pool: Arc<ClientPool>,
+        cluster_name: String,
+        addrs: Vec<String>,
+        config: PlacementStorage,
+    ) -> Self {
+        let offset_manager = PlaceOffsetManager::new(client_pool.clone(), addrs.clone());
+        PlacementStorageAdapter {
+            client_pool,
+            addrs,
+            cluster_name,
+            config,
+            offset_manager,
+            shard_lock: Arc::new(DashMap::with_capacity(256)),
+        }
+    }
+
+    pub fn shard_meta_key(&self, namespace: &str, shard_name: &str) -> String {
+        format!("/storage/{}/{}/meta", namespace, shard_name)
+    }
+
+    pub fn record_key(&self, namespace: &str, shard_name: &str, offset: u64) -> String {
+        format!("/storage/{}/{}/record/{}", namespace, shard_name, offset)
+    }
+
+    pub fn check_record_size(&self, value: &str) -> Result<(), CommonError> {
+        if value.len() as u64 > self.config.max_record_size {
+            return Err(CommonError::CommonError(format!(
+                "Record size {} exceeds the placement storage limit of {} bytes",
+                value.len(),
+                self.config.max_record_size
+            )));
+        }
+        Ok(())
+    }
+
+    // First offset that must be kept once the shard is over max_shard_records.
+    pub fn retained_start_offset(&self, meta: &ShardMeta) -> u64 {
+        let count_start = meta
+            .next_offset
+            .saturating_sub(self.config.max_shard_records);
+        meta.start_offset.max(count_start)
+    }
+
+    // Adds an index entry when the record starts a new time step.
+    pub fn index_record(&self, meta: &mut ShardMeta, offset: u64, timestamp: u64) {
+        let step = if self.config.retention_sec > 0 {
+            (self.config.retention_sec / 100).max(1)
+        } else {
+            DEFAULT_TIME_INDEX_STEP_SEC
+        };
+        if let Some(&(_, last_timestamp)) = meta.time_index.last() {
+            if timestamp < last_timestamp.saturating_add(step) {
+                return;
+            }
+        }
+        if meta.time_index.len() >= MAX_TIME_INDEX_LEN {
+            // Merge the two oldest steps, which only delays their expiry
+            meta.time_index.remove(1);
+        }
+        meta.time_index.push((offset, timestamp));
+    }
+
+    // Moves start_offset past the records that are over the count limit or expired,
+    // and returns the offsets to delete. A step expires once the step after it
+    // started before the expire time, so expiry is at most one step late.
+    pub fn apply_retention(&self, meta: &mut ShardMeta, now: u64) -> Range<u64> {
+        let mut start_offset = self.retained_start_offset(meta);
+
+        if self.config.retention_sec > 0 {
+            let expire_time = now.saturating_sub(self.config.retention_sec);
+            for steps in meta.time_index.windows(2) {
+                if steps[1].1 > expire_time {
+                    break;
+                }
+                start_offset = start_offset.max(steps[1].0);
+            }
+        }
+
+        let expired = meta.start_offset..start_offset;
+        meta.start_offset = start_offset;
+        while meta.time_index.len() > 1 && meta.time_index[1].0 <= start_offset {
+            meta.time_index.remove(0);
+        }
+        expired
+    }
+
+    fn lock(&self, namespace: &str, shard_name: &str) -> Arc<Mutex<()>> {
+        self.shard_lock
+            .entry(format!("{}_{}", namespace, shard_name))
+            .or_default()
+            .clone()
+    }
+
+    async fn kv_get_record(&self, key: String) -> Result<Option<Record>, CommonError> {
+        let reply = placement_get(&self.client_pool, &self.addrs, GetRequest { key }).await?;
+        if reply.value.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str::<Record>(&reply.value)?))
+    }
+
+    // Returns the stored meta as well, since it is the expected value of the next
+    // compare-and-set. An empty string means the shard has no meta yet.
+    async fn get_shard_meta(
+        &self,
+        namespace: &str,
+        shard_name: &str,
+    ) -> Result<(String, Option<ShardMeta>), CommonError> {
+        let key = self.shard_meta_key(namespace, shard_name);
+        let reply = placement_get(&self.client_pool, &self.addrs, GetRequest { key }).await?;
+        if reply.value.is_empty() {
+            return Ok((reply.value, None));
+        }
+        let meta = serde_json::from_str::<ShardMeta>(&reply.value)?;
+        Ok((reply.value, Some(meta)))
+    }
+
+    async fn save_records(
+        &self,
+        namespace: &str,
+        shard_name: &str,
+        records: Vec<Record>,
+    ) -> Result<Vec<u64>, CommonError> {
+        let lock = self.lock(namespace, shard_name);
+        let _guard = lock.lock().await;
+        let meta_key = self.shard_meta_key(namespace, shard_name);
+
+        for _ in 0..MAX_WRITE_RETRIES {
+            let (meta_value, meta) = self.get_shard_meta(namespace, shard_name).await?;
+            let mut meta = meta.unwrap_or_default();
+
+            let mut pairs = Vec::with_capacity(records.len() + 1);
+            let mut offsets = Vec::with_capacity(records.len());
+            for record in records.iter() {
+                let offset = meta.next_offset;
+                let mut record = record.clone();
+                record.offset = Some(offset);
+                let value = serde_json::to_string(&record)?;
+                self.check_record_size(&value)?;
+                self.index_record(&mut meta, offset, record.timestamp);
+                pairs.push(KvPair {
+                    key: self.record_key(namespace, shard_name, offset),
+                    value,
+                });
+                offsets.push(offset);
+                meta.next_offset = offset + 1;
+            }
+
+            let delete_keys = self
+                .apply_retention(&mut meta, now_second())
+                .map(|offset| self.record_key(namespace, shard_name, offset))
+                .collect();
+            pairs.push(KvPair {
+                key: meta_key.clone(),
+                value: serde_json::to_string(&meta)?,
+            });
+
+            let request = BatchSetRequest {
+                pairs,
+                delete_keys,
+                check_key: meta_key.clone(),
+                check_value: meta_value,
+            };
+            let reply = placement_batch_set(&self.client_pool, &self.addrs, request).await?;
+            if reply.success {
+                return Ok(offsets);
+            }
+        }
+
+        Err(CommonError::CommonError(format!(
+            "Shard {} in namespace {} was changed by another writer {} times in a row",
+            shard_name, namespace, MAX_WRITE_RETRIES
+        )))
+    }
+
+    async fn scan_records(
+        &self,
+        namespace: &str,
+        shard_name: &str,
+        offset: u64,
+        read_config: &ReadConfig,
+        filter: impl Fn(&Record) -> bool + Send,
+    ) -> Result<Vec<Record>, CommonError> {
+        let meta = match self.get_shard_meta(namespace, shard_name).await? {
+            (_, Some(meta)) => meta,
+            (_, None) => return Ok(Vec::new()),
+        };
+
+        let mut result = Vec::new();
+        let mut size = 0;
+        let mut offset = offset.max(meta.start_offset);
+        while offset < meta.next_offset {
+            let end = meta.next_offset.min(offset + KV_BATCH_SIZE as u64);
+            let keys = (offset..end)
+                .map(|offset| self.record_key(namespace, shard_name, offset))
+                .collect();
+            let reply =
+                placement_batch_get(&self.client_pool, &self.addrs, BatchGetRequest { keys })
+                    .await?;
+
+            // Pairs come back in key order, without the records retention removed
+            for pair in reply.pairs {
+                let record = serde_json::from_str::<Record>(&pair.value)?;
+                if !filter(&record) {
+                    continue;
+                }
+                size += record.data.len() as u64;
+                result.push(record);
+                if size >= read_config.max_size
+                    || (result.len() as u64) >= read_config.max_record_num
+                {
+                    return Ok(result);
+                }
+            }
+            offset = end;
+        }
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl StorageAdapter for PlacementStorageAdapter {
+    async fn create_shard(
+        &self,
+        namespace: String,
+        shard_name: String,
+        _: ShardConfig,
+    ) -> Result<(), CommonError> {
+        // Only applies when the shard has no meta yet, so an existing shard is kept
+        let meta_key = self.shard_meta_key(&namespace, &shard_name);
+        let request = BatchSetRequest {
+            pairs: vec![KvPair {
+                key: meta_key.clone(),
+                value: serde_json::to_string(&ShardMeta::default())?,
+            }],
+            delete_keys: Vec::new(),
+            check_key: meta_key,
+            check_value: "".to_string(),
+        };
+        placement_batch_set(&self.client_pool, &self.addrs, request).await?;
+        Ok(())
+    }
+
+    async fn delete_shard(&self, namespace: String, shard_name: String) -> Result<(), CommonError> {
+        let lock = self.lock(&namespace, &shard_name);
+        let _guard = lock.lock().await;
+        if let (_, Some(meta)) = self.get_shard_meta(&namespace, &shard_name).await? {
+            let mut offset = meta.start_offset;
+            while offset < meta.next_offset {
+                let end = meta.next_offset.min(offset + KV_BATCH_SIZE as u64);
+                let keys = (offset..end)
+                    .map(|offset| self.record_key(&namespace, &shard_name, offset))
+                    .collect();
+                placement_batch_delete(&self.client_pool, &self.addrs, BatchDeleteRequest { keys })
+                    .await?;
+                offset = end;
+            }
+            let keys = vec![self.shard_meta_key(&namespace, &shard_name)];
+            placement_batch_delete(&self.client_pool, &self.addrs, BatchDeleteRequest { keys })
+                .await?;
+        }
+        self.shard_lock
+            .remove(&format!("{}_{}", namespace, shard_name));
+        Ok(())
+    }
+
+    async fn write(
+        &self,
+        namespace: String,
+        shard_name: String,
+        data: Record,
+    ) -> Result<u64, CommonError> {
+        let offsets = self
+            .save_records(&namespace, &shard_name, vec![data])
+            .await?;
+        Ok(offsets[0])
+    }
+
+    async fn batch_write(
+        &self,
+        namespace: String,
+        shard_name: String,
+        data: Vec<Record>,
+    ) -> Result<Vec<u64>, CommonError> {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.save_records(&namespace, &shard_name, data).await
+    }
+
+    async fn read_by_offset(
+        &self,
+        namespace: String,
+        shard_name: String,
+        offset: u64,
+        read_config: ReadConfig,
+    ) -> Result<Vec<Record>, CommonError> {
+        self.scan_records(&namespace, &shard_name, offset, &read_config, |_| true)
+            .await
+    }
+
+    async fn read_by_tag(
+        &self,
+        namespace: String,
+        shard_name: String,
+        offset: u64,
+        tag: String,
+        read_config: ReadConfig,
+    ) -> Result<Vec<Record>, CommonError> {
+        self.scan_records(&namespace, &shard_name, offset, &read_config, |record| {
+            record.tags.contains(&tag)
+        })
+        .await
+    }
+
+    async fn read_by_key(
+        &self,
+        namespace: String,
+        shard_name: String,
+        offset: u64,
+        key: String,
+        read_config: ReadConfig,
+    ) -> Result<Vec<Record>, CommonError> {
+        self.scan_records(&namespace, &shard_name, offset, &read_config, |record| {
+            record.key == key
+        })
+        .await
+    }
+
+    async fn get_offset_by_timestamp(
+        &self,
+        namespace: String,
+        shard_name: String,
+        timestamp: u64,
+    ) -> Result<Option<ShardOffset>, CommonError> {
+        let meta = match self.get_shard_meta(&namespace, &shard_name).await? {
+            (_, Some(meta)) => meta,
+            (_, None) => return Ok(None),
+        };
+
+        // Records are appended in time order, so narrow the range with the time
+        // index and binary search what is left.
+        let mut low = meta.start_offset;
+        let mut high = meta.next_offset;
+        for &(offset, step_timestamp) in meta.time_index.iter() {
+            if step_timestamp > timestamp {
+                high = high.min(offset);
+                break;
+            }
+            low = low.max(offset);
+        }
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let key = self.record_key(&namespace, &shard_name, mid);
+            match self.kv_get_record(key).await? {
+                Some(record) if record.timestamp >= timestamp => high = mid,
+                _ => low = mid + 1,
+            }
+        }
+
+        if low >= meta.next_offset {
+            return Ok(None);
+        }
+        Ok(Some(ShardOffset {
+            namespace,
+            shard_name,
+            offset: low,
+            ..Default::default()
+        }))
+    }
+
+    async fn get_offset_by_group(
+        &self,
+        group_name: String,
+    ) -> Result<Vec<ShardOffset>, CommonError> {
+        self.offset_manager
+            .get_shard_offset(&self.cluster_name, &group_name)
+            .await
+    }
+
+    async fn commit_offset(
+        &self,
+        group_name: String,
+        namespace: String,
+        offset: HashMap<String, u64>,
+    ) -> Result<(), CommonError> {
+        self.offset_manager
+            .commit_offset(&self.cluster_name, &group_name, &namespace, offset)
+            .await
+    }
+
+    async fn close(&self) -> Result<(), CommonError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_base::config::common::PlacementStorage;
+    use grpc_clients::pool::ClientPool;
+
+    use super::{PlacementStorageAdapter, ShardMeta};
+
+    fn build_adapter(config: PlacementStorage) -> PlacementStorageAdapter {
+        PlacementStorageAdapter::new(
+            Arc::new(ClientPool::new(1)),
+            "test-cluster".to_string(),
+            vec!["127.0.0.1:1228".to_string()],
+            config,
+        )
+    }
+
+    #[test]
+    fn key_test() {
+        let adapter = build_adapter(PlacementStorage::default());
+        assert_eq!(adapter.shard_meta_key("ns", "s1"), "/storage/ns/s1/meta");
+        assert_eq!(adapter.record_key("ns", "s1", 7), "/storage/ns/s1/record/7");
+    }
+
+    #[test]
+    fn record_size_limit_test() {
+        let adapter = build_adapter(PlacementStorage {
+            max_record_size: 4,
+            max_shard_records: 10,
+            retention_sec: 0,
+        });
+        assert!(adapter.check_record_size("1234").is_ok());
+        assert!(adapter.check_record_size("12345").is_err());
+    }
+
+    #[test]
+    fn retained_start_offset_test() {
+        let adapter = build_adapter(PlacementStorage {
+            max_record_size: 1024,
+            max_shard_records: 10,
+            retention_sec: 0,
+        });
+
+        let meta = ShardMeta {
+            start_offset: 0,
+            next_offset: 5,
+            ..Default::default()
+        };
+        assert_eq!(adapter.retained_start_offset(&meta), 0);
+
+        let meta = ShardMeta {
+            start_offset: 0,
+            next_offset: 25,
+            ..Default::default()
+        };
+        assert_eq!(adapter.retained_start_offset(&meta), 15);
+
+        let meta = ShardMeta {
+            start_offset: 18,
+            next_offset: 25,
+            ..Default::default()
+        };
+        assert_eq!(adapter.retained_start_offset(&meta), 18);
+    }
+
+    #[test]
+    fn time_index_test() {
+        let adapter = build_adapter(PlacementStorage {
+            max_record_size: 1024,
+            max_shard_records: 1000,
+            retention_sec: 1000,
+        });
+
+        // One index entry per 10 second step
+        let mut meta = ShardMeta::default();
+        for (offset, timestamp) in [(0, 100), (1, 105), (2, 110), (3, 119), (4, 125)] {
+            adapter.index_record(&mut meta, offset, timestamp);
+        }
+        assert_eq!(meta.time_index, vec![(0, 100), (2, 110), (4, 125)]);
+    }
+
+    #[test]
+    fn apply_retention_test() {
+        let adapter = build_adapter(PlacementStorage {
+            max_record_size: 1024,
+            max_shard_records: 1000,
+            retention_sec: 100,
+        });
+
+        let mut meta = ShardMeta {
+            start_offset: 0,
+            next_offset: 30,
+            time_index: vec![(0, 1000), (10, 1050), (20, 1100)],
+        };
+
+        // Nothing is expired while the second step is newer than the expire time
+        assert_eq!(adapter.apply_retention(&mut meta, 1149), 0..0);
+        assert_eq!(meta.start_offset, 0);
+
+        assert_eq!(adapter.apply_retention(&mut meta, 1150), 0..10);
+        assert_eq!(meta.start_offset, 10);
+        assert_eq!(meta.time_index, vec![(10, 1050), (20, 1100)]);
+
+        // The newest step is kept until a later step exists
+        assert_eq!(adapter.apply_retention(&mut meta, 5000), 10..20);
+        assert_eq!(meta.time_index, vec![(20, 1100)]);
+
+        // The count limit drops the index entries it passes as well
+        let adapter = build_adapter(PlacementStorage {
+            max_record_size: 1024,
+            max_shard_records: 5,
+            retention_sec: 0,
+        });
+        let mut meta = ShardMeta {
+            start_offset: 0,
+            next_offset: 30,
+            time_index: vec![(0, 1000), (10, 1050), (20, 1100)],
+        };
+        assert_eq!(adapter.apply_retention(&mut meta, 1000), 0..25);
+        assert_eq!(meta.time_index, vec![(20, 1100)]);
+    }
+}
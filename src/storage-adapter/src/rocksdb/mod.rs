@@ -12,391 +12,497 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-// use std::fmt::Display;
-// use std::sync::Arc;
-
-// use axum::async_trait;
-// use common_base::error::common::CommonError;
-// use metadata_struct::adapter::record::Record;
-// use rocksdb_engine::RocksDBEngine;
-
-// use crate::storage::{ShardConfig, StorageAdapter};
-
-// const DB_COLUMN_FAMILY_KV: &str = "kv";
-// const DB_COLUMN_FAMILY_RECORD: &str = "record";
-
-// fn column_family_list() -> Vec<String> {
-//     vec![
-//         DB_COLUMN_FAMILY_KV.to_string(),
-//         DB_COLUMN_FAMILY_RECORD.to_string(),
-//     ]
-// }
-
-// #[derive(Clone)]
-// pub struct RocksDBStorageAdapter {
-//     pub db: Arc<RocksDBEngine>,
-// }
-
-// impl RocksDBStorageAdapter {
-//     pub fn new(db_path: &str, max_open_files: i32) -> Self {
-//         RocksDBStorageAdapter {
-//             db: Arc::new(RocksDBEngine::new(
-//                 db_path,
-//                 max_open_files,
-//                 column_family_list(),
-//             )),
-//         }
-//     }
-
-//     #[inline(always)]
-//     pub fn record_key<S1: Display>(&self, namespace: S1, shard_name: S1, index: u128) -> String {
-//         format!("{}_{}_record_{}", namespace, shard_name, index)
-//     }
-
-//     #[inline(always)]
-//     pub fn offset_shard_key<S1: Display>(&self, namespace: S1, shard_name: S1) -> String {
-//         format!("{}_{}_{}", namespace, shard_name, "shard_offset")
-//     }
-
-//     #[inline(always)]
-//     pub fn offset_key<S1: Display, S2: Display>(
-//         &self,
-//         namespace: S1,
-//         shard_name: S1,
-//         group_id: S2,
-//     ) -> String {
-//         format!("{}_{}_{}", namespace, shard_name, group_id)
-//     }
-
-//     pub fn get_offset<S1: Display, S2: Display>(
-//         &self,
-//         namespace: S1,
-//         shard_name: S1,
-//         group_id: S2,
-//     ) -> Option<u128> {
-//         let key = self.offset_key(namespace, shard_name, group_id);
-//         self.db
-//             .cf_handle(DB_COLUMN_FAMILY_KV)
-//             .and_then(|cf| self.db.read(cf, &key).ok()?)
-//     }
-// }
-
-// impl RocksDBStorageAdapter {}
-
-// #[async_trait]
-// impl StorageAdapter for RocksDBStorageAdapter {
-//     async fn create_shard(
-//         &self,
-//         namespace: String,
-//         shard_name: String,
-//         _: ShardConfig,
-//     ) -> Result<(), CommonError> {
-//         let cf = self.db.cf_handle(DB_COLUMN_FAMILY_RECORD).unwrap();
-//         let key = self.offset_shard_key(namespace, shard_name);
-//         self.db.write(cf, key.as_str(), &0_u128)
-//     }
-
-//     async fn delete_shard(&self, namespace: String, shard_name: String) -> Result<(), CommonError> {
-//         let cf = self.db.cf_handle(DB_COLUMN_FAMILY_RECORD).unwrap();
-//         let key = self.offset_shard_key(namespace, shard_name);
-//         self.db.delete_prefix(cf, &key)
-//     }
-
-//     async fn write(
-//         &self,
-//         namespace: String,
-//         shard_name: String,
-//         message: Vec<Record>,
-//     ) -> Result<Vec<usize>, CommonError> {
-//         let cf = self.db.cf_handle(DB_COLUMN_FAMILY_RECORD).unwrap();
-//         let key_shard_offset = self.offset_shard_key(namespace.clone(), shard_name.clone());
-//         let offset = self
-//             .db
-//             .read::<u128>(cf, key_shard_offset.as_str())?
-//             .unwrap_or(0);
-
-//         let mut start_offset = offset;
-
-//         let mut offset_res = Vec::new();
-//         for mut msg in message {
-//             offset_res.push(start_offset as usize);
-//             msg.offset = start_offset;
-
-//             let record_key = self.record_key(namespace.clone(), shard_name.clone(), start_offset);
-//             self.db.write(cf, &record_key, &msg)?;
-//             start_offset += 1;
-//         }
-
-//         self.db
-//             .write(cf, key_shard_offset.as_str(), &start_offset)?;
-
-//         Ok(offset_res)
-//     }
-
-//     async fn stream_read(
-//         &self,
-//         namespace: String,
-//         shard_name: String,
-//         group_id: String,
-//         record_num: Option<u128>,
-//         _: Option<usize>,
-//     ) -> Result<Option<Vec<Record>>, CommonError> {
-//         let cf = self.db.cf_handle(DB_COLUMN_FAMILY_RECORD).unwrap();
-//         let group_offset_key = self.offset_key(namespace.clone(), shard_name.clone(), group_id);
-//         let offset = self
-//             .db
-//             .read::<u128>(cf, group_offset_key.as_str())?
-//             .unwrap_or(0);
-
-//         let num = record_num.unwrap_or(10);
-
-//         let mut cur_offset = 0;
-//         let mut result = Vec::new();
-//         for i in offset..(offset + num) {
-//             let record_key = self.record_key(namespace.clone(), shard_name.clone(), i);
-//             let value = self.db.read::<Record>(cf, &record_key)?;
-
-//             if let Some(value) = value {
-//                 result.push(value.clone());
-//                 cur_offset += 1;
-//             } else {
-//                 break;
-//             }
-//         }
-
-//         if cur_offset > 0 {
-//             self.db
-//                 .write(cf, group_offset_key.as_str(), &(offset + cur_offset))?;
-//         }
-//         Ok(Some(result))
-//     }
-
-//     async fn stream_commit_offset(
-//         &self,
-//         namespace: String,
-//         shard_name: String,
-//         group_id: String,
-//         offset: u128,
-//     ) -> Result<bool, CommonError> {
-//         let cf = self.db.cf_handle(DB_COLUMN_FAMILY_RECORD).unwrap();
-//         let key = self.offset_key(namespace, group_id, shard_name);
-//         self.db.write(cf, key.as_str(), &offset)?;
-//         Ok(true)
-//     }
-
-//     async fn stream_read_by_offset(
-//         &self,
-//         namespace: String,
-//         shard_name: String,
-//         offset: usize,
-//     ) -> Result<Option<Record>, CommonError> {
-//         let cf = self.db.cf_handle(DB_COLUMN_FAMILY_RECORD).unwrap();
-//         self.db.read::<Record>(
-//             cf,
-//             self.record_key(namespace, shard_name, offset as u128)
-//                 .as_str(),
-//         )
-//     }
-
-//     async fn stream_read_by_timestamp(
-//         &self,
-//         _: String,
-//         _: String,
-//         _: u128,
-//         _: u128,
-//         _: Option<usize>,
-//         _: Option<usize>,
-//     ) -> Result<Option<Vec<Record>>, CommonError> {
-//         Ok(None)
-//     }
-
-//     async fn stream_read_by_key(
-//         &self,
-//         _: String,
-//         _: String,
-//         _: String,
-//     ) -> Result<Option<Record>, CommonError> {
-//         Ok(None)
-//     }
-//     async fn close(&self) -> Result<(), CommonError> {
-//         Ok(())
-//     }
-// }
-
-// #[cfg(test)]
-// mod tests {
-//     use common_base::tools::unique_id;
-//     use metadata_struct::adapter::record::Record;
-
-//     use super::RocksDBStorageAdapter;
-//     use crate::storage::StorageAdapter;
-//     #[tokio::test]
-//     async fn stream_read_write() {
-//         let db_path = format!("/tmp/robustmq_{}", unique_id());
-
-//         let storage_adapter = RocksDBStorageAdapter::new(db_path.as_str(), 100);
-//         let namespace = unique_id();
-//         let shard_name = "test-11".to_string();
-//         let ms1 = "test1".to_string();
-//         let ms2 = "test2".to_string();
-//         let data = vec![
-//             Record::build_b(ms1.clone().as_bytes().to_vec()),
-//             Record::build_b(ms2.clone().as_bytes().to_vec()),
-//         ];
-
-//         let result = storage_adapter
-//             .stream_write(namespace.clone(), shard_name.clone(), data)
-//             .await
-//             .unwrap();
-
-//         assert_eq!(result.first().unwrap().clone(), 0);
-//         assert_eq!(result.get(1).unwrap().clone(), 1);
-//         assert_eq!(
-//             storage_adapter
-//                 .stream_read(
-//                     namespace.clone(),
-//                     shard_name.clone(),
-//                     "test_".to_string(),
-//                     Some(10),
-//                     None
-//                 )
-//                 .await
-//                 .unwrap()
-//                 .unwrap()
-//                 .len(),
-//             2
-//         );
-
-//         let ms3 = "test3".to_string();
-//         let ms4 = "test4".to_string();
-//         let data = vec![
-//             Record::build_b(ms3.clone().as_bytes().to_vec()),
-//             Record::build_b(ms4.clone().as_bytes().to_vec()),
-//         ];
-
-//         let result = storage_adapter
-//             .stream_write(namespace.clone(), shard_name.clone(), data)
-//             .await
-//             .unwrap();
-//         let result_read = storage_adapter
-//             .stream_read(
-//                 namespace.clone(),
-//                 shard_name.clone(),
-//                 "test_".to_string(),
-//                 Some(10),
-//                 None,
-//             )
-//             .await
-//             .unwrap();
-//         println!("{:?}", result_read);
-
-//         assert_eq!(result.first().unwrap().clone(), 2);
-//         assert_eq!(result.get(1).unwrap().clone(), 3);
-//         assert_eq!(result_read.unwrap().len(), 2);
-
-//         let group_id = "test_group_id".to_string();
-//         let record_num = Some(1);
-//         let record_size = None;
-//         let res = storage_adapter
-//             .stream_read(
-//                 namespace.clone(),
-//                 shard_name.clone(),
-//                 group_id.clone(),
-//                 record_num,
-//                 record_size,
-//             )
-//             .await
-//             .unwrap()
-//             .unwrap();
-//         assert_eq!(
-//             String::from_utf8(res.first().unwrap().clone().data).unwrap(),
-//             ms1
-//         );
-//         storage_adapter
-//             .stream_commit_offset(
-//                 namespace.clone(),
-//                 shard_name.clone(),
-//                 group_id.clone(),
-//                 res.first().unwrap().clone().offset,
-//             )
-//             .await
-//             .unwrap();
-
-//         let res = storage_adapter
-//             .stream_read(
-//                 namespace.clone(),
-//                 shard_name.clone(),
-//                 group_id.clone(),
-//                 record_num,
-//                 record_size,
-//             )
-//             .await
-//             .unwrap()
-//             .unwrap();
-//         assert_eq!(
-//             String::from_utf8(res.first().unwrap().clone().data).unwrap(),
-//             ms2
-//         );
-//         storage_adapter
-//             .stream_commit_offset(
-//                 namespace.clone(),
-//                 shard_name.clone(),
-//                 group_id.clone(),
-//                 res.first().unwrap().clone().offset,
-//             )
-//             .await
-//             .unwrap();
-
-//         let res = storage_adapter
-//             .stream_read(
-//                 namespace.clone(),
-//                 shard_name.clone(),
-//                 group_id.clone(),
-//                 record_num,
-//                 record_size,
-//             )
-//             .await
-//             .unwrap()
-//             .unwrap();
-//         assert_eq!(
-//             String::from_utf8(res.first().unwrap().clone().data).unwrap(),
-//             ms3
-//         );
-//         storage_adapter
-//             .stream_commit_offset(
-//                 namespace.clone(),
-//                 shard_name.clone(),
-//                 group_id.clone(),
-//                 res.first().unwrap().clone().offset,
-//             )
-//             .await
-//             .unwrap();
-
-//         let res = storage_adapter
-//             .stream_read(
-//                 namespace.clone(),
-//                 shard_name.clone(),
-//                 group_id.clone(),
-//                 record_num,
-//                 record_size,
-//             )
-//             .await
-//             .unwrap()
-//             .unwrap();
-//         assert_eq!(
-//             String::from_utf8(res.first().unwrap().clone().data).unwrap(),
-//             ms4
-//         );
-//         storage_adapter
-//             .stream_commit_offset(
-//                 namespace.clone(),
-//                 shard_name.clone(),
-//                 group_id.clone(),
-//                 res.first().unwrap().clone().offset,
-//             )
-//             .await
-//             .unwrap();
-
-//         let _ = std::fs::remove_dir_all(&db_path);
-//     }
-// }
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::async_trait;
+use common_base::error::common::CommonError;
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use metadata_struct::adapter::read_config::ReadConfig;
+use metadata_struct::adapter::record::Record;
+use rocksdb::BoundColumnFamily;
+use rocksdb_engine::RocksDBEngine;
+
+use crate::storage::{ShardConfig, ShardOffset, StorageAdapter};
+
+const DB_COLUMN_FAMILY_KV: &str = "kv";
+const DB_COLUMN_FAMILY_RECORD: &str = "record";
+
+fn column_family_list() -> Vec<String> {
+    vec![
+        DB_COLUMN_FAMILY_KV.to_string(),
+        DB_COLUMN_FAMILY_RECORD.to_string(),
+    ]
+}
+
+#[derive(Clone)]
+pub struct RocksDBStorageAdapter {
+    pub db: Arc<RocksDBEngine>,
+    // namespace_shard_name, next offset
+    shard_offset: DashMap<String, u64>,
+}
+
+impl RocksDBStorageAdapter {
+    pub fn new(db_path: &str, max_open_files: i32) -> Self {
+        RocksDBStorageAdapter {
+            db: Arc::new(RocksDBEngine::new(
+                db_path,
+                max_open_files,
+                column_family_list(),
+            )),
+            shard_offset: DashMap::with_capacity(256),
+        }
+    }
+
+    pub fn shard_key(&self, namespace: &str, shard_name: &str) -> String {
+        format!("{}_{}", namespace, shard_name)
+    }
+
+    // Offsets are zero-padded so that a prefix scan returns records in offset order.
+    pub fn record_key(&self, namespace: &str, shard_name: &str, offset: u64) -> String {
+        format!("/record/{}/{}/{:020}", namespace, shard_name, offset)
+    }
+
+    pub fn shard_offset_key(&self, namespace: &str, shard_name: &str) -> String {
+        format!("/shard_offset/{}/{}", namespace, shard_name)
+    }
+
+    pub fn tag_index_key(
+        &self,
+        namespace: &str,
+        shard_name: &str,
+        tag: &str,
+        offset: u64,
+    ) -> String {
+        format!("/tag/{}/{}/{}/{:020}", namespace, shard_name, tag, offset)
+    }
+
+    pub fn key_index_key(
+        &self,
+        namespace: &str,
+        shard_name: &str,
+        key: &str,
+        offset: u64,
+    ) -> String {
+        format!("/key/{}/{}/{}/{:020}", namespace, shard_name, key, offset)
+    }
+
+    pub fn group_offset_key(&self, group_name: &str, namespace: &str, shard_name: &str) -> String {
+        format!("/group/{}/{}/{}", group_name, namespace, shard_name)
+    }
+
+    fn cf_handle(&self, name: &str) -> Result<Arc<BoundColumnFamily>, CommonError> {
+        self.db
+            .cf_handle(name)
+            .ok_or(CommonError::RocksDBFamilyNotAvailable(name.to_string()))
+    }
+
+    fn shard_prefixes(&self, namespace: &str, shard_name: &str) -> Vec<String> {
+        ["record", "tag", "key"]
+            .iter()
+            .map(|index| format!("/{}/{}/{}/", index, namespace, shard_name))
+            .collect()
+    }
+
+    fn next_offset(&self, namespace: &str, shard_name: &str) -> Result<u64, CommonError> {
+        let cf = self.cf_handle(DB_COLUMN_FAMILY_RECORD)?;
+        Ok(self
+            .db
+            .read::<u64>(cf, &self.shard_offset_key(namespace, shard_name))?
+            .unwrap_or(0))
+    }
+
+    fn save_records(
+        &self,
+        namespace: &str,
+        shard_name: &str,
+        records: Vec<Record>,
+    ) -> Result<Vec<u64>, CommonError> {
+        let shard_key = self.shard_key(namespace, shard_name);
+        let mut offset_res = Vec::with_capacity(records.len());
+
+        // Holding the entry guard serializes offset allocation per shard.
+        let mut next_offset = match self.shard_offset.entry(shard_key) {
+            Entry::Occupied(entry) => entry.into_ref(),
+            Entry::Vacant(entry) => entry.insert(self.next_offset(namespace, shard_name)?),
+        };
+
+        let cf = self.cf_handle(DB_COLUMN_FAMILY_RECORD)?;
+        let mut offset = *next_offset;
+        for mut record in records {
+            record.offset = Some(offset);
+            for tag in record.tags.iter() {
+                self.db.write(
+                    cf.clone(),
+                    &self.tag_index_key(namespace, shard_name, tag, offset),
+                    &offset,
+                )?;
+            }
+            if !record.key.is_empty() {
+                self.db.write(
+                    cf.clone(),
+                    &self.key_index_key(namespace, shard_name, &record.key, offset),
+                    &offset,
+                )?;
+            }
+            self.db.write(
+                cf.clone(),
+                &self.record_key(namespace, shard_name, offset),
+                &record,
+            )?;
+            offset_res.push(offset);
+            offset += 1;
+        }
+
+        self.db
+            .write(cf, &self.shard_offset_key(namespace, shard_name), &offset)?;
+        *next_offset = offset;
+        Ok(offset_res)
+    }
+
+    fn read_by_index(
+        &self,
+        prefix: &str,
+        namespace: &str,
+        shard_name: &str,
+        offset: u64,
+        read_config: &ReadConfig,
+        filter: impl Fn(&Record) -> bool,
+    ) -> Result<Vec<Record>, CommonError> {
+        let cf = self.cf_handle(DB_COLUMN_FAMILY_RECORD)?;
+        let mut result = Vec::new();
+        for (_, value) in self.db.read_prefix(cf.clone(), prefix)? {
+            if result.len() as u64 >= read_config.max_record_num {
+                break;
+            }
+            let record_offset = serde_json::from_slice::<u64>(&value)?;
+            if record_offset < offset {
+                continue;
+            }
+            if let Some(record) = self.db.read::<Record>(
+                cf.clone(),
+                &self.record_key(namespace, shard_name, record_offset),
+            )? {
+                if filter(&record) {
+                    result.push(record);
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl StorageAdapter for RocksDBStorageAdapter {
+    async fn create_shard(
+        &self,
+        namespace: String,
+        shard_name: String,
+        _: ShardConfig,
+    ) -> Result<(), CommonError> {
+        let cf = self.cf_handle(DB_COLUMN_FAMILY_RECORD)?;
+        let key = self.shard_offset_key(&namespace, &shard_name);
+        if self.db.read::<u64>(cf.clone(), &key)?.is_none() {
+            self.db.write(cf, &key, &0_u64)?;
+        }
+        Ok(())
+    }
+
+    async fn delete_shard(&self, namespace: String, shard_name: String) -> Result<(), CommonError> {
+        let cf = self.cf_handle(DB_COLUMN_FAMILY_RECORD)?;
+        for prefix in self.shard_prefixes(&namespace, &shard_name) {
+            for (key, _) in self.db.read_prefix(cf.clone(), &prefix)? {
+                self.db.delete(cf.clone(), &key)?;
+            }
+        }
+        self.db
+            .delete(cf, &self.shard_offset_key(&namespace, &shard_name))?;
+        self.shard_offset
+            .remove(&self.shard_key(&namespace, &shard_name));
+        Ok(())
+    }
+
+    async fn write(
+        &self,
+        namespace: String,
+        shard_name: String,
+        data: Record,
+    ) -> Result<u64, CommonError> {
+        let offsets = self.save_records(&namespace, &shard_name, vec![data])?;
+        Ok(offsets[0])
+    }
+
+    async fn batch_write(
+        &self,
+        namespace: String,
+        shard_name: String,
+        data: Vec<Record>,
+    ) -> Result<Vec<u64>, CommonError> {
+        self.save_records(&namespace, &shard_name, data)
+    }
+
+    async fn read_by_offset(
+        &self,
+        namespace: String,
+        shard_name: String,
+        offset: u64,
+        read_config: ReadConfig,
+    ) -> Result<Vec<Record>, CommonError> {
+        let cf = self.cf_handle(DB_COLUMN_FAMILY_RECORD)?;
+        let mut result = Vec::new();
+        for i in offset..(offset + read_config.max_record_num) {
+            let key = self.record_key(&namespace, &shard_name, i);
+            if let Some(record) = self.db.read::<Record>(cf.clone(), &key)? {
+                result.push(record);
+            } else {
+                break;
+            }
+        }
+        Ok(result)
+    }
+
+    async fn read_by_tag(
+        &self,
+        namespace: String,
+        shard_name: String,
+        offset: u64,
+        tag: String,
+        read_config: ReadConfig,
+    ) -> Result<Vec<Record>, CommonError> {
+        let prefix = format!("/tag/{}/{}/{}/", namespace, shard_name, tag);
+        self.read_by_index(
+            &prefix,
+            &namespace,
+            &shard_name,
+            offset,
+            &read_config,
+            |record| record.tags.contains(&tag),
+        )
+    }
+
+    async fn read_by_key(
+        &self,
+        namespace: String,
+        shard_name: String,
+        offset: u64,
+        key: String,
+        read_config: ReadConfig,
+    ) -> Result<Vec<Record>, CommonError> {
+        let prefix = format!("/key/{}/{}/{}/", namespace, shard_name, key);
+        self.read_by_index(
+            &prefix,
+            &namespace,
+            &shard_name,
+            offset,
+            &read_config,
+            |record| record.key == key,
+        )
+    }
+
+    async fn get_offset_by_timestamp(
+        &self,
+        namespace: String,
+        shard_name: String,
+        timestamp: u64,
+    ) -> Result<Option<ShardOffset>, CommonError> {
+        let cf = self.cf_handle(DB_COLUMN_FAMILY_RECORD)?;
+        let prefix = format!("/record/{}/{}/", namespace, shard_name);
+        for (_, value) in self.db.read_prefix(cf, &prefix)? {
+            let record = serde_json::from_slice::<Record>(&value)?;
+            if record.timestamp >= timestamp {
+                return Ok(Some(ShardOffset {
+                    namespace,
+                    shard_name,
+                    offset: record.offset.unwrap_or_default(),
+                    ..Default::default()
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn get_offset_by_group(
+        &self,
+        group_name: String,
+    ) -> Result<Vec<ShardOffset>, CommonError> {
+        let cf = self.cf_handle(DB_COLUMN_FAMILY_KV)?;
+        let prefix = format!("/group/{}/", group_name);
+        let mut results = Vec::new();
+        for (_, value) in self.db.read_prefix(cf, &prefix)? {
+            let (namespace, shard_name, offset) =
+                serde_json::from_slice::<(String, String, u64)>(&value)?;
+            results.push(ShardOffset {
+                namespace,
+                shard_name,
+                offset,
+                ..Default::default()
+            });
+        }
+        Ok(results)
+    }
+
+    async fn commit_offset(
+        &self,
+        group_name: String,
+        namespace: String,
+        offset: HashMap<String, u64>,
+    ) -> Result<(), CommonError> {
+        let cf = self.cf_handle(DB_COLUMN_FAMILY_KV)?;
+        for (shard_name, offset) in offset {
+            let key = self.group_offset_key(&group_name, &namespace, &shard_name);
+            self.db
+                .write(cf.clone(), &key, &(namespace.clone(), shard_name, offset))?;
+        }
+        Ok(())
+    }
+
+    async fn close(&self) -> Result<(), CommonError> {
+        for cf_name in column_family_list() {
+            let cf = self.cf_handle(&cf_name)?;
+            self.db.db.flush_cf(&cf)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use common_base::tools::unique_id;
+    use metadata_struct::adapter::read_config::ReadConfig;
+    use metadata_struct::adapter::record::Record;
+
+    use super::RocksDBStorageAdapter;
+    use crate::storage::{ShardConfig, StorageAdapter};
+
+    #[tokio::test]
+    async fn stream_read_write() {
+        let db_path = format!("/tmp/robustmq_{}", unique_id());
+        let storage_adapter = RocksDBStorageAdapter::new(db_path.as_str(), 100);
+        let namespace = unique_id();
+        let shard_name = "test-11".to_string();
+        storage_adapter
+            .create_shard(
+                namespace.clone(),
+                shard_name.clone(),
+                ShardConfig::default(),
+            )
+            .await
+            .unwrap();
+
+        let data = vec![
+            Record::build_str("test1".to_string()),
+            Record::build_str("test2".to_string()),
+        ];
+        let result = storage_adapter
+            .batch_write(namespace.clone(), shard_name.clone(), data)
+            .await
+            .unwrap();
+        assert_eq!(result, vec![0, 1]);
+
+        let result = storage_adapter
+            .write(
+                namespace.clone(),
+                shard_name.clone(),
+                Record::build_str("test3".to_string()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result, 2);
+
+        let read_config = ReadConfig::new();
+        let res = storage_adapter
+            .read_by_offset(namespace.clone(), shard_name.clone(), 1, read_config)
+            .await
+            .unwrap();
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].offset, Some(1));
+        assert_eq!(String::from_utf8(res[1].data.clone()).unwrap(), "test3");
+
+        let group_id = unique_id();
+        let mut offset_data = HashMap::new();
+        offset_data.insert(shard_name.clone(), 1);
+        storage_adapter
+            .commit_offset(group_id.clone(), namespace.clone(), offset_data)
+            .await
+            .unwrap();
+        let offsets = storage_adapter
+            .get_offset_by_group(group_id.clone())
+            .await
+            .unwrap();
+        assert_eq!(offsets.len(), 1);
+        assert_eq!(offsets[0].namespace, namespace);
+        assert_eq!(offsets[0].shard_name, shard_name);
+        assert_eq!(offsets[0].offset, 1);
+
+        storage_adapter
+            .delete_shard(namespace.clone(), shard_name.clone())
+            .await
+            .unwrap();
+        let res = storage_adapter
+            .read_by_offset(namespace.clone(), shard_name.clone(), 0, ReadConfig::new())
+            .await
+            .unwrap();
+        assert!(res.is_empty());
+
+        storage_adapter.close().await.unwrap();
+        let _ = std::fs::remove_dir_all(&db_path);
+    }
+
+    #[tokio::test]
+    async fn read_by_tag_and_key() {
+        let db_path = format!("/tmp/robustmq_{}", unique_id());
+        let storage_adapter = RocksDBStorageAdapter::new(db_path.as_str(), 100);
+        let namespace = unique_id();
+        let shard_name = "test-tag".to_string();
+
+        let mut data = Vec::new();
+        for i in 0..6 {
+            let mut record = Record::build_str(format!("data-{}", i));
+            record.set_tags(vec![format!("tag-{}", i % 2)]);
+            record.set_key(format!("key-{}", i % 3));
+            data.push(record);
+        }
+        storage_adapter
+            .batch_write(namespace.clone(), shard_name.clone(), data)
+            .await
+            .unwrap();
+
+        let res = storage_adapter
+            .read_by_tag(
+                namespace.clone(),
+                shard_name.clone(),
+                1,
+                "tag-0".to_string(),
+                ReadConfig::new(),
+            )
+            .await
+            .unwrap();
+        let offsets: Vec<Option<u64>> = res.iter().map(|r| r.offset).collect();
+        assert_eq!(offsets, vec![Some(2), Some(4)]);
+
+        let res = storage_adapter
+            .read_by_key(
+                namespace.clone(),
+                shard_name.clone(),
+                0,
+                "key-1".to_string(),
+                ReadConfig::new(),
+            )
+            .await
+            .unwrap();
+        let offsets: Vec<Option<u64>> = res.iter().map(|r| r.offset).collect();
+        assert_eq!(offsets, vec![Some(1), Some(4)]);
+
+        let res = storage_adapter
+            .get_offset_by_timestamp(namespace.clone(), shard_name.clone(), 0)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(res.offset, 0);
+
+        let _ = std::fs::remove_dir_all(&db_path);
+    }
+}
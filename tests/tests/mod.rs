@@ -18,3 +18,4 @@ pub mod mqtt_client;
 pub mod mqtt_protocol;
 pub mod mqtt_server;
 pub mod place_server;
+pub mod storage_adapter;
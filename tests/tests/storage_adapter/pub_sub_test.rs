@@ -0,0 +1,84 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_base::config::default_mqtt::default_placement_storage;
+    use common_base::tools::unique_id;
+    use grpc_clients::pool::ClientPool;
+    use storage_adapter::journal::JournalStorageAdapter;
+    use storage_adapter::memory::MemoryStorageAdapter;
+    use storage_adapter::mysql::MySQLStorageAdapter;
+    use storage_adapter::placement::PlacementStorageAdapter;
+    use storage_adapter::rocksdb::RocksDBStorageAdapter;
+    use storage_adapter::storage::StorageAdapter;
+    use third_driver::mysql::build_mysql_conn_pool;
+
+    use crate::storage_adapter::common::{
+        journal_addrs, mysql_addr, placement_addrs, publish_subscribe,
+    };
+
+    #[tokio::test]
+    async fn memory_pub_sub_test() {
+        let adapter = MemoryStorageAdapter::new();
+        publish_subscribe(&adapter).await;
+        adapter.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn rocksdb_pub_sub_test() {
+        let db_path = format!("/tmp/robustmq_{}", unique_id());
+        let adapter = RocksDBStorageAdapter::new(&db_path, 10000);
+        publish_subscribe(&adapter).await;
+        adapter.close().await.unwrap();
+        let _ = std::fs::remove_dir_all(&db_path);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn mysql_pub_sub_test() {
+        let pool = build_mysql_conn_pool(&mysql_addr()).unwrap();
+        let adapter = MySQLStorageAdapter::new(pool).unwrap();
+        publish_subscribe(&adapter).await;
+        adapter.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn journal_pub_sub_test() {
+        let client_pool = Arc::new(ClientPool::new(10));
+        let adapter = JournalStorageAdapter::new(
+            client_pool,
+            unique_id(),
+            journal_addrs(),
+            placement_addrs(),
+        );
+        publish_subscribe(&adapter).await;
+        adapter.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn placement_pub_sub_test() {
+        let client_pool = Arc::new(ClientPool::new(10));
+        let adapter = PlacementStorageAdapter::new(
+            client_pool,
+            unique_id(),
+            placement_addrs(),
+            default_placement_storage(),
+        );
+        publish_subscribe(&adapter).await;
+        adapter.close().await.unwrap();
+    }
+}